@@ -11,7 +11,9 @@ fn main() {
         "example_configs/yap_colors.toml",
         "example_configs/yap_espflash_profiles.toml",
         "example_configs/yap_keybinds.toml",
+        "example_configs/yap_theme.toml",
         "example_configs/macros",
+        "example_configs/scripts",
     ];
 
     for file in &items_to_copy {
@@ -1,7 +1,7 @@
 use std::{
     borrow::Cow,
     collections::VecDeque,
-    sync::Arc,
+    sync::{Arc, atomic::AtomicBool},
     thread::JoinHandle,
     time::{Duration, Instant},
 };
@@ -9,6 +9,7 @@ use std::{
 use chrono::{DateTime, Local};
 
 use color_eyre::eyre::{Context, Result};
+use compact_str::{CompactString, ToCompactString};
 use crokey::{KeyCombination, key};
 use crossbeam::channel::{Receiver, Select, Sender, TrySendError};
 use enum_rotate::EnumRotate;
@@ -17,7 +18,7 @@ use num_integer::Integer;
 use ratatui::{
     Frame, Terminal,
     crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
-    layout::{Constraint, Layout, Margin, Offset, Rect, Size},
+    layout::{Constraint, Layout, Margin, Offset, Position, Rect, Size},
     prelude::Backend,
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span},
@@ -29,7 +30,9 @@ use ratatui::{
 
 use ratatui_macros::{horizontal, line, span, vertical};
 use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
-use struct_table::{ArrowKey, StructTable};
+use struct_table::{
+    ArrowKey, FieldInputError, FieldParseError, StructTable, TableLayout, TableMouse,
+};
 use strum::{VariantArray, VariantNames};
 use takeable::Takeable;
 
@@ -40,43 +43,65 @@ use tui_input::{Input, backend::crossterm::EventHandler};
 
 use crate::{
     TcpStreamHealth,
-    buffer::Buffer,
+    buffer::{Buffer, SourceLocation},
     config_adjacent_path,
     event_carousel::CarouselHandle,
+    exit_conditions::{ExitConditions, ExitOutcome},
+    external_command::{CommandContext, CommandEvent, RUN_COMMAND_PREFIX, spawn_command},
     is_ctrl_c,
-    keybinds::{Action, BaseAction, BuiltinAction, Keybinds, PortAction, ShowPopupAction},
-    notifications::{EMERGE_TIME, EXPAND_TIME, EXPIRE_TIME, Notifications, PAUSE_AND_SHOW_TIME},
+    keybinds::{
+        Action, BaseAction, BuiltinAction, KeybindContext, Keybinds, MouseBinding, MouseInput,
+        PortAction, ReservedKey, ShowPopupAction, SnapshotAction, chord_to_string,
+    },
+    notifications::{
+        EMERGE_TIME, EXPAND_TIME, EXPIRE_TIME, Notifications, PAUSE_AND_SHOW_TIME,
+        PastNotification, Severity,
+    },
     serial::{
         DeserializedUsb, PrintablePortInfo, ReconnectType, Reconnections, SerialDisconnectReason,
         SerialEvent,
         handle::{BlockingCommandError, SerialHandle},
-        worker::{InnerPortStatus, MOCK_PORT_NAME},
+        port_lock::PortHolder,
+        worker::{InnerPortStatus, MOCK_PORT_NAME, WorkerError},
+    },
+    settings::{
+        Behavior, BellBehavior, PortSettings, Rendering, Settings, SettingsChange, SettingsError,
+        SettingsSection, UpdateChannel,
+        profiles::{SETTINGS_PROFILES_DIR_PATH, SettingsProfiles},
     },
-    settings::{Behavior, PortSettings, Rendering, Settings},
-    text_input::TextInput,
+    text_input::{HISTORY_PATH, TextInput},
     traits::{FirstChars, LastIndex, LineHelpers, RequiresPort, ToggleBool},
     tui::{
-        POPUP_MENU_SELECTOR_COUNT, centered_rect_size,
+        BufferNavigation, CommandPalette, CompletionKind, Completions, ComposeEditor,
+        KeybindEditor, KeybindEditorState, POPUP_MENU_SELECTOR_COUNT, PaletteCommand, PaletteEntry,
+        SettingsSearch, SettingsSearchEntry, SetupWizard, centered_rect_size,
         color_rules::{COLOR_RULES_PATH, ColorRuleLoadError, ColorRules},
         prompts::{
             AttemptReconnectPrompt, DisconnectPrompt, IgnorePortByNamePrompt,
-            IgnoreUsbDevicePrompt, PromptKeybind, PromptTable,
+            IgnoreUsbDevicePrompt, LargePastePrompt, PortInUsePrompt, PromptKeybind, PromptTable,
+            RevertSettingsPrompt,
         },
-        show_keybinds,
+        render_chord_hint, render_help_footer, show_keybinds,
         single_line_selector::{SingleLineSelector, SingleLineSelectorState},
+        theme::{THEME_PATH, theme},
     },
-    updates::{UpdateBeginPrompt, UpdateCheckConsentPrompt, UpdateHandle},
+    updates::{UpdateBeginPrompt, UpdateCheckConsentPrompt, UpdateHandle, release_notes_lines},
 };
 
+#[cfg(feature = "self-replace")]
+use crate::updates::UpdateFromArchivePrompt;
 #[cfg(all(windows, feature = "self-replace"))]
 use crate::updates::UpdateLaunchPrompt;
 
 #[cfg(feature = "defmt")]
 use crate::{
-    buffer::defmt::{DefmtDecoder, DefmtLoadError, LocationsError},
-    keybinds::DefmtSelectAction,
+    buffer::defmt::{DefmtDecoder, DefmtLoadError, LocationsError, module_filter::ModuleFilter},
+    keybinds::{DefmtAction, DefmtSelectAction},
     settings::Defmt,
-    tui::defmt::{DefmtHelpers, DefmtRecentElfs, DefmtRecentError},
+    tui::defmt::{
+        DefmtFoundElfPrompt, DefmtHelpers, DefmtRecentElfs, DefmtRecentError, elf_search_root,
+        find_newest_defmt_elf, hexdump_rows,
+    },
 };
 #[cfg(feature = "defmt")]
 use {camino::Utf8Path, ratatui_explorer::FileExplorer};
@@ -87,12 +112,27 @@ use crate::buffer::defmt::elf_watcher::{ElfWatchEvent, ElfWatchHandle, ElfWatche
 #[cfg(feature = "macros")]
 use crate::{
     keybinds::MacroBuiltinAction,
-    macros::{MacroNameTag, MacroNotFound, Macros},
+    macros::{
+        MACRO_BUNDLES_DIR_PATH, MacroCapture, MacroContent, MacroEditor, MacroEditorField,
+        MacroExpectation, MacroImport, MacroNameTag, MacroNotFound, MacroRecorder,
+        MacroVariablesForm, Macros, RepeatingMacro, ResponseCapture, hotbar_slot,
+        toggle_hotbar_pin,
+    },
+    tui::prompts::{ConfirmMacroPrompt, DeleteMacroPrompt, ImportMacroConflictPrompt},
+};
+
+#[cfg(feature = "scripting")]
+use crate::{
+    keybinds::ScriptBuiltinAction,
+    scripting::{SCRIPT_ACTION_PREFIX, SCRIPTS_DIR_PATH, ScriptEvent, Scripts},
 };
 
 #[cfg(feature = "logging")]
 use crate::{
-    buffer::LoggingEvent, keybinds::LoggingAction, settings::Logging,
+    buffer::{LoggingEvent, TrafficDirection},
+    keybinds::LoggingAction,
+    replay::{Replay, SEEK_STEP},
+    settings::{LogForwarding, Logging},
     tui::logging::sync_logs_button,
 };
 
@@ -106,17 +146,20 @@ use crate::{
     tui::esp::{self, EspFlashHelper},
 };
 
+#[cfg(feature = "settings-watch")]
+use crate::settings::watcher::{SettingsWatchEvent, SettingsWatchHandle};
+
 use crate::updates::UpdateEvent;
 
 #[derive(Clone, Debug)]
 pub enum CrosstermEvent {
     Resize,
     KeyPress(KeyEvent),
-    MouseScroll {
-        up: bool,
-    },
-    /// Currently the only mouse click event handled, for pasting.
-    RightClick,
+    /// Mouse button or scroll, to be checked against the user's mouse bindings,
+    /// along with where in the terminal it happened.
+    Mouse(MouseBinding, Position),
+    /// Text pasted into the terminal, given all at once thanks to bracketed paste.
+    Paste(String),
 }
 
 impl From<CrosstermEvent> for Event {
@@ -132,7 +175,7 @@ pub enum Event {
     /// Serial port connection events/signals/errors.
     Serial(SerialEvent),
     /// Bytes recieved from attached device and when.
-    RxBuffer((DateTime<Local>, Vec<u8>)),
+    RxBuffer((DateTime<Local>, u64, Vec<u8>)),
     /// Typically sent from event_carousel to trigger UI updates/actions on a timer.
     Tick(Tick),
     #[cfg(feature = "logging")]
@@ -145,8 +188,24 @@ pub enum Event {
     /// User selected a defmt ELF from an OS-provided file picker.
     // TODO have error too?
     DefmtFromFilePicker(camino::Utf8PathBuf),
+    #[cfg(feature = "defmt")]
+    /// Search for the newest defmt ELF finished, with whatever it found.
+    DefmtNewestElfSearched {
+        search_root: camino::Utf8PathBuf,
+        found: Option<camino::Utf8PathBuf>,
+    },
+    /// Open the given location in the user's editor, suspending the TUI until it exits.
+    OpenInEditor(SourceLocation),
     /// Update notifications and progress.
     Updates(UpdateEvent),
+    /// Completion of a shell command run by a keybind.
+    Command(CommandEvent),
+    #[cfg(feature = "settings-watch")]
+    /// Sent when the settings file is changed from outside of yap, or an error occurs.
+    SettingsWatch(SettingsWatchEvent),
+    #[cfg(feature = "scripting")]
+    /// Requests from and completion of a running script.
+    Script(ScriptEvent),
     /// Begin closing app gracefully.
     Quit,
 }
@@ -167,6 +226,17 @@ pub enum Tick {
     Notification,
     /// Used to trigger further consumption of the Action Queue
     Action,
+    #[cfg(feature = "macros")]
+    /// Time to re-send the currently repeating macro
+    MacroRepeat,
+    #[cfg(feature = "macros")]
+    /// Used to end a macro response capture that's taking too long
+    MacroCapture,
+    #[cfg(feature = "logging")]
+    /// Time for the next chunk of a replayed capture to be played
+    Replay,
+    /// Used to end the session once the `--timeout` has passed
+    ExitTimeout,
 }
 
 impl From<Tick> for Event {
@@ -203,6 +273,29 @@ pub enum SettingsMenu {
     Defmt,
 }
 
+impl SettingsMenu {
+    fn section(&self) -> SettingsSection {
+        match self {
+            SettingsMenu::SerialPort => SettingsSection::Serial,
+            SettingsMenu::Rendering => SettingsSection::Rendering,
+            SettingsMenu::Behavior => SettingsSection::Behavior,
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => SettingsSection::Logging,
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => SettingsSection::Defmt,
+        }
+    }
+    /// Popup row of the menu's first settings table field.
+    fn first_field_row(&self) -> usize {
+        match self {
+            // After the select new/recent ELF buttons.
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => POPUP_MENU_SELECTOR_COUNT + 2,
+            _ => POPUP_MENU_SELECTOR_COUNT,
+        }
+    }
+}
+
 #[cfg(any(feature = "espflash", feature = "macros"))]
 #[derive(Debug, Clone, PartialEq, Eq, EnumRotate, VariantArray, VariantNames)]
 #[repr(u8)]
@@ -222,26 +315,101 @@ pub enum Popup {
     #[cfg(any(feature = "espflash", feature = "macros"))]
     ToolMenu(ToolMenu),
     CurrentKeybinds,
+    /// Rebinding actions, see `App::keybind_editor`.
+    KeybindEditor,
+    /// Switching between settings profiles, see `App::settings_profiles`.
+    SettingsProfiles,
+    /// Looking back over past notifications, see `Notifications::history`.
+    NotificationHistory,
+    /// Asking before undoing or reverting settings changes, returning to the given menu after.
+    RevertSettingsPrompt(SettingsMenu),
+    /// Finding a setting across every settings menu, see `App::settings_search`.
+    SettingsSearch,
+    /// Finding any action, macro, or settings menu by name and running it, see `App::command_palette`.
+    CommandPalette,
+    /// Writing several lines to send one after another, see `App::compose_editor`.
+    ComposeEditor,
+    /// Picking the basics on first launch, see `App::setup_wizard`.
+    SetupWizard,
     #[cfg(feature = "defmt")]
     DefmtNewElf(FileExplorer),
     #[cfg(feature = "defmt")]
     DefmtRecentElf,
+    #[cfg(feature = "defmt")]
+    DefmtModuleFilter,
+    #[cfg(feature = "defmt")]
+    DefmtStats,
+    #[cfg(feature = "defmt")]
+    DefmtFoundElf(camino::Utf8PathBuf),
+    #[cfg(feature = "defmt")]
+    /// Hexdump of a defmt frame's raw bytes that failed to decode.
+    DefmtFailedFrame(Vec<u8>),
+    #[cfg(feature = "macros")]
+    /// Creating or editing the macro in `Macros::editor`.
+    MacroEditor,
+    #[cfg(feature = "macros")]
+    DeleteMacroPrompt(MacroNameTag),
+    #[cfg(feature = "macros")]
+    /// Asking before sending a macro marked with `confirm`, see `App::pending_confirmation`.
+    ConfirmMacroPrompt(MacroNameTag),
+    #[cfg(feature = "macros")]
+    /// Asking what to do with an imported macro that already exists, see `App::macro_import`.
+    ImportMacroConflict,
+    #[cfg(feature = "macros")]
+    /// Asking for the values of a macro's variables, see `Macros::variables_form`.
+    MacroVariables,
+    #[cfg(feature = "macros")]
+    /// What a macro would send, see `Macros::previewing`.
+    MacroPreview,
 
     DisconnectPrompt,
     AttemptReconnectPrompt,
     IgnoreByUsb(String, UsbPortInfo),
     IgnoreByName(String),
     SerialConnectionFailed(String),
+    /// The chosen port is held by someone else, see `App::port_in_use_prompt_choice`.
+    PortInUse(SerialPortInfo, PortHolder),
+    /// Asking before sending a large paste or compose draft to the device,
+    /// see `App::handle_paste` and `App::send_composed_lines`.
+    LargePastePrompt(LargeSend),
 
     UpdateCheckConsentPrompt,
 
     UpdateBeginPrompt,
     #[cfg(all(windows, feature = "self-replace"))]
     UpdateLaunchPrompt,
+    /// Asking before installing from the archive given with `--update-from`.
+    #[cfg(feature = "self-replace")]
+    UpdateFromArchivePrompt(camino::Utf8PathBuf),
     #[cfg(feature = "self-replace")]
     UpdateDownloading(f64),
 }
 
+#[derive(Debug, PartialEq)]
+/// Text held back by `Popup::LargePastePrompt` until it's confirmed.
+pub enum LargeSend {
+    /// Pasted with the Pseudo Shell off, sent as-is.
+    Paste(String),
+    /// Lines from the compose editor, sent one at a time.
+    ComposedLines(Vec<String>),
+}
+
+impl LargeSend {
+    /// Bytes to be sent, not counting line endings added to composed lines.
+    fn len(&self) -> usize {
+        match self {
+            Self::Paste(text) => text.len(),
+            Self::ComposedLines(lines) => lines.iter().map(String::len).sum(),
+        }
+    }
+    fn line_count(&self) -> usize {
+        match self {
+            Self::Paste(text) => text.lines().count(),
+            Self::ComposedLines(lines) => lines.len(),
+        }
+    }
+}
+
 #[cfg(any(feature = "espflash", feature = "macros"))]
 impl From<ToolMenu> for Popup {
     fn from(value: ToolMenu) -> Self {
@@ -277,14 +445,39 @@ pub const COMMON_BAUD_TRUNC: &[u32] = {
 /// How long to keep the input bar Red after trying to send to a disconnected port.
 const FAILED_SEND_VISUAL_TIME: Duration = Duration::from_millis(750);
 
+/// How long to keep the bar under the buffer highlighted after recieving a BEL byte.
+const BELL_FLASH_TIME: Duration = Duration::from_millis(150);
+
+/// How many columns the `scroll-left` and `scroll-right` actions move the buffer by.
+const HORIZONTAL_SCROLL_COLUMNS: i16 = 4;
+/// How many of the newest port lines words are taken from when completing input.
+const COMPLETION_RX_LINES: usize = 100;
+
 /// Max time to wait before erroring when connecting to a port.
-const CONNECT_ATTEMPT_BLOCK_MAX: Duration = Duration::from_secs(15);
+pub const CONNECT_ATTEMPT_BLOCK_MAX: Duration = Duration::from_secs(15);
 
 /// Max time to wait before erroring when recieving initial available ports.
-const SCAN_BLOCK_MAX: Duration = Duration::from_secs(5);
+pub const SCAN_BLOCK_MAX: Duration = Duration::from_secs(5);
+
+/// Most settings changes kept to undo.
+const SETTINGS_HISTORY_LEN: usize = 32;
+
+#[cfg(feature = "macros")]
+/// Max actions allowed in the queue when expanding a macro sequence,
+/// to stop sequences that include themselves from running forever.
+const MAX_QUEUED_ACTIONS: usize = 512;
+
+/// A settings field being typed into directly, in place of its displayed value.
+struct SettingsFieldEditor {
+    /// Index of the field within the current settings menu's table.
+    field_index: usize,
+    /// Row the field is shown at, which differs from its index if rows above it are hidden.
+    row: usize,
+    input: Input,
+}
 
 #[derive(Debug, thiserror::Error)]
-enum NoSenders {
+pub enum NoSenders {
     #[error("Serial Buffer sender has hung up unexpectedly!")]
     SerialRx,
     #[error("All event senders have hung up unexpectedly!")]
@@ -307,12 +500,19 @@ pub struct App {
 
     event_tx: Sender<Event>,
     event_rx: Receiver<Event>,
-    serial_buf_rx: Receiver<(DateTime<Local>, Vec<u8>)>,
+    serial_buf_rx: Receiver<(DateTime<Local>, u64, Vec<u8>)>,
     crossterm_rx: Receiver<CrosstermEvent>,
+    /// Set while the terminal is handed over to an editor, pausing the crossterm thread.
+    input_paused: Arc<AtomicBool>,
 
     baud_selection_state: SingleLineSelectorState,
     /// User input destination for custom baud
     baud_input: Input,
+    /// Open when typing a value into a settings field that allows it.
+    settings_field_editor: Option<SettingsFieldEditor>,
+    /// Where the current settings menu's table was last drawn and its scroll offset,
+    /// for finding which row was clicked.
+    settings_table_area: Option<(Rect, usize)>,
 
     pub popup: Option<Popup>,
     /// Selection index of current popup
@@ -346,6 +546,9 @@ pub struct App {
     /// for highlighting Input bar Red momentarily.
     failed_send_at: Option<Instant>,
 
+    /// Last time a BEL byte was recieved, for flashing the repeating pattern momentarily.
+    bell_flash_at: Option<Instant>,
+
     /// If `true` when not using Pseudo-shell, next key/keycombo
     /// will be handled by the app rather than sending to the port.
     escape_next_keypress: bool,
@@ -355,13 +558,60 @@ pub struct App {
     last_raw_sequence: ArrayVec<[u8; 16]>,
 
     keybinds: Keybinds,
+    keybind_editor: KeybindEditor,
+    settings_search: SettingsSearch,
+    command_palette: CommandPalette,
+    /// Draft from the compose popup, kept between openings until it's sent.
+    compose_editor: ComposeEditor,
+    /// Shown above the Pseudo Shell's input while cycling through what could complete it.
+    completions: Option<Completions>,
+    /// Choices made in the first-time setup wizard, with the current step in `popup_menu_scroll`.
+    setup_wizard: SetupWizard,
+    settings_profiles: SettingsProfiles,
+    /// Settings from before each change this session, most recent last.
+    settings_history: VecDeque<Settings>,
+    #[cfg(feature = "settings-watch")]
+    _settings_watcher: SettingsWatchHandle,
+    navigation: BufferNavigation,
+    /// Keys pressed so far of a chord, with the possible next keys shown until it's finished or cancelled.
+    pending_chord: Vec<KeyCombination>,
+
+    #[cfg(feature = "scripting")]
+    scripts: Scripts,
 
     #[cfg(feature = "macros")]
     macros: Macros,
 
     /// Queue for actions from keybinds with multiple actions,
-    /// and the keycombo thet triggered them
-    action_queue: VecDeque<(KeyCombination, Action)>,
+    /// and the keycombo thet triggered them (if any, i.e. macro sequences sent from the menu)
+    action_queue: VecDeque<(Option<KeyCombination>, Action)>,
+    /// If a `Tick::Action` is on its way to consume the queue,
+    /// so we never have more than one in flight.
+    action_queue_ticking: bool,
+
+    #[cfg(feature = "macros")]
+    /// Macro being re-sent on an interval until stopped, if any.
+    repeating_macro: Option<RepeatingMacro>,
+    #[cfg(feature = "macros")]
+    /// Macro waiting on the user to confirm sending it.
+    pending_confirmation: Option<(MacroNameTag, MacroContent, Option<KeyCombination>)>,
+    #[cfg(feature = "macros")]
+    /// Macro bundles being imported, paused while asking about a conflict.
+    macro_import: Option<MacroImport>,
+    #[cfg(feature = "macros")]
+    /// Pattern a macro sequence is waiting to receive before continuing.
+    macro_expectation: Option<MacroExpectation>,
+    #[cfg(feature = "macros")]
+    /// Input sent while recording a new macro.
+    macro_recorder: Option<MacroRecorder>,
+    #[cfg(feature = "macros")]
+    /// Response being captured from a just-sent macro.
+    macro_capture: Option<ResponseCapture>,
+
+    /// Patterns and time limit from the command line that end the session.
+    exit_conditions: Option<ExitConditions>,
+    /// Which exit condition ended the session, if any.
+    pub exit_outcome: Option<ExitOutcome>,
 
     /// User chose to break connection _and_ stay on Terminal view.
     user_broke_connection: bool,
@@ -373,6 +623,10 @@ pub struct App {
     /// Recent defmt ELFs and file update watcher handle
     pub defmt_helpers: DefmtHelpers,
 
+    #[cfg(feature = "logging")]
+    /// Capture being played back into the buffer in place of a port, from `--replay`.
+    replay: Option<Replay>,
+
     /// Each Ctrl-C must be acknowledged back to the thread actually recieving terminal events.
     ///
     /// If enough aren't acknowledged within a period of time,
@@ -385,27 +639,34 @@ pub struct App {
 
     pub update_worker: UpdateHandle,
     pub update_found_version: Option<String>,
+    /// Markdown release notes of `update_found_version`.
+    pub update_found_notes: String,
 
     /// If connecting directly to a port via CLI,
     /// assume "No, ask again later" to all first-time-setup questions
     /// and just let the user connect and work.
     allow_first_time_setup: bool,
+    /// No settings file existed before this launch, so walk the user through the basics.
+    new_config: bool,
 }
 
 impl App {
     /// Load all remaining configs and spin up worker threads.
+    #[allow(clippy::too_many_arguments)]
     pub fn build(
         event_tx: Sender<Event>,
         event_rx: Receiver<Event>,
         ctrl_c_tx: Sender<()>,
         crossterm_rx: Receiver<CrosstermEvent>,
+        input_paused: Arc<AtomicBool>,
         settings: Settings,
         tcp_log_health: Arc<TcpStreamHealth>,
         allow_first_time_setup: bool,
+        new_config: bool,
     ) -> Result<Self> {
         let keybinds = Keybinds::load()?;
 
-        let buffer_input = TextInput::default();
+        let mut buffer_input = TextInput::default();
 
         let saved_baud_rate = settings.serial.baud_rate;
         let (baud_input, baud_index) = {
@@ -445,6 +706,16 @@ impl App {
 
         let color_rules = ColorRules::load_from_file(config_adjacent_path(COLOR_RULES_PATH))?;
 
+        // The default theme is kept until a broken theme file's fixed, it's no reason to not start.
+        let theme_error = settings
+            .rendering
+            .theme
+            .apply(config_adjacent_path(THEME_PATH))
+            .err();
+        if let Some(e) = &theme_error {
+            error!("Failed loading theme: {e}");
+        }
+
         let buffer = Buffer::new(
             line_ending,
             color_rules,
@@ -485,20 +756,62 @@ impl App {
             }
         }
 
+        #[cfg(feature = "defmt-watch")]
+        defmt_helpers
+            .watcher_handle
+            .watch_extra_paths(&settings.defmt.extra_watched_elfs)?;
+
         #[cfg(feature = "macros")]
         let macros = {
-            let (macros, errors) =
-                Macros::load_from_folder(config_adjacent_path(crate::macros::MACROS_DIR_PATH))?;
+            let (macros, errors) = Macros::load_from_folder(
+                config_adjacent_path(crate::macros::MACROS_DIR_PATH),
+                crate::macros::project_macros_folder(),
+            )?;
             if let Some(e) = errors.into_iter().next() {
                 return Err(e)?;
             }
             macros
         };
 
+        #[cfg(feature = "scripting")]
+        let scripts = Scripts::load_from_folder(config_adjacent_path(SCRIPTS_DIR_PATH))?;
+
+        let (settings_profiles, settings_profile_errors) =
+            SettingsProfiles::load_from_folder(config_adjacent_path(SETTINGS_PROFILES_DIR_PATH))?;
+
+        #[cfg(feature = "settings-watch")]
+        let _settings_watcher = SettingsWatchHandle::build(&settings.path, event_tx.clone())
+            .wrap_err("failed to watch settings file")?;
+
         let update_worker = UpdateHandle::new(event_tx.clone());
 
         if settings.updates.allow_checking_for_updates {
-            update_worker.query_latest(settings.updates.allow_pre_releases)?;
+            update_worker.query_latest(&settings.updates)?;
+        }
+
+        let mut notifs = Notifications::new(event_tx.clone());
+        notifs.set_filter(
+            settings.behavior.notification_level,
+            settings.behavior.do_not_disturb,
+        );
+
+        if settings.behavior.retain_history
+            && let Err(e) = buffer_input.load_history(&config_adjacent_path(HISTORY_PATH))
+        {
+            error!("Failed loading input history: {e}");
+            notifs.notify_str(
+                format!("Failed loading input history! {e}"),
+                Severity::Error,
+            );
+        }
+        if let Some(e) = theme_error {
+            notifs.notify_str(
+                format!("Failed loading theme, using the default! {e}"),
+                Severity::Error,
+            );
+        }
+        for e in settings_profile_errors {
+            notifs.notify_str(format!("Skipped settings profile! {e}"), Severity::Warning);
         }
 
         // debug!("{buffer:#?}");
@@ -510,6 +823,8 @@ impl App {
             popup_hint_scroll: -2,
             baud_selection_state: SingleLineSelectorState::new().with_selected(baud_index),
             baud_input,
+            settings_field_editor: None,
+            settings_table_area: None,
             popup_menu_scroll: 0,
 
             ports,
@@ -526,20 +841,51 @@ impl App {
 
             repeating_line_flip: false,
             failed_send_at: None,
+            bell_flash_at: None,
             escape_next_keypress: false,
             last_raw_sequence: ArrayVec::new(),
 
             #[cfg(feature = "macros")]
             macros,
+            #[cfg(feature = "scripting")]
+            scripts,
             action_queue: VecDeque::new(),
+            action_queue_ticking: false,
+            #[cfg(feature = "macros")]
+            repeating_macro: None,
+            #[cfg(feature = "macros")]
+            pending_confirmation: None,
+            #[cfg(feature = "macros")]
+            macro_import: None,
+            #[cfg(feature = "macros")]
+            macro_expectation: None,
+            #[cfg(feature = "macros")]
+            macro_recorder: None,
+            #[cfg(feature = "macros")]
+            macro_capture: None,
+            exit_conditions: None,
+            exit_outcome: None,
             scratch: settings.clone(),
+            setup_wizard: SetupWizard::new(&settings),
             settings,
             keybinds,
-            notifs: Notifications::new(event_tx.clone()),
+            keybind_editor: KeybindEditor::default(),
+            settings_search: SettingsSearch::new(SettingsSearchEntry::all()),
+            command_palette: CommandPalette::default(),
+            compose_editor: ComposeEditor::default(),
+            completions: None,
+            settings_history: VecDeque::new(),
+            settings_profiles,
+            #[cfg(feature = "settings-watch")]
+            _settings_watcher,
+            navigation: BufferNavigation::default(),
+            pending_chord: Vec::new(),
+            notifs,
             event_tx,
             event_rx,
             serial_buf_rx,
             crossterm_rx,
+            input_paused,
 
             #[cfg(feature = "espflash")]
             espflash: { EspFlashHelper::build().wrap_err("failed to load espflash profiles")? },
@@ -547,14 +893,19 @@ impl App {
             #[cfg(feature = "defmt")]
             defmt_helpers,
 
+            #[cfg(feature = "logging")]
+            replay: None,
+
             user_broke_connection: false,
 
             ctrl_c_tx,
             tcp_log_health,
 
             update_found_version: None,
+            update_found_notes: String::new(),
             update_worker,
             allow_first_time_setup,
+            new_config,
         })
     }
     fn is_running(&self) -> bool {
@@ -565,6 +916,7 @@ impl App {
         if self.allow_first_time_setup {
             self.first_time_setup();
         }
+        self.notify_keybind_issues();
         // Get initial size of buffer.
         self.buffer.update_terminal_size(&mut terminal)?;
         let mut max_draw = Duration::default();
@@ -609,7 +961,9 @@ impl App {
             } else {
                 // Otherwise, discard whatever we get.
                 let mut bytes_discarded = 0;
-                while let Ok((_timestamp, vec_to_discard)) = self.serial_buf_rx.try_recv() {
+                while let Ok((_timestamp, _monotonic_us, vec_to_discard)) =
+                    self.serial_buf_rx.try_recv()
+                {
                     bytes_discarded += vec_to_discard.len();
                 }
                 if bytes_discarded > 0 {
@@ -675,10 +1029,41 @@ impl App {
         match event {
             Event::Quit => self.shutdown(),
 
-            Event::RxBuffer((timestamp, data)) => {
-                self.buffer.fresh_rx_bytes(timestamp, data);
+            Event::RxBuffer((timestamp, monotonic_us, data)) => {
+                #[cfg(feature = "scripting")]
+                self.scripts.feed_rx(&data);
+
+                #[cfg(feature = "macros")]
+                if let Some(expectation) = &mut self.macro_expectation
+                    && expectation.feed(&data)
+                {
+                    self.macro_expectation = None;
+                    self.carousel
+                        .add_oneshot("ActionQueue", Tick::Action, Duration::ZERO)?;
+                }
+                #[cfg(feature = "macros")]
+                if let Some(capture) = &mut self.macro_capture
+                    && capture.feed(&data)
+                {
+                    self.finish_macro_capture(false);
+                }
+
+                if let Some(outcome) = self.exit_conditions.as_mut().and_then(|c| c.feed(&data)) {
+                    self.exit_with(outcome);
+                }
+
+                self.buffer.fresh_rx_bytes(timestamp, monotonic_us, data);
                 self.buffer.scroll_by(0);
 
+                if self.buffer.take_unhandled_bells() > 0 {
+                    self.ring_bell()?;
+                }
+
+                #[cfg(feature = "defmt")]
+                if self.buffer.take_new_device_elf_hash() {
+                    self.warn_defmt_elf_mismatch();
+                }
+
                 self.repeating_line_flip.flip();
             }
 
@@ -687,36 +1072,32 @@ impl App {
                 terminal.autoresize()?;
                 self.buffer.update_terminal_size(terminal)?;
             }
+            Event::OpenInEditor(location) => self.open_in_editor(&location, terminal)?,
             Event::Crossterm(CrosstermEvent::KeyPress(key)) => self.handle_key_press(key)?,
-            Event::Crossterm(CrosstermEvent::MouseScroll { up })
+            Event::Crossterm(CrosstermEvent::Paste(text)) => self.handle_paste(text)?,
+            Event::Crossterm(CrosstermEvent::Mouse(binding, _))
                 if matches!(self.popup, Some(Popup::CurrentKeybinds)) =>
             {
-                if up {
-                    self.popup_menu_scroll = self.popup_menu_scroll.saturating_sub(1);
-                } else {
-                    self.popup_menu_scroll += 1;
+                match binding.input {
+                    MouseInput::ScrollUp => {
+                        self.popup_menu_scroll = self.popup_menu_scroll.saturating_sub(1);
+                    }
+                    MouseInput::ScrollDown => self.popup_menu_scroll += 1,
+                    _ => (),
                 }
             }
-            Event::Crossterm(CrosstermEvent::MouseScroll { up }) => {
-                let amount = if up { 1 } else { -1 };
-                self.buffer.scroll_by(amount);
+            Event::Crossterm(CrosstermEvent::Mouse(binding, position))
+                if matches!(self.popup, Some(Popup::SettingsMenu(_)))
+                    && binding.modifiers.is_empty() =>
+            {
+                self.handle_settings_mouse(binding.input, position);
             }
-
-            Event::Crossterm(CrosstermEvent::RightClick)
+            Event::Crossterm(CrosstermEvent::Mouse(binding, _))
                 if self.menu == Menu::Terminal && self.popup.is_none() =>
             {
-                if let Some(clipboard) = &mut self.text_input.clipboard {
-                    match clipboard.get_text() {
-                        Ok(clipboard_text) => {
-                            self.text_input.append_to_input(&clipboard_text);
-                        }
-                        Err(e) => {
-                            error!("error getting clipboard text: {e}");
-                        }
-                    }
-                }
+                self.handle_mouse_binding(binding)?;
             }
-            Event::Crossterm(CrosstermEvent::RightClick) => {}
+            Event::Crossterm(CrosstermEvent::Mouse(..)) => {}
 
             Event::Serial(SerialEvent::Connected(reconnect)) => {
                 if let Some(reconnect_type) = &reconnect {
@@ -728,7 +1109,7 @@ impl App {
                         ReconnectType::LastDitch => "Connected to COM port by name.",
                     };
 
-                    self.notifs.notify_str(text, Color::Green);
+                    self.notifs.notify_str(text, Severity::Success);
                 } else {
                     // If starting session with device.
                     info!("Connected!");
@@ -781,7 +1162,7 @@ impl App {
                     SerialDisconnectReason::UserBrokeConnection => {
                         self.user_broke_connection = true;
                         let text = "Broke serial connection! (Reconnections paused!)";
-                        self.notifs.notify_str(text, Color::Red);
+                        self.notifs.notify_str(text, Severity::Error);
                     }
                     SerialDisconnectReason::Error(error) => {
                         error!("Serial worker reported error on disconnect! {error}");
@@ -794,7 +1175,7 @@ impl App {
                         };
                         self.notifs.notify_str(
                             format!("Port error: {error} - {reconnect_text}"),
-                            Color::Red,
+                            Severity::Error,
                         );
                     }
                 }
@@ -822,7 +1203,7 @@ impl App {
             #[cfg(feature = "espflash")]
             Event::Serial(SerialEvent::EspFlash(esp_event)) => match esp_event {
                 EspEvent::Error(e) => {
-                    self.notifs.notify_str(&e, Color::Red);
+                    self.notifs.notify_str(&e, Severity::Error);
                     self.action_queue.clear();
                 }
                 _ => self
@@ -832,11 +1213,20 @@ impl App {
             #[cfg(feature = "logging")]
             Event::Logging(LoggingEvent::FinishedReconsumption) => self
                 .notifs
-                .notify_str("Finished syncing contents to log!", Color::Green),
+                .notify_str("Finished syncing contents to log!", Severity::Success),
+            #[cfg(feature = "logging")]
+            Event::Logging(LoggingEvent::DumpedRecent { path, triggered }) => {
+                let text = if triggered {
+                    format!("Trigger seen, saved recent traffic to {}", path.display())
+                } else {
+                    format!("Saved recent traffic to {}", path.display())
+                };
+                self.notifs.notify_str(text, Severity::Success);
+            }
             #[cfg(feature = "logging")]
             Event::Logging(LoggingEvent::Error(error)) => self
                 .notifs
-                .notify_str(format!("Logging error: {error}"), Color::Red),
+                .notify_str(format!("Logging error: {error}"), Severity::Error),
 
             // Recieved once every second
             Event::Tick(Tick::PerSecond) => match self.menu {
@@ -899,6 +1289,26 @@ impl App {
             Event::Tick(Tick::Action) => {
                 self.consume_one_queued_action()?;
             }
+            #[cfg(feature = "logging")]
+            Event::Tick(Tick::Replay) => {
+                self.advance_replay()?;
+            }
+            #[cfg(feature = "macros")]
+            Event::Tick(Tick::MacroRepeat) => {
+                self.resend_repeating_macro()?;
+            }
+            #[cfg(feature = "macros")]
+            Event::Tick(Tick::MacroCapture) => {
+                // Could be from an earlier, already finished capture.
+                if self.macro_capture.as_ref().is_some_and(|c| c.timed_out()) {
+                    self.finish_macro_capture(true);
+                }
+            }
+            Event::Tick(Tick::ExitTimeout) => {
+                if let Some(outcome) = self.exit_conditions.as_ref().and_then(|c| c.timed_out()) {
+                    self.exit_with(outcome);
+                }
+            }
             Event::Tick(Tick::Notification) => {
                 // debug!("notif!");
                 if let Some(notif) = &self.notifs.inner {
@@ -919,6 +1329,8 @@ impl App {
                 debug!("Requested tick recieved from: {origin}");
                 self.failed_send_at
                     .take_if(|i| i.elapsed() >= FAILED_SEND_VISUAL_TIME);
+                self.bell_flash_at
+                    .take_if(|i| i.elapsed() >= BELL_FLASH_TIME);
                 #[cfg(feature = "espflash")]
                 {
                     use crate::tui::esp::ERASE_FLASH_CONFIRM_PERIOD;
@@ -943,6 +1355,14 @@ impl App {
                 #[cfg(feature = "defmt-watch")]
                 false,
             ),
+            #[cfg(feature = "defmt")]
+            Event::DefmtNewestElfSearched { search_root, found } => match found {
+                Some(elf_path) => self.show_popup(Popup::DefmtFoundElf(elf_path)),
+                None => self.notifs.notify_str(
+                    format!("No defmt ELFs found in {search_root}!"),
+                    Severity::Error,
+                ),
+            },
             #[cfg(feature = "defmt-watch")]
             Event::DefmtElfWatch(ElfWatchEvent::ElfUpdated(elf_path)) => {
                 if self.settings.defmt.watch_elf_for_changes {
@@ -959,16 +1379,20 @@ impl App {
             }
             #[cfg(feature = "defmt-watch")]
             Event::DefmtElfWatch(ElfWatchEvent::Error(err)) => {
-                self.notifs.notify_str(err, Color::Red);
+                self.notifs.notify_str(err, Severity::Error);
             }
 
             Event::Updates(UpdateEvent::UpToDate) => {
                 info!("App is up-to-date!");
             }
-            Event::Updates(UpdateEvent::UpdateFound(new)) => {
+            Event::Updates(UpdateEvent::UpdateFound {
+                version: new,
+                notes,
+            }) => {
                 if new != self.settings.updates.skipped_version {
                     info!("Update found! v{new}");
                     self.update_found_version = Some(new);
+                    self.update_found_notes = notes;
                 } else {
                     info!("Update found, but ignoring! (v{new})");
                 }
@@ -999,40 +1423,786 @@ impl App {
                 info!("Starting new version!");
                 self.update_worker.start_new_version()?;
             }
+            #[cfg(feature = "scripting")]
+            Event::Script(event) => self.handle_script_event(event)?,
+            Event::Command(event) => self.handle_command_event(event),
+            #[cfg(feature = "settings-watch")]
+            Event::SettingsWatch(SettingsWatchEvent::Changed) => self.reload_settings_file(),
+            #[cfg(feature = "settings-watch")]
+            Event::SettingsWatch(SettingsWatchEvent::Error(err)) => {
+                self.notifs.notify_str(err, Severity::Error);
+            }
+        }
+        Ok(())
+    }
+    #[cfg(feature = "scripting")]
+    fn handle_script_event(&mut self, event: ScriptEvent) -> Result<()> {
+        match event {
+            ScriptEvent::Send { text, line_ending } => {
+                if !self.serial.port_status.load().inner.is_connected() {
+                    if let Some(name) = self.scripts.stop() {
+                        self.notifs.notify_str(
+                            format!("Port isn't ready! Stopping script {name}..."),
+                            Severity::Error,
+                        );
+                    }
+                    return Ok(());
+                }
+
+                let line_ending: &[u8] = match line_ending {
+                    false => &[],
+                    #[cfg(feature = "macros")]
+                    true => self.settings.serial.macro_line_ending.as_bytes(
+                        &self.settings.serial.rx_line_ending,
+                        &self.settings.serial.tx_line_ending,
+                    ),
+                    #[cfg(not(feature = "macros"))]
+                    true => self
+                        .settings
+                        .serial
+                        .tx_line_ending
+                        .as_bytes(&self.settings.serial.rx_line_ending),
+                };
+
+                // Script strings already support escapes of their own.
+                self.serial.send_str(&text, line_ending, false)?;
+                self.buffer.append_user_text(
+                    &text,
+                    line_ending,
+                    #[cfg(feature = "macros")]
+                    None,
+                );
+                self.buffer.scroll_by(i32::MIN);
+            }
+            ScriptEvent::Notify(text) => {
+                self.notifs
+                    .notify_str_colored(text, Color::LightBlue, Severity::Info)
+            }
+            ScriptEvent::Finished { name, error } => {
+                self.scripts.finished();
+                match error {
+                    None => self
+                        .notifs
+                        .notify_str(format!("Script {name} finished."), Severity::Success),
+                    Some(e) => self
+                        .notifs
+                        .notify_str(format!("Script {name} failed: {e}"), Severity::Error),
+                }
+            }
         }
         Ok(())
     }
+    fn handle_command_event(&mut self, event: CommandEvent) {
+        match event {
+            CommandEvent::Finished {
+                command,
+                result,
+                stderr,
+            } => match result {
+                Ok(Some(0)) => self
+                    .notifs
+                    .notify_str(format!("`{command}` finished."), Severity::Success),
+                Ok(code) => {
+                    let code = code.map_or_else(|| "no code".to_owned(), |c| c.to_string());
+                    error!("`{command}` exited with {code}: {stderr}");
+                    self.notifs
+                        .notify_str(format!("`{command}` exited with {code}!"), Severity::Error);
+                }
+                Err(e) => {
+                    error!("`{command}` failed to run: {e}");
+                    self.notifs
+                        .notify_str(format!("`{command}` failed to run: {e}"), Severity::Error);
+                }
+            },
+        }
+    }
+    /// Run a shell command, with placeholders filled in from the current port and selected line.
+    fn run_external_command(&mut self, command: CompactString) {
+        let context = CommandContext {
+            port: self
+                .serial
+                .port_status
+                .load()
+                .current_port
+                .as_ref()
+                .map(|port| port.port_name.clone()),
+            log_dir: config_adjacent_path("logs"),
+            selected_line: self
+                .buffer
+                .selected_line_bytes()
+                .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+        };
+        match spawn_command(command.clone(), context, self.event_tx.clone()) {
+            Ok(_) => self.notifs.notify_str_colored(
+                format!("Running `{command}`..."),
+                Color::LightBlue,
+                Severity::Info,
+            ),
+            Err(e) => {
+                error!("Failed to start `{command}`: {e}");
+                self.notifs
+                    .notify_str(format!("Failed to start `{command}`: {e}"), Severity::Error);
+            }
+        }
+    }
     pub fn shutdown(&mut self) {
         self.state = RunningState::Finished;
     }
+    /// Watch recieved data for the given exit conditions, ending the session once one is met.
+    pub fn set_exit_conditions(&mut self, conditions: ExitConditions) -> Result<()> {
+        if let Some(timeout) = conditions.until_deadline() {
+            self.carousel
+                .add_oneshot("ExitTimeout", Tick::ExitTimeout, timeout)?;
+        }
+        self.exit_conditions = Some(conditions);
+        Ok(())
+    }
+    fn exit_with(&mut self, outcome: ExitOutcome) {
+        info!("Exiting: {}", outcome.describe());
+        self.exit_conditions = None;
+        self.exit_outcome = Some(outcome);
+        self.shutdown();
+    }
+    #[cfg(feature = "macros")]
+    /// The macro under the cursor in the Macros menu, if any.
+    fn selected_macro_tag(&self) -> Option<MacroNameTag> {
+        if self.popup != Some(Popup::ToolMenu(ToolMenu::Macros)) {
+            return None;
+        }
+        let index = self.get_corrected_popup_index()?;
+        self.macros
+            .filtered_macro_iter()
+            .nth(index)
+            .map(|(tag, _)| tag.to_owned())
+    }
+    #[cfg(feature = "macros")]
+    fn macro_hotbar_shown(&self) -> bool {
+        self.settings.behavior.show_macro_hotbar && !self.settings.behavior.macro_hotbar.is_empty()
+    }
+    #[cfg(not(feature = "macros"))]
+    fn macro_hotbar_shown(&self) -> bool {
+        false
+    }
+    #[cfg(feature = "macros")]
+    fn render_macro_hotbar(&self, frame: &mut Frame, area: Rect) {
+        if area.is_empty() {
+            return;
+        }
+        let line = self
+            .macros
+            .hotbar_line(&self.settings.behavior.macro_hotbar, area.width);
+        frame.render_widget(line, area);
+    }
+    #[cfg(not(feature = "macros"))]
+    fn render_macro_hotbar(&self, _frame: &mut Frame, _area: Rect) {}
+    #[cfg(feature = "macros")]
+    fn hotbar_macro_for_key(&self, key_combo: KeyCombination) -> Option<MacroNameTag> {
+        if !self.macro_hotbar_shown() || self.popup.is_some() || self.menu != Menu::Terminal {
+            return None;
+        }
+        let slot = hotbar_slot(key_combo)?;
+        self.settings.behavior.macro_hotbar.get(slot).cloned()
+    }
+    #[cfg(feature = "macros")]
+    fn toggle_macro_hotbar_pin(&mut self, tag: &MacroNameTag) -> Result<()> {
+        let text = match toggle_hotbar_pin(&mut self.settings.behavior.macro_hotbar, tag) {
+            Some(true) => format!("Pinned \"{tag}\" to hotbar."),
+            Some(false) => format!("Unpinned \"{tag}\" from hotbar."),
+            None => {
+                self.notifs
+                    .notify_str("Hotbar is full! Unpin a macro first.", Severity::Warning);
+                return Ok(());
+            }
+        };
+        self.scratch.behavior.macro_hotbar = self.settings.behavior.macro_hotbar.clone();
+        self.settings.save()?;
+        self.notifs.notify_str(text, Severity::Success);
+        Ok(())
+    }
+    #[cfg(feature = "macros")]
+    /// Open the macro editor, either for the selected macro,
+    /// or for a new one (in the selected macro's category, if any).
+    fn open_macro_editor(&mut self, edit_selected: bool) {
+        let selected = self
+            .selected_macro_tag()
+            .and_then(|tag| self.macros.all.get_key_value(&tag));
+
+        self.macros.editor = match selected {
+            Some((tag, content)) if edit_selected => MacroEditor::editing(tag, content),
+            None if edit_selected => {
+                self.notifs
+                    .notify_str("Select a macro to edit!", Severity::Warning);
+                return;
+            }
+            selected => MacroEditor::new(selected.and_then(|(tag, _)| tag.category.as_deref())),
+        };
+
+        self.show_popup(Popup::MacroEditor);
+    }
+    #[cfg(feature = "macros")]
+    /// Show what the selected macro would send, without sending it.
+    fn preview_selected_macro(&mut self) {
+        let Some((tag, content)) = self
+            .selected_macro_tag()
+            .and_then(|tag| self.macros.all.get_key_value(&tag))
+        else {
+            self.notifs
+                .notify_str("Select a macro to preview!", Severity::Warning);
+            return;
+        };
+
+        match self.macros.preview(tag, content, &self.settings.serial) {
+            Ok(preview) => {
+                self.macros.previewing = Some(preview);
+                self.show_popup(Popup::MacroPreview);
+            }
+            Err(e) => {
+                error!("Failed to preview macro {tag}: {e}");
+                self.notifs
+                    .notify_str(format!("Can't preview macro: {e}"), Severity::Error);
+            }
+        }
+    }
+    #[cfg(feature = "macros")]
+    fn save_edited_macro(&mut self) {
+        let editor = &self.macros.editor;
+        let original = editor.original.clone();
+        let (tag, content) = editor.to_tag_and_content();
+
+        match self
+            .macros
+            .save_macro(original.as_ref(), tag.clone(), content)
+        {
+            Ok(()) => {
+                self.notifs
+                    .notify_str(format!("Saved macro \"{tag}\"!"), Severity::Success);
+                self.show_popup(Popup::ToolMenu(ToolMenu::Macros));
+            }
+            Err(e) => {
+                error!("Failed to save macro {tag}: {e}");
+                self.notifs
+                    .notify_str(format!("Failed to save macro: {e}"), Severity::Error);
+            }
+        }
+    }
+    #[cfg(feature = "macros")]
+    fn delete_macro_prompt_choice(&mut self, choice: DeleteMacroPrompt) {
+        let Some(Popup::DeleteMacroPrompt(tag)) = self.popup.take() else {
+            unreachable!("Can't delete a macro without its tag!");
+        };
+
+        match choice {
+            DeleteMacroPrompt::Cancel => (),
+            DeleteMacroPrompt::Delete => match self.macros.delete_macro(&tag) {
+                Ok(()) => self
+                    .notifs
+                    .notify_str(format!("Deleted macro \"{tag}\"!"), Severity::Success),
+                Err(e) => {
+                    error!("Failed to delete macro {tag}: {e}");
+                    self.notifs
+                        .notify_str(format!("Failed to delete macro: {e}"), Severity::Error);
+                }
+            },
+        }
+        self.show_popup(Popup::ToolMenu(ToolMenu::Macros));
+    }
     #[cfg(feature = "macros")]
     fn send_one_macro(
         &mut self,
         macro_ref: MacroNameTag,
         key_combo_opt: Option<KeyCombination>,
     ) -> Result<()> {
-        let (macro_tag, macro_content) = self
+        let macro_content = self
             .macros
             .all
-            .iter()
-            .find(|(tag, _string)| &&macro_ref == tag)
-            .ok_or(MacroNotFound)?;
+            .get(&macro_ref)
+            .ok_or(MacroNotFound)?
+            .clone();
 
-        let italic = Style::new().italic();
-
-        assert!(!macro_content.is_empty());
+        self.send_macro_content(macro_ref, macro_content, key_combo_opt)
+    }
+    #[cfg(feature = "macros")]
+    /// Send a macro's content, unless it's still cooling down or needs confirming first.
+    fn send_macro_content(
+        &mut self,
+        macro_tag: MacroNameTag,
+        macro_content: MacroContent,
+        key_combo_opt: Option<KeyCombination>,
+    ) -> Result<()> {
+        if let Some(remaining) = self.macros.cooldown_remaining(&macro_tag, &macro_content) {
+            self.notifs.notify_str(
+                format!(
+                    "Macro \"{macro_tag}\" is cooling down, {:.1}s left.",
+                    remaining.as_secs_f32()
+                ),
+                Severity::Warning,
+            );
+            return Ok(());
+        }
 
-        let (notif_line, notif_color) = match (key_combo_opt, macro_content) {
-            // (_, _) if macro_content.is_empty() => (
-            //     line!["Macro \"", span!(italic; macro_tag), "\" is empty!"],
-            //     Color::Yellow,
-            // ),
-            (Some(key_combo), _) => (
-                line![span!(italic; macro_tag), span!(" [{key_combo}]")],
-                Color::Green,
-            ),
+        if macro_content.confirm {
+            self.pending_confirmation = Some((macro_tag.clone(), macro_content, key_combo_opt));
+            self.show_popup(Popup::ConfirmMacroPrompt(macro_tag));
+            return Ok(());
+        }
 
-            (None, _) => (line![span!(italic; "{macro_tag}")], Color::Green),
+        self.send_macro_content_confirmed(macro_tag, macro_content, key_combo_opt)
+    }
+    #[cfg(feature = "macros")]
+    /// Import macros until one conflicts with an existing macro and the user needs to be asked,
+    /// or until they've all been imported.
+    fn continue_macro_import(&mut self) {
+        let Some(import) = &mut self.macro_import else {
+            return;
+        };
+        match import.next_conflict(&mut self.macros) {
+            Ok(true) => {
+                self.show_popup(Popup::ImportMacroConflict);
+                return;
+            }
+            Ok(false) => {
+                let text = format!(
+                    "Imported {} macros from {} bundles, skipped {}.",
+                    import.imported,
+                    import.bundle_count(),
+                    import.skipped
+                );
+                if import.failed.is_empty() {
+                    self.notifs.notify_str(text, Severity::Success);
+                } else {
+                    let failed: Vec<&str> = import
+                        .failed
+                        .iter()
+                        .filter_map(|path| path.file_name())
+                        .collect();
+                    self.notifs.notify_str(
+                        format!("{text} Couldn't read: {}", failed.join(", ")),
+                        Severity::Warning,
+                    );
+                }
+                if let Some(import) = self.macro_import.take() {
+                    import.finish(&config_adjacent_path(MACRO_BUNDLES_DIR_PATH));
+                }
+            }
+            Err(e) => {
+                error!("Failed to import macro: {e}");
+                self.notifs
+                    .notify_str(format!("Failed to import macro: {e}"), Severity::Error);
+                self.macro_import = None;
+            }
+        }
+        if self.popup == Some(Popup::ImportMacroConflict) {
+            self.show_popup(Popup::ToolMenu(ToolMenu::Macros));
+        }
+    }
+    #[cfg(feature = "macros")]
+    fn import_macro_conflict_choice(&mut self, choice: ImportMacroConflictPrompt) -> Result<()> {
+        let Some(import) = &mut self.macro_import else {
+            unreachable!("Can't resolve an import conflict without an import!");
+        };
+
+        let result = match choice {
+            ImportMacroConflictPrompt::Replace => import.replace_conflict(&mut self.macros),
+            ImportMacroConflictPrompt::KeepBoth => import.keep_both(&mut self.macros),
+            ImportMacroConflictPrompt::Skip | ImportMacroConflictPrompt::SkipAll => {
+                import.skip_conflict(matches!(choice, ImportMacroConflictPrompt::SkipAll));
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            error!("Failed to import macro: {e}");
+            self.notifs
+                .notify_str(format!("Failed to import macro: {e}"), Severity::Error);
+        }
+
+        self.continue_macro_import();
+        Ok(())
+    }
+    #[cfg(feature = "macros")]
+    fn confirm_macro_prompt_choice(&mut self, choice: ConfirmMacroPrompt) -> Result<()> {
+        let Some((tag, content, key_combo)) = self.pending_confirmation.take() else {
+            unreachable!("Can't confirm a macro without one pending!");
+        };
+        self.dismiss_popup();
+
+        match choice {
+            ConfirmMacroPrompt::Send => self.send_macro_content_confirmed(tag, content, key_combo),
+            ConfirmMacroPrompt::Cancel => {
+                if !self.action_queue.is_empty() {
+                    self.notifs.notify_str(
+                        format!("Cleared {} queued actions.", self.action_queue.len()),
+                        Severity::Warning,
+                    );
+                    self.action_queue.clear();
+                }
+                Ok(())
+            }
+        }
+    }
+    #[cfg(feature = "macros")]
+    /// Send a macro's content, queueing its steps if it's a sequence,
+    /// or first asking for the values of any variables it contains.
+    fn send_macro_content_confirmed(
+        &mut self,
+        macro_tag: MacroNameTag,
+        macro_content: MacroContent,
+        key_combo_opt: Option<KeyCombination>,
+    ) -> Result<()> {
+        self.macros.mark_sent(&macro_tag, &macro_content);
+
+        if macro_content.is_sequence() {
+            if self.queue_macro_sequence(&macro_tag, &macro_content, key_combo_opt)? {
+                self.start_macro_repeat(&macro_tag, &macro_content, key_combo_opt)?;
+            }
+            return Ok(());
+        }
+
+        let variables = macro_content.variables();
+
+        // Skip asking if every variable was captured from an earlier macro's response.
+        if !variables.is_empty()
+            && variables
+                .iter()
+                .all(|v| self.macros.captured_values.contains_key(&v.name))
+        {
+            let filled = macro_content.with_variables(&self.macros.captured_values);
+            self.transmit_macro(&macro_tag, &filled, key_combo_opt)?;
+            return self.start_macro_repeat(&macro_tag, &filled, key_combo_opt);
+        }
+
+        if !variables.is_empty() {
+            self.macros.variables_form = Some(MacroVariablesForm::new(
+                macro_tag,
+                macro_content,
+                key_combo_opt,
+                &self.macros.last_variable_values,
+            ));
+            self.show_popup(Popup::MacroVariables);
+            return Ok(());
+        }
+
+        self.transmit_macro(&macro_tag, &macro_content, key_combo_opt)?;
+        self.start_macro_repeat(&macro_tag, &macro_content, key_combo_opt)
+    }
+    #[cfg(feature = "macros")]
+    /// Place a macro sequence's steps at the front of the action queue, to be run next.
+    ///
+    /// Returns `false` if the sequence couldn't be queued.
+    fn queue_macro_sequence(
+        &mut self,
+        macro_tag: &MacroNameTag,
+        macro_content: &MacroContent,
+        key_combo_opt: Option<KeyCombination>,
+    ) -> Result<bool> {
+        let fuzzy = self.settings.behavior.fuzzy_macro_match;
+        if let Some(cycle) = self.macros.find_sequence_cycle(macro_tag, fuzzy) {
+            let chain: Vec<String> = cycle.iter().map(ToString::to_string).collect();
+            self.notifs.notify_str(
+                format!(
+                    "Macro sequence {macro_tag} would never finish: {}! Not sending.",
+                    chain.join(" -> ")
+                ),
+                Severity::Error,
+            );
+            return Ok(false);
+        }
+
+        let mut actions = Vec::with_capacity(macro_content.steps.len() * 2);
+
+        for step in &macro_content.steps {
+            if let Some(macro_ref) = &step.macro_ref {
+                let Some(tag) = self
+                    .macros
+                    .get_by_string(macro_ref, self.settings.behavior.fuzzy_macro_match)
+                else {
+                    self.notifs.notify_str(
+                        format!("Macro \"{macro_ref}\" in sequence {macro_tag} not found!"),
+                        Severity::Error,
+                    );
+                    return Ok(false);
+                };
+                actions.push(Action::MacroInvocation(tag));
+            }
+            if let Some(content) = step.content.as_ref().filter(|c| !c.is_empty()) {
+                let line_ending = step
+                    .line_ending
+                    .clone()
+                    .or_else(|| macro_content.escaped_line_ending.clone());
+                let step_content = MacroContent::new_with_line_ending(
+                    content,
+                    line_ending,
+                    macro_content.sensitive,
+                );
+                actions.push(Action::MacroPayload(
+                    macro_tag.clone(),
+                    Box::new(step_content),
+                ));
+            }
+            if let Some(pattern) = &step.expect {
+                if let Err(e) = regex::bytes::Regex::new(pattern) {
+                    error!("Invalid expect pattern in {macro_tag}: {e}");
+                    self.notifs.notify_str(
+                        format!("Invalid expect pattern \"{pattern}\" in sequence {macro_tag}!"),
+                        Severity::Error,
+                    );
+                    return Ok(false);
+                }
+                actions.push(Action::MacroExpect(
+                    macro_tag.clone(),
+                    pattern.clone(),
+                    step.expect_timeout(),
+                ));
+            }
+            if let Some(delay_ms) = step.delay_ms {
+                actions.push(Action::Pause(Duration::from_millis(delay_ms)));
+            }
+        }
+
+        // Catches sequences that nest too many others to queue.
+        if self.action_queue.len() + actions.len() > MAX_QUEUED_ACTIONS {
+            self.notifs.notify_str(
+                format!("Macro sequence {macro_tag} is too long! Clearing queue..."),
+                Severity::Error,
+            );
+            self.action_queue.clear();
+            return Ok(false);
+        }
+
+        for action in actions.into_iter().rev() {
+            self.action_queue.push_front((key_combo_opt, action));
+        }
+
+        self.start_action_queue()?;
+        Ok(true)
+    }
+    #[cfg(feature = "macros")]
+    /// If the just-sent macro repeats, begin re-sending it on its interval,
+    /// replacing any other repeating macro.
+    fn start_macro_repeat(
+        &mut self,
+        macro_tag: &MacroNameTag,
+        macro_content: &MacroContent,
+        key_combo_opt: Option<KeyCombination>,
+    ) -> Result<()> {
+        let Some(repeating) = RepeatingMacro::new(macro_tag, macro_content, key_combo_opt) else {
+            return Ok(());
+        };
+
+        if let Some(previous) = &self.repeating_macro
+            && previous.tag != repeating.tag
+        {
+            self.notifs.notify_str(
+                format!("Stopped repeating {}.", previous.tag),
+                Severity::Warning,
+            );
+        }
+
+        self.carousel
+            .add_oneshot("MacroRepeat", Tick::MacroRepeat, repeating.interval)?;
+        self.repeating_macro = Some(repeating);
+        Ok(())
+    }
+    #[cfg(feature = "macros")]
+    fn resend_repeating_macro(&mut self) -> Result<()> {
+        let Some(repeating) = &self.repeating_macro else {
+            // Was stopped before this tick arrived.
+            return Ok(());
+        };
+        let interval = repeating.interval;
+
+        if !self.serial.port_status.load().inner.is_connected() {
+            self.notifs.notify_str(
+                format!("Port isn't ready! Stopped repeating {}.", repeating.tag),
+                Severity::Error,
+            );
+            self.repeating_macro = None;
+            return Ok(());
+        }
+
+        // Wait for any running sequence or variable prompt to finish, rather than piling up.
+        if !self.action_queue.is_empty()
+            || self.macro_expectation.is_some()
+            || self.popup == Some(Popup::MacroVariables)
+        {
+            self.carousel
+                .add_oneshot("MacroRepeat", Tick::MacroRepeat, interval)?;
+            return Ok(());
+        }
+
+        let RepeatingMacro {
+            tag,
+            content,
+            key_combo,
+            ..
+        } = repeating.clone();
+
+        let sent = if content.is_sequence() {
+            self.queue_macro_sequence(&tag, &content, key_combo)?
+        } else {
+            self.transmit_macro(&tag, &content, key_combo)?;
+            true
+        };
+
+        let Some(repeating) = self.repeating_macro.as_mut().filter(|_| sent) else {
+            self.repeating_macro = None;
+            return Ok(());
+        };
+
+        match &mut repeating.remaining {
+            Some(1) => self.repeating_macro = None,
+            Some(remaining) => {
+                *remaining -= 1;
+                self.carousel
+                    .add_oneshot("MacroRepeat", Tick::MacroRepeat, interval)?;
+            }
+            None => {
+                self.carousel
+                    .add_oneshot("MacroRepeat", Tick::MacroRepeat, interval)?;
+            }
+        }
+
+        Ok(())
+    }
+    #[cfg(feature = "macros")]
+    /// Header indicator for a macro being recorded or repeating, if any.
+    fn macro_status_line(&self) -> Option<Line<'_>> {
+        if let Some(recorder) = &self.macro_recorder {
+            return Some(line![
+                span!(Style::new().red(); "Recording Macro: "),
+                span!("{} sent", recorder.len()),
+            ]);
+        }
+        let repeating = self.repeating_macro.as_ref()?;
+        let remaining = match repeating.remaining {
+            Some(remaining) => format!(" ({remaining} left)"),
+            None => String::new(),
+        };
+        Some(line![
+            span!(Style::new().yellow(); "Repeating: "),
+            span!(Style::new().italic(); repeating.tag),
+            Span::raw(remaining),
+        ])
+    }
+    #[cfg(not(feature = "macros"))]
+    fn macro_status_line(&self) -> Option<Line<'_>> {
+        None
+    }
+    #[cfg(feature = "logging")]
+    /// Load a capture to play back into the buffer, showing the terminal as if connected.
+    pub fn start_replay(&mut self, path: &camino::Utf8Path) -> Result<()> {
+        let replay = Replay::load(path)
+            .wrap_err_with(|| format!("failed to load capture to replay from {path}"))?;
+        self.notifs
+            .notify_str(format!("Replaying {}", replay.path()), Severity::Success);
+        self.replay = Some(replay);
+        self.menu = Menu::Terminal;
+        self.advance_replay()
+    }
+    #[cfg(feature = "logging")]
+    /// Play any chunks of the replay that are due, and wait for the one after.
+    fn advance_replay(&mut self) -> Result<()> {
+        let Some(replay) = &mut self.replay else {
+            // Was stopped before this tick arrived.
+            return Ok(());
+        };
+        for chunk in replay.take_due() {
+            match chunk.direction {
+                TrafficDirection::Rx => self.buffer.fresh_rx_bytes(
+                    chunk.timestamp,
+                    crate::monotonic_micros(),
+                    chunk.bytes.clone(),
+                ),
+                TrafficDirection::Tx => self
+                    .buffer
+                    .replayed_user_bytes(chunk.timestamp, &chunk.bytes),
+            }
+        }
+        if let Some(next_in) = replay.until_next() {
+            self.carousel.add_oneshot("Replay", Tick::Replay, next_in)?;
+        }
+        Ok(())
+    }
+    #[cfg(feature = "logging")]
+    fn control_replay(&mut self, action: LoggingAction) -> Result<()> {
+        let Some(replay) = &mut self.replay else {
+            self.notifs
+                .notify_str("No capture is being replayed!", Severity::Warning);
+            return Ok(());
+        };
+        match action {
+            LoggingAction::ReplayPause => _ = replay.toggle_pause(),
+            LoggingAction::ReplayForward => _ = replay.seek(true, SEEK_STEP),
+            LoggingAction::ReplayBack => {
+                if replay.seek(false, SEEK_STEP) {
+                    self.buffer.clear();
+                }
+            }
+            LoggingAction::ReplayFaster => _ = replay.change_speed(true),
+            LoggingAction::ReplaySlower => _ = replay.change_speed(false),
+            _ => unreachable!("not a replay action"),
+        }
+        self.advance_replay()
+    }
+    #[cfg(feature = "macros")]
+    /// Send the variable-filled macro from the form, remembering the entered values.
+    fn submit_macro_variables(&mut self) -> Result<()> {
+        let Some(form) = self.macros.variables_form.take() else {
+            unreachable!("Can't submit variables without a form!");
+        };
+        self.dismiss_popup();
+
+        let values = form.values();
+        let content = form.content.with_variables(&values);
+        self.macros.last_variable_values.extend(values);
+
+        if content.is_empty() {
+            self.notifs.notify_str("Macro is empty!", Severity::Warning);
+            return Ok(());
+        }
+
+        self.transmit_macro(&form.tag, &content, form.key_combo)?;
+        self.start_macro_repeat(&form.tag, &content, form.key_combo)
+    }
+    #[cfg(feature = "macros")]
+    /// Dismiss the variables form without sending, along with any actions queued after the macro.
+    fn cancel_macro_variables(&mut self) {
+        self.macros.variables_form = None;
+        self.dismiss_popup();
+
+        if !self.action_queue.is_empty() {
+            self.notifs.notify_str(
+                format!("Cleared {} queued actions.", self.action_queue.len()),
+                Severity::Warning,
+            );
+            self.action_queue.clear();
+        }
+    }
+    #[cfg(feature = "macros")]
+    fn transmit_macro(
+        &mut self,
+        macro_tag: &MacroNameTag,
+        macro_content: &MacroContent,
+        key_combo_opt: Option<KeyCombination>,
+    ) -> Result<()> {
+        let italic = Style::new().italic();
+
+        assert!(!macro_content.is_empty());
+
+        // Expanded on every send, so repeats get a fresh time/counter/random value.
+        let expanded = self.macros.expand_templates(macro_tag, macro_content);
+        let macro_content = expanded.as_ref().unwrap_or(macro_content);
+
+        let (notif_line, notif_severity) = match (key_combo_opt, macro_content) {
+            // (_, _) if macro_content.is_empty() => (
+            //     line!["Macro \"", span!(italic; macro_tag), "\" is empty!"],
+            //     Severity::Warning,
+            // ),
+            (Some(key_combo), _) => (
+                line![span!(italic; macro_tag), span!(" [{key_combo}]")],
+                Severity::Success,
+            ),
+
+            (None, _) => (line![span!(italic; "{macro_tag}")], Severity::Success),
         };
 
         let default_macro_line_ending = self.settings.serial.macro_line_ending.as_bytes(
@@ -1056,6 +2226,36 @@ impl App {
 
         match macro_content {
             _ if macro_content.is_empty() => (),
+            MacroContent {
+                content_file: Some(content_file),
+                ..
+            } => {
+                let content = match self.macros.read_content_file(macro_tag, content_file) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        error!("Failed to read content file for macro {macro_tag}: {e}");
+                        self.notifs
+                            .notify_str(format!("Failed to read macro file: {e}"), Severity::Error);
+                        return Ok(());
+                    }
+                };
+                // Files are sent verbatim, unless the macro explicitly asks for a line ending.
+                let line_ending: &[u8] = match &macro_content.escaped_line_ending {
+                    Some(_) => &macro_line_ending,
+                    None => &[],
+                };
+                debug!(
+                    "Sending {} bytes from Macro file {content_file}",
+                    content.len()
+                );
+                self.serial.send_bytes(content.clone(), Some(line_ending))?;
+                self.buffer.append_user_bytes(
+                    &content,
+                    line_ending,
+                    #[cfg(feature = "macros")]
+                    Some(macro_content.sensitive),
+                );
+            }
             _ if macro_content.has_escaped_bytes => {
                 let content = macro_content.unescape_bytes();
                 self.serial
@@ -1089,7 +2289,7 @@ impl App {
             }
         };
 
-        self.notifs.notify(notif_line, notif_color);
+        self.notifs.notify(notif_line, notif_severity);
 
         // Scroll all the way down
         // TODO: Make this behavior a toggle
@@ -1097,8 +2297,102 @@ impl App {
 
         // TODO scroll bar is goofed with just user lines???
 
+        if let Some(capture) = &macro_content.capture {
+            self.start_macro_capture(macro_tag, capture)?;
+        }
+
         Ok(())
     }
+    #[cfg(feature = "macros")]
+    fn start_macro_capture(
+        &mut self,
+        macro_tag: &MacroNameTag,
+        capture: &MacroCapture,
+    ) -> Result<()> {
+        let capture = match ResponseCapture::new(macro_tag, capture) {
+            Ok(capture) => capture,
+            Err(e) => {
+                error!("Invalid capture pattern in {macro_tag}: {e}");
+                self.notifs.notify_str(
+                    format!("Invalid capture pattern in {macro_tag}!"),
+                    Severity::Error,
+                );
+                return Ok(());
+            }
+        };
+
+        let timeout = capture.deadline.saturating_duration_since(Instant::now());
+        self.carousel
+            .add_oneshot("MacroCapture", Tick::MacroCapture, timeout)?;
+        self.macro_capture = Some(capture);
+        Ok(())
+    }
+    #[cfg(feature = "macros")]
+    /// Store and/or copy what was captured, even if only partially when timed out.
+    fn finish_macro_capture(&mut self, timed_out: bool) {
+        let Some(capture) = self.macro_capture.take() else {
+            return;
+        };
+
+        if timed_out && capture.line_count() == 0 {
+            self.notifs.notify_str(
+                format!("Timed out capturing response to {}!", capture.tag),
+                Severity::Error,
+            );
+            return;
+        }
+
+        let text = capture.text();
+        let mut destinations = Vec::new();
+
+        if let Some(variable) = &capture.variable {
+            self.macros
+                .captured_values
+                .insert(variable.clone(), text.as_str().into());
+            self.macros
+                .last_variable_values
+                .insert(variable.clone(), text.as_str().into());
+            destinations.push(format!("{{{{{variable}}}}}"));
+        }
+
+        if capture.clipboard {
+            match self
+                .text_input
+                .clipboard
+                .as_mut()
+                .map(|c| c.set_text(&text))
+            {
+                Some(Ok(())) => destinations.push("clipboard".to_owned()),
+                Some(Err(e)) => {
+                    error!("error setting clipboard text: {e}");
+                    self.notifs
+                        .notify_str("Failed to copy to clipboard!", Severity::Error);
+                    return;
+                }
+                None => {
+                    self.notifs
+                        .notify_str("Clipboard not supported!", Severity::Error);
+                    return;
+                }
+            }
+        }
+
+        let lines = capture.line_count();
+        let destinations = if destinations.is_empty() {
+            String::new()
+        } else {
+            format!(" into {}", destinations.join(" and "))
+        };
+        let (partial, severity) = if timed_out {
+            (" (timed out)", Severity::Warning)
+        } else {
+            ("", Severity::Success)
+        };
+        self.notifs.notify_str(
+            format!("Captured {lines} lines{destinations}{partial}."),
+            severity,
+        );
+    }
     // TODO fuzz this
     fn handle_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
         // Intentionally putting this before the Ctrl-C ack-er,
@@ -1129,6 +2423,11 @@ impl App {
         let key_combo = KeyCombination::from(key_event);
         // debug!("{key_combo}");
 
+        // Mid-chord, every key goes towards finishing it rather than typing or sending.
+        if !self.pending_chord.is_empty() {
+            return self.continue_chord(key_combo);
+        }
+
         let mut port_selection_actions = false;
         let mut terminal_view_actions = false;
         // Filter for when we decide to handle user *text input*.
@@ -1162,7 +2461,7 @@ impl App {
             if let Err(e) = file_explorer.handle(input) {
                 error!("File Explorer Error: {e}");
                 self.notifs
-                    .notify_str(format!("Explorer Error: {e}"), Color::Red);
+                    .notify_str(format!("Explorer Error: {e}"), Severity::Error);
                 self.dismiss_popup();
                 return Ok(());
             };
@@ -1176,7 +2475,7 @@ impl App {
 
                         let Ok(elf_path) = camino::Utf8PathBuf::from_path_buf(path_buf) else {
                             self.notifs
-                                .notify_str("Path is not valid UTF-8!", Color::Red);
+                                .notify_str("Path is not valid UTF-8!", Severity::Error);
                             return Ok(());
                         };
 
@@ -1194,10 +2493,70 @@ impl App {
             }
         }
 
+        // While typing into a settings field, all keys but Ctrl-C go to its editor.
+        if let Some(editor) = &mut self.settings_field_editor
+            && !is_ctrl_c(&key_event)
+        {
+            match key_event.code {
+                KeyCode::Enter => self.apply_settings_field_editor(),
+                KeyCode::Esc => self.settings_field_editor = None,
+                _ => {
+                    editor
+                        .input
+                        .handle_event(&ratatui::crossterm::event::Event::Key(key_event));
+                }
+            }
+            return Ok(());
+        }
+
+        // Keys go to an ongoing history search first, anything it doesn't use ends the search and is handled normally.
+        if matches!((self.menu, &self.popup), (Menu::Terminal, None))
+            && self.settings.behavior.pseudo_shell
+            && !self.navigation.active
+            && !self
+                .keybinds
+                .key_has_single_action(key_combo, BaseAction::ReverseHistorySearch.into())
+            && self.text_input.reverse_search_key(key_event)
+        {
+            return Ok(());
+        }
+
+        // While completions are shown, Tab and Shift-Tab cycle through them and Esc puts back what was typed.
+        // Anything else keeps the one picked, and is then handled as usual.
+        if matches!((self.menu, &self.popup), (Menu::Terminal, None))
+            && let Some(completions) = &mut self.completions
+        {
+            match key_event.code {
+                KeyCode::Tab | KeyCode::BackTab => {
+                    if key_event.code == KeyCode::Tab {
+                        completions.select_next();
+                    } else {
+                        completions.select_prev();
+                    }
+                    self.text_input
+                        .replace_input_with_text(completions.selected_input());
+                    return Ok(());
+                }
+                KeyCode::Esc => {
+                    self.text_input
+                        .replace_input_with_text(completions.original());
+                    self.completions = None;
+                    return Ok(());
+                }
+                _ => self.completions = None,
+            }
+        }
+
         match (self.menu, &self.popup) {
             (Menu::Terminal, None) => {
                 terminal_view_actions = true;
                 match key_combo {
+                    // Unhandled keys are only checked against keybinds, never typed or sent.
+                    _ if self.navigation.active => {
+                        if self.navigation_key_press(key_event) {
+                            return Ok(());
+                        }
+                    }
                     // Consuming Ctrl-A so input_box.handle_event doesn't move my cursor.
                     key!(ctrl - a) => (),
                     key!(del) | key!(backspace) if self.text_input.all_text_selected => (),
@@ -1248,6 +2607,21 @@ impl App {
                     self.reconnect_prompt_choice(pressed, shift_pressed, ctrl_pressed)?;
                 }
             }
+            (_, Some(Popup::PortInUse(_, _))) if !is_ctrl_c(&key_event) => {
+                if let Some(pressed) = PortInUsePrompt::from_key_code(key_event.code) {
+                    self.port_in_use_prompt_choice(pressed)?;
+                }
+            }
+            (_, Some(Popup::LargePastePrompt(_))) if !is_ctrl_c(&key_event) => {
+                let pressed = match key_event.code {
+                    KeyCode::Esc => Some(LargePastePrompt::Cancel),
+                    code => LargePastePrompt::from_key_code(code),
+                };
+                if let Some(pressed) = pressed {
+                    self.large_paste_prompt_choice(pressed)?;
+                    return Ok(());
+                }
+            }
             (_, Some(Popup::IgnoreByName(_))) if !is_ctrl_c(&key_event) => {
                 if let Some(pressed) = IgnorePortByNamePrompt::from_key_code(key_event.code) {
                     self.ignore_port_name_prompt_choice(pressed)?;
@@ -1258,6 +2632,215 @@ impl App {
                     self.ignore_usb_device_prompt_choice(pressed)?;
                 }
             }
+            #[cfg(feature = "defmt")]
+            #[cfg(feature = "defmt-watch")]
+            (_, Some(Popup::DefmtRecentElf)) if key_event.code == KeyCode::Char('w') => {
+                self.toggle_extra_watched_elf()?;
+                return Ok(());
+            }
+            #[cfg(feature = "defmt")]
+            (_, Some(Popup::DefmtRecentElf))
+                if matches!(key_event.code, KeyCode::Char('p') | KeyCode::Delete) =>
+            {
+                let recent_elfs = &mut self.defmt_helpers.recent_elfs;
+                if key_event.code == KeyCode::Delete {
+                    recent_elfs.remove(self.popup_menu_scroll)?;
+                    if recent_elfs.is_empty() {
+                        self.dismiss_popup();
+                    } else {
+                        self.popup_menu_scroll = self.popup_menu_scroll.min(recent_elfs.len() - 1);
+                    }
+                } else {
+                    let was_pinned = recent_elfs.is_pinned(self.popup_menu_scroll);
+                    recent_elfs.toggle_pin(self.popup_menu_scroll)?;
+                    // Follow the entry to its new spot.
+                    self.popup_menu_scroll = if was_pinned {
+                        recent_elfs.pinned_len()
+                    } else {
+                        recent_elfs.pinned_len().saturating_sub(1)
+                    };
+                }
+                return Ok(());
+            }
+            #[cfg(feature = "defmt")]
+            (_, Some(Popup::DefmtFailedFrame(bytes)))
+                if key_event.code == KeyCode::Char('c') && !is_ctrl_c(&key_event) =>
+            {
+                let hexdump = hexdump_rows(bytes).join("\n");
+                match self
+                    .text_input
+                    .clipboard
+                    .as_mut()
+                    .map(|c| c.set_text(hexdump))
+                {
+                    Some(Ok(())) => self
+                        .notifs
+                        .notify_str("Copied frame hexdump to clipboard!", Severity::Success),
+                    Some(Err(e)) => {
+                        error!("error setting clipboard text: {e}");
+                        self.notifs
+                            .notify_str("Failed to copy to clipboard!", Severity::Error);
+                    }
+                    None => self
+                        .notifs
+                        .notify_str("Clipboard not supported!", Severity::Error),
+                }
+                return Ok(());
+            }
+            #[cfg(feature = "defmt")]
+            (_, Some(Popup::DefmtFoundElf(_))) if !is_ctrl_c(&key_event) => {
+                if let Some(pressed) = DefmtFoundElfPrompt::from_key_code(key_event.code) {
+                    self.defmt_found_elf_choice(pressed);
+                }
+            }
+            (_, Some(Popup::NotificationHistory))
+                if key_event.code == KeyCode::Char('c') && !is_ctrl_c(&key_event) =>
+            {
+                self.copy_selected_notification();
+                return Ok(());
+            }
+            (_, Some(Popup::KeybindEditor)) if !is_ctrl_c(&key_event) => {
+                self.keybind_editor_key_press(key_event);
+                return Ok(());
+            }
+            (_, Some(Popup::SetupWizard)) if !is_ctrl_c(&key_event) => {
+                self.setup_wizard_key_press(key_event)?;
+                return Ok(());
+            }
+            (_, Some(Popup::SettingsSearch)) if !is_ctrl_c(&key_event) => {
+                let search = &mut self.settings_search;
+                match key_event.code {
+                    KeyCode::Esc => self.dismiss_popup(),
+                    KeyCode::Enter => self.jump_to_searched_setting(),
+                    KeyCode::Down | KeyCode::Tab => search.select_next(),
+                    KeyCode::Up | KeyCode::BackTab => search.select_prev(),
+                    _ => {
+                        search
+                            .input
+                            .handle_event(&ratatui::crossterm::event::Event::Key(key_event));
+                        search.update_matches();
+                    }
+                }
+                return Ok(());
+            }
+            (_, Some(Popup::ComposeEditor)) if !is_ctrl_c(&key_event) => {
+                let send_pressed = match key_event.code {
+                    KeyCode::Enter => key_event
+                        .modifiers
+                        .intersects(KeyModifiers::ALT | KeyModifiers::CONTROL),
+                    KeyCode::Char('s') => key_event.modifiers == KeyModifiers::CONTROL,
+                    _ => false,
+                };
+                if send_pressed {
+                    self.send_composed_lines()?;
+                } else if key_event.code == KeyCode::Esc {
+                    self.dismiss_popup();
+                } else {
+                    self.compose_editor.handle_key(key_event);
+                }
+                return Ok(());
+            }
+            (_, Some(Popup::CommandPalette)) if !is_ctrl_c(&key_event) => {
+                let palette = &mut self.command_palette;
+                match key_event.code {
+                    KeyCode::Esc => self.dismiss_popup(),
+                    KeyCode::Enter => self.run_palette_command()?,
+                    KeyCode::Down | KeyCode::Tab => palette.select_next(),
+                    KeyCode::Up | KeyCode::BackTab => palette.select_prev(),
+                    _ => {
+                        palette
+                            .input
+                            .handle_event(&ratatui::crossterm::event::Event::Key(key_event));
+                        palette.update_matches();
+                    }
+                }
+                return Ok(());
+            }
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::MacroEditor)) if !is_ctrl_c(&key_event) => {
+                let editor = &mut self.macros.editor;
+                match key_event.code {
+                    KeyCode::Enter => self.save_edited_macro(),
+                    KeyCode::Esc => self.show_popup(Popup::ToolMenu(ToolMenu::Macros)),
+                    KeyCode::Down | KeyCode::Tab => editor.selected = editor.selected.next(),
+                    KeyCode::Up | KeyCode::BackTab => editor.selected = editor.selected.prev(),
+                    KeyCode::Char(' ') if editor.selected == MacroEditorField::Sensitive => {
+                        editor.sensitive.flip();
+                    }
+                    _ => {
+                        if let Some(input) = editor.selected_input() {
+                            input.handle_event(&ratatui::crossterm::event::Event::Key(key_event));
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::MacroVariables)) if !is_ctrl_c(&key_event) => {
+                let Some(form) = &mut self.macros.variables_form else {
+                    unreachable!("variables popup open without a form!");
+                };
+                match key_event.code {
+                    KeyCode::Enter => self.submit_macro_variables()?,
+                    KeyCode::Esc => self.cancel_macro_variables(),
+                    KeyCode::Down | KeyCode::Tab => form.select_next(),
+                    KeyCode::Up | KeyCode::BackTab => form.select_prev(),
+                    _ => {
+                        if let Some(input) = form.selected_input() {
+                            input.handle_event(&ratatui::crossterm::event::Event::Key(key_event));
+                        }
+                    }
+                }
+                return Ok(());
+            }
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::MacroPreview))
+                if matches!(key_event.code, KeyCode::Esc | KeyCode::Enter) =>
+            {
+                self.macros.previewing = None;
+                self.show_popup(Popup::ToolMenu(ToolMenu::Macros));
+                return Ok(());
+            }
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::DeleteMacroPrompt(_))) if !is_ctrl_c(&key_event) => {
+                if let Some(pressed) = DeleteMacroPrompt::from_key_code(key_event.code) {
+                    self.delete_macro_prompt_choice(pressed);
+                }
+            }
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::ConfirmMacroPrompt(_))) if !is_ctrl_c(&key_event) => {
+                // Escaping has to cancel too, so a pending chain doesn't get stuck waiting.
+                let pressed = match key_event.code {
+                    KeyCode::Esc => Some(ConfirmMacroPrompt::Cancel),
+                    code => ConfirmMacroPrompt::from_key_code(code),
+                };
+                if let Some(pressed) = pressed {
+                    self.confirm_macro_prompt_choice(pressed)?;
+                    return Ok(());
+                }
+            }
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::ImportMacroConflict)) if !is_ctrl_c(&key_event) => {
+                // Escaping skips the rest, so the import isn't left half-finished.
+                let pressed = match key_event.code {
+                    KeyCode::Esc => Some(ImportMacroConflictPrompt::SkipAll),
+                    code => ImportMacroConflictPrompt::from_key_code(code),
+                };
+                if let Some(pressed) = pressed {
+                    self.import_macro_conflict_choice(pressed)?;
+                    return Ok(());
+                }
+            }
+            (_, Some(Popup::RevertSettingsPrompt(_))) if !is_ctrl_c(&key_event) => {
+                let pressed = match key_event.code {
+                    KeyCode::Esc => Some(RevertSettingsPrompt::Cancel),
+                    code => RevertSettingsPrompt::from_key_code(code),
+                };
+                if let Some(pressed) = pressed {
+                    self.revert_settings_prompt_choice(pressed)?;
+                    return Ok(());
+                }
+            }
             (_, Some(Popup::UpdateCheckConsentPrompt)) if !is_ctrl_c(&key_event) => {
                 if let Some(pressed) = UpdateCheckConsentPrompt::from_key_code(key_event.code) {
                     self.update_check_consent_choice(pressed)?;
@@ -1274,6 +2857,25 @@ impl App {
                     self.update_launch_choice(pressed)?;
                 }
             }
+            #[cfg(feature = "self-replace")]
+            (_, Some(Popup::UpdateFromArchivePrompt(_))) if !is_ctrl_c(&key_event) => {
+                if let Some(pressed) = UpdateFromArchivePrompt::from_key_code(key_event.code) {
+                    self.update_from_archive_choice(pressed)?;
+                }
+            }
+            #[cfg(feature = "defmt")]
+            (_, Some(Popup::DefmtModuleFilter)) if !is_ctrl_c(&key_event) => {
+                match key_event.code {
+                    KeyCode::Enter => self.apply_defmt_module_filter()?,
+                    KeyCode::Esc => self.dismiss_popup(),
+                    _ => {
+                        self.defmt_helpers
+                            .module_filter_input
+                            .handle_event(&ratatui::crossterm::event::Event::Key(key_event));
+                    }
+                }
+                return Ok(());
+            }
 
             (_, Some(Popup::SettingsMenu(SettingsMenu::SerialPort)))
                 if self.get_corrected_popup_index() == Some(0) =>
@@ -1325,6 +2927,9 @@ impl App {
             (Menu::Terminal, None) => false,
             _ => true,
         };
+        if let Some(reserved) = ReservedKey::from_key_combo(key_combo) {
+            return self.reserved_key_pressed(reserved, terminal_view_actions);
+        }
         // TODO split this up into more functions based on menu
         match key_combo {
             // Start of _Hardcoded_ keybinds.
@@ -1336,41 +2941,15 @@ impl App {
             {
                 self.show_popup(Popup::UpdateBeginPrompt);
             }
-            key!(ctrl - shift - c) => self.shutdown(),
-            // move into ctrl-c func?
-            key!(ctrl - c) => match (self.menu, &self.popup) {
-                (_, Some(Popup::AttemptReconnectPrompt)) | (_, Some(Popup::DisconnectPrompt)) => {
-                    self.shutdown()
-                }
-                (Menu::Terminal, None) => {
-                    self.show_popup(Popup::DisconnectPrompt);
-                }
-                (_, Some(_)) => {
-                    self.dismiss_popup();
-                }
-                _ => self.shutdown(),
-            },
             key!(ctrl - a) if terminal_view_actions && !self.text_input.value().is_empty() => {
                 self.text_input.all_text_selected = true;
             }
-            key!(ctrl - backspace)
-                if terminal_view_actions && !self.text_input.value().is_empty() =>
-            {
-                self.text_input.remove_one_word();
-            }
             key!(home) if self.popup.is_some() => {
                 self.popup_menu_scroll = 0;
             }
-            key!(ctrl - pageup) | key!(shift - pageup) => self.buffer.scroll_by(i32::MAX),
-            key!(ctrl - pagedown) | key!(shift - pagedown) => self.buffer.scroll_by(i32::MIN),
-            key!(ctrl - shift - delete) | key!(ctrl - shift - backspace) => {
-                self.text_input.clear();
-            }
             key!(delete) | key!(backspace) if self.text_input.all_text_selected => {
                 self.text_input.clear();
             }
-            key!(pageup) => self.buffer.scroll_page_up(),
-            key!(pagedown) => self.buffer.scroll_page_down(),
             // KeyCode::F(f_key) if ctrl_pressed && shift_pressed => {
             //     let meow = key!(ctrl - c);
             //     self.notify(format!("Pressed Ctrl-Shift-F{f_key}"), Color::Blue)
@@ -1403,62 +2982,387 @@ impl App {
                     }
                 }
             }
-            key!(up) => self.up_pressed(),
-            key!(down) => self.down_pressed(),
-            key!(left) => self.left_pressed(),
-            key!(right) => self.right_pressed(),
-            key!(enter) => self.enter_pressed(false, false)?,
-            key!(ctrl - enter) => self.enter_pressed(true, false)?,
-            key!(shift - enter) => self.enter_pressed(false, true)?,
-            key!(ctrl - shift - enter) => self.enter_pressed(true, true)?,
             key!(tab) if terminal_view_actions && self.popup.is_none() => {
-                self.text_input.find_input_in_history();
+                self.start_completion(false);
+            }
+            _ if key_event.code == KeyCode::BackTab
+                && terminal_view_actions
+                && self.popup.is_none() =>
+            {
+                self.start_completion(true);
             }
             // KeyCode::Tab => self.tab_pressed(),
             key!(ctrl - r) if self.popup == Some(Popup::CurrentKeybinds) => {
                 self.run_builtin_action(BuiltinAction::Base(BaseAction::ReloadKeybinds))?;
             }
+            key!(ctrl - e) if self.popup == Some(Popup::CurrentKeybinds) => {
+                self.show_popup(Popup::KeybindEditor);
+            }
+            key!(ctrl - d) if matches!(self.popup, Some(Popup::SettingsMenu(_))) => {
+                let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+                    unreachable!();
+                };
+                self.show_popup(Popup::RevertSettingsPrompt(menu.clone()));
+            }
+            key!(ctrl - f) if matches!(self.popup, Some(Popup::SettingsMenu(_))) => {
+                self.show_popup(Popup::SettingsSearch);
+            }
+            key!(delete) if matches!(self.popup, Some(Popup::SettingsMenu(_))) => {
+                self.reset_selected_setting();
+            }
             #[cfg(feature = "macros")]
             key!(ctrl - r) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
                 self.run_builtin_action(BuiltinAction::MacroBuiltin(
                     MacroBuiltinAction::ReloadMacros,
                 ))?;
             }
+            #[cfg(feature = "macros")]
+            key!(ctrl - n) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
+                self.open_macro_editor(false);
+            }
+            #[cfg(feature = "macros")]
+            key!(ctrl - e) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
+                self.open_macro_editor(true);
+            }
+            #[cfg(feature = "macros")]
+            key!(ctrl - d) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
+                if let Some(tag) = self.selected_macro_tag() {
+                    self.show_popup(Popup::DeleteMacroPrompt(tag));
+                }
+            }
+            #[cfg(feature = "macros")]
+            key!(ctrl - s) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
+                self.run_builtin_action(BuiltinAction::MacroBuiltin(
+                    MacroBuiltinAction::ExportMacros,
+                ))?;
+            }
+            #[cfg(feature = "macros")]
+            key!(ctrl - o) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
+                self.run_builtin_action(BuiltinAction::MacroBuiltin(
+                    MacroBuiltinAction::ImportMacros,
+                ))?;
+            }
+            #[cfg(feature = "macros")]
+            key!(ctrl - w) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
+                self.preview_selected_macro();
+            }
+            #[cfg(feature = "macros")]
+            key!(ctrl - p) if self.popup == Some(Popup::ToolMenu(ToolMenu::Macros)) => {
+                if let Some(tag) = self.selected_macro_tag() {
+                    self.toggle_macro_hotbar_pin(&tag)?;
+                }
+            }
             #[cfg(feature = "espflash")]
             key!(ctrl - r) if self.popup == Some(Popup::ToolMenu(ToolMenu::EspFlash)) => {
                 self.run_builtin_action(BuiltinAction::EspBuiltin(
                     EspBuiltinAction::ReloadProfiles,
                 ))?;
             }
-            key!(esc) => self.esc_pressed(),
-            key_combo => {
-                let Some(actions_str) = self.keybinds.action_strs_from_key_combo(key_combo)
-                // .map(ToOwned::to_owned)
-                else {
-                    return Ok(());
-                };
+            key_combo if self.keybinds.is_chord_prefix(&[key_combo]) => {
+                self.pending_chord.push(key_combo);
+            }
+            key_combo => {
+                let Some(actions_str) = self
+                    .keybinds
+                    .action_strs_in_contexts(&self.keybind_contexts(), key_combo)
+                    .cloned()
+                else {
+                    // User keybinds take priority over the hotbar's.
+                    #[cfg(feature = "macros")]
+                    if let Some(tag) = self.hotbar_macro_for_key(key_combo) {
+                        self.queue_keybinds_action_set(
+                            vec![Action::MacroInvocation(tag)],
+                            Some(key_combo),
+                        )?;
+                    }
+                    return Ok(());
+                };
+
+                let Some(actions) = self.actions_from_strings(&actions_str) else {
+                    return Ok(());
+                };
+
+                debug!("{key_combo}: {actions:?}");
+
+                self.queue_keybinds_action_set(actions, Some(key_combo))?;
+            }
+        }
+        Ok(())
+    }
+    /// Handle the keys the app always keeps for itself, see [`ReservedKey`].
+    fn reserved_key_pressed(
+        &mut self,
+        reserved: ReservedKey,
+        terminal_view_actions: bool,
+    ) -> Result<()> {
+        match reserved {
+            ReservedKey::CtrlShiftC => self.shutdown(),
+            // move into ctrl-c func?
+            ReservedKey::CtrlC => match (self.menu, &self.popup) {
+                (_, Some(Popup::AttemptReconnectPrompt)) | (_, Some(Popup::DisconnectPrompt)) => {
+                    self.shutdown()
+                }
+                (Menu::Terminal, None) => {
+                    self.show_popup(Popup::DisconnectPrompt);
+                }
+                (_, Some(_)) => {
+                    self.dismiss_popup();
+                }
+                _ => self.shutdown(),
+            },
+            ReservedKey::CtrlBackspace => {
+                if terminal_view_actions && !self.text_input.value().is_empty() {
+                    self.text_input.remove_one_word();
+                }
+            }
+            ReservedKey::CtrlPageUp | ReservedKey::ShiftPageUp => self.buffer.scroll_by(i32::MAX),
+            ReservedKey::CtrlPageDown | ReservedKey::ShiftPageDown => {
+                self.buffer.scroll_by(i32::MIN)
+            }
+            ReservedKey::CtrlShiftDelete | ReservedKey::CtrlShiftBackspace => {
+                self.text_input.clear();
+            }
+            ReservedKey::PageUp => self.buffer.scroll_page_up(),
+            ReservedKey::PageDown => self.buffer.scroll_page_down(),
+            ReservedKey::Up => self.up_pressed(),
+            ReservedKey::Down => self.down_pressed(),
+            ReservedKey::Left => self.left_pressed(),
+            ReservedKey::Right => self.right_pressed(),
+            ReservedKey::Enter => self.enter_pressed(false, false)?,
+            ReservedKey::CtrlEnter => self.enter_pressed(true, false)?,
+            ReservedKey::ShiftEnter => self.enter_pressed(false, true)?,
+            ReservedKey::CtrlShiftEnter => self.enter_pressed(true, true)?,
+            ReservedKey::Esc => self.esc_pressed(),
+        }
+        Ok(())
+    }
+    /// Show what could complete the Pseudo Shell's input, previewing the first candidate (or last, if going backwards).
+    ///
+    /// If there's only the one candidate, it's used right away.
+    /// In byte entry, Tab instead steps back through byte history starting with what's entered.
+    fn start_completion(&mut self, backwards: bool) {
+        if !self.settings.behavior.pseudo_shell {
+            return;
+        }
+        // Bytes have no words to complete.
+        if self.text_input.byte_entry_active() {
+            if !backwards {
+                self.text_input.find_input_in_history();
+            }
+            return;
+        }
+
+        let rx_words = self.buffer.recent_rx_words(COMPLETION_RX_LINES);
+        let words = rx_words
+            .iter()
+            .map(|word| (CompletionKind::Received, word.as_str()));
+        // Macros are fewer and named on purpose, so they're offered before anything received.
+        #[cfg(feature = "macros")]
+        let words = self
+            .macros
+            .all
+            .keys()
+            .map(|tag| (CompletionKind::Macro, tag.name.as_str()))
+            .chain(words);
+
+        let Some(mut completions) = Completions::new(
+            self.text_input.value(),
+            self.text_input.text_history(),
+            words,
+        ) else {
+            return;
+        };
+        if backwards {
+            completions.select_prev();
+        }
+        self.text_input
+            .replace_input_with_text(completions.selected_input());
+        if !completions.is_single() {
+            self.completions = Some(completions);
+        }
+    }
+    /// Keybind contexts for what's currently on screen, most specific first.
+    fn keybind_contexts(&self) -> Vec<KeybindContext> {
+        match (self.menu, &self.popup) {
+            (Menu::Terminal, None) if self.settings.rendering.hex_view => {
+                vec![KeybindContext::HexView, KeybindContext::Terminal]
+            }
+            (Menu::Terminal, None) => vec![KeybindContext::Terminal],
+            (Menu::PortSelection, None) => vec![KeybindContext::PortSelection],
+            (_, Some(Popup::SettingsMenu(_))) => vec![KeybindContext::Settings],
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::ToolMenu(ToolMenu::Macros))) => vec![KeybindContext::Macros],
+            _ => Vec::new(),
+        }
+    }
+    /// Actions worth showing the keys of in the help footer, for what's currently on screen.
+    fn help_footer_actions(&self) -> Vec<(BuiltinAction, &'static str)> {
+        let mut actions: Vec<(BuiltinAction, &'static str)> = match (self.menu, &self.popup) {
+            // These take every key for themselves, so no keybinds would work.
+            (
+                _,
+                Some(
+                    Popup::SettingsSearch
+                    | Popup::CommandPalette
+                    | Popup::ComposeEditor
+                    | Popup::KeybindEditor
+                    | Popup::SetupWizard,
+                ),
+            ) => return Vec::new(),
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::MacroEditor | Popup::MacroVariables)) => return Vec::new(),
+            (Menu::Terminal, None) => {
+                let mut actions = vec![
+                    (ShowPopupAction::ShowPortSettings.into(), "Port Settings"),
+                    (BaseAction::ToggleTextwrap.into(), "Wrap"),
+                    (BaseAction::EnterNavigationMode.into(), "Navigate"),
+                    (
+                        ShowPopupAction::ShowNotificationHistory.into(),
+                        "Notifications",
+                    ),
+                ];
+                if self.settings.behavior.pseudo_shell {
+                    actions.push((ShowPopupAction::ShowComposeEditor.into(), "Compose"));
+                    actions.push((BaseAction::ReverseHistorySearch.into(), "Search History"));
+                } else {
+                    actions.insert(0, (BaseAction::EscapeKeypress.into(), "Escape Keypress"));
+                }
+                actions
+            }
+            (Menu::PortSelection, None) => vec![
+                (ShowPopupAction::ShowPortSettings.into(), "Port Settings"),
+                (ShowPopupAction::ShowBehavior.into(), "Behavior"),
+                (ShowPopupAction::ShowRendering.into(), "Rendering"),
+            ],
+            (_, Some(Popup::SettingsMenu(_))) => vec![
+                (BaseAction::UndoSettingsChange.into(), "Undo Change"),
+                (ShowPopupAction::ShowSettingsSearch.into(), "Search"),
+                (ShowPopupAction::ShowSettingsProfiles.into(), "Profiles"),
+            ],
+            #[cfg(feature = "macros")]
+            (_, Some(Popup::ToolMenu(ToolMenu::Macros))) => vec![
+                (MacroBuiltinAction::ReloadMacros.into(), "Reload"),
+                (MacroBuiltinAction::ImportMacros.into(), "Import"),
+                (MacroBuiltinAction::ExportMacros.into(), "Export"),
+            ],
+            _ => Vec::new(),
+        };
+        actions.push((ShowPopupAction::ShowCommandPalette.into(), "Commands"));
+        actions.push((ShowPopupAction::ShowKeybinds.into(), "Keybinds"));
+        actions
+    }
+    fn handle_mouse_binding(&mut self, binding: MouseBinding) -> Result<()> {
+        let Some(actions_str) = self.keybinds.action_strs_from_mouse(binding).cloned() else {
+            return Ok(());
+        };
+        let Some(actions) = self.actions_from_strings(&actions_str) else {
+            return Ok(());
+        };
+
+        debug!("{binding}: {actions:?}");
+
+        self.queue_keybinds_action_set(actions, None)
+    }
+    /// Parse each action, notifying the user and returning `None` if any aren't recognized.
+    fn actions_from_strings(&mut self, actions_str: &[String]) -> Option<Vec<Action>> {
+        let mut actions = Vec::new();
+
+        for action in actions_str {
+            if let Some(action) = self.get_action_from_string(action) {
+                actions.push(action);
+            } else {
+                self.notifs.notify_str(
+                    format!("Unrecognized keybind action: \"{action}\""),
+                    Severity::Warning,
+                );
+                return None;
+            }
+        }
 
-                let mut actions = Vec::new();
+        Some(actions)
+    }
+    /// Add the key to the chord being entered, running its actions once a chord is finished.
+    ///
+    /// Esc, or any key that doesn't lead anywhere, cancels the chord.
+    fn continue_chord(&mut self, key_combo: KeyCombination) -> Result<()> {
+        if key_combo == key!(esc) {
+            self.pending_chord.clear();
+            return Ok(());
+        }
 
-                for action in actions_str {
-                    if let Some(action) = self.get_action_from_string(action) {
-                        actions.push(action);
-                    } else {
-                        self.notifs.notify_str(
-                            format!("Unrecognized keybind action: \"{action}\""),
-                            Color::Yellow,
-                        );
-                        return Ok(());
-                    }
-                }
+        self.pending_chord.push(key_combo);
 
-                debug!("{key_combo}: {actions:?}");
+        if let Some(actions_str) = self.keybinds.chord_actions(&self.pending_chord).cloned() {
+            let chord = std::mem::take(&mut self.pending_chord);
+            let Some(actions) = self.actions_from_strings(&actions_str) else {
+                return Ok(());
+            };
+            debug!("{}: {actions:?}", chord_to_string(&chord));
+            return self.queue_keybinds_action_set(actions, Some(key_combo));
+        }
+
+        if !self.keybinds.is_chord_prefix(&self.pending_chord) {
+            let chord = std::mem::take(&mut self.pending_chord);
+            self.notifs.notify_str(
+                format!("No chord bound to {}", chord_to_string(&chord)),
+                Severity::Warning,
+            );
+        }
+
+        Ok(())
+    }
+    /// Insert text pasted into the terminal all at once, rather than as if each character were typed.
+    ///
+    /// Pastes with several lines go to the compose editor with their line breaks intact.
+    /// With the Pseudo Shell off, pastes are sent straight to the device instead,
+    /// asking first if they're over `Behavior::paste_confirm_threshold`.
+    fn handle_paste(&mut self, text: String) -> Result<()> {
+        let terminal_input = matches!((self.menu, &self.popup), (Menu::Terminal, None))
+            && !self.navigation.active
+            && self.text_input.reverse_search().is_none();
 
-                self.queue_keybinds_action_set(actions, key_combo)?;
+        match self.popup {
+            Some(Popup::ComposeEditor) => self.compose_editor.insert_str(&text),
+            None if terminal_input && self.settings.behavior.pseudo_shell => {
+                self.completions = None;
+                // A line copied along with its line ending is still just the one line.
+                let text = text.trim_end_matches(['\r', '\n']);
+                if text.contains(['\r', '\n']) && !self.text_input.byte_entry_active() {
+                    self.show_popup(Popup::ComposeEditor);
+                    self.compose_editor.insert_str(text);
+                } else {
+                    self.text_input.insert_at_cursor(text);
+                }
+            }
+            None if terminal_input && !self.escape_next_keypress => {
+                // Line breaks are sent the same as pressing Enter would.
+                let text = text.replace("\r\n", "\r").replace('\n', "\r");
+                let threshold = self.settings.behavior.paste_confirm_threshold;
+                if threshold > 0 && text.len() > threshold {
+                    self.show_popup(Popup::LargePastePrompt(LargeSend::Paste(text)));
+                } else {
+                    self.send_paste(text)?;
+                }
+            }
+            // Anywhere else, typed out like it would've been without bracketed paste.
+            _ => {
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    self.handle_key_press(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))?;
+                }
             }
         }
         Ok(())
     }
+    /// Send pasted text as-is, like it was typed with the Pseudo Shell off.
+    fn send_paste(&mut self, text: String) -> Result<()> {
+        if !self.serial.port_status.load().inner.is_connected() {
+            return self.trigger_send_failed_visual();
+        }
+        self.serial.send_bytes(text.into_bytes(), None)?;
+        self.last_raw_sequence = Default::default();
+        self.repeating_line_flip.flip();
+        Ok(())
+    }
     fn send_crossterm_event_to_port(&mut self, key_event: KeyEvent) -> Result<()> {
         let serial_healthy = self.serial.port_status.load().inner.is_connected();
 
@@ -1482,7 +3386,7 @@ impl App {
     fn queue_keybinds_action_set(
         &mut self,
         mut actions: Vec<Action>,
-        key_combo: KeyCombination,
+        key_combo: Option<KeyCombination>,
     ) -> Result<()> {
         assert!(
             !actions.is_empty(),
@@ -1498,13 +3402,13 @@ impl App {
             if action.requires_connection() && !port_status.is_connected() {
                 self.notifs.notify_str(
                     "Action requires healthy port connection! Not acting...",
-                    Color::Red,
+                    Severity::Error,
                 );
                 return Ok(());
             } else if action.requires_terminal_view() && !matches!(self.menu, Menu::Terminal) {
                 self.notifs.notify_str(
                     "Action requires terminal view active! Not acting...",
-                    Color::Red,
+                    Severity::Error,
                 );
                 return Ok(());
             }
@@ -1519,8 +3423,14 @@ impl App {
         self.action_queue
             .extend(actions.into_iter().map(|a| (key_combo, a)));
 
-        self.event_tx.send(Tick::Action.into())?;
-
+        self.start_action_queue()
+    }
+    /// Begin consuming the action queue, if it isn't already being consumed.
+    fn start_action_queue(&mut self) -> Result<()> {
+        if !self.action_queue_ticking {
+            self.action_queue_ticking = true;
+            self.event_tx.send(Tick::Action.into())?;
+        }
         Ok(())
     }
     pub fn get_action_from_string(&self, action: &str) -> Option<Action> {
@@ -1571,13 +3481,82 @@ impl App {
             return Some(Action::Pause(duration));
         }
 
+        let parse_snapshot_name = |s: &str, prefix: &str| -> Option<CompactString> {
+            let s_start = s.first_chars(prefix.len())?;
+            if !s_start.eq_ignore_ascii_case(prefix) {
+                return None;
+            }
+            let name = s[prefix.len()..].trim();
+            (!name.is_empty()).then(|| name.to_compact_string())
+        };
+
+        // Check if it's a snapshot request
+        if let Some(name) = parse_snapshot_name(action, SnapshotAction::TAKE_PREFIX) {
+            return Some(Action::Snapshot(SnapshotAction::Take(name)));
+        }
+        if let Some(name) = parse_snapshot_name(action, SnapshotAction::DIFF_PREFIX) {
+            return Some(Action::Snapshot(SnapshotAction::ToggleDiff(name)));
+        }
+
+        if let Some(command) = parse_snapshot_name(action, RUN_COMMAND_PREFIX) {
+            return Some(Action::RunCommand(command));
+        }
+
+        #[cfg(feature = "scripting")]
+        if let Some(name) = parse_snapshot_name(action, SCRIPT_ACTION_PREFIX)
+            && let Some(name) = self.scripts.get(&name)
+        {
+            return Some(Action::Script(name.clone()));
+        }
+
         // Otherwise, it's nothing we recognize.
         None
     }
     // To be used only when chewing through queued actions.
     // Refrain from placing single-action keybind logic here.
     fn consume_one_queued_action(&mut self) -> Result<()> {
+        #[cfg(feature = "macros")]
+        if let Some(expectation) = &self.macro_expectation {
+            if !expectation.timed_out() {
+                let remaining = expectation
+                    .deadline
+                    .saturating_duration_since(Instant::now());
+                self.carousel
+                    .add_oneshot("ActionQueue", Tick::Action, remaining)?;
+                return Ok(());
+            }
+
+            let mut text = format!(
+                "Timed out waiting for \"{}\" in {}!",
+                expectation.pattern, expectation.tag
+            );
+            if !self.action_queue.is_empty() {
+                text.push_str(&format!(
+                    " Cleared {} queued actions.",
+                    self.action_queue.len()
+                ));
+            }
+            self.notifs.notify_str(text, Severity::Error);
+            self.macro_expectation = None;
+            self.action_queue.clear();
+            self.action_queue_ticking = false;
+            return Ok(());
+        }
+
         if self.action_queue.is_empty() {
+            self.action_queue_ticking = false;
+            return Ok(());
+        }
+
+        // Hold off on the rest of the chain until the user's done filling in a macro's variables
+        // or confirming it, or a macro's response has been captured for later ones to use.
+        #[cfg(feature = "macros")]
+        if self.popup == Some(Popup::MacroVariables)
+            || self.pending_confirmation.is_some()
+            || self.macro_capture.is_some()
+        {
+            self.carousel
+                .add_oneshot("ActionQueue", Tick::Action, Duration::from_millis(100))?;
             return Ok(());
         }
 
@@ -1606,8 +3585,9 @@ impl App {
                         self.action_queue.len()
                     )
                 };
-                self.notifs.notify_str(text, Color::Red);
+                self.notifs.notify_str(text, Severity::Error);
                 self.action_queue.clear();
+                self.action_queue_ticking = false;
                 return Ok(());
             }
         }
@@ -1636,7 +3616,7 @@ impl App {
     fn queued_keybind_action_dispatch(
         &mut self,
         action: Action,
-        key_combo: KeyCombination,
+        key_combo: Option<KeyCombination>,
     ) -> Result<Option<Duration>> {
         debug!("Consuming action: {action:?} - Key: {key_combo:?}");
 
@@ -1653,9 +3633,34 @@ impl App {
 
             Action::BuiltinAction(method) => self.run_builtin_action(method)?,
 
+            Action::Snapshot(snapshot) => self.run_snapshot_action(snapshot),
+
+            Action::RunCommand(command) => self.run_external_command(command),
+
+            Action::ComposedLine(line) => {
+                let user_le = &self.settings.serial.tx_line_ending;
+                let user_le_bytes = user_le
+                    .as_bytes(&self.settings.serial.rx_line_ending)
+                    .to_owned();
+                self.send_user_text(&line, &user_le_bytes)?;
+                self.buffer.scroll_by(i32::MIN);
+            }
+
+            #[cfg(feature = "scripting")]
+            Action::Script(name) => match self.scripts.run(&name, self.event_tx.clone()) {
+                Ok(()) => self.notifs.notify_str_colored(
+                    format!("Running script {name}..."),
+                    Color::LightBlue,
+                    Severity::Info,
+                ),
+                Err(e) => self
+                    .notifs
+                    .notify_str(format!("Script {name} not started: {e}!"), Severity::Error),
+            },
+
             #[cfg(feature = "macros")]
             Action::MacroInvocation(name_tag) => {
-                self.send_one_macro(name_tag, Some(key_combo))?;
+                self.send_one_macro(name_tag, key_combo)?;
                 // if let Err(report) = self.send_one_macro(name_tag, key_combo_opt) {
                 //     match report.downcast_ref::<MacroNotFound>() {
                 //                           TODO maybe handle separately later?
@@ -1664,6 +3669,18 @@ impl App {
                 //     }
                 // }
             }
+            #[cfg(feature = "macros")]
+            Action::MacroPayload(sequence_tag, content) => {
+                self.send_macro_content(sequence_tag, *content, key_combo)?;
+            }
+            #[cfg(feature = "macros")]
+            Action::MacroExpect(sequence_tag, pattern, timeout) => {
+                return Ok(Some(self.begin_macro_expectation(
+                    sequence_tag,
+                    &pattern,
+                    timeout,
+                )));
+            }
 
             #[cfg(feature = "espflash")]
             Action::EspFlashProfile(profile) => {
@@ -1671,17 +3688,72 @@ impl App {
                     panic!("espflash profile existed but disappeared?")
                 };
 
-                self.notifs.notify_str(
-                    format!("espflash profile: {} [{}]", profile.name(), key_combo),
+                let key_combo = key_combo.map(|k| format!(" [{k}]")).unwrap_or_default();
+                self.notifs.notify_str_colored(
+                    format!("espflash profile: {}{key_combo}", profile.name()),
                     Color::LightBlue,
+                    Severity::Info,
                 );
 
                 self.esp_flash_profile(profile)?;
             }
         }
 
+        // Start watching for the response right away, so a quick one isn't missed.
+        #[cfg(feature = "macros")]
+        if let Some((_, Action::MacroExpect(..))) = self.action_queue.front() {
+            let Some((_, Action::MacroExpect(sequence_tag, pattern, timeout))) =
+                self.action_queue.pop_front()
+            else {
+                unreachable!("front was just checked to be an expectation")
+            };
+            return Ok(Some(self.begin_macro_expectation(
+                sequence_tag,
+                &pattern,
+                timeout,
+            )));
+        }
+
         Ok(post_action_pause_duration)
     }
+    #[cfg(feature = "macros")]
+    /// Hold the action queue until the pattern is received, returning how long to wait at most.
+    fn begin_macro_expectation(
+        &mut self,
+        sequence_tag: MacroNameTag,
+        pattern: &str,
+        timeout: Duration,
+    ) -> Duration {
+        // Already checked to be valid when the sequence was queued.
+        let pattern = regex::bytes::Regex::new(pattern).expect("expect pattern should be valid");
+        debug!("Waiting up to {timeout:?} for \"{pattern}\" from {sequence_tag}");
+        self.macro_expectation = Some(MacroExpectation::new(sequence_tag, pattern, timeout));
+        timeout
+    }
+
+    fn run_snapshot_action(&mut self, action: SnapshotAction) {
+        match action {
+            SnapshotAction::Take(name) => {
+                let line_count = self.buffer.take_snapshot(&name);
+                self.notifs.notify_str(
+                    format!("Took snapshot \"{name}\" of {line_count} unique lines."),
+                    Severity::Success,
+                );
+            }
+            SnapshotAction::ToggleDiff(name) => match self.buffer.toggle_snapshot_diff(&name) {
+                Ok(Some(added_lines)) => self.notifs.notify_str_colored(
+                    format!("Diffing against \"{name}\", {added_lines} lines added."),
+                    Color::LightBlue,
+                    Severity::Info,
+                ),
+                Ok(None) => self.notifs.notify_str(
+                    format!("Stopped diffing against \"{name}\"."),
+                    Severity::Info,
+                ),
+                Err(e) => self.notifs.notify_str(e.to_string(), Severity::Warning),
+            },
+        }
+    }
 
     #[cfg(feature = "espflash")]
     fn esp_flash_profile(&mut self, profile: esp::EspProfile) -> Result<(), SerialWorkerMissing> {
@@ -1734,13 +3806,53 @@ impl App {
                 self.serial
                     .request_reconnect(Some(Reconnections::LooseChecks))?;
             }
+            A::Port(PortAction::ResendSelectedLine) => {
+                let Some(bytes) = self.buffer.selected_line_bytes() else {
+                    self.notifs
+                        .notify_str("No port line selected to re-send!", Severity::Warning);
+                    return Ok(());
+                };
+
+                let user_le_bytes = self
+                    .settings
+                    .serial
+                    .tx_line_ending
+                    .as_bytes(&self.settings.serial.rx_line_ending);
+
+                self.serial.send_bytes(bytes.clone(), Some(user_le_bytes))?;
+                self.buffer.append_user_bytes(
+                    &bytes,
+                    user_le_bytes,
+                    #[cfg(feature = "macros")]
+                    None,
+                );
+                self.repeating_line_flip.flip();
+
+                self.notifs.notify_str(
+                    format!("Re-sent {} bytes from selected line.", bytes.len()),
+                    Severity::Info,
+                );
+            }
             A::Base(BaseAction::ToggleTextwrap) => {
                 let state = pretty_bool(self.settings.rendering.wrap_text.flip());
                 self.buffer
                     .update_render_settings(self.settings.rendering.clone());
                 self.settings.save()?;
                 self.notifs
-                    .notify_str(format!("Toggled Text Wrapping {state}"), Color::Gray);
+                    .notify_str(format!("Toggled Text Wrapping {state}"), Severity::Info);
+            }
+            A::Base(BaseAction::ToggleDoNotDisturb) => {
+                let enabled = self.settings.behavior.do_not_disturb.flip();
+                self.settings.save()?;
+                let text = format!("Toggled Do Not Disturb {}", pretty_bool(enabled));
+                // Notified while Do Not Disturb is off either way, so the toggle's always seen.
+                if enabled {
+                    self.notifs.notify_str(text, Severity::Info);
+                    self.apply_notification_filter();
+                } else {
+                    self.apply_notification_filter();
+                    self.notifs.notify_str(text, Severity::Info);
+                }
             }
             A::Base(BaseAction::ToggleTimestamps) => {
                 let state = pretty_bool(self.settings.rendering.timestamps.flip());
@@ -1748,7 +3860,7 @@ impl App {
                     .update_render_settings(self.settings.rendering.clone());
                 self.settings.save()?;
                 self.notifs
-                    .notify_str(format!("Toggled Timestamps {state}"), Color::Gray);
+                    .notify_str(format!("Toggled Timestamps {state}"), Severity::Info);
             }
 
             A::Base(BaseAction::ToggleIndices) => {
@@ -1758,7 +3870,7 @@ impl App {
                 self.settings.save()?;
                 self.notifs.notify_str(
                     format!("Toggled Line Indices + Length {state}"),
-                    Color::Gray,
+                    Severity::Info,
                 );
             }
 
@@ -1768,7 +3880,7 @@ impl App {
                     .update_render_settings(self.settings.rendering.clone());
                 self.settings.save()?;
                 self.notifs
-                    .notify_str(format!("Toggled Indices as Hex {state}"), Color::Gray);
+                    .notify_str(format!("Toggled Indices as Hex {state}"), Severity::Info);
             }
 
             A::Base(BaseAction::ToggleHexView) => {
@@ -1778,7 +3890,7 @@ impl App {
                 self.settings.save()?;
                 self.buffer.scroll_by(0);
                 self.notifs
-                    .notify_str(format!("Toggled Hex View {state}"), Color::Gray);
+                    .notify_str(format!("Toggled Hex View {state}"), Severity::Info);
             }
 
             A::Base(BaseAction::ToggleHexViewHeader) => {
@@ -1787,14 +3899,14 @@ impl App {
                     .update_render_settings(self.settings.rendering.clone());
                 self.settings.save()?;
                 self.notifs
-                    .notify_str(format!("Toggled Hex View Header {state}"), Color::Gray);
+                    .notify_str(format!("Toggled Hex View Header {state}"), Severity::Info);
             }
 
             A::Base(BaseAction::TogglePseudoShell) => {
                 let state = pretty_bool(self.settings.behavior.pseudo_shell.flip());
                 self.settings.save()?;
                 self.notifs
-                    .notify_str(format!("Toggled Pseudo Shell {state}"), Color::Gray);
+                    .notify_str(format!("Toggled Pseudo Shell {state}"), Severity::Info);
             }
 
             A::Base(BaseAction::TogglePseudoShellHex) => {
@@ -1803,41 +3915,222 @@ impl App {
                 debug!("{}", self.text_input.byte_entry_active());
             }
 
+            A::Base(BaseAction::ExportHtml) => {
+                let title = self
+                    .serial
+                    .port_status
+                    .load()
+                    .current_port
+                    .as_ref()
+                    .map_or_else(
+                        || "yap".to_owned(),
+                        |port| format!("yap - {}", port.port_name),
+                    );
+                match self.buffer.export_html(&title) {
+                    Ok(path) => self
+                        .notifs
+                        .notify_str(format!("Exported buffer to {path}"), Severity::Success),
+                    Err(e) => {
+                        error!("Failed to export buffer as HTML: {e}");
+                        self.notifs
+                            .notify_str(format!("Failed to export buffer: {e}"), Severity::Error);
+                    }
+                }
+            }
+            A::Base(BaseAction::PasteClipboard) => {
+                if let Some(clipboard) = &mut self.text_input.clipboard {
+                    match clipboard.get_text() {
+                        Ok(clipboard_text) => {
+                            self.text_input.append_to_input(&clipboard_text);
+                        }
+                        Err(e) => {
+                            error!("error getting clipboard text: {e}");
+                        }
+                    }
+                }
+            }
+            A::Base(BaseAction::ReverseHistorySearch) if !self.settings.behavior.pseudo_shell => {
+                self.notifs.notify_str(
+                    "History search needs the Pseudo Shell enabled!",
+                    Severity::Warning,
+                );
+            }
+            A::Base(BaseAction::ReverseHistorySearch) => self.text_input.reverse_search_older(),
+            A::Base(BaseAction::ScrollUp) => self.buffer.scroll_by(1),
+            A::Base(BaseAction::ScrollDown) => self.buffer.scroll_by(-1),
+            A::Base(BaseAction::ScrollLeft) => {
+                self.buffer.scroll_horizontally(-HORIZONTAL_SCROLL_COLUMNS);
+            }
+            A::Base(BaseAction::ScrollRight) => {
+                self.buffer.scroll_horizontally(HORIZONTAL_SCROLL_COLUMNS);
+            }
+
+            A::Base(BaseAction::UndoSettingsChange) => self.undo_settings_change()?,
+
+            A::Base(BaseAction::CycleKeybindProfile) => {
+                if !self.keybinds.cycle_profile() {
+                    self.notifs.notify_str(
+                        "No keybind profiles! Add some under [profiles] in the keybinds file.",
+                        Severity::Warning,
+                    );
+                    return Ok(());
+                }
+                let profile = self.keybinds.active_profile().unwrap_or("Default");
+                self.notifs
+                    .notify_str(format!("Keybind profile: {profile}"), Severity::Success);
+            }
+
+            A::Base(BaseAction::EnterNavigationMode) => {
+                self.navigation.active = true;
+            }
+
             A::Base(BaseAction::EscapeKeypress) => {
                 if self.escape_next_keypress {
                     self.notifs
-                        .notify_str("Keypress was already escaped!", Color::Yellow);
+                        .notify_str("Keypress was already escaped!", Severity::Warning);
                 } else if self.settings.behavior.pseudo_shell {
                     self.notifs.notify_str(
                         "Can only escape keypress when Pseudo Shell is disabled!",
-                        Color::Yellow,
+                        Severity::Warning,
                     );
                 }
             }
 
             #[cfg(feature = "macros")]
             A::MacroBuiltin(MacroBuiltinAction::ReloadMacros) => {
-                match Macros::load_from_folder(config_adjacent_path(crate::macros::MACROS_DIR_PATH))
-                {
+                match Macros::load_from_folder(
+                    config_adjacent_path(crate::macros::MACROS_DIR_PATH),
+                    crate::macros::project_macros_folder(),
+                ) {
                     Ok((macros, errors)) => {
                         let err_len = errors.len();
-                        self.macros = macros;
+                        let previous = std::mem::replace(&mut self.macros, macros);
+                        self.macros.keep_state_from(previous);
                         if errors.is_empty() {
                             self.notifs
-                                .notify_str("Reloaded Macros Successfully!", Color::Green);
+                                .notify_str("Reloaded Macros Successfully!", Severity::Success);
                         } else {
                             self.notifs.notify_str(
                                 format!("Reloaded Macros! {err_len} files had errors!"),
-                                Color::Yellow,
+                                Severity::Warning,
                             );
                         }
                     }
                     Err(e) => {
                         self.notifs
-                            .notify_str(format!("Error opening macros: {e}!"), Color::Red);
+                            .notify_str(format!("Error opening macros: {e}!"), Severity::Error);
+                    }
+                }
+            }
+
+            #[cfg(feature = "macros")]
+            A::MacroBuiltin(MacroBuiltinAction::RecordMacro) => match self.macro_recorder.take() {
+                None => {
+                    self.macro_recorder = Some(MacroRecorder::default());
+                    if self.settings.behavior.pseudo_shell {
+                        self.notifs.notify_str_colored(
+                            "Recording sent input as a macro...",
+                            Color::LightBlue,
+                            Severity::Info,
+                        );
+                    } else {
+                        self.notifs.notify_str(
+                            "Recording, but only Pseudo-shell input is captured!",
+                            Severity::Warning,
+                        );
+                    }
+                }
+                Some(recorder) if recorder.is_empty() => {
+                    self.notifs
+                        .notify_str("Nothing was recorded!", Severity::Warning);
+                }
+                Some(recorder) => {
+                    self.macros.editor = recorder.into_editor(None);
+                    self.show_popup(Popup::MacroEditor);
+                }
+            },
+            #[cfg(feature = "macros")]
+            A::MacroBuiltin(MacroBuiltinAction::ExportMacros) => {
+                let bundles_dir = config_adjacent_path(MACRO_BUNDLES_DIR_PATH);
+                match self.macros.export_selected_category(&bundles_dir) {
+                    Ok((path, count)) => self.notifs.notify_str(
+                        format!("Exported {count} macros to {path}"),
+                        Severity::Success,
+                    ),
+                    Err(e) => {
+                        error!("Failed to export macros: {e}");
+                        self.notifs
+                            .notify_str(format!("Failed to export macros: {e}"), Severity::Error);
                     }
                 }
             }
+            #[cfg(feature = "macros")]
+            A::MacroBuiltin(MacroBuiltinAction::ImportMacros) => {
+                let bundles_dir = config_adjacent_path(MACRO_BUNDLES_DIR_PATH);
+                match self.macros.begin_import(&bundles_dir) {
+                    Ok(import) if import.is_empty() => self.notifs.notify_str(
+                        format!("No macro bundles to import in {bundles_dir}"),
+                        Severity::Warning,
+                    ),
+                    Ok(import) => {
+                        self.macro_import = Some(import);
+                        self.continue_macro_import();
+                    }
+                    Err(e) => {
+                        error!("Failed to read macro bundles: {e}");
+                        self.notifs.notify_str(
+                            format!("Failed to read macro bundles: {e}"),
+                            Severity::Error,
+                        );
+                    }
+                }
+            }
+            #[cfg(feature = "macros")]
+            A::MacroBuiltin(MacroBuiltinAction::StopRepeatingMacro) => {
+                if let Some(repeating) = self.repeating_macro.take() {
+                    self.notifs.notify_str(
+                        format!("Stopped repeating {}.", repeating.tag),
+                        Severity::Success,
+                    );
+                } else {
+                    self.notifs
+                        .notify_str("No macro is repeating!", Severity::Warning);
+                }
+            }
+
+            #[cfg(feature = "scripting")]
+            A::ScriptBuiltin(ScriptBuiltinAction::ReloadScripts) => {
+                if let Some(name) = self.scripts.running() {
+                    self.notifs.notify_str(
+                        format!("Can't reload while script {name} is running!"),
+                        Severity::Warning,
+                    );
+                } else {
+                    match Scripts::load_from_folder(config_adjacent_path(SCRIPTS_DIR_PATH)) {
+                        Ok(scripts) => {
+                            let count = scripts.all.len();
+                            self.scripts = scripts;
+                            self.notifs
+                                .notify_str(format!("Found {count} scripts!"), Severity::Success);
+                        }
+                        Err(e) => {
+                            self.notifs.notify_str(
+                                format!("Error opening scripts: {e}!"),
+                                Severity::Error,
+                            );
+                        }
+                    }
+                }
+            }
+            #[cfg(feature = "scripting")]
+            A::ScriptBuiltin(ScriptBuiltinAction::StopScript) => match self.scripts.stop() {
+                Some(name) => self
+                    .notifs
+                    .notify_str(format!("Stopping script {name}..."), Severity::Warning),
+                None => self
+                    .notifs
+                    .notify_str("No script is running!", Severity::Warning),
+            },
 
             A::Base(BaseAction::ReloadColors) => {
                 if let Err(e) = self.buffer.reload_color_rules() {
@@ -1847,30 +4140,44 @@ impl App {
                     };
                     self.notifs.notify_str(
                         format!("Error reloading Color Rules: {err_str}! See log for details."),
-                        Color::Red,
+                        Severity::Error,
                     );
                     let report = color_eyre::Report::new(e);
                     error!("Error reloading Color Rules: {report:#}");
                 } else {
                     self.notifs
-                        .notify_str("Reloaded Color Rules!", Color::Green);
+                        .notify_str("Reloaded Color Rules!", Severity::Success);
                 }
             }
 
             A::Base(BaseAction::ReloadKeybinds) => match Keybinds::load() {
-                Ok(new) => {
+                Ok(mut new) => {
+                    // Staying on the same profile, if it's still there.
+                    new.set_profile(self.keybinds.active_profile());
                     self.keybinds = new;
-                    self.notifs.notify_str("Reloaded Keybinds!", Color::Green);
+                    if !self.notify_keybind_issues() {
+                        self.notifs
+                            .notify_str("Reloaded Keybinds!", Severity::Success);
+                    }
                 }
                 Err(e) => {
                     self.notifs.notify_str(
                         format!("Error reloading Keybinds: {e}! See log for details."),
-                        Color::Red,
+                        Severity::Error,
                     );
                     let report = color_eyre::Report::new(e);
                     error!("Error reloading Keybinds: {report:#}");
                 }
             },
+            A::Base(BaseAction::OpenSelectedInEditor) => {
+                let Some(location) = self.buffer.selected_source_location() else {
+                    self.notifs
+                        .notify_str("Selected line has no source location!", Severity::Warning);
+                    return Ok(());
+                };
+                // Opened from the event loop, since the terminal needs to be handed over.
+                self.event_tx.send(Event::OpenInEditor(location.clone()))?;
+            }
 
             #[cfg(feature = "logging")]
             A::Logging(LoggingAction::Sync) => {
@@ -1878,13 +4185,60 @@ impl App {
                 let Some(_) = &port_status_guard.current_port else {
                     self.notifs.notify_str(
                         "Not (previously) connected to port? Unable to sync log.",
-                        Color::Yellow,
+                        Severity::Warning,
                     );
                     return Ok(());
                 };
                 self.buffer.relog_buffer()?;
                 self.notifs
-                    .notify_str("Requested logging start!", Color::Green);
+                    .notify_str("Requested logging start!", Severity::Success);
+            }
+
+            #[cfg(feature = "logging")]
+            A::Logging(LoggingAction::Toggle) => {
+                let paused = !self.buffer.log_handle.is_paused();
+                self.buffer.log_handle.set_paused(paused)?;
+                if paused {
+                    self.notifs.notify_str("Logging paused.", Severity::Warning);
+                } else {
+                    self.notifs
+                        .notify_str("Logging resumed.", Severity::Success);
+                }
+            }
+
+            #[cfg(feature = "logging")]
+            A::Logging(
+                action @ (LoggingAction::ReplayPause
+                | LoggingAction::ReplayForward
+                | LoggingAction::ReplayBack
+                | LoggingAction::ReplayFaster
+                | LoggingAction::ReplaySlower),
+            ) => self.control_replay(action)?,
+
+            #[cfg(feature = "logging")]
+            A::Logging(LoggingAction::DumpRecent) => {
+                self.buffer.log_handle.dump_recent()?;
+            }
+
+            #[cfg(feature = "logging")]
+            A::Logging(LoggingAction::ExportPcapng) => {
+                let port_name = self
+                    .serial
+                    .port_status
+                    .load()
+                    .current_port
+                    .as_ref()
+                    .map_or_else(|| "serial".to_owned(), |port| port.port_name.clone());
+                match self.buffer.export_pcapng(&port_name) {
+                    Ok(path) => self
+                        .notifs
+                        .notify_str(format!("Exported capture to {path}"), Severity::Success),
+                    Err(e) => {
+                        error!("Failed to export pcapng capture: {e}");
+                        self.notifs
+                            .notify_str(format!("Failed to export capture: {e}"), Severity::Error);
+                    }
+                }
             }
 
             #[cfg(feature = "espflash")]
@@ -1923,12 +4277,12 @@ impl App {
                     Ok(new_helper) => {
                         self.espflash = new_helper;
                         self.notifs
-                            .notify_str("Reloaded espflash profiles!", Color::Green);
+                            .notify_str("Reloaded espflash profiles!", Severity::Success);
                     }
                     Err(e) => {
                         self.notifs.notify_str(
                             format!("Error reloading espflash profiles: {e}! See log for details."),
-                            Color::Red,
+                            Severity::Error,
                         );
                         let report = color_eyre::Report::new(e);
                         error!("Error reloading espflash profiles: {report:#}");
@@ -1936,10 +4290,44 @@ impl App {
                 }
             }
             #[cfg(feature = "defmt")]
+            A::Defmt(DefmtAction::CycleMinLevel) => {
+                let defmt = &mut self.settings.defmt;
+                defmt.max_log_level = defmt.max_log_level.cycled();
+                let level = defmt.max_log_level.clone();
+                self.buffer.update_defmt_settings(defmt.clone());
+                self.settings.save()?;
+                self.notifs
+                    .notify_str(format!("defmt Minimum Level: {level}"), Severity::Info);
+            }
+            #[cfg(feature = "defmt")]
+            A::Defmt(DefmtAction::ModuleFilter) => self.show_popup(Popup::DefmtModuleFilter),
+            #[cfg(feature = "defmt")]
+            A::Defmt(DefmtAction::ShowStats) => self.show_popup(Popup::DefmtStats),
+            #[cfg(feature = "defmt")]
+            A::Defmt(DefmtAction::ExpandFailedFrame) => {
+                match self.buffer.selected_failed_defmt_frame() {
+                    Some(bytes) => self.show_popup(Popup::DefmtFailedFrame(bytes)),
+                    None => self.notifs.notify_str(
+                        "Selected line isn't a defmt frame that failed to decode!",
+                        Severity::Warning,
+                    ),
+                }
+            }
+            #[cfg(feature = "defmt")]
             A::ShowDefmtSelect(DefmtSelectAction::SelectRecent) => {
                 self.show_popup(Popup::DefmtRecentElf)
             }
             #[cfg(feature = "defmt")]
+            A::ShowDefmtSelect(DefmtSelectAction::SelectNewest) => {
+                let search_root = elf_search_root(&self.settings.defmt.elf_search_path);
+                let tx = self.event_tx.clone();
+                // Reading through a whole target directory can take a while.
+                std::thread::spawn(move || {
+                    let found = find_newest_defmt_elf(&search_root);
+                    _ = tx.send(Event::DefmtNewestElfSearched { search_root, found });
+                });
+            }
+            #[cfg(feature = "defmt")]
             A::ShowDefmtSelect(DefmtSelectAction::SelectTui) => {
                 self.show_popup(Popup::DefmtNewElf(create_file_explorer()?))
             }
@@ -1992,6 +4380,156 @@ impl App {
                 && !port_settings_guard.reconnections.allowed())
     }
     // fn tab_pressed(&mut self) {}
+    /// Warn the user about any problems with their keybinds, returning `true` if there were any.
+    fn notify_keybind_issues(&mut self) -> bool {
+        let report = self.keybinds.report();
+        if report.is_empty() {
+            return false;
+        }
+        warn!("Keybind issues found: {report:?}");
+        let text = match self.keybinds.show_keybinds_hint() {
+            "UNBOUND" => report.summary(),
+            hint => format!("{} (See {hint})", report.summary()),
+        };
+        self.notifs.notify_str(text, Severity::Warning);
+        true
+    }
+    fn keybind_editor_key_press(&mut self, key_event: KeyEvent) {
+        let editor = &mut self.keybind_editor;
+        let Some(action) = editor.selected_action().map(|entry| entry.name.clone()) else {
+            return;
+        };
+
+        let key_combo = match (&editor.state, key_event.code) {
+            (KeybindEditorState::Browsing, KeyCode::Esc) => {
+                self.show_popup(Popup::CurrentKeybinds);
+                return;
+            }
+            (KeybindEditorState::Browsing, KeyCode::Down | KeyCode::Tab) => {
+                editor.select_next();
+                return;
+            }
+            (KeybindEditorState::Browsing, KeyCode::Up | KeyCode::BackTab) => {
+                editor.select_prev();
+                return;
+            }
+            (KeybindEditorState::Browsing, KeyCode::Enter) => {
+                editor.state = KeybindEditorState::Capturing;
+                return;
+            }
+            (KeybindEditorState::Browsing, KeyCode::Delete | KeyCode::Backspace) => {
+                let bound = self.keybinds.keys_with_single_action(&action);
+                if bound.is_empty() {
+                    self.notifs
+                        .notify_str(format!("{action} isn't bound!"), Severity::Warning);
+                    return;
+                }
+                for key_combo in bound {
+                    if let Err(e) = self.keybinds.save_binding(key_combo, Vec::new()) {
+                        error!("Failed to unbind {key_combo}: {e}");
+                        self.notifs.notify_str(
+                            format!("Failed to unbind {key_combo}: {e}"),
+                            Severity::Error,
+                        );
+                        return;
+                    }
+                }
+                self.notifs
+                    .notify_str(format!("Unbound {action}!"), Severity::Success);
+                return;
+            }
+            (KeybindEditorState::Browsing, _) => return,
+
+            (KeybindEditorState::Capturing, KeyCode::Esc)
+            | (KeybindEditorState::Conflict { .. }, KeyCode::Esc) => {
+                editor.state = KeybindEditorState::Browsing;
+                return;
+            }
+            (KeybindEditorState::Capturing, _) => {
+                let key_combo = KeyCombination::from(key_event);
+                match self.keybinds.action_strs_from_key_combo(key_combo) {
+                    Some(existing) if *existing != [action.clone()] => {
+                        editor.state = KeybindEditorState::Conflict {
+                            key_combo,
+                            existing: existing.clone(),
+                        };
+                        return;
+                    }
+                    _ => key_combo,
+                }
+            }
+            (KeybindEditorState::Conflict { key_combo, .. }, KeyCode::Enter) => *key_combo,
+            (KeybindEditorState::Conflict { .. }, _) => return,
+        };
+
+        self.keybind_editor.state = KeybindEditorState::Browsing;
+        match self.keybinds.save_binding(key_combo, vec![action.clone()]) {
+            Ok(()) => self
+                .notifs
+                .notify_str(format!("Bound {key_combo} to {action}!"), Severity::Success),
+            Err(e) => {
+                error!("Failed to bind {key_combo}: {e}");
+                self.notifs
+                    .notify_str(format!("Failed to save keybind: {e}"), Severity::Error);
+            }
+        }
+    }
+    /// Handle a key in navigation mode, returning `false` if it isn't one of the mode's keys.
+    fn navigation_key_press(&mut self, key_event: KeyEvent) -> bool {
+        if let Some(input) = &mut self.navigation.search_input {
+            match key_event.code {
+                KeyCode::Esc => self.navigation.search_input = None,
+                KeyCode::Enter => {
+                    let query = input.value().to_owned();
+                    self.navigation.search_input = None;
+                    if !query.is_empty() {
+                        self.navigation.last_search = Some(query);
+                    }
+                    self.repeat_navigation_search(true);
+                }
+                _ => {
+                    input.handle_event(&ratatui::crossterm::event::Event::Key(key_event));
+                }
+            }
+            return true;
+        }
+
+        if !(key_event.modifiers - KeyModifiers::SHIFT).is_empty() {
+            return false;
+        }
+        match key_event.code {
+            KeyCode::Char('j') => self.buffer.scroll_by(-1),
+            KeyCode::Char('k') => self.buffer.scroll_by(1),
+            KeyCode::Char('g') => self.buffer.scroll_by(i32::MAX),
+            KeyCode::Char('G') => self.buffer.scroll_by(i32::MIN),
+            KeyCode::Char('/') => self.navigation.search_input = Some(Input::default()),
+            KeyCode::Char('n') => self.repeat_navigation_search(true),
+            KeyCode::Char('N') => self.repeat_navigation_search(false),
+            KeyCode::Char('i') => self.navigation.active = false,
+            _ => return false,
+        }
+        true
+    }
+    /// Search for the last query again, upwards being towards older lines.
+    fn repeat_navigation_search(&mut self, upwards: bool) {
+        let Some(query) = &self.navigation.last_search else {
+            self.notifs.notify_str(
+                "Nothing searched yet! Press / to search.",
+                Severity::Warning,
+            );
+            return;
+        };
+        if self.settings.rendering.hex_view {
+            self.notifs
+                .notify_str("Can't search while in hex view!", Severity::Warning);
+        } else if !self.buffer.search_port_lines(query, upwards) {
+            let direction = if upwards { "above" } else { "below" };
+            self.notifs.notify_str(
+                format!("\"{query}\" not found {direction} selected line."),
+                Severity::Warning,
+            );
+        }
+    }
     fn esc_pressed(&mut self) {
         match self.popup {
             None => (),
@@ -2018,6 +4556,19 @@ impl App {
             Some(Popup::CurrentKeybinds) => {
                 self.popup_menu_scroll = self.popup_menu_scroll.saturating_sub(1);
             }
+            Some(Popup::KeybindEditor) => self.keybind_editor.select_prev(),
+            Some(Popup::SettingsSearch) => self.settings_search.select_prev(),
+            Some(Popup::CommandPalette) => self.command_palette.select_prev(),
+            Some(Popup::ComposeEditor) => (),
+            Some(Popup::SetupWizard) => {
+                self.popup_menu_scroll = self.popup_menu_scroll.saturating_sub(1);
+            }
+            Some(Popup::SettingsProfiles) | Some(Popup::NotificationHistory) => {
+                match self.popup_menu_scroll {
+                    0 => self.select_last_popup_item(),
+                    _ => self.popup_menu_scroll -= 1,
+                }
+            }
             #[cfg(not(any(feature = "espflash", feature = "macros")))]
             Some(Popup::SettingsMenu(_)) => match self.popup_menu_scroll {
                 0 => self.select_last_popup_item(),
@@ -2033,6 +4584,19 @@ impl App {
             #[cfg(feature = "defmt")]
             Some(Popup::DefmtNewElf(_)) => (),
             #[cfg(feature = "defmt")]
+            Some(Popup::DefmtModuleFilter) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtStats) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFailedFrame(_)) => {
+                self.popup_menu_scroll = self.popup_menu_scroll.saturating_sub(1);
+            }
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFoundElf(_)) => match self.popup_menu_scroll {
+                0 => self.select_last_popup_item(),
+                _ => self.popup_menu_scroll -= 1,
+            },
+            #[cfg(feature = "defmt")]
             Some(Popup::DefmtRecentElf) => match self.popup_menu_scroll {
                 0 => self.select_last_popup_item(),
                 _ => self.popup_menu_scroll -= 1,
@@ -2040,18 +4604,33 @@ impl App {
             Some(Popup::AttemptReconnectPrompt)
             | Some(Popup::DisconnectPrompt)
             | Some(Popup::IgnoreByName(_))
-            | Some(Popup::IgnoreByUsb(_, _)) => match self.popup_menu_scroll {
+            | Some(Popup::IgnoreByUsb(_, _))
+            | Some(Popup::PortInUse(_, _))
+            | Some(Popup::LargePastePrompt(_)) => match self.popup_menu_scroll {
                 0 => self.select_last_popup_item(),
                 _ => self.popup_menu_scroll -= 1,
             },
-
-            Some(Popup::UpdateCheckConsentPrompt) | Some(Popup::UpdateBeginPrompt) => {
-                match self.popup_menu_scroll {
-                    0 => self.select_last_popup_item(),
-                    _ => self.popup_menu_scroll -= 1,
-                }
+            #[cfg(feature = "macros")]
+            Some(Popup::DeleteMacroPrompt(_))
+            | Some(Popup::ConfirmMacroPrompt(_))
+            | Some(Popup::ImportMacroConflict) => match self.popup_menu_scroll {
+                0 => self.select_last_popup_item(),
+                _ => self.popup_menu_scroll -= 1,
+            },
+            #[cfg(feature = "macros")]
+            Some(Popup::MacroEditor) | Some(Popup::MacroVariables) => (),
+            #[cfg(feature = "macros")]
+            Some(Popup::MacroPreview) => {
+                self.popup_menu_scroll = self.popup_menu_scroll.saturating_sub(1);
             }
 
+            Some(Popup::UpdateCheckConsentPrompt)
+            | Some(Popup::UpdateBeginPrompt)
+            | Some(Popup::RevertSettingsPrompt(_)) => match self.popup_menu_scroll {
+                0 => self.select_last_popup_item(),
+                _ => self.popup_menu_scroll -= 1,
+            },
+
             #[cfg(feature = "self-replace")]
             Some(Popup::UpdateDownloading(_)) => (),
 
@@ -2060,10 +4639,20 @@ impl App {
                 0 => self.select_last_popup_item(),
                 _ => self.popup_menu_scroll -= 1,
             },
+            #[cfg(feature = "self-replace")]
+            Some(Popup::UpdateFromArchivePrompt(_)) => match self.popup_menu_scroll {
+                0 => self.select_last_popup_item(),
+                _ => self.popup_menu_scroll -= 1,
+            },
 
             Some(Popup::SerialConnectionFailed(_)) => (),
         }
 
+        // Section headers can't be selected, moving past them instead.
+        if self.section_header_selected() {
+            self.up_pressed();
+        }
+
         if self.popup.is_some() {
             return;
         }
@@ -2084,6 +4673,19 @@ impl App {
             Some(Popup::CurrentKeybinds) => {
                 self.popup_menu_scroll += 1;
             }
+            Some(Popup::KeybindEditor) => self.keybind_editor.select_next(),
+            Some(Popup::SettingsSearch) => self.settings_search.select_next(),
+            Some(Popup::CommandPalette) => self.command_palette.select_next(),
+            Some(Popup::SetupWizard) if !SetupWizard::is_last_step(self.popup_menu_scroll) => {
+                self.popup_menu_scroll += 1;
+            }
+            Some(Popup::ComposeEditor) | Some(Popup::SetupWizard) => (),
+            Some(Popup::SettingsProfiles) | Some(Popup::NotificationHistory) => {
+                match self.popup_menu_scroll {
+                    _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
+                    _ => self.popup_menu_scroll += 1,
+                }
+            }
             #[cfg(not(any(feature = "espflash", feature = "macros")))]
             Some(Popup::SettingsMenu(_)) => match self.popup_menu_scroll {
                 _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
@@ -2099,6 +4701,19 @@ impl App {
             #[cfg(feature = "defmt")]
             Some(Popup::DefmtNewElf(_)) => (),
             #[cfg(feature = "defmt")]
+            Some(Popup::DefmtModuleFilter) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtStats) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFailedFrame(_)) => {
+                self.popup_menu_scroll += 1;
+            }
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFoundElf(_)) => match self.popup_menu_scroll {
+                _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
+                _ => self.popup_menu_scroll += 1,
+            },
+            #[cfg(feature = "defmt")]
             Some(Popup::DefmtRecentElf) => match self.popup_menu_scroll {
                 _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
                 _ => self.popup_menu_scroll += 1,
@@ -2106,18 +4721,33 @@ impl App {
             Some(Popup::AttemptReconnectPrompt)
             | Some(Popup::DisconnectPrompt)
             | Some(Popup::IgnoreByName(_))
-            | Some(Popup::IgnoreByUsb(_, _)) => match self.popup_menu_scroll {
+            | Some(Popup::IgnoreByUsb(_, _))
+            | Some(Popup::PortInUse(_, _))
+            | Some(Popup::LargePastePrompt(_)) => match self.popup_menu_scroll {
                 _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
                 _ => self.popup_menu_scroll += 1,
             },
-
-            Some(Popup::UpdateCheckConsentPrompt) | Some(Popup::UpdateBeginPrompt) => {
-                match self.popup_menu_scroll {
-                    _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
-                    _ => self.popup_menu_scroll += 1,
-                }
+            #[cfg(feature = "macros")]
+            Some(Popup::DeleteMacroPrompt(_))
+            | Some(Popup::ConfirmMacroPrompt(_))
+            | Some(Popup::ImportMacroConflict) => match self.popup_menu_scroll {
+                _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
+                _ => self.popup_menu_scroll += 1,
+            },
+            #[cfg(feature = "macros")]
+            Some(Popup::MacroEditor) | Some(Popup::MacroVariables) => (),
+            #[cfg(feature = "macros")]
+            Some(Popup::MacroPreview) => {
+                self.popup_menu_scroll += 1;
             }
 
+            Some(Popup::UpdateCheckConsentPrompt)
+            | Some(Popup::UpdateBeginPrompt)
+            | Some(Popup::RevertSettingsPrompt(_)) => match self.popup_menu_scroll {
+                _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
+                _ => self.popup_menu_scroll += 1,
+            },
+
             #[cfg(feature = "self-replace")]
             Some(Popup::UpdateDownloading(_)) => (),
 
@@ -2126,10 +4756,20 @@ impl App {
                 _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
                 _ => self.popup_menu_scroll += 1,
             },
+            #[cfg(feature = "self-replace")]
+            Some(Popup::UpdateFromArchivePrompt(_)) => match self.popup_menu_scroll {
+                _last if self.last_popup_item_selected() => self.popup_menu_scroll = 0,
+                _ => self.popup_menu_scroll += 1,
+            },
 
             Some(Popup::SerialConnectionFailed(_)) => (),
         }
 
+        // Section headers can't be selected, moving past them instead.
+        if self.section_header_selected() {
+            self.down_pressed();
+        }
+
         if self.popup.is_some() {
             return;
         }
@@ -2154,7 +4794,24 @@ impl App {
             | Some(Popup::IgnoreByName(_))
             | Some(Popup::IgnoreByUsb(_, _))
             | Some(Popup::SerialConnectionFailed(_))
-            | Some(Popup::CurrentKeybinds) => (),
+            | Some(Popup::PortInUse(_, _))
+            | Some(Popup::LargePastePrompt(_))
+            | Some(Popup::CurrentKeybinds)
+            | Some(Popup::KeybindEditor)
+            | Some(Popup::SettingsProfiles)
+            | Some(Popup::NotificationHistory)
+            | Some(Popup::RevertSettingsPrompt(_))
+            | Some(Popup::SettingsSearch)
+            | Some(Popup::CommandPalette)
+            | Some(Popup::ComposeEditor)
+            | Some(Popup::SetupWizard) => (),
+            #[cfg(feature = "macros")]
+            Some(Popup::MacroEditor)
+            | Some(Popup::MacroVariables)
+            | Some(Popup::MacroPreview)
+            | Some(Popup::DeleteMacroPrompt(_))
+            | Some(Popup::ConfirmMacroPrompt(_))
+            | Some(Popup::ImportMacroConflict) => (),
             #[cfg(not(any(feature = "espflash", feature = "macros")))]
             Some(Popup::SettingsMenu(_)) if self.popup_menu_scroll == 0 => {}
             #[cfg(any(feature = "espflash", feature = "macros"))]
@@ -2171,26 +4828,17 @@ impl App {
                 self.cycle_sub_menu(false);
             }
             Some(Popup::SettingsMenu(SettingsMenu::SerialPort)) => {
-                self.scratch
-                    .serial
-                    .handle_input(ArrowKey::Left, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
-
-                if let Some(0) = self.get_corrected_popup_index() {
+                let changed = self.cycle_selected_setting(ArrowKey::Left);
+                // Keeping the baud rate's text input in step with the cycled value.
+                if changed == Some(0) {
                     self.baud_input = self.scratch.serial.baud_rate.to_string().into();
                 }
             }
             Some(Popup::SettingsMenu(SettingsMenu::Behavior)) => {
-                self.scratch
-                    .behavior
-                    .handle_input(ArrowKey::Left, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Left);
             }
             Some(Popup::SettingsMenu(SettingsMenu::Rendering)) => {
-                self.scratch
-                    .rendering
-                    .handle_input(ArrowKey::Left, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Left);
             }
             #[cfg(feature = "macros")]
             Some(Popup::ToolMenu(ToolMenu::Macros)) => {
@@ -2207,21 +4855,22 @@ impl App {
                 }
             }
             #[cfg(feature = "espflash")]
-            Some(Popup::ToolMenu(ToolMenu::EspFlash)) => {
-                if self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + 1 {
-                    self.espflash.unchecked_bootloader.flip();
-                }
+            Some(Popup::ToolMenu(ToolMenu::EspFlash))
+                if self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + 1 =>
+            {
+                self.espflash.unchecked_bootloader.flip();
             }
+            #[cfg(feature = "espflash")]
+            Some(Popup::ToolMenu(ToolMenu::EspFlash)) => (),
             #[cfg(feature = "logging")]
             Some(Popup::SettingsMenu(SettingsMenu::Logging)) => {
-                if self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + Logging::VISIBLE_FIELDS {
+                if self.popup_menu_scroll
+                    == POPUP_MENU_SELECTOR_COUNT + self.scratch.logging.shown_rows()
+                {
                     return;
                 }
 
-                self.scratch
-                    .logging
-                    .handle_input(ArrowKey::Left, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Left);
             }
             #[cfg(feature = "defmt")]
             Some(Popup::SettingsMenu(SettingsMenu::Defmt)) => {
@@ -2231,14 +4880,19 @@ impl App {
                     return;
                 }
 
-                self.scratch
-                    .defmt
-                    .handle_input(ArrowKey::Left, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Left);
             }
             #[cfg(feature = "defmt")]
             Some(Popup::DefmtNewElf(_)) => (),
             #[cfg(feature = "defmt")]
+            Some(Popup::DefmtModuleFilter) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtStats) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFailedFrame(_)) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFoundElf(_)) => (),
+            #[cfg(feature = "defmt")]
             Some(Popup::DefmtRecentElf) => (),
 
             Some(Popup::UpdateCheckConsentPrompt) | Some(Popup::UpdateBeginPrompt) => (),
@@ -2248,6 +4902,9 @@ impl App {
 
             #[cfg(all(windows, feature = "self-replace"))]
             Some(Popup::UpdateLaunchPrompt) => (),
+
+            #[cfg(feature = "self-replace")]
+            Some(Popup::UpdateFromArchivePrompt(_)) => (),
         }
         if self.popup.is_some() {
             return;
@@ -2278,7 +4935,24 @@ impl App {
             | Some(Popup::IgnoreByName(_))
             | Some(Popup::IgnoreByUsb(_, _))
             | Some(Popup::SerialConnectionFailed(_))
-            | Some(Popup::CurrentKeybinds) => (),
+            | Some(Popup::PortInUse(_, _))
+            | Some(Popup::LargePastePrompt(_))
+            | Some(Popup::CurrentKeybinds)
+            | Some(Popup::KeybindEditor)
+            | Some(Popup::SettingsProfiles)
+            | Some(Popup::NotificationHistory)
+            | Some(Popup::RevertSettingsPrompt(_))
+            | Some(Popup::SettingsSearch)
+            | Some(Popup::CommandPalette)
+            | Some(Popup::ComposeEditor)
+            | Some(Popup::SetupWizard) => (),
+            #[cfg(feature = "macros")]
+            Some(Popup::MacroEditor)
+            | Some(Popup::MacroVariables)
+            | Some(Popup::MacroPreview)
+            | Some(Popup::DeleteMacroPrompt(_))
+            | Some(Popup::ConfirmMacroPrompt(_))
+            | Some(Popup::ImportMacroConflict) => (),
             #[cfg(not(any(feature = "espflash", feature = "macros")))]
             Some(Popup::SettingsMenu(_)) if self.popup_menu_scroll == 0 => {}
             #[cfg(any(feature = "espflash", feature = "macros"))]
@@ -2295,26 +4969,17 @@ impl App {
                 self.cycle_sub_menu(true);
             }
             Some(Popup::SettingsMenu(SettingsMenu::SerialPort)) => {
-                self.scratch
-                    .serial
-                    .handle_input(ArrowKey::Right, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
-
-                if let Some(0) = self.get_corrected_popup_index() {
+                let changed = self.cycle_selected_setting(ArrowKey::Right);
+                // Keeping the baud rate's text input in step with the cycled value.
+                if changed == Some(0) {
                     self.baud_input = self.scratch.serial.baud_rate.to_string().into();
                 }
             }
             Some(Popup::SettingsMenu(SettingsMenu::Behavior)) => {
-                self.scratch
-                    .behavior
-                    .handle_input(ArrowKey::Right, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Right);
             }
             Some(Popup::SettingsMenu(SettingsMenu::Rendering)) => {
-                self.scratch
-                    .rendering
-                    .handle_input(ArrowKey::Right, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Right);
             }
             #[cfg(feature = "macros")]
             Some(Popup::ToolMenu(ToolMenu::Macros)) => {
@@ -2331,21 +4996,22 @@ impl App {
                 }
             }
             #[cfg(feature = "espflash")]
-            Some(Popup::ToolMenu(ToolMenu::EspFlash)) => {
-                if self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + 1 {
-                    self.espflash.unchecked_bootloader.flip();
-                }
+            Some(Popup::ToolMenu(ToolMenu::EspFlash))
+                if self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + 1 =>
+            {
+                self.espflash.unchecked_bootloader.flip();
             }
+            #[cfg(feature = "espflash")]
+            Some(Popup::ToolMenu(ToolMenu::EspFlash)) => (),
             #[cfg(feature = "logging")]
             Some(Popup::SettingsMenu(SettingsMenu::Logging)) => {
-                if self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + Logging::VISIBLE_FIELDS {
+                if self.popup_menu_scroll
+                    == POPUP_MENU_SELECTOR_COUNT + self.scratch.logging.shown_rows()
+                {
                     return;
                 }
 
-                self.scratch
-                    .logging
-                    .handle_input(ArrowKey::Right, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Right);
             }
             #[cfg(feature = "defmt")]
             Some(Popup::SettingsMenu(SettingsMenu::Defmt)) => {
@@ -2355,14 +5021,19 @@ impl App {
                     return;
                 }
 
-                self.scratch
-                    .defmt
-                    .handle_input(ArrowKey::Right, self.get_corrected_popup_index().unwrap())
-                    .unwrap();
+                self.cycle_selected_setting(ArrowKey::Right);
             }
             #[cfg(feature = "defmt")]
             Some(Popup::DefmtNewElf(_)) => (),
             #[cfg(feature = "defmt")]
+            Some(Popup::DefmtModuleFilter) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtStats) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFailedFrame(_)) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFoundElf(_)) => (),
+            #[cfg(feature = "defmt")]
             Some(Popup::DefmtRecentElf) => (),
 
             Some(Popup::UpdateCheckConsentPrompt) | Some(Popup::UpdateBeginPrompt) => (),
@@ -2372,6 +5043,9 @@ impl App {
 
             #[cfg(all(windows, feature = "self-replace"))]
             Some(Popup::UpdateLaunchPrompt) => (),
+
+            #[cfg(feature = "self-replace")]
+            Some(Popup::UpdateFromArchivePrompt(_)) => (),
         }
         if self.popup.is_some() {
             return;
@@ -2389,6 +5063,19 @@ impl App {
     fn enter_pressed(&mut self, ctrl_pressed: bool, shift_pressed: bool) -> Result<()> {
         let serial_healthy = self.serial.port_status.load().inner.is_connected();
         let popup_was_some = self.popup.is_some();
+        // Fields that can be typed into are edited instead of saving the menu.
+        if let Some(text) = self.selected_setting_edit_text() {
+            self.settings_field_editor = Some(SettingsFieldEditor {
+                field_index: self
+                    .selected_setting_index()
+                    .expect("editable field must be selected"),
+                row: self
+                    .get_corrected_popup_index()
+                    .expect("editable field must be selected"),
+                input: text.into(),
+            });
+            return Ok(());
+        }
         // debug!("{:?}", self.menu);
         match &self.popup {
             None => (),
@@ -2401,17 +5088,31 @@ impl App {
             }
             // Some(Popup::ErrorMessage(_)) => self.dismiss_popup(),
             Some(Popup::CurrentKeybinds) => self.dismiss_popup(),
+            Some(Popup::KeybindEditor) => (),
+            Some(Popup::SettingsSearch) => self.jump_to_searched_setting(),
+            Some(Popup::CommandPalette) => self.run_palette_command()?,
+            Some(Popup::ComposeEditor) => (),
+            Some(Popup::SetupWizard) => self.setup_wizard_next_step()?,
+            Some(Popup::NotificationHistory) => self.copy_selected_notification(),
+            Some(Popup::SettingsProfiles) => {
+                if let Some(name) = self.settings_profiles.nth_name(self.popup_menu_scroll) {
+                    let name = name.to_owned();
+                    self.switch_settings_profile(&name)?;
+                }
+                self.dismiss_popup();
+            }
             Some(Popup::SettingsMenu(SettingsMenu::SerialPort)) => {
                 let baud_rate = match self.baud_input.value().parse::<u32>() {
                     Ok(baud) => baud,
                     Err(e) => {
                         self.notifs
-                            .notify_str(format!("Invalid Baud Rate: {e}!"), Color::Red);
+                            .notify_str(format!("Invalid Baud Rate: {e}!"), Severity::Error);
                         return Ok(());
                     }
                 };
                 self.scratch.serial.baud_rate = baud_rate;
 
+                self.push_settings_history();
                 self.settings.serial = self.scratch.serial.clone();
                 self.buffer
                     .update_line_ending(self.scratch.serial.rx_line_ending.as_bytes());
@@ -2419,37 +5120,45 @@ impl App {
                 self.serial.update_settings(self.scratch.serial.clone())?;
 
                 self.settings.save()?;
-                self.notifs.notify_str("Port settings saved!", Color::Green);
+                self.notifs
+                    .notify_str("Port settings saved!", Severity::Success);
 
                 self.dismiss_popup();
             }
             Some(Popup::SettingsMenu(SettingsMenu::Behavior)) => {
+                self.push_settings_history();
                 self.settings.behavior = self.scratch.behavior.clone();
+                self.apply_notification_filter();
 
                 self.settings.save()?;
                 self.dismiss_popup();
                 self.notifs
-                    .notify_str("Behavior settings saved!", Color::Green);
+                    .notify_str("Behavior settings saved!", Severity::Success);
             }
             Some(Popup::SettingsMenu(SettingsMenu::Rendering)) => {
+                self.push_settings_history();
                 self.settings.rendering = self.scratch.rendering.clone();
                 self.buffer
                     .update_render_settings(self.settings.rendering.clone());
+                self.apply_theme();
 
                 self.settings.save()?;
                 self.dismiss_popup();
                 self.notifs
-                    .notify_str("Rendering settings saved!", Color::Green);
+                    .notify_str("Rendering settings saved!", Severity::Success);
             }
             #[cfg(feature = "logging")]
             Some(Popup::SettingsMenu(SettingsMenu::Logging)) => {
                 // if Sync Logs button was selected
-                if self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + Logging::VISIBLE_FIELDS {
+                if self.popup_menu_scroll
+                    == POPUP_MENU_SELECTOR_COUNT + self.scratch.logging.shown_rows()
+                {
                     self.run_builtin_action(BuiltinAction::Logging(LoggingAction::Sync))?;
                     return Ok(());
                 }
                 // Otherwise, save settings.
 
+                self.push_settings_history();
                 self.settings.logging = self.scratch.logging.clone();
                 // let current_port = {
                 //     let port_status_guard = self.serial.port_status.load();
@@ -2461,7 +5170,7 @@ impl App {
                 self.settings.save()?;
                 self.dismiss_popup();
                 self.notifs
-                    .notify_str("Logging settings saved!", Color::Green);
+                    .notify_str("Logging settings saved!", Severity::Success);
             }
             #[cfg(feature = "defmt")]
             Some(Popup::SettingsMenu(SettingsMenu::Defmt)) => {
@@ -2485,6 +5194,7 @@ impl App {
                 }
                 // Otherwise, save settings.
 
+                self.push_settings_history();
                 self.settings.defmt = self.scratch.defmt.clone();
 
                 self.buffer
@@ -2493,12 +5203,13 @@ impl App {
                 self.settings.save()?;
                 self.dismiss_popup();
                 self.notifs
-                    .notify_str("defmt settings saved!", Color::Green);
+                    .notify_str("defmt settings saved!", Severity::Success);
             }
             #[cfg(feature = "macros")]
             Some(Popup::ToolMenu(ToolMenu::Macros)) => {
-                // Category selector active, just ignore.
+                // Category selector active, expand or collapse the selected category.
                 if self.popup_menu_scroll == 2 {
+                    self.macros.toggle_selected_category_collapsed();
                     return Ok(());
                 }
                 let index = self
@@ -2510,12 +5221,32 @@ impl App {
 
                 if ctrl_pressed || shift_pressed {
                     if !serial_healthy {
-                        self.notifs.notify_str("Port isn't ready!", Color::Red);
+                        self.notifs.notify_str("Port isn't ready!", Severity::Error);
                         return Ok(());
                     }
                     // Putting macro content into buffer.
                     match content {
                         _ if content.is_empty() => (),
+                        _ if content.is_sequence() => self.notifs.notify_str(
+                            "Macro sequences can't be placed in the input!",
+                            Severity::Warning,
+                        ),
+                        MacroContent {
+                            content_file: Some(content_file),
+                            ..
+                        } => match self.macros.read_content_file(&tag, content_file) {
+                            Ok(bytes) => {
+                                self.text_input.replace_input_with_bytes(&bytes);
+                                self.dismiss_popup();
+                            }
+                            Err(e) => {
+                                error!("Failed to read content file for macro {tag}: {e}");
+                                self.notifs.notify_str(
+                                    format!("Failed to read macro file: {e}"),
+                                    Severity::Error,
+                                );
+                            }
+                        },
                         bytes if content.has_escaped_bytes => {
                             self.text_input
                                 .replace_input_with_bytes(&bytes.unescape_bytes());
@@ -2528,12 +5259,12 @@ impl App {
                     }
                 } else {
                     if !serial_healthy {
-                        self.notifs.notify_str("Port isn't ready!", Color::Red);
+                        self.notifs.notify_str("Port isn't ready!", Severity::Error);
                         return Ok(());
                     }
                     match content {
                         _ if content.is_empty() => {
-                            self.notifs.notify_str("Macro is empty!", Color::Yellow)
+                            self.notifs.notify_str("Macro is empty!", Severity::Warning)
                         }
                         _ => {
                             self.send_one_macro(tag, None)?;
@@ -2546,7 +5277,7 @@ impl App {
             #[cfg(feature = "espflash")]
             Some(Popup::ToolMenu(ToolMenu::EspFlash)) => {
                 if !serial_healthy {
-                    self.notifs.notify_str("Port isn't ready!", Color::Red);
+                    self.notifs.notify_str("Port isn't ready!", Severity::Error);
                     return Ok(());
                 }
                 let selected = self.get_corrected_popup_index().unwrap();
@@ -2597,7 +5328,7 @@ impl App {
                             } else {
                                 self.notifs.notify_str(
                                     "Press again to confirm erasing flash!",
-                                    Color::Yellow,
+                                    Severity::Warning,
                                 );
                                 Some(Instant::now())
                             };
@@ -2609,6 +5340,18 @@ impl App {
             #[cfg(feature = "defmt")]
             Some(Popup::DefmtNewElf(_)) => (),
             #[cfg(feature = "defmt")]
+            Some(Popup::DefmtModuleFilter) => (),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtStats) => self.dismiss_popup(),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFailedFrame(_)) => self.dismiss_popup(),
+            #[cfg(feature = "defmt")]
+            Some(Popup::DefmtFoundElf(_)) => {
+                self.defmt_found_elf_choice(
+                    DefmtFoundElfPrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                );
+            }
+            #[cfg(feature = "defmt")]
             Some(Popup::DefmtRecentElf) => {
                 if !self.defmt_helpers.recent_elfs.is_empty() {
                     let elf_path = self
@@ -2648,7 +5391,42 @@ impl App {
                     IgnorePortByNamePrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
                 )?;
             }
+            #[cfg(feature = "macros")]
+            Some(Popup::MacroEditor) | Some(Popup::MacroVariables) | Some(Popup::MacroPreview) => {}
+            #[cfg(feature = "macros")]
+            Some(Popup::DeleteMacroPrompt(_)) => {
+                self.delete_macro_prompt_choice(
+                    DeleteMacroPrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                );
+            }
+            #[cfg(feature = "macros")]
+            Some(Popup::ConfirmMacroPrompt(_)) => {
+                self.confirm_macro_prompt_choice(
+                    ConfirmMacroPrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                )?;
+            }
+            #[cfg(feature = "macros")]
+            Some(Popup::ImportMacroConflict) => {
+                self.import_macro_conflict_choice(
+                    ImportMacroConflictPrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                )?;
+            }
             Some(Popup::SerialConnectionFailed(_)) => self.dismiss_popup(),
+            Some(Popup::PortInUse(_, _)) => {
+                self.port_in_use_prompt_choice(
+                    PortInUsePrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                )?;
+            }
+            Some(Popup::LargePastePrompt(_)) => {
+                self.large_paste_prompt_choice(
+                    LargePastePrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                )?;
+            }
+            Some(Popup::RevertSettingsPrompt(_)) => {
+                self.revert_settings_prompt_choice(
+                    RevertSettingsPrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                )?;
+            }
             Some(Popup::UpdateCheckConsentPrompt) => {
                 self.update_check_consent_choice(
                     UpdateCheckConsentPrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
@@ -2666,6 +5444,12 @@ impl App {
                 )?;
             }
             #[cfg(feature = "self-replace")]
+            Some(Popup::UpdateFromArchivePrompt(_)) => {
+                self.update_from_archive_choice(
+                    UpdateFromArchivePrompt::try_from(self.popup_menu_scroll as u8).unwrap(),
+                )?;
+            }
+            #[cfg(feature = "self-replace")]
             Some(Popup::UpdateDownloading(_)) => (),
         }
         if self.popup.is_some() || popup_was_some {
@@ -2681,43 +5465,26 @@ impl App {
                     (scroll, Some(port_info)) if scroll < self.ports.len() => {
                         info!("Port {}", port_info.port_name);
 
-                        let baud_rate = if COMMON_BAUD
-                            .last_index_eq(self.baud_selection_state.current_index)
-                        {
-                            match self.baud_input.value().parse::<u32>() {
-                                Ok(b) => b,
-                                Err(e) => {
-                                    self.notifs
-                                        .notify_str(format!("Invalid Baud Rate: {e}!"), Color::Red);
-                                    return Ok(());
+                        let baud_rate =
+                            if COMMON_BAUD.last_index_eq(self.baud_selection_state.current_index) {
+                                match self.baud_input.value().parse::<u32>() {
+                                    Ok(b) => b,
+                                    Err(e) => {
+                                        self.notifs.notify_str(
+                                            format!("Invalid Baud Rate: {e}!"),
+                                            Severity::Error,
+                                        );
+                                        return Ok(());
+                                    }
                                 }
-                            }
-                        } else {
-                            COMMON_BAUD[self.baud_selection_state.current_index]
-                        };
+                            } else {
+                                COMMON_BAUD[self.baud_selection_state.current_index]
+                            };
 
                         self.settings.serial.baud_rate = baud_rate;
                         self.settings.save()?;
 
-                        match self.serial.connect_blocking(
-                            port_info.clone(),
-                            self.settings.serial.clone(),
-                            Some(baud_rate),
-                            CONNECT_ATTEMPT_BLOCK_MAX,
-                        ) {
-                            Ok(()) => {
-                                self.menu = Menu::Terminal;
-                            }
-                            Err(BlockingCommandError::Worker(e)) => {
-                                let report = color_eyre::Report::new(e);
-                                let mut error_string = String::new();
-                                for e in report.chain() {
-                                    error_string.push_str(&format!("\n{e}"));
-                                }
-                                self.popup = Some(Popup::SerialConnectionFailed(error_string));
-                            }
-                            Err(e) => Err(e)?,
-                        }
+                        self.connect_to_chosen_port(port_info.clone(), false)?;
                     }
                     (scroll, None) if scroll < self.ports.len() => {
                         unreachable!()
@@ -2750,7 +5517,7 @@ impl App {
                         Err(e) => {
                             error!("Failed parsing user's bytes to send to port! {e}");
                             self.notifs
-                                .notify_str(format!("Byte parse error! `{e}`"), Color::Red);
+                                .notify_str(format!("Byte parse error! `{e}`"), Severity::Error);
                             return Ok(());
                         }
                     };
@@ -2771,32 +5538,116 @@ impl App {
                             None,
                         );
                         self.repeating_line_flip.flip();
+
+                        #[cfg(feature = "macros")]
+                        if let Some(recorder) = &mut self.macro_recorder {
+                            recorder.record_bytes(&bytes, user_le_bytes.unwrap_or(&[]));
+                        }
                     }
                 } else if !user_input.is_empty() || !user_le_bytes.is_empty() {
-                    self.serial.send_str(
-                        user_input,
-                        user_le_bytes,
-                        self.settings.behavior.unescape_typed_bytes,
-                    )?;
-                    self.buffer.append_user_text(
-                        user_input,
-                        user_le_bytes,
-                        #[cfg(feature = "macros")]
-                        None,
-                    );
-                    self.repeating_line_flip.flip();
+                    let (user_input, user_le_bytes) =
+                        (user_input.to_owned(), user_le_bytes.to_owned());
+                    self.send_user_text(&user_input, &user_le_bytes)?;
                 }
 
+                let entered = !self.text_input.value().is_empty();
                 self.text_input.commit_input_to_history();
+                if entered {
+                    self.save_input_history();
+                }
+
+                // Scroll all the way down
+                // TODO: Make this behavior a toggle
+                self.buffer.scroll_by(i32::MIN);
+            }
+            Menu::Terminal => (),
+        }
+        Ok(())
+    }
+    /// Add the Pseudo Shell's newest entry to the history file, if it's being kept between sessions.
+    fn save_input_history(&mut self) {
+        let behavior = &self.settings.behavior;
+        if !behavior.retain_history {
+            return;
+        }
+        if let Err(e) = self.text_input.append_history(
+            &config_adjacent_path(HISTORY_PATH),
+            behavior.retain_byte_history,
+            !behavior.history_ignore_space,
+        ) {
+            error!("Failed saving input history: {e}");
+            self.notifs
+                .notify_str(format!("Failed saving input history! {e}"), Severity::Error);
+        }
+    }
+    /// Send typed text to the port and show it in the buffer, along with recording it if a macro's being recorded.
+    fn send_user_text(&mut self, text: &str, line_ending: &[u8]) -> Result<()> {
+        self.serial.send_str(
+            text,
+            line_ending,
+            self.settings.behavior.unescape_typed_bytes,
+        )?;
+        self.buffer.append_user_text(
+            text,
+            line_ending,
+            #[cfg(feature = "macros")]
+            None,
+        );
+        self.repeating_line_flip.flip();
 
-                // Scroll all the way down
-                // TODO: Make this behavior a toggle
-                self.buffer.scroll_by(i32::MIN);
-            }
-            Menu::Terminal => (),
+        #[cfg(feature = "macros")]
+        if let Some(recorder) = &mut self.macro_recorder {
+            recorder.record_text(
+                text,
+                line_ending,
+                self.settings.behavior.unescape_typed_bytes,
+            );
         }
         Ok(())
     }
+    /// Queue every line in the compose editor to be sent in order, with the configured delay between each,
+    /// asking first if they're over `Behavior::paste_confirm_threshold` altogether.
+    fn send_composed_lines(&mut self) -> Result<()> {
+        if !self.serial.port_status.load().inner.is_connected() {
+            // Draft's kept as-is, to send once the port's back.
+            self.trigger_send_failed_visual()?;
+            return Ok(());
+        }
+        let lines = self.compose_editor.take_lines();
+        self.dismiss_popup();
+        if lines.is_empty() {
+            self.notifs
+                .notify_str("Nothing to send!", Severity::Warning);
+            return Ok(());
+        }
+
+        let threshold = self.settings.behavior.paste_confirm_threshold;
+        let lines = LargeSend::ComposedLines(lines);
+        if threshold > 0 && lines.len() > threshold {
+            self.show_popup(Popup::LargePastePrompt(lines));
+            return Ok(());
+        }
+        self.send_large(lines)
+    }
+    /// Send what `Popup::LargePastePrompt` was asking about, or anything under its threshold.
+    fn send_large(&mut self, send: LargeSend) -> Result<()> {
+        match send {
+            LargeSend::Paste(text) => self.send_paste(text),
+            LargeSend::ComposedLines(lines) => self.queue_composed_lines(lines),
+        }
+    }
+    fn queue_composed_lines(&mut self, lines: Vec<String>) -> Result<()> {
+        let delay = self.settings.behavior.compose_line_delay;
+        let actions = lines
+            .into_iter()
+            .enumerate()
+            .flat_map(|(index, line)| {
+                let pause = (index > 0).then_some(Action::Pause(delay));
+                pause.into_iter().chain([Action::ComposedLine(line.into())])
+            })
+            .collect();
+        self.queue_keybinds_action_set(actions, None)
+    }
     fn trigger_send_failed_visual(&mut self) -> Result<()> {
         self.failed_send_at = Some(Instant::now());
         // Temporarily show text on red background when trying to send while unhealthy
@@ -2808,12 +5659,37 @@ impl App {
         )?;
         Ok(())
     }
+    fn ring_bell(&mut self) -> Result<()> {
+        match self.settings.rendering.bell {
+            BellBehavior::ShowEscaped => (),
+            BellBehavior::Flash => {
+                self.bell_flash_at = Some(Instant::now());
+                self.carousel.add_oneshot(
+                    "BellFlash",
+                    Tick::Requested("Bell Flash Removal"),
+                    BELL_FLASH_TIME,
+                )?;
+            }
+            BellBehavior::TerminalBell => {
+                use std::io::Write;
+                // Doesn't move the cursor or otherwise disturb ratatui's idea of the screen.
+                let mut stdout = std::io::stdout();
+                stdout.write_all(&[0x07])?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
     fn return_to_port_selection(&mut self) -> Result<()> {
         self.serial.request_disconnect()?;
         // Refresh port listings
         self.ports = self.serial.request_port_scan_blocking(SCAN_BLOCK_MAX)?;
 
         self.buffer.intentional_disconnect_clear()?;
+        #[cfg(feature = "logging")]
+        {
+            self.replay = None;
+        }
         // Clear the input box, but keep the user history!
         self.text_input.clear();
 
@@ -2861,19 +5737,21 @@ impl App {
                 self.user_broke_connection = false;
                 self.dismiss_popup();
                 self.notifs
-                    .notify_str("Unpausing reconnections!", Color::LightGreen);
+                    .notify_str("Unpausing reconnections!", Severity::Success);
             }
             AttemptReconnectPrompt::AttemptReconnect if shift_pressed || ctrl_pressed => {
                 self.repeating_line_flip.flip();
                 self.notifs
-                    .notify_str("Attempting to reconnect! (Loose Checks)", Color::Yellow);
+                    .notify_str("Attempting to reconnect! (Loose Checks)", Severity::Warning);
                 self.serial
                     .request_reconnect(Some(Reconnections::LooseChecks))?;
             }
             AttemptReconnectPrompt::AttemptReconnect => {
                 self.repeating_line_flip.flip();
-                self.notifs
-                    .notify_str("Attempting to reconnect! (Strict Checks)", Color::Yellow);
+                self.notifs.notify_str(
+                    "Attempting to reconnect! (Strict Checks)",
+                    Severity::Warning,
+                );
                 self.serial
                     .request_reconnect(Some(Reconnections::StrictChecks))?;
             }
@@ -2917,6 +5795,93 @@ impl App {
         self.dismiss_popup();
         Ok(())
     }
+    fn large_paste_prompt_choice(&mut self, choice: LargePastePrompt) -> Result<()> {
+        let Some(Popup::LargePastePrompt(send)) = self.popup.take() else {
+            unreachable!("Can't send a paste without one pending!");
+        };
+        self.dismiss_popup();
+
+        match (choice, send) {
+            (LargePastePrompt::Send, send) => self.send_large(send)?,
+            // Giving the draft back to keep working on.
+            (LargePastePrompt::Cancel, LargeSend::ComposedLines(lines)) => {
+                self.compose_editor.set_text(&lines.join("\n"));
+                self.show_popup(Popup::ComposeEditor);
+            }
+            (LargePastePrompt::Cancel, LargeSend::Paste(_)) => (),
+        }
+        Ok(())
+    }
+    fn port_in_use_prompt_choice(&mut self, choice: PortInUsePrompt) -> Result<()> {
+        let Some(Popup::PortInUse(port_info, _)) = self.popup.take() else {
+            unreachable!("Can't act on port in use without its info!");
+        };
+        self.dismiss_popup();
+
+        match choice {
+            PortInUsePrompt::Retry => self.connect_to_chosen_port(port_info, false)?,
+            PortInUsePrompt::PickAnotherPort => {
+                self.ports.clear();
+                self.serial.request_port_scan()?;
+            }
+            #[cfg(unix)]
+            PortInUsePrompt::ForceConnect => self.connect_to_chosen_port(port_info, true)?,
+        }
+        Ok(())
+    }
+    /// Connect to the port chosen in Port Selection, showing why in a popup if it failed.
+    ///
+    /// If `force` is set, the port is connected to even if another yap instance claims it.
+    fn connect_to_chosen_port(&mut self, port_info: SerialPortInfo, force: bool) -> Result<()> {
+        let settings = self.settings.serial.clone();
+        let baud_rate = Some(settings.baud_rate);
+
+        #[cfg(unix)]
+        let result = if force {
+            self.serial.force_connect_blocking(
+                port_info.clone(),
+                settings,
+                baud_rate,
+                CONNECT_ATTEMPT_BLOCK_MAX,
+            )
+        } else {
+            self.serial.connect_blocking(
+                port_info.clone(),
+                settings,
+                baud_rate,
+                CONNECT_ATTEMPT_BLOCK_MAX,
+            )
+        };
+        #[cfg(not(unix))]
+        let result = {
+            debug_assert!(!force, "forced connections are unix-only");
+            self.serial.connect_blocking(
+                port_info.clone(),
+                settings,
+                baud_rate,
+                CONNECT_ATTEMPT_BLOCK_MAX,
+            )
+        };
+
+        match result {
+            Ok(()) => {
+                self.menu = Menu::Terminal;
+            }
+            Err(BlockingCommandError::Worker(WorkerError::PortInUse(holder))) => {
+                self.show_popup(Popup::PortInUse(port_info, holder));
+            }
+            Err(BlockingCommandError::Worker(e)) => {
+                let report = color_eyre::Report::new(e);
+                let mut error_string = String::new();
+                for e in report.chain() {
+                    error_string.push_str(&format!("\n{e}"));
+                }
+                self.popup = Some(Popup::SerialConnectionFailed(error_string));
+            }
+            Err(e) => Err(e)?,
+        }
+        Ok(())
+    }
     fn add_ignored_name(&mut self, name: String) -> Result<()> {
         if name == MOCK_PORT_NAME {
             return Ok(());
@@ -2963,22 +5928,36 @@ impl App {
             panic!("no popup means no item count!")
         };
         match popup {
+            Popup::SettingsProfiles => self.settings_profiles.len(),
+            Popup::NotificationHistory => self.notifs.history().len(),
             #[cfg(feature = "defmt")]
             Popup::DefmtRecentElf => self.defmt_helpers.recent_elfs.len(),
+            #[cfg(feature = "defmt")]
+            Popup::DefmtModuleFilter | Popup::DefmtStats | Popup::DefmtFailedFrame(_) => 0,
+            #[cfg(feature = "macros")]
+            Popup::MacroEditor | Popup::MacroVariables | Popup::MacroPreview => 0,
+            #[cfg(feature = "macros")]
+            Popup::DeleteMacroPrompt(_) => <DeleteMacroPrompt as VariantArray>::VARIANTS.len(),
+            #[cfg(feature = "macros")]
+            Popup::ConfirmMacroPrompt(_) => <ConfirmMacroPrompt as VariantArray>::VARIANTS.len(),
+            #[cfg(feature = "macros")]
+            Popup::ImportMacroConflict => {
+                <ImportMacroConflictPrompt as VariantArray>::VARIANTS.len()
+            }
             Popup::SettingsMenu(settings) => {
                 let items = match settings {
-                    SettingsMenu::SerialPort => PortSettings::VISIBLE_FIELDS,
-                    SettingsMenu::Behavior => Behavior::VISIBLE_FIELDS,
-                    SettingsMenu::Rendering => Rendering::VISIBLE_FIELDS,
+                    SettingsMenu::SerialPort => self.scratch.serial.shown_rows(),
+                    SettingsMenu::Behavior => self.scratch.behavior.shown_rows(),
+                    SettingsMenu::Rendering => self.scratch.rendering.shown_rows(),
                     #[cfg(feature = "logging")]
                     SettingsMenu::Logging => {
                         1 + // Start/Stop Logging button
-                Logging::VISIBLE_FIELDS
+                self.scratch.logging.shown_rows()
                     }
                     #[cfg(feature = "defmt")]
                     SettingsMenu::Defmt => {
                         2 + // Select New/Recent ELF buttons
-                Defmt::VISIBLE_FIELDS
+                self.scratch.defmt.shown_rows()
                     }
                 };
                 items + POPUP_MENU_SELECTOR_COUNT
@@ -3003,13 +5982,25 @@ impl App {
                 <AttemptReconnectPrompt as VariantArray>::VARIANTS.len()
             }
             Popup::IgnoreByName(_) => <IgnorePortByNamePrompt as VariantArray>::VARIANTS.len(),
+            Popup::PortInUse(_, _) => <PortInUsePrompt as VariantArray>::VARIANTS.len(),
+            Popup::LargePastePrompt(_) => <LargePastePrompt as VariantArray>::VARIANTS.len(),
+            #[cfg(feature = "defmt")]
+            Popup::DefmtFoundElf(_) => <DefmtFoundElfPrompt as VariantArray>::VARIANTS.len(),
             Popup::IgnoreByUsb(_, _) => <IgnoreUsbDevicePrompt as VariantArray>::VARIANTS.len(),
+            Popup::RevertSettingsPrompt(_) => {
+                <RevertSettingsPrompt as VariantArray>::VARIANTS.len()
+            }
+            Popup::SetupWizard => SetupWizard::VISIBLE_FIELDS,
             Popup::UpdateBeginPrompt => <UpdateBeginPrompt as VariantArray>::VARIANTS.len(),
             Popup::UpdateCheckConsentPrompt => {
                 <UpdateCheckConsentPrompt as VariantArray>::VARIANTS.len()
             }
             #[cfg(all(windows, feature = "self-replace"))]
             Popup::UpdateLaunchPrompt => <UpdateLaunchPrompt as VariantArray>::VARIANTS.len(),
+            #[cfg(feature = "self-replace")]
+            Popup::UpdateFromArchivePrompt(_) => {
+                <UpdateFromArchivePrompt as VariantArray>::VARIANTS.len()
+            }
             _ => unreachable!("popup {popup:?} has no item count"),
         }
     }
@@ -3107,6 +6098,10 @@ impl App {
         self.popup_menu_scroll = self.current_popup_selectable_item_count().saturating_sub(1);
     }
     pub fn draw(&mut self, terminal: &mut Terminal<impl Backend>) -> Result<()> {
+        let reserved_lines = crate::buffer::DEFAULT_RESERVED_LINES
+            + u16::from(self.macro_hotbar_shown())
+            + u16::from(self.settings.rendering.help_footer);
+        self.buffer.set_reserved_lines(reserved_lines, terminal)?;
         // let start = Instant::now();
         terminal.draw(|frame| self.render_app(frame))?;
         // debug!("A4: {:?}", start.elapsed());
@@ -3116,10 +6111,15 @@ impl App {
         // self.buffer.update_terminal_size(frame.area().as_size());
         // TODO, make more reactive based on frame size :)
 
+        let footer_height = u16::from(self.settings.rendering.help_footer);
+        let [menu_area, footer_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(footer_height)])
+                .areas(frame.area());
+
         // let start = Instant::now();
         match self.menu {
-            Menu::PortSelection => self.port_selection(frame),
-            Menu::Terminal => self.terminal_menu(frame),
+            Menu::PortSelection => self.port_selection(frame, menu_area),
+            Menu::Terminal => self.terminal_menu(frame, menu_area),
         }
         // debug!("a1: {:?}", start.elapsed());
 
@@ -3128,6 +6128,20 @@ impl App {
         // debug!("a2: {:?}", start.elapsed());
 
         // let start = Instant::now();
+        if !self.pending_chord.is_empty() {
+            render_chord_hint(&self.keybinds, &self.pending_chord, frame, frame.area());
+        }
+
+        if self.settings.rendering.help_footer {
+            render_help_footer(
+                &self.keybinds,
+                &self.keybind_contexts(),
+                &self.help_footer_actions(),
+                frame,
+                footer_area,
+            );
+        }
+
         self.render_notifs(frame, frame.area());
         // debug!("a3: {:?}", start.elapsed());
 
@@ -3158,6 +6172,101 @@ impl App {
                 show_keybinds(&self.keybinds, &mut scroll, frame, area, self);
                 self.popup_menu_scroll = scroll as usize;
             }
+            Popup::KeybindEditor => self.keybind_editor.render(&self.keybinds, frame, area),
+            Popup::SettingsSearch => self.settings_search.render(frame, area),
+            Popup::CommandPalette => self.command_palette.render(frame, area),
+            Popup::ComposeEditor => self.compose_editor.render(frame, area),
+            Popup::SetupWizard => self
+                .setup_wizard
+                .render(self.popup_menu_scroll, frame, area),
+            Popup::SettingsProfiles => {
+                let area = centered_rect_size(
+                    Size {
+                        width: area.width.min(60),
+                        height: (self.settings_profiles.len() as u16 + 2).min(15),
+                    },
+                    area,
+                );
+
+                let title = Line::raw(" Switch to settings profile: ")
+                    .centered()
+                    .reset();
+                let hint = Line::raw(" Enter: Switch | Esc: Cancel ")
+                    .centered()
+                    .reset();
+
+                let block = Block::bordered()
+                    .border_style(Style::new().light_magenta())
+                    .title_top(title)
+                    .title_bottom(hint);
+
+                let inner = block.inner(area);
+
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+
+                frame.render_widget(Clear, area);
+                frame.render_widget(block, area);
+                frame.render_stateful_widget(
+                    self.settings_profiles
+                        .as_table(self.settings.profile.as_deref()),
+                    inner,
+                    &mut table_state,
+                );
+            }
+            Popup::NotificationHistory => {
+                use ratatui::widgets::Cell;
+
+                let history = self.notifs.history();
+                let area = centered_rect_size(
+                    Size {
+                        width: area.width.min(90),
+                        height: (history.len() as u16 + 2).min(20),
+                    },
+                    area,
+                );
+
+                let title = Line::raw(format!(" Notification History ({}) ", history.len()))
+                    .centered()
+                    .reset();
+                let hint = Line::raw(" c: Copy | Esc: Close ").centered().reset();
+
+                let block = Block::bordered()
+                    .border_style(theme().border_style())
+                    .title_top(title)
+                    .title_bottom(hint);
+
+                let rows: Vec<Row> = history
+                    .iter()
+                    .map(|past| {
+                        let mut text_style = theme().notification_style(past.color, past.severity);
+                        // Ones that were never shown are set apart from the rest.
+                        if past.suppressed {
+                            text_style = text_style.add_modifier(Modifier::ITALIC);
+                        }
+                        Row::new([
+                            Cell::from(past.shown_at.format("%H:%M:%S").to_string())
+                                .style(theme().dim_style()),
+                            Cell::from(past.severity.to_string()).style(theme().dim_style()),
+                            Cell::from(past.text.as_str()).style(text_style),
+                        ])
+                    })
+                    .collect();
+                let table = Table::new(
+                    rows,
+                    [
+                        Constraint::Length(8),
+                        Constraint::Length(7),
+                        Constraint::Fill(1),
+                    ],
+                )
+                .row_highlight_style(theme().selection_style())
+                .block(block);
+
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(table, area, &mut table_state);
+            }
             #[cfg(feature = "defmt")]
             Popup::DefmtNewElf(file_explorer) => {
                 let area = centered_rect_size(
@@ -3183,10 +6292,16 @@ impl App {
                 let title = Line::raw(" Select from recently used ELFs: ")
                     .centered()
                     .reset();
+                #[cfg(feature = "defmt-watch")]
+                let hint = " p: Pin/Unpin | w: Watch/Unwatch | Del: Remove ";
+                #[cfg(not(feature = "defmt-watch"))]
+                let hint = " p: Pin/Unpin | Del: Remove ";
+                let hint = Line::raw(hint).centered().reset();
 
                 let block = Block::bordered()
                     .border_style(Style::new().light_red())
-                    .title_top(title);
+                    .title_top(title)
+                    .title_bottom(hint);
 
                 let inner = block.inner(area);
 
@@ -3200,6 +6315,134 @@ impl App {
                     &mut table_state,
                 );
             }
+            #[cfg(feature = "defmt")]
+            Popup::DefmtModuleFilter => {
+                let area = centered_rect_size(
+                    Size {
+                        width: area.width.min(60),
+                        height: 5,
+                    },
+                    area,
+                );
+
+                let title = Line::raw(" Filter defmt logs by module: ")
+                    .centered()
+                    .reset();
+                let hint = Line::raw(" Enter: Apply | Esc: Cancel ").centered().reset();
+
+                let block = Block::bordered()
+                    .border_style(Style::new().light_red())
+                    .title_top(title)
+                    .title_bottom(hint);
+
+                let [input_area, _, example_area] =
+                    vertical![==1, ==1, ==1].areas(block.inner(area));
+
+                let input = &self.defmt_helpers.module_filter_input;
+                // So the cursor doesn't bleed off the edge
+                let width = input_area.width.max(1).saturating_sub(1) as usize;
+                let scroll = input.visual_scroll(width);
+                let cursor_offset = input.visual_cursor().saturating_sub(scroll);
+
+                frame.render_widget(Clear, area);
+                frame.render_widget(block, area);
+                frame.render_widget(
+                    Paragraph::new(input.value()).scroll((0, scroll as u16)),
+                    input_area,
+                );
+                frame.render_widget(
+                    Line::raw("i.e. my_app::*, !my_app::radio")
+                        .all_spans_styled(theme().dim_style())
+                        .centered(),
+                    example_area,
+                );
+                frame.set_cursor_position((input_area.x + cursor_offset as u16, input_area.y));
+            }
+            #[cfg(feature = "defmt")]
+            Popup::DefmtStats => {
+                use ratatui::widgets::Cell;
+
+                let stats = self.buffer.defmt_stats();
+
+                let header = |text: &'static str| {
+                    Row::new([Cell::from(text)]).style(Style::new().light_red().bold())
+                };
+                let count_row = |(label, count): (&'static str, usize)| {
+                    Row::new([
+                        Cell::from(label),
+                        Cell::from(Line::raw(count.to_string()).right_aligned()),
+                    ])
+                };
+
+                let rows: Vec<Row> = std::iter::once(header("Decoded Frames"))
+                    .chain(stats.frames_per_level().map(count_row))
+                    .chain([Row::default(), header("Decode Failures")])
+                    .chain(stats.failures().into_iter().map(count_row))
+                    .chain([
+                        Row::default(),
+                        count_row(("Non-frame slices", stats.non_frame_slices())),
+                    ])
+                    .collect();
+
+                let hint = if stats.framing_suspect() {
+                    Line::raw(" Frequent failures, check the defmt framing setting! ")
+                        .centered()
+                        .red()
+                } else {
+                    Line::raw(" Esc: Close ").centered().reset()
+                };
+
+                let area = centered_rect_size(
+                    Size {
+                        width: area.width.min(50),
+                        height: rows.len() as u16 + 2,
+                    },
+                    area,
+                );
+
+                let block = Block::bordered()
+                    .border_style(Style::new().light_red())
+                    .title_top(Line::raw(" defmt Statistics ").centered().reset())
+                    .title_bottom(hint);
+
+                let table = Table::new(rows, [Constraint::Fill(1), Constraint::Length(10)]);
+
+                frame.render_widget(Clear, area);
+                frame.render_widget(table.block(block), area);
+            }
+            #[cfg(feature = "defmt")]
+            Popup::DefmtFailedFrame(bytes) => {
+                let rows: Vec<Line> = hexdump_rows(bytes).into_iter().map(Line::raw).collect();
+
+                let area = centered_rect_size(
+                    Size {
+                        width: 77,
+                        height: (rows.len() as u16 + 2).min(20),
+                    },
+                    area,
+                );
+                let visible_rows = area.height.saturating_sub(2) as usize;
+                self.popup_menu_scroll = self
+                    .popup_menu_scroll
+                    .min(rows.len().saturating_sub(visible_rows));
+
+                let title = Line::raw(format!(" Failed defmt frame ({} bytes) ", bytes.len()))
+                    .centered()
+                    .reset();
+                let hint = Line::raw(" c: Copy | Esc: Close ").centered().reset();
+
+                let block = Block::bordered()
+                    .border_style(Style::new().light_red())
+                    .title_top(title)
+                    .title_bottom(hint);
+
+                let paragraph = Paragraph::new(rows)
+                    .block(block)
+                    .scroll((self.popup_menu_scroll as u16, 0));
+
+                frame.render_widget(Clear, area);
+                frame.render_widget(paragraph, area);
+            }
             Popup::AttemptReconnectPrompt => {
                 let user_broke_connection = if self.user_broke_connection {
                     Some("(Reconnections paused!)")
@@ -3214,55 +6457,149 @@ impl App {
                     frame,
                     area,
                     &mut table_state,
-                )
+                )
+            }
+            Popup::DisconnectPrompt => {
+                let port_state = { self.serial.port_status.load().inner };
+                let reconns_paused = if self.settings.serial.reconnections.allowed()
+                    && port_state.is_premature_disconnect()
+                {
+                    Some("(Auto-reconnections paused while open)")
+                } else {
+                    None
+                };
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+                DisconnectPrompt::render_prompt_block_popup(
+                    Some("Disconnect from port?"),
+                    reconns_paused,
+                    Style::new().blue(),
+                    frame,
+                    area,
+                    &mut table_state,
+                );
+            }
+            Popup::IgnoreByUsb(name, usb) => {
+                let UsbPortInfo {
+                    vid,
+                    pid,
+                    serial_number,
+                    ..
+                } = usb;
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+                let mut info = format!("VID: {vid:04X}, PID: {pid:04X}, Serial #: ");
+                if let Some(serial_num) = serial_number {
+                    info.push_str(serial_num);
+                } else {
+                    info.push_str("N/A");
+                }
+                IgnoreUsbDevicePrompt::render_prompt_block_popup(
+                    Some(&format!("Hide {name} (USB) from Port Selection?")),
+                    Some(&info),
+                    Style::new().yellow(),
+                    frame,
+                    area,
+                    &mut table_state,
+                );
+            }
+            #[cfg(feature = "defmt")]
+            Popup::DefmtFoundElf(path) => {
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+                DefmtFoundElfPrompt::render_prompt_block_popup(
+                    Some("Load newest defmt ELF?"),
+                    Some(path.as_str()),
+                    Style::new().light_red(),
+                    frame,
+                    area,
+                    &mut table_state,
+                );
+            }
+            Popup::PortInUse(port_info, holder) => {
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+                PortInUsePrompt::render_prompt_block_popup(
+                    Some(&format!("{} is in use!", port_info.port_name)),
+                    Some(&format!("Held by {holder}")),
+                    Style::new().red(),
+                    frame,
+                    area,
+                    &mut table_state,
+                );
+            }
+            Popup::LargePastePrompt(send) => {
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+                let what = match send {
+                    LargeSend::Paste(_) => "pasted",
+                    LargeSend::ComposedLines(_) => "composed",
+                };
+                LargePastePrompt::render_prompt_block_popup(
+                    Some(&format!("Send {} {what} bytes?", send.len())),
+                    Some(&format!("{} lines", send.line_count())),
+                    Style::new().red(),
+                    frame,
+                    area,
+                    &mut table_state,
+                );
+            }
+            Popup::IgnoreByName(name) => {
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+                IgnorePortByNamePrompt::render_prompt_block_popup(
+                    Some(&format!("Hide {name} from Port Selection?")),
+                    Some(name),
+                    Style::new().yellow(),
+                    frame,
+                    area,
+                    &mut table_state,
+                );
             }
-            Popup::DisconnectPrompt => {
-                let port_state = { self.serial.port_status.load().inner };
-                let reconns_paused = if self.settings.serial.reconnections.allowed()
-                    && port_state.is_premature_disconnect()
-                {
-                    Some("(Auto-reconnections paused while open)")
-                } else {
-                    None
-                };
+            #[cfg(feature = "macros")]
+            Popup::MacroEditor => self.macros.editor.render(frame, area),
+            #[cfg(feature = "macros")]
+            Popup::MacroVariables => {
+                if let Some(form) = &self.macros.variables_form {
+                    form.render(frame, area);
+                }
+            }
+            #[cfg(feature = "macros")]
+            Popup::MacroPreview => {
+                if let Some(preview) = &self.macros.previewing {
+                    preview.render(frame, area, &mut self.popup_menu_scroll);
+                }
+            }
+            #[cfg(feature = "macros")]
+            Popup::DeleteMacroPrompt(tag) => {
                 let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
-                DisconnectPrompt::render_prompt_block_popup(
-                    Some("Disconnect from port?"),
-                    reconns_paused,
-                    Style::new().blue(),
+                DeleteMacroPrompt::render_prompt_block_popup(
+                    Some("Delete macro?"),
+                    Some(&tag.to_string()),
+                    Style::new().green(),
                     frame,
                     area,
                     &mut table_state,
                 );
             }
-            Popup::IgnoreByUsb(name, usb) => {
-                let UsbPortInfo {
-                    vid,
-                    pid,
-                    serial_number,
-                    ..
-                } = usb;
+            #[cfg(feature = "macros")]
+            Popup::ConfirmMacroPrompt(tag) => {
                 let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
-                let mut info = format!("VID: {vid:04X}, PID: {pid:04X}, Serial #: ");
-                if let Some(serial_num) = serial_number {
-                    info.push_str(serial_num);
-                } else {
-                    info.push_str("N/A");
-                }
-                IgnoreUsbDevicePrompt::render_prompt_block_popup(
-                    Some(&format!("Hide {name} (USB) from Port Selection?")),
-                    Some(&info),
+                ConfirmMacroPrompt::render_prompt_block_popup(
+                    Some("Send macro?"),
+                    Some(&tag.to_string()),
                     Style::new().yellow(),
                     frame,
                     area,
                     &mut table_state,
                 );
             }
-            Popup::IgnoreByName(name) => {
+            #[cfg(feature = "macros")]
+            Popup::ImportMacroConflict => {
+                let conflict = self
+                    .macro_import
+                    .as_ref()
+                    .and_then(|import| import.conflict.as_ref())
+                    .map(|(tag, _)| tag.to_string())
+                    .unwrap_or_default();
                 let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
-                IgnorePortByNamePrompt::render_prompt_block_popup(
-                    Some(&format!("Hide {name} from Port Selection?")),
-                    Some(name),
+                ImportMacroConflictPrompt::render_prompt_block_popup(
+                    Some("Imported macro already exists!"),
+                    Some(&conflict),
                     Style::new().yellow(),
                     frame,
                     area,
@@ -3294,6 +6631,54 @@ impl App {
                 frame.render_widget(&block, area);
                 frame.render_widget(para, block.inner(area));
             }
+            Popup::RevertSettingsPrompt(menu) => {
+                let choice = RevertSettingsPrompt::try_from(self.popup_menu_scroll as u8)
+                    .unwrap_or(RevertSettingsPrompt::Cancel);
+                let change_lines: Vec<Line> = match self.settings_after_revert(menu, &choice) {
+                    Ok((_, changes)) if changes.is_empty() => {
+                        vec![Line::styled("Nothing will change.", theme().dim_style())]
+                    }
+                    Ok((_, changes)) => changes
+                        .iter()
+                        .map(|change| Line::raw(change.to_string()))
+                        .collect(),
+                    Err(e) => vec![Line::raw(format!("Failed comparing settings! {e}")).red()],
+                };
+
+                const MAX_LISTED_CHANGES: usize = 12;
+                let prompt_height = <RevertSettingsPrompt as VariantNames>::VARIANTS.len() as u16;
+                let width = change_lines
+                    .iter()
+                    .map(Line::width)
+                    .max()
+                    .unwrap_or_default()
+                    .max(32) as u16
+                    + 2;
+                let height = prompt_height + change_lines.len().min(MAX_LISTED_CHANGES) as u16 + 3;
+                let popup_area = centered_rect_size(Size { width, height }, area);
+
+                let block = Block::bordered()
+                    .border_style(Style::new().red())
+                    .title_top(Line::raw(" Revert settings? ").centered().reset());
+                let block = if change_lines.len() > MAX_LISTED_CHANGES {
+                    let hidden = change_lines.len() - MAX_LISTED_CHANGES;
+                    block.title_bottom(Line::raw(format!(" +{hidden} more ")).centered().reset())
+                } else {
+                    block
+                };
+                let [prompt_area, _, changes_area] =
+                    vertical![==prompt_height, ==1, *=1].areas(block.inner(popup_area));
+
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+                frame.render_widget(Clear, popup_area);
+                frame.render_widget(block, popup_area);
+                frame.render_stateful_widget(
+                    RevertSettingsPrompt::prompt_table(),
+                    prompt_area,
+                    &mut table_state,
+                );
+                frame.render_widget(Paragraph::new(change_lines), changes_area);
+            }
             Popup::UpdateCheckConsentPrompt => {
                 let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
 
@@ -3311,15 +6696,61 @@ impl App {
 
                 let current_version = env!("CARGO_PKG_VERSION");
                 let new_version = self.update_found_version.as_ref().unwrap();
+                let channel = semver::Version::parse(new_version)
+                    .map(|v| UpdateChannel::of(&v))
+                    .unwrap_or_default();
 
-                UpdateBeginPrompt::render_prompt_block_popup(
-                    Some(&format!("New version found! v{current_version}")),
-                    Some(&format!("v{current_version} -> v{new_version}")),
-                    Style::new().green(),
-                    frame,
-                    area,
-                    &mut table_state,
-                );
+                let mut note_lines = release_notes_lines(&self.update_found_notes);
+                if note_lines.is_empty() {
+                    UpdateBeginPrompt::render_prompt_block_popup(
+                        Some(&format!("New {channel} version found!")),
+                        Some(&format!("v{current_version} -> v{new_version}")),
+                        Style::new().green(),
+                        frame,
+                        area,
+                        &mut table_state,
+                    );
+                } else {
+                    const MAX_NOTE_LINES: usize = 16;
+                    const MAX_NOTE_WIDTH: usize = 76;
+                    if note_lines.len() > MAX_NOTE_LINES {
+                        note_lines.truncate(MAX_NOTE_LINES - 1);
+                        note_lines.push(Line::styled(
+                            "... (Open GitHub Repo to read the rest)",
+                            theme().dim_style(),
+                        ));
+                    }
+                    let prompt_height = <UpdateBeginPrompt as VariantNames>::VARIANTS.len() as u16;
+                    let width = note_lines
+                        .iter()
+                        .map(Line::width)
+                        .max()
+                        .unwrap_or_default()
+                        .clamp(40, MAX_NOTE_WIDTH) as u16
+                        + 4;
+                    let height = prompt_height + note_lines.len() as u16 + 3;
+                    let popup_area = centered_rect_size(Size { width, height }, area);
+
+                    let block = Block::bordered()
+                        .border_style(Style::new().green())
+                        .title_alignment(ratatui::layout::Alignment::Center)
+                        .title_top(format!("New {channel} version found!").reset())
+                        .title_bottom(format!("v{current_version} -> v{new_version}").reset());
+                    let [prompt_area, _, notes_area] =
+                        vertical![==prompt_height, ==1, *=1].areas(block.inner(popup_area));
+
+                    frame.render_widget(Clear, popup_area);
+                    frame.render_widget(block, popup_area);
+                    frame.render_stateful_widget(
+                        UpdateBeginPrompt::prompt_table(),
+                        prompt_area,
+                        &mut table_state,
+                    );
+                    frame.render_widget(
+                        Paragraph::new(note_lines),
+                        notes_area.inner(Margin::new(1, 0)),
+                    );
+                }
             }
             #[cfg(all(windows, feature = "self-replace"))]
             Popup::UpdateLaunchPrompt => {
@@ -3335,6 +6766,19 @@ impl App {
                 );
             }
             #[cfg(feature = "self-replace")]
+            Popup::UpdateFromArchivePrompt(archive) => {
+                let mut table_state = TableState::new().with_selected(Some(self.popup_menu_scroll));
+
+                UpdateFromArchivePrompt::render_prompt_block_popup(
+                    Some("Update from archive?"),
+                    Some(archive.file_name().unwrap_or(archive.as_str())),
+                    Style::new().yellow(),
+                    frame,
+                    area,
+                    &mut table_state,
+                );
+            }
+            #[cfg(feature = "self-replace")]
             Popup::UpdateDownloading(percentage) => {
                 let center_area = centered_rect_size(
                     Size {
@@ -3448,6 +6892,53 @@ impl App {
             _ => unreachable!("popup isnt a settings or tool menu"),
         }
     }
+    /// Draws the field editor over the value of the row being edited, if it's open.
+    fn render_settings_field_editor(
+        &self,
+        frame: &mut Frame,
+        table_area: Rect,
+        table_state: &TableState,
+        layout: TableLayout,
+        hint_area: Rect,
+    ) {
+        let Some(editor) = &self.settings_field_editor else {
+            return;
+        };
+        let Some(row) = editor.row.checked_sub(table_state.offset()) else {
+            return;
+        };
+        if row >= table_area.height as usize {
+            return;
+        }
+
+        // Same spacing as the table's default, to line up with the value column.
+        let [_, mut input_area] = Layout::horizontal(layout.widths)
+            .spacing(1)
+            .areas(table_area);
+        input_area.y += row as u16;
+        input_area.height = 1;
+        frame.render_widget(Clear, input_area);
+
+        let width = input_area.width.max(1).saturating_sub(1);
+        let scroll = editor.input.visual_scroll(width as usize);
+        let input_text = Paragraph::new(editor.input.value())
+            .scroll((0, scroll as u16))
+            .style(theme().selection_style())
+            .italic();
+        frame.render_widget(input_text, input_area);
+        frame.set_cursor_position((
+            input_area.x + (editor.input.visual_cursor().max(scroll) - scroll) as u16,
+            input_area.y,
+        ));
+
+        frame.render_widget(Clear, hint_area);
+        frame.render_widget(
+            Line::raw("Enter: Apply | Esc: Cancel")
+                .all_spans_styled(theme().dim_style())
+                .centered(),
+            hint_area,
+        );
+    }
     fn render_settings_popup(
         &mut self,
         frame: &mut Frame,
@@ -3534,6 +7025,13 @@ impl App {
             .end_symbol(Some("↓"));
 
         let height = settings_area.height;
+        #[cfg(feature = "defmt")]
+        let defmt_settings_area = {
+            let mut area = center_inner_area;
+            area.y = area.top().saturating_add(6);
+            area.height = area.height.saturating_sub(8);
+            area
+        };
 
         match popup {
             SettingsMenu::SerialPort => {
@@ -3545,7 +7043,8 @@ impl App {
 
                 // If Baud Rate is selected, render using the normal Input method
                 if let Some(0) = self.get_corrected_popup_index() {
-                    let [_, mut input_area] = horizontal![==50%, ==50%].areas(settings_area);
+                    let [_, mut input_area] =
+                        Layout::horizontal(PortSettings::LAYOUT.widths).areas(settings_area);
                     input_area.height = 1;
                     frame.render_widget(Clear, input_area);
 
@@ -3565,7 +7064,7 @@ impl App {
                     let scroll = self.baud_input.visual_scroll(width as usize);
                     let input_text = Paragraph::new(baud_line)
                         .scroll((0, scroll as u16))
-                        .reversed()
+                        .style(theme().selection_style())
                         .italic();
 
                     frame.render_widget(input_text, input_area);
@@ -3580,6 +7079,7 @@ impl App {
 
                 let text: &str = table_state
                     .selected()
+                    .and_then(|row| self.scratch.serial.field_index_of_row(row))
                     .map(|i| PortSettings::DOCSTRINGS[i])
                     .unwrap_or("");
                 render_scrolling_line(
@@ -3589,8 +7089,8 @@ impl App {
                     &mut self.popup_hint_scroll,
                 );
                 frame.render_widget(
-                    Line::raw("Esc: Cancel | Enter: Save")
-                        .all_spans_styled(Color::DarkGray.into())
+                    Line::raw("Esc: Cancel | Enter: Save | Ctrl+D: Revert | Ctrl+F: Search")
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     button_hint_text_area,
                 );
@@ -3603,6 +7103,7 @@ impl App {
                 );
                 let text: &str = table_state
                     .selected()
+                    .and_then(|row| self.scratch.behavior.field_index_of_row(row))
                     .map(|i| Behavior::DOCSTRINGS[i])
                     .unwrap_or("");
                 render_scrolling_line(
@@ -3612,8 +7113,8 @@ impl App {
                     &mut self.popup_hint_scroll,
                 );
                 frame.render_widget(
-                    Line::raw("Esc: Cancel | Enter: Save")
-                        .all_spans_styled(Color::DarkGray.into())
+                    Line::raw("Esc: Cancel | Enter: Save | Ctrl+D: Revert | Ctrl+F: Search")
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     button_hint_text_area,
                 );
@@ -3626,6 +7127,7 @@ impl App {
                 );
                 let text: &str = table_state
                     .selected()
+                    .and_then(|row| self.scratch.rendering.field_index_of_row(row))
                     .map(|i| Rendering::DOCSTRINGS[i])
                     .unwrap_or("");
                 render_scrolling_line(
@@ -3635,8 +7137,8 @@ impl App {
                     &mut self.popup_hint_scroll,
                 );
                 frame.render_widget(
-                    Line::raw("Esc: Cancel | Enter: Save")
-                        .all_spans_styled(Color::DarkGray.into())
+                    Line::raw("Esc: Cancel | Enter: Save | Ctrl+D: Revert | Ctrl+F: Search")
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     button_hint_text_area,
                 );
@@ -3667,7 +7169,7 @@ impl App {
                 let logs_dir = config_adjacent_path("logs/");
                 let log_path_text = format!("Saving to: {logs_dir}");
                 let log_path_line = Line::raw(log_path_text)
-                    .all_spans_styled(Color::DarkGray.into())
+                    .all_spans_styled(theme().dim_style())
                     .centered();
 
                 if log_path_line.width() <= bottom_sep_line_area.width as usize {
@@ -3677,15 +7179,15 @@ impl App {
                 }
 
                 frame.render_widget(
-                    Line::raw("Esc: Close | Enter: Select/Save")
-                        .all_spans_styled(Color::DarkGray.into())
+                    Line::raw("Esc: Close | Enter: Select/Save | Ctrl+D: Revert | Ctrl+F: Search")
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     button_hint_text_area,
                 );
 
                 let sync_button = sync_logs_button();
-                let log_sync_selected =
-                    self.popup_menu_scroll == POPUP_MENU_SELECTOR_COUNT + Logging::VISIBLE_FIELDS;
+                let log_sync_selected = self.popup_menu_scroll
+                    == POPUP_MENU_SELECTOR_COUNT + self.scratch.logging.shown_rows();
                 if log_sync_selected {
                     frame.render_stateful_widget(sync_button, button_area, &mut table_state);
                     frame.render_widget(&line_block, new_separator);
@@ -3710,6 +7212,7 @@ impl App {
 
                     let text: &str = table_state
                         .selected()
+                        .and_then(|row| self.scratch.logging.field_index_of_row(row))
                         .map(|i| Logging::DOCSTRINGS[i])
                         .unwrap_or("");
                     render_scrolling_line(
@@ -3728,18 +7231,12 @@ impl App {
                     area.height = 1;
                     area
                 };
-                let defmt_settings_area = {
-                    let mut area = center_inner_area;
-                    area.y = area.top().saturating_add(6);
-                    area.height = area.height.saturating_sub(8);
-                    area
-                };
                 let line_block = Block::new()
                     .borders(Borders::TOP)
                     .border_style(Style::from(block_color));
                 frame.render_widget(
                     Line::raw("Powered by knurling-rs/defmt v1.0.0!")
-                        .all_spans_styled(Color::DarkGray.into())
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     bottom_sep_line_area,
                 );
@@ -3753,8 +7250,8 @@ impl App {
                 ] = vertical![==1,==1,==1,==1,*=1].areas(settings_area);
 
                 frame.render_widget(
-                    Line::raw("Esc: Close | Enter: Select/Save")
-                        .all_spans_styled(Color::DarkGray.into())
+                    Line::raw("Esc: Close | Enter: Select/Save | Ctrl+D: Revert | Ctrl+F: Search")
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     button_hint_text_area,
                 );
@@ -3771,19 +7268,28 @@ impl App {
                 };
 
                 let select_style = if self.popup_menu_scroll == 2 {
-                    Style::new().reversed()
+                    theme().selection_style()
                 } else {
                     Style::new()
                 };
                 let recent_style = if self.popup_menu_scroll == 3 {
-                    Style::new().reversed()
+                    theme().selection_style()
                 } else {
                     Style::new()
                 };
 
                 let current_elf_text = if let Some(decoder) = &self.buffer.defmt_decoder {
+                    let extras = match self.buffer.extra_defmt_decoders.len() {
+                        0 => String::new(),
+                        count => format!(" (+{count} extra)"),
+                    };
+                    let mismatch = if self.buffer.defmt_elf_mismatch().is_some() {
+                        " [Device Mismatch!]"
+                    } else {
+                        ""
+                    };
                     Cow::Owned(format!(
-                        "Current ELF MD5: {}",
+                        "Current ELF MD5: {}{extras}{mismatch}",
                         &decoder.elf_md5.as_str()[..8]
                     ))
                 } else {
@@ -3791,7 +7297,9 @@ impl App {
                 };
 
                 frame.render_widget(
-                    Line::raw(current_elf_text).centered().dark_gray(),
+                    Line::raw(current_elf_text)
+                        .centered()
+                        .style(theme().dim_style()),
                     elf_title,
                 );
                 frame.render_widget(Line::raw(current_elf_str).centered(), current_elf);
@@ -3818,6 +7326,7 @@ impl App {
                     );
                     let text: &str = table_state
                         .selected()
+                        .and_then(|row| self.scratch.defmt.field_index_of_row(row))
                         .map(|i| Defmt::DOCSTRINGS[i])
                         .unwrap_or("");
                     render_scrolling_line(
@@ -3848,13 +7357,44 @@ impl App {
                 }
                 frame.render_widget(
                     Line::raw("Settings:")
-                        .all_spans_styled(Color::DarkGray.into())
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     new_separator,
                 );
             }
         }
 
+        // Where the menu's table was drawn, for placing the field editor over it.
+        let field_table_area = match popup {
+            #[cfg(feature = "logging")]
+            // Above the separator and sync button.
+            SettingsMenu::Logging => {
+                let mut area = settings_area;
+                area.height = area.height.saturating_sub(2);
+                area
+            }
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => defmt_settings_area,
+            _ => settings_area,
+        };
+        self.settings_table_area = Some((field_table_area, table_state.offset()));
+        let layout = match popup {
+            SettingsMenu::SerialPort => PortSettings::LAYOUT,
+            SettingsMenu::Behavior => Behavior::LAYOUT,
+            SettingsMenu::Rendering => Rendering::LAYOUT,
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => Logging::LAYOUT,
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => Defmt::LAYOUT,
+        };
+        self.render_settings_field_editor(
+            frame,
+            field_table_area,
+            &table_state,
+            layout,
+            button_hint_text_area,
+        );
+
         let content_length = self.current_popup_selectable_item_count();
         let mut scrollbar_state = ScrollbarState::new(
             content_length
@@ -4000,7 +7540,7 @@ impl App {
                         .copied()
                         .map(String::from)
                         .map(Line::raw)
-                        .chain(self.macros.categories().map(String::from).map(Line::raw));
+                        .chain(self.macros.category_labels().map(Line::raw));
                     let categories_selector = SingleLineSelector::new(categories_iter)
                         .with_next_symbol(">")
                         .with_prev_symbol("<")
@@ -4048,16 +7588,23 @@ impl App {
                 frame.render_stateful_widget(table, macros_table_area, &mut table_state);
 
                 frame.render_widget(
-                    Line::raw("Ctrl-R: Reload")
-                        .all_spans_styled(Color::DarkGray.into())
-                        .centered(),
+                    Line::raw(
+                        "Ctrl-N: New | Ctrl-E: Edit | Ctrl-D: Del. | Ctrl-W: Preview | Ctrl-P: Pin | Ctrl-S: Export | Ctrl-O: Import | Ctrl-R: Reload",
+                    )
+                    .all_spans_styled(theme().dim_style())
+                    .centered(),
                     line_area,
                 );
 
+                let enter_hint = if self.popup_menu_scroll == 2 {
+                    "Esc: Close | Enter: Expand/Collapse"
+                } else {
+                    "Esc: Close | Enter: Send"
+                };
                 frame.render_widget(
-                    Line::raw("Esc: Close | Enter: Send")
-                        .all_spans_styled(Color::DarkGray.into())
-                        .centered(), // .dark_gray()
+                    Line::raw(enter_hint)
+                        .all_spans_styled(theme().dim_style())
+                        .centered(),
                     hint_text_area,
                 );
 
@@ -4065,7 +7612,10 @@ impl App {
                     let (_, content) = self.macros.filtered_macro_iter().nth(index).unwrap();
                     // for now i guess
                     // TOOD replace with fancy line preview
-                    let macro_preview = content.as_str();
+                    let macro_preview = match &content.content_file {
+                        Some(file) => Cow::Owned(format!("[File: {file}]")),
+                        None => Cow::Borrowed(content.as_str()),
+                    };
                     let line = if !content.sensitive {
                         use ratatui::text::ToLine;
 
@@ -4115,14 +7665,14 @@ impl App {
                     .border_style(Style::from(block_color));
                 frame.render_widget(
                     Line::raw("Powered by esp-rs/espflash v4.0.1!")
-                        .all_spans_styled(Color::DarkGray.into())
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     line_area,
                 );
 
                 frame.render_widget(
                     Line::raw("Esc: Close | Enter: Select")
-                        .all_spans_styled(Color::DarkGray.into())
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     hint_text_area,
                 );
@@ -4218,7 +7768,7 @@ impl App {
                 }
                 frame.render_widget(
                     Line::raw("Flash Profiles | Ctrl-R: Reload")
-                        .all_spans_styled(Color::DarkGray.into())
+                        .all_spans_styled(theme().dim_style())
                         .centered(),
                     new_separator,
                 );
@@ -4247,10 +7797,16 @@ impl App {
     }
 
     /// The main screen rendered when connected to a serial device.
-    pub fn terminal_menu(&mut self, frame: &mut Frame) {
-        let area = frame.area();
+    pub fn terminal_menu(&mut self, frame: &mut Frame, area: Rect) {
         let popup_shown = self.popup.is_some();
-        let [terminal_area, line_area, whole_input_area] = vertical![*=1, ==1, ==1].areas(area);
+        let hotbar_height = u16::from(self.macro_hotbar_shown());
+        let [terminal_area, line_area, whole_input_area, hotbar_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(hotbar_height),
+        ])
+        .areas(area);
         let [input_symbol_area, input_area] = horizontal![==1, *=1].areas(whole_input_area);
 
         // let start = Instant::now();
@@ -4259,6 +7815,7 @@ impl App {
         } else {
             frame.render_widget(&mut self.buffer, terminal_area);
         }
+        self.render_macro_hotbar(frame, hotbar_area);
         // debug!("1: {:?}", start.elapsed());
         // let start = Instant::now();
 
@@ -4287,13 +7844,32 @@ impl App {
         };
 
         repeating_pattern_widget(frame, line_area, self.repeating_line_flip, port_state);
+        if self.bell_flash_at.is_some() {
+            frame
+                .buffer_mut()
+                .set_style(line_area, theme().selection_style());
+        }
 
         let widget_margin: u16 = if area.width >= 100 { 3 } else { 0 };
 
+        let bells_text = match self.buffer.bells_rung() {
+            0 => String::new(),
+            bells => format!(" | Bells: {bells}"),
+        };
+
+        #[cfg(feature = "logging")]
+        let logging_paused_text = if self.buffer.log_handle.is_paused() {
+            " | Logging Paused"
+        } else {
+            ""
+        };
+        #[cfg(not(feature = "logging"))]
+        let logging_paused_text = "";
+
         #[cfg(debug_assertions)]
         {
             let line = Line::raw(format!(
-                "Entries: {} | Lines: {}",
+                "Entries: {} | Lines: {}{bells_text}{logging_paused_text}",
                 self.buffer.port_lines_len(),
                 self.buffer.combined_height()
             ))
@@ -4309,8 +7885,11 @@ impl App {
 
         #[cfg(not(debug_assertions))]
         {
-            let line =
-                Line::raw(format!("Lines: {}", self.buffer.port_lines_len())).right_aligned();
+            let line = Line::raw(format!(
+                "Lines: {}{bells_text}{logging_paused_text}",
+                self.buffer.port_lines_len()
+            ))
+            .right_aligned();
             frame.render_widget(
                 line,
                 line_area.inner(Margin {
@@ -4320,14 +7899,22 @@ impl App {
             );
         }
 
-        if self.action_queue.is_empty() {
-            let port_name_line = Line::raw(port_text).centered();
-            frame.render_widget(port_name_line, line_area);
+        #[cfg(feature = "logging")]
+        let replay_line = self.replay.as_ref().map(Replay::status_line);
+        #[cfg(not(feature = "logging"))]
+        let replay_line = None;
+
+        // A repeating macro's own sequence steps would otherwise hide it.
+        let center_line = if let Some(replay_line) = replay_line {
+            replay_line
+        } else if let Some(macro_line) = self.macro_status_line() {
+            macro_line
+        } else if !self.action_queue.is_empty() {
+            Line::raw(format!("Queued Actions: {}", self.action_queue.len()))
         } else {
-            let port_name_line =
-                Line::raw(format!("Queued Actions: {}", self.action_queue.len())).centered();
-            frame.render_widget(port_name_line, line_area);
-        }
+            Line::raw(port_text)
+        };
+        frame.render_widget(center_line.centered(), line_area);
 
         {
             let reversed_if_true = |signal: bool| -> Modifier {
@@ -4375,20 +7962,35 @@ impl App {
             );
         }
 
-        let input_style = match (&self.failed_send_at, self.text_input.all_text_selected) {
-            (Some(instant), _) if instant.elapsed() < FAILED_SEND_VISUAL_TIME => {
-                Style::new().on_red()
-            }
-            (Some(instant), true) if instant.elapsed() < FAILED_SEND_VISUAL_TIME => {
-                Style::new().reversed().on_red()
-            }
-            (_, true) => Style::new().reversed(),
-            _ => Style::new(),
+        if self.navigation.active {
+            self.navigation
+                .render(frame, whole_input_area, !popup_shown);
+            return;
+        }
+
+        let failed_send = self
+            .failed_send_at
+            .is_some_and(|instant| instant.elapsed() < FAILED_SEND_VISUAL_TIME);
+        let failed_send_style = if failed_send {
+            Style::new().on_red()
+        } else {
+            Style::new()
+        };
+        let input_style = if self.text_input.all_text_selected && !failed_send {
+            theme().selection_style()
+        } else {
+            failed_send_style
         };
 
+        if let Some(completions) = &self.completions
+            && !popup_shown
+        {
+            completions.render(frame, terminal_area, input_area.x);
+        }
+
         if self.settings.behavior.pseudo_shell {
             let input_symbol_style = if port_state.is_connected() {
-                input_style.not_reversed().green()
+                failed_send_style.green()
             } else {
                 input_style.red()
             };
@@ -4414,14 +8016,50 @@ impl App {
                         .collect(),
                 )
             };
-            let dark_gray = Style::new().dark_gray();
-            let line = line![span!(dark_gray; "Last sent: "), span!(dark_gray; value)];
+            let dim = theme().dim_style();
+            let line = line![span!(dim; "Last sent: "), span!(dim; value)];
 
             frame.render_widget(line, whole_input_area);
         }
 
         let should_position_cursor = !popup_shown;
 
+        if self.settings.behavior.pseudo_shell
+            && let Some(search) = self.text_input.reverse_search()
+        {
+            let dim = theme().dim_style();
+            let label = if search.failing {
+                "(failing reverse-i-search)`"
+            } else {
+                "(reverse-i-search)`"
+            };
+            let query_end = (Span::raw(label).width() + Span::raw(&search.query).width()) as u16;
+            let prefix = line![
+                span!(dim; label),
+                Span::raw(search.query.as_str()),
+                span!(dim; "': "),
+            ];
+            let [prefix_area, match_area] = Layout::horizontal([
+                Constraint::Length(prefix.width() as u16),
+                Constraint::Fill(1),
+            ])
+            .areas(input_area);
+
+            frame.render_widget(prefix, prefix_area);
+            frame.render_widget(
+                Line::raw(self.text_input.value()).style(input_style),
+                match_area,
+            );
+            if should_position_cursor {
+                // Cursor stays on the query, since that's what's being typed into.
+                frame.set_cursor_position((
+                    (input_area.x + query_end).min(input_area.right().saturating_sub(1)),
+                    input_area.y,
+                ));
+            }
+            return;
+        }
+
         match (
             self.settings.behavior.pseudo_shell,
             self.text_input.value().is_empty(),
@@ -4438,7 +8076,7 @@ impl App {
                     " {input_type} `{port_settings_hint}` for port settings.",
                 ))
                 .style(input_style)
-                .dark_gray()
+                .fg(theme().dim)
                 .italic();
                 frame.render_widget(input_hint, input_area);
                 if should_position_cursor {
@@ -4483,11 +8121,11 @@ impl App {
 
                 let scroll = visual_scroll(adj_cursor, width as usize, chars_iter) as u16;
 
-                frame.render_widget(input_para.scroll((0, scroll as u16)), input_area);
+                frame.render_widget(input_para.scroll((0, scroll)), input_area);
                 if should_position_cursor {
                     frame.set_cursor_position((
                         // Put cursor past the end of the input text
-                        input_area.x + ((adj_cursor as u16).max(scroll) - scroll) as u16,
+                        input_area.x + (adj_cursor as u16).max(scroll) - scroll,
                         input_area.y,
                     ));
                 }
@@ -4513,14 +8151,14 @@ impl App {
                 let input_hint =
                     Line::raw("Escaping next keypress, will not be sent to connected device.")
                         .style(input_style)
-                        .yellow()
+                        .fg(theme().highlight)
                         .centered();
                 frame.render_widget(input_hint, whole_input_area);
             }
             (false, _) if self.popup.is_some() => {
                 let input_hint = Line::raw("Popup is active, not sending keypresses.")
                     .style(input_style)
-                    .dark_gray()
+                    .fg(theme().dim)
                     .centered();
                 frame.render_widget(input_hint, whole_input_area);
             }
@@ -4530,7 +8168,7 @@ impl App {
                     "Keyboard input is being sent directly. Press `{escape_hint}` to escape a keypress.",
                 ))
                 .style(input_style)
-                .dark_gray()
+                .fg(theme().dim)
                 .centered();
                 frame.render_widget(input_hint, whole_input_area);
             }
@@ -4539,8 +8177,7 @@ impl App {
         // debug!("2: {:?}", start.elapsed());
     }
 
-    fn port_selection(&mut self, frame: &mut Frame) {
-        let frame_area = frame.area();
+    fn port_selection(&mut self, frame: &mut Frame, frame_area: Rect) {
         let vertical_slices = Layout::vertical([
             Constraint::Fill(1),
             Constraint::Fill(4),
@@ -4556,12 +8193,12 @@ impl App {
             .build();
         frame.render_widget(big_text, vertical_slices[0]);
 
-        let dark_gray = Style::new().dark_gray();
+        let dim = theme().dim_style();
         let green = Style::new().green();
 
         let [_, credit_and_version_area] = vertical![*=1, ==1].areas(frame_area);
 
-        let current_version = Span::styled(format!("v{}", env!("CARGO_PKG_VERSION")), dark_gray);
+        let current_version = Span::styled(format!("v{}", env!("CARGO_PKG_VERSION")), dim);
 
         let version = if let Some(new) = &self.update_found_version {
             let meow = Span::styled(format!(" -> v{new}! [U]pdate found!"), green);
@@ -4570,7 +8207,7 @@ impl App {
             Line::from(current_version)
         };
 
-        let me_in_current_year = "nullstalgia, 2025".dark_gray();
+        let me_in_current_year = Span::styled("nullstalgia, 2025", dim);
 
         frame.render_widget(me_in_current_year, credit_and_version_area);
         frame.render_widget(version.right_aligned(), credit_and_version_area);
@@ -4584,8 +8221,9 @@ impl App {
 
         let show_keybinds_hint = self.keybinds.show_keybinds_hint();
         let controls = line![
-            span!(dark_gray;"Ignore port: [I] | Show Keybinds: [{show_keybinds_hint}] | Select: [Enter]")
-        ].centered();
+            span!(dim;"Ignore port: [I] | Show Keybinds: [{show_keybinds_hint}] | Select: [Enter]")
+        ]
+        .centered();
 
         let block = Block::bordered()
             .title("Port Selection")
@@ -4628,7 +8266,7 @@ impl App {
         let widths = [Constraint::Percentage(25), Constraint::Percentage(75)];
 
         let table = Table::new(rows, widths)
-            .row_highlight_style(Style::new().reversed())
+            .row_highlight_style(theme().selection_style())
             .highlight_spacing(HighlightSpacing::Always)
             .highlight_symbol(">>");
 
@@ -4704,7 +8342,7 @@ impl App {
                 horizontal![*=1, ==10, *=1].areas(filler_or_custom_baud_entry);
 
             let style = if baud_selected {
-                Style::new().reversed()
+                theme().selection_style()
             } else {
                 Style::new()
             };
@@ -4741,7 +8379,7 @@ impl App {
 
         if options_selected {
             frame.render_widget(
-                Line::from(more_options_button.reversed()).centered(),
+                Line::from(more_options_button.style(theme().selection_style())).centered(),
                 more_options,
             );
         } else {
@@ -4785,12 +8423,12 @@ impl App {
                 "{bindings_with_unrecognized_actions} keybindings with unknown actions, {show_keybinds_hint} to see all bindings."
             ))
             .centered()
-            .yellow();
+            .fg(theme().highlight);
             misc_lines.push(line);
         } else {
             let line = Line::raw(format!("{show_keybinds_hint} to see all keybindings."))
                 .centered()
-                .dark_gray();
+                .fg(theme().dim);
             misc_lines.push(line);
         }
 
@@ -4802,19 +8440,356 @@ impl App {
                 socket_addr.to_string()
             ]
             .centered()
-            .dark_gray();
+            .fg(theme().dim);
             misc_lines.push(line);
         }
 
+        #[cfg(feature = "logging")]
+        if self.settings.logging.log_text_to_file
+            && self.settings.logging.forward_to != LogForwarding::Disabled
+        {
+            let address = &self.settings.logging.forward_address;
+            let line = if self.buffer.log_handle.forwarding_ok() {
+                line!["Forwarding log lines to: ", address.as_str()].style(theme().dim_style())
+            } else {
+                line!["Failed forwarding log lines to: ", address.as_str()].red()
+            };
+            misc_lines.push(line.centered());
+        }
+
         let config_path = config_adjacent_path("");
         let config_path_line = line!["Config and logs at: ", config_path.to_string()]
             .centered()
-            .dark_gray();
+            .fg(theme().dim);
         misc_lines.push(config_path_line);
 
-        // if !misc_lines.is_empty() {
-        frame.render_widget(Paragraph::new(misc_lines), vertical_slices[2]);
-        // }
+        // if !misc_lines.is_empty() {
+        frame.render_widget(Paragraph::new(misc_lines), vertical_slices[2]);
+        // }
+    }
+    /// Apply the named profile's sections to the current settings and save them.
+    fn switch_settings_profile(&mut self, name: &str) -> Result<()> {
+        self.push_settings_history();
+        let (name, profile) = self.settings_profiles.get(name)?;
+        let name = name.to_owned();
+        self.settings.apply_profile(&name, profile);
+
+        self.buffer
+            .update_line_ending(self.settings.serial.rx_line_ending.as_bytes());
+        self.serial.update_settings(self.settings.serial.clone())?;
+        self.buffer
+            .update_render_settings(self.settings.rendering.clone());
+        self.apply_theme();
+        self.apply_notification_filter();
+        #[cfg(feature = "defmt")]
+        self.buffer
+            .update_defmt_settings(self.settings.defmt.clone());
+
+        self.settings.save()?;
+        self.notifs.notify_str(
+            format!("Switched to settings profile: {name}"),
+            Severity::Success,
+        );
+        Ok(())
+    }
+    #[cfg(feature = "settings-watch")]
+    /// Apply outside edits to the settings file, ignoring writes that changed nothing (i.e. our own saves).
+    ///
+    /// Problems are only notified, a half-written file shouldn't close yap.
+    fn reload_settings_file(&mut self) {
+        let (reloaded, changes) = match self
+            .settings
+            .reload()
+            .and_then(|reloaded| Ok((self.settings.diff(&reloaded)?, reloaded)))
+        {
+            Ok((changes, reloaded)) => (reloaded, changes),
+            Err(e) => {
+                error!("Failed reloading settings: {e}");
+                self.notifs
+                    .notify_str(format!("Failed reloading settings! {e}"), Severity::Error);
+                return;
+            }
+        };
+        if changes.is_empty() {
+            return;
+        }
+        let changed: Vec<&str> = changes.iter().map(|change| change.field.as_str()).collect();
+
+        info!("Settings file changed: {}", changed.join(", "));
+        self.push_settings_history();
+        self.settings = reloaded;
+        // Otherwise a settings popup would save over the reloaded settings with what it had open.
+        let kept_edits = self.refresh_scratch_keeping_edits();
+
+        if let Err(e) = self.apply_settings_changes(&changes) {
+            error!("Failed applying reloaded settings: {e}");
+            self.notifs.notify_str(
+                format!("Failed applying reloaded settings! {e}"),
+                Severity::Error,
+            );
+            return;
+        }
+
+        match kept_edits {
+            Some(section)
+                if changes
+                    .iter()
+                    .any(|change| change.section() == section.as_ref()) =>
+            {
+                self.notifs.notify_str(
+                    format!(
+                        "Settings reloaded, kept unsaved {} edits: {}",
+                        section.as_ref(),
+                        SettingsChange::summarize(&changes)
+                    ),
+                    Severity::Warning,
+                );
+            }
+            _ => self.notifs.notify_str(
+                format!("Settings reloaded: {}", SettingsChange::summarize(&changes)),
+                Severity::Success,
+            ),
+        }
+    }
+    #[cfg(feature = "settings-watch")]
+    /// Take the current settings into the scratch copy, apart from the section
+    /// being edited in an open settings popup, which is returned.
+    fn refresh_scratch_keeping_edits(&mut self) -> Option<SettingsSection> {
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            self.refresh_scratch();
+            return None;
+        };
+        let section = menu.section();
+        let edited = std::mem::replace(&mut self.scratch, self.settings.clone());
+        match section {
+            SettingsSection::Serial => self.scratch.serial = edited.serial,
+            SettingsSection::Rendering => self.scratch.rendering = edited.rendering,
+            SettingsSection::Behavior => self.scratch.behavior = edited.behavior,
+            #[cfg(feature = "logging")]
+            SettingsSection::Logging => self.scratch.logging = edited.logging,
+            #[cfg(feature = "defmt")]
+            SettingsSection::Defmt => self.scratch.defmt = edited.defmt,
+        }
+        if section != SettingsSection::Serial {
+            self.baud_input = self.settings.serial.baud_rate.to_string().into();
+        }
+        Some(section)
+    }
+    /// Pass the already-changed settings along to whatever uses each changed section.
+    fn apply_settings_changes(&mut self, changes: &[SettingsChange]) -> Result<()> {
+        let section_changed = |section: SettingsSection| {
+            changes
+                .iter()
+                .any(|change| change.section() == section.as_ref())
+        };
+
+        if section_changed(SettingsSection::Serial) {
+            self.buffer
+                .update_line_ending(self.settings.serial.rx_line_ending.as_bytes());
+            self.serial.update_settings(self.settings.serial.clone())?;
+        }
+        if section_changed(SettingsSection::Rendering) {
+            self.buffer
+                .update_render_settings(self.settings.rendering.clone());
+            self.apply_theme();
+        }
+        if section_changed(SettingsSection::Behavior) {
+            self.apply_notification_filter();
+        }
+        #[cfg(feature = "defmt")]
+        if section_changed(SettingsSection::Defmt) {
+            self.buffer
+                .update_defmt_settings(self.settings.defmt.clone());
+        }
+        #[cfg(feature = "logging")]
+        if section_changed(SettingsSection::Logging) {
+            self.buffer
+                .update_logging_settings(self.settings.logging.clone())?;
+        }
+        Ok(())
+    }
+    /// What the settings would be after the chosen revert, and every field that would change.
+    fn settings_after_revert(
+        &self,
+        menu: &SettingsMenu,
+        choice: &RevertSettingsPrompt,
+    ) -> Result<(Settings, Vec<SettingsChange>), SettingsError> {
+        let mut reverted = self.settings.clone();
+        match choice {
+            RevertSettingsPrompt::RevertUnsavedChanges => {
+                let changes = self.scratch.diff(&self.settings)?;
+                return Ok((reverted, changes));
+            }
+            RevertSettingsPrompt::UndoLastChange => {
+                if let Some((index, changes)) = self.last_settings_change()? {
+                    return Ok((self.settings_history[index].clone(), changes));
+                }
+            }
+            RevertSettingsPrompt::ResetThisSection => reverted.reset_section(menu.section()),
+            RevertSettingsPrompt::ResetAllSettings => SettingsSection::VARIANTS
+                .iter()
+                .for_each(|section| reverted.reset_section(*section)),
+            RevertSettingsPrompt::Cancel => (),
+        }
+        let changes = self.settings.diff(&reverted)?;
+        Ok((reverted, changes))
+    }
+    fn revert_settings_prompt_choice(&mut self, choice: RevertSettingsPrompt) -> Result<()> {
+        let Some(Popup::RevertSettingsPrompt(menu)) = self.popup.take() else {
+            unreachable!("Can't revert settings without the menu they came from!");
+        };
+
+        match choice {
+            RevertSettingsPrompt::Cancel => {
+                let scratch = self.scratch.clone();
+                self.show_popup(Popup::SettingsMenu(menu));
+                // Back to the menu with its unsaved edits intact.
+                self.baud_input = scratch.serial.baud_rate.to_string().into();
+                self.scratch = scratch;
+                return Ok(());
+            }
+            RevertSettingsPrompt::RevertUnsavedChanges => {
+                let changes = self.scratch.diff(&self.settings)?;
+                if changes.is_empty() {
+                    self.notifs
+                        .notify_str("No unsaved changes to revert!", Severity::Warning);
+                } else {
+                    self.notifs.notify_str(
+                        format!("Reverted {} unsaved changes!", changes.len()),
+                        Severity::Success,
+                    );
+                }
+                // Showing the menu again refreshes the scratch settings, which does the reverting.
+            }
+            RevertSettingsPrompt::UndoLastChange => self.undo_settings_change()?,
+            RevertSettingsPrompt::ResetThisSection | RevertSettingsPrompt::ResetAllSettings => {
+                let (reset, changes) = self.settings_after_revert(&menu, &choice)?;
+                if changes.is_empty() {
+                    self.notifs
+                        .notify_str("Settings already at defaults!", Severity::Warning);
+                } else {
+                    self.push_settings_history();
+                    self.settings = reset;
+                    self.apply_settings_changes(&changes)?;
+                    self.settings.save()?;
+                    self.notifs.notify_str(
+                        format!("Reset {} settings to defaults!", changes.len()),
+                        Severity::Success,
+                    );
+                }
+            }
+        }
+        self.show_popup(Popup::SettingsMenu(menu));
+        Ok(())
+    }
+    /// Remember the settings as they are now, so the change about to be made to them can be undone.
+    fn push_settings_history(&mut self) {
+        if self.settings_history.len() >= SETTINGS_HISTORY_LEN {
+            self.settings_history.pop_front();
+        }
+        self.settings_history.push_back(self.settings.clone());
+    }
+    /// The most recent history entry that differs from the current settings, and how.
+    ///
+    /// Entries from saves that didn't change anything are skipped over.
+    fn last_settings_change(&self) -> Result<Option<(usize, Vec<SettingsChange>)>, SettingsError> {
+        for (index, previous) in self.settings_history.iter().enumerate().rev() {
+            let changes = self.settings.diff(previous)?;
+            if !changes.is_empty() {
+                return Ok(Some((index, changes)));
+            }
+        }
+        Ok(None)
+    }
+    fn undo_settings_change(&mut self) -> Result<()> {
+        let Some((index, changes)) = self.last_settings_change()? else {
+            self.notifs
+                .notify_str("No settings changes to undo!", Severity::Warning);
+            return Ok(());
+        };
+        self.settings_history.truncate(index + 1);
+        let previous = self
+            .settings_history
+            .pop_back()
+            .expect("history entry was just found");
+
+        let changed: Vec<&str> = changes.iter().map(|change| change.field.as_str()).collect();
+        info!("Undoing settings change: {}", changed.join(", "));
+        self.settings = previous;
+        self.apply_settings_changes(&changes)?;
+        self.settings.save()?;
+        self.refresh_scratch();
+
+        self.notifs.notify_str(
+            format!(
+                "Undid settings change: {}",
+                SettingsChange::summarize(&changes)
+            ),
+            Severity::Success,
+        );
+        Ok(())
+    }
+    /// Switch to the theme chosen in the Rendering settings, keeping the current one if it fails to load.
+    fn apply_theme(&mut self) {
+        let theme_path = config_adjacent_path(THEME_PATH);
+        match self.settings.rendering.theme.apply(theme_path) {
+            Ok(true) => self.buffer.restyle_for_theme(),
+            Ok(false) => (),
+            Err(e) => self
+                .notifs
+                .notify_str(format!("Failed loading theme! {e}"), Severity::Error),
+        }
+    }
+    /// Show or hold back notifications as the Behavior settings say to.
+    fn apply_notification_filter(&mut self) {
+        self.notifs.set_filter(
+            self.settings.behavior.notification_level,
+            self.settings.behavior.do_not_disturb,
+        );
+    }
+    /// Run the palette's selected entry, closing the palette first so anything it opens is seen.
+    fn run_palette_command(&mut self) -> Result<()> {
+        let Some(command) = self
+            .command_palette
+            .selected_entry()
+            .map(|entry| entry.command.clone())
+        else {
+            return Ok(());
+        };
+        self.dismiss_popup();
+        match command {
+            PaletteCommand::Action(name) => {
+                if let Some(actions) = self.actions_from_strings(&[name]) {
+                    self.queue_keybinds_action_set(actions, None)?;
+                }
+            }
+            #[cfg(feature = "macros")]
+            PaletteCommand::Macro(tag) => {
+                self.queue_keybinds_action_set(vec![Action::MacroInvocation(tag)], None)?;
+            }
+            PaletteCommand::Settings(menu) => self.show_popup(Popup::SettingsMenu(menu)),
+        }
+        Ok(())
+    }
+    /// Open the chosen search result's settings menu, with its row selected.
+    fn jump_to_searched_setting(&mut self) {
+        let Some(entry) = self.settings_search.selected_entry() else {
+            return;
+        };
+        let (menu, field_index) = (entry.menu.clone(), entry.field_index);
+        let first_field_row = menu.first_field_row();
+        self.show_popup(Popup::SettingsMenu(menu.clone()));
+        // Hidden rows above the field move it up in the table.
+        let row = match menu {
+            SettingsMenu::SerialPort => self.scratch.serial.row_of_field_index(field_index),
+            SettingsMenu::Behavior => self.scratch.behavior.row_of_field_index(field_index),
+            SettingsMenu::Rendering => self.scratch.rendering.row_of_field_index(field_index),
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => self.scratch.logging.row_of_field_index(field_index),
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => self.scratch.defmt.row_of_field_index(field_index),
+        };
+        self.popup_menu_scroll = first_field_row + row;
     }
     fn refresh_scratch(&mut self) {
         self.scratch = self.settings.clone();
@@ -4825,33 +8800,381 @@ impl App {
 
         self.baud_input = self.settings.serial.baud_rate.to_string().into();
     }
+    /// The field index of the selected settings row, counting any rows hidden from the table.
+    ///
+    /// Returns `None` if a menu selector or button is selected instead.
+    fn selected_setting_index(&self) -> Option<usize> {
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            return None;
+        };
+        if self.popup_menu_scroll < POPUP_MENU_SELECTOR_COUNT {
+            return None;
+        }
+        let row = self.get_corrected_popup_index()?;
+        match menu {
+            SettingsMenu::SerialPort => self.scratch.serial.field_index_of_row(row),
+            SettingsMenu::Behavior => self.scratch.behavior.field_index_of_row(row),
+            SettingsMenu::Rendering => self.scratch.rendering.field_index_of_row(row),
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => self.scratch.logging.field_index_of_row(row),
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => {
+                use crate::tui::defmt::DEFMT_BUTTONS;
+
+                if self.popup_menu_scroll < POPUP_MENU_SELECTOR_COUNT + DEFMT_BUTTONS {
+                    return None;
+                }
+                self.scratch.defmt.field_index_of_row(row)
+            }
+        }
+    }
+    /// The text to open the field editor with, if the selected settings field can be typed into.
+    fn selected_setting_edit_text(&self) -> Option<String> {
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            return None;
+        };
+        let index = self.selected_setting_index()?;
+        match menu {
+            SettingsMenu::SerialPort => self.scratch.serial.edit_text(index),
+            SettingsMenu::Behavior => self.scratch.behavior.edit_text(index),
+            SettingsMenu::Rendering => self.scratch.rendering.edit_text(index),
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => self.scratch.logging.edit_text(index),
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => self.scratch.defmt.edit_text(index),
+        }
+    }
+    /// If the selected settings row is a section header, which can't be selected.
+    fn section_header_selected(&self) -> bool {
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            return false;
+        };
+        let Some(index) = self.selected_setting_index() else {
+            return false;
+        };
+        match menu {
+            SettingsMenu::SerialPort => PortSettings::is_section_header(index),
+            SettingsMenu::Behavior => Behavior::is_section_header(index),
+            SettingsMenu::Rendering => Rendering::is_section_header(index),
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => Logging::is_section_header(index),
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => Defmt::is_section_header(index),
+        }
+    }
+    /// Cycle the selected settings field, letting the user know if its new value was refused.
+    ///
+    /// Returns the index of the field if it changed.
+    fn cycle_selected_setting(&mut self, input: ArrowKey) -> Option<usize> {
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            return None;
+        };
+        let index = self.selected_setting_index()?;
+        let result = match menu {
+            SettingsMenu::SerialPort => self.scratch.serial.handle_input(input, index),
+            SettingsMenu::Behavior => self.scratch.behavior.handle_input(input, index),
+            SettingsMenu::Rendering => self.scratch.rendering.handle_input(input, index),
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => self.scratch.logging.handle_input(input, index),
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => self.scratch.defmt.handle_input(input, index),
+        };
+        match result {
+            Ok(changed) => changed,
+            Err(FieldInputError::Rejected(reason)) => {
+                self.notifs.notify_str(reason, Severity::Warning);
+                None
+            }
+            Err(e @ FieldInputError::InvalidFieldIndex) => panic!("{e}"),
+        }
+    }
+    /// Copy the selected notification from the history, along with when it was shown.
+    fn copy_selected_notification(&mut self) {
+        let Some(text) = self
+            .notifs
+            .history()
+            .get(self.popup_menu_scroll)
+            .map(PastNotification::to_clipboard_text)
+        else {
+            return;
+        };
+        match self.text_input.clipboard.as_mut().map(|c| c.set_text(text)) {
+            Some(Ok(())) => self
+                .notifs
+                .notify_str("Copied notification to clipboard!", Severity::Success),
+            Some(Err(e)) => {
+                error!("error setting clipboard text: {e}");
+                self.notifs
+                    .notify_str("Failed to copy to clipboard!", Severity::Error);
+            }
+            None => self
+                .notifs
+                .notify_str("Clipboard not supported!", Severity::Error),
+        }
+    }
+    /// Put just the selected settings field back to its default, leaving the rest of the menu as-is.
+    fn reset_selected_setting(&mut self) {
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            return;
+        };
+        let Some(index) = self.selected_setting_index() else {
+            return;
+        };
+        let (result, name) = match menu {
+            SettingsMenu::SerialPort => (
+                self.scratch.serial.reset_field(index),
+                PortSettings::FIELD_NAMES[index],
+            ),
+            SettingsMenu::Behavior => (
+                self.scratch.behavior.reset_field(index),
+                Behavior::FIELD_NAMES[index],
+            ),
+            SettingsMenu::Rendering => (
+                self.scratch.rendering.reset_field(index),
+                Rendering::FIELD_NAMES[index],
+            ),
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => (
+                self.scratch.logging.reset_field(index),
+                Logging::FIELD_NAMES[index],
+            ),
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => (
+                self.scratch.defmt.reset_field(index),
+                Defmt::FIELD_NAMES[index],
+            ),
+        };
+        match result {
+            Ok(Some(changed)) => {
+                // Keeping the baud rate's text input in step with the reset value.
+                if changed == 0 && *menu == SettingsMenu::SerialPort {
+                    self.baud_input = self.scratch.serial.baud_rate.to_string().into();
+                }
+                self.notifs
+                    .notify_str(format!("Reset {name} to default!"), Severity::Success);
+            }
+            Ok(None) => {
+                self.notifs
+                    .notify_str(format!("{name} already at default!"), Severity::Warning);
+            }
+            Err(e) => panic!("{e}"),
+        }
+    }
+    /// Select settings rows by clicking them, cycling their value if it was clicked,
+    /// and move the selection with the scroll wheel.
+    fn handle_settings_mouse(&mut self, input: MouseInput, position: Position) {
+        if self.settings_field_editor.is_some() {
+            return;
+        }
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            return;
+        };
+        let Some((area, offset)) = self.settings_table_area else {
+            return;
+        };
+        let mouse = match input {
+            MouseInput::LeftClick => TableMouse::Click(position),
+            MouseInput::ScrollUp => TableMouse::ScrollUp,
+            MouseInput::ScrollDown => TableMouse::ScrollDown,
+            _ => return,
+        };
+
+        let menu = menu.clone();
+        let selected_row = self
+            .selected_setting_index()
+            .and(self.get_corrected_popup_index());
+        let mut table_state = TableState::new()
+            .with_offset(offset)
+            .with_selected(selected_row);
+        let result = match menu {
+            SettingsMenu::SerialPort => {
+                self.scratch
+                    .serial
+                    .handle_mouse(mouse, area, &mut table_state)
+            }
+            SettingsMenu::Behavior => {
+                self.scratch
+                    .behavior
+                    .handle_mouse(mouse, area, &mut table_state)
+            }
+            SettingsMenu::Rendering => {
+                self.scratch
+                    .rendering
+                    .handle_mouse(mouse, area, &mut table_state)
+            }
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => {
+                self.scratch
+                    .logging
+                    .handle_mouse(mouse, area, &mut table_state)
+            }
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => self
+                .scratch
+                .defmt
+                .handle_mouse(mouse, area, &mut table_state),
+        };
+
+        if let Some(row) = table_state.selected()
+            && table_state.selected() != selected_row
+        {
+            self.popup_menu_scroll = menu.first_field_row() + row;
+            self.popup_hint_scroll = -2;
+        }
+        match result {
+            // Keeping the baud rate's text input in step with the cycled value.
+            Ok(Some(0)) if menu == SettingsMenu::SerialPort => {
+                self.baud_input = self.scratch.serial.baud_rate.to_string().into();
+            }
+            Ok(_) => (),
+            Err(FieldInputError::Rejected(reason)) => {
+                self.notifs.notify_str(reason, Severity::Warning);
+            }
+            Err(e @ FieldInputError::InvalidFieldIndex) => panic!("{e}"),
+        }
+    }
+    /// Apply the field editor's text to its field, keeping the editor open if it's invalid.
+    fn apply_settings_field_editor(&mut self) {
+        let Some(editor) = self.settings_field_editor.take() else {
+            return;
+        };
+        let Some(Popup::SettingsMenu(menu)) = &self.popup else {
+            return;
+        };
+        let index = editor.field_index;
+        let text = editor.input.value();
+        let result: Result<Option<usize>, FieldParseError> = match menu {
+            SettingsMenu::SerialPort => self.scratch.serial.apply_text(index, text),
+            SettingsMenu::Behavior => self.scratch.behavior.apply_text(index, text),
+            SettingsMenu::Rendering => self.scratch.rendering.apply_text(index, text),
+            #[cfg(feature = "logging")]
+            SettingsMenu::Logging => self.scratch.logging.apply_text(index, text),
+            #[cfg(feature = "defmt")]
+            SettingsMenu::Defmt => self.scratch.defmt.apply_text(index, text),
+        };
+        if let Err(e) = result {
+            self.notifs
+                .notify_str(format!("Invalid value: {e}!"), Severity::Error);
+            self.settings_field_editor = Some(editor);
+        }
+    }
     fn show_popup(&mut self, popup: Popup) {
+        self.settings_field_editor = None;
         match &popup {
+            Popup::KeybindEditor => self.keybind_editor.state = KeybindEditorState::Browsing,
+            Popup::SettingsSearch => self.settings_search.reset(),
+            Popup::CommandPalette => {
+                let entries = PaletteEntry::builtin();
+                #[cfg(feature = "macros")]
+                let entries = entries
+                    .into_iter()
+                    .chain(
+                        self.macros
+                            .all
+                            .iter()
+                            .map(|(tag, content)| PaletteEntry::from_macro(tag, content)),
+                    )
+                    .collect();
+                self.command_palette.replace_entries(entries);
+            }
+            // Anything already typed is carried over, rather than left behind in the input box.
+            Popup::ComposeEditor if self.compose_editor.is_empty() => {
+                self.compose_editor.set_text(self.text_input.value());
+                self.text_input.clear();
+            }
+            Popup::SettingsProfiles => {
+                let profiles_dir = config_adjacent_path(SETTINGS_PROFILES_DIR_PATH);
+                match SettingsProfiles::load_from_folder(&profiles_dir) {
+                    Ok((profiles, errors)) => {
+                        self.settings_profiles = profiles;
+                        for e in errors {
+                            self.notifs.notify_str(
+                                format!("Skipped settings profile! {e}"),
+                                Severity::Warning,
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        self.notifs.notify_str(
+                            format!("Failed loading settings profiles! {e}"),
+                            Severity::Error,
+                        );
+                        return;
+                    }
+                }
+                if self.settings_profiles.is_empty() {
+                    self.notifs.notify_str(
+                        format!("No settings profiles found in {profiles_dir}!"),
+                        Severity::Error,
+                    );
+                    return;
+                }
+                self.popup_menu_scroll = 0;
+            }
+            Popup::NotificationHistory => {
+                if self.notifs.history().is_empty() {
+                    self.notifs
+                        .notify_str("No notifications yet!", Severity::Warning);
+                    return;
+                }
+                // Starting from the newest.
+                self.popup_menu_scroll = self.notifs.history().len() - 1;
+            }
+
             Popup::CurrentKeybinds
             | Popup::AttemptReconnectPrompt
             | Popup::DisconnectPrompt
             | Popup::IgnoreByName(_)
             | Popup::IgnoreByUsb(_, _)
+            | Popup::PortInUse(_, _)
+            | Popup::LargePastePrompt(_)
+            | Popup::RevertSettingsPrompt(_)
+            | Popup::SetupWizard
             | Popup::UpdateBeginPrompt
             | Popup::UpdateCheckConsentPrompt => self.popup_menu_scroll = 0,
 
+            #[cfg(feature = "self-replace")]
+            Popup::UpdateFromArchivePrompt(_) => self.popup_menu_scroll = 0,
+
+            #[cfg(feature = "macros")]
+            Popup::MacroEditor
+            | Popup::MacroVariables
+            | Popup::MacroPreview
+            | Popup::DeleteMacroPrompt(_)
+            | Popup::ConfirmMacroPrompt(_)
+            | Popup::ImportMacroConflict => self.popup_menu_scroll = 0,
+
             #[cfg(feature = "defmt")]
             Popup::DefmtRecentElf => {
                 if self.defmt_helpers.recent_elfs.is_empty() {
                     self.notifs.notify_str(
                         "No recent ELFs to select from! Try loading some!",
-                        Color::Red,
+                        Severity::Error,
                     );
                     return;
                 } else {
                     self.popup_menu_scroll = 0
                 }
             }
+            #[cfg(feature = "defmt")]
+            Popup::DefmtStats | Popup::DefmtFoundElf(_) | Popup::DefmtFailedFrame(_) => {
+                self.popup_menu_scroll = 0
+            }
+            #[cfg(feature = "defmt")]
+            Popup::DefmtModuleFilter => {
+                self.popup_menu_scroll = 0;
+                self.defmt_helpers.module_filter_input =
+                    self.settings.defmt.module_filter.clone().into();
+            }
             _ => self.popup_menu_scroll = 1,
         }
 
+        // Kept for the revert prompt, so unsaved edits can be looked over and reverted (or not).
+        let keep_scratch = matches!(popup, Popup::RevertSettingsPrompt(_));
         self.popup = Some(popup);
-        self.refresh_scratch();
+        if !keep_scratch {
+            self.refresh_scratch();
+        }
         self.popup_hint_scroll = -2;
 
         let has_scrollable_text = match self.popup.as_ref().unwrap() {
@@ -4871,6 +9194,19 @@ impl App {
     fn show_popup_from_action(&mut self, popup: ShowPopupAction) {
         let popup_menu = match popup {
             ShowPopupAction::ShowKeybinds => Popup::CurrentKeybinds,
+            ShowPopupAction::ShowKeybindEditor => Popup::KeybindEditor,
+            ShowPopupAction::ShowSettingsProfiles => Popup::SettingsProfiles,
+            ShowPopupAction::ShowNotificationHistory => Popup::NotificationHistory,
+            ShowPopupAction::ShowSettingsSearch => Popup::SettingsSearch,
+            ShowPopupAction::ShowCommandPalette => Popup::CommandPalette,
+            ShowPopupAction::ShowComposeEditor if !self.settings.behavior.pseudo_shell => {
+                self.notifs.notify_str(
+                    "Compose editor needs the Pseudo Shell enabled!",
+                    Severity::Warning,
+                );
+                return;
+            }
+            ShowPopupAction::ShowComposeEditor => Popup::ComposeEditor,
             ShowPopupAction::ShowPortSettings => Popup::SettingsMenu(SettingsMenu::SerialPort),
             ShowPopupAction::ShowBehavior => Popup::SettingsMenu(SettingsMenu::Behavior),
             ShowPopupAction::ShowRendering => Popup::SettingsMenu(SettingsMenu::Rendering),
@@ -4904,6 +9240,7 @@ impl App {
         }
 
         self.refresh_scratch();
+        self.settings_field_editor = None;
         self.popup.take();
         self.popup_menu_scroll = 0;
         self.popup_hint_scroll = -2;
@@ -4960,6 +9297,73 @@ impl App {
 
         Ok(())
     }
+    /// Hand the terminal over to the user's editor at the given location, resuming once it exits.
+    fn open_in_editor(
+        &mut self,
+        location: &SourceLocation,
+        terminal: &mut Terminal<impl Backend>,
+    ) -> Result<()> {
+        use crate::tui::editor::{editor_command, run_suspended};
+
+        let text = format!("{}:{}", location.file, location.line);
+        let result = editor_command(&self.settings.behavior.editor_command, location)
+            .and_then(|command| run_suspended(command, &self.input_paused));
+        // Whatever was on screen before is gone now.
+        terminal.clear()?;
+
+        match result {
+            Ok(status) if status.success() => (),
+            Ok(status) => self.notifs.notify_str(
+                format!("Editor for {text} exited with {status}"),
+                Severity::Warning,
+            ),
+            Err(e) => {
+                error!("Error opening {text} in editor: {e}");
+                self.notifs
+                    .notify_str(format!("Couldn't open editor: {e}"), Severity::Error);
+            }
+        }
+        Ok(())
+    }
+    #[cfg(feature = "defmt")]
+    fn defmt_found_elf_choice(&mut self, choice: DefmtFoundElfPrompt) {
+        let Some(Popup::DefmtFoundElf(elf_path)) = self.popup.take() else {
+            unreachable!("Can't load a found ELF without its path!");
+        };
+        // Dismissed first so any popup shown while loading isn't closed right away.
+        self.dismiss_popup();
+
+        match choice {
+            DefmtFoundElfPrompt::Cancel => (),
+            DefmtFoundElfPrompt::Load => self.try_load_defmt_elf(
+                &elf_path,
+                #[cfg(feature = "defmt-watch")]
+                false,
+            ),
+        }
+    }
+    #[cfg(feature = "defmt")]
+    /// Apply the directives entered into the module filter popup,
+    /// enabling filtering by module if any were given.
+    fn apply_defmt_module_filter(&mut self) -> Result<()> {
+        let directives = self.defmt_helpers.module_filter_input.value().trim();
+        let filtering = !ModuleFilter::parse(directives).is_empty();
+
+        let defmt = &mut self.settings.defmt;
+        defmt.module_filter = directives.to_owned();
+        defmt.filter_by_module = filtering;
+        self.buffer.update_defmt_settings(defmt.clone());
+        self.settings.save()?;
+        self.dismiss_popup();
+
+        let text = if filtering {
+            "defmt module filter applied!"
+        } else {
+            "defmt module filter cleared!"
+        };
+        self.notifs.notify_str(text, Severity::Info);
+        Ok(())
+    }
     #[cfg(feature = "defmt")]
     /// Try to load the given file path as a defmt elf,
     /// if successful, loads decoder into Buffer and Logger
@@ -5003,26 +9407,124 @@ impl App {
             &mut self.defmt_helpers.watcher_handle,
         ) {
             Ok(None) => {
-                self.notifs.notify_str(success_text, Color::Green);
+                self.notifs.notify_str(success_text, Severity::Success);
+                self.warn_defmt_elf_mismatch();
             }
             Ok(Some(locs_err)) => {
                 self.notifs.notify_str(
                     format!("defmt ELF had location data err: {locs_err}"),
-                    Color::Yellow,
+                    Severity::Warning,
                 );
+                self.warn_defmt_elf_mismatch();
             }
             Err(e) => {
                 let text = format!("{fail_text} {e}");
                 error!("{text}");
-                self.notifs.notify_str(text, Color::Red);
+                self.notifs.notify_str(text, Severity::Error);
             }
         }
     }
+    #[cfg(feature = "defmt-watch")]
+    /// Toggle if the selected recent ELF is watched for rebuilds alongside the loaded one.
+    fn toggle_extra_watched_elf(&mut self) -> color_eyre::Result<()> {
+        let Some(path) = self
+            .defmt_helpers
+            .recent_elfs
+            .nth_path(self.popup_menu_scroll)
+            .map(ToOwned::to_owned)
+        else {
+            return Ok(());
+        };
+
+        let watched = &mut self.settings.defmt.extra_watched_elfs;
+        let text = if let Some(index) = watched.iter().position(|p| *p == path) {
+            watched.remove(index);
+            format!("Stopped watching {path}")
+        } else {
+            watched.push(path.clone());
+            format!("Watching {path} for rebuilds")
+        };
+
+        self.defmt_helpers
+            .watcher_handle
+            .watch_extra_paths(watched)?;
+        self.settings.save()?;
+        self.notifs.notify_str(text, Severity::Info);
+        Ok(())
+    }
+    #[cfg(feature = "defmt")]
+    /// Let the user know if the device reported running a different ELF than the one loaded.
+    fn warn_defmt_elf_mismatch(&mut self) {
+        if let Some(device_hash) = self.buffer.defmt_elf_mismatch() {
+            self.notifs.notify_str(
+                format!("Device's ELF (SHA256 {device_hash}...) doesn't match loaded defmt ELF!"),
+                Severity::Warning,
+            );
+        }
+    }
+    #[cfg(feature = "self-replace")]
+    /// Ask before installing from an archive downloaded beforehand.
+    pub fn offer_update_from_archive(&mut self, archive: camino::Utf8PathBuf) {
+        self.show_popup(Popup::UpdateFromArchivePrompt(archive));
+    }
     fn first_time_setup(&mut self) {
-        if !self.settings.updates.user_dismissed_prompt {
+        if self.new_config {
+            self.show_popup(Popup::SetupWizard);
+        } else if !self.settings.updates.user_dismissed_prompt {
             self.show_popup(Popup::UpdateCheckConsentPrompt);
         }
     }
+    fn setup_wizard_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
+        match key_event.code {
+            KeyCode::Left | KeyCode::Right => {
+                let input = if key_event.code == KeyCode::Left {
+                    ArrowKey::Left
+                } else {
+                    ArrowKey::Right
+                };
+                self.setup_wizard
+                    .handle_input(input, self.popup_menu_scroll)?;
+            }
+            KeyCode::Up | KeyCode::BackTab => self.up_pressed(),
+            KeyCode::Down | KeyCode::Tab => self.down_pressed(),
+            KeyCode::Enter => self.setup_wizard_next_step()?,
+            // Skipping keeps the defaults, but still asks about update checks.
+            KeyCode::Esc => {
+                self.dismiss_popup();
+                if !self.settings.updates.user_dismissed_prompt {
+                    self.show_popup(Popup::UpdateCheckConsentPrompt);
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+    fn setup_wizard_next_step(&mut self) -> Result<()> {
+        if !SetupWizard::is_last_step(self.popup_menu_scroll) {
+            self.popup_menu_scroll += 1;
+            return Ok(());
+        }
+
+        let previous = self.settings.clone();
+        self.setup_wizard.apply_to(&mut self.settings);
+        let changes = previous.diff(&self.settings)?;
+        self.apply_settings_changes(&changes)?;
+        self.settings.save()?;
+        self.refresh_scratch();
+
+        let consent = if self.setup_wizard.check_for_updates {
+            UpdateCheckConsentPrompt::Yes
+        } else {
+            UpdateCheckConsentPrompt::Never
+        };
+        // Saves the settings again with the choice, and dismisses the wizard.
+        self.update_check_consent_choice(consent)?;
+        self.notifs.notify_str(
+            "Setup complete! Everything else can be found in the settings menus.",
+            Severity::Success,
+        );
+        Ok(())
+    }
 }
 
 #[cfg(feature = "defmt")]
@@ -5053,7 +9555,7 @@ pub fn _try_load_defmt_elf(
         Ok((new_decoder, locations_err_opt)) => {
             let decoder_arc = Arc::new(new_decoder);
             let _ = decoder_opt.insert(decoder_arc.clone());
-            recent_elfs.elf_loaded(path)?;
+            recent_elfs.elf_loaded(path, &decoder_arc.elf_md5)?;
             #[cfg(feature = "logging")]
             logging.update_defmt_decoder(Some(decoder_arc.clone()))?;
             #[cfg(feature = "defmt-watch")]
@@ -5068,6 +9570,26 @@ pub fn _try_load_defmt_elf(
     }
 }
 
+#[cfg(feature = "defmt")]
+/// Load an ELF whose table is only tried when the main one fails to decode a frame.
+///
+/// Not added to the recent ELFs or watched for changes, since those track the main ELF.
+pub fn _try_load_extra_defmt_elf(
+    path: &Utf8Path,
+    extra_decoders: &mut Vec<Arc<DefmtDecoder>>,
+    #[cfg(feature = "logging")] logging: &crate::buffer::LoggingHandle,
+) -> Result<Option<LocationsError>, YapLoadDefmtError> {
+    let (new_decoder, locations_err_opt) = DefmtDecoder::from_elf_path(path).map_err(|e| {
+        error!("error loading extra defmt elf {e}");
+        e
+    })?;
+    extra_decoders.push(Arc::new(new_decoder));
+    #[cfg(feature = "logging")]
+    logging.update_extra_defmt_decoders(extra_decoders.clone())?;
+
+    Ok(locations_err_opt)
+}
+
 pub fn repeating_pattern_widget(
     frame: &mut Frame,
     area: Rect,
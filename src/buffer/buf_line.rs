@@ -7,13 +7,15 @@ use ratatui::{
     text::{Line, Span},
     widgets::{Paragraph, Wrap},
 };
+use unicode_width::UnicodeWidthStr;
 
 #[cfg(feature = "defmt")]
 use crate::settings::Defmt;
 use crate::{
-    buffer::{LineEnding, RangeSlice},
+    buffer::{LineEnding, LocationKind, RangeSlice, SourceLocation},
     settings::Rendering,
     traits::LineHelpers,
+    tui::theme::theme,
 };
 
 const TIME_FORMAT: &str = "[%H:%M:%S%.3f] ";
@@ -32,7 +34,14 @@ pub struct BufLine {
     // Truncated from usize, since even the ratatui sizes are capped there.
     rendered_line_height: u16,
 
+    /// If set, `value` was dropped to save memory
+    /// and needs to be recreated from the raw buffer before being shown.
+    styling_evicted: bool,
+
     pub line_type: LineType,
+
+    /// Panic, backtrace, or defmt error location found in this line.
+    pub(super) source_location: Option<SourceLocation>,
 }
 
 #[derive(Clone, Copy)]
@@ -101,6 +110,8 @@ pub(super) enum LineType {
         location: Option<FrameLocation>,
         /// Timestamp generated using a format string and device's local time-since-boot.
         device_timestamp: Option<CompactString>,
+        /// Name of the ELF whose table decoded this line, if multiple were loaded.
+        image: Option<CompactString>,
     },
 }
 
@@ -123,6 +134,13 @@ pub struct FrameLocation {
     file: CompactString,
 }
 
+#[cfg(feature = "defmt")]
+impl FrameLocation {
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+}
+
 #[cfg(feature = "defmt")]
 impl From<&defmt_decoder::Location> for FrameLocation {
     fn from(value: &defmt_decoder::Location) -> Self {
@@ -169,6 +187,7 @@ pub struct BufLineKit<'a> {
 impl BufLine {
     fn new_inner(line: Line<'static>, kit: BufLineKit, line_type: LineType) -> Self {
         let timestamp = kit.timestamp;
+        let source_location = find_source_location(&line, &line_type);
 
         let mut bufline = Self {
             // timestamp_str: timestamp.format(TIME_FORMAT).to_compact_string(),
@@ -176,7 +195,9 @@ impl BufLine {
             range_in_raw_buffer: kit.full_range_slice.range,
             value: line,
             rendered_line_height: 0,
+            styling_evicted: false,
             line_type,
+            source_location,
         };
         // bufline.populate_line_ending(raw_value, line_ending);
         bufline.update_line_height(kit.area_width, kit.render);
@@ -219,7 +240,7 @@ impl BufLine {
     pub fn hidden_content_port_line(kit: BufLineKit, line_ending: &LineEnding) -> Self {
         let span = Span::styled(
             "[All content was omitted by color rules.]",
-            Style::new().dark_gray(),
+            theme().dim_style(),
         );
 
         let line_type = LineType::PortHidden(
@@ -243,11 +264,13 @@ impl BufLine {
         level: Option<defmt_parser::Level>,
         device_timestamp: Option<&dyn std::fmt::Display>,
         location: Option<FrameLocation>,
+        image: Option<&str>,
     ) -> Self {
         let line_type = LineType::PortDefmt {
             level,
             device_timestamp: device_timestamp.map(|ts| format_compact!("[{ts}] ")),
             location,
+            image: image.map(|name| format_compact!("[{name}] ")),
         };
 
         Self::new_inner(line, kit, line_type)
@@ -258,15 +281,17 @@ impl BufLine {
         level: Option<defmt_parser::Level>,
         device_timestamp: Option<&dyn std::fmt::Display>,
         location: Option<FrameLocation>,
+        image: Option<&str>,
     ) -> Self {
         let span = Span::styled(
             "[All content was omitted by color rules.]",
-            Style::new().dark_gray(),
+            theme().dim_style(),
         );
         let line_type = LineType::PortDefmt {
             level,
             device_timestamp: device_timestamp.map(|ts| format_compact!("[{ts}] ")),
             location,
+            image: image.map(|name| format_compact!("[{name}] ")),
         };
 
         Self::new_inner(span.into(), kit, line_type)
@@ -308,7 +333,21 @@ impl BufLine {
         // redoing the line height check again.
         let width_minus_scrollbar = terminal_width.saturating_sub(1);
 
-        let para = Paragraph::new(self.as_line(rendering)).wrap(Wrap { trim: false });
+        let line = self.as_line(rendering);
+
+        // Measuring in terminal cells rather than chars, since CJK and most emoji take up two cells each.
+        let display_width: usize = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref().width())
+            .sum();
+        if width_minus_scrollbar > 0 && display_width <= width_minus_scrollbar as usize {
+            // Fits on one row, no need to simulate the wrapping.
+            self.rendered_line_height = 1;
+            return 1;
+        }
+
+        let para = Paragraph::new(line).wrap(Wrap { trim: false });
         // Paragraph::line_count comes from an unstable ratatui feature (unstable-rendered-line-info)
         // which may be changed/removed in the future. If so, I'll need to roll my own wrapping/find someone's to steal.
         let height = para.line_count(width_minus_scrollbar);
@@ -320,12 +359,75 @@ impl BufLine {
         self.rendered_line_height
     }
 
+    /// Only normal, terminated port lines can be restyled from their raw bytes alone.
+    pub(super) fn can_evict_styling(&self) -> bool {
+        !self.styling_evicted
+            && matches!(self.line_type, LineType::Port(LineFinished::LineEnding(_)))
+    }
+
+    /// Drops the styled text, keeping the cached height so scrolling remains accurate.
+    pub(super) fn evict_styling(&mut self) {
+        debug_assert!(self.can_evict_styling());
+        self.value = Line::default();
+        self.styling_evicted = true;
+    }
+
+    pub(super) fn styling_evicted(&self) -> bool {
+        self.styling_evicted
+    }
+
+    /// If the line's shown text contains the query, ignoring ASCII case.
+    ///
+    /// Lines with evicted styling are searched through their raw bytes instead.
+    pub(super) fn text_contains(&self, query: &str, raw: &[u8]) -> bool {
+        if self.styling_evicted {
+            contains_ignoring_case(raw.iter().copied(), query.as_bytes())
+        } else {
+            let text = self
+                .value
+                .spans
+                .iter()
+                .flat_map(|span| span.content.bytes());
+            contains_ignoring_case(text, query.as_bytes())
+        }
+    }
+
+    /// Takes the styled text and height from a freshly restyled copy of this line.
+    pub(super) fn restore_styling(&mut self, restyled: BufLine) {
+        debug_assert!(self.styling_evicted);
+        self.value = restyled.value;
+        self.rendered_line_height = restyled.rendered_line_height;
+        self.styling_evicted = false;
+    }
+
+    /// Sets the cached height, for when it was determined from a restyled copy of this line.
+    pub(super) fn set_line_height(&mut self, height: u16) -> usize {
+        self.rendered_line_height = height;
+        height as usize
+    }
+
     /// Returns an owned `ratatui::Line` that borrows from the BufLine's actual text spans,
     /// and appending optional Spans depending on line type and user's rendering/defmt settings.
     pub fn as_line(&self, rendering: RenderSettings) -> Line<'_> {
-        let borrowed_spans = self.value.borrowed_spans_iter();
-
-        let dark_gray = Style::new().dark_gray();
+        let location_style = self
+            .source_location
+            .as_ref()
+            .filter(|_| rendering.rendering.highlight_source_locations)
+            .map(|location| match location.kind {
+                LocationKind::Backtrace => Style::new().yellow(),
+                _ => Style::new().light_red().bold(),
+            });
+        let borrowed_spans =
+            self.value
+                .borrowed_spans_iter()
+                .map(move |span| match location_style {
+                    Some(style) if span.style.fg.is_none() => span.patch_style(style),
+                    // Keeping colors from ANSI escapes, just adding the emphasis.
+                    Some(style) => span.patch_style(Style { fg: None, ..style }),
+                    None => span,
+                });
+
+        let dim = theme().dim_style();
 
         let indices_and_len = std::iter::once(&self.line_type)
             .filter_map(|lt| match lt {
@@ -340,12 +442,12 @@ impl BufLine {
                     rendering.rendering.indices_as_hex,
                 )),
             })
-            .map(|i| Span::styled(i, dark_gray));
+            .map(|i| Span::styled(i, dim));
 
         let timestamp = rendering
             .rendering
             .timestamps
-            .then(|| Span::styled(self.timestamp.format(TIME_FORMAT).to_string(), dark_gray))
+            .then(|| Span::styled(self.timestamp.format(TIME_FORMAT).to_string(), dim))
             .into_iter();
 
         #[cfg(feature = "defmt")]
@@ -354,7 +456,15 @@ impl BufLine {
             LineType::PortDefmt {
                 device_timestamp: Some(device_timestamp),
                 ..
-            } => Some(Span::styled(device_timestamp, dark_gray)),
+            } => Some(Span::styled(device_timestamp, dim)),
+            _ => None,
+        });
+
+        #[cfg(feature = "defmt")]
+        let defmt_image = std::iter::once(&self.line_type).filter_map(|lt| match lt {
+            LineType::PortDefmt {
+                image: Some(image), ..
+            } => Some(Span::styled(image.as_str(), dim)),
             _ => None,
         });
 
@@ -441,7 +551,7 @@ impl BufLine {
                     format!(
                         " {module}{module_file_separator}{file}{file_line_separator}{line_num}"
                     ),
-                    Style::new().dark_gray(),
+                    theme().dim_style(),
                 ))
             }
             _ => None,
@@ -451,10 +561,10 @@ impl BufLine {
             _ if !rendering.rendering.show_line_ending => None,
 
             LineType::Port(LineFinished::LineEnding(line_ending)) => {
-                Some(Span::styled(Cow::Borrowed(line_ending.as_str()), dark_gray))
+                Some(Span::styled(Cow::Borrowed(line_ending.as_str()), dim))
             }
             LineType::PortHidden(LineFinished::LineEnding(line_ending)) => {
-                Some(Span::styled(Cow::Borrowed(line_ending.as_str()), dark_gray))
+                Some(Span::styled(Cow::Borrowed(line_ending.as_str()), dim))
             }
 
             LineType::Port(LineFinished::CutShort) => None,
@@ -465,7 +575,7 @@ impl BufLine {
             LineType::User {
                 escaped_line_ending: Some(line_ending),
                 ..
-            } => Some(Span::styled(Cow::Borrowed(line_ending.as_str()), dark_gray)),
+            } => Some(Span::styled(Cow::Borrowed(line_ending.as_str()), dim)),
 
             LineType::User {
                 escaped_line_ending: None,
@@ -486,7 +596,7 @@ impl BufLine {
         let spans = spans.chain(indices_and_len);
 
         #[cfg(feature = "defmt")]
-        let spans = spans.chain(defmt_level);
+        let spans = spans.chain(defmt_image).chain(defmt_level);
 
         let spans = spans.chain(borrowed_spans).chain(line_ending);
 
@@ -501,6 +611,59 @@ impl BufLine {
     }
 }
 
+/// Searches without joining the haystack, since a line's text is split across its spans.
+fn contains_ignoring_case(mut haystack: impl Iterator<Item = u8> + Clone, needle: &[u8]) -> bool {
+    loop {
+        let mut candidate = haystack.clone();
+        if needle
+            .iter()
+            .all(|n| candidate.next().is_some_and(|h| h.eq_ignore_ascii_case(n)))
+        {
+            return true;
+        }
+        if haystack.next().is_none() {
+            return false;
+        }
+    }
+}
+
+fn find_source_location(line: &Line, line_type: &LineType) -> Option<SourceLocation> {
+    match line_type {
+        LineType::Port(_) => (),
+        #[cfg(feature = "defmt")]
+        LineType::PortDefmt { .. } => (),
+        LineType::PortHidden(_) | LineType::User { .. } => return None,
+    }
+
+    // Every location has a colon in it somewhere, so lines without one aren't worth joining up.
+    let text: Cow<str> = match line.spans.as_slice() {
+        [span] => Cow::Borrowed(&span.content),
+        spans if spans.iter().any(|s| s.content.contains(':')) => {
+            Cow::Owned(spans.iter().map(|s| s.content.as_ref()).collect())
+        }
+        _ => Cow::Borrowed(""),
+    };
+    let found = SourceLocation::find_in_text(&text);
+
+    #[cfg(feature = "defmt")]
+    if found.is_none()
+        && let LineType::PortDefmt {
+            level: Some(defmt_parser::Level::Error),
+            location: Some(location),
+            ..
+        } = line_type
+    {
+        return Some(SourceLocation {
+            kind: LocationKind::DefmtError,
+            file: location.file.clone(),
+            line: location.line,
+            column: None,
+        });
+    }
+
+    found
+}
+
 fn make_index_info(range: &Range<usize>, hex: bool) -> CompactString {
     let start = range.start;
     let end = range.end;
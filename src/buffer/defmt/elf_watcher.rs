@@ -1,17 +1,16 @@
 use std::{
+    collections::BTreeSet,
     thread::JoinHandle,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use camino::{Utf8Path, Utf8PathBuf};
 use crossbeam::channel::{Receiver, Sender, TryRecvError, bounded};
-use notify::{
-    EventKind, RecommendedWatcher, Watcher,
-    event::{ModifyKind, RenameMode},
-};
+use fs_err as fs;
+use notify::{RecommendedWatcher, Watcher};
 use tracing::{debug, error, info};
 
-use crate::app::Event;
+use crate::{app::Event, file_watch::event_updates_file};
 
 #[derive(Debug)]
 pub enum ElfWatchEvent {
@@ -29,6 +28,8 @@ enum ElfWatchCommand {
     /// Begin watching the specified path for changes, triggering a defmt
     /// decoder rebuild.
     BeginWatch(Utf8PathBuf),
+    /// Replace the set of additional paths watched alongside the loaded ELF.
+    WatchExtra(Vec<Utf8PathBuf>),
     Shutdown(Sender<()>),
 }
 
@@ -49,7 +50,10 @@ impl ElfWatchHandle {
             watcher_rx,
             watcher,
             file_under_watch: None,
-            load_debounce_instant: Instant::now(),
+            extra_files_under_watch: BTreeSet::new(),
+            watched_dirs: BTreeSet::new(),
+            updated_files: BTreeSet::new(),
+            last_update_instant: None,
         };
 
         let worker = std::thread::spawn(move || {
@@ -69,6 +73,13 @@ impl ElfWatchHandle {
         Ok(())
     }
 
+    /// Watch these ELFs as well as the loaded one, reloading whichever was rebuilt most recently.
+    pub fn watch_extra_paths(&self, elf_paths: &[Utf8PathBuf]) -> HandleResult<()> {
+        let paths = elf_paths.to_vec();
+        self.command_tx.send(ElfWatchCommand::WatchExtra(paths))?;
+        Ok(())
+    }
+
     pub fn shutdown(&self) -> Result<(), ()> {
         let (shutdown_tx, shutdown_rx) = bounded(0);
         if self
@@ -101,15 +112,24 @@ impl<T> From<crossbeam::channel::SendError<T>> for ElfWatcherMissing {
     }
 }
 
-const DEBOUNCE_DURATION: Duration = Duration::from_secs(2);
+/// How long watched files need to go without changes before a reload is requested,
+/// so a build that touches several binaries is only acted on once it's done.
+const DEBOUNCE_DURATION: Duration = Duration::from_secs(1);
 
 struct ElfWatchWorker {
     command_rx: Receiver<ElfWatchCommand>,
     event_tx: Sender<Event>,
     watcher: RecommendedWatcher,
     watcher_rx: Receiver<Result<notify::Event, notify::Error>>,
+    /// The currently loaded ELF.
     file_under_watch: Option<Utf8PathBuf>,
-    load_debounce_instant: Instant,
+    /// User-chosen ELFs, i.e. other binaries in the same workspace.
+    extra_files_under_watch: BTreeSet<Utf8PathBuf>,
+    /// Parent directories of all watched files, since that's what's actually given to `notify`.
+    watched_dirs: BTreeSet<Utf8PathBuf>,
+    /// Watched files that changed since the last reload request.
+    updated_files: BTreeSet<Utf8PathBuf>,
+    last_update_instant: Option<Instant>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -136,32 +156,37 @@ impl ElfWatchWorker {
             let mut channel_notifier = crossbeam::channel::Select::new();
             channel_notifier.recv(&self.watcher_rx);
             channel_notifier.recv(&self.command_rx);
-            // Waiting...
-            let _ready_index = channel_notifier.ready();
+            // Waiting, but only until pending updates have settled.
+            let settled = if let Some(last_update) = self.last_update_instant {
+                let remaining = DEBOUNCE_DURATION.saturating_sub(last_update.elapsed());
+                channel_notifier.ready_timeout(remaining).is_err()
+            } else {
+                let _ready_index = channel_notifier.ready();
+                false
+            };
+            drop(channel_notifier);
+
+            if settled {
+                self.request_reload()?;
+                continue;
+            }
 
             match self.watcher_rx.try_recv() {
-                Ok(watcher_event_res) => {
-                    match watcher_event_res {
-                        Ok(watcher_event) if self.event_matches_watched_file(&watcher_event) => {
-                            // trace!("File watcher event: {watcher_event:?}");
-
-                            if self.load_debounce_instant.elapsed() < DEBOUNCE_DURATION {
-                                info!("Ignoring ELF file update, too soon since last one.");
-                            } else {
-                                let owned_watched_path =
-                                    self.file_under_watch.as_ref().unwrap().to_owned();
-
-                                self.event_tx
-                                    .send(ElfWatchEvent::ElfUpdated(owned_watched_path).into())?;
-
-                                self.load_debounce_instant = Instant::now();
-                                debug!("ELF Watcher sent reload request.");
-                            }
+                Ok(watcher_event_res) => match watcher_event_res {
+                    Ok(watcher_event) => {
+                        // trace!("File watcher event: {watcher_event:?}");
+                        let updated: Vec<_> = self
+                            .all_watched_files()
+                            .filter(|path| event_updates_file(&watcher_event, path.as_std_path()))
+                            .cloned()
+                            .collect();
+                        if !updated.is_empty() {
+                            self.updated_files.extend(updated);
+                            self.last_update_instant = Some(Instant::now());
                         }
-                        Ok(_) => (),
-                        Err(e) => error!("File watcher error: {e}"),
                     }
-                }
+                    Err(e) => error!("File watcher error: {e}"),
+                },
                 Err(TryRecvError::Empty) => (),
                 Err(TryRecvError::Disconnected) => break Err(ElfWatchError::WatcherDisconnect),
             }
@@ -183,36 +208,30 @@ impl ElfWatchWorker {
             }
         }
     }
-    fn event_matches_watched_file(&self, event: &notify::Event) -> bool {
-        if let Some(watched_path) = &self.file_under_watch
-            && event.paths.iter().any(|p| p == watched_path)
-        {
-            // guh.
-            match event.kind {
-                EventKind::Create(_) => true,
-                EventKind::Modify(modify_kind) => match modify_kind {
-                    ModifyKind::Data(_) => true,
-                    ModifyKind::Any => true,
-                    ModifyKind::Other => true,
-                    ModifyKind::Metadata(_) => false,
-                    ModifyKind::Name(rename_mode) => match rename_mode {
-                        RenameMode::To => true,
-                        RenameMode::From => false,
-
-                        RenameMode::Both if event.paths[1] == *watched_path => true,
-                        RenameMode::Both => false,
-
-                        RenameMode::Any | RenameMode::Other => true,
-                    },
-                },
-                EventKind::Any => true,
-                EventKind::Other => true,
-                EventKind::Access(_) => false,
-                EventKind::Remove(_) => false,
-            }
-        } else {
-            false
-        }
+    /// Asks the app to load whichever updated file was most recently modified,
+    /// assuming that's the binary the user just built and is about to flash.
+    fn request_reload(&mut self) -> Result<(), ElfWatchError> {
+        _ = self.last_update_instant.take();
+        let updated = std::mem::take(&mut self.updated_files);
+
+        let newest = updated
+            .into_iter()
+            .max_by_key(|path| {
+                fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+            })
+            .expect("reload only requested after an update");
+
+        self.event_tx
+            .send(ElfWatchEvent::ElfUpdated(newest).into())?;
+        debug!("ELF Watcher sent reload request.");
+        Ok(())
+    }
+    fn all_watched_files(&self) -> impl Iterator<Item = &Utf8PathBuf> {
+        self.file_under_watch
+            .iter()
+            .chain(&self.extra_files_under_watch)
     }
     fn handle_command(&mut self, command: ElfWatchCommand) -> Result<(), ElfWatchError> {
         match command {
@@ -225,35 +244,58 @@ impl ElfWatchWorker {
                     info!("Already watching! Not acting further.");
                     return Ok(());
                 }
-                // Forget old file
-                self.unwatch_last_path();
 
-                let Some(new_file_parent) = new_file.parent() else {
+                if new_file.parent().is_none() {
                     error!("Requested file to watch has no parent? Not acting further.");
                     return Ok(());
-                };
-
-                if let Err(e) = self.watcher.watch(
-                    new_file_parent.as_ref(),
-                    notify::RecursiveMode::NonRecursive,
-                ) {
-                    self.event_tx
-                        .send(ElfWatchEvent::Error(e.to_string()).into())?;
-                } else {
-                    info!("Watch started for: {new_file}");
-                    _ = self.file_under_watch.insert(new_file);
                 }
+
+                _ = self.file_under_watch.insert(new_file);
+                self.sync_watched_dirs()?;
+            }
+            ElfWatchCommand::WatchExtra(new_files) => {
+                info!("Asked to also watch {} other ELFs", new_files.len());
+                self.extra_files_under_watch = new_files
+                    .into_iter()
+                    .filter(|path| path.parent().is_some())
+                    .collect();
+                self.sync_watched_dirs()?;
             }
             ElfWatchCommand::Shutdown(_) => unreachable!("shutdown handled in work_loop"),
         }
         Ok(())
     }
-    fn unwatch_last_path(&mut self) {
-        if let Some(old_path) = self.file_under_watch.take() {
-            let old_path_parent = old_path.parent().expect("inserted path should have parent");
-            if let Err(e) = self.watcher.unwatch(old_path_parent.as_ref()) {
-                error!("Error unwatching file: {e}, but continuing anyway")
+    /// Starts and stops watching directories so only those holding a watched file are tracked.
+    fn sync_watched_dirs(&mut self) -> Result<(), ElfWatchError> {
+        let wanted_dirs: BTreeSet<Utf8PathBuf> = self
+            .all_watched_files()
+            .filter_map(|path| path.parent())
+            .map(ToOwned::to_owned)
+            .collect();
+
+        for old_dir in self.watched_dirs.difference(&wanted_dirs) {
+            if let Err(e) = self.watcher.unwatch(old_dir.as_ref()) {
+                error!("Error unwatching dir: {e}, but continuing anyway")
             }
         }
+
+        let mut watched_dirs = BTreeSet::new();
+        for dir in wanted_dirs {
+            if self.watched_dirs.contains(&dir) {
+                watched_dirs.insert(dir);
+            } else if let Err(e) = self
+                .watcher
+                .watch(dir.as_ref(), notify::RecursiveMode::NonRecursive)
+            {
+                self.event_tx
+                    .send(ElfWatchEvent::Error(e.to_string()).into())?;
+            } else {
+                info!("Watch started for: {dir}");
+                watched_dirs.insert(dir);
+            }
+        }
+        self.watched_dirs = watched_dirs;
+
+        Ok(())
     }
 }
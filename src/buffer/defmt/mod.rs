@@ -1,6 +1,9 @@
+use std::cell::Cell;
+
 use camino::Utf8PathBuf;
 use defmt_decoder::{DecodeError, Locations};
 use fs_err as fs;
+use sha2::{Digest, Sha256};
 use tracing::warn;
 
 // Huge inspiration from defmt-print
@@ -20,6 +23,10 @@ pub struct DefmtDecoder {
     ///
     /// Only real use is to let the user be sure as to what's loaded.
     pub elf_md5: String,
+    /// Lowercase hex SHA256 of entire ELF file.
+    ///
+    /// Compared against the hash some bootloaders (i.e. ESP-IDF's) print on boot.
+    pub elf_sha256: String,
     /// Path to loaded ELF.
     pub elf_path: Utf8PathBuf,
     /// The actual Table that maps indices to strings.
@@ -35,6 +42,12 @@ pub mod elf_watcher;
 
 pub mod frame_delimiting;
 
+pub mod module_filter;
+
+pub mod stats;
+
+pub mod timestamp;
+
 #[derive(Debug, thiserror::Error)]
 pub enum DefmtPacketError {
     #[error("no defmt table loaded")]
@@ -45,6 +58,8 @@ pub enum DefmtPacketError {
     DefmtDecode,
     #[error("defmt raw/uncompressed parse error, ceasing further attempts")]
     MalformedRawFrame,
+    #[error("defmt raw/uncompressed parse error, skipped ahead to next frame")]
+    ResyncedRawFrame,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -120,6 +135,7 @@ impl DefmtDecoder {
 
         let decoder = DefmtDecoder {
             elf_md5: format!("{:X}", md5::compute(&elf_data)),
+            elf_sha256: hex::encode(Sha256::digest(&elf_data)),
             elf_path,
             locations,
             table,
@@ -128,6 +144,124 @@ impl DefmtDecoder {
 
         Ok((decoder, locations_err))
     }
+    /// Returns `true` if the hash prefix reported by the device matches this ELF.
+    pub fn matches_device_hash(&self, device_hash: &str) -> bool {
+        self.elf_sha256
+            .starts_with(device_hash.to_ascii_lowercase().as_str())
+    }
+    /// Short name for the loaded image, used to tag lines when multiple ELFs are loaded.
+    pub fn image_name(&self) -> &str {
+        self.elf_path.file_stem().unwrap_or(self.elf_path.as_str())
+    }
+}
+
+/// Attempt to decode a frame with each given table in turn (i.e. an application and its bootloader),
+/// returning the first successful decode, the amount of bytes consumed, and which decoder resolved it.
+///
+/// Tables tend to share the same range of indexes, so a frame from one can often be decoded
+/// (wrongly) by another. The table at `last_matched` is tried first, and updated on success,
+/// so a stream sticks with the table that last made sense of it.
+///
+/// If every table failed, `UnexpectedEof` is returned if any table thought the frame was incomplete.
+pub fn decode_with_any<'a>(
+    decoders: impl IntoIterator<Item = &'a DefmtDecoder, IntoIter: Clone>,
+    bytes: &[u8],
+    last_matched: &Cell<usize>,
+) -> Result<(defmt_decoder::Frame<'a>, usize, &'a DefmtDecoder), DecodeError> {
+    let decoders = decoders.into_iter().enumerate();
+    let preferred = last_matched.get();
+    let ordered = decoders
+        .clone()
+        .skip(preferred)
+        .take(1)
+        .chain(decoders.filter(|(index, _)| *index != preferred));
+
+    let mut error = DecodeError::Malformed;
+    for (index, decoder) in ordered {
+        match decoder.table.decode(bytes) {
+            Ok((frame, consumed)) => {
+                last_matched.set(index);
+                return Ok((frame, consumed, decoder));
+            }
+            Err(DecodeError::UnexpectedEof) => error = DecodeError::UnexpectedEof,
+            Err(DecodeError::Malformed) => (),
+        }
+    }
+    Err(error)
+}
+
+/// Furthest past a malformed raw/uncompressed frame to search for the next decodable one.
+pub const RAW_RESYNC_MAX_SKIP: usize = 256;
+/// How many resyncs a raw/uncompressed stream gets before decoding is given up on entirely.
+pub const RAW_RESYNC_BUDGET: usize = 8;
+/// Frames that need to decode cleanly in a row to refill the resync budget.
+const RAW_RESYNC_REFILL_FRAMES: usize = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RawResyncOutcome {
+    /// Skipping this many bytes lands on the next plausible frame.
+    Skip(usize),
+    /// Not enough bytes buffered yet to tell where the next frame starts.
+    NeedMoreData,
+    /// Budget spent or nothing decodable within reach, stop decoding this stream.
+    GiveUp,
+}
+
+#[derive(Debug, Default, Clone)]
+/// Recovery state for raw/uncompressed defmt streams, which lack any delimiters
+/// to fall back on when a single frame gets corrupted.
+pub struct RawResync {
+    spent: usize,
+    clean_frames: usize,
+}
+
+impl RawResync {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+    pub fn frame_decoded(&mut self) {
+        self.clean_frames += 1;
+        if self.clean_frames >= RAW_RESYNC_REFILL_FRAMES {
+            self.spent = 0;
+        }
+    }
+    /// Called when the frame at the start of `bytes` is malformed,
+    /// searches byte-by-byte for the next offset that decodes.
+    ///
+    /// `decode` returns how many bytes a successfully decoded frame spanned.
+    pub fn find_next_frame(
+        &mut self,
+        bytes: &[u8],
+        decode: impl Fn(&[u8]) -> Result<usize, DecodeError>,
+    ) -> RawResyncOutcome {
+        self.clean_frames = 0;
+        if self.spent >= RAW_RESYNC_BUDGET {
+            return RawResyncOutcome::GiveUp;
+        }
+
+        for skip in 1..bytes.len().min(RAW_RESYNC_MAX_SKIP + 1) {
+            let candidate = &bytes[skip..];
+            match decode(candidate) {
+                Err(DecodeError::Malformed) => continue,
+                Err(DecodeError::UnexpectedEof) => return RawResyncOutcome::NeedMoreData,
+                // Noise can easily happen to decode as some frame in the table,
+                // so only trust it if what follows isn't obviously broken either.
+                Ok(consumed) => match decode(&candidate[consumed..]) {
+                    Err(DecodeError::Malformed) => continue,
+                    _ => {
+                        self.spent += 1;
+                        return RawResyncOutcome::Skip(skip);
+                    }
+                },
+            }
+        }
+
+        if bytes.len() > RAW_RESYNC_MAX_SKIP {
+            RawResyncOutcome::GiveUp
+        } else {
+            RawResyncOutcome::NeedMoreData
+        }
+    }
 }
 
 // Variant of
@@ -0,0 +1,80 @@
+use compact_str::CompactString;
+
+/// Parsed form of the user's defmt module filter directives, a la `RUST_LOG`.
+///
+/// Directives are comma-separated module path globs (where `*` matches any run of characters),
+/// matching a module and all of its submodules. Prefixing a directive with `!` hides matching modules instead.
+///
+/// Exclusions always win, and if any inclusions are given, only modules matching one of them are shown.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModuleFilter {
+    include: Vec<CompactString>,
+    exclude: Vec<CompactString>,
+}
+
+impl ModuleFilter {
+    pub fn parse(directives: &str) -> Self {
+        let mut filter = Self::default();
+        for directive in directives.split(',').map(str::trim) {
+            match directive.strip_prefix('!').map(str::trim) {
+                Some("") => (),
+                Some(excluded) => filter.exclude.push(excluded.into()),
+                None if directive.is_empty() => (),
+                None => filter.include.push(directive.into()),
+            }
+        }
+        filter
+    }
+    /// Returns `true` if there are no directives, meaning every module is allowed.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+    /// Returns `true` if logs from the given module path should be shown.
+    pub fn allows(&self, module: &str) -> bool {
+        let matches = |pattern: &CompactString| module_matches(pattern, module);
+        if self.exclude.iter().any(matches) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(matches)
+    }
+}
+
+/// Checks the module's full path, and the path of each of its parents, against the pattern.
+fn module_matches(pattern: &str, module: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    glob_match(pattern, module.as_bytes())
+        || module
+            .match_indices("::")
+            .any(|(end, _)| glob_match(pattern, &module.as_bytes()[..end]))
+}
+
+/// Bare-bones glob matching, only supporting `*` wildcards.
+///
+/// Backtracks to the most recent `*` on mismatch, letting it eat one more byte.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                last_star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match last_star {
+                Some((star_p, star_t)) => {
+                    last_star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
@@ -0,0 +1,75 @@
+use defmt_parser::Level;
+
+use super::DefmtPacketError;
+
+const LEVEL_LABELS: [&str; 6] = ["Trace", "Debug", "Info", "Warn", "Error", "No Level"];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Running tallies of defmt decoding outcomes for the current buffer,
+/// meant to help users tell at a glance if their framing setting is wrong.
+pub struct DefmtStats {
+    /// Successfully decoded frames, indexed from Trace to Error, followed by frames without a level.
+    frames: [usize; 6],
+    no_decoder: usize,
+    rzcobs_decompress: usize,
+    defmt_decode: usize,
+    /// Times the raw/uncompressed stream was deemed malformed, be it resynced or halted.
+    malformed_raw: usize,
+    /// Slices that weren't delimited as defmt frames, and were shown as text instead.
+    non_frame_slices: usize,
+}
+
+impl DefmtStats {
+    pub fn record_frame(&mut self, level: Option<Level>) {
+        let index = match level {
+            Some(Level::Trace) => 0,
+            Some(Level::Debug) => 1,
+            Some(Level::Info) => 2,
+            Some(Level::Warn) => 3,
+            Some(Level::Error) => 4,
+            None => 5,
+        };
+        self.frames[index] += 1;
+    }
+    pub fn record_failure(&mut self, reason: &DefmtPacketError) {
+        match reason {
+            DefmtPacketError::NoDecoder => self.no_decoder += 1,
+            DefmtPacketError::RzcobsDecompress => self.rzcobs_decompress += 1,
+            DefmtPacketError::DefmtDecode => self.defmt_decode += 1,
+            DefmtPacketError::MalformedRawFrame | DefmtPacketError::ResyncedRawFrame => {
+                self.malformed_raw += 1
+            }
+        }
+    }
+    pub fn record_non_frame(&mut self) {
+        self.non_frame_slices += 1;
+    }
+    /// Decoded frame counts alongside the name of their level.
+    pub fn frames_per_level(&self) -> impl Iterator<Item = (&'static str, usize)> {
+        LEVEL_LABELS.into_iter().zip(self.frames)
+    }
+    /// Failure counts alongside a short description of each kind.
+    pub fn failures(&self) -> [(&'static str, usize); 4] {
+        [
+            ("No defmt table loaded", self.no_decoder),
+            ("rzcobs decompress failed", self.rzcobs_decompress),
+            ("Packet decode failed", self.defmt_decode),
+            ("Malformed raw stream", self.malformed_raw),
+        ]
+    }
+    pub fn non_frame_slices(&self) -> usize {
+        self.non_frame_slices
+    }
+    pub fn total_frames(&self) -> usize {
+        self.frames.iter().sum()
+    }
+    pub fn total_failures(&self) -> usize {
+        self.failures().iter().map(|(_, count)| count).sum()
+    }
+    /// Returns `true` if decode failures (ignoring a missing table) are at least as common as decoded frames,
+    /// a good sign the framing setting doesn't match what the device is sending.
+    pub fn framing_suspect(&self) -> bool {
+        let failures = self.total_failures() - self.no_decoder;
+        failures > 0 && failures >= self.total_frames()
+    }
+}
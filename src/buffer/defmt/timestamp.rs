@@ -0,0 +1,65 @@
+use chrono::{DateTime, Local, TimeDelta};
+use compact_str::{CompactString, ToCompactString, format_compact};
+
+use crate::settings::{Defmt, DeviceTimestampMode};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Keeps track of which host time a device's timestamp of zero is assumed to line up with,
+/// to render device timestamps as wall-clock time.
+pub struct TimestampAnchor {
+    /// Host time at (assumed) device boot, and the last seen seconds since boot.
+    anchor: Option<(DateTime<Local>, f64)>,
+}
+
+impl TimestampAnchor {
+    pub fn reset(&mut self) {
+        self.anchor = None;
+    }
+    /// Convert a frame's rendered device timestamp according to the current settings.
+    ///
+    /// Returns `None` if it should be shown as sent, either by choice or if it's not a plain number.
+    pub fn convert(
+        &mut self,
+        rendered: &str,
+        host_time: DateTime<Local>,
+        settings: &Defmt,
+    ) -> Option<CompactString> {
+        let since_boot = seconds_since_boot(rendered, settings.device_tick_rate)?;
+
+        match settings.device_timestamp_mode {
+            DeviceTimestampMode::AsSent => None,
+            DeviceTimestampMode::SinceBoot => Some(format_compact!("{since_boot:.6}s")),
+            DeviceTimestampMode::WallClock => {
+                let boot_time = match self.anchor {
+                    Some((_, last)) if settings.reanchor_on_reset && since_boot < last => None,
+                    Some((boot_time, _)) => Some(boot_time),
+                    None => None,
+                }
+                .unwrap_or_else(|| host_time - seconds_to_delta(since_boot));
+
+                self.anchor = Some((boot_time, since_boot));
+
+                let wall_clock = boot_time + seconds_to_delta(since_boot);
+                Some(wall_clock.format("%H:%M:%S%.6f").to_compact_string())
+            }
+        }
+    }
+}
+
+/// Parses the timestamp as an integer tick count, or as seconds if it has a fractional part
+/// (as is the case with defmt's `us`/`ms` display hints), ignoring the tick rate.
+fn seconds_since_boot(rendered: &str, tick_rate: u32) -> Option<f64> {
+    let rendered = rendered.trim();
+    if let Ok(ticks) = rendered.parse::<u64>() {
+        Some(ticks as f64 / tick_rate.max(1) as f64)
+    } else {
+        rendered
+            .parse::<f64>()
+            .ok()
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+    }
+}
+
+fn seconds_to_delta(seconds: f64) -> TimeDelta {
+    TimeDelta::microseconds((seconds * 1_000_000.0) as i64)
+}
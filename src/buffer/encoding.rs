@@ -0,0 +1,92 @@
+use std::borrow::Cow;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::Display,
+    strum::VariantArray,
+)]
+/// How incoming port bytes should be decoded into text before any ANSI parsing.
+pub enum InputEncoding {
+    #[strum(serialize = "UTF-8")]
+    Utf8,
+    #[strum(serialize = "UTF-16LE")]
+    Utf16Le,
+    #[strum(serialize = "Latin-1")]
+    Latin1,
+    /// Treat lines that look like ASCII-range UTF-16LE as such, otherwise as UTF-8.
+    Auto,
+}
+
+impl InputEncoding {
+    /// Transcodes the given line's bytes into UTF-8, to be given to `ansi-to-tui`.
+    ///
+    /// UTF-8 input (or input detected as such) is returned as-is, with invalid sequences left
+    /// for the lossy conversion to handle.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+        match self {
+            Self::Utf8 => Cow::Borrowed(bytes),
+            Self::Latin1 => latin1_to_utf8(bytes),
+            Self::Utf16Le => Cow::Owned(utf16le_to_utf8(bytes)),
+            Self::Auto if looks_like_utf16le(bytes) => Cow::Owned(utf16le_to_utf8(bytes)),
+            Self::Auto => Cow::Borrowed(bytes),
+        }
+    }
+}
+
+/// Every Latin-1 byte maps directly to the Unicode code point of the same value.
+fn latin1_to_utf8(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if bytes.is_ascii() {
+        return Cow::Borrowed(bytes);
+    }
+    let text: String = bytes.iter().map(|&b| char::from(b)).collect();
+    Cow::Owned(text.into_bytes())
+}
+
+/// Since line endings are split on the raw bytes, a single-byte line ending like `\n`
+/// leaves the high `0x00` byte of its code unit at the start of the following line.
+///
+/// Skip it if present, so the remaining bytes are aligned to code units again.
+fn strip_orphaned_high_byte(bytes: &[u8]) -> &[u8] {
+    match bytes {
+        [0x00, rest @ ..] if !bytes.len().is_multiple_of(2) => rest,
+        _ => bytes,
+    }
+}
+
+fn utf16le_to_utf8(bytes: &[u8]) -> Vec<u8> {
+    let bytes = strip_orphaned_high_byte(bytes);
+    let chunks = bytes.chunks_exact(2);
+    // Odd trailing byte is either the start of a code unit that hasn't arrived yet,
+    // or garbage. Either way it can't be shown as-is.
+    let has_remainder = !chunks.remainder().is_empty();
+
+    let units = chunks.map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+    let mut text: String = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    if has_remainder {
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+    text.into_bytes()
+}
+
+/// Heuristic for `InputEncoding::Auto`, checking if most code units are ASCII-range characters
+/// encoded as UTF-16LE (i.e. every other byte is `0x00`).
+fn looks_like_utf16le(bytes: &[u8]) -> bool {
+    let bytes = strip_orphaned_high_byte(bytes);
+    if bytes.len() < 2 {
+        return false;
+    }
+    let units = bytes.len() / 2;
+    let ascii_units = bytes
+        .chunks_exact(2)
+        .filter(|pair| pair[0] != 0x00 && pair[1] == 0x00)
+        .count();
+    ascii_units * 4 >= units * 3
+}
@@ -0,0 +1,200 @@
+use super::{Buffer, buf_line::BufLine};
+
+/// A Fenwick tree (binary indexed tree) of line heights,
+/// allowing for `O(log n)` updates and prefix sums over the rendered heights of `BufLine`s.
+#[derive(Debug, Default, Clone)]
+pub(super) struct HeightTree {
+    /// Partial sums, where node `i` (1-indexed) covers the values in `(i - lowbit(i), i]`.
+    tree: Vec<usize>,
+    /// The height supplied for each line, kept to allow diffing when setting.
+    values: Vec<usize>,
+}
+
+/// Lowest set bit of `i`.
+#[inline]
+fn lowbit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+impl HeightTree {
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    pub fn clear(&mut self) {
+        self.tree.clear();
+        self.values.clear();
+    }
+    /// Drops all entries past `len`.
+    ///
+    /// Since no node depends on values after its own index, the remaining tree stays valid.
+    pub fn truncate(&mut self, len: usize) {
+        self.tree.truncate(len);
+        self.values.truncate(len);
+    }
+    pub fn push(&mut self, value: usize) {
+        let node = self.values.len() + 1;
+        // Sum of every value this new node covers, itself included.
+        let covered = value + self.prefix_sum(node - 1) - self.prefix_sum(node - lowbit(node));
+        self.tree.push(covered);
+        self.values.push(value);
+    }
+    #[allow(dead_code)] // Only exercised by tests so far, lines that change height mark the index stale instead.
+    pub fn set(&mut self, index: usize, value: usize) {
+        let old = std::mem::replace(&mut self.values[index], value);
+        if old == value {
+            return;
+        }
+        let mut node = index + 1;
+        while node <= self.tree.len() {
+            // Every node covering `index` already contains `old`, so this can't underflow.
+            self.tree[node - 1] = self.tree[node - 1] - old + value;
+            node += lowbit(node);
+        }
+    }
+    /// Sum of the first `count` values.
+    pub fn prefix_sum(&self, mut count: usize) -> usize {
+        let mut sum = 0;
+        while count > 0 {
+            sum += self.tree[count - 1];
+            count -= lowbit(count);
+        }
+        sum
+    }
+    pub fn total(&self) -> usize {
+        self.prefix_sum(self.len())
+    }
+}
+
+#[derive(Debug, Default)]
+/// Cumulative visible heights of the port and user lines,
+/// kept in sync with `StyledLines` by `Buffer::sync_height_index`.
+pub(super) struct HeightIndex {
+    pub rx: HeightTree,
+    pub tx: HeightTree,
+    /// If set, every entry needs recalculating (i.e. line heights or visibility filters changed.)
+    pub stale: bool,
+}
+
+impl Buffer {
+    /// How many rows this line contributes to the scrollable area, `0` if filtered out.
+    fn rx_line_weight(&self, line: &BufLine) -> usize {
+        if !self.rx_line_visible(line) {
+            0
+        } else if self.rendering.wrap_text {
+            line.get_line_height() as usize
+        } else {
+            1
+        }
+    }
+    /// How many rows this line contributes to the scrollable area, `0` if filtered out.
+    fn tx_line_weight(&self, line: &BufLine) -> usize {
+        if !self.tx_line_visible(line) {
+            0
+        } else if self.rendering.wrap_text {
+            line.get_line_height() as usize
+        } else {
+            1
+        }
+    }
+
+    /// Bring the height index up to date with the current `StyledLines`.
+    ///
+    /// Only the last indexed line of each kind is re-checked (as it may have been an unfinished line
+    /// that has since been added to), alongside any lines added since, unless a full rebuild was requested.
+    pub(super) fn sync_height_index(&self) {
+        let mut index = self.height_index.borrow_mut();
+
+        if std::mem::take(&mut index.stale) {
+            index.rx.clear();
+            index.tx.clear();
+        }
+
+        let rx_lines = &self.styled_lines.rx;
+        if index.rx.len() > rx_lines.len() {
+            // Lines were removed out from under us, can't trust anything else.
+            index.rx.clear();
+        }
+        let rx_start = index.rx.len().saturating_sub(1);
+        index.rx.truncate(rx_start);
+        for line in &rx_lines[rx_start..] {
+            index.rx.push(self.rx_line_weight(line));
+        }
+
+        let tx_lines = &self.styled_lines.tx;
+        if index.tx.len() > tx_lines.len() {
+            index.tx.clear();
+        }
+        let tx_start = index.tx.len().saturating_sub(1);
+        index.tx.truncate(tx_start);
+        for line in &tx_lines[tx_start..] {
+            index.tx.push(self.tx_line_weight(line));
+        }
+    }
+
+    /// How many user lines would be emitted before the given port line
+    /// by the interleaved iterator in `buflines_iter`.
+    fn tx_before(&self, port: &BufLine) -> usize {
+        self.styled_lines.tx.partition_point(|user| {
+            user.range().start < port.range().start
+                || (user.range().start == port.range().start && user.timestamp < port.timestamp)
+        })
+    }
+
+    /// Sum of all visible rows before the given port line.
+    pub(super) fn rows_before_port_line(&self, rx_index: usize) -> usize {
+        self.sync_height_index();
+        let index = self.height_index.borrow();
+        index.rx.prefix_sum(rx_index)
+            + index
+                .tx
+                .prefix_sum(self.tx_before(&self.styled_lines.rx[rx_index]))
+    }
+
+    /// Find the visible line containing the given row,
+    /// returning the indices in `rx` and `tx` to start iterating from to reach it,
+    /// and the sum of all visible rows before those indices.
+    ///
+    /// Lookups are `O(log² n)`, then only the user lines between two port lines may need to be walked.
+    pub(super) fn locate_row(&self, row: usize) -> (usize, usize, usize) {
+        self.sync_height_index();
+        let index = self.height_index.borrow();
+
+        let rx = &self.styled_lines.rx;
+
+        let rows_before = |rx_index: usize| -> usize {
+            index.rx.prefix_sum(rx_index) + index.tx.prefix_sum(self.tx_before(&rx[rx_index]))
+        };
+
+        // Binary search for the last port line starting at or before the requested row.
+        let (mut low, mut high) = (0, rx.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if rows_before(mid) <= row {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        match low.checked_sub(1) {
+            Some(rx_index) => {
+                let tx_index = self.tx_before(&rx[rx_index]);
+                (rx_index, tx_index, rows_before(rx_index))
+            }
+            // Requested row is before any port lines.
+            None => (0, 0, 0),
+        }
+    }
+
+    /// Total visible rows across all port and user lines.
+    pub(super) fn indexed_height(&self) -> usize {
+        self.sync_height_index();
+        let index = self.height_index.borrow();
+        index.rx.total() + index.tx.total()
+    }
+
+    /// Request the whole height index be rebuilt on next use.
+    pub(super) fn mark_height_index_stale(&self) {
+        self.height_index.borrow_mut().stale = true;
+    }
+}
@@ -0,0 +1,223 @@
+use std::{borrow::Cow, fmt::Write as _, io::Write};
+
+use chrono::Local;
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Line,
+};
+
+use crate::{config_adjacent_path, traits::LineHelpers};
+
+use super::Buffer;
+
+/// Colors assumed for text without its own, matching a typical dark terminal.
+const DEFAULT_FG: &str = "#d0d0d0";
+const DEFAULT_BG: &str = "#101010";
+
+/// xterm's values for the 16 named colors, in ANSI order.
+const ANSI_COLORS: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+impl Buffer {
+    /// Write every shown line, as currently styled, to a standalone HTML file in the logs folder,
+    /// returning its path.
+    pub fn export_html(&self, title: &str) -> Result<camino::Utf8PathBuf, std::io::Error> {
+        let lines = self.buflines_iter_from(0, 0).map(|l| match self.styled(l) {
+            Cow::Borrowed(styled) => styled.as_line(self.line_render_settings()),
+            Cow::Owned(restyled) => restyled.as_line(self.line_render_settings()).new_owned(),
+        });
+
+        let logs_dir = config_adjacent_path("logs/");
+        fs_err::create_dir_all(&logs_dir)?;
+        let path = logs_dir.join(
+            Local::now()
+                .format("yap-%Y-%m-%d_%H-%M-%S.html")
+                .to_string(),
+        );
+        let mut file = std::io::BufWriter::new(fs_err::File::create(&path)?);
+        write_html(&mut file, title, lines)?;
+        Ok(path)
+    }
+}
+
+/// Write the lines as a page of preformatted text, with each span's style inlined.
+fn write_html<'a>(
+    writer: &mut impl Write,
+    title: &str,
+    lines: impl IntoIterator<Item = Line<'a>>,
+) -> Result<(), std::io::Error> {
+    let title = escape(title);
+    write!(
+        writer,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+        <style>body {{ margin: 0; background: {DEFAULT_BG}; }} \
+        pre {{ margin: 0; padding: 1em; color: {DEFAULT_FG}; \
+        font-family: ui-monospace, Menlo, Consolas, monospace; }}</style>\n\
+        </head>\n<body>\n<pre>"
+    )?;
+
+    let mut html = String::new();
+    for line in lines {
+        html.clear();
+        for span in &line.spans {
+            let style = line.style.patch(span.style);
+            let css = span_css(style);
+            let content = escape(&span.content);
+            if css.is_empty() {
+                html.push_str(&content);
+            } else {
+                _ = write!(html, "<span style=\"{css}\">{content}</span>");
+            }
+        }
+        html.push('\n');
+        writer.write_all(html.as_bytes())?;
+    }
+
+    writer.write_all(b"</pre>\n</body>\n</html>\n")?;
+    writer.flush()
+}
+
+fn span_css(style: Style) -> String {
+    let mut fg = style.fg.and_then(css_color);
+    let mut bg = style.bg.and_then(css_color);
+    if style.add_modifier.contains(Modifier::REVERSED) {
+        (fg, bg) = (
+            Some(bg.unwrap_or(Cow::Borrowed(DEFAULT_BG))),
+            Some(fg.unwrap_or(Cow::Borrowed(DEFAULT_FG))),
+        );
+    }
+
+    let mut css = String::new();
+    if let Some(fg) = fg {
+        _ = write!(css, "color:{fg};");
+    }
+    if let Some(bg) = bg {
+        _ = write!(css, "background:{bg};");
+    }
+
+    let modifiers = style.add_modifier;
+    if modifiers.contains(Modifier::BOLD) {
+        css.push_str("font-weight:bold;");
+    }
+    if modifiers.contains(Modifier::DIM) {
+        css.push_str("opacity:0.6;");
+    }
+    if modifiers.contains(Modifier::ITALIC) {
+        css.push_str("font-style:italic;");
+    }
+    match (
+        modifiers.contains(Modifier::UNDERLINED),
+        modifiers.contains(Modifier::CROSSED_OUT),
+    ) {
+        (true, true) => css.push_str("text-decoration:underline line-through;"),
+        (true, false) => css.push_str("text-decoration:underline;"),
+        (false, true) => css.push_str("text-decoration:line-through;"),
+        (false, false) => (),
+    }
+    if modifiers.contains(Modifier::HIDDEN) {
+        css.push_str("visibility:hidden;");
+    }
+    css
+}
+
+/// The CSS equivalent of a terminal color, or `None` to leave the default.
+fn css_color(color: Color) -> Option<Cow<'static, str>> {
+    let indexed = |index: u8| -> Cow<'static, str> {
+        match index {
+            0..16 => Cow::Borrowed(ANSI_COLORS[index as usize]),
+            // The 6x6x6 color cube.
+            16..232 => {
+                let level = |value: u8| if value == 0 { 0 } else { value * 40 + 55 };
+                let cube = index - 16;
+                let (r, g, b) = (level(cube / 36), level(cube / 6 % 6), level(cube % 6));
+                Cow::Owned(format!("#{r:02x}{g:02x}{b:02x}"))
+            }
+            // The grayscale ramp.
+            232.. => {
+                let gray = (index - 232) * 10 + 8;
+                Cow::Owned(format!("#{gray:02x}{gray:02x}{gray:02x}"))
+            }
+        }
+    };
+
+    Some(match color {
+        Color::Reset => return None,
+        Color::Black => indexed(0),
+        Color::Red => indexed(1),
+        Color::Green => indexed(2),
+        Color::Yellow => indexed(3),
+        Color::Blue => indexed(4),
+        Color::Magenta => indexed(5),
+        Color::Cyan => indexed(6),
+        Color::Gray => indexed(7),
+        Color::DarkGray => indexed(8),
+        Color::LightRed => indexed(9),
+        Color::LightGreen => indexed(10),
+        Color::LightYellow => indexed(11),
+        Color::LightBlue => indexed(12),
+        Color::LightMagenta => indexed(13),
+        Color::LightCyan => indexed(14),
+        Color::White => indexed(15),
+        Color::Indexed(index) => indexed(index),
+        Color::Rgb(r, g, b) => Cow::Owned(format!("#{r:02x}{g:02x}{b:02x}")),
+    })
+}
+
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"']) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{style::Stylize, text::Span};
+
+    use super::*;
+
+    #[test]
+    fn lines_written_with_inline_styles() {
+        let lines = [
+            Line::from(vec![
+                Span::raw("plain <tag> & "),
+                Span::raw("\"red\"").red().bold(),
+            ]),
+            Line::from(vec![Span::raw("swapped").reversed().on_blue()]).italic(),
+            Line::from(vec![
+                Span::raw("cube").fg(Color::Indexed(196)),
+                Span::raw("gray").bg(Color::Indexed(244)),
+            ]),
+        ];
+        let mut html = Vec::new();
+        write_html(&mut html, "COM1 & friends", lines).unwrap();
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.contains("<title>COM1 &amp; friends</title>"));
+        let body = html
+            .split_once("<pre>")
+            .and_then(|(_, rest)| rest.split_once("</pre>"))
+            .unwrap()
+            .0;
+        assert_eq!(
+            body,
+            "plain &lt;tag&gt; &amp; \
+            <span style=\"color:#cd0000;font-weight:bold;\">&quot;red&quot;</span>\n\
+            <span style=\"color:#0000ee;background:#d0d0d0;font-style:italic;\">swapped</span>\n\
+            <span style=\"color:#ff0000;\">cube</span>\
+            <span style=\"background:#808080;\">gray</span>\n"
+        );
+    }
+}
@@ -1,11 +1,7 @@
-use std::{
-    borrow::Cow,
-    io::{Seek, Write},
-    thread::JoinHandle,
-    time::Duration,
-};
+use std::{borrow::Cow, io::Write, thread::JoinHandle, time::Duration};
 
 #[cfg(feature = "defmt")]
+use std::io::Seek;
 use std::sync::Arc;
 
 use chrono::{DateTime, Local};
@@ -15,18 +11,32 @@ use serialport::SerialPortInfo;
 use tracing::{debug, error, warn};
 
 #[cfg(feature = "defmt")]
-use crate::{changed, settings::Defmt};
+use crate::settings::Defmt;
 
 use crate::{
-    app::Event, config_adjacent_path, serial::ReconnectType, settings::Logging,
-    traits::ByteSuffixCheck,
+    TcpStreamHealth, app::Event, changed, config_adjacent_path, serial::ReconnectType,
+    settings::Logging, traits::ByteSuffixCheck,
 };
 
 use super::{LineEnding, line_ending_iter};
 
+mod compression;
+mod forwarding;
+use forwarding::LineForwarder;
+mod pcapng;
+pub use pcapng::write_pcapng;
+mod recent;
+use recent::RecentTraffic;
+mod rotation;
+#[cfg(feature = "defmt")]
+use rotation::open_appending;
+use rotation::{CaptureFile, file_name_format};
+
 #[cfg_attr(test, derive(Clone))]
 pub struct LoggingHandle {
     command_tx: Sender<LoggingCommand>,
+    forwarding_health: Arc<TcpStreamHealth>,
+    paused: bool,
 }
 
 pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.9f";
@@ -44,14 +54,18 @@ pub enum LoggingCommand {
         back_to_port_selection: bool,
     },
     BeginRelogging(Receiver<SyncBatch>),
-    RxBytes(DateTime<Local>, Vec<u8>),
+    DumpRecent,
+    RxBytes(DateTime<Local>, u64, Vec<u8>),
     TxBytes(TxPayload),
     LineEndingChange(LineEnding),
     Settings(Logging),
+    Paused(DateTime<Local>, bool),
     #[cfg(feature = "defmt")]
     DefmtSettings(Defmt),
     #[cfg(feature = "defmt")]
     DefmtDecoder(Option<Arc<super::defmt::DefmtDecoder>>),
+    #[cfg(feature = "defmt")]
+    ExtraDefmtDecoders(Vec<Arc<super::defmt::DefmtDecoder>>),
     Shutdown(Sender<()>),
 }
 
@@ -59,11 +73,21 @@ pub enum LoggingCommand {
 // we batch together consecutive RX/TX entries
 // (just to minimize the amount of chatter between threads)
 pub enum SyncBatch {
-    RxBatch(Vec<(DateTime<Local>, Vec<u8>)>),
+    RxBatch(Vec<(DateTime<Local>, u64, Vec<u8>)>),
     TxBatch(Vec<TxPayload>),
     Done,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Which way the bytes went over the port.
+pub enum TrafficDirection {
+    /// Received from the port.
+    Rx,
+    /// Sent by the user.
+    Tx,
+}
+
 enum LineType {
     /// Recieved from port
     Rx,
@@ -77,6 +101,11 @@ enum LineType {
 #[derive(Debug)]
 pub enum LoggingEvent {
     FinishedReconsumption,
+    /// Recent traffic was saved to the given path, either when asked to or by the trigger text.
+    DumpedRecent {
+        path: std::path::PathBuf,
+        triggered: bool,
+    },
     Error(String),
 }
 
@@ -90,17 +119,29 @@ struct LoggingWorker {
     event_tx: Sender<Event>,
     command_rx: Receiver<LoggingCommand>,
 
-    text_file: Option<fs::File>,
-    raw_file: Option<fs::File>,
+    text_file: Option<CaptureFile>,
+    raw_file: Option<CaptureFile>,
+    /// Every RX/TX chunk (and decoded defmt frame) as a JSON record, one per line.
+    jsonl_file: Option<CaptureFile>,
     started_logging_at: Option<DateTime<Local>>,
+    /// How many times logging has begun since the worker started, for `{session}` in file names.
+    session: u32,
     settings: Logging,
     line_ending: LineEnding,
 
     /// Flag for if continued RX content could be appended without adding a newline first.
     last_rx_completed: bool,
 
+    /// Set while the user has paused logging, leaving the files open without writing to them.
+    paused: bool,
+
+    recent: RecentTraffic,
+
     current_port: Option<SerialPortInfo>,
 
+    /// Cleared when forwarding text log lines to a collector fails.
+    forwarding_health: Arc<TcpStreamHealth>,
+
     #[cfg(feature = "defmt")]
     defmt: DefmtKit,
 }
@@ -126,9 +167,17 @@ struct DefmtKit {
     unconsumed: Option<(DateTime<Local>, Vec<u8>)>,
     settings: Defmt,
     decoder: Option<Arc<super::defmt::DefmtDecoder>>,
-    /// Flag if last defmt raw/uncompressed decode attempt failed.
+    /// Tables tried after `decoder` fails to decode a frame.
+    extra_decoders: Vec<Arc<super::defmt::DefmtDecoder>>,
+    /// Flag if last defmt raw/uncompressed decode attempt failed and couldn't be resynced.
     /// Further parsing attempts will not be allowed if set to true.
     defmt_raw_malformed: bool,
+    /// Budget and progress for skipping past corrupted raw/uncompressed frames.
+    raw_resync: super::defmt::RawResync,
+    /// Which table last decoded a frame, see `decode_with_any`.
+    last_matched: std::cell::Cell<usize>,
+    /// JSONL sidecar to the text log, holding each decoded frame as a structured record.
+    records_file: Option<fs::File>,
 }
 
 impl LoggingHandle {
@@ -139,6 +188,7 @@ impl LoggingHandle {
         #[cfg(feature = "defmt")] defmt_settings: Defmt,
     ) -> (Self, JoinHandle<()>) {
         let (command_tx, command_rx) = crossbeam::channel::unbounded();
+        let forwarding_health = Arc::new(TcpStreamHealth::new(true));
 
         let mut worker = LoggingWorker {
             event_tx,
@@ -147,9 +197,14 @@ impl LoggingHandle {
             line_ending,
             text_file: None,
             raw_file: None,
+            jsonl_file: None,
             started_logging_at: None,
+            session: 0,
             last_rx_completed: true,
+            paused: false,
+            recent: RecentTraffic::default(),
             current_port: None,
+            forwarding_health: forwarding_health.clone(),
             #[cfg(feature = "defmt")]
             defmt: DefmtKit {
                 settings: defmt_settings,
@@ -165,7 +220,28 @@ impl LoggingHandle {
             }
         });
 
-        (Self { command_tx }, worker)
+        (
+            Self {
+                command_tx,
+                forwarding_health,
+                paused: false,
+            },
+            worker,
+        )
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    /// Stop or continue writing to the log files, without closing them.
+    pub fn set_paused(&mut self, paused: bool) -> HandleResult<()> {
+        self.command_tx
+            .send(LoggingCommand::Paused(Local::now(), paused))?;
+        self.paused = paused;
+        Ok(())
+    }
+    /// If forwarding text log lines to a collector hasn't failed.
+    pub fn forwarding_ok(&self) -> bool {
+        self.forwarding_health.is_ok()
     }
     pub fn log_port_connected(
         &self,
@@ -179,6 +255,11 @@ impl LoggingHandle {
         ))?;
         Ok(())
     }
+    /// Save the recent traffic kept in memory to the logs folder.
+    pub fn dump_recent(&self) -> HandleResult<()> {
+        self.command_tx.send(LoggingCommand::DumpRecent)?;
+        Ok(())
+    }
     pub(super) fn begin_relogging(&self, receiver: Receiver<SyncBatch>) -> HandleResult<()> {
         self.command_tx
             .send(LoggingCommand::BeginRelogging(receiver))?;
@@ -187,10 +268,11 @@ impl LoggingHandle {
     pub(super) fn log_rx_bytes(
         &self,
         timestamp: DateTime<Local>,
+        monotonic_us: u64,
         bytes: Vec<u8>,
     ) -> HandleResult<()> {
         self.command_tx
-            .send(LoggingCommand::RxBytes(timestamp, bytes))?;
+            .send(LoggingCommand::RxBytes(timestamp, monotonic_us, bytes))?;
         Ok(())
     }
     pub(super) fn log_tx_bytes(
@@ -221,6 +303,15 @@ impl LoggingHandle {
             .send(LoggingCommand::DefmtDecoder(decoder))?;
         Ok(())
     }
+    #[cfg(feature = "defmt")]
+    pub fn update_extra_defmt_decoders(
+        &self,
+        decoders: Vec<Arc<super::defmt::DefmtDecoder>>,
+    ) -> HandleResult<()> {
+        self.command_tx
+            .send(LoggingCommand::ExtraDefmtDecoders(decoders))?;
+        Ok(())
+    }
     pub(super) fn update_line_ending(&self, line_ending: LineEnding) -> HandleResult<()> {
         self.command_tx
             .send(LoggingCommand::LineEndingChange(line_ending))?;
@@ -257,6 +348,15 @@ impl LoggingHandle {
     }
 }
 
+impl Drop for LoggingWorker {
+    // Compressed files are unreadable without their ends, so make sure they get them.
+    fn drop(&mut self) {
+        if let Err(e) = self.close_files(true) {
+            error!("Failed closing log files on drop! {e}");
+        }
+    }
+}
+
 impl LoggingWorker {
     fn work_loop(&mut self) -> Result<(), LoggingError> {
         loop {
@@ -280,9 +380,23 @@ impl LoggingWorker {
         timestamp: DateTime<Local>,
         connected_to: Option<&SerialPortInfo>,
     ) -> Result<(), std::io::Error> {
-        if !self.settings.log_connection_events {
+        if !self.settings.log_connection_events || self.paused {
             return Ok(());
         }
+        let message = if let Some(port_info) = connected_to {
+            let port_name = &port_info.port_name;
+            format!("Connected to {port_name}!")
+        } else {
+            "Disconnected from port!".to_owned()
+        };
+        self.write_text_marker(timestamp, &message)
+    }
+    /// Write a timestamped message on its own line in the text log, if there is one.
+    fn write_text_marker(
+        &mut self,
+        timestamp: DateTime<Local>,
+        message: &str,
+    ) -> Result<(), std::io::Error> {
         if let Some(text_file) = &mut self.text_file {
             if !self.last_rx_completed {
                 write_line_ending(text_file)?;
@@ -296,13 +410,7 @@ impl LoggingWorker {
             };
 
             let time = timestamp.format(timestamp_format);
-
-            let text = if let Some(port_info) = connected_to {
-                let port_name = &port_info.port_name;
-                format!("{time} | Connected to {port_name}!")
-            } else {
-                format!("{time} | Disconnected from port!")
-            };
+            let text = format!("{time} | {message}");
 
             text_file.write_all(text.as_bytes())?;
             write_line_ending(text_file)?;
@@ -313,6 +421,9 @@ impl LoggingWorker {
     fn handle_command(&mut self, cmd: LoggingCommand) -> Result<(), LoggingError> {
         match cmd {
             LoggingCommand::PortConnected(timestamp, port_info, _reconnect_type) => {
+                if self.settings.new_file_per_connection {
+                    self.close_files(false)?;
+                }
                 self.create_and_close_log_files(timestamp, &port_info)?;
                 self.log_connection_event(timestamp, Some(&port_info))?;
                 self.current_port = Some(port_info);
@@ -325,67 +436,59 @@ impl LoggingWorker {
                     )?;
                     return Ok(());
                 };
+                // Lines already sent once shouldn't be forwarded again.
+                let forwarder = self
+                    .text_file
+                    .as_mut()
+                    .and_then(CaptureFile::take_forwarder);
+                if let Some(text) = self.text_file.take() {
+                    let mut text = text.truncate()?;
+                    write_header_to_text_file(&mut text, port_info)?;
+                    self.text_file = Some(text);
+                }
+                if let Some(raw) = self.raw_file.take() {
+                    self.raw_file = Some(raw.truncate()?);
+                }
+                if let Some(jsonl) = self.jsonl_file.take() {
+                    self.jsonl_file = Some(jsonl.truncate()?);
+                }
+                #[cfg(feature = "defmt")]
+                if let Some(records) = &mut self.defmt.records_file {
+                    records.set_len(0)?;
+                    records.seek(std::io::SeekFrom::Start(0))?;
+                }
+
+                let result = self.consume_sync_batches(receiver);
                 if let Some(text) = &mut self.text_file {
-                    text.set_len(0)?;
-                    text.seek(std::io::SeekFrom::Start(0))?;
-                    write_header_to_text_file(text, port_info)?;
-                }
-                if let Some(raw) = &mut self.raw_file {
-                    raw.set_len(0)?;
-                    raw.seek(std::io::SeekFrom::Start(0))?;
-                }
-
-                for msg in receiver.into_iter() {
-                    match msg {
-                        SyncBatch::RxBatch(rx_batch) => {
-                            for (timestamp, bytes) in rx_batch {
-                                if let Some(raw_file) = &mut self.raw_file {
-                                    raw_file.write_all(&bytes)?;
-                                }
-                                if self.text_file.is_some() {
-                                    self.consume_bytes_for_text_file(timestamp, bytes)?;
-                                }
-                            }
-                        }
-                        SyncBatch::TxBatch(tx_batch) => {
-                            for TxPayload {
-                                timestamp,
-                                bytes,
-                                line_ending,
-                            } in tx_batch
-                            {
-                                let Some(text_file) = &mut self.text_file else {
-                                    warn!("not logging tx bytes, no text file!");
-                                    return Ok(());
-                                };
-                                if !self.settings.log_user_input {
-                                    warn!("not logging tx bytes, user log disabled!");
-                                    return Ok(());
-                                }
-                                self.last_rx_completed = write_buffer_to_text_file(
-                                    timestamp,
-                                    &self.settings.timestamp,
-                                    &bytes,
-                                    self.last_rx_completed,
-                                    text_file,
-                                    &self.line_ending,
-                                    // self.settings.timestamps,
-                                    LineType::Tx { line_ending },
-                                )?;
-                            }
-                        }
-                        SyncBatch::Done => {
-                            self.flush_files(false)?;
-                            self.event_tx
-                                .send(LoggingEvent::FinishedReconsumption.into())?;
-                        }
-                    }
+                    text.set_forwarder(forwarder);
                 }
+                result?;
             }
-            LoggingCommand::RxBytes(timestamp, buf) => {
+            LoggingCommand::DumpRecent => self.dump_recent(false)?,
+            LoggingCommand::RxBytes(timestamp, monotonic_us, buf) => {
+                // Kept even while paused, since it's not written anywhere until dumped.
+                if self
+                    .recent
+                    .push(&self.settings, timestamp, monotonic_us, &buf)
+                {
+                    self.dump_recent(true)?;
+                }
+                if self.paused {
+                    return Ok(());
+                }
+                self.rotate_files_if_due()?;
                 if let Some(raw_file) = &mut self.raw_file {
                     raw_file.write_all(&buf)?;
                 }
+                if let Some(jsonl_file) = &mut self.jsonl_file {
+                    write_traffic_record(
+                        jsonl_file,
+                        timestamp,
+                        Some(monotonic_us),
+                        TrafficDirection::Rx,
+                        &buf,
+                    )?;
+                }
                 if self.text_file.is_some() {
                     self.consume_bytes_for_text_file(timestamp, buf)?;
                 }
@@ -395,14 +498,22 @@ impl LoggingWorker {
                 bytes,
                 line_ending,
             }) => {
-                let Some(text_file) = &mut self.text_file else {
-                    warn!("not logging tx bytes, no text file!");
+                if self.paused {
                     return Ok(());
-                };
+                }
+                self.rotate_files_if_due()?;
                 if !self.settings.log_user_input {
                     warn!("not logging tx bytes, user log disabled!");
                     return Ok(());
                 }
+                if let Some(jsonl_file) = &mut self.jsonl_file {
+                    let sent = [bytes.as_slice(), line_ending.as_slice()].concat();
+                    write_traffic_record(jsonl_file, timestamp, None, TrafficDirection::Tx, &sent)?;
+                }
+                let Some(text_file) = &mut self.text_file else {
+                    warn!("not logging tx bytes, no text file!");
+                    return Ok(());
+                };
                 self.last_rx_completed = write_buffer_to_text_file(
                     timestamp,
                     &self.settings.timestamp,
@@ -415,9 +526,37 @@ impl LoggingWorker {
                 )?;
             }
             LoggingCommand::LineEndingChange(new_ending) => self.line_ending = new_ending,
+            LoggingCommand::Paused(timestamp, paused) => {
+                if paused == self.paused {
+                    return Ok(());
+                }
+                if paused {
+                    self.write_text_marker(timestamp, "Logging paused.")?;
+                    self.flush_files(false)?;
+                } else {
+                    // Whatever frame was partway through when paused won't be finished.
+                    #[cfg(feature = "defmt")]
+                    {
+                        _ = self.defmt.unconsumed.take();
+                    }
+                    self.write_text_marker(timestamp, "Logging resumed.")?;
+                }
+                self.paused = paused;
+            }
             LoggingCommand::Settings(new) => {
-                _ = std::mem::replace(&mut self.settings, new);
-                // let new = &self.settings;
+                let old = std::mem::replace(&mut self.settings, new);
+                let new = &self.settings;
+
+                if changed!(old, new, forward_to, forward_address)
+                    && let Some(text_file) = &mut self.text_file
+                {
+                    text_file.set_forwarder(None);
+                    text_file.set_forwarder(connect_forwarder(
+                        new,
+                        &self.forwarding_health,
+                        &self.event_tx,
+                    ));
+                }
 
                 if let Some(current_port) = self.current_port.clone() {
                     self.create_and_close_log_files(Local::now(), &current_port)?;
@@ -430,6 +569,7 @@ impl LoggingWorker {
 
                 if changed!(old, new, defmt_parsing) && self.defmt.unconsumed.is_some() {
                     self.defmt.defmt_raw_malformed = false;
+                    self.defmt.raw_resync.reset();
                     _ = self.defmt.unconsumed.take();
                 }
             }
@@ -437,11 +577,16 @@ impl LoggingWorker {
             LoggingCommand::DefmtDecoder(decoder) => {
                 self.defmt.decoder = decoder;
             }
+            #[cfg(feature = "defmt")]
+            LoggingCommand::ExtraDefmtDecoders(decoders) => {
+                self.defmt.extra_decoders = decoders;
+            }
             LoggingCommand::PortDisconnect {
                 timestamp,
                 back_to_port_selection,
             } => {
-                if self.raw_file.is_none() && self.text_file.is_none() {
+                if self.raw_file.is_none() && self.text_file.is_none() && self.jsonl_file.is_none()
+                {
                     return Ok(());
                 }
                 self.log_connection_event(timestamp, None)?;
@@ -456,6 +601,75 @@ impl LoggingWorker {
         Ok(())
     }
 
+    fn consume_sync_batches(&mut self, receiver: Receiver<SyncBatch>) -> Result<(), LoggingError> {
+        for msg in receiver.into_iter() {
+            match msg {
+                SyncBatch::RxBatch(rx_batch) => {
+                    for (timestamp, monotonic_us, bytes) in rx_batch {
+                        if let Some(raw_file) = &mut self.raw_file {
+                            raw_file.write_all(&bytes)?;
+                        }
+                        if let Some(jsonl_file) = &mut self.jsonl_file {
+                            write_traffic_record(
+                                jsonl_file,
+                                timestamp,
+                                Some(monotonic_us),
+                                TrafficDirection::Rx,
+                                &bytes,
+                            )?;
+                        }
+                        if self.text_file.is_some() {
+                            self.consume_bytes_for_text_file(timestamp, bytes)?;
+                        }
+                    }
+                }
+                SyncBatch::TxBatch(tx_batch) => {
+                    for TxPayload {
+                        timestamp,
+                        bytes,
+                        line_ending,
+                    } in tx_batch
+                    {
+                        if !self.settings.log_user_input {
+                            warn!("not logging tx bytes, user log disabled!");
+                            return Ok(());
+                        }
+                        // Reloggable bytes already include the line ending.
+                        if let Some(jsonl_file) = &mut self.jsonl_file {
+                            write_traffic_record(
+                                jsonl_file,
+                                timestamp,
+                                None,
+                                TrafficDirection::Tx,
+                                &bytes,
+                            )?;
+                        }
+                        let Some(text_file) = &mut self.text_file else {
+                            warn!("not logging tx bytes, no text file!");
+                            return Ok(());
+                        };
+                        self.last_rx_completed = write_buffer_to_text_file(
+                            timestamp,
+                            &self.settings.timestamp,
+                            &bytes,
+                            self.last_rx_completed,
+                            text_file,
+                            &self.line_ending,
+                            // self.settings.timestamps,
+                            LineType::Tx { line_ending },
+                        )?;
+                    }
+                }
+                SyncBatch::Done => {
+                    self.flush_files(false)?;
+                    self.event_tx
+                        .send(LoggingEvent::FinishedReconsumption.into())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn consume_bytes_for_text_file(
         &mut self,
         timestamp: DateTime<Local>,
@@ -552,14 +766,24 @@ impl LoggingWorker {
             return Ok(());
         };
 
-        use crate::buffer::defmt::rzcobs_decode;
+        use crate::buffer::defmt::{decode_with_any, rzcobs_decode};
         use defmt_decoder::DecodeError;
 
+        let extra_decoders = &self.defmt.extra_decoders;
+        let last_matched = &self.defmt.last_matched;
+        let decode = |bytes: &[u8]| {
+            let decoders = std::iter::once(decoder)
+                .chain(extra_decoders)
+                .map(Arc::as_ref);
+            decode_with_any(decoders, bytes, last_matched)
+        };
+
         match self.defmt.settings.defmt_parsing {
             DefmtSupport::Disabled => unreachable!("shouldn't be called when disabled"),
             DefmtSupport::Raw => loop {
-                match decoder.table.decode(unconsumed_buf) {
-                    Ok((decoded_frame, _consumed)) => {
+                match decode(unconsumed_buf) {
+                    Ok((decoded_frame, consumed, frame_decoder)) => {
+                        self.defmt.raw_resync.frame_decoded();
                         self.last_rx_completed = write_defmt_frame_to_text_file(
                             *timestamp,
                             &self.settings.timestamp,
@@ -567,22 +791,48 @@ impl LoggingWorker {
                             self.last_rx_completed,
                             text_file,
                         )?;
+                        write_defmt_record(
+                            *timestamp,
+                            &decoded_frame,
+                            frame_decoder,
+                            self.defmt.records_file.as_mut(),
+                            self.jsonl_file.as_mut(),
+                        )?;
+                        unconsumed_buf.drain(..consumed);
                     }
                     Err(DecodeError::UnexpectedEof) => break,
                     Err(DecodeError::Malformed) => {
-                        self.defmt.defmt_raw_malformed = true;
+                        use crate::buffer::defmt::RawResyncOutcome;
+
+                        let outcome = self
+                            .defmt
+                            .raw_resync
+                            .find_next_frame(unconsumed_buf, |bytes| {
+                                decode(bytes).map(|(_, consumed, _)| consumed)
+                            });
+                        let text = match outcome {
+                            RawResyncOutcome::Skip(_) => "malformed defmt packet, skipping ahead",
+                            RawResyncOutcome::NeedMoreData => break,
+                            RawResyncOutcome::GiveUp => {
+                                self.defmt.defmt_raw_malformed = true;
+                                "malformed defmt packet, ceasing further decode attempts"
+                            }
+                        };
 
                         self.last_rx_completed = write_buffer_to_text_file(
                             *timestamp,
                             &self.settings.timestamp,
-                            "malformed defmt packet, ceasing further decode attempts".as_bytes(),
+                            text.as_bytes(),
                             self.last_rx_completed,
                             text_file,
                             &LineEnding::None,
                             LineType::DefmtRx,
                         )?;
 
-                        break;
+                        match outcome {
+                            RawResyncOutcome::Skip(skipped) => _ = unconsumed_buf.drain(..skipped),
+                            _ => break,
+                        }
                     }
                 }
             },
@@ -614,8 +864,8 @@ impl LoggingWorker {
                     continue;
                 };
 
-                match decoder.table.decode(&uncompressed) {
-                    Ok((decoded_frame, _consumed)) => {
+                match decode(&uncompressed) {
+                    Ok((decoded_frame, _consumed, frame_decoder)) => {
                         self.last_rx_completed = write_defmt_frame_to_text_file(
                             *timestamp,
                             &self.settings.timestamp,
@@ -623,6 +873,13 @@ impl LoggingWorker {
                             self.last_rx_completed,
                             text_file,
                         )?;
+                        write_defmt_record(
+                            *timestamp,
+                            &decoded_frame,
+                            frame_decoder,
+                            self.defmt.records_file.as_mut(),
+                            self.jsonl_file.as_mut(),
+                        )?;
                     }
                     Err(_) => {
                         self.last_rx_completed = write_buffer_to_text_file(
@@ -669,8 +926,8 @@ impl LoggingWorker {
                             continue;
                         };
 
-                        match decoder.table.decode(&uncompressed) {
-                            Ok((decoded_frame, _consumed)) => {
+                        match decode(&uncompressed) {
+                            Ok((decoded_frame, _consumed, frame_decoder)) => {
                                 self.last_rx_completed = write_defmt_frame_to_text_file(
                                     *timestamp,
                                     &self.settings.timestamp,
@@ -678,6 +935,13 @@ impl LoggingWorker {
                                     self.last_rx_completed,
                                     text_file,
                                 )?;
+                                write_defmt_record(
+                                    *timestamp,
+                                    &decoded_frame,
+                                    frame_decoder,
+                                    self.defmt.records_file.as_mut(),
+                                    self.jsonl_file.as_mut(),
+                                )?;
                             }
                             Err(_) => {
                                 self.last_rx_completed = write_buffer_to_text_file(
@@ -716,30 +980,29 @@ impl LoggingWorker {
     }
 
     fn flush_files(&mut self, ignore_errors: bool) -> Result<(), std::io::Error> {
-        let flush_file = |f: &mut fs::File| -> Result<(), std::io::Error> {
-            match f.flush() {
-                Ok(()) => (),
-                Err(e) if ignore_errors => error!("Error flushing file, ignoring: {e}"),
-                Err(e) => {
-                    error!("Error flushing file: {e}");
-                    return Err(e);
-                }
+        let check = |result: Result<(), std::io::Error>| match result {
+            Ok(()) => Ok(()),
+            Err(e) if ignore_errors => {
+                error!("Error flushing file, ignoring: {e}");
+                Ok(())
             }
-            match f.sync_all() {
-                Ok(()) => (),
-                Err(e) if ignore_errors => error!("Error flushing file, ignoring: {e}"),
-                Err(e) => {
-                    error!("Error flushing file: {e}");
-                    return Err(e);
-                }
+            Err(e) => {
+                error!("Error flushing file: {e}");
+                Err(e)
             }
-            Ok(())
         };
-        if let Some(raw_file) = &mut self.raw_file {
-            flush_file(raw_file)?;
+        let capture_files = [
+            &mut self.raw_file,
+            &mut self.text_file,
+            &mut self.jsonl_file,
+        ];
+        for capture_file in capture_files.into_iter().flatten() {
+            check(capture_file.sync())?;
         }
-        if let Some(text_file) = &mut self.text_file {
-            flush_file(text_file)?;
+        #[cfg(feature = "defmt")]
+        if let Some(records_file) = &mut self.defmt.records_file {
+            check(records_file.flush())?;
+            check(records_file.sync_all())?;
         }
 
         Ok(())
@@ -749,8 +1012,23 @@ impl LoggingWorker {
         _ = self.started_logging_at.take();
 
         self.flush_files(ignore_errors)?;
-        _ = self.raw_file.take();
-        _ = self.text_file.take();
+        let capture_files = [
+            self.raw_file.take(),
+            self.text_file.take(),
+            self.jsonl_file.take(),
+        ];
+        for capture_file in capture_files.into_iter().flatten() {
+            match capture_file.finish() {
+                Ok(()) => (),
+                Err(e) if ignore_errors => error!("Error closing file, ignoring: {e}"),
+                Err(e) => {
+                    error!("Error closing file: {e}");
+                    return Err(e.into());
+                }
+            }
+        }
+        #[cfg(feature = "defmt")]
+        let _ = self.defmt.records_file.take();
 
         _ = self.current_port.take();
 
@@ -772,20 +1050,38 @@ impl LoggingWorker {
             }
         }
 
-        let make_binary_log = || -> Result<fs::File, std::io::Error> {
-            let timestamped_name = started_at.format("yap-%Y-%m-%d_%H-%M-%S.bin");
-
-            fs::File::create(config_adjacent_path(format!("logs/{timestamped_name}")))
+        let settings = &self.settings;
+        let wants_files = settings.log_text_to_file
+            || settings.log_raw_input_to_file
+            || settings.log_jsonl_to_file;
+        // Files opened later in the same session take the name it began with.
+        let started_at = match self.started_logging_at {
+            Some(started_logging_at) => started_logging_at,
+            None if wants_files => {
+                self.session += 1;
+                *self.started_logging_at.insert(started_at)
+            }
+            None => started_at,
         };
-
-        let make_text_log = |port_info: &SerialPortInfo| -> Result<fs::File, std::io::Error> {
-            let timestamped_name = started_at.format("yap-%Y-%m-%d_%H-%M-%S.txt");
-
-            let path = config_adjacent_path(format!("logs/{timestamped_name}"));
-            fs::File::create(path).and_then(|mut file| {
-                write_header_to_text_file(&mut file, port_info)?;
-                Ok(file)
-            })
+        let name_format = file_name_format(
+            &settings.file_name_template,
+            &port_info.port_name,
+            self.session,
+        );
+
+        let make_binary_log =
+            || CaptureFile::create(name_format.clone(), "bin", started_at, settings);
+
+        let make_jsonl_log =
+            || CaptureFile::create(name_format.clone(), "jsonl", started_at, settings);
+
+        let make_text_log = |port_info: &SerialPortInfo| -> Result<CaptureFile, std::io::Error> {
+            CaptureFile::create(name_format.clone(), "txt", started_at, settings).and_then(
+                |mut file| {
+                    write_header_to_text_file(&mut file, port_info)?;
+                    Ok(file)
+                },
+            )
         };
 
         if self.text_file.is_none() {
@@ -802,9 +1098,7 @@ impl LoggingWorker {
             }
             // Need to close our file
             (false, raw @ Some(_)) => {
-                let mut raw_file = raw.take().unwrap();
-                raw_file.flush()?;
-                raw_file.sync_all()?;
+                raw.take().unwrap().finish()?;
             }
         }
 
@@ -813,23 +1107,143 @@ impl LoggingWorker {
             (true, Some(_)) | (false, None) => (),
             // Need to open a file
             (true, empty_text @ None) => {
-                let new_text = make_text_log(port_info)?;
+                let mut new_text = make_text_log(port_info)?;
+                new_text.set_forwarder(connect_forwarder(
+                    settings,
+                    &self.forwarding_health,
+                    &self.event_tx,
+                ));
                 _ = empty_text.insert(new_text);
             }
             // Need to close our file
             (false, text @ Some(_)) => {
-                let mut text_file = text.take().unwrap();
-                text_file.flush()?;
-                text_file.sync_all()?;
+                text.take().unwrap().finish()?;
+            }
+        }
+
+        match (self.settings.log_jsonl_to_file, &mut self.jsonl_file) {
+            // No action needed
+            (true, Some(_)) | (false, None) => (),
+            // Need to open a file
+            (true, empty_jsonl @ None) => {
+                let new_jsonl = make_jsonl_log()?;
+                _ = empty_jsonl.insert(new_jsonl);
+            }
+            // Need to close our file
+            (false, jsonl @ Some(_)) => {
+                jsonl.take().unwrap().finish()?;
+            }
+        }
+
+        #[cfg(feature = "defmt")]
+        {
+            let make_records_log = || -> Result<fs::File, std::io::Error> {
+                let name = started_at.format(&name_format);
+
+                open_appending(
+                    config_adjacent_path(format!("logs/{name}.defmt.jsonl")).as_std_path(),
+                )
+            };
+
+            // Records are only produced while decoding for the text log.
+            let wants_records = self.settings.log_text_to_file && self.settings.log_defmt_records;
+            match (wants_records, &mut self.defmt.records_file) {
+                // No action needed
+                (true, Some(_)) | (false, None) => (),
+                // Need to open a file
+                (true, empty_records @ None) => {
+                    let new_records = make_records_log()?;
+                    _ = empty_records.insert(new_records);
+                }
+                // Need to close our file
+                (false, records @ Some(_)) => {
+                    let mut records_file = records.take().unwrap();
+                    records_file.flush()?;
+                    records_file.sync_all()?;
+                }
             }
         }
 
         Ok(())
     }
+    fn dump_recent(&mut self, triggered: bool) -> Result<(), LoggingError> {
+        if self.recent.is_empty() {
+            self.event_tx.send(
+                LoggingEvent::Error("No recent traffic kept in memory to save.".into()).into(),
+            )?;
+            return Ok(());
+        }
+        match self.recent.dump(Local::now()) {
+            Ok(path) => self
+                .event_tx
+                .send(LoggingEvent::DumpedRecent { path, triggered }.into())?,
+            Err(e) => {
+                error!("Failed saving recent traffic: {e}");
+                self.event_tx.send(
+                    LoggingEvent::Error(format!("Couldn't save recent traffic: {e}")).into(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+    /// Start new log files if the current ones have grown too large or old.
+    fn rotate_files_if_due(&mut self) -> Result<(), LoggingError> {
+        let now = Local::now();
+        if let Some(raw_file) = self
+            .raw_file
+            .take_if(|raw| raw.rotation_due(&self.settings, now))
+        {
+            self.raw_file = Some(raw_file.rotate(&self.settings, now)?);
+        }
+        if let Some(jsonl_file) = self
+            .jsonl_file
+            .take_if(|jsonl| jsonl.rotation_due(&self.settings, now))
+        {
+            self.jsonl_file = Some(jsonl_file.rotate(&self.settings, now)?);
+        }
+        // Only between lines, so none get split across files.
+        if !self.last_rx_completed {
+            return Ok(());
+        }
+        if let Some(text_file) = self
+            .text_file
+            .take_if(|text| text.rotation_due(&self.settings, now))
+        {
+            let mut text_file = text_file.rotate(&self.settings, now)?;
+            if let Some(port_info) = &self.current_port {
+                write_header_to_text_file(&mut text_file, port_info)?;
+            }
+            self.text_file = Some(text_file);
+        }
+        Ok(())
+    }
+}
+
+/// Connect to the collector that text log lines are forwarded to, if enabled,
+/// reporting any failure to the UI rather than stopping the text log from being made.
+fn connect_forwarder(
+    settings: &Logging,
+    health: &Arc<TcpStreamHealth>,
+    event_tx: &Sender<Event>,
+) -> Option<LineForwarder> {
+    match LineForwarder::connect(settings, health.clone(), event_tx.clone()) {
+        Ok(forwarder) => forwarder,
+        Err(e) => {
+            error!("Failed starting log forwarder: {e}");
+            _ = event_tx.send(
+                LoggingEvent::Error(format!(
+                    "Couldn't forward to {}: {e}",
+                    settings.forward_address
+                ))
+                .into(),
+            );
+            None
+        }
+    }
 }
 
 fn write_header_to_text_file(
-    file: &mut fs::File,
+    file: &mut impl Write,
     // started_at: DateTime<Local>,
     // timestamp_fmt: &str,
     port_info: &SerialPortInfo,
@@ -875,7 +1289,7 @@ fn write_header_to_text_file(
 }
 
 /// Output a line ending, not for rendering [`LineEndings`].
-fn write_line_ending(file: &mut fs::File) -> Result<(), std::io::Error> {
+fn write_line_ending(file: &mut impl Write) -> Result<(), std::io::Error> {
     file.write_all(b"\n")
 }
 
@@ -884,7 +1298,7 @@ fn write_buffer_to_text_file(
     timestamp_fmt: &str,
     bytes: &[u8],
     mut last_line_was_completed: bool,
-    text_file: &mut fs::File,
+    text_file: &mut impl Write,
     line_ending: &LineEnding,
     // with_timestamp: bool,
     line_type: LineType,
@@ -950,7 +1364,7 @@ fn write_defmt_frame_to_text_file(
     timestamp_fmt: &str,
     frame: &defmt_decoder::Frame,
     last_line_was_completed: bool,
-    text_file: &mut fs::File,
+    text_file: &mut impl Write,
 ) -> Result<bool, std::io::Error> {
     if !last_line_was_completed {
         write_line_ending(text_file)?;
@@ -982,6 +1396,128 @@ fn write_defmt_frame_to_text_file(
     Ok(true)
 }
 
+#[derive(serde::Serialize)]
+/// One chunk of traffic (or decoded defmt frame), as written to the JSON Lines log.
+struct TrafficRecord<'a> {
+    /// When the chunk arrived or was sent, in RFC 3339 format.
+    timestamp: String,
+    /// When the chunk arrived in microseconds from a monotonic clock,
+    /// for timing between chunks that isn't thrown off by the system clock being adjusted.
+    ///
+    /// Only meaningful relative to other records from the same run of yap.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    monotonic_us: Option<u64>,
+    direction: TrafficDirection,
+    /// The bytes as-is, base64 encoded.
+    ///
+    /// Absent for defmt frames, since their bytes are already in an earlier RX record.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw: Option<String>,
+    /// The bytes decoded as UTF-8, with invalid sequences replaced.
+    text: Cow<'a, str>,
+    #[cfg(feature = "defmt")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    defmt: Option<&'a DefmtMetadata<'a>>,
+}
+
+/// Appends the bytes as a single-line JSON record.
+fn write_traffic_record(
+    jsonl_file: &mut impl Write,
+    timestamp: DateTime<Local>,
+    monotonic_us: Option<u64>,
+    direction: TrafficDirection,
+    bytes: &[u8],
+) -> Result<(), std::io::Error> {
+    use base64::Engine;
+
+    let record = TrafficRecord {
+        timestamp: timestamp.to_rfc3339(),
+        monotonic_us,
+        direction,
+        raw: Some(base64::engine::general_purpose::STANDARD.encode(bytes)),
+        text: String::from_utf8_lossy(bytes),
+        #[cfg(feature = "defmt")]
+        defmt: None,
+    };
+
+    serde_json::to_writer(&mut *jsonl_file, &record)?;
+    write_line_ending(jsonl_file)
+}
+
+#[cfg(feature = "defmt")]
+#[derive(serde::Serialize)]
+/// A decoded defmt frame's contents and where it was logged from.
+struct DefmtMetadata<'a> {
+    level: Option<&'static str>,
+    module: Option<&'a str>,
+    file: Option<String>,
+    line: Option<u64>,
+    message: String,
+    /// Device's timestamp as rendered by the frame's format string.
+    device_timestamp: Option<String>,
+}
+
+#[cfg(feature = "defmt")]
+#[derive(serde::Serialize)]
+/// A decoded defmt frame, as written to the JSONL sidecar file.
+struct DefmtRecord<'a> {
+    /// When the frame began arriving, in RFC 3339 format.
+    timestamp: String,
+    #[serde(flatten)]
+    metadata: DefmtMetadata<'a>,
+}
+
+#[cfg(feature = "defmt")]
+/// Appends the frame as a single-line JSON record to the sidecar and JSON Lines logs, if either are being logged.
+fn write_defmt_record(
+    timestamp: DateTime<Local>,
+    frame: &defmt_decoder::Frame,
+    decoder: &super::defmt::DefmtDecoder,
+    records_file: Option<&mut fs::File>,
+    jsonl_file: Option<&mut CaptureFile>,
+) -> Result<(), std::io::Error> {
+    if records_file.is_none() && jsonl_file.is_none() {
+        return Ok(());
+    }
+
+    let location = decoder
+        .locations
+        .as_ref()
+        .and_then(|locs| locs.get(&frame.index()));
+
+    let metadata = DefmtMetadata {
+        level: frame.level().map(|level| level.as_str()),
+        module: location.map(|loc| loc.module.as_str()),
+        file: location.map(|loc| loc.file.display().to_string()),
+        line: location.map(|loc| loc.line),
+        message: frame.display_message().to_string(),
+        device_timestamp: frame.display_timestamp().map(|ts| ts.to_string()),
+    };
+
+    if let Some(jsonl_file) = jsonl_file {
+        let record = TrafficRecord {
+            timestamp: timestamp.to_rfc3339(),
+            monotonic_us: None,
+            direction: TrafficDirection::Rx,
+            raw: None,
+            text: Cow::Borrowed(&metadata.message),
+            defmt: Some(&metadata),
+        };
+        serde_json::to_writer(&mut *jsonl_file, &record)?;
+        write_line_ending(jsonl_file)?;
+    }
+
+    if let Some(records_file) = records_file {
+        let record = DefmtRecord {
+            timestamp: timestamp.to_rfc3339(),
+            metadata,
+        };
+        serde_json::to_writer(&mut *records_file, &record)?;
+        write_line_ending(records_file)?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, thiserror::Error)]
 enum LoggingError {
     #[error("logging file error")]
@@ -999,3 +1535,52 @@ impl<T> From<SendError<T>> for LoggingError {
         Self::EventSend
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn traffic_records_round_trip() {
+        let timestamp = Local.with_ymd_and_hms(2024, 5, 17, 12, 0, 0).unwrap();
+        let mut jsonl = Vec::new();
+        write_traffic_record(
+            &mut jsonl,
+            timestamp,
+            Some(1234),
+            TrafficDirection::Rx,
+            b"ok\xFF\r\n",
+        )
+        .unwrap();
+        write_traffic_record(&mut jsonl, timestamp, None, TrafficDirection::Tx, b"help").unwrap();
+
+        let jsonl = String::from_utf8(jsonl).unwrap();
+        let records: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert!(jsonl.ends_with('\n'));
+
+        let [rx, tx] = &records[..] else {
+            unreachable!()
+        };
+        let read_timestamp = DateTime::parse_from_rfc3339(rx["timestamp"].as_str().unwrap());
+        assert_eq!(read_timestamp.unwrap(), timestamp);
+        assert_eq!(rx["monotonic_us"], 1234);
+        assert_eq!(rx["direction"], "rx");
+        assert_eq!(rx["text"], "ok\u{FFFD}\r\n");
+        let raw = base64::engine::general_purpose::STANDARD
+            .decode(rx["raw"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(raw, b"ok\xFF\r\n");
+
+        // Left out entirely, rather than written as null.
+        assert!(tx.get("monotonic_us").is_none());
+        assert_eq!(tx["direction"], "tx");
+        assert_eq!(tx["text"], "help");
+    }
+}
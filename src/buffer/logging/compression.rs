@@ -0,0 +1,120 @@
+use std::io::Write;
+
+use fs_err as fs;
+
+use crate::settings::LogCompression;
+
+/// Destination of a log file's bytes, compressing them first if enabled.
+pub(super) enum CaptureWriter {
+    Plain(fs::File),
+    #[cfg(feature = "log-compression")]
+    Gzip(flate2::write::GzEncoder<fs::File>),
+    #[cfg(feature = "log-compression")]
+    Zstd(zstd::Encoder<'static, fs::File>),
+}
+
+impl CaptureWriter {
+    pub(super) fn new(file: fs::File, compression: LogCompression) -> std::io::Result<Self> {
+        Ok(match compression {
+            LogCompression::None => Self::Plain(file),
+            #[cfg(feature = "log-compression")]
+            LogCompression::Gzip => Self::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            #[cfg(feature = "log-compression")]
+            LogCompression::Zstd => Self::Zstd(zstd::Encoder::new(file, 0)?),
+        })
+    }
+    /// Flush everything written so far through to the disk.
+    ///
+    /// Compressed files stay readable up to this point, even if never finished.
+    pub(super) fn sync(&mut self) -> std::io::Result<()> {
+        self.flush()?;
+        match self {
+            Self::Plain(file) => file.sync_all(),
+            #[cfg(feature = "log-compression")]
+            Self::Gzip(encoder) => encoder.get_mut().sync_all(),
+            #[cfg(feature = "log-compression")]
+            Self::Zstd(encoder) => encoder.get_mut().sync_all(),
+        }
+    }
+    /// End any compressed stream and flush it to disk, handing back the file.
+    pub(super) fn finish(mut self) -> std::io::Result<fs::File> {
+        self.sync()?;
+        let file = match self {
+            Self::Plain(file) => file,
+            #[cfg(feature = "log-compression")]
+            Self::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "log-compression")]
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        file.sync_all()?;
+        Ok(file)
+    }
+}
+
+impl Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(file) => file.write(buf),
+            #[cfg(feature = "log-compression")]
+            Self::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "log-compression")]
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(file) => file.flush(),
+            #[cfg(feature = "log-compression")]
+            Self::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "log-compression")]
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::VariantArray;
+
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    fn decompress(bytes: &[u8], compression: LogCompression) -> Vec<u8> {
+        match compression {
+            LogCompression::None => bytes.to_vec(),
+            #[cfg(feature = "log-compression")]
+            LogCompression::Gzip => {
+                use std::io::Read;
+                let mut decoded = Vec::new();
+                flate2::read::GzDecoder::new(bytes)
+                    .read_to_end(&mut decoded)
+                    .unwrap();
+                decoded
+            }
+            #[cfg(feature = "log-compression")]
+            LogCompression::Zstd => zstd::decode_all(bytes).unwrap(),
+        }
+    }
+
+    #[test]
+    fn finished_files_decompress_to_what_was_written() {
+        let dir = scratch_dir("capture-writer");
+        let written = b"boot\r\nhelp\n".repeat(100);
+        for compression in LogCompression::VARIANTS {
+            let path = dir.join(format!("{compression}.log"));
+            let mut writer =
+                CaptureWriter::new(fs::File::create(&path).unwrap(), *compression).unwrap();
+            writer.write_all(&written[..500]).unwrap();
+            writer.sync().unwrap();
+            writer.write_all(&written[500..]).unwrap();
+            writer.finish().unwrap();
+
+            let bytes = fs::read(&path).unwrap();
+            assert_eq!(decompress(&bytes, *compression), written, "{compression}");
+        }
+        _ = fs::remove_dir_all(&dir);
+    }
+}
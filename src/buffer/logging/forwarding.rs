@@ -0,0 +1,172 @@
+use std::{
+    io::Write,
+    net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Local;
+use crossbeam::channel::{Receiver, Sender, TrySendError, bounded};
+use tracing::{debug, error};
+
+use crate::{
+    TcpStreamHealth,
+    app::Event,
+    settings::{LogForwarding, Logging},
+};
+
+use super::LoggingEvent;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Longest a line can take to send before the collector's considered gone.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Most lines waiting to be sent, past which new ones are dropped rather than hold up logging.
+const QUEUED_LINES: usize = 1024;
+
+/// `user-level` facility (1) times eight, plus `informational` severity (6).
+const SYSLOG_PRIORITY: u8 = 14;
+
+enum Connection {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+    Syslog(UdpSocket),
+}
+
+/// Sends each complete line written through it to a remote collector.
+///
+/// Connecting and sending happen on a thread of its own, so a slow or unreachable collector
+/// never holds up logging. Failures are reported once through `health` and the event channel,
+/// after which nothing more is sent until a new forwarder is connected.
+pub(super) struct LineForwarder {
+    lines_tx: Sender<Vec<u8>>,
+    /// Bytes of the line being written, sent once it ends.
+    line: Vec<u8>,
+}
+
+impl LineForwarder {
+    /// Start forwarding to the collector set in the settings, or `None` if forwarding is disabled.
+    pub(super) fn connect(
+        settings: &Logging,
+        health: Arc<TcpStreamHealth>,
+        event_tx: Sender<Event>,
+    ) -> Result<Option<Self>, std::io::Error> {
+        if settings.forward_to == LogForwarding::Disabled {
+            return Ok(None);
+        }
+        let (lines_tx, lines_rx) = bounded(QUEUED_LINES);
+        let forwarding = Forwarding {
+            kind: settings.forward_to,
+            address: settings.forward_address.trim().to_owned(),
+            health,
+            event_tx,
+        };
+        std::thread::Builder::new()
+            .name("yap log forwarder".into())
+            .spawn(move || forwarding.run(lines_rx))?;
+
+        Ok(Some(Self {
+            lines_tx,
+            line: Vec::new(),
+        }))
+    }
+    /// Take in bytes being written to the text log, sending off any lines they complete.
+    pub(super) fn push(&mut self, bytes: &[u8]) {
+        let mut lines = bytes.split(|b| *b == b'\n');
+        // The last piece is always the start of a line yet to be ended.
+        let unended = lines.next_back().unwrap_or_default();
+        for ended in lines {
+            self.line.extend_from_slice(ended);
+            let line = std::mem::take(&mut self.line);
+            // Either too far behind or stopped after failing, neither worth waiting on.
+            if let Err(TrySendError::Full(_)) = self.lines_tx.try_send(line) {
+                debug!("Log forwarding fell behind, dropped a line");
+            }
+        }
+        self.line.extend_from_slice(unended);
+    }
+}
+
+/// The forwarding thread's half, connecting and then sending lines until the forwarder's dropped.
+struct Forwarding {
+    kind: LogForwarding,
+    address: String,
+    health: Arc<TcpStreamHealth>,
+    event_tx: Sender<Event>,
+}
+
+impl Forwarding {
+    fn run(self, lines_rx: Receiver<Vec<u8>>) {
+        let (address, mut connection) = match self.open() {
+            Ok(connected) => connected,
+            Err(e) => {
+                error!("Failed connecting to log collector: {e}");
+                self.fail(format!("Couldn't forward to {}: {e}", self.address));
+                return;
+            }
+        };
+        debug!("Forwarding log lines to {address}");
+        self.health.mark_ok();
+
+        for line in lines_rx {
+            if let Err(e) = send_line(&mut connection, &line) {
+                error!("Failed forwarding log line to {address}: {e}");
+                self.fail(format!("Stopped forwarding to {address}: {e}"));
+                return;
+            }
+        }
+    }
+    fn fail(&self, message: String) {
+        self.health.mark_failed();
+        _ = self.event_tx.send(LoggingEvent::Error(message).into());
+    }
+    fn open(&self) -> Result<(SocketAddr, Connection), std::io::Error> {
+        let address = self.address.to_socket_addrs()?.next().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no address found for {}", self.address),
+            )
+        })?;
+
+        let udp = || -> Result<UdpSocket, std::io::Error> {
+            let local: SocketAddr = if address.is_ipv4() {
+                ([0, 0, 0, 0], 0).into()
+            } else {
+                ([0u16; 8], 0).into()
+            };
+            let socket = UdpSocket::bind(local)?;
+            socket.connect(address)?;
+            socket.set_write_timeout(Some(WRITE_TIMEOUT))?;
+            Ok(socket)
+        };
+
+        let connection = match self.kind {
+            LogForwarding::Disabled => unreachable!("checked before starting"),
+            LogForwarding::Tcp => {
+                let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Connection::Tcp(stream)
+            }
+            LogForwarding::Udp => Connection::Udp(udp()?),
+            LogForwarding::Syslog => Connection::Syslog(udp()?),
+        };
+        Ok((address, connection))
+    }
+}
+
+fn send_line(connection: &mut Connection, line: &[u8]) -> Result<(), std::io::Error> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    match connection {
+        Connection::Tcp(stream) => stream.write_all(line).and_then(|_| stream.write_all(b"\n")),
+        Connection::Udp(socket) => socket.send(line).map(|_| ()),
+        Connection::Syslog(socket) => {
+            let mut message = format!(
+                "<{SYSLOG_PRIORITY}>1 {} - yap {} - - ",
+                Local::now().to_rfc3339(),
+                std::process::id()
+            )
+            .into_bytes();
+            message.extend_from_slice(line);
+            socket.send(&message).map(|_| ())
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::io::Write;
+
+use chrono::{DateTime, Local};
+
+use super::TrafficDirection;
+
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+/// `LINKTYPE_USER0`, since there's no link type for plain serial data.
+///
+/// Wireshark can be told how to dissect it under Preferences > Protocols > DLT_USER.
+const LINKTYPE_USER0: u16 = 147;
+
+const OPT_END_OF_OPT: u16 = 0;
+const OPT_IF_NAME: u16 = 2;
+const OPT_EPB_FLAGS: u16 = 2;
+
+impl TrafficDirection {
+    /// Inbound/outbound bits of the `epb_flags` option.
+    fn epb_flags(self) -> u32 {
+        match self {
+            Self::Rx => 0b01,
+            Self::Tx => 0b10,
+        }
+    }
+}
+
+/// Write the traffic as a pcapng capture, with one packet per chunk of bytes,
+/// all on a single interface named after the port.
+pub fn write_pcapng<'a>(
+    writer: &mut impl Write,
+    port_name: &str,
+    packets: impl IntoIterator<Item = (DateTime<Local>, TrafficDirection, &'a [u8])>,
+) -> Result<(), std::io::Error> {
+    let mut section_header = Vec::new();
+    section_header.extend(BYTE_ORDER_MAGIC.to_le_bytes());
+    // Version 1.0
+    section_header.extend(1u16.to_le_bytes());
+    section_header.extend(0u16.to_le_bytes());
+    // Section length isn't known up front.
+    section_header.extend((-1i64).to_le_bytes());
+    write_block(writer, SECTION_HEADER_BLOCK, &section_header)?;
+
+    let mut interface = Vec::new();
+    interface.extend(LINKTYPE_USER0.to_le_bytes());
+    // Reserved
+    interface.extend(0u16.to_le_bytes());
+    // No snapshot length limit.
+    interface.extend(0u32.to_le_bytes());
+    push_option(&mut interface, OPT_IF_NAME, port_name.as_bytes());
+    push_option(&mut interface, OPT_END_OF_OPT, &[]);
+    write_block(writer, INTERFACE_DESCRIPTION_BLOCK, &interface)?;
+
+    let mut packet = Vec::new();
+    for (timestamp, direction, bytes) in packets {
+        packet.clear();
+        // Timestamps are in the default resolution of microseconds.
+        let micros = timestamp.timestamp_micros() as u64;
+        let len = bytes.len() as u32;
+
+        // Interface ID
+        packet.extend(0u32.to_le_bytes());
+        packet.extend(((micros >> 32) as u32).to_le_bytes());
+        packet.extend((micros as u32).to_le_bytes());
+        // Captured and original length.
+        packet.extend(len.to_le_bytes());
+        packet.extend(len.to_le_bytes());
+        packet.extend(bytes);
+        pad_to_u32(&mut packet);
+        push_option(
+            &mut packet,
+            OPT_EPB_FLAGS,
+            &direction.epb_flags().to_le_bytes(),
+        );
+        push_option(&mut packet, OPT_END_OF_OPT, &[]);
+        write_block(writer, ENHANCED_PACKET_BLOCK, &packet)?;
+    }
+
+    writer.flush()
+}
+
+/// Write a block, with its total length on either side as pcapng expects.
+///
+/// `body` must already be padded to a multiple of four bytes.
+fn write_block(writer: &mut impl Write, block_type: u32, body: &[u8]) -> std::io::Result<()> {
+    debug_assert_eq!(body.len() % 4, 0);
+    // Type and both lengths.
+    let total_len = (body.len() + 12) as u32;
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&total_len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&total_len.to_le_bytes())
+}
+
+fn push_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend(code.to_le_bytes());
+    body.extend((value.len() as u16).to_le_bytes());
+    body.extend(value);
+    pad_to_u32(body);
+}
+
+fn pad_to_u32(body: &mut Vec<u8>) {
+    let padded_len = body.len().next_multiple_of(4);
+    body.resize(padded_len, 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_matches_the_pcapng_layout() {
+        // Split across both halves of the timestamp.
+        let timestamp = DateTime::from_timestamp_micros(0x1_0000_0002)
+            .unwrap()
+            .with_timezone(&Local);
+        let mut capture = Vec::new();
+        write_pcapng(
+            &mut capture,
+            "COM1",
+            [(timestamp, TrafficDirection::Rx, b"hi\n".as_slice())],
+        )
+        .unwrap();
+
+        let expected: Vec<u8> = [
+            // Section header: byte order magic, version 1.0, and an unknown section length.
+            &[0x0A, 0x0D, 0x0D, 0x0A, 28, 0, 0, 0][..],
+            &[0x4D, 0x3C, 0x2B, 0x1A, 1, 0, 0, 0],
+            &[0xFF; 8],
+            &[28, 0, 0, 0],
+            // Interface: link type, reserved, no snapshot length, and the port's name.
+            &[1, 0, 0, 0, 32, 0, 0, 0],
+            &[147, 0, 0, 0, 0, 0, 0, 0],
+            &[2, 0, 4, 0, b'C', b'O', b'M', b'1'],
+            &[0, 0, 0, 0],
+            &[32, 0, 0, 0],
+            // Packet: interface, timestamp high and low, captured and original length.
+            &[6, 0, 0, 0, 48, 0, 0, 0],
+            &[0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0],
+            &[3, 0, 0, 0, 3, 0, 0, 0],
+            // Padded data, then inbound flags.
+            &[b'h', b'i', b'\n', 0],
+            &[2, 0, 4, 0, 0b01, 0, 0, 0],
+            &[0, 0, 0, 0],
+            &[48, 0, 0, 0],
+        ]
+        .concat();
+        assert_eq!(capture, expected);
+    }
+}
@@ -0,0 +1,117 @@
+use std::{collections::VecDeque, io::Write, path::PathBuf};
+
+use chrono::{DateTime, Local, TimeDelta};
+use fs_err as fs;
+
+use crate::{config_adjacent_path, settings::Logging};
+
+use super::{TrafficDirection, write_traffic_record};
+
+/// The most recently recieved chunks, kept in memory regardless of file logging,
+/// so the moments around an intermittent fault can be saved after it's noticed.
+#[derive(Default)]
+pub(super) struct RecentTraffic {
+    chunks: VecDeque<(DateTime<Local>, u64, Vec<u8>)>,
+    /// Bytes across all kept chunks.
+    len: usize,
+    /// Tail end of the bytes recieved so far, for trigger text split across chunks.
+    trigger_carry: Vec<u8>,
+    /// When the last triggered dump happened, so one fault doesn't dump over and over.
+    last_triggered: Option<DateTime<Local>>,
+}
+
+impl RecentTraffic {
+    /// Keep the chunk, dropping any that are now too old or over the size limit.
+    ///
+    /// Returns `true` if the chunk completed the trigger text and a dump is due.
+    pub(super) fn push(
+        &mut self,
+        settings: &Logging,
+        timestamp: DateTime<Local>,
+        monotonic_us: u64,
+        bytes: &[u8],
+    ) -> bool {
+        if settings.keep_recent_secs == 0 {
+            self.chunks.clear();
+            self.len = 0;
+            return false;
+        }
+
+        let triggered = self.check_trigger(settings, timestamp, bytes);
+
+        self.chunks
+            .push_back((timestamp, monotonic_us, bytes.to_vec()));
+        self.len += bytes.len();
+
+        let max_len = settings.keep_recent_max_kb as usize * 1024;
+        let oldest_kept = timestamp - TimeDelta::seconds(settings.keep_recent_secs.into());
+        while let Some((front_timestamp, _, front)) = self.chunks.front()
+            && (self.len > max_len || *front_timestamp < oldest_kept)
+        {
+            self.len -= front.len();
+            self.chunks.pop_front();
+        }
+
+        triggered
+    }
+    fn check_trigger(
+        &mut self,
+        settings: &Logging,
+        timestamp: DateTime<Local>,
+        bytes: &[u8],
+    ) -> bool {
+        let trigger = settings.dump_recent_trigger.as_bytes();
+        if trigger.is_empty() {
+            self.trigger_carry.clear();
+            return false;
+        }
+
+        self.trigger_carry.extend_from_slice(bytes);
+        let found = self
+            .trigger_carry
+            .windows(trigger.len())
+            .any(|window| window == trigger);
+        // Only enough to finish a match starting in this chunk is needed next time.
+        let carry_from = self
+            .trigger_carry
+            .len()
+            .saturating_sub(trigger.len().saturating_sub(1));
+        self.trigger_carry.drain(..carry_from);
+
+        let cooled_down = self.last_triggered.is_none_or(|last| {
+            timestamp - last >= TimeDelta::seconds(settings.keep_recent_secs.into())
+        });
+        if found && cooled_down {
+            self.last_triggered = Some(timestamp);
+            true
+        } else {
+            false
+        }
+    }
+    pub(super) fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+    /// Write every kept chunk to a JSON Lines capture in the logs folder, returning its path.
+    ///
+    /// Uses the same records as the JSON Lines log, so it can be replayed later.
+    pub(super) fn dump(&self, now: DateTime<Local>) -> Result<PathBuf, std::io::Error> {
+        let logs_dir = config_adjacent_path("logs/");
+        fs::create_dir_all(&logs_dir)?;
+        let path: PathBuf = logs_dir
+            .join(now.format("yap-recent-%Y-%m-%d_%H-%M-%S.jsonl").to_string())
+            .into();
+
+        let mut file = std::io::BufWriter::new(fs::File::create(&path)?);
+        for (timestamp, monotonic_us, bytes) in &self.chunks {
+            write_traffic_record(
+                &mut file,
+                *timestamp,
+                Some(*monotonic_us),
+                TrafficDirection::Rx,
+                bytes,
+            )?;
+        }
+        file.flush()?;
+        Ok(path)
+    }
+}
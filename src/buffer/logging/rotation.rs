@@ -0,0 +1,376 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    ffi::OsString,
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, Timelike};
+use fs_err as fs;
+use tracing::debug;
+
+use crate::{
+    config_adjacent_path,
+    settings::{
+        DEFAULT_FILE_NAME_TEMPLATE, LogCompression, LogRotationInterval, Logging, RolloverNaming,
+    },
+};
+
+use super::{compression::CaptureWriter, forwarding::LineForwarder};
+
+/// Most files kept with [`RolloverNaming::Numbered`] when keeping them all,
+/// past which rotating fails rather than searching for a free number forever.
+const MAX_NUMBERED_ROTATIONS: usize = 10_000;
+
+/// A log file, tracking its size and age so it can be rotated per the [`Logging`] settings.
+///
+/// Needs to be [`finish`](Self::finish)ed once done with, so compressed files are ended properly.
+pub(super) struct CaptureFile {
+    writer: CaptureWriter,
+    path: PathBuf,
+    /// `strftime` format the file is named with, used again when naming rotated files by timestamp.
+    name_format: String,
+    /// Kind of log this is, like `txt` or `bin`.
+    extension: &'static str,
+    /// Kept as it was when the file was opened, so every rotated file matches its extension.
+    compression: LogCompression,
+    opened_at: DateTime<Local>,
+    /// Bytes in the file, or written since it was last truncated, before any compression.
+    written: u64,
+    /// Files already rotated out this session with [`RolloverNaming::Timestamped`], oldest first.
+    rotated_out: VecDeque<PathBuf>,
+    /// Sent everything written to the file as well, kept across rotations and truncations.
+    forwarder: Option<LineForwarder>,
+}
+
+impl CaptureFile {
+    /// Open a file in the logs folder, named by formatting `opened_at` with `name_format`,
+    /// continuing on from the end of it if it already exists.
+    pub(super) fn create(
+        name_format: String,
+        extension: &'static str,
+        opened_at: DateTime<Local>,
+        settings: &Logging,
+    ) -> Result<Self, std::io::Error> {
+        #[cfg(feature = "log-compression")]
+        let compression = settings.compression;
+        #[cfg(not(feature = "log-compression"))]
+        let compression = {
+            _ = settings;
+            LogCompression::None
+        };
+
+        let path = log_path(&name_format, extension, compression, opened_at);
+        let file = open_appending(&path)?;
+        // Continuing an existing file counts what's already in it towards its size limit.
+        let written = file.metadata()?.len();
+        Ok(Self {
+            writer: CaptureWriter::new(file, compression)?,
+            path,
+            name_format,
+            extension,
+            compression,
+            opened_at,
+            written,
+            rotated_out: VecDeque::new(),
+            forwarder: None,
+        })
+    }
+    pub(super) fn set_forwarder(&mut self, forwarder: Option<LineForwarder>) {
+        self.forwarder = forwarder;
+    }
+    pub(super) fn take_forwarder(&mut self) -> Option<LineForwarder> {
+        self.forwarder.take()
+    }
+    /// Flush everything written so far through to the disk.
+    pub(super) fn sync(&mut self) -> Result<(), std::io::Error> {
+        self.writer.sync()
+    }
+    /// Flush and close the file, ending its compressed stream if it has one.
+    pub(super) fn finish(self) -> Result<(), std::io::Error> {
+        self.writer.finish()?;
+        Ok(())
+    }
+    /// Empty the file to be written from the start again.
+    pub(super) fn truncate(mut self) -> Result<Self, std::io::Error> {
+        let mut file = self.writer.finish()?;
+        file.set_len(0)?;
+        file.seek(std::io::SeekFrom::Start(0))?;
+        self.writer = CaptureWriter::new(file, self.compression)?;
+        self.written = 0;
+        Ok(self)
+    }
+    pub(super) fn rotation_due(&self, settings: &Logging, now: DateTime<Local>) -> bool {
+        rotation_due(self.written, self.opened_at, settings, now)
+    }
+    /// Close this file and continue in a fresh one, removing any past the retained amount.
+    ///
+    /// Consumes the file since it needs to be closed before it can be renamed on some platforms.
+    pub(super) fn rotate(
+        mut self,
+        settings: &Logging,
+        now: DateTime<Local>,
+    ) -> Result<Self, std::io::Error> {
+        let retained = settings.retained_rotations as usize;
+        match settings.rollover_naming {
+            RolloverNaming::Numbered => {
+                drop(self.writer.finish()?);
+
+                shift_numbered(&self.path, retained)?;
+                fs::rename(&self.path, numbered_path(&self.path, 1))?;
+                self.writer = CaptureWriter::new(fs::File::create(&self.path)?, self.compression)?;
+            }
+            RolloverNaming::Timestamped => {
+                // Templates without a time would never give a different name.
+                let name_format = if self.name_format.contains("%S") {
+                    Cow::Borrowed(self.name_format.as_str())
+                } else {
+                    Cow::Owned(format!("{}_%H-%M-%S", self.name_format))
+                };
+                let path = log_path(&name_format, self.extension, self.compression, now);
+                // Wait until the timestamp changes rather than overwrite ourselves.
+                if path == self.path {
+                    return Ok(self);
+                }
+                drop(self.writer.finish()?);
+                self.writer = CaptureWriter::new(fs::File::create(&path)?, self.compression)?;
+
+                let previous = std::mem::replace(&mut self.path, path);
+                self.rotated_out.push_back(previous);
+                while retained > 0 && self.rotated_out.len() > retained {
+                    let oldest = self
+                        .rotated_out
+                        .pop_front()
+                        .expect("length was just checked");
+                    remove_if_exists(&oldest)?;
+                }
+            }
+        }
+        debug!("Rotated log file, now writing to {}", self.path.display());
+
+        self.opened_at = now;
+        self.written = 0;
+        Ok(self)
+    }
+}
+
+impl Write for CaptureFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.written += written as u64;
+        if let Some(forwarder) = &mut self.forwarder {
+            forwarder.push(&buf[..written]);
+        }
+        Ok(written)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// If a file with this much written since being opened at `opened_at` is due to be rotated.
+fn rotation_due(
+    written: u64,
+    opened_at: DateTime<Local>,
+    settings: &Logging,
+    now: DateTime<Local>,
+) -> bool {
+    let max_size = settings.rotate_at_size_mb.saturating_mul(1024 * 1024);
+    let too_large = max_size > 0 && written >= max_size;
+
+    let too_old = match settings.rotation_interval {
+        LogRotationInterval::Never => false,
+        LogRotationInterval::Hourly => {
+            (now.date_naive(), now.hour()) != (opened_at.date_naive(), opened_at.hour())
+        }
+        LogRotationInterval::Daily => now.date_naive() != opened_at.date_naive(),
+    };
+
+    too_large || too_old
+}
+
+fn log_path(
+    name_format: &str,
+    extension: &str,
+    compression: LogCompression,
+    timestamp: DateTime<Local>,
+) -> PathBuf {
+    let mut name = timestamp.format(name_format).to_string();
+    name.push('.');
+    name.push_str(extension);
+    if let Some(extension) = compression.extension() {
+        name.push('.');
+        name.push_str(extension);
+    }
+    config_adjacent_path(format!("logs/{name}")).into()
+}
+
+/// `<path>.<index>`, the name of a file rotated out with [`RolloverNaming::Numbered`].
+fn numbered_path(path: &Path, index: usize) -> PathBuf {
+    let mut numbered = OsString::from(path);
+    numbered.push(format!(".{index}"));
+    PathBuf::from(numbered)
+}
+
+/// Make room for a newly rotated file at `<path>.1` by shifting each older file up one,
+/// removing the oldest if there's already `retained` of them (with 0 keeping them all).
+///
+/// Keeping them all fails once there's [`MAX_NUMBERED_ROTATIONS`] of them.
+fn shift_numbered(path: &Path, retained: usize) -> Result<(), std::io::Error> {
+    let last = if retained > 0 {
+        retained
+    } else {
+        (1..=MAX_NUMBERED_ROTATIONS)
+            .find(|index| !numbered_path(path, *index).exists())
+            .ok_or_else(|| {
+                std::io::Error::other(format!(
+                    "already {MAX_NUMBERED_ROTATIONS} rotated files of {}, remove some or keep fewer",
+                    path.display()
+                ))
+            })?
+    };
+
+    remove_if_exists(&numbered_path(path, last))?;
+    for index in (1..last).rev() {
+        let older = numbered_path(path, index);
+        if older.exists() {
+            fs::rename(&older, numbered_path(path, index + 1))?;
+        }
+    }
+    Ok(())
+}
+
+/// Open a log file for writing, leaving anything already in it.
+pub(super) fn open_appending(path: &Path) -> Result<fs::File, std::io::Error> {
+    fs::OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Turn a [`Logging::file_name_template`] into a `strftime` format for [`CaptureFile::create`].
+///
+/// Anything outside of the placeholders is kept literally,
+/// besides characters that aren't allowed in file names.
+pub(super) fn file_name_format(template: &str, port_name: &str, session: u32) -> String {
+    // Just the last part of paths like `/dev/ttyUSB0`.
+    let port_name = Path::new(port_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(port_name);
+
+    let template = if template.trim().is_empty() {
+        DEFAULT_FILE_NAME_TEMPLATE
+    } else {
+        template.trim()
+    };
+
+    sanitize_file_name(template)
+        .replace('%', "%%")
+        .replace("{port}", &sanitize_file_name(port_name).replace('%', "%%"))
+        .replace("{date}", "%Y-%m-%d")
+        .replace("{time}", "%H-%M-%S")
+        .replace("{session}", &session.to_string())
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), std::io::Error> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 5, 17, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn rotation_due_by_size_or_age() {
+        let mut settings = Logging {
+            rotate_at_size_mb: 0,
+            rotation_interval: LogRotationInterval::Never,
+            ..Default::default()
+        };
+        assert!(!rotation_due(u64::MAX, at(0, 0), &settings, at(23, 59)));
+
+        settings.rotate_at_size_mb = 1;
+        assert!(!rotation_due(
+            1024 * 1024 - 1,
+            at(0, 0),
+            &settings,
+            at(0, 0)
+        ));
+        assert!(rotation_due(1024 * 1024, at(0, 0), &settings, at(0, 0)));
+
+        settings.rotate_at_size_mb = 0;
+        settings.rotation_interval = LogRotationInterval::Hourly;
+        assert!(!rotation_due(0, at(10, 0), &settings, at(10, 59)));
+        assert!(rotation_due(0, at(10, 59), &settings, at(11, 0)));
+
+        settings.rotation_interval = LogRotationInterval::Daily;
+        assert!(!rotation_due(0, at(0, 0), &settings, at(23, 59)));
+        let next_day = Local.with_ymd_and_hms(2024, 5, 18, 0, 0, 0).unwrap();
+        assert!(rotation_due(0, at(23, 59), &settings, next_day));
+    }
+
+    fn contents(path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok()
+    }
+
+    #[test]
+    fn numbered_files_shift_up_and_drop_the_oldest() {
+        let dir = scratch_dir("shift-numbered");
+        let path = dir.join("log.txt").into_std_path_buf();
+        for index in 1..=3 {
+            fs::write(numbered_path(&path, index), index.to_string()).unwrap();
+        }
+
+        shift_numbered(&path, 3).unwrap();
+        assert_eq!(contents(&numbered_path(&path, 1)), None);
+        assert_eq!(contents(&numbered_path(&path, 2)).as_deref(), Some("1"));
+        assert_eq!(contents(&numbered_path(&path, 3)).as_deref(), Some("2"));
+        assert_eq!(contents(&numbered_path(&path, 4)), None);
+
+        // Keeping them all just makes room at the start.
+        fs::write(numbered_path(&path, 1), "new").unwrap();
+        shift_numbered(&path, 0).unwrap();
+        assert_eq!(contents(&numbered_path(&path, 2)).as_deref(), Some("new"));
+        assert_eq!(contents(&numbered_path(&path, 3)).as_deref(), Some("1"));
+        assert_eq!(contents(&numbered_path(&path, 4)).as_deref(), Some("2"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeping_all_numbered_files_is_bounded() {
+        let dir = scratch_dir("shift-numbered-bound");
+        let path = dir.join("log.txt").into_std_path_buf();
+        for index in 1..=MAX_NUMBERED_ROTATIONS {
+            fs::File::create(numbered_path(&path, index)).unwrap();
+        }
+
+        assert!(shift_numbered(&path, 0).is_err());
+        // Nothing was moved or removed.
+        assert!(numbered_path(&path, 1).exists());
+        assert!(numbered_path(&path, MAX_NUMBERED_ROTATIONS).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,6 @@
+use std::{borrow::Cow, cell::RefCell, cmp::Ordering, collections::HashMap, ops::Range};
 #[cfg(feature = "defmt")]
-use std::sync::Arc;
-use std::{cell::Cell, cmp::Ordering, ops::Range};
+use std::{cell::Cell, collections::BTreeSet, sync::Arc};
 
 use ansi_to_tui::{IntoText, LossyFlavor};
 use bstr::{ByteSlice, ByteVec};
@@ -12,7 +12,7 @@ use itertools::{Either, Itertools};
 use memchr::memmem::Finder;
 use ratatui::{
     layout::Size,
-    style::{Color, Style, Stylize},
+    style::{Style, Stylize},
     text::{Line, Span},
     widgets::ScrollbarState,
 };
@@ -22,16 +22,20 @@ use tracing::{debug, error, warn};
 use crate::{
     buffer::buf_line::{BufLineKit, LineFinished, RenderSettings},
     changed,
-    settings::{Rendering, Settings},
+    settings::{BellBehavior, Rendering, Settings},
     traits::{ByteSuffixCheck, LineHelpers, interleave_by},
-    tui::color_rules::ColorRules,
+    tui::{color_rules::ColorRules, theme::theme},
 };
 
 #[cfg(feature = "defmt")]
 use crate::{
     buffer::{
         buf_line::FrameLocation,
-        defmt::{DefmtDecoder, DefmtPacketError, rzcobs_decode},
+        defmt::{
+            DefmtDecoder, DefmtPacketError, RawResync, RawResyncOutcome, decode_with_any,
+            module_filter::ModuleFilter, rzcobs_decode, stats::DefmtStats,
+            timestamp::TimestampAnchor,
+        },
     },
     settings::{Defmt, DefmtSupport},
 };
@@ -40,10 +44,19 @@ use crate::{
 use crate::{app::Event, settings::Logging};
 
 mod buf_line;
+mod encoding;
+pub use encoding::InputEncoding;
+mod height_index;
+use height_index::HeightIndex;
 mod hex_spans;
+mod html;
 pub use hex_spans::*;
 mod range_slice;
 pub use range_slice::RangeSlice;
+mod snapshot;
+use snapshot::BufferSnapshot;
+mod source_location;
+pub use source_location::{LocationKind, SourceLocation};
 mod tui;
 
 #[cfg(feature = "defmt")]
@@ -53,13 +66,28 @@ pub mod defmt;
 mod logging;
 
 #[cfg(feature = "logging")]
-pub use logging::{DEFAULT_TIMESTAMP_FORMAT, LoggingEvent, LoggingHandle, LoggingWorkerMissing};
+pub use logging::{
+    DEFAULT_TIMESTAMP_FORMAT, LoggingEvent, LoggingHandle, LoggingWorkerMissing, TrafficDirection,
+};
 #[cfg(feature = "logging")]
-use {crossbeam::channel::Sender, takeable::Takeable};
+use {crate::session::SessionRecorder, crossbeam::channel::Sender, takeable::Takeable};
 
 #[cfg(test)]
 mod tests;
 
+/// ASCII Bell control character.
+const BEL: u8 = 0x07;
+
+/// The line from the repeating_pattern_widget and the input buffer.
+pub const DEFAULT_RESERVED_LINES: u16 = 2;
+
+#[cfg(feature = "defmt")]
+/// Printed by ESP-IDF style bootloaders on boot, followed by a prefix of the running ELF's SHA256.
+const DEVICE_ELF_HASH_MARKER: &[u8] = b"ELF file SHA256:";
+#[cfg(feature = "defmt")]
+/// Anything shorter is too likely to be a coincidental match.
+const DEVICE_ELF_HASH_MIN_LEN: usize = 8;
+
 pub struct Buffer {
     /// Raw bytes from the port.
     raw: RawBuffer,
@@ -67,12 +95,19 @@ pub struct Buffer {
     /// (UTF-8 -> ANSI -> Color Rules)
     /// ratatui Lines to be rendered.
     styled_lines: StyledLines,
-    /// Cache for `combined_height`'s output
-    cached_combined_height: Cell<Option<usize>>,
+    /// Cumulative visible heights of `styled_lines`, used for `combined_height` and scrolling lookups.
+    height_index: RefCell<HeightIndex>,
+    /// Port lines before this index may have had their styling evicted, see `Rendering::styled_line_limit`.
+    styled_from: usize,
+    /// Port lines before `styled_from` that had their styling restored while in view,
+    /// evicted again once scrolled away from.
+    viewed_styled: Range<usize>,
 
     /// The last known size of the area given to
     /// render the buffer in (including the area taken by the scrollbar.)
     last_terminal_size: Size,
+    /// Lines of the screen taken up under the buffer, like the input line and macro hotbar.
+    reserved_lines: u16,
 
     pub state: BufferState,
 
@@ -86,6 +121,16 @@ pub struct Buffer {
     /// Text coloring, censoring, and omitting rules.
     color_rules: ColorRules,
 
+    /// Named snapshots of port line contents, kept across disconnects to diff against later.
+    snapshots: HashMap<CompactString, BufferSnapshot>,
+    /// Name of the snapshot currently being diffed against, if any.
+    diffing_against: Option<CompactString>,
+
+    /// Total BEL bytes recieved since the last clear, while not set to be shown as text.
+    bells_rung: usize,
+    /// BEL bytes recieved since the app last checked via `take_unhandled_bells`.
+    unhandled_bells: usize,
+
     #[cfg(feature = "logging")]
     pub log_handle: LoggingHandle,
     #[cfg(feature = "logging")]
@@ -93,17 +138,45 @@ pub struct Buffer {
     #[cfg(feature = "logging")]
     /// Clone of Logging settings, ditto the sentiment from Rendering.
     log_settings: Logging,
+    #[cfg(feature = "logging")]
+    /// Session recording started with `--record`, if any.
+    recorder: Option<SessionRecorder>,
 
     #[cfg(feature = "defmt")]
     /// Populated when a defmt ELF is successfully loaded.
     pub defmt_decoder: Option<Arc<DefmtDecoder>>,
     #[cfg(feature = "defmt")]
+    /// Further ELFs (i.e. a bootloader) whose tables are tried in order
+    /// when the main one can't decode a frame.
+    pub extra_defmt_decoders: Vec<Arc<DefmtDecoder>>,
+    #[cfg(feature = "defmt")]
     /// Clone of Defmt settings, ditto.
     defmt_settings: Defmt,
     #[cfg(feature = "defmt")]
-    /// Flag if last defmt raw/uncompressed decode attempt failed.
+    /// Parsed from the module filter directives in the Defmt settings.
+    defmt_module_filter: ModuleFilter,
+    #[cfg(feature = "defmt")]
+    /// Flag if last defmt raw/uncompressed decode attempt failed and couldn't be resynced.
     /// Further parsing attempts will not be allowed if set to true.
     defmt_raw_malformed: bool,
+    #[cfg(feature = "defmt")]
+    /// Budget and progress for skipping past corrupted raw/uncompressed frames.
+    defmt_raw_resync: RawResync,
+    #[cfg(feature = "defmt")]
+    /// Which table last decoded a frame, see `decode_with_any`.
+    defmt_last_matched: Cell<usize>,
+    #[cfg(feature = "defmt")]
+    /// Tallies of decoded frames and failures, rebuilt alongside the styled lines.
+    defmt_stats: DefmtStats,
+    #[cfg(feature = "defmt")]
+    /// Prefix of the running ELF's SHA256, if the device reported it on boot.
+    device_elf_hash: Option<CompactString>,
+    #[cfg(feature = "defmt")]
+    /// Set when a new `device_elf_hash` is found, until the app checks via `take_new_device_elf_hash`.
+    device_elf_hash_unhandled: bool,
+    #[cfg(feature = "defmt")]
+    /// Index into the raw buffer to resume searching for the device's ELF hash from.
+    device_elf_hash_scanned_to: usize,
 }
 
 #[derive(Debug)]
@@ -116,6 +189,8 @@ pub struct BufferState {
     /// Keep scroll stuck to bottom, on the newest inputs.
     // TODO maybe remove and use with vert_scroll == 0?
     stuck_to_bottom: bool,
+    /// Columns scrolled to the right, only used while text wrapping is off.
+    hor_scroll: u16,
     /// When in Hex View, controls how many bytes are shown per row,
     /// filled by `determine_bytes_per_line`, optionally capped by user.
     hex_bytes_per_line: u8,
@@ -338,7 +413,10 @@ struct RawBuffer {
     /// Raw bytes as recieved from the serial port.
     inner: Vec<u8>,
     /// Time-tagged indexes into `raw_buffer`, from each input from the port.
-    buffer_timestamps: Vec<(usize, DateTime<Local>, usize)>,
+    ///
+    /// Tagged with both the wall-clock time, and [`monotonic_micros`](crate::monotonic_micros)
+    /// for timing between chunks that's unaffected by the system clock being adjusted.
+    buffer_timestamps: Vec<(usize, DateTime<Local>, u64, usize)>,
     /// Slice retrieval methods start from this index.
     consumed_up_to: usize,
 }
@@ -359,10 +437,10 @@ impl RawBuffer {
         self.consumed_up_to = 0;
     }
     /// Fresh bytes!
-    fn feed(&mut self, new: &[u8], timestamp: DateTime<Local>) {
+    fn feed(&mut self, new: &[u8], timestamp: DateTime<Local>, monotonic_us: u64) {
         // warn!("fed {} bytes", new.len());
         self.buffer_timestamps
-            .push((self.inner.len(), timestamp, new.len()));
+            .push((self.inner.len(), timestamp, monotonic_us, new.len()));
         self.inner.extend(new);
     }
     fn consumed(&mut self, amount: usize) {
@@ -486,9 +564,37 @@ struct StyledLines {
     rx: Vec<BufLine>,
     /// User-sent psuedo-shell inputs and macros.
     tx: Vec<BufLine>,
+    #[cfg(feature = "defmt")]
+    /// Used when showing device timestamps as wall-clock time.
+    device_timestamp_anchor: TimestampAnchor,
+    #[cfg(feature = "defmt")]
+    /// Start indices in the raw buffer of frames that failed to decode,
+    /// to tell their lines apart from regular text.
+    failed_defmt_frames: BTreeSet<usize>,
 }
 
 impl StyledLines {
+    /// Recreate an evicted port line's styled text from its bytes in the raw buffer.
+    fn restyle_evicted(
+        line: &BufLine,
+        raw_buffer: &RawBuffer,
+        color_rules: &ColorRules,
+        line_ending: &LineEnding,
+        area_width: u16,
+        render: RenderSettings,
+    ) -> BufLine {
+        let range = line.range().clone();
+        let kit = BufLineKit {
+            full_range_slice: RangeSlice {
+                slice: &raw_buffer.inner[range.clone()],
+                range,
+            },
+            timestamp: line.timestamp,
+            area_width,
+            render,
+        };
+        Self::slice_as_port_text(kit, None, color_rules, line_ending)
+    }
     #[cfg(feature = "defmt")]
     fn failed_decode(
         &mut self,
@@ -503,6 +609,9 @@ impl StyledLines {
             _ => unreachable!("non-defmt slice can't fail decoding"),
         };
 
+        self.failed_defmt_frames
+            .insert(kit.full_range_slice.range.start);
+
         let mut text = format!("Couldn't decode defmt rzcobs packet ({reason}): ");
         text.extend(raw.iter().map(|b| format!("{b:02X}")));
 
@@ -611,7 +720,7 @@ impl StyledLines {
         debug_assert!(!kit.full_range_slice.slice.is_empty());
 
         let lossy_flavor = if kit.render.rendering.escape_unprintable_bytes {
-            LossyFlavor::escaped_bytes_styled(Style::new().dark_gray())
+            LossyFlavor::escaped_bytes_styled(theme().dim_style())
         } else {
             LossyFlavor::replacement_char()
         };
@@ -633,6 +742,17 @@ impl StyledLines {
             original
         };
 
+        // Anything not already UTF-8 gets transcoded first, so the ANSI parser and
+        // Color Rules only ever see UTF-8 (which may still be invalid, handled lossily below.)
+        let decoded = kit.render.rendering.input_encoding.decode(truncated);
+        // BELs are handled by the app instead if not being shown.
+        let decoded =
+            if kit.render.rendering.bell != BellBehavior::ShowEscaped && decoded.contains(&BEL) {
+                Cow::Owned(decoded.iter().copied().filter(|&b| b != BEL).collect())
+            } else {
+                decoded
+            };
+
         // If we encountered an ANSI Clear Line command in a previous scan through this buffer,
         // we don't need to reconsume those cleared bytes utf-8 or ansi-to-tui conversion!
         //
@@ -640,8 +760,10 @@ impl StyledLines {
         // since it's still part of the same line (just not yet terminated).
         //
         // If None, starts from 0 with a default style.
+        // (Also starting over if the index is past the end, i.e. if `Auto` changed its guess.)
+        let continue_from = continue_from.filter(|(index, _)| *index <= decoded.len());
         let (continue_index, continue_style) = continue_from.unwrap_or_default();
-        let truncated_continued = &truncated[continue_index..];
+        let truncated_continued = &decoded[continue_index..];
 
         let (line, clear_info) =
             match truncated_continued.to_line_lossy_flagged(continue_style, lossy_flavor) {
@@ -667,7 +789,7 @@ impl StyledLines {
                 }
             };
 
-        if let Some(mut recolored_line) = color_rules.apply_onto(truncated, line) {
+        if let Some(mut recolored_line) = color_rules.apply_onto(&decoded, line) {
             recolored_line.remove_unsavory_chars(kit.render.rendering.escape_unprintable_bytes);
             let line: Line<'static> = recolored_line.new_owned();
             BufLine::port_text_line(line, kit, clear_info, line_ending)
@@ -678,13 +800,16 @@ impl StyledLines {
     #[cfg(feature = "defmt")]
     /// A valid defmt frame has been parsed, convert into a single BufLine,
     /// running through the same Color Rules.
+    ///
+    /// Returns how many BEL characters were in the frame's message.
     fn consume_frame(
         &mut self,
         kit: BufLineKit,
         decoder: &DefmtDecoder,
         frame: &defmt_decoder::Frame<'_>,
         color_rules: &ColorRules,
-    ) {
+        tag_image: bool,
+    ) -> usize {
         // let meow = std::time::Instant::now();
         // error!("{:?}", meow.elapsed());
         // debug!("{frame:#?}");
@@ -696,17 +821,26 @@ impl StyledLines {
             .and_then(|locs| locs.get(&frame.index()))
             .map(FrameLocation::from);
 
+        // Only worth showing which ELF the frame came from if there's more than one.
+        let image = tag_image.then(|| decoder.image_name());
+
         // Get just the log's text content,
         // timestamp and level are handled separately.
         let message = frame.display_message().to_string();
+        let bells = message.bytes().filter(|&b| b == BEL).count();
         // Break into lines if more than one is present
         let message_lines = message.lines();
 
-        let device_timestamp = frame.display_timestamp();
+        // display_timestamp returns an object whose only purpose
+        // is as a fmt::Display adapter, so render it to try converting it.
+        let device_timestamp = frame.display_timestamp().map(|ts| {
+            let rendered = ts.to_compact_string();
+            self.device_timestamp_anchor
+                .convert(&rendered, kit.timestamp, kit.render.defmt)
+                .unwrap_or(rendered)
+        });
         let device_timestamp_ref = device_timestamp
             .as_ref()
-            // display_timestamp returns an object whose only purpose
-            // is as a fmt::Display adapter, so just use it as that.
             .map(|ts| ts as &dyn std::fmt::Display);
 
         for line in message_lines {
@@ -730,6 +864,7 @@ impl StyledLines {
                     frame.level(),
                     device_timestamp_ref,
                     loc_opt.clone(),
+                    image,
                 ));
             } else {
                 self.rx.push(BufLine::hidden_content_port_defmt_line(
@@ -737,9 +872,11 @@ impl StyledLines {
                     frame.level(),
                     device_timestamp_ref,
                     loc_opt.clone(),
+                    image,
                 ));
             }
         }
+        bells
     }
 }
 
@@ -779,14 +916,22 @@ impl Buffer {
             styled_lines: StyledLines {
                 rx: Vec::with_capacity(1024),
                 tx: Vec::with_capacity(1024),
+                #[cfg(feature = "defmt")]
+                device_timestamp_anchor: TimestampAnchor::default(),
+                #[cfg(feature = "defmt")]
+                failed_defmt_frames: BTreeSet::new(),
             },
-            cached_combined_height: Cell::new(None),
+            height_index: RefCell::new(HeightIndex::default()),
+            styled_from: 0,
+            viewed_styled: 0..0,
 
             last_terminal_size: Size::default(),
+            reserved_lines: DEFAULT_RESERVED_LINES,
             state: BufferState {
                 vert_scroll: 0,
                 scrollbar_state: ScrollbarState::default(),
                 stuck_to_bottom: false,
+                hor_scroll: 0,
                 hex_bytes_per_line: 0,
                 hex_section_width: 0,
             },
@@ -794,39 +939,77 @@ impl Buffer {
             line_ending,
             color_rules,
 
+            snapshots: HashMap::new(),
+            diffing_against: None,
+            bells_rung: 0,
+            unhandled_bells: 0,
+
             #[cfg(feature = "logging")]
             log_handle,
             #[cfg(feature = "logging")]
             log_thread: Takeable::new(log_thread),
             #[cfg(feature = "logging")]
             log_settings: logging,
+            #[cfg(feature = "logging")]
+            recorder: None,
 
             #[cfg(feature = "defmt")]
             defmt_decoder: None,
             #[cfg(feature = "defmt")]
+            extra_defmt_decoders: Vec::new(),
+            #[cfg(feature = "defmt")]
+            defmt_module_filter: ModuleFilter::parse(&defmt.module_filter),
+            #[cfg(feature = "defmt")]
             defmt_settings: defmt,
             #[cfg(feature = "defmt")]
             defmt_raw_malformed: false,
+            #[cfg(feature = "defmt")]
+            defmt_raw_resync: RawResync::default(),
+            #[cfg(feature = "defmt")]
+            defmt_last_matched: Cell::new(0),
+            #[cfg(feature = "defmt")]
+            defmt_stats: DefmtStats::default(),
+            #[cfg(feature = "defmt")]
+            device_elf_hash: None,
+            #[cfg(feature = "defmt")]
+            device_elf_hash_unhandled: false,
+            #[cfg(feature = "defmt")]
+            device_elf_hash_scanned_to: 0,
         }
     }
 
     /// The public interface where newly recieved bytes are sent.
-    pub fn fresh_rx_bytes(&mut self, timestamp: DateTime<Local>, bytes: Vec<u8>) {
+    pub fn fresh_rx_bytes(
+        &mut self,
+        timestamp: DateTime<Local>,
+        monotonic_us: u64,
+        bytes: Vec<u8>,
+    ) {
         // debug!("{lines:?}");
         // debug!("{:#?}", self.lines);
 
         // First append the new bytes to the raw buffer
-        self.raw.feed(&bytes, timestamp);
+        self.raw.feed(&bytes, timestamp, monotonic_us);
+
+        #[cfg(feature = "defmt")]
+        self.scan_for_device_elf_hash();
+
+        #[cfg(feature = "logging")]
+        self.record_session(TrafficDirection::Rx, timestamp, monotonic_us, &bytes);
 
         #[cfg(feature = "logging")]
         // And send them to the logging thread if needed
-        self.log_handle.log_rx_bytes(timestamp, bytes).unwrap();
+        self.log_handle
+            .log_rx_bytes(timestamp, monotonic_us, bytes)
+            .unwrap();
 
         // let meow = std::time::Instant::now();
 
         // And *then* do the work to consume them as text/defmt.
-        self.consume_latest_bytes(timestamp);
-        self.invalidate_height_cache();
+        // New or amended lines are picked up by the height index on next use.
+        let bells = self.consume_latest_bytes(timestamp);
+        self.count_bells(bells);
+        self.enforce_styled_line_limit();
         // error!("{:?}", meow.elapsed());
 
         // self.raw.inner.extend(bytes.iter());
@@ -842,7 +1025,12 @@ impl Buffer {
 
     /// Check if the latest bytes are ready to be consumed
     /// (which might not always be the case, such as when a defmt frame is sent in chunks, it wont be ready until terminated).
-    fn consume_latest_bytes(&mut self, timestamp: DateTime<Local>) {
+    ///
+    /// Returns how many BEL bytes were in the consumed text and decoded defmt messages.
+    fn consume_latest_bytes(&mut self, timestamp: DateTime<Local>) -> usize {
+        let count_bells = |bytes: &[u8]| bytes.iter().filter(|&&b| b == BEL).count();
+        let mut bells = 0;
+
         #[cfg(not(feature = "defmt"))]
         while let Some((_index_in_buffer, delimited_slice)) = self.raw.next_slice_raw() {
             #[allow(irrefutable_let_patterns)] // Other variants are conditionally compiled.
@@ -863,6 +1051,7 @@ impl Buffer {
                 },
             };
 
+            bells += count_bells(slice);
             self.styled_lines
                 .consume_as_text(&self.raw, &self.color_rules, kit, &self.line_ending);
             self.raw.consumed(slice.len());
@@ -888,6 +1077,8 @@ impl Buffer {
                         },
                     };
 
+                    self.defmt_stats.record_non_frame();
+                    bells += count_bells(slice);
                     self.styled_lines.consume_as_text(
                         &self.raw,
                         &self.color_rules,
@@ -908,14 +1099,21 @@ impl Buffer {
                             RangeSlice::from_parent_and_child(&self.raw.inner, raw_uncompressed)
                         },
                     };
-                    if let Some(decoder) = &self.defmt_decoder {
-                        match decoder.table.decode(raw_uncompressed) {
-                            Ok((frame, consumed)) => {
-                                self.styled_lines.consume_frame(
+                    if let Some(primary) = &self.defmt_decoder {
+                        let decoders = std::iter::once(primary)
+                            .chain(&self.extra_defmt_decoders)
+                            .map(Arc::as_ref);
+                        match decode_with_any(decoders, raw_uncompressed, &self.defmt_last_matched)
+                        {
+                            Ok((frame, consumed, decoder)) => {
+                                self.defmt_stats.record_frame(frame.level());
+                                self.defmt_raw_resync.frame_decoded();
+                                bells += self.styled_lines.consume_frame(
                                     kit,
                                     decoder,
                                     &frame,
                                     &self.color_rules,
+                                    !self.extra_defmt_decoders.is_empty(),
                                 );
 
                                 self.raw.consumed(consumed);
@@ -924,18 +1122,58 @@ impl Buffer {
                                 break;
                             }
                             Err(defmt_decoder::DecodeError::Malformed) => {
-                                self.defmt_raw_malformed = true;
-                                self.styled_lines.failed_decode(
-                                    delimited_slice,
-                                    DefmtPacketError::MalformedRawFrame,
-                                    kit,
-                                    &self.line_ending,
-                                );
-                                break;
+                                let decode = |bytes: &[u8]| {
+                                    let decoders = std::iter::once(primary)
+                                        .chain(&self.extra_defmt_decoders)
+                                        .map(Arc::as_ref);
+                                    decode_with_any(decoders, bytes, &self.defmt_last_matched)
+                                        .map(|(_, consumed, _)| consumed)
+                                };
+                                match self
+                                    .defmt_raw_resync
+                                    .find_next_frame(raw_uncompressed, decode)
+                                {
+                                    RawResyncOutcome::Skip(skipped) => {
+                                        let skipped_slice = &raw_uncompressed[..skipped];
+                                        let kit = BufLineKit {
+                                            full_range_slice: unsafe {
+                                                RangeSlice::from_parent_and_child(
+                                                    &self.raw.inner,
+                                                    skipped_slice,
+                                                )
+                                            },
+                                            ..kit
+                                        };
+                                        self.defmt_stats
+                                            .record_failure(&DefmtPacketError::ResyncedRawFrame);
+                                        self.styled_lines.failed_decode(
+                                            DelimitedSlice::DefmtRaw(skipped_slice),
+                                            DefmtPacketError::ResyncedRawFrame,
+                                            kit,
+                                            &self.line_ending,
+                                        );
+                                        self.raw.consumed(skipped);
+                                    }
+                                    RawResyncOutcome::NeedMoreData => break,
+                                    RawResyncOutcome::GiveUp => {
+                                        self.defmt_raw_malformed = true;
+                                        self.defmt_stats
+                                            .record_failure(&DefmtPacketError::MalformedRawFrame);
+                                        self.styled_lines.failed_decode(
+                                            delimited_slice,
+                                            DefmtPacketError::MalformedRawFrame,
+                                            kit,
+                                            &self.line_ending,
+                                        );
+                                        break;
+                                    }
+                                }
                             }
                         }
                     } else {
                         let slice_len = delimited_slice.raw_len();
+                        self.defmt_stats
+                            .record_failure(&DefmtPacketError::NoDecoder);
                         self.styled_lines.failed_decode(
                             delimited_slice,
                             DefmtPacketError::NoDecoder,
@@ -959,16 +1197,25 @@ impl Buffer {
                     };
                     let raw_slice_len = raw.len();
 
-                    if let Some(decoder) = &self.defmt_decoder {
+                    if let Some(primary) = &self.defmt_decoder {
+                        let decoders = std::iter::once(primary)
+                            .chain(&self.extra_defmt_decoders)
+                            .map(Arc::as_ref);
                         if let Ok(uncompressed) = rzcobs_decode(inner) {
-                            if let Ok((frame, _consumed)) = decoder.table.decode(&uncompressed) {
-                                self.styled_lines.consume_frame(
+                            if let Ok((frame, _consumed, decoder)) =
+                                decode_with_any(decoders, &uncompressed, &self.defmt_last_matched)
+                            {
+                                self.defmt_stats.record_frame(frame.level());
+                                bells += self.styled_lines.consume_frame(
                                     kit,
                                     decoder,
                                     &frame,
                                     &self.color_rules,
+                                    !self.extra_defmt_decoders.is_empty(),
                                 );
                             } else {
+                                self.defmt_stats
+                                    .record_failure(&DefmtPacketError::DefmtDecode);
                                 self.styled_lines.failed_decode(
                                     delimited_slice,
                                     DefmtPacketError::DefmtDecode,
@@ -977,6 +1224,8 @@ impl Buffer {
                                 );
                             }
                         } else {
+                            self.defmt_stats
+                                .record_failure(&DefmtPacketError::RzcobsDecompress);
                             self.styled_lines.failed_decode(
                                 delimited_slice,
                                 DefmtPacketError::RzcobsDecompress,
@@ -985,6 +1234,8 @@ impl Buffer {
                             );
                         }
                     } else {
+                        self.defmt_stats
+                            .record_failure(&DefmtPacketError::NoDecoder);
                         self.styled_lines.failed_decode(
                             delimited_slice,
                             DefmtPacketError::NoDecoder,
@@ -997,6 +1248,7 @@ impl Buffer {
                 }
             }
         }
+        bells
     }
 
     /// Clears recieved styled lines and reconsumes the whole
@@ -1012,6 +1264,9 @@ impl Buffer {
         }
 
         self.styled_lines.rx.clear();
+        self.invalidate_height_cache();
+        self.styled_from = 0;
+        self.viewed_styled = 0..0;
 
         let user_timestamps: Vec<_> = self
             .styled_lines
@@ -1053,6 +1308,10 @@ impl Buffer {
         #[cfg(feature = "defmt")]
         {
             self.defmt_raw_malformed = false;
+            self.defmt_raw_resync.reset();
+            self.defmt_stats = DefmtStats::default();
+            self.styled_lines.device_timestamp_anchor.reset();
+            self.styled_lines.failed_defmt_frames.clear();
         }
 
         // Getting all time-tagged indices in the buffer where either
@@ -1062,14 +1321,17 @@ impl Buffer {
             rx_buffer
                 .buffer_timestamps
                 .iter()
-                .map(|(index, timestamp, _len)| (*index, *timestamp, false))
+                .map(|(index, timestamp, monotonic_us, _len)| {
+                    (*index, *timestamp, *monotonic_us, false)
+                })
                 // Add a "finale" element to capture any remaining buffer, always placed at the end.
-                .chain(std::iter::once((orig_buf_len, Local::now(), false))),
+                // Only port points start a slice that gets fed, so the others' monotonic time is unused.
+                .chain(std::iter::once((orig_buf_len, Local::now(), 0, false))),
             user_timestamps
                 .into_iter()
                 // If a user line isn't visible, ignore it when taking external new-lines into account.
                 .filter(|(line_type, _, _)| user_echo.filter_user_line(line_type))
-                .map(|(_, index, timestamp)| (index, timestamp, true)),
+                .map(|(_, index, timestamp)| (index, timestamp, 0, true)),
             // Interleaving by sorting in order of raw_buffer_index, if they're equal, then whichever has a sooner timestamp.
             |port, user| match port.0.cmp(&user.0) {
                 Ordering::Equal => port.1 <= user.1,
@@ -1084,15 +1346,18 @@ impl Buffer {
         let buffer_slices = interleaved_points
             .tuple_windows()
             // Filtering out some empty slices, unless they indicate a user event.
-            .filter(|((start_index, _, was_user_line), (end_index, _, _))| {
-                start_index != end_index || *was_user_line
-            })
+            .filter(
+                |((start_index, _, _, was_user_line), (end_index, _, _, _))| {
+                    start_index != end_index || *was_user_line
+                },
+            )
             // Building the parent slices (pre-newline splitting)
             .map(
-                |((start_index, timestamp, was_user_line), (end_index, _, _))| {
+                |((start_index, timestamp, monotonic_us, was_user_line), (end_index, _, _, _))| {
                     (
                         &rx_buffer.inner[start_index..end_index],
                         timestamp,
+                        monotonic_us,
                         was_user_line,
                         (start_index, end_index),
                     )
@@ -1100,7 +1365,7 @@ impl Buffer {
             );
 
         for x in buffer_slices {
-            let (slice, timestamp, was_user_line, _range) = x;
+            let (slice, timestamp, monotonic_us, was_user_line, _range) = x;
             // If this was where a user line we allow to render is,
             // then we'll finish this line early if it's not already finished.
             if was_user_line {
@@ -1114,7 +1379,7 @@ impl Buffer {
                 }
                 continue;
             }
-            self.raw.feed(slice, timestamp);
+            self.raw.feed(slice, timestamp, monotonic_us);
             self.consume_latest_bytes(timestamp);
             // info!(
             //     "Getting {le} slices from [{slice_start}..{slice_end}], {timestamp}, {was_user_line}",
@@ -1162,9 +1427,200 @@ impl Buffer {
             );
         });
 
+        self.enforce_styled_line_limit();
         self.scroll_by(0);
     }
 
+    /// Evict styling from port lines older than the newest `styled_line_limit` lines,
+    /// or restore any that fall back within it if the limit was raised.
+    ///
+    /// The raw buffer is always kept, so evicted lines are just restyled from it when needed.
+    fn enforce_styled_line_limit(&mut self) {
+        let limit = self.rendering.styled_line_limit;
+        let rx = &mut self.styled_lines.rx;
+        let keep_from = if limit == 0 {
+            0
+        } else {
+            rx.len().saturating_sub(limit)
+        };
+        let previous = std::mem::replace(&mut self.styled_from, keep_from).min(rx.len());
+
+        if keep_from > previous {
+            // Lines still in view are left to `style_viewed_lines` to evict once scrolled away from.
+            let viewed = &self.viewed_styled;
+            rx[previous..keep_from]
+                .iter_mut()
+                .enumerate()
+                .filter(|(index, l)| !viewed.contains(&(previous + index)) && l.can_evict_styling())
+                .for_each(|(_, l)| l.evict_styling());
+        } else if keep_from < previous {
+            let render = RenderSettings {
+                rendering: &self.rendering,
+                #[cfg(feature = "defmt")]
+                defmt: &self.defmt_settings,
+            };
+            for line in rx[keep_from..previous]
+                .iter_mut()
+                .filter(|l| l.styling_evicted())
+            {
+                let restyled = StyledLines::restyle_evicted(
+                    line,
+                    &self.raw,
+                    &self.color_rules,
+                    &self.line_ending,
+                    self.last_terminal_size.width,
+                    render,
+                );
+                line.restore_styling(restyled);
+            }
+        }
+    }
+
+    /// Restore styling to evicted port lines in view, and evict it again from those scrolled away from,
+    /// so lines older than the newest `styled_line_limit` are only restyled once while on screen.
+    fn style_viewed_lines(&mut self) {
+        let rx_len = self.styled_lines.rx.len();
+        let evictable_end = self.styled_from.min(rx_len);
+
+        let viewed = if evictable_end == 0 || self.rendering.hex_view {
+            0..0
+        } else {
+            let height = self.last_terminal_size.height as usize;
+            let (first, _, _) = self.locate_row(self.state.vert_scroll);
+            let (last, _, _) = self.locate_row(self.state.vert_scroll + height);
+            let end = (last + 1).min(evictable_end);
+            first.min(end)..end
+        };
+
+        let previous = std::mem::replace(&mut self.viewed_styled, viewed.clone());
+        let previous = previous.start.min(evictable_end)..previous.end.min(evictable_end);
+
+        for index in previous.filter(|index| !viewed.contains(index)) {
+            let line = &mut self.styled_lines.rx[index];
+            if line.can_evict_styling() {
+                line.evict_styling();
+            }
+        }
+
+        let render = RenderSettings {
+            rendering: &self.rendering,
+            #[cfg(feature = "defmt")]
+            defmt: &self.defmt_settings,
+        };
+        for line in self.styled_lines.rx[viewed]
+            .iter_mut()
+            .filter(|l| l.styling_evicted())
+        {
+            let restyled = StyledLines::restyle_evicted(
+                line,
+                &self.raw,
+                &self.color_rules,
+                &self.line_ending,
+                self.last_terminal_size.width,
+                render,
+            );
+            line.restore_styling(restyled);
+        }
+    }
+
+    /// Tally BEL bytes found in newly recieved text, if they're not being shown as text.
+    ///
+    /// Counted from what was consumed rather than the raw bytes, since binary defmt frames
+    /// are bound to contain 0x07 bytes that aren't meant as bells.
+    fn count_bells(&mut self, bells: usize) {
+        if self.rendering.bell == BellBehavior::ShowEscaped {
+            return;
+        }
+        self.bells_rung += bells;
+        self.unhandled_bells += bells;
+    }
+
+    /// Returns how many BEL bytes were recieved since the last call.
+    pub fn take_unhandled_bells(&mut self) -> usize {
+        std::mem::take(&mut self.unhandled_bells)
+    }
+
+    /// Total BEL bytes recieved since the buffer was last cleared.
+    pub fn bells_rung(&self) -> usize {
+        self.bells_rung
+    }
+
+    #[cfg(feature = "defmt")]
+    /// Look through newly recieved bytes for the device reporting which ELF it's running.
+    fn scan_for_device_elf_hash(&mut self) {
+        let start = self.device_elf_hash_scanned_to.min(self.raw.inner.len());
+        let haystack = &self.raw.inner[start..];
+        // Unless a match turns out to be incomplete, resume from where a marker
+        // split across two chunks would start.
+        let mut resume_at = haystack
+            .len()
+            .saturating_sub(DEVICE_ELF_HASH_MARKER.len() - 1);
+
+        for found in Finder::new(DEVICE_ELF_HASH_MARKER).find_iter(haystack) {
+            let after = &haystack[found + DEVICE_ELF_HASH_MARKER.len()..];
+            let hash = after.trim_start_with(|c| c == ' ');
+            let hash_len = hash.iter().take_while(|b| b.is_ascii_hexdigit()).count();
+            if hash_len == hash.len() {
+                // The rest of the hash may not have arrived yet.
+                resume_at = found;
+                break;
+            }
+            if hash_len < DEVICE_ELF_HASH_MIN_LEN {
+                continue;
+            }
+            let hash = hash[..hash_len].to_str_lossy().to_compact_string();
+            if self.device_elf_hash.as_ref() != Some(&hash) {
+                self.device_elf_hash = Some(hash);
+                self.device_elf_hash_unhandled = true;
+            }
+        }
+
+        self.device_elf_hash_scanned_to = start + resume_at;
+    }
+
+    #[cfg(feature = "defmt")]
+    /// Returns `true` once after the device reports an ELF hash different to the last one.
+    pub fn take_new_device_elf_hash(&mut self) -> bool {
+        std::mem::take(&mut self.device_elf_hash_unhandled)
+    }
+
+    #[cfg(feature = "defmt")]
+    /// If the device reported its ELF hash and no loaded defmt ELF matches it,
+    /// returns the device's hash.
+    pub fn defmt_elf_mismatch(&self) -> Option<&str> {
+        let device_hash = self.device_elf_hash.as_deref()?;
+        let primary = self.defmt_decoder.as_ref()?;
+        let matched = std::iter::once(primary)
+            .chain(&self.extra_defmt_decoders)
+            .any(|decoder| decoder.matches_device_hash(device_hash));
+        (!matched).then_some(device_hash)
+    }
+
+    #[cfg(feature = "defmt")]
+    /// Tallies of defmt decoding outcomes since the buffer was last cleared or reconsumed.
+    pub fn defmt_stats(&self) -> &DefmtStats {
+        &self.defmt_stats
+    }
+
+    /// Returns the line as-is, or a temporary restyled copy if its styling was evicted.
+    ///
+    /// Lines in view are restyled ahead of rendering by `style_viewed_lines`, so this only copies
+    /// for lines off-screen (i.e. when exporting or taking a snapshot.)
+    fn styled<'a>(&self, line: &'a BufLine) -> Cow<'a, BufLine> {
+        if line.styling_evicted() {
+            Cow::Owned(StyledLines::restyle_evicted(
+                line,
+                &self.raw,
+                &self.color_rules,
+                &self.line_ending,
+                self.last_terminal_size.width,
+                self.line_render_settings(),
+            ))
+        } else {
+            Cow::Borrowed(line)
+        }
+    }
+
     #[cfg(feature = "logging")]
     /// Relog contents in buffer
     pub fn relog_buffer(&mut self) -> Result<(), LoggingWorkerMissing> {
@@ -1200,17 +1656,26 @@ impl Buffer {
             self.raw
                 .buffer_timestamps
                 .iter()
-                .map(|(index, timestamp, _len)| (*index, *timestamp, blank_port_line_type.clone()))
+                .map(|(index, timestamp, monotonic_us, _len)| {
+                    (
+                        *index,
+                        *timestamp,
+                        *monotonic_us,
+                        blank_port_line_type.clone(),
+                    )
+                })
                 // Add a "finale" element to capture any remaining buffer, always placed at the end.
                 .chain(std::iter::once((
                     orig_buf_len,
                     Local::now(),
+                    0,
                     blank_port_line_type.clone(),
                 ))),
             user_timestamps
                 .into_iter()
                 // If a user line isn't visible, ignore it when taking external new-lines into account.
-                .map(|(line_type, index, timestamp)| (index, timestamp, line_type)),
+                // User lines aren't logged with a monotonic time.
+                .map(|(line_type, index, timestamp)| (index, timestamp, 0, line_type)),
             // Interleaving by sorting in order of raw_buffer_index, if they're equal, then whichever has a sooner timestamp.
             |port, user| match port.0.cmp(&user.0) {
                 Ordering::Equal => port.1 <= user.1,
@@ -1224,18 +1689,21 @@ impl Buffer {
         let buffer_slices = interleaved_points
             .tuple_windows()
             // Filtering out some empty slices, unless they indicate a user event.
-            .filter(|((start_index, _, line_type), (end_index, _, _))| {
+            .filter(|((start_index, _, _, line_type), (end_index, _, _, _))| {
                 start_index != end_index || matches!(line_type, LineType::User { .. })
             })
             // Building the parent slices (pre-newline splitting)
-            .map(|((start_index, timestamp, line_type), (end_index, _, _))| {
-                (
-                    &self.raw.inner[start_index..end_index],
-                    timestamp,
-                    line_type,
-                    (start_index, end_index),
-                )
-            });
+            .map(
+                |((start_index, timestamp, monotonic_us, line_type), (end_index, _, _, _))| {
+                    (
+                        &self.raw.inner[start_index..end_index],
+                        timestamp,
+                        monotonic_us,
+                        line_type,
+                        (start_index, end_index),
+                    )
+                },
+            );
 
         let (relog_tx, relog_rx) = unbounded();
         use crate::buffer::{
@@ -1248,7 +1716,8 @@ impl Buffer {
         let mut rx_batch = Vec::new();
         let mut tx_batch = Vec::new();
 
-        for (slice, timestamp, line_type, (_slice_start, _slice_end)) in buffer_slices {
+        for (slice, timestamp, monotonic_us, line_type, (_slice_start, _slice_end)) in buffer_slices
+        {
             // If this was where a user line we allow to render is,
             // then we'll finish this line early if it's not already finished.
             if let LineType::User {
@@ -1280,7 +1749,7 @@ impl Buffer {
                     relog_tx.send(SyncBatch::TxBatch(transmitted_tx))?;
                 }
 
-                rx_batch.push((timestamp, slice.to_owned()));
+                rx_batch.push((timestamp, monotonic_us, slice.to_owned()));
             }
         }
 
@@ -1295,6 +1764,46 @@ impl Buffer {
         Ok(())
     }
 
+    #[cfg(feature = "logging")]
+    /// Write all RX and TX traffic in the buffer as a pcapng capture in the logs folder, returning its path.
+    pub fn export_pcapng(&self, port_name: &str) -> Result<camino::Utf8PathBuf, std::io::Error> {
+        use crate::config_adjacent_path;
+
+        let rx = self
+            .raw
+            .buffer_timestamps
+            .iter()
+            .map(|(index, timestamp, _monotonic_us, len)| {
+                (
+                    *timestamp,
+                    TrafficDirection::Rx,
+                    &self.raw.inner[*index..*index + *len],
+                )
+            });
+        let tx = self.styled_lines.tx.iter().filter_map(|line| {
+            let LineType::User { reloggable_raw, .. } = &line.line_type else {
+                return None;
+            };
+            Some((
+                line.timestamp,
+                TrafficDirection::Tx,
+                reloggable_raw.as_slice(),
+            ))
+        });
+        let packets = interleave_by(rx, tx, |rx, tx| rx.0 <= tx.0);
+
+        let logs_dir = config_adjacent_path("logs/");
+        fs_err::create_dir_all(&logs_dir)?;
+        let path = logs_dir.join(
+            Local::now()
+                .format("yap-%Y-%m-%d_%H-%M-%S.pcapng")
+                .to_string(),
+        );
+        let mut file = std::io::BufWriter::new(fs_err::File::create(&path)?);
+        logging::write_pcapng(&mut file, port_name, packets)?;
+        Ok(path)
+    }
+
     // pub fn update_line_ending(&mut self, line_ending: &str) {
     pub fn update_line_ending(&mut self, line_ending: &[u8]) {
         self.invalidate_height_cache();
@@ -1311,7 +1820,14 @@ impl Buffer {
         self.invalidate_height_cache();
         let old = std::mem::replace(&mut self.rendering, rendering);
         let new = &self.rendering;
-        let should_reconsume = changed!(old, new, echo_user_input, escape_unprintable_bytes);
+        let should_reconsume = changed!(
+            old,
+            new,
+            echo_user_input,
+            escape_unprintable_bytes,
+            input_encoding,
+            bell
+        );
 
         let should_rewrap_lines = changed!(
             old,
@@ -1322,6 +1838,8 @@ impl Buffer {
             show_line_ending
         );
 
+        let limit_changed = changed!(old, new, styled_line_limit);
+
         if changed!(old, new, bytes_per_line) {
             self.determine_bytes_per_line(new.bytes_per_line.into());
             self.correct_hex_view_scroll();
@@ -1331,8 +1849,13 @@ impl Buffer {
 
         if should_reconsume {
             self.reconsume_raw_buffer();
-        } else if should_rewrap_lines {
-            self.update_wrapped_line_heights();
+        } else {
+            if limit_changed {
+                self.enforce_styled_line_limit();
+            }
+            if should_rewrap_lines {
+                self.update_wrapped_line_heights();
+            }
         }
 
         self.scroll_by(0);
@@ -1342,7 +1865,18 @@ impl Buffer {
         self.invalidate_height_cache();
         let old = std::mem::replace(&mut self.defmt_settings, defmt);
         let new = &self.defmt_settings;
-        let should_reconsume = changed!(old, new, defmt_parsing);
+        let should_reconsume = changed!(
+            old,
+            new,
+            defmt_parsing,
+            device_timestamp_mode,
+            device_tick_rate,
+            reanchor_on_reset
+        );
+
+        if changed!(old, new, module_filter) {
+            self.defmt_module_filter = ModuleFilter::parse(&new.module_filter);
+        }
 
         let should_rewrap_lines = changed!(
             old,
@@ -1381,6 +1915,12 @@ impl Buffer {
         #[cfg(feature = "logging")]
         self.log_handle.log_port_disconnected(true)?;
 
+        self.clear();
+        Ok(())
+    }
+
+    /// Drop all recieved and sent content, as if nothing had been recieved yet.
+    pub fn clear(&mut self) {
         self.styled_lines.rx.clear();
         self.styled_lines.rx.shrink_to(1024);
 
@@ -1388,9 +1928,103 @@ impl Buffer {
         self.styled_lines.tx.shrink_to(1024);
 
         self.raw.reset();
+        self.invalidate_height_cache();
+        self.styled_from = 0;
+        self.viewed_styled = 0..0;
+        self.bells_rung = 0;
+        self.unhandled_bells = 0;
+        #[cfg(feature = "defmt")]
+        {
+            self.defmt_stats = DefmtStats::default();
+            self.styled_lines.device_timestamp_anchor.reset();
+            self.styled_lines.failed_defmt_frames.clear();
+            self.device_elf_hash = None;
+            self.device_elf_hash_unhandled = false;
+            self.device_elf_hash_scanned_to = 0;
+        }
+    }
 
+    #[cfg(feature = "logging")]
+    /// Record everything sent and recieved from now on to a new session recording at `path`.
+    pub fn start_recording(&mut self, path: &camino::Utf8Path) -> Result<(), std::io::Error> {
+        self.recorder = Some(SessionRecorder::create(path)?);
         Ok(())
     }
+    #[cfg(feature = "logging")]
+    fn record_session(
+        &mut self,
+        direction: TrafficDirection,
+        timestamp: DateTime<Local>,
+        monotonic_us: u64,
+        bytes: &[u8],
+    ) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+        if let Err(e) = recorder.record(direction, timestamp, monotonic_us, bytes) {
+            error!("Failed writing to session recording, stopping recording: {e}");
+            self.recorder = None;
+        }
+    }
+
+    /// Add a user line, cutting any unterminated port line short if it's going to be shown.
+    fn push_user_line(&mut self, user_buf_line: BufLine) {
+        if let Some(last_rx) = self.styled_lines.rx.last_mut()
+            && matches!(
+                last_rx.line_type,
+                LineType::Port(LineFinished::Unfinished { .. })
+            )
+            && self
+                .rendering
+                .echo_user_input
+                .filter_user_line(&user_buf_line.line_type)
+        {
+            last_rx.line_type = LineType::Port(LineFinished::CutShort);
+        }
+        self.styled_lines.tx.push(user_buf_line);
+    }
+
+    #[cfg(feature = "logging")]
+    /// Show input sent in a replayed capture as it was, without sending, logging, or recording it again.
+    ///
+    /// Shown as text, split by whichever line ending it ends with.
+    pub fn replayed_user_bytes(&mut self, timestamp: DateTime<Local>, bytes: &[u8]) {
+        let user_span = span!(theme().user_input; "USER> ");
+
+        let text = [b"\r\n".as_slice(), b"\n", b"\r"]
+            .iter()
+            .find_map(|ending| bytes.strip_suffix(*ending))
+            .unwrap_or(bytes);
+        let line_ending_bytes = &bytes[text.len()..];
+        let tx_line_ending: LineEnding = line_ending_bytes.into();
+
+        for (trunc, _orig, _range) in line_ending_iter(text, &tx_line_ending) {
+            let mut line = Line::from(String::from_utf8_lossy(trunc).to_string());
+            line.spans.insert(0, user_span.clone());
+            line.style_all_spans(theme().user_input.into());
+
+            let kit = BufLineKit {
+                timestamp,
+                area_width: self.last_terminal_size.width,
+                render: self.line_render_settings(),
+                full_range_slice: RangeSlice {
+                    range: self.raw.inner.len()..self.raw.inner.len(),
+                    slice: &[],
+                },
+            };
+            let reloggable_raw = [trunc, line_ending_bytes].concat();
+            let user_buf_line = BufLine::user_line(
+                line,
+                kit,
+                &tx_line_ending,
+                false,
+                #[cfg(feature = "macros")]
+                false,
+                reloggable_raw,
+            );
+            self.push_user_line(user_buf_line);
+        }
+    }
 
     /// User sent an input in Pseudo-shells byte mode, or a macro with escaped bytes.
     pub fn append_user_bytes(
@@ -1400,14 +2034,14 @@ impl Buffer {
         #[cfg(feature = "macros")] macro_sensitivity: Option<bool>,
     ) {
         let now = Local::now();
-        let user_span = span!(Color::DarkGray; "BYTE> ");
+        let user_span = span!(theme().user_input; "BYTE> ");
 
         #[cfg(not(feature = "macros"))]
         let macro_sensitivity = None;
 
         // If input is a macro and is marked sensitive, just replace every shown byte with a *
         let text = if let Some(true) = macro_sensitivity {
-            span!(Style::new().dark_gray(); "*".repeat(bytes.len()))
+            span!(theme().dim_style(); "*".repeat(bytes.len()))
         } else {
             let text: Span = bytes
                 .iter()
@@ -1416,13 +2050,25 @@ impl Buffer {
                 .join("")
                 .into();
 
-            text.dark_gray().italic().bold()
+            text.fg(theme().user_input).italic().bold()
         };
 
         let line = Line::from(vec![user_span, text]);
 
         let tx_line_ending: LineEnding = line_ending_bytes.into();
 
+        let reloggable_raw = bytes
+            .iter()
+            .chain(line_ending_bytes.iter())
+            .copied()
+            .collect::<Vec<u8>>();
+        #[cfg(feature = "logging")]
+        self.record_session(
+            TrafficDirection::Tx,
+            now,
+            crate::monotonic_micros(),
+            &reloggable_raw,
+        );
         let kit = BufLineKit {
             timestamp: now,
             area_width: self.last_terminal_size.width,
@@ -1432,11 +2078,6 @@ impl Buffer {
                 slice: &[],
             },
         };
-        let reloggable_raw = bytes
-            .iter()
-            .chain(line_ending_bytes.iter())
-            .copied()
-            .collect::<Vec<u8>>();
         let user_buf_line = BufLine::user_line(
             line,
             kit,
@@ -1447,22 +2088,10 @@ impl Buffer {
             reloggable_raw,
         );
 
-        // If we're going to show this input, cut any unterminated port line short.
-        if let Some(last_rx) = self.styled_lines.rx.last_mut()
-            && matches!(
-                last_rx.line_type,
-                LineType::Port(LineFinished::Unfinished { .. })
-            )
-            && self
-                .rendering
-                .echo_user_input
-                .filter_user_line(&user_buf_line.line_type)
-        {
-            last_rx.line_type = LineType::Port(LineFinished::CutShort);
-        }
-
         #[cfg(feature = "logging")]
-        if self.log_settings.log_text_to_file && self.log_settings.log_user_input {
+        if (self.log_settings.log_text_to_file || self.log_settings.log_jsonl_to_file)
+            && self.log_settings.log_user_input
+        {
             self.log_handle
                 .log_tx_bytes(
                     now,
@@ -1471,8 +2100,7 @@ impl Buffer {
                 )
                 .expect("Logging worker has disappeared!");
         }
-        self.styled_lines.tx.push(user_buf_line);
-        self.invalidate_height_cache();
+        self.push_user_line(user_buf_line);
     }
 
     pub fn append_user_text(
@@ -1485,20 +2113,29 @@ impl Buffer {
 
         let tx_line_ending: LineEnding = line_ending_bytes.into();
 
-        let user_span = span!(Color::DarkGray;"USER> ");
+        let user_span = span!(theme().user_input; "USER> ");
+
+        #[cfg(feature = "logging")]
+        self.record_session(
+            TrafficDirection::Tx,
+            now,
+            crate::monotonic_micros(),
+            &[text.as_bytes(), line_ending_bytes].concat(),
+        );
 
-        // TODO HANDLE MULTI-LINE USER INPUT IN THE UI AAAAAAAAA
+        // Typed input is a line at a time (the compose editor sends each of its lines separately),
+        // but macros can still hold several, so each is shown as its own user line.
         for (trunc, _orig, _range) in line_ending_iter(text.as_bytes(), &tx_line_ending) {
             #[cfg(not(feature = "macros"))]
             let macro_sensitivity = None;
 
             let line = if let Some(true) = macro_sensitivity {
-                let text = span!(Style::new().dark_gray(); "*".repeat(trunc.len()));
+                let text = span!(theme().dim_style(); "*".repeat(trunc.len()));
                 Line::from(vec![user_span.clone(), text])
             } else {
                 let mut line = Line::from(String::from_utf8_lossy(trunc).to_string());
                 line.spans.insert(0, user_span.clone());
-                line.style_all_spans(Color::DarkGray.into());
+                line.style_all_spans(theme().user_input.into());
                 line
             };
 
@@ -1526,22 +2163,10 @@ impl Buffer {
                 reloggable_raw,
             );
 
-            // If we're going to show this input, cut any unterminated port line short.
-            if let Some(last_rx) = self.styled_lines.rx.last_mut()
-                && matches!(
-                    last_rx.line_type,
-                    LineType::Port(LineFinished::Unfinished { .. })
-                )
-                && self
-                    .rendering
-                    .echo_user_input
-                    .filter_user_line(&user_buf_line.line_type)
-            {
-                last_rx.line_type = LineType::Port(LineFinished::CutShort);
-            }
-
             #[cfg(feature = "logging")]
-            if self.log_settings.log_text_to_file && self.log_settings.log_user_input {
+            if (self.log_settings.log_text_to_file || self.log_settings.log_jsonl_to_file)
+                && self.log_settings.log_user_input
+            {
                 self.log_handle
                     .log_tx_bytes(
                         now,
@@ -1550,9 +2175,8 @@ impl Buffer {
                     )
                     .expect("Logging worker has disappeared!");
             }
-            self.styled_lines.tx.push(user_buf_line);
+            self.push_user_line(user_buf_line);
         }
-        self.invalidate_height_cache();
     }
 }
 
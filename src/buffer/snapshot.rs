@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+use compact_str::{CompactString, ToCompactString};
+
+use super::{
+    Buffer,
+    buf_line::{BufLine, LineType},
+};
+
+/// The contents of every visible port line at the time of capture,
+/// used to highlight lines that have appeared since.
+pub struct BufferSnapshot {
+    lines: HashSet<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("No snapshot named \"{0}\" exists!")]
+pub struct SnapshotNotFound(pub CompactString);
+
+impl BufLine {
+    /// Returns the line's text content (ignoring styling) if it came from the port
+    /// and wasn't hidden by Color Rules.
+    fn snapshot_key(&self) -> Option<String> {
+        match &self.line_type {
+            LineType::User { .. } | LineType::PortHidden(_) => None,
+            _ => Some(
+                self.value
+                    .spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Buffer {
+    /// Takes a snapshot of all current port lines, replacing any existing snapshot with the same name.
+    ///
+    /// Returns how many unique lines were captured.
+    pub fn take_snapshot(&mut self, name: &str) -> usize {
+        let lines: HashSet<String> = self
+            .styled_lines
+            .rx
+            .iter()
+            .filter_map(|l| self.styled(l).snapshot_key())
+            .collect();
+        let unique_lines = lines.len();
+
+        self.snapshots
+            .insert(name.to_compact_string(), BufferSnapshot { lines });
+
+        unique_lines
+    }
+
+    /// Start highlighting lines not present in the named snapshot,
+    /// or stop if already diffing against it.
+    ///
+    /// Returns `Some` with the amount of added lines if diffing was started.
+    pub fn toggle_snapshot_diff(&mut self, name: &str) -> Result<Option<usize>, SnapshotNotFound> {
+        if self.diffing_against.as_deref() == Some(name) {
+            self.diffing_against = None;
+            return Ok(None);
+        }
+
+        let Some(snapshot) = self.snapshots.get(name) else {
+            return Err(SnapshotNotFound(name.to_compact_string()));
+        };
+
+        let added_lines = self
+            .styled_lines
+            .rx
+            .iter()
+            .filter_map(|l| self.styled(l).snapshot_key())
+            .filter(|key| !snapshot.lines.contains(key))
+            .count();
+
+        self.diffing_against = Some(name.to_compact_string());
+
+        Ok(Some(added_lines))
+    }
+
+    /// Returns `true` if a snapshot diff is active and the supplied line wasn't present in it.
+    pub(super) fn added_since_snapshot(&self, line: &BufLine) -> bool {
+        let Some(snapshot) = self
+            .diffing_against
+            .as_ref()
+            .and_then(|name| self.snapshots.get(name))
+        else {
+            return false;
+        };
+
+        self.styled(line)
+            .snapshot_key()
+            .is_some_and(|key| !snapshot.lines.contains(&key))
+    }
+}
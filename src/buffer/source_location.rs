@@ -0,0 +1,71 @@
+use compact_str::CompactString;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationKind {
+    /// The line a Rust panic occurred at.
+    Panic,
+    /// A single frame of a printed backtrace.
+    Backtrace,
+    #[cfg(feature = "defmt")]
+    /// Where a defmt ERROR-level log was invoked.
+    DefmtError,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A `file:line[:column]` location found in a port line, openable in the user's editor.
+pub struct SourceLocation {
+    pub kind: LocationKind,
+    pub file: CompactString,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+impl SourceLocation {
+    /// Looks for the location in a Rust panic message or backtrace frame.
+    ///
+    /// Handles both the current `panicked at src/main.rs:2:5:` format,
+    /// and the older `panicked at 'message', src/main.rs:2:5` one.
+    pub fn find_in_text(text: &str) -> Option<Self> {
+        if let Some((_, rest)) = text.split_once("panicked at ") {
+            let rest = match rest.strip_prefix('\'') {
+                Some(quoted) => quoted.rsplit_once("', ")?.1,
+                None => rest,
+            };
+            return parse_location(first_token(rest), LocationKind::Panic);
+        }
+
+        let frame = text.trim_start().strip_prefix("at ")?;
+        parse_location(first_token(frame), LocationKind::Backtrace)
+    }
+}
+
+fn first_token(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or_default()
+}
+
+/// Splits a `file:line[:column]` token, ignoring any trailing colon.
+fn parse_location(token: &str, kind: LocationKind) -> Option<SourceLocation> {
+    let token = token.strip_suffix(':').unwrap_or(token);
+
+    let (rest, last) = token.rsplit_once(':')?;
+    let last: u32 = last.parse().ok()?;
+
+    let (file, line, column) = match rest.rsplit_once(':') {
+        Some((file, line)) if !file.is_empty() && line.parse::<u32>().is_ok() => {
+            (file, line.parse().ok()?, Some(last))
+        }
+        _ => (rest, last, None),
+    };
+
+    // Keeping things like "at 10:30" from being taken as a location.
+    if !file.contains(['/', '\\', '.']) {
+        return None;
+    }
+
+    Some(SourceLocation {
+        kind,
+        file: file.into(),
+        line,
+        column,
+    })
+}
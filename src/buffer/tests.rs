@@ -1,11 +1,36 @@
 use crate::{
     buffer::{Buffer, LineEnding, line_ending_iter},
+    monotonic_micros,
+    settings::Settings,
     tui::color_rules::ColorRules,
 };
 use chrono::Local;
 use memchr::memmem::Finder;
 use rand::distr::SampleString;
 
+#[cfg(feature = "logging")]
+type BufferEvents = crossbeam::channel::Receiver<crate::app::Event>;
+#[cfg(not(feature = "logging"))]
+type BufferEvents = ();
+
+/// A buffer with `\n` line endings and no color rules,
+/// alongside what it sends to the app, which should be kept around for as long as it is.
+fn test_buffer(settings: &Settings) -> (Buffer, BufferEvents) {
+    #[cfg(feature = "logging")]
+    let (tx, events) = crossbeam::channel::bounded(0);
+    #[cfg(not(feature = "logging"))]
+    let events = ();
+
+    let buffer = Buffer::new(
+        b"\n",
+        ColorRules::default(),
+        settings,
+        #[cfg(feature = "logging")]
+        tx,
+    );
+    (buffer, events)
+}
+
 #[test]
 fn test_single_line() {
     let s = b"hello";
@@ -273,9 +298,9 @@ fn reconsumption_smoke_test() {
         } else if random_string {
             let text = alphanumeric.sample_string(&mut rng, byte_count as usize);
             let bytes = text.into();
-            buffer.fresh_rx_bytes(Local::now(), bytes);
+            buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), bytes);
         } else {
-            buffer.fresh_rx_bytes(Local::now(), bytes);
+            buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), bytes);
         }
     }
     let pre_raw = buffer.raw.clone();
@@ -288,3 +313,494 @@ fn reconsumption_smoke_test() {
     #[cfg(feature = "logging")]
     assert!(rx.is_empty());
 }
+
+#[test]
+fn height_tree_matches_naive_sums() {
+    use crate::buffer::height_index::HeightTree;
+    use rand::prelude::*;
+
+    let mut rng = rand::rng();
+    let mut tree = HeightTree::default();
+    let mut naive: Vec<usize> = Vec::new();
+
+    for _ in 0..2048 {
+        match rng.random_range(0..10) {
+            0 if !naive.is_empty() => {
+                let len = rng.random_range(0..naive.len());
+                tree.truncate(len);
+                naive.truncate(len);
+            }
+            1..=3 if !naive.is_empty() => {
+                let index = rng.random_range(0..naive.len());
+                let value = rng.random_range(0..8);
+                tree.set(index, value);
+                naive[index] = value;
+            }
+            _ => {
+                let value = rng.random_range(0..8);
+                tree.push(value);
+                naive.push(value);
+            }
+        }
+
+        assert_eq!(tree.len(), naive.len());
+        let count = rng.random_range(0..=naive.len());
+        assert_eq!(tree.prefix_sum(count), naive[..count].iter().sum::<usize>());
+        assert_eq!(tree.total(), naive.iter().sum::<usize>());
+    }
+}
+
+#[test]
+fn indexed_height_matches_lines() {
+    use rand::prelude::*;
+
+    let line_ending = b"\n";
+    let settings = Settings::default();
+
+    let (mut buffer, _events) = test_buffer(&settings);
+
+    let mut rng = rand::rng();
+    let alphanumeric = rand::distr::Alphanumeric;
+
+    let naive_height = |buffer: &Buffer| -> usize {
+        let weight = |l: &crate::buffer::buf_line::BufLine| {
+            if buffer.rendering.wrap_text {
+                l.get_line_height() as usize
+            } else {
+                1
+            }
+        };
+        buffer
+            .styled_lines
+            .rx
+            .iter()
+            .filter(|l| buffer.rx_line_visible(l))
+            .map(weight)
+            .sum::<usize>()
+            + buffer
+                .styled_lines
+                .tx
+                .iter()
+                .filter(|l| buffer.tx_line_visible(l))
+                .map(weight)
+                .sum::<usize>()
+    };
+
+    for _ in 0..256 {
+        let byte_count = rng.random_range(1..=64);
+        let mut text = alphanumeric.sample_string(&mut rng, byte_count);
+        if rng.random_bool(0.5) {
+            text.push('\n');
+        }
+        if rng.random_bool(0.2) {
+            buffer.append_user_text(
+                &text,
+                line_ending,
+                #[cfg(feature = "macros")]
+                None,
+            );
+        } else {
+            buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), text.into());
+        }
+        assert_eq!(buffer.combined_height(), naive_height(&buffer));
+    }
+
+    let mut rendering = buffer.rendering.clone();
+    rendering.wrap_text = !rendering.wrap_text;
+    buffer.update_render_settings(rendering);
+    assert_eq!(buffer.combined_height(), naive_height(&buffer));
+}
+
+#[test]
+fn evicted_styling_restores_identically() {
+    let mut settings = Settings::default();
+    settings.rendering.styled_line_limit = 4;
+
+    let (mut limited, _events) = test_buffer(&settings);
+    settings.rendering.styled_line_limit = 0;
+    let (mut unlimited, _unlimited_events) = test_buffer(&settings);
+
+    for i in 0..32 {
+        let text = format!("\x1b[3{}mline {i}\x1b[0m\n", i % 8);
+        let (now, now_us) = (Local::now(), monotonic_micros());
+        limited.fresh_rx_bytes(now, now_us, text.clone().into());
+        unlimited.fresh_rx_bytes(now, now_us, text.into());
+    }
+
+    let evicted = limited
+        .styled_lines
+        .rx
+        .iter()
+        .filter(|l| l.styling_evicted())
+        .count();
+    assert_eq!(evicted, limited.styled_lines.rx.len() - 4);
+
+    for (limited_line, unlimited_line) in limited
+        .styled_lines
+        .rx
+        .iter()
+        .zip(&unlimited.styled_lines.rx)
+    {
+        assert_eq!(limited.styled(limited_line).as_ref(), unlimited_line);
+    }
+
+    limited.update_render_settings(settings.rendering.clone());
+    assert_eq!(limited.styled_lines, unlimited.styled_lines);
+}
+
+#[test]
+fn viewed_lines_keep_styling_until_scrolled_away() {
+    let mut settings = Settings::default();
+    settings.rendering.styled_line_limit = 4;
+
+    let (mut buffer, _events) = test_buffer(&settings);
+    buffer.last_terminal_size = ratatui::layout::Size::new(80, 3);
+
+    for i in 0..32 {
+        let text = format!("\x1b[3{}mline {i}\x1b[0m\n", i % 8);
+        buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), text.into());
+    }
+    let evicted = |buffer: &Buffer| -> Vec<usize> {
+        (buffer.styled_lines.rx.iter().enumerate())
+            .filter(|(_, l)| l.styling_evicted())
+            .map(|(index, _)| index)
+            .collect()
+    };
+    assert_eq!(evicted(&buffer).len(), 28);
+
+    buffer.state.vert_scroll = 5;
+    buffer.style_viewed_lines();
+    let still_evicted = evicted(&buffer);
+    assert!((5..=8).all(|index| !still_evicted.contains(&index)));
+    assert_eq!(still_evicted.len(), 24);
+
+    // More lines arriving shouldn't take the styling from lines still in view.
+    buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), "line 32\n".into());
+    let still_evicted = evicted(&buffer);
+    assert!((5..=8).all(|index| !still_evicted.contains(&index)));
+
+    buffer.state.vert_scroll = 20;
+    buffer.style_viewed_lines();
+    let still_evicted = evicted(&buffer);
+    assert!((5..=8).all(|index| still_evicted.contains(&index)));
+    assert!((20..=23).all(|index| !still_evicted.contains(&index)));
+}
+
+#[test]
+fn wide_chars_wrap_by_display_width() {
+    let mut settings = Settings::default();
+    settings.rendering.timestamps = false;
+    settings.rendering.show_line_ending = false;
+
+    let (mut buffer, _events) = test_buffer(&settings);
+    // Ten usable columns, with one taken by the scrollbar.
+    buffer.last_terminal_size = ratatui::layout::Size::new(11, 20);
+
+    buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), "日本語ab\n".into());
+    buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), "日本語日本語\n".into());
+
+    let heights: Vec<u16> = buffer
+        .styled_lines
+        .rx
+        .iter()
+        .map(|l| l.get_line_height())
+        .collect();
+    assert_eq!(heights, [1, 2]);
+}
+
+#[test]
+fn input_encodings_decode_to_utf8() {
+    use crate::buffer::InputEncoding;
+
+    let utf16: Vec<u8> = "hé\n2".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let le = LineEnding::Byte(b'\n');
+    let lines: Vec<_> = line_ending_iter(&utf16, &le)
+        .map(|(line, ..)| line)
+        .collect();
+
+    assert_eq!(
+        InputEncoding::Utf16Le.decode(lines[0]).as_ref(),
+        "hé".as_bytes()
+    );
+    // Second line starts with the `0x00` left over from the UTF-16 line ending.
+    assert_eq!(InputEncoding::Utf16Le.decode(lines[1]).as_ref(), b"2");
+    assert_eq!(InputEncoding::Auto.decode(lines[1]).as_ref(), b"2");
+    assert_eq!(InputEncoding::Auto.decode(b"plain").as_ref(), b"plain");
+
+    assert_eq!(
+        InputEncoding::Latin1.decode(b"caf\xe9").as_ref(),
+        "café".as_bytes()
+    );
+    assert_eq!(InputEncoding::Utf8.decode(b"caf\xe9").as_ref(), b"caf\xe9");
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn defmt_module_filter_directives() {
+    use crate::buffer::defmt::module_filter::ModuleFilter;
+
+    let filter = ModuleFilter::parse("my_app::*, !my_app::radio, other_crate");
+    assert!(filter.allows("my_app::net"));
+    assert!(filter.allows("other_crate::deep::module"));
+    // Exclusions cover submodules too, and win over inclusions.
+    assert!(!filter.allows("my_app::radio"));
+    assert!(!filter.allows("my_app::radio::driver"));
+    // `other_crate` shouldn't match just by prefix.
+    assert!(!filter.allows("other_crate_2"));
+    assert!(!filter.allows("embassy_executor"));
+
+    let exclude_only = ModuleFilter::parse("!embassy_*,");
+    assert!(exclude_only.allows("my_app"));
+    assert!(!exclude_only.allows("embassy_executor::raw"));
+
+    assert!(ModuleFilter::parse(" , ! ,").is_empty());
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn defmt_stats_flag_suspect_framing() {
+    use crate::buffer::defmt::{DefmtPacketError, stats::DefmtStats};
+
+    let mut stats = DefmtStats::default();
+    // A missing table isn't a framing problem.
+    stats.record_failure(&DefmtPacketError::NoDecoder);
+    assert!(!stats.framing_suspect());
+
+    stats.record_frame(Some(defmt_parser::Level::Info));
+    stats.record_frame(None);
+    stats.record_failure(&DefmtPacketError::RzcobsDecompress);
+    assert!(!stats.framing_suspect());
+    assert_eq!(stats.total_frames(), 2);
+    assert_eq!(stats.total_failures(), 2);
+
+    stats.record_failure(&DefmtPacketError::DefmtDecode);
+    assert!(stats.framing_suspect());
+
+    let per_level: Vec<_> = stats.frames_per_level().collect();
+    assert_eq!(per_level[2], ("Info", 1));
+    assert_eq!(per_level[5], ("No Level", 1));
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn device_timestamps_convert_to_wall_clock() {
+    use crate::{
+        buffer::defmt::timestamp::TimestampAnchor,
+        settings::{Defmt, DeviceTimestampMode},
+    };
+    use chrono::TimeDelta;
+
+    let mut settings = Defmt {
+        device_timestamp_mode: DeviceTimestampMode::SinceBoot,
+        device_tick_rate: 1_000,
+        ..Default::default()
+    };
+    let mut anchor = TimestampAnchor::default();
+    let now = Local::now();
+
+    assert_eq!(anchor.convert("1500", now, &settings).unwrap(), "1.500000s");
+    // Already in seconds, tick rate is ignored.
+    assert_eq!(anchor.convert("2.25", now, &settings).unwrap(), "2.250000s");
+    assert_eq!(anchor.convert("not a tick", now, &settings), None);
+
+    settings.device_timestamp_mode = DeviceTimestampMode::WallClock;
+    let first = anchor.convert("1000", now, &settings).unwrap();
+    assert_eq!(first, now.format("%H:%M:%S%.6f").to_string());
+
+    // Anchored to the first frame, so later host arrival times don't matter.
+    let later = anchor
+        .convert("3000", now + TimeDelta::seconds(10), &settings)
+        .unwrap();
+    let expected = now + TimeDelta::seconds(2);
+    assert_eq!(later, expected.format("%H:%M:%S%.6f").to_string());
+
+    // Ticks went backwards, device must've reset.
+    let reset_at = now + TimeDelta::seconds(20);
+    let after_reset = anchor.convert("0", reset_at, &settings).unwrap();
+    assert_eq!(after_reset, reset_at.format("%H:%M:%S%.6f").to_string());
+
+    settings.device_timestamp_mode = DeviceTimestampMode::AsSent;
+    assert_eq!(anchor.convert("1000", now, &settings), None);
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn device_elf_hash_found_across_chunks() {
+    let settings = Settings::default();
+
+    let (mut buffer, _events) = test_buffer(&settings);
+
+    buffer.fresh_rx_bytes(
+        Local::now(),
+        monotonic_micros(),
+        "I (42) app_init: ELF fi".into(),
+    );
+    buffer.fresh_rx_bytes(
+        Local::now(),
+        monotonic_micros(),
+        "le SHA256:  3a5b0c".into(),
+    );
+    assert!(!buffer.take_new_device_elf_hash());
+
+    buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), "9e1f2d4c6b...\n".into());
+    assert!(buffer.take_new_device_elf_hash());
+    assert_eq!(buffer.device_elf_hash.as_deref(), Some("3a5b0c9e1f2d4c6b"));
+    // Only reported once per new hash.
+    assert!(!buffer.take_new_device_elf_hash());
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn defmt_raw_resync_skips_corruption() {
+    use crate::buffer::defmt::{
+        RAW_RESYNC_BUDGET, RAW_RESYNC_MAX_SKIP, RawResync, RawResyncOutcome,
+    };
+    use defmt_decoder::DecodeError;
+
+    // Stand-in for a defmt table, where every frame is 0xAA followed by a single byte.
+    let decode = |bytes: &[u8]| match bytes {
+        [] | [0xAA] => Err(DecodeError::UnexpectedEof),
+        [0xAA, _, ..] => Ok(2),
+        _ => Err(DecodeError::Malformed),
+    };
+
+    let mut resync = RawResync::default();
+    assert_eq!(
+        resync.find_next_frame(&[0x01, 0x02, 0xAA, 0x10, 0xAA, 0x11], decode),
+        RawResyncOutcome::Skip(2)
+    );
+    // A frame followed by more garbage isn't trusted.
+    assert_eq!(
+        resync.find_next_frame(&[0x01, 0xAA, 0x10, 0x05, 0xAA, 0x20, 0xAA, 0x21], decode),
+        RawResyncOutcome::Skip(4)
+    );
+    assert_eq!(
+        resync.find_next_frame(&[0x01, 0xAA], decode),
+        RawResyncOutcome::NeedMoreData
+    );
+    assert_eq!(
+        resync.find_next_frame(&[0x01; RAW_RESYNC_MAX_SKIP + 8], decode),
+        RawResyncOutcome::GiveUp
+    );
+
+    let garbled = [0x01, 0xAA, 0x10];
+    let mut resync = RawResync::default();
+    for _ in 0..RAW_RESYNC_BUDGET {
+        assert_eq!(
+            resync.find_next_frame(&garbled, decode),
+            RawResyncOutcome::Skip(1)
+        );
+    }
+    assert_eq!(
+        resync.find_next_frame(&garbled, decode),
+        RawResyncOutcome::GiveUp
+    );
+
+    // Decoding enough frames cleanly refills the budget.
+    for _ in 0..16 {
+        resync.frame_decoded();
+    }
+    assert_eq!(
+        resync.find_next_frame(&garbled, decode),
+        RawResyncOutcome::Skip(1)
+    );
+}
+
+#[test]
+fn source_locations_found_in_panics() {
+    use crate::buffer::{LocationKind, SourceLocation};
+
+    let panic =
+        SourceLocation::find_in_text("thread 'main' panicked at src/main.rs:12:5:").unwrap();
+    assert_eq!(panic.kind, LocationKind::Panic);
+    assert_eq!(panic.file, "src/main.rs");
+    assert_eq!((panic.line, panic.column), (12, Some(5)));
+
+    let old_panic =
+        SourceLocation::find_in_text("panicked at 'oh no, it broke', src/lib.rs:40:9").unwrap();
+    assert_eq!(old_panic.file, "src/lib.rs");
+    assert_eq!((old_panic.line, old_panic.column), (40, Some(9)));
+
+    let frame = SourceLocation::find_in_text("             at ./src/radio.rs:7").unwrap();
+    assert_eq!(frame.kind, LocationKind::Backtrace);
+    assert_eq!(frame.file, "./src/radio.rs");
+    assert_eq!((frame.line, frame.column), (7, None));
+
+    assert_eq!(SourceLocation::find_in_text("Alarm set at 10:30"), None);
+    assert_eq!(SourceLocation::find_in_text("at 10:30"), None);
+    assert_eq!(SourceLocation::find_in_text("hello world"), None);
+}
+
+#[test]
+fn search_selects_matching_port_lines() {
+    let settings = Settings::default();
+
+    let (mut buffer, _events) = test_buffer(&settings);
+    buffer.last_terminal_size = ratatui::layout::Size::new(80, 3);
+
+    for i in 0..10 {
+        // Split across styled spans, so only found by searching the shown text.
+        let text = if i == 5 {
+            format!("\x1b[31mNee\x1b[0mdle {i}\n")
+        } else if i == 2 {
+            format!("Needle {i}\n")
+        } else {
+            format!("hay {i}\n")
+        };
+        buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), text.into());
+    }
+    // Following the newest lines, like the app does.
+    buffer.scroll_by(i32::MIN);
+    assert_eq!(buffer.selected_line_bytes().unwrap(), b"hay 9");
+
+    assert!(buffer.search_port_lines("needle", true));
+    assert_eq!(
+        buffer.selected_line_bytes().unwrap(),
+        b"\x1b[31mNee\x1b[0mdle 5"
+    );
+    assert!(buffer.search_port_lines("NEEDLE", true));
+    assert_eq!(buffer.selected_line_bytes().unwrap(), b"Needle 2");
+    assert!(!buffer.search_port_lines("needle", true));
+    assert_eq!(buffer.selected_line_bytes().unwrap(), b"Needle 2");
+
+    assert!(buffer.search_port_lines("needle", false));
+    assert_eq!(
+        buffer.selected_line_bytes().unwrap(),
+        b"\x1b[31mNee\x1b[0mdle 5"
+    );
+    assert!(!buffer.search_port_lines("missing", true));
+}
+
+#[cfg(feature = "logging")]
+#[test]
+fn replayed_input_shown_as_user_lines() {
+    use crate::buffer::buf_line::LineType;
+    use chrono::TimeZone;
+
+    let settings = Settings::default();
+    let (mut buffer, _events) = test_buffer(&settings);
+    let sent_at = Local.with_ymd_and_hms(2024, 5, 17, 12, 0, 0).unwrap();
+
+    buffer.fresh_rx_bytes(Local::now(), monotonic_micros(), b"> ".to_vec());
+    buffer.replayed_user_bytes(sent_at, b"reset\r\n");
+    buffer.replayed_user_bytes(sent_at, b"no ending");
+
+    let sent: Vec<_> = buffer
+        .styled_lines
+        .tx
+        .iter()
+        .map(|line| {
+            let LineType::User { reloggable_raw, .. } = &line.line_type else {
+                panic!("replayed input should be a user line");
+            };
+            (line.timestamp, reloggable_raw.as_slice())
+        })
+        .collect();
+    assert_eq!(
+        sent,
+        [
+            (sent_at, b"reset\r\n".as_slice()),
+            (sent_at, b"no ending".as_slice())
+        ]
+    );
+}
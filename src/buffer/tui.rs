@@ -4,35 +4,53 @@
 // In addition, placing the allow directive inside the function
 // was't silencing it, so I'm making it file-wide.
 
-use std::cmp::Ordering;
+use std::{borrow::Cow, cmp::Ordering, iter::repeat_n};
 
+use bstr::ByteSlice;
 use itertools::{Either, Itertools};
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Clear, Paragraph, Scrollbar, ScrollbarOrientation, Wrap},
 };
 use ratatui_macros::{horizontal, vertical};
+use unicode_width::UnicodeWidthChar;
 
 use crate::{
     buffer::{LineType, buf_line::RenderSettings},
     config_adjacent_path,
     settings::HexHighlightStyle,
-    traits::{ToggleBool, interleave_by},
-    tui::color_rules::{COLOR_RULES_PATH, ColorRuleLoadError, ColorRules},
+    traits::{ByteSuffixCheck, LineHelpers, ToggleBool, interleave_by},
+    tui::{
+        color_rules::{COLOR_RULES_PATH, ColorRuleLoadError, ColorRules},
+        theme::theme,
+    },
 };
 
-use super::{Buffer, UserEcho, buf_line::BufLine, hex_spans::*};
+use super::{Buffer, StyledLines, UserEcho, buf_line::BufLine, hex_spans::*};
 
 impl Buffer {
     /// Updates each BufLine's render height with the new terminal width, returning the sum total at the end
     pub fn update_wrapped_line_heights(&mut self) -> usize {
-        self.styled_lines.rx.iter_mut().fold(0, |total, l| {
+        let total = self.styled_lines.rx.iter_mut().fold(0, |total, l| {
             let render_settings = RenderSettings {
                 rendering: &self.rendering,
                 #[cfg(feature = "defmt")]
                 defmt: &self.defmt_settings,
             };
-            let new_height = l.update_line_height(self.last_terminal_size.width, render_settings);
+            let new_height = if l.styling_evicted() {
+                // Restyle just long enough to measure it.
+                let restyled = StyledLines::restyle_evicted(
+                    l,
+                    &self.raw,
+                    &self.color_rules,
+                    &self.line_ending,
+                    self.last_terminal_size.width,
+                    render_settings,
+                );
+                l.set_line_height(restyled.get_line_height())
+            } else {
+                l.update_line_height(self.last_terminal_size.width, render_settings)
+            };
 
             total + new_height
         }) + self.styled_lines.tx.iter_mut().fold(0, |total, l| {
@@ -44,7 +62,9 @@ impl Buffer {
             let new_height = l.update_line_height(self.last_terminal_size.width, render_settings);
 
             total + new_height
-        })
+        });
+        self.invalidate_height_cache();
+        total
     }
     // #[cfg(feature = "defmt")]
     // fn rx_lines_iter(&self) -> impl Iterator<Item = &BufLine> {
@@ -59,25 +79,51 @@ impl Buffer {
     // fn rx_lines_iter(&self) -> impl Iterator<Item = &BufLine> {
     //     self.styled_lines.rx.iter()
     // }
-    fn buflines_iter(&self) -> impl Iterator<Item = &BufLine> {
-        let rx_iter = self.styled_lines.rx.iter().filter(|bf| match bf.line_type {
+    /// Returns `true` if the port line isn't filtered out by current settings.
+    pub(super) fn rx_line_visible(&self, line: &BufLine) -> bool {
+        match &line.line_type {
             LineType::PortHidden(_) => self.rendering.show_hidden_lines,
             #[cfg(feature = "defmt")]
             LineType::PortDefmt {
-                level: Some(level), ..
-            } => self.defmt_settings.max_log_level <= crate::settings::Level::from(level),
+                level, location, ..
+            } => {
+                let level_shown = level.is_none_or(|level| {
+                    self.defmt_settings
+                        .level_shown(crate::settings::Level::from(level))
+                });
+                let module_shown = !self.defmt_settings.filter_by_module
+                    || location
+                        .as_ref()
+                        .is_none_or(|location| self.defmt_module_filter.allows(location.module()));
+                level_shown && module_shown
+            }
             _ => true,
-        });
+        }
+    }
+    /// Returns `true` if the user line isn't filtered out by current settings.
+    pub(super) fn tx_line_visible(&self, line: &BufLine) -> bool {
+        self.rendering
+            .echo_user_input
+            .filter_user_line(&line.line_type)
+    }
+    /// Iterate over all visible port and user lines in order,
+    /// starting from the given indices into each.
+    pub(super) fn buflines_iter_from(
+        &self,
+        rx_start: usize,
+        tx_start: usize,
+    ) -> impl Iterator<Item = &BufLine> {
+        let rx_iter = self.styled_lines.rx[rx_start..]
+            .iter()
+            .filter(|bf| self.rx_line_visible(bf));
         if self.rendering.echo_user_input == UserEcho::None {
             Either::Left(rx_iter)
         } else {
             Either::Right(interleave_by(
                 rx_iter,
-                self.styled_lines.tx.iter().filter(|l| {
-                    self.rendering
-                        .echo_user_input
-                        .filter_user_line(&l.line_type)
-                }),
+                self.styled_lines.tx[tx_start..]
+                    .iter()
+                    .filter(|l| self.tx_line_visible(l)),
                 |port, user| match port.range().start.cmp(&user.range().start) {
                     Ordering::Equal => port.timestamp <= user.timestamp,
                     Ordering::Less => true,
@@ -88,12 +134,173 @@ impl Buffer {
     }
     pub fn lines_iter(&self) -> (impl Iterator<Item = Line<'_>>, u16) {
         let (buflines, wrapped_scroll) = self.visible_buflines_iter();
+        // Only highlighting the selection when scrolled up, otherwise the newest line
+        // would constantly be underlined while following incoming data.
+        let highlighted = if self.state.stuck_to_bottom {
+            None
+        } else {
+            self.selected_port_line()
+        };
         (
-            buflines.map(|l| l.as_line(self.line_render_settings())),
+            buflines.map(move |l| {
+                let mut line = match self.styled(l) {
+                    Cow::Borrowed(styled) => styled.as_line(self.line_render_settings()),
+                    Cow::Owned(restyled) => {
+                        restyled.as_line(self.line_render_settings()).new_owned()
+                    }
+                };
+                if self.added_since_snapshot(l) {
+                    // Dark green, distinct enough without clobbering any ANSI/Color Rule foregrounds.
+                    line = line.bg(Color::Indexed(22));
+                }
+                if highlighted.is_some_and(|h| std::ptr::eq(h, l)) {
+                    line.underlined()
+                } else {
+                    line
+                }
+            }),
             wrapped_scroll,
         )
     }
 
+    /// Returns the "selected" line for line-based actions,
+    /// being the bottom-most visible line that came from the port.
+    fn selected_port_line(&self) -> Option<&BufLine> {
+        if self.rendering.hex_view {
+            return None;
+        }
+        // Not the last of `visible_buflines_iter`, since it also has lines cut off by the bottom.
+        let total_rows = self.combined_height();
+        let height = self.last_terminal_size.height as usize;
+        let bottom_row = (self.state.vert_scroll + height)
+            .min(total_rows)
+            .checked_sub(1)?;
+        let (rx_index, _, _) = self.locate_row(bottom_row);
+
+        let rx = &self.styled_lines.rx;
+        let index = (0..=rx_index).rev().find(|index| {
+            rx.get(*index)
+                .is_some_and(|line| self.rx_line_visible(line))
+        })?;
+        // Only user lines in view.
+        let line_height = if self.rendering.wrap_text {
+            rx[index].get_line_height() as usize
+        } else {
+            1
+        };
+        (self.rows_before_port_line(index) + line_height > self.state.vert_scroll)
+            .then(|| &rx[index])
+    }
+
+    /// Returns a copy of the raw bytes backing the selected port line, without the RX line ending.
+    ///
+    /// Returns `None` if no port line is visible, or if it would be empty once the line ending is removed.
+    pub fn selected_line_bytes(&self) -> Option<Vec<u8>> {
+        let line = self.selected_port_line()?;
+        let raw = self.raw.inner.get(line.range().clone())?;
+
+        let raw = if raw.has_line_ending(&self.line_ending) {
+            &raw[..raw.len() - self.line_ending.as_bytes().len()]
+        } else {
+            raw
+        };
+
+        if raw.is_empty() {
+            None
+        } else {
+            Some(raw.to_vec())
+        }
+    }
+
+    #[cfg(feature = "defmt")]
+    /// Returns a copy of the raw bytes of the selected port line, if it's a defmt frame that failed to decode.
+    pub fn selected_failed_defmt_frame(&self) -> Option<Vec<u8>> {
+        let line = self.selected_port_line()?;
+        if !self
+            .styled_lines
+            .failed_defmt_frames
+            .contains(&line.range().start)
+        {
+            return None;
+        }
+        self.raw.inner.get(line.range().clone()).map(<[u8]>::to_vec)
+    }
+
+    /// Returns the panic, backtrace, or defmt error location found in the selected port line.
+    pub fn selected_source_location(&self) -> Option<&crate::buffer::SourceLocation> {
+        self.selected_port_line()?.source_location.as_ref()
+    }
+
+    /// Words from the given number of newest visible port lines, newest line first and without repeats.
+    ///
+    /// Words are runs of letters, digits, and any of `_-./:`, at least two characters long.
+    pub fn recent_rx_words(&self, lines: usize) -> Vec<String> {
+        self.styled_lines
+            .rx
+            .iter()
+            .rev()
+            .filter(|line| self.rx_line_visible(line))
+            .take(lines)
+            .filter_map(|line| self.raw.inner.get(line.range().clone()))
+            .flat_map(|raw| {
+                let stripped = strip_ansi_escapes::strip(raw);
+                stripped
+                    .to_str_lossy()
+                    .split(|c: char| !(c.is_alphanumeric() || "_-./:".contains(c)))
+                    .filter(|word| word.chars().count() >= 2)
+                    .map(str::to_owned)
+                    .collect_vec()
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Scroll to the nearest visible port line whose text contains the query (ignoring ASCII case),
+    /// searching upwards (towards older lines) or downwards from the selected line.
+    ///
+    /// The found line becomes the selected line, returns `false` if nothing was found.
+    pub fn search_port_lines(&mut self, query: &str, upwards: bool) -> bool {
+        if query.is_empty() || self.rendering.hex_view {
+            return false;
+        }
+        let rx = &self.styled_lines.rx;
+        let start = match self.selected_port_line() {
+            Some(selected) => rx.partition_point(|l| l.range().start < selected.range().start),
+            None => rx.len(),
+        };
+
+        let matches = |index: &usize| {
+            let line = &rx[*index];
+            self.rx_line_visible(line)
+                && self
+                    .raw
+                    .inner
+                    .get(line.range().clone())
+                    .is_some_and(|raw| line.text_contains(query, raw))
+        };
+
+        let found = if upwards {
+            (0..start).rev().find(matches)
+        } else {
+            (start + 1..rx.len()).find(matches)
+        };
+        let Some(found) = found else {
+            return false;
+        };
+
+        // Selected line is the bottom-most visible one, so the found line is placed at the bottom.
+        let line_height = if self.rendering.wrap_text {
+            rx[found].get_line_height() as usize
+        } else {
+            1
+        };
+        let bottom_row = self.rows_before_port_line(found) + line_height;
+        self.state.vert_scroll = bottom_row.saturating_sub(self.last_terminal_size.height as usize);
+        self.state.stuck_to_bottom = false;
+        self.scroll_by(0);
+        true
+    }
+
     fn visible_buflines_iter(&self) -> (impl Iterator<Item = &BufLine>, u16) {
         let last_size = &self.last_terminal_size;
         let area_height = last_size.height as usize;
@@ -101,6 +308,12 @@ impl Buffer {
         let more_lines_than_height =
             (area_height < self.styled_lines.rx.len()) || (area_height < self.combined_height());
 
+        let (rx_start, tx_start, rows_before_start) = if more_lines_than_height {
+            self.locate_row(self.state.vert_scroll)
+        } else {
+            (0, 0, 0)
+        };
+
         let entries_to_skip: usize;
         let entries_to_take: usize;
 
@@ -108,53 +321,55 @@ impl Buffer {
 
         if more_lines_than_height {
             let desired_visible_lines = area_height;
-            if self.rendering.wrap_text {
-                let vert_scroll = self.state.vert_scroll;
-                let (spillover_index, _spillover_lines_visible, spilt_line_total_height) = {
-                    let mut current_line_index: usize = 0;
-                    let mut current_line_height: usize = 0;
-
-                    let mut lines_from_top: usize = 0;
-                    for (index, entries_lines) in self
-                        .buflines_iter()
-                        .map(|l| l.get_line_height())
-                        .enumerate()
-                    {
-                        current_line_index = index;
-                        current_line_height = entries_lines as usize;
+            let vert_scroll = self.state.vert_scroll;
+            let wrap_text = self.rendering.wrap_text;
 
-                        lines_from_top += entries_lines as usize;
-                        if lines_from_top > vert_scroll {
-                            break;
-                        }
+            let row_weight = |l: &BufLine| -> usize {
+                if wrap_text {
+                    l.get_line_height() as usize
+                } else {
+                    1
+                }
+            };
+
+            // The height index got us to the nearest port line at or before the scroll position,
+            // so only walk past any user lines between it and the actual top entry.
+            let (top_index, top_line_height, found) = {
+                let mut rows_before = rows_before_start;
+                let mut top_index: usize = 0;
+                let mut top_line_height: usize = 0;
+                let mut found = false;
+
+                for (index, line) in self.buflines_iter_from(rx_start, tx_start).enumerate() {
+                    top_index = index;
+                    top_line_height = row_weight(line);
+
+                    if rows_before + top_line_height > vert_scroll {
+                        found = true;
+                        break;
                     }
+                    rows_before += top_line_height;
+                }
 
-                    // Hmm, this crashed with an overflow when resizing once.
-                    // TODO, ensure scrolls are valid for size calcing? Unsure.
-                    let visible_lines = lines_from_top.saturating_sub(vert_scroll);
-
-                    let spillover_lines = if current_line_height == visible_lines {
-                        // If we can see all of the lines of this entry, then it's not spilling over
-                        0
-                    } else {
-                        wrapped_scroll = (current_line_height - visible_lines) as u16;
-                        // Returns how many lines are visibly spilling over from the
-                        // entry being cropped by the top of the buffer window.
-                        visible_lines
-                    };
-
-                    (current_line_index, spillover_lines, current_line_height)
-                };
+                if found && wrap_text {
+                    // Rows of this entry cropped by the top of the buffer window.
+                    wrapped_scroll = (vert_scroll - rows_before) as u16;
+                } else if wrap_text {
+                    // Scrolled past every line, keep whatever's left of the last one in view.
+                    wrapped_scroll = top_line_height as u16;
+                }
 
-                // debug!("scroll: {vert_scroll}, index: {spillover_index}, spillover lines: {spillover_lines_visible}, wrapped scroll: {wrapped_scroll}");
+                (top_index, top_line_height, found)
+            };
 
-                entries_to_skip = spillover_index;
+            if wrap_text {
+                entries_to_skip = top_index;
                 entries_to_take = {
-                    let mut visible_lines: isize = -(spilt_line_total_height as isize);
+                    let mut visible_lines: isize = -(top_line_height as isize);
                     let mut entries_to_take = 0;
 
                     for entry_lines in self
-                        .buflines_iter()
+                        .buflines_iter_from(rx_start, tx_start)
                         .skip(entries_to_skip)
                         .map(|l| l.get_line_height())
                     {
@@ -162,22 +377,15 @@ impl Buffer {
                         visible_lines += entry_lines as isize;
 
                         if visible_lines > desired_visible_lines as isize {
-                            // debug!(
-                            //     "visible_lines: {visible_lines}, desired: {desired_visible_lines}"
-                            // );
                             break;
                         }
                     }
 
-                    // debug!(
-                    //     "entries_to_skip: {entries_to_skip}, entries_to_take: {entries_to_take}"
-                    // );
-
                     entries_to_take
                 };
             } else {
-                entries_to_skip = self.state.vert_scroll;
-                // self.lines.len() - last_size.height as usize;
+                // If the scroll is past every line, show nothing.
+                entries_to_skip = if found { top_index } else { top_index + 1 };
                 entries_to_take = desired_visible_lines;
             }
         } else {
@@ -186,7 +394,7 @@ impl Buffer {
         }
 
         (
-            self.buflines_iter()
+            self.buflines_iter_from(rx_start, tx_start)
                 .skip(entries_to_skip)
                 .take(entries_to_take),
             wrapped_scroll,
@@ -196,9 +404,14 @@ impl Buffer {
     pub fn terminal_paragraph(&self) -> Paragraph<'_> {
         let (lines_iter, vert_scroll) = self.lines_iter();
         let lines: Vec<_> = lines_iter.collect();
+        let hor_scroll = if self.rendering.wrap_text {
+            0
+        } else {
+            self.state.hor_scroll
+        };
         let para = Paragraph::new(lines)
             .block(Block::new().borders(Borders::RIGHT))
-            .scroll((vert_scroll, 0));
+            .scroll((vert_scroll, hor_scroll));
         if self.rendering.wrap_text {
             para.wrap(Wrap { trim: false })
         } else {
@@ -216,6 +429,11 @@ impl Buffer {
         self.scroll_by(amount);
     }
 
+    /// Scroll sideways by the given amount of columns, only visible while text wrapping is off.
+    pub fn scroll_horizontally(&mut self, right: i16) {
+        self.state.hor_scroll = self.state.hor_scroll.saturating_add_signed(right);
+    }
+
     pub fn scroll_by(&mut self, up: i32) {
         let total_lines = self.combined_height();
 
@@ -264,70 +482,32 @@ impl Buffer {
             .content_length(total_lines.saturating_sub(last_size.height as usize));
     }
     // fn wrapped_line_count(&self) -> usize {
-    //     self.buflines_iter().map(|l| l.get_line_height()).sum()
+    //     self.buflines_iter_from(0, 0).map(|l| l.get_line_height()).sum()
     // }
 
     /// Returns the total amount of lines that can be rendered,
     /// taking into account if text wrapping is enabled or not.
     pub fn combined_height(&self) -> usize {
-        if let Some(cached) = self.cached_combined_height.get() {
-            return cached;
-        }
         if self.raw.inner.is_empty() {
             return 0;
         }
-        let result = if !self.rendering.hex_view {
-            if self.rendering.wrap_text {
-                self.buflines_iter()
-                    .map(|l| l.get_line_height() as usize)
-                    .sum()
-            } else {
-                // self.buflines_iter().count()
-                //
-                // inlining some of its functionality instead
-                // to avoid .count()-ing a potentially huge iter (100k+ items)
-                // (especially when wrapping is disabled, when its even more dead simple)
-                // when we can just read the length of a vector
-                let can_use_vector_length =
-                    { !self.rendering.wrap_text && self.rendering.show_hidden_lines };
-                #[cfg(feature = "defmt")]
-                let can_use_vector_length = {
-                    can_use_vector_length
-                        && self.defmt_settings.max_log_level == crate::settings::Level::Trace
-                };
-                let rx_lines = if can_use_vector_length {
-                    self.styled_lines.rx.len()
-                } else {
-                    self.buflines_iter().count()
-                };
-                if self.rendering.echo_user_input == UserEcho::None {
-                    rx_lines
-                } else {
-                    rx_lines
-                        + self
-                            .styled_lines
-                            .tx
-                            .iter()
-                            .filter(|l| {
-                                self.rendering
-                                    .echo_user_input
-                                    .filter_user_line(&l.line_type)
-                            })
-                            .count()
-                }
-            }
+        if !self.rendering.hex_view {
+            // Kept up to date incrementally as lines are added,
+            // so this stays cheap even with hundreds of thousands of lines.
+            self.indexed_height()
         } else {
             let header_margin = { if self.rendering.hex_view_header { 2 } else { 0 } };
             (self.raw.inner.len() as f64 / (self.state.hex_bytes_per_line as f64)).ceil() as usize
                 + header_margin
-        };
-
-        self.cached_combined_height.set(Some(result));
-        result
+        }
     }
 
+    /// Marks every line's contribution to `combined_height` as needing recalculation,
+    /// call when line heights or visibility filters change.
+    ///
+    /// Not needed for newly added lines, those are picked up automatically.
     pub fn invalidate_height_cache(&self) {
-        self.cached_combined_height.set(None);
+        self.mark_height_index_stale();
     }
 
     pub fn port_lines_len(&self) -> usize {
@@ -340,9 +520,7 @@ impl Buffer {
     ) -> std::io::Result<()> {
         self.last_terminal_size = {
             let mut terminal_size = terminal.size()?;
-            // `2` is the lines from the repeating_pattern_widget and the input buffer.
-            // Might need to make more dynamic later?
-            terminal_size.height = terminal_size.height.saturating_sub(2);
+            terminal_size.height = terminal_size.height.saturating_sub(self.reserved_lines);
             terminal_size
         };
         self.update_wrapped_line_heights();
@@ -351,6 +529,26 @@ impl Buffer {
         Ok(())
     }
 
+    /// Change how many lines are taken up under the buffer, resizing if it's different.
+    pub fn set_reserved_lines(
+        &mut self,
+        lines: u16,
+        terminal: &mut ratatui::Terminal<impl ratatui::prelude::Backend>,
+    ) -> std::io::Result<()> {
+        if lines == self.reserved_lines {
+            return Ok(());
+        }
+        self.reserved_lines = lines;
+        self.update_terminal_size(terminal)
+    }
+
+    /// Lines are styled with the theme's colors as they come in, so they're restyled once it changes.
+    pub fn restyle_for_theme(&mut self) {
+        if !self.raw.inner.is_empty() {
+            self.reconsume_raw_buffer();
+        }
+    }
+
     pub fn reload_color_rules(&mut self) -> Result<(), ColorRuleLoadError> {
         self.color_rules = ColorRules::load_from_file(config_adjacent_path(COLOR_RULES_PATH))?;
         self.reconsume_raw_buffer();
@@ -523,7 +721,7 @@ impl Buffer {
             Line::raw("ASCII").centered().render(ascii_text, buf);
             Block::new()
                 .borders(Borders::TOP)
-                .border_style(Style::new().dark_gray())
+                .border_style(theme().dim_style())
                 .render(sep_line_area, buf);
         }
         scrollbar_area.height = area.height;
@@ -531,7 +729,7 @@ impl Buffer {
 
         let vert_block = Block::new()
             .borders(Borders::LEFT)
-            .border_style(Style::new().dark_gray());
+            .border_style(theme().dim_style());
 
         let vert_line = &vert_block;
 
@@ -583,7 +781,7 @@ impl Buffer {
                     scrollbar_area.x,
                     sep_line_area.y,
                     symbols::line::CROSS,
-                    Style::new().dark_gray(),
+                    theme().dim_style(),
                 );
             }
         }
@@ -593,7 +791,7 @@ impl Buffer {
                 line_1_area.x,
                 sep_line_area.y,
                 symbols::line::CROSS,
-                Style::new().dark_gray(),
+                theme().dim_style(),
             );
         }
 
@@ -602,7 +800,7 @@ impl Buffer {
                 line_2_area.x,
                 sep_line_area.y,
                 symbols::line::CROSS,
-                Style::new().dark_gray(),
+                theme().dim_style(),
             );
         }
 
@@ -633,7 +831,7 @@ impl Buffer {
         );
 
         if !self.state.stuck_to_bottom {
-            let scroll_notice = Line::raw("More... Shift+PgDn to jump to newest").dark_gray();
+            let scroll_notice = Line::raw("More... Shift+PgDn to jump to newest").fg(theme().dim);
             let notice_area = {
                 let mut rect = area;
                 rect.y = rect.bottom().saturating_sub(1);
@@ -684,7 +882,7 @@ fn byte_color_ascii(byte: u8, colors: AlternatingStyles) -> Style {
         }
         b' ' => Style::new().green(),
 
-        _ => Style::new().dark_gray(),
+        _ => theme().dim_style(),
     }
 }
 
@@ -696,14 +894,14 @@ fn style_select(
     match highlight_config {
         HexHighlightStyle::None => fallback_alternating_styles.into(),
         HexHighlightStyle::DarkenNulls => match byte {
-            0x00 => Style::new().dark_gray(),
+            0x00 => theme().dim_style(),
             _ => fallback_alternating_styles.into(),
         },
         HexHighlightStyle::HighlightAsciiSymbols => {
             byte_color_ascii(byte, fallback_alternating_styles)
         }
         HexHighlightStyle::StyleA => match byte {
-            0x00 => Style::new().dark_gray(),
+            0x00 => theme().dim_style(),
             0x01..=0x1F => Style::new().blue(),
             0x20..=0x3F => Style::new().red(),
             0x40..=0x5F => Style::new().green(),
@@ -714,7 +912,7 @@ fn style_select(
             0xE0..=0xFF => Style::new().light_green(),
         },
         HexHighlightStyle::StyleB => match byte {
-            0x00 => Style::new().dark_gray(),
+            0x00 => theme().dim_style(),
             0x01..=0x4F => Style::new().green(),
             0x50..=0x7F => Style::new().light_blue(),
             0x80..=0xCF => Style::new().magenta(),
@@ -733,6 +931,8 @@ fn render_ascii(
     // This function renders the ASCII representation of the byte lines in the hex viewer.
     //
     // Each line only shows printable ASCII characters (0x20..=0x7E), other bytes are shown as '.'.
+    // Multi-byte UTF-8 characters that fit within their own bytes' cells (i.e. CJK or emoji)
+    // are shown over their first cells, padding the rest to keep alignment with the hex pane.
     // - slice: the bytes to render
     // - bytes_per_line: number of bytes per row
     // - scroll: starting row index (for scrolling/paging)
@@ -747,7 +947,7 @@ fn render_ascii(
             .ceil() as u16,
     );
 
-    let mut style_bool = scroll % 2 != 0;
+    let mut style_bool = !scroll.is_multiple_of(2);
 
     for line in 0..total_lines {
         style_bool.flip();
@@ -755,7 +955,20 @@ fn render_ascii(
         let line_bytes = &slice[offset..slice.len().min(offset + bytes_per_line as usize)];
 
         let mut ascii_spans = Vec::with_capacity(bytes_per_line as usize);
-        for &byte in line_bytes {
+        let mut index = 0;
+        while index < line_bytes.len() {
+            let byte = line_bytes[index];
+
+            if let Some((wide_char, char_len, char_width)) = multi_byte_char(&line_bytes[index..]) {
+                let char_style =
+                    AlternatingStyles(style_bool, Style::new().white(), Style::new().gray());
+                ascii_spans.push(Span::styled(wide_char.to_string(), char_style));
+                ascii_spans.extend(repeat_n(Span::raw(" "), char_len - char_width));
+                index += char_len;
+                continue;
+            }
+            index += 1;
+
             let span_style = style_select(
                 byte,
                 AlternatingStyles(style_bool, Style::new().white(), Style::new().gray()),
@@ -766,7 +979,7 @@ fn render_ascii(
                 (0x20..=0x7E, _) => {
                     Span::styled(ASCII_PRINTABLE[(byte - 0x20) as usize], span_style)
                 }
-                _ => Span::styled(".", Style::new().dark_gray()),
+                _ => Span::styled(".", theme().dim_style()),
             };
 
             ascii_spans.push(ch);
@@ -786,6 +999,18 @@ fn render_ascii(
     }
 }
 
+/// If the bytes start with a printable multi-byte UTF-8 character that's no wider
+/// than the amount of bytes encoding it, returns it alongside its byte length and display width.
+fn multi_byte_char(bytes: &[u8]) -> Option<(char, usize, usize)> {
+    if bytes.first().is_none_or(u8::is_ascii) {
+        return None;
+    }
+    let (ch, char_len) = bstr::decode_utf8(bytes);
+    let ch = ch.filter(|c| !c.is_control())?;
+    let char_width = ch.width().filter(|w| (1..=char_len).contains(w))?;
+    Some((ch, char_len, char_width))
+}
+
 fn byte_markers(bytes_per_line: u8, area: Rect, buf: &mut ratatui::prelude::Buffer) {
     // This function renders a row of hex offset values as byte markers for the columns in the hex view.
     //
@@ -805,7 +1030,7 @@ fn byte_markers(bytes_per_line: u8, area: Rect, buf: &mut ratatui::prelude::Buff
 
         spans.push(ratatui::text::Span::styled(
             format!("{i:02X}"),
-            Style::default().dark_gray(),
+            theme().dim_style(),
         ));
     }
     let marker_line = Line::from(spans);
@@ -841,7 +1066,7 @@ fn render_offsets(
     // Note: Y coordinate of `area` should be the top-most row to render.
     //       This draws vertically downward, one offset per line.
 
-    let mut style_bool = scroll % 2 != 0;
+    let mut style_bool = !scroll.is_multiple_of(2);
 
     let styles = (Style::new().light_yellow(), Style::new().yellow());
 
@@ -864,7 +1089,7 @@ fn render_offsets(
         };
 
         let dark_zeroes = "0".repeat(rect.width as usize);
-        Line::styled(dark_zeroes, Style::new().dark_gray()).render(rect, buf);
+        Line::styled(dark_zeroes, theme().dim_style()).render(rect, buf);
         let offset_str = format!("{offset:X}");
         Line::from(vec![Span::styled(
             offset_str,
@@ -903,7 +1128,7 @@ fn render_bytes(
             .ceil() as u16,
     );
 
-    let mut style_bool = scroll % 2 != 0;
+    let mut style_bool = !scroll.is_multiple_of(2);
 
     for line in 0..total_lines {
         style_bool.flip();
@@ -956,11 +1181,12 @@ impl Widget for &mut Buffer {
     where
         Self: Sized,
     {
+        self.style_viewed_lines();
         let para = self.terminal_paragraph();
         para.render(area, buf);
 
         if !self.state.stuck_to_bottom {
-            let scroll_notice = Line::raw("More... Shift+PgDn to jump to newest").dark_gray();
+            let scroll_notice = Line::raw("More... Shift+PgDn to jump to newest").fg(theme().dim);
             let notice_area = {
                 let mut rect = area;
                 rect.y = rect.bottom().saturating_sub(1);
@@ -983,14 +1209,17 @@ pub mod defmt {
     use defmt_parser::Level;
     use ratatui::prelude::*;
 
+    use crate::tui::theme::theme;
+
     pub fn defmt_level_color(level: Option<Level>) -> Color {
+        let theme = theme();
         match level {
-            Some(Level::Error) => Color::Red,
-            Some(Level::Warn) => Color::Yellow,
-            Some(Level::Info) => Color::Green,
-            Some(Level::Debug) => Color::Blue,
-            Some(Level::Trace) => Color::Magenta,
-            None => Color::Gray,
+            Some(Level::Error) => theme.defmt_error,
+            Some(Level::Warn) => theme.defmt_warn,
+            Some(Level::Info) => theme.defmt_info,
+            Some(Level::Debug) => theme.defmt_debug,
+            Some(Level::Trace) => theme.defmt_trace,
+            None => theme.defmt_unknown,
         }
     }
 
@@ -1011,11 +1240,11 @@ pub mod defmt {
             Some(Level::Info) | Some(Level::Warn) => "]  ",
             Some(_) => "] ",
         };
-        let dark_gray = Style::new().dark_gray();
+        let dim = theme().dim_style();
         vec![
-            Span::styled("[", dark_gray),
+            Span::styled("[", dim),
             defmt_level_span(level),
-            Span::styled(end_bracket, dark_gray),
+            Span::styled(end_bracket, dim),
         ]
     }
 }
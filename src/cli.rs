@@ -1,25 +1,211 @@
 use camino::Utf8PathBuf;
+use clap_complete::engine::{ArgValueCompleter, CompletionCandidate};
+
+use crate::settings::overrides::SettingOverride;
 
 #[derive(Debug, clap::Parser)]
 /// For when you just need to quickly yap at a device
 #[command(version, about)]
 pub struct YapCli {
     /// Skip port selection, use given serial port path, or search for USB VID:PID[:SERIAL], exits if connection fails
+    #[clap(add = ArgValueCompleter::new(complete_ports))]
     pub port: Option<String>,
 
     /// Override saved baud when connecting to [PORT]
     pub baud: Option<u32>,
 
+    #[command(subcommand)]
+    pub command: Option<YapCommand>,
+
     #[cfg(feature = "defmt")]
     /// Supply an ELF with defmt information to decode incoming serial data
     #[clap(short, long)]
     pub defmt_elf: Option<Utf8PathBuf>,
 
+    #[cfg(feature = "defmt")]
+    /// Supply further ELFs (i.e. a bootloader) whose defmt tables are tried when the main ELF's can't decode a frame
+    #[clap(long)]
+    pub extra_defmt_elf: Vec<Utf8PathBuf>,
+
+    #[cfg(feature = "logging")]
+    /// Play back a JSON Lines capture from an earlier session instead of connecting to a port
+    #[clap(long, value_name = "CAPTURE", conflicts_with = "port")]
+    pub replay: Option<Utf8PathBuf>,
+
+    #[cfg(feature = "logging")]
+    /// Record everything sent and recieved this session to a `.yapsession` file, to play back with `yap play`
+    #[clap(long, value_name = "SESSION")]
+    pub record: Option<Utf8PathBuf>,
+
+    #[cfg(feature = "self-replace")]
+    /// Install yap from a release archive downloaded beforehand, once it's verified
+    /// with the `.sha512` and `.sha512.minisig` files downloaded beside it,
+    /// and found to be a newer version of the same flavor and target
+    #[clap(long, value_name = "ARCHIVE", conflicts_with = "port")]
+    pub update_from: Option<Utf8PathBuf>,
+
+    /// Print recieved data to stdout instead of showing the TUI, requires [PORT]
+    #[clap(long, requires = "port")]
+    pub headless: bool,
+
+    /// Exit successfully once recieved data matches this regex
+    #[clap(long, value_name = "REGEX", value_parser = regex::bytes::Regex::new)]
+    pub expect: Option<regex::bytes::Regex>,
+
+    /// Exit with code 1 once recieved data matches this regex, checked before --expect
+    #[clap(long, value_name = "REGEX", value_parser = regex::bytes::Regex::new)]
+    pub fail_on: Option<regex::bytes::Regex>,
+
+    /// Exit after this many seconds, with code 2 if --expect was given and never matched
+    #[clap(long, value_name = "SECS")]
+    pub timeout: Option<u64>,
+
     /// Override path for configs, logs, macros, etc
     #[clap(short, long)]
     pub config_path: Option<Utf8PathBuf>,
 
+    /// Start with the named settings profile from the `settings_profiles` folder applied
+    #[clap(long)]
+    pub profile: Option<String>,
+
+    /// Override a setting for this session only without saving it, i.e. `--set serial.baud_rate=115200`,
+    /// also read from `YAP_<SECTION>__<KEY>` environment variables
+    #[clap(long = "set", value_name = "SECTION.KEY=VALUE")]
+    pub set: Vec<SettingOverride>,
+
+    /// Print all found serial ports (besides ignored devices) and exit
+    #[clap(long, conflicts_with = "port")]
+    pub list_ports: bool,
+
+    /// Print the found ports as JSON instead of a table
+    #[clap(long, requires = "list_ports")]
+    pub json: bool,
+
     /// Print all built-in Actions to be used in keybinds
     #[clap(short, long)]
     pub print_actions: bool,
 }
+
+#[derive(Debug, clap::Subcommand)]
+pub enum YapCommand {
+    /// Send a payload to a port without the TUI, printing anything recieved back, then exit
+    Send(SendArgs),
+    #[cfg(feature = "defmt")]
+    /// Decode defmt frames from a capture file or stdin with the given ELF, then exit
+    DefmtDecode(DefmtDecodeArgs),
+    #[cfg(feature = "logging")]
+    /// Play back a `.yapsession` recording (or JSON Lines capture) in the terminal view
+    Play { session: Utf8PathBuf },
+    /// Print a script that sets up tab completion for the given shell, including found port names
+    ///
+    /// i.e. `source <(yap completions bash)` in `.bashrc`, or `yap completions fish | source` in `config.fish`
+    Completions { shell: clap_complete::Shell },
+    /// Show the tracing output other yap instances send to their `log_tcp_socket`, live
+    Logview {
+        /// Address to listen for yap instances on, should match their `log_tcp_socket` setting
+        #[clap(long, default_value = "127.0.0.1:7331")]
+        listen: std::net::SocketAddr,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+pub struct SendArgs {
+    /// Serial port path, or USB VID:PID[:SERIAL] to search for
+    #[clap(long, add = ArgValueCompleter::new(complete_ports))]
+    pub port: String,
+
+    /// Override saved baud
+    #[clap(long)]
+    pub baud: Option<u32>,
+
+    /// Text to send, with escaped bytes like `\r\n` or `\x00` sent as bytes
+    #[clap(required_unless_present = "file", conflicts_with = "file")]
+    pub payload: Option<String>,
+
+    /// Send the contents of this file as-is instead
+    #[clap(long, value_name = "FILE")]
+    pub file: Option<Utf8PathBuf>,
+
+    /// After sending, wait until recieved data matches this regex
+    #[clap(long, value_name = "REGEX", value_parser = regex::bytes::Regex::new)]
+    pub until: Option<regex::bytes::Regex>,
+
+    /// After sending, wait until nothing's been recieved for this many milliseconds
+    #[clap(long = "quiet", value_name = "MS")]
+    pub quiet_ms: Option<u64>,
+
+    /// Give up waiting after this many seconds, exiting with code 2
+    #[clap(long, value_name = "SECS", default_value_t = 10)]
+    pub timeout: u64,
+}
+
+#[cfg(feature = "defmt")]
+#[derive(Debug, clap::Args)]
+pub struct DefmtDecodeArgs {
+    /// ELF with the defmt information to decode with
+    #[clap(long)]
+    pub elf: Utf8PathBuf,
+
+    /// Further ELFs (i.e. a bootloader) whose defmt tables are tried when the main ELF's can't decode a frame
+    #[clap(long)]
+    pub extra_elf: Vec<Utf8PathBuf>,
+
+    /// How frames are encoded, one of `FramedRzcobs`, `UnframedRzcobs`, or `Raw`,
+    /// defaults to the saved defmt parsing setting (or `FramedRzcobs` if it's disabled)
+    #[clap(long)]
+    pub encoding: Option<crate::settings::DefmtSupport>,
+
+    /// Capture to decode, reads from stdin if not given or `-`
+    pub input: Option<Utf8PathBuf>,
+}
+
+/// Port paths that start with what's been typed so far, looked up each time completions are asked for.
+fn complete_ports(current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
+    let Some(current) = current.to_str() else {
+        return Vec::new();
+    };
+    let Ok(ports) = serialport::available_ports() else {
+        return Vec::new();
+    };
+
+    ports
+        .into_iter()
+        .filter(|port| port.port_name.starts_with(current))
+        .map(|port| {
+            let help = match port.port_type {
+                serialport::SerialPortType::UsbPort(usb) => Some(
+                    usb.product
+                        .unwrap_or_else(|| format!("{:04X}:{:04X}", usb.vid, usb.pid))
+                        .into(),
+                ),
+                _ => None,
+            };
+            CompletionCandidate::new(port.port_name).help(help)
+        })
+        .collect()
+}
+
+/// Write the script that registers yap's completions with the shell.
+///
+/// The script calls back into yap as completions are needed, which is handled by
+/// [`clap_complete::CompleteEnv`] before any arguments are parsed.
+pub fn print_completions(shell: clap_complete::Shell) -> Result<(), std::io::Error> {
+    use clap_complete::env::Shells;
+
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&shell.to_string())
+        .expect("every clap_complete shell is a builtin");
+    let bin = std::env::current_exe()?;
+    let bin = bin.to_string_lossy();
+    completer.write_registration(
+        COMPLETE_ENV_VAR,
+        crate::get_executable_name().as_str(),
+        &bin,
+        &bin,
+        &mut std::io::stdout(),
+    )
+}
+
+/// Environment variable clap_complete looks for when the shell asks for completions.
+pub const COMPLETE_ENV_VAR: &str = "COMPLETE";
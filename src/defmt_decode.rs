@@ -0,0 +1,167 @@
+//! Decoding defmt captures taken elsewhere, from a file or stdin, without the TUI.
+
+use std::{
+    cell::Cell,
+    io::{Read, Write},
+};
+
+use color_eyre::eyre::{Context, eyre};
+use defmt_decoder::DecodeError;
+use fs_err as fs;
+
+use crate::{
+    buffer::{
+        DelimitedSlice,
+        defmt::{
+            DefmtDecoder, RawResync, RawResyncOutcome, decode_with_any,
+            frame_delimiting::{esp_println_delimited, zero_delimited},
+            rzcobs_decode,
+        },
+    },
+    cli::DefmtDecodeArgs,
+    settings::{DefmtSupport, Settings},
+};
+
+/// How much of the input is read at a time.
+const READ_CHUNK_LEN: usize = 4096;
+
+/// Decode every frame in the input, printing each one to stdout as it's found.
+///
+/// Any text between frames (with [`DefmtSupport::FramedRzcobs`]) is printed as-is,
+/// while problems with the frames themselves are printed to stderr.
+pub fn run(args: &DefmtDecodeArgs, settings: &Settings) -> color_eyre::Result<()> {
+    let encoding = match args
+        .encoding
+        .as_ref()
+        .unwrap_or(&settings.defmt.defmt_parsing)
+    {
+        DefmtSupport::Disabled => DefmtSupport::FramedRzcobs,
+        encoding => encoding.clone(),
+    };
+
+    let mut decoders = Vec::new();
+    for elf in std::iter::once(&args.elf).chain(&args.extra_elf) {
+        let (decoder, locations_err) = DefmtDecoder::from_elf_path(elf)
+            .wrap_err_with(|| format!("failed to load defmt data from {elf}"))?;
+        if let Some(e) = locations_err {
+            eprintln!("{elf}: locations {e}");
+        }
+        decoders.push(decoder);
+    }
+
+    let mut input: Box<dyn Read> = match &args.input {
+        Some(path) if path != "-" => Box::new(fs::File::open(path)?),
+        _ => Box::new(std::io::stdin().lock()),
+    };
+
+    let mut stream = DecodeStream {
+        decoders: &decoders,
+        encoding,
+        unconsumed: Vec::new(),
+        raw_resync: RawResync::default(),
+        last_matched: Cell::new(0),
+        stdout: std::io::stdout().lock(),
+    };
+
+    let mut chunk = [0u8; READ_CHUNK_LEN];
+    loop {
+        let read = input.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        stream.unconsumed.extend_from_slice(&chunk[..read]);
+        stream.decode_available()?;
+    }
+
+    stream.stdout.flush()?;
+    if !stream.unconsumed.is_empty() {
+        return Err(eyre!(
+            "input ended partway through a frame, {} bytes left undecoded",
+            stream.unconsumed.len()
+        ));
+    }
+    Ok(())
+}
+
+struct DecodeStream<'a, W: Write> {
+    decoders: &'a [DefmtDecoder],
+    encoding: DefmtSupport,
+    /// Bytes read but not yet decoded, i.e. the start of a frame that isn't complete yet.
+    unconsumed: Vec<u8>,
+    raw_resync: RawResync,
+    /// Which of `decoders` last decoded a frame, see [`decode_with_any`].
+    last_matched: Cell<usize>,
+    stdout: W,
+}
+
+impl<W: Write> DecodeStream<'_, W> {
+    /// Decode as many frames as are complete so far.
+    fn decode_available(&mut self) -> color_eyre::Result<()> {
+        match self.encoding {
+            DefmtSupport::Disabled => unreachable!("replaced with a real encoding beforehand"),
+            DefmtSupport::Raw => loop {
+                match decode_with_any(self.decoders, &self.unconsumed, &self.last_matched) {
+                    Ok((frame, consumed, _)) => {
+                        self.raw_resync.frame_decoded();
+                        writeln!(self.stdout, "{}", frame.display(false))?;
+                        self.unconsumed.drain(..consumed);
+                    }
+                    Err(DecodeError::UnexpectedEof) => break,
+                    Err(DecodeError::Malformed) => {
+                        let outcome = self.raw_resync.find_next_frame(&self.unconsumed, |bytes| {
+                            decode_with_any(self.decoders, bytes, &self.last_matched)
+                                .map(|(_, consumed, _)| consumed)
+                        });
+                        match outcome {
+                            RawResyncOutcome::Skip(skipped) => {
+                                eprintln!("malformed defmt packet, skipping {skipped} bytes ahead");
+                                self.unconsumed.drain(..skipped);
+                            }
+                            RawResyncOutcome::NeedMoreData => break,
+                            RawResyncOutcome::GiveUp => {
+                                return Err(eyre!(
+                                    "malformed defmt packet with nothing decodable after it"
+                                ));
+                            }
+                        }
+                    }
+                }
+            },
+            DefmtSupport::UnframedRzcobs | DefmtSupport::FramedRzcobs => loop {
+                let delimit: fn(&[u8]) -> nom::IResult<&[u8], DelimitedSlice<'_>> =
+                    if self.encoding == DefmtSupport::FramedRzcobs {
+                        esp_println_delimited
+                    } else {
+                        zero_delimited
+                    };
+
+                let unconsumed_len = self.unconsumed.len();
+                let Ok((rest, delimited_slice)) = delimit(&self.unconsumed) else {
+                    break;
+                };
+                let consumed = unconsumed_len - rest.len();
+
+                match delimited_slice {
+                    DelimitedSlice::DefmtRzcobs { inner, .. } => match rzcobs_decode(inner) {
+                        Ok(uncompressed) => {
+                            match decode_with_any(self.decoders, &uncompressed, &self.last_matched)
+                            {
+                                Ok((frame, _, _)) => {
+                                    writeln!(self.stdout, "{}", frame.display(false))?
+                                }
+                                Err(_) => eprintln!("malformed defmt packet"),
+                            }
+                        }
+                        Err(_) => eprintln!("malformed rzcobs packet"),
+                    },
+                    DelimitedSlice::Unknown(text) => self.stdout.write_all(text)?,
+                    DelimitedSlice::DefmtRaw(_) => unreachable!(),
+                }
+
+                self.unconsumed.drain(..consumed);
+            },
+        }
+        self.stdout.flush()?;
+        Ok(())
+    }
+}
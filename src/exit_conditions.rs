@@ -0,0 +1,231 @@
+//! Ending the session once recieved data shows a test has passed or failed,
+//! for running firmware smoke tests from scripts and CI.
+
+use std::{
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use regex::bytes::Regex;
+
+use crate::cli::YapCli;
+
+/// How much of the most recently recieved data patterns are matched against,
+/// so matches split across chunks are still found.
+const MATCH_WINDOW_LEN: usize = 4096;
+
+/// Exit code for when the `--fail-on` pattern was seen.
+const FAILED_EXIT_CODE: u8 = 1;
+/// Exit code for when the `--timeout` passed before what was being waited for.
+const TIMED_OUT_EXIT_CODE: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitOutcome {
+    /// The `--expect` pattern was seen.
+    Expected,
+    /// Nothing was recieved for the whole quiet period.
+    Quiet,
+    /// The `--fail-on` pattern was seen.
+    Failed,
+    /// The `--timeout` passed without what was being waited for, if anything was.
+    TimedOut { expecting: bool },
+}
+
+impl ExitOutcome {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Expected | Self::Quiet | Self::TimedOut { expecting: false } => ExitCode::SUCCESS,
+            Self::Failed => ExitCode::from(FAILED_EXIT_CODE),
+            Self::TimedOut { expecting: true } => ExitCode::from(TIMED_OUT_EXIT_CODE),
+        }
+    }
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Expected => "Expected pattern found",
+            Self::Quiet => "Port went quiet",
+            Self::Failed => "Failure pattern found",
+            Self::TimedOut { expecting: true } => "Timed out waiting",
+            Self::TimedOut { expecting: false } => "Timed out without failure",
+        }
+    }
+}
+
+/// The patterns and time limits to end the session with, and what's been recieved so far.
+pub struct ExitConditions {
+    expect: Option<Regex>,
+    fail_on: Option<Regex>,
+    deadline: Option<Instant>,
+    /// How long nothing needs to be recieved for to end the session.
+    quiet: Option<Duration>,
+    last_received: Instant,
+    received: Vec<u8>,
+}
+
+impl ExitConditions {
+    /// Returns `None` if no exit conditions were given.
+    pub fn from_cli(cli: &YapCli) -> Option<Self> {
+        if cli.expect.is_none() && cli.fail_on.is_none() && cli.timeout.is_none() {
+            return None;
+        }
+        Some(Self::new(
+            cli.expect.clone(),
+            cli.fail_on.clone(),
+            None,
+            cli.timeout.map(Duration::from_secs),
+        ))
+    }
+    pub fn new(
+        expect: Option<Regex>,
+        fail_on: Option<Regex>,
+        quiet: Option<Duration>,
+        timeout: Option<Duration>,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            expect,
+            fail_on,
+            deadline: timeout.map(|timeout| now + timeout),
+            quiet,
+            last_received: now,
+            received: Vec::new(),
+        }
+    }
+    /// Add freshly recieved bytes, returning the outcome if either pattern has now matched.
+    ///
+    /// The failure pattern is checked first, so a chunk matching both counts as a failure.
+    pub fn feed(&mut self, bytes: &[u8]) -> Option<ExitOutcome> {
+        self.last_received = Instant::now();
+        self.received.extend_from_slice(bytes);
+        if self.received.len() > MATCH_WINDOW_LEN {
+            let excess = self.received.len() - MATCH_WINDOW_LEN;
+            self.received.drain(..excess);
+        }
+
+        if self
+            .fail_on
+            .as_ref()
+            .is_some_and(|fail_on| fail_on.is_match(&self.received))
+        {
+            Some(ExitOutcome::Failed)
+        } else if self
+            .expect
+            .as_ref()
+            .is_some_and(|expect| expect.is_match(&self.received))
+        {
+            Some(ExitOutcome::Expected)
+        } else {
+            None
+        }
+    }
+    /// Time left until the timeout or the end of the quiet period, whichever's sooner,
+    /// or `None` if there's neither.
+    pub fn until_deadline(&self) -> Option<Duration> {
+        let quiet_end = self.quiet.map(|quiet| self.last_received + quiet);
+        [self.deadline, quiet_end]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|end| end.saturating_duration_since(Instant::now()))
+    }
+    /// The outcome if the timeout or quiet period has passed.
+    pub fn timed_out(&self) -> Option<ExitOutcome> {
+        let now = Instant::now();
+        if self
+            .quiet
+            .is_some_and(|quiet| now >= self.last_received + quiet)
+        {
+            return Some(ExitOutcome::Quiet);
+        }
+        self.deadline
+            .is_some_and(|deadline| now >= deadline)
+            .then_some(ExitOutcome::TimedOut {
+                expecting: self.expect.is_some() || self.quiet.is_some(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regex(pattern: &str) -> Option<Regex> {
+        Some(Regex::new(pattern).unwrap())
+    }
+
+    #[test]
+    fn patterns_matched_across_chunks() {
+        let mut conditions = ExitConditions::new(regex("ALL PASS"), None, None, None);
+        assert_eq!(conditions.feed(b"running... ALL P"), None);
+        assert_eq!(conditions.feed(b"ASS\r\n"), Some(ExitOutcome::Expected));
+    }
+
+    #[test]
+    fn failure_wins_over_expected() {
+        let mut conditions = ExitConditions::new(regex("DONE"), regex("PANIC"), None, None);
+        assert_eq!(conditions.feed(b"PANIC, DONE"), Some(ExitOutcome::Failed));
+    }
+
+    #[test]
+    fn old_data_leaves_the_match_window() {
+        let mut conditions = ExitConditions::new(regex("^start"), None, None, None);
+        assert_eq!(conditions.feed(b"st"), None);
+        conditions.feed(&[b'.'; MATCH_WINDOW_LEN]);
+        assert_eq!(conditions.received.len(), MATCH_WINDOW_LEN);
+        // The start of the window has moved on from where the pattern could've matched.
+        assert_eq!(conditions.feed(b"art"), None);
+    }
+
+    #[test]
+    fn timeouts_and_quiet_periods() {
+        let mut conditions = ExitConditions::new(None, None, None, Some(Duration::ZERO));
+        assert_eq!(
+            conditions.timed_out(),
+            Some(ExitOutcome::TimedOut { expecting: false })
+        );
+        assert_eq!(conditions.until_deadline(), Some(Duration::ZERO));
+
+        conditions.expect = regex("never");
+        assert_eq!(
+            conditions.timed_out(),
+            Some(ExitOutcome::TimedOut { expecting: true })
+        );
+
+        let mut conditions = ExitConditions::new(
+            None,
+            None,
+            Some(Duration::ZERO),
+            Some(Duration::from_secs(86_400)),
+        );
+        assert_eq!(conditions.timed_out(), Some(ExitOutcome::Quiet));
+
+        conditions.quiet = Some(Duration::from_secs(3600));
+        conditions.feed(b"still talking");
+        assert_eq!(conditions.timed_out(), None);
+        // The quiet period ends well before the timeout.
+        assert!(conditions.until_deadline().unwrap() <= Duration::from_secs(3600));
+
+        assert!(
+            ExitConditions::new(None, None, None, None)
+                .until_deadline()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(ExitOutcome::Expected.exit_code(), ExitCode::SUCCESS);
+        assert_eq!(ExitOutcome::Quiet.exit_code(), ExitCode::SUCCESS);
+        assert_eq!(
+            ExitOutcome::TimedOut { expecting: false }.exit_code(),
+            ExitCode::SUCCESS
+        );
+        assert_eq!(
+            ExitOutcome::Failed.exit_code(),
+            ExitCode::from(FAILED_EXIT_CODE)
+        );
+        assert_eq!(
+            ExitOutcome::TimedOut { expecting: true }.exit_code(),
+            ExitCode::from(TIMED_OUT_EXIT_CODE)
+        );
+    }
+}
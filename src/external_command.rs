@@ -0,0 +1,237 @@
+use std::{
+    process::{Command, Stdio},
+    thread::JoinHandle,
+};
+
+use camino::Utf8PathBuf;
+use compact_str::CompactString;
+use crossbeam::channel::Sender;
+use tracing::{debug, error};
+
+use crate::app::Event;
+
+/// Keybind actions starting with this run the rest as a shell command.
+pub const RUN_COMMAND_PREFIX: &str = "run:";
+
+/// Most bytes of a failed command's stderr to keep for the log.
+const STDERR_TAIL_LEN: usize = 1024;
+
+#[derive(Debug)]
+pub enum CommandEvent {
+    /// Command exited (or couldn't be started), with its exit code if it had one.
+    Finished {
+        command: CompactString,
+        result: Result<Option<i32>, String>,
+        stderr: String,
+    },
+}
+
+impl From<CommandEvent> for Event {
+    fn from(value: CommandEvent) -> Self {
+        Self::Command(value)
+    }
+}
+
+#[derive(Debug, Default)]
+/// Values for a command's `{port}`, `{log_dir}`, and `{line}` placeholders.
+///
+/// Also given to the command as the `YAP_PORT`, `YAP_LOG_DIR`, and `YAP_LINE` environment variables.
+pub struct CommandContext {
+    /// Name of the connected port.
+    pub port: Option<String>,
+    /// Folder log files are written to.
+    pub log_dir: Utf8PathBuf,
+    /// Text of the selected line.
+    pub selected_line: Option<String>,
+}
+
+impl CommandContext {
+    fn placeholders(&self) -> [(&'static str, &'static str, &str); 3] {
+        [
+            (
+                "{port}",
+                "YAP_PORT",
+                self.port.as_deref().unwrap_or_default(),
+            ),
+            ("{log_dir}", "YAP_LOG_DIR", self.log_dir.as_str()),
+            (
+                "{line}",
+                "YAP_LINE",
+                self.selected_line.as_deref().unwrap_or_default(),
+            ),
+        ]
+    }
+}
+
+/// Quote the value so the shell passes it along as a single argument, as-is.
+///
+/// Selected lines come from the device, so they must never be interpreted by the shell.
+fn shell_quote(value: &str) -> String {
+    if cfg!(windows) {
+        // cmd.exe has no way to escape a double quote inside of one, so they're dropped.
+        // Variables are still expanded between quotes, so any `%` is escaped outside of them.
+        format!("\"{}\"", value.replace('"', "").replace('%', "\"^%\""))
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// Replace each placeholder in the command with its quoted value.
+///
+/// Done in a single pass over the command, so placeholders inside of a value are left as-is.
+fn expand_placeholders(command: &str, context: &CommandContext) -> String {
+    let placeholders = context.placeholders();
+    let mut expanded = String::with_capacity(command.len());
+    let mut rest = command;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match placeholders
+            .iter()
+            .find(|(placeholder, _, _)| rest.starts_with(placeholder))
+        {
+            Some((placeholder, _, value)) => {
+                expanded.push_str(&shell_quote(value));
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Run the command through the system shell on a separate thread,
+/// sending a `CommandEvent::Finished` once it exits.
+///
+/// Output is captured rather than shown, since it would draw over the TUI.
+pub fn spawn_command(
+    command: CompactString,
+    context: CommandContext,
+    event_tx: Sender<Event>,
+) -> std::io::Result<JoinHandle<()>> {
+    let expanded = expand_placeholders(&command, &context);
+    debug!("Running command: {expanded}");
+
+    #[cfg(windows)]
+    let mut process = {
+        use std::os::windows::process::CommandExt;
+        // Given as-is, since `arg`'s escaping is for programs that parse their arguments like MSVC's runtime,
+        // which cmd.exe doesn't, and the placeholders were quoted for cmd.exe already.
+        let mut process = Command::new("cmd");
+        process.raw_arg("/C").raw_arg(&expanded);
+        process
+    };
+    #[cfg(not(windows))]
+    let mut process = {
+        let mut process = Command::new("sh");
+        process.arg("-c").arg(&expanded);
+        process
+    };
+    for (_, variable, value) in context.placeholders() {
+        process.env(variable, value);
+    }
+    process
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    std::thread::Builder::new()
+        .name("yap command".into())
+        .spawn(move || {
+            let (result, stderr) = match process.output() {
+                Ok(output) => {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    let tail_start = stderr.len().saturating_sub(STDERR_TAIL_LEN);
+                    let tail_start = (tail_start..stderr.len())
+                        .find(|i| stderr.is_char_boundary(*i))
+                        .unwrap_or(stderr.len());
+                    (Ok(output.status.code()), stderr[tail_start..].to_owned())
+                }
+                Err(e) => (Err(e.to_string()), String::new()),
+            };
+            let finished = CommandEvent::Finished {
+                command,
+                result,
+                stderr,
+            };
+            if event_tx.send(finished.into()).is_err() {
+                error!("App closed before command finished!");
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> CommandContext {
+        CommandContext {
+            port: Some("/dev/ttyUSB0".to_owned()),
+            log_dir: "/tmp/yap logs".into(),
+            selected_line: Some("it's $HOME; `reboot` \"100%\"".to_owned()),
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn quoted_values_reach_the_shell_as_is() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+
+        let line = context().selected_line.unwrap();
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf %s {}", shell_quote(&line)))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), line);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn quoted_values_escape_variables() {
+        assert_eq!(shell_quote("plain"), "\"plain\"");
+        assert_eq!(shell_quote("say \"hi\""), "\"say hi\"");
+        assert_eq!(shell_quote("100%PATH%"), "\"100\"^%\"PATH\"^%\"\"");
+    }
+
+    #[test]
+    fn placeholders_are_replaced_with_quoted_values() {
+        let context = context();
+        let expanded = expand_placeholders("tool {port} {log_dir} {line} {port}", &context);
+        assert_eq!(
+            expanded,
+            format!(
+                "tool {port} {log_dir} {line} {port}",
+                port = shell_quote("/dev/ttyUSB0"),
+                log_dir = shell_quote("/tmp/yap logs"),
+                line = shell_quote(context.selected_line.as_deref().unwrap()),
+            )
+        );
+
+        // A value holding another placeholder is only ever quoted, not expanded again.
+        let context = CommandContext {
+            port: Some("{line}".to_owned()),
+            log_dir: "{port}".into(),
+            selected_line: Some("'; reboot; '".to_owned()),
+        };
+        let expanded = expand_placeholders("tool {port} {log_dir} {{line}}", &context);
+        assert_eq!(
+            expanded,
+            format!(
+                "tool {} {} {{{}}}",
+                shell_quote("{line}"),
+                shell_quote("{port}"),
+                shell_quote("'; reboot; '"),
+            )
+        );
+
+        // Missing values still leave an (empty) argument in place.
+        let expanded = expand_placeholders("tool {port} {unknown}", &CommandContext::default());
+        assert_eq!(expanded, format!("tool {} {{unknown}}", shell_quote("")));
+    }
+}
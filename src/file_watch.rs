@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use notify::{
+    EventKind,
+    event::{ModifyKind, RenameMode},
+};
+
+/// Returns `true` if the event signals the watched file was (re)written.
+pub fn event_updates_file(event: &notify::Event, watched_path: &Path) -> bool {
+    if event.paths.iter().any(|p| p == watched_path) {
+        // guh.
+        match event.kind {
+            EventKind::Create(_) => true,
+            EventKind::Modify(modify_kind) => match modify_kind {
+                ModifyKind::Data(_) => true,
+                ModifyKind::Any => true,
+                ModifyKind::Other => true,
+                ModifyKind::Metadata(_) => false,
+                ModifyKind::Name(rename_mode) => match rename_mode {
+                    RenameMode::To => true,
+                    RenameMode::From => false,
+
+                    RenameMode::Both if event.paths[1] == watched_path => true,
+                    RenameMode::Both => false,
+
+                    RenameMode::Any | RenameMode::Other => true,
+                },
+            },
+            EventKind::Any => true,
+            EventKind::Other => true,
+            EventKind::Access(_) => false,
+            EventKind::Remove(_) => false,
+        }
+    } else {
+        false
+    }
+}
@@ -0,0 +1,171 @@
+//! Connecting to a port without the TUI, printing everything recieved straight to stdout.
+
+use std::{io::Write, process::ExitCode, time::Duration};
+
+use bstr::ByteVec;
+use chrono::{DateTime, Local};
+use color_eyre::eyre::{Context, eyre};
+use crossbeam::channel::Receiver;
+use fs_err as fs;
+use serialport::SerialPortInfo;
+
+use crate::{
+    app::{CONNECT_ATTEMPT_BLOCK_MAX, Event, NoSenders, SCAN_BLOCK_MAX},
+    cli::SendArgs,
+    exit_conditions::{ExitConditions, ExitOutcome},
+    serial::{SerialDisconnectReason, SerialEvent, handle::SerialHandle},
+    settings::Settings,
+};
+
+/// How long to wait for data before checking the timeout again, when there isn't one.
+const IDLE_WAIT: Duration = Duration::from_secs(1);
+
+/// A serial worker connected to a port, without any app around it.
+struct HeadlessPort {
+    serial: SerialHandle,
+    event_rx: Receiver<Event>,
+    serial_buf_rx: Receiver<(DateTime<Local>, u64, Vec<u8>)>,
+}
+
+impl HeadlessPort {
+    fn connect(
+        port_info: SerialPortInfo,
+        baud: Option<u32>,
+        settings: &Settings,
+    ) -> color_eyre::Result<Self> {
+        let (event_tx, event_rx) = crossbeam::channel::unbounded::<Event>();
+        let (serial_buf_tx, serial_buf_rx) = crossbeam::channel::unbounded();
+        let (serial, _serial_thread, _ports) = SerialHandle::build(
+            event_tx,
+            serial_buf_tx,
+            settings.serial.clone(),
+            settings.ignored_devices.clone(),
+            SCAN_BLOCK_MAX,
+        )
+        .wrap_err("failed to build serial worker")?;
+
+        serial.connect_blocking(
+            port_info,
+            settings.serial.clone(),
+            baud,
+            CONNECT_ATTEMPT_BLOCK_MAX,
+        )?;
+
+        Ok(Self {
+            serial,
+            event_rx,
+            serial_buf_rx,
+        })
+    }
+    /// Print recieved data until an exit condition is met, or forever if there's none.
+    ///
+    /// Connection status is printed to stderr, so it can be kept apart from the port's output.
+    fn stream(
+        &self,
+        mut conditions: Option<&mut ExitConditions>,
+    ) -> color_eyre::Result<Option<ExitOutcome>> {
+        let mut stdout = std::io::stdout().lock();
+        loop {
+            let wait = conditions
+                .as_ref()
+                .and_then(|c| c.until_deadline())
+                .unwrap_or(IDLE_WAIT);
+
+            crossbeam::select! {
+                recv(self.serial_buf_rx) -> msg => {
+                    let (_timestamp, _monotonic_us, bytes) = msg.map_err(|_| NoSenders::SerialRx)?;
+                    stdout.write_all(&bytes)?;
+                    stdout.flush()?;
+                    if let Some(outcome) = conditions.as_mut().and_then(|c| c.feed(&bytes)) {
+                        return Ok(Some(outcome));
+                    }
+                }
+                recv(self.event_rx) -> event => self.handle_event(event.map_err(|_| NoSenders::Events)?)?,
+                default(wait) => (),
+            }
+
+            if let Some(outcome) = conditions.as_ref().and_then(|c| c.timed_out()) {
+                return Ok(Some(outcome));
+            }
+        }
+    }
+    fn handle_event(&self, event: Event) -> color_eyre::Result<()> {
+        match event {
+            Event::Serial(SerialEvent::Disconnected(SerialDisconnectReason::Error(e))) => {
+                eprintln!("Disconnected from port: {e}");
+            }
+            Event::Serial(SerialEvent::Connected(Some(_))) => {
+                eprintln!("Reconnected to port.");
+            }
+            Event::Serial(SerialEvent::UnsentTx(unsent)) => {
+                return Err(eyre!("failed to send {} bytes to port", unsent.len()));
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+    /// Stop the worker, which finishes sending anything queued beforehand.
+    fn shutdown(self) -> color_eyre::Result<()> {
+        _ = self.serial.shutdown();
+        for event in self.event_rx.try_iter() {
+            self.handle_event(event)?;
+        }
+        Ok(())
+    }
+}
+
+fn finish(outcome: Option<ExitOutcome>) -> ExitCode {
+    match outcome {
+        Some(outcome) => {
+            eprintln!("{}", outcome.describe());
+            outcome.exit_code()
+        }
+        None => ExitCode::SUCCESS,
+    }
+}
+
+/// Stream the port to stdout until an exit condition is met, or until interrupted.
+pub fn run(
+    port_info: SerialPortInfo,
+    baud: Option<u32>,
+    settings: &Settings,
+    mut conditions: Option<ExitConditions>,
+) -> color_eyre::Result<ExitCode> {
+    let port = HeadlessPort::connect(port_info, baud, settings)?;
+    let result = port.stream(conditions.as_mut());
+    port.shutdown()?;
+
+    Ok(finish(result?))
+}
+
+/// Send a payload to the port and exit,
+/// optionally waiting for a response or for the port to go quiet first.
+pub fn send(
+    port_info: SerialPortInfo,
+    args: &SendArgs,
+    settings: &Settings,
+) -> color_eyre::Result<ExitCode> {
+    let payload = match (&args.payload, &args.file) {
+        (_, Some(path)) => fs::read(path)?,
+        (Some(text), None) => Vec::unescape_bytes(text),
+        (None, None) => unreachable!("clap requires a payload or file"),
+    };
+
+    let port = HeadlessPort::connect(port_info, args.baud, settings)?;
+    port.serial.send_bytes(payload, None)?;
+
+    let result = if args.until.is_some() || args.quiet_ms.is_some() {
+        let mut conditions = ExitConditions::new(
+            args.until.clone(),
+            None,
+            args.quiet_ms.map(Duration::from_millis),
+            Some(Duration::from_secs(args.timeout)),
+        );
+        port.stream(Some(&mut conditions))
+    } else {
+        Ok(None)
+    };
+    port.shutdown()?;
+
+    Ok(finish(result?))
+}
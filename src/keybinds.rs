@@ -13,11 +13,12 @@ use crokey::KeyCombination;
 use crossterm::event::{KeyCode, KeyModifiers};
 use fs_err as fs;
 use indexmap::IndexMap;
+use itertools::Itertools;
 use serde::Deserialize;
 use strum::{EnumMessage, VariantArray};
 
 #[cfg(feature = "macros")]
-use crate::macros::MacroNameTag;
+use crate::macros::{MacroContent, MacroNameTag};
 use crate::{config_adjacent_path, traits::RequiresPort};
 
 #[derive(
@@ -39,6 +40,18 @@ use crate::{config_adjacent_path, traits::RequiresPort};
 pub enum ShowPopupAction {
     /// Show all current keybinds, highlighting unrecognized actions.
     ShowKeybinds,
+    /// Open the keybind editor, to rebind actions without editing the keybinds file.
+    ShowKeybindEditor,
+    /// Open the settings profile list, to switch between saved sets of Serial, Rendering, and defmt settings.
+    ShowSettingsProfiles,
+    /// Search every settings menu for an option by name or description, jumping to it.
+    ShowSettingsSearch,
+    /// Look back over past notifications and when they were shown, copying them with c.
+    ShowNotificationHistory,
+    /// Search every action, macro, and settings menu by name, running the one picked.
+    ShowCommandPalette,
+    /// Write several lines at once to send one after another, with the Pseudo Shell.
+    ShowComposeEditor,
     #[strum(serialize = "show-portsettings")]
     /// Open the Port Settings menu.
     ShowPortSettings,
@@ -66,6 +79,10 @@ impl RequiresPort for ShowPopupAction {
         // Requires no port presence, only UI actions.
         false
     }
+    fn requires_terminal_view(&self) -> bool {
+        // Lines are sent from the terminal view, even if the port isn't connected yet.
+        matches!(self, ShowPopupAction::ShowComposeEditor)
+    }
 }
 
 // impl From<ShowPopupAction> for PopupMenu {
@@ -125,6 +142,30 @@ pub enum BaseAction {
     ReloadKeybinds,
     /// Escape a Keypress to avoid sending a key to the device to trigger an app menu or action.
     EscapeKeypress,
+    /// Open the selected line's panic, backtrace, or defmt error location in an editor.
+    OpenSelectedInEditor,
+    /// Paste the clipboard's text into the input box.
+    PasteClipboard,
+    /// Scroll the buffer up by a line.
+    ScrollUp,
+    /// Scroll the buffer down by a line.
+    ScrollDown,
+    /// Scroll the buffer left, when text wrapping is off.
+    ScrollLeft,
+    /// Scroll the buffer right, when text wrapping is off.
+    ScrollRight,
+    /// Undo the last settings change made this session.
+    UndoSettingsChange,
+    /// Switch to the next keybind profile, or back to the regular keybinds after the last one.
+    CycleKeybindProfile,
+    /// Navigate the buffer with Vim-style keys (j/k/g/G, / to search, n/N for next/previous), i to return to input.
+    EnterNavigationMode,
+    /// Save the buffer as it's currently shown, colors and all, to an HTML file in the logs folder.
+    ExportHtml,
+    /// Toggle Do Not Disturb, keeping notifications out of sight and only in the notification history.
+    ToggleDoNotDisturb,
+    /// Search back through Pseudo Shell history as you type, pressing again for older matches.
+    ReverseHistorySearch,
 }
 
 impl RequiresPort for BaseAction {
@@ -136,6 +177,10 @@ impl RequiresPort for BaseAction {
             // Has no use when not in the terminal view,
             // and behavior doesn't rely on port having healthy connection.
             BaseAction::EscapeKeypress => true,
+            BaseAction::EnterNavigationMode => true,
+            BaseAction::PasteClipboard => true,
+            BaseAction::ExportHtml => true,
+            BaseAction::ReverseHistorySearch => true,
             _ => false,
         }
     }
@@ -176,6 +221,9 @@ pub enum PortAction {
     AttemptReconnectStrict,
     /// Attempt to reconnect to device, best-effort.
     AttemptReconnectLoose,
+
+    /// Re-send the raw bytes of the selected (bottom-most visible) port line, with the TX line ending.
+    ResendSelectedLine,
 }
 
 impl RequiresPort for PortAction {
@@ -210,6 +258,14 @@ impl RequiresPort for PortAction {
 pub enum MacroBuiltinAction {
     /// Reload all Macros.
     ReloadMacros,
+    /// Stop re-sending the currently repeating Macro.
+    StopRepeatingMacro,
+    /// Start recording Pseudo-shell input as a new Macro, or stop and save it.
+    RecordMacro,
+    /// Export the Macros in the category selected in the Macros menu to a bundle file.
+    ExportMacros,
+    /// Import any Macro bundles placed in the `macro_bundles` folder.
+    ImportMacros,
 }
 
 #[cfg(feature = "macros")]
@@ -219,6 +275,35 @@ impl RequiresPort for MacroBuiltinAction {
     }
 }
 
+#[cfg(feature = "scripting")]
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum::EnumString,
+    strum::Display,
+    strum::AsRefStr,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[strum(ascii_case_insensitive)]
+pub enum ScriptBuiltinAction {
+    /// Re-scan the scripts folder.
+    ReloadScripts,
+    /// Stop the currently running Script.
+    StopScript,
+}
+
+#[cfg(feature = "scripting")]
+impl RequiresPort for ScriptBuiltinAction {
+    fn requires_connection(&self) -> bool {
+        false
+    }
+}
+
 #[cfg(feature = "espflash")]
 #[derive(
     Debug,
@@ -280,6 +365,30 @@ pub enum LoggingAction {
     #[strum(serialize = "logging-sync")]
     /// Sync any active log files with entire buffer content, current settings, and defmt table if loaded.
     Sync,
+    #[strum(serialize = "logging-export-pcapng")]
+    /// Export all RX and TX traffic in the buffer as a pcapng capture in the logs folder, for Wireshark.
+    ExportPcapng,
+    #[strum(serialize = "logging-toggle")]
+    /// Pause or resume writing to log files, marking in the text log where it was paused and resumed.
+    Toggle,
+    #[strum(serialize = "logging-dump-recent")]
+    /// Save the recent recieved data kept in memory to the logs folder, even if not logging to files.
+    DumpRecent,
+    #[strum(serialize = "logging-replay-pause")]
+    /// Pause or resume the capture being replayed with --replay.
+    ReplayPause,
+    #[strum(serialize = "logging-replay-forward")]
+    /// Skip ten seconds ahead in the capture being replayed.
+    ReplayForward,
+    #[strum(serialize = "logging-replay-back")]
+    /// Go back ten seconds in the capture being replayed, replaying the buffer up to there.
+    ReplayBack,
+    #[strum(serialize = "logging-replay-faster")]
+    /// Speed up playback of the capture being replayed.
+    ReplayFaster,
+    #[strum(serialize = "logging-replay-slower")]
+    /// Slow down playback of the capture being replayed.
+    ReplaySlower,
 }
 
 #[cfg(feature = "logging")]
@@ -293,6 +402,15 @@ impl RequiresPort for LoggingAction {
             // and any existing files are flushed and dropped
             // when returning to port selection screen.
             Self::Sync => true,
+            Self::ExportPcapng => true,
+            // Pausing before connecting keeps the first files empty until resumed.
+            Self::Toggle => false,
+            Self::DumpRecent => false,
+            Self::ReplayPause
+            | Self::ReplayForward
+            | Self::ReplayBack
+            | Self::ReplayFaster
+            | Self::ReplaySlower => true,
             // _ => false,
         }
     }
@@ -325,6 +443,9 @@ pub enum DefmtSelectAction {
     #[strum(serialize = "defmt-select-recent")]
     /// Select a recently-used defmt ELF.
     SelectRecent,
+    #[strum(serialize = "defmt-select-newest")]
+    /// Find the most recently built defmt ELF in the cargo target directory, and offer to load it.
+    SelectNewest,
 }
 
 #[cfg(feature = "defmt")]
@@ -335,6 +456,44 @@ impl RequiresPort for DefmtSelectAction {
     }
 }
 
+#[cfg(feature = "defmt")]
+#[derive(
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    strum::EnumString,
+    strum::Display,
+    strum::AsRefStr,
+    strum::VariantArray,
+    strum::EnumMessage,
+)]
+#[strum(serialize_all = "kebab-case")]
+#[strum(ascii_case_insensitive)]
+/// Actions to adjust how decoded defmt logs are shown.
+pub enum DefmtAction {
+    #[strum(serialize = "defmt-cycle-min-level")]
+    /// Cycle the minimum defmt log level shown, from Trace up to Error and back.
+    CycleMinLevel,
+    #[strum(serialize = "defmt-module-filter")]
+    /// Open a quick filter for which modules' defmt logs are shown.
+    ModuleFilter,
+    #[strum(serialize = "defmt-show-stats")]
+    /// Show counts of decoded defmt frames and decoding failures.
+    ShowStats,
+    #[strum(serialize = "defmt-expand-failed-frame")]
+    /// Expand the selected defmt frame that failed to decode into a hexdump, with the option to copy it.
+    ExpandFailedFrame,
+}
+
+#[cfg(feature = "defmt")]
+impl RequiresPort for DefmtAction {
+    fn requires_connection(&self) -> bool {
+        false
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, strum::AsRefStr)]
 pub enum BuiltinAction {
     Base(BaseAction),
@@ -342,12 +501,16 @@ pub enum BuiltinAction {
     Port(PortAction),
     #[cfg(feature = "macros")]
     MacroBuiltin(MacroBuiltinAction),
+    #[cfg(feature = "scripting")]
+    ScriptBuiltin(ScriptBuiltinAction),
     #[cfg(feature = "espflash")]
     EspBuiltin(EspBuiltinAction),
     #[cfg(feature = "logging")]
     Logging(LoggingAction),
     #[cfg(feature = "defmt")]
     ShowDefmtSelect(DefmtSelectAction),
+    #[cfg(feature = "defmt")]
+    Defmt(DefmtAction),
 }
 
 impl RequiresPort for BuiltinAction {
@@ -358,12 +521,16 @@ impl RequiresPort for BuiltinAction {
             Self::Port(action) => action.requires_connection(),
             #[cfg(feature = "macros")]
             Self::MacroBuiltin(action) => action.requires_connection(),
+            #[cfg(feature = "scripting")]
+            Self::ScriptBuiltin(action) => action.requires_connection(),
             #[cfg(feature = "espflash")]
             Self::EspBuiltin(action) => action.requires_connection(),
             #[cfg(feature = "logging")]
             Self::Logging(action) => action.requires_connection(),
             #[cfg(feature = "defmt")]
             Self::ShowDefmtSelect(action) => action.requires_connection(),
+            #[cfg(feature = "defmt")]
+            Self::Defmt(action) => action.requires_connection(),
         }
     }
     fn requires_terminal_view(&self) -> bool {
@@ -373,12 +540,16 @@ impl RequiresPort for BuiltinAction {
             Self::Port(action) => action.requires_terminal_view(),
             #[cfg(feature = "macros")]
             Self::MacroBuiltin(action) => action.requires_terminal_view(),
+            #[cfg(feature = "scripting")]
+            Self::ScriptBuiltin(action) => action.requires_terminal_view(),
             #[cfg(feature = "espflash")]
             Self::EspBuiltin(action) => action.requires_terminal_view(),
             #[cfg(feature = "logging")]
             Self::Logging(action) => action.requires_terminal_view(),
             #[cfg(feature = "defmt")]
             Self::ShowDefmtSelect(action) => action.requires_terminal_view(),
+            #[cfg(feature = "defmt")]
+            Self::Defmt(action) => action.requires_terminal_view(),
         }
     }
 }
@@ -391,12 +562,16 @@ impl fmt::Display for BuiltinAction {
             BuiltinAction::Port(action) => write!(f, "{action}"),
             #[cfg(feature = "macros")]
             BuiltinAction::MacroBuiltin(action) => write!(f, "{action}"),
+            #[cfg(feature = "scripting")]
+            BuiltinAction::ScriptBuiltin(action) => write!(f, "{action}"),
             #[cfg(feature = "espflash")]
             BuiltinAction::EspBuiltin(action) => write!(f, "{action}"),
             #[cfg(feature = "logging")]
             BuiltinAction::Logging(action) => write!(f, "{action}"),
             #[cfg(feature = "defmt")]
             BuiltinAction::ShowDefmtSelect(action) => write!(f, "{action}"),
+            #[cfg(feature = "defmt")]
+            BuiltinAction::Defmt(action) => write!(f, "{action}"),
         }
     }
 }
@@ -426,6 +601,13 @@ impl From<MacroBuiltinAction> for BuiltinAction {
     }
 }
 
+#[cfg(feature = "scripting")]
+impl From<ScriptBuiltinAction> for BuiltinAction {
+    fn from(action: ScriptBuiltinAction) -> Self {
+        BuiltinAction::ScriptBuiltin(action)
+    }
+}
+
 #[cfg(feature = "espflash")]
 impl From<EspBuiltinAction> for BuiltinAction {
     fn from(action: EspBuiltinAction) -> Self {
@@ -447,6 +629,13 @@ impl From<DefmtSelectAction> for BuiltinAction {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl From<DefmtAction> for BuiltinAction {
+    fn from(action: DefmtAction) -> Self {
+        BuiltinAction::Defmt(action)
+    }
+}
+
 impl FromStr for BuiltinAction {
     type Err = String;
 
@@ -469,6 +658,10 @@ impl FromStr for BuiltinAction {
         if let Ok(macros) = s.parse::<MacroBuiltinAction>() {
             return Ok(BuiltinAction::MacroBuiltin(macros));
         }
+        #[cfg(feature = "scripting")]
+        if let Ok(scripts) = s.parse::<ScriptBuiltinAction>() {
+            return Ok(BuiltinAction::ScriptBuiltin(scripts));
+        }
         #[cfg(feature = "espflash")]
         if let Ok(esp) = s.parse::<EspBuiltinAction>() {
             return Ok(BuiltinAction::EspBuiltin(esp));
@@ -477,6 +670,10 @@ impl FromStr for BuiltinAction {
         if let Ok(defmt_select) = s.parse::<DefmtSelectAction>() {
             return Ok(BuiltinAction::ShowDefmtSelect(defmt_select));
         }
+        #[cfg(feature = "defmt")]
+        if let Ok(defmt) = s.parse::<DefmtAction>() {
+            return Ok(BuiltinAction::Defmt(defmt));
+        }
 
         Err(format!(
             "Unrecognized BuiltinAction variant for string: {s}"
@@ -484,6 +681,30 @@ impl FromStr for BuiltinAction {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Buffer snapshot actions, parsed from `snapshot:[name]` and `snapshot-diff:[name]`.
+pub enum SnapshotAction {
+    /// Take a named snapshot of the buffer's current port lines.
+    Take(CompactString),
+    /// Highlight port lines not present in the named snapshot, or stop if already diffing against it.
+    ToggleDiff(CompactString),
+}
+
+impl SnapshotAction {
+    pub const TAKE_PREFIX: &str = "snapshot:";
+    pub const DIFF_PREFIX: &str = "snapshot-diff:";
+}
+
+impl RequiresPort for SnapshotAction {
+    fn requires_connection(&self) -> bool {
+        false
+    }
+    fn requires_terminal_view(&self) -> bool {
+        // Nothing to snapshot or see highlighted outside of the terminal view.
+        true
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Action {
     BuiltinAction(BuiltinAction),
@@ -491,6 +712,20 @@ pub enum Action {
     EspFlashProfile(String),
     #[cfg(feature = "macros")]
     MacroInvocation(MacroNameTag),
+    #[cfg(feature = "macros")]
+    /// Content from a step of the named macro sequence, boxed since it's much larger than other actions.
+    MacroPayload(MacroNameTag, Box<MacroContent>),
+    #[cfg(feature = "macros")]
+    /// Wait for the pattern to be received (or time out) before continuing the named macro sequence.
+    MacroExpect(MacroNameTag, CompactString, Duration),
+    Snapshot(SnapshotAction),
+    #[cfg(feature = "scripting")]
+    /// Name of a script to run, parsed from `script:[name]`.
+    Script(CompactString),
+    /// Shell command to run, parsed from `run:[command]`.
+    RunCommand(CompactString),
+    /// A line written in the compose editor, sent with the TX line ending.
+    ComposedLine(CompactString),
     Pause(Duration),
 }
 
@@ -503,7 +738,17 @@ impl RequiresPort for Action {
             Self::EspFlashProfile(_) => true,
             #[cfg(feature = "macros")]
             // Sending contents to a port requires a healthy connection.
-            Self::MacroInvocation(_) => true,
+            Self::MacroInvocation(_) | Self::MacroPayload(..) => true,
+            #[cfg(feature = "macros")]
+            // Nothing will be received without one.
+            Self::MacroExpect(..) => true,
+            Self::Snapshot(action) => action.requires_connection(),
+            #[cfg(feature = "scripting")]
+            // Scripts are mostly for talking to a device.
+            Self::Script(_) => true,
+            // Commands can do whatever they like, with or without a port.
+            Self::RunCommand(_) => false,
+            Self::ComposedLine(_) => true,
             // Sitting around and doing nothing does not.
             Self::Pause(_) => false,
         }
@@ -511,6 +756,7 @@ impl RequiresPort for Action {
     fn requires_terminal_view(&self) -> bool {
         match self {
             Self::BuiltinAction(action) => action.requires_terminal_view(),
+            Self::Snapshot(action) => action.requires_terminal_view(),
             _ => self.requires_connection(),
         }
     }
@@ -533,11 +779,25 @@ ctrl-b = "toggle-pseudo-shell-hex"
 
 ctrl-f = "reload-colors"
 
+ctrl-n = "show-notification-history"
+alt-p = "show-command-palette"
+alt-enter = "show-compose-editor"
+ctrl-r = "reverse-history-search"
+
 ctrl-t = "escape-keypress"
 
 ctrl-h = "show-keybinds"
 ctrl-k = "show-keybinds"
 'ctrl-/' = "show-keybinds"
+
+[mouse]
+scroll-up = "scroll-up"
+scroll-down = "scroll-down"
+scroll-left = "scroll-left"
+scroll-right = "scroll-right"
+shift-scroll-up = "scroll-left"
+shift-scroll-down = "scroll-right"
+right-click = "paste-clipboard"
 "#;
 
 pub const CONFIG_TOML_PATH: &str = "yap_keybinds.toml";
@@ -548,6 +808,87 @@ pub const CONFIG_TOML_PATH: &str = "yap_keybinds.toml";
 const DEFAULT_KEYPRESS_ESCAPE: KeyCombination =
     KeyCombination::one_key(KeyCode::Char('t'), KeyModifiers::CONTROL);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "kebab-case")]
+#[strum(ascii_case_insensitive)]
+/// Mouse buttons and scroll directions that can be bound under `[mouse]`.
+pub enum MouseInput {
+    LeftClick,
+    MiddleClick,
+    RightClick,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A mouse input along with any held modifiers, like `ctrl-scroll-up`.
+pub struct MouseBinding {
+    pub input: MouseInput,
+    pub modifiers: KeyModifiers,
+}
+
+impl MouseBinding {
+    /// Returns `None` for mouse events that can't be bound, like moving or releasing a button.
+    pub fn from_event(event: &crossterm::event::MouseEvent) -> Option<Self> {
+        use crossterm::event::{MouseButton, MouseEventKind};
+
+        let input = match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => MouseInput::LeftClick,
+            MouseEventKind::Down(MouseButton::Middle) => MouseInput::MiddleClick,
+            MouseEventKind::Down(MouseButton::Right) => MouseInput::RightClick,
+            MouseEventKind::ScrollUp => MouseInput::ScrollUp,
+            MouseEventKind::ScrollDown => MouseInput::ScrollDown,
+            MouseEventKind::ScrollLeft => MouseInput::ScrollLeft,
+            MouseEventKind::ScrollRight => MouseInput::ScrollRight,
+            _ => return None,
+        };
+        // Only these can be reliably reported by terminals.
+        let modifiers =
+            event.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        Some(Self { input, modifiers })
+    }
+}
+
+impl FromStr for MouseBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s.trim();
+        while let Some((prefix, remaining)) = rest.split_once('-') {
+            let modifier = match prefix.to_ascii_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => break,
+            };
+            modifiers |= modifier;
+            rest = remaining;
+        }
+        let input = rest
+            .parse::<MouseInput>()
+            .map_err(|_| format!("unknown mouse input \"{s}\""))?;
+        Ok(Self { input, modifiers })
+    }
+}
+
+impl fmt::Display for MouseBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        write!(f, "{}", self.input)
+    }
+}
+
 // TODO add a category for "text-based" actions?
 // like history scrolling/word removal/cursor manipulation?
 
@@ -557,6 +898,30 @@ pub struct Keybinds {
     #[serde(default)]
     pub keybindings: IndexMap<KeyCombination, Vec<String>>,
 
+    /// Multi-step bindings, like `ctrl-space l c`, triggered by pressing each key in turn.
+    #[serde(deserialize_with = "deserialize_chords_map")]
+    #[serde(default)]
+    pub chords: IndexMap<Vec<KeyCombination>, Vec<String>>,
+
+    /// Named sets of keybinds, layered over `[keybindings]` while switched to.
+    #[serde(default)]
+    profiles: IndexMap<String, KeybindMap>,
+    /// Name of the profile currently layered over the regular keybinds.
+    #[serde(skip)]
+    active_profile: Option<String>,
+    /// Regular keybinds without any profile applied.
+    #[serde(skip)]
+    base_keybindings: IndexMap<KeyCombination, Vec<String>>,
+
+    /// Bindings only used in certain screens, checked before `keybindings`.
+    #[serde(default)]
+    pub contexts: IndexMap<KeybindContext, KeybindMap>,
+
+    /// Bindings for mouse buttons and scrolling, only used in the terminal view with no popup open.
+    #[serde(deserialize_with = "deserialize_mouse_map")]
+    #[serde(default)]
+    pub mouse: IndexMap<MouseBinding, Vec<String>>,
+
     // To avoid doing a search + to_string()
     // every single time we render these commonly shown hints.
     #[serde(skip)]
@@ -565,6 +930,113 @@ pub struct Keybinds {
     show_keybinds_hint: Option<CompactString>,
     #[serde(skip)]
     escape_keypress_hint: CompactString,
+
+    /// Key combos written more than once in the user's keybinds file (like `ctrl-w` and `Ctrl-w`),
+    /// with each spelling used. Only the last one is kept.
+    #[serde(skip)]
+    duplicates: Vec<(KeyCombination, Vec<String>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, VariantArray)]
+/// Keys always handled by the app itself before keybinds are checked,
+/// so anything bound to them is never triggered.
+///
+/// The app matches on these exhaustively, so each one is sure to have a handler.
+pub enum ReservedKey {
+    CtrlC,
+    CtrlShiftC,
+    Esc,
+    Enter,
+    CtrlEnter,
+    ShiftEnter,
+    CtrlShiftEnter,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    CtrlPageUp,
+    CtrlPageDown,
+    ShiftPageUp,
+    ShiftPageDown,
+    CtrlBackspace,
+    CtrlShiftBackspace,
+    CtrlShiftDelete,
+}
+
+impl ReservedKey {
+    pub fn key_combo(self) -> KeyCombination {
+        use crokey::key;
+        match self {
+            ReservedKey::CtrlC => key!(ctrl - c),
+            ReservedKey::CtrlShiftC => key!(ctrl - shift - c),
+            ReservedKey::Esc => key!(esc),
+            ReservedKey::Enter => key!(enter),
+            ReservedKey::CtrlEnter => key!(ctrl - enter),
+            ReservedKey::ShiftEnter => key!(shift - enter),
+            ReservedKey::CtrlShiftEnter => key!(ctrl - shift - enter),
+            ReservedKey::Up => key!(up),
+            ReservedKey::Down => key!(down),
+            ReservedKey::Left => key!(left),
+            ReservedKey::Right => key!(right),
+            ReservedKey::PageUp => key!(pageup),
+            ReservedKey::PageDown => key!(pagedown),
+            ReservedKey::CtrlPageUp => key!(ctrl - pageup),
+            ReservedKey::CtrlPageDown => key!(ctrl - pagedown),
+            ReservedKey::ShiftPageUp => key!(shift - pageup),
+            ReservedKey::ShiftPageDown => key!(shift - pagedown),
+            ReservedKey::CtrlBackspace => key!(ctrl - backspace),
+            ReservedKey::CtrlShiftBackspace => key!(ctrl - shift - backspace),
+            ReservedKey::CtrlShiftDelete => key!(ctrl - shift - delete),
+        }
+    }
+    pub fn from_key_combo(key_combo: KeyCombination) -> Option<Self> {
+        Self::VARIANTS
+            .iter()
+            .copied()
+            .find(|reserved| reserved.key_combo() == key_combo)
+    }
+}
+
+/// Actions that should always have a key, since their popups are how everything else is found.
+fn important_actions() -> [BuiltinAction; 2] {
+    [
+        ShowPopupAction::ShowKeybinds.into(),
+        ShowPopupAction::ShowPortSettings.into(),
+    ]
+}
+
+#[derive(Debug, Default)]
+/// Problems with the loaded keybinds, to tell the user about rather than silently picking one.
+pub struct KeybindReport {
+    /// Key combos written more than once, and each spelling used.
+    pub duplicates: Vec<(KeyCombination, Vec<String>)>,
+    /// Bound key combos the app handles itself first.
+    pub shadowed: Vec<KeyCombination>,
+    /// Important actions with no key bound to only them.
+    pub unbound: Vec<BuiltinAction>,
+}
+
+impl KeybindReport {
+    pub fn is_empty(&self) -> bool {
+        self.duplicates.is_empty() && self.shadowed.is_empty() && self.unbound.is_empty()
+    }
+    /// Short description of the problems found, for a notification.
+    pub fn summary(&self) -> String {
+        let mut problems = Vec::new();
+        if !self.duplicates.is_empty() {
+            problems.push(format!("{} bound twice", self.duplicates.len()));
+        }
+        if !self.shadowed.is_empty() {
+            problems.push(format!("{} unreachable", self.shadowed.len()));
+        }
+        if !self.unbound.is_empty() {
+            let unbound: Vec<String> = self.unbound.iter().map(ToString::to_string).collect();
+            problems.push(format!("unbound: {}", unbound.join(", ")));
+        }
+        format!("Keybind issues: {}", problems.join(" | "))
+    }
 }
 
 // fn serialize_macros_map<S>(
@@ -587,6 +1059,26 @@ pub struct Keybinds {
 //     map_ser.end()
 // }
 
+/// Key combos in the `[keybindings]` table that are written more than once in different ways,
+/// since the later ones would otherwise silently replace the earlier.
+fn find_duplicate_keys(input: &str) -> Vec<(KeyCombination, Vec<String>)> {
+    let Ok(document) = toml::from_str::<toml::Table>(input) else {
+        return Vec::new();
+    };
+    let Some(toml::Value::Table(bindings)) = document.get("keybindings") else {
+        return Vec::new();
+    };
+
+    let mut spellings: IndexMap<KeyCombination, Vec<String>> = IndexMap::new();
+    for key in bindings.keys() {
+        if let Ok(key_combo) = crokey::parse(key) {
+            spellings.entry(key_combo).or_default().push(key.to_owned());
+        }
+    }
+    spellings.retain(|_, keys| keys.len() > 1);
+    spellings.into_iter().collect()
+}
+
 fn deserialize_keybinds_map<'de, D>(
     deserializer: D,
 ) -> Result<IndexMap<KeyCombination, Vec<String>>, D::Error>
@@ -623,21 +1115,14 @@ where
             while let Some((key, value)) =
                 map.next_entry::<KeyCombination, SingleOrSeveral<String>>()?
             {
-                let mut tags = match value {
-                    SingleOrSeveral::Single(value) if value.trim().is_empty() => continue,
+                let tags = match value {
                     SingleOrSeveral::Single(single) => vec![single],
                     SingleOrSeveral::Several(pre_split) => pre_split,
                 };
 
-                // Remove any empty chain entries
-                tags.retain(|tag| !tag.trim().is_empty());
-
-                // And just don't bother remembering empty bindings.
-                if tags.is_empty() {
-                    continue;
+                if let Some(tags) = binding_actions(tags) {
+                    result.insert(key, tags);
                 }
-
-                result.insert(key, tags);
             }
 
             Ok(result)
@@ -647,6 +1132,145 @@ where
     deserializer.deserialize_map(KeybindsMapVisitor)
 }
 
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Deserialize,
+    strum::Display,
+    strum::VariantArray,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "title_case")]
+/// Screens that can have their own keybinds, under `[contexts.<name>]`.
+pub enum KeybindContext {
+    PortSelection,
+    Terminal,
+    /// Terminal view while hex view is active, checked before `terminal`.
+    HexView,
+    #[cfg(feature = "macros")]
+    Macros,
+    Settings,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(transparent)]
+/// Keybinds in the same format as `[keybindings]`, for a context or profile.
+pub struct KeybindMap(
+    #[serde(deserialize_with = "deserialize_keybinds_map")]
+    pub  IndexMap<KeyCombination, Vec<String>>,
+);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// One action, or several to run in order.
+enum BoundActions {
+    Single(String),
+    Several(Vec<String>),
+}
+
+impl BoundActions {
+    /// See `binding_actions`.
+    fn into_actions(self) -> Option<Vec<String>> {
+        binding_actions(match self {
+            BoundActions::Single(single) => vec![single],
+            BoundActions::Several(pre_split) => pre_split,
+        })
+    }
+}
+
+/// Binding a key to just this unbinds it, even if it has a default.
+pub const UNBIND_ACTION: &str = "none";
+
+/// Removes any empty entries from a binding's actions, returning `None` if nothing's left
+/// (so a default binding stays in place), or no actions if it was bound to [`UNBIND_ACTION`].
+fn binding_actions(mut actions: Vec<String>) -> Option<Vec<String>> {
+    actions.retain(|action| !action.trim().is_empty());
+    match actions.as_slice() {
+        [] => None,
+        [only] if only.trim().eq_ignore_ascii_case(UNBIND_ACTION) => Some(Vec::new()),
+        _ => Some(actions),
+    }
+}
+
+/// Separates each key combo in a chord's sequence.
+const CHORD_DELIMITER: char = ' ';
+
+fn deserialize_chords_map<'de, D>(
+    deserializer: D,
+) -> Result<IndexMap<Vec<KeyCombination>, Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let raw = IndexMap::<String, BoundActions>::deserialize(deserializer)?;
+    let mut result = IndexMap::new();
+
+    for (sequence, actions) in raw {
+        let keys = sequence
+            .split(CHORD_DELIMITER)
+            .filter(|key| !key.is_empty())
+            .map(crokey::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| D::Error::custom(format!("invalid chord \"{sequence}\": {e}")))?;
+        if keys.len() < 2 {
+            return Err(D::Error::custom(format!(
+                "chord \"{sequence}\" needs at least two keys, bind single keys under [keybindings]"
+            )));
+        }
+
+        if let Some(actions) = actions.into_actions() {
+            result.insert(keys, actions);
+        }
+    }
+
+    Ok(result)
+}
+
+fn deserialize_mouse_map<'de, D>(
+    deserializer: D,
+) -> Result<IndexMap<MouseBinding, Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let raw = IndexMap::<String, BoundActions>::deserialize(deserializer)?;
+    let mut result = IndexMap::new();
+
+    for (binding, actions) in raw {
+        let binding = binding.parse::<MouseBinding>().map_err(D::Error::custom)?;
+        if let Some(actions) = actions.into_actions() {
+            result.insert(binding, actions);
+        }
+    }
+
+    Ok(result)
+}
+
+/// The chord's keys as they'd be written in the keybinds file.
+pub fn chord_to_string(keys: &[KeyCombination]) -> String {
+    keys.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(&CHORD_DELIMITER.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What pressing a key next would do while part of a chord has been entered.
+pub enum ChordContinuation<'a> {
+    /// Finishes a chord, running these actions.
+    Actions(&'a [String]),
+    /// Leads to this many more chords.
+    Prefix(usize),
+}
+
 impl Keybinds {
     /// Load keybinds from disk.
     ///
@@ -671,7 +1295,7 @@ impl Keybinds {
         // We require this to be bound since otherwise the user can get themselves stuck.
         // Ideally this never overrides a user's action, but c'est la vie.
         if self
-            .find_key_with_single_action(BaseAction::EscapeKeypress.into())
+            .find_key_with_single_action(&BaseAction::EscapeKeypress.into())
             .is_none()
         {
             self.keybindings.insert(
@@ -681,15 +1305,15 @@ impl Keybinds {
         }
 
         self.port_settings_hint = self
-            .find_key_with_single_action(ShowPopupAction::ShowPortSettings.into())
+            .find_key_with_single_action(&ShowPopupAction::ShowPortSettings.into())
             .map(|kc| kc.to_compact_string());
 
         self.show_keybinds_hint = self
-            .find_key_with_single_action(ShowPopupAction::ShowKeybinds.into())
+            .find_key_with_single_action(&ShowPopupAction::ShowKeybinds.into())
             .map(|kc| kc.to_compact_string());
 
         self.escape_keypress_hint = self
-            .find_key_with_single_action(BaseAction::EscapeKeypress.into())
+            .find_key_with_single_action(&BaseAction::EscapeKeypress.into())
             .map(|kc| kc.to_compact_string())
             .expect("This action must be bound, and should've been forcibly bound on load.");
     }
@@ -712,6 +1336,7 @@ impl Keybinds {
         let mut deserialized: Self =
             toml::from_str(OVERRIDABLE_DEFAULTS).expect("hardcoded default should be valid");
 
+        deserialized.base_keybindings = deserialized.keybindings.clone();
         deserialized.fill_hints();
 
         deserialized
@@ -721,18 +1346,93 @@ impl Keybinds {
 
         let user_settings: Self = toml::from_str(input)?;
 
+        overridable.duplicates = find_duplicate_keys(input);
+
+        overridable.chords.extend(user_settings.chords);
+        overridable.chords.retain(|_, actions| !actions.is_empty());
+
+        for (context, keybinds) in user_settings.contexts {
+            let existing = overridable.contexts.entry(context).or_default();
+            existing.0.extend(keybinds.0);
+            existing.0.retain(|_, actions| !actions.is_empty());
+        }
+
+        overridable.mouse.extend(user_settings.mouse);
+        overridable.mouse.retain(|_, actions| !actions.is_empty());
+
+        overridable.profiles = user_settings.profiles;
+
         if user_settings.keybindings.is_empty() {
             return Ok(overridable);
         }
-        // Anything the user has supplied with the same key will be overwritten.
+        // Anything the user has supplied with the same key will be overwritten,
+        // and anything bound to "none" is unbound.
         overridable.keybindings.extend(user_settings.keybindings);
+        overridable
+            .keybindings
+            .retain(|_, actions| !actions.is_empty());
 
+        overridable.base_keybindings = overridable.keybindings.clone();
         overridable.fill_hints();
 
         Ok(overridable)
     }
+    /// Name of the profile layered over the regular keybinds, if any.
+    pub fn active_profile(&self) -> Option<&str> {
+        self.active_profile.as_deref()
+    }
+    /// Layer the named profile over the regular keybinds, or remove any profile if `None`.
+    ///
+    /// Returns `false` if no profile has that name.
+    pub fn set_profile(&mut self, name: Option<&str>) -> bool {
+        let profile = match name {
+            Some(name) => match self.profiles.get_key_value(name) {
+                Some((name, _)) => Some(name.to_owned()),
+                None => return false,
+            },
+            None => None,
+        };
+        self.active_profile = profile;
+        self.apply_profile();
+        true
+    }
+    /// Switch to the next profile, going back to the regular keybinds after the last one.
+    ///
+    /// Returns `false` if there are no profiles to switch between.
+    pub fn cycle_profile(&mut self) -> bool {
+        if self.profiles.is_empty() {
+            return false;
+        }
+        let next = match &self.active_profile {
+            None => Some(0),
+            Some(active) => self
+                .profiles
+                .get_index_of(active)
+                .map(|index| index + 1)
+                .filter(|index| *index < self.profiles.len()),
+        };
+        self.active_profile = next
+            .and_then(|index| self.profiles.get_index(index))
+            .map(|(name, _)| name.to_owned());
+        self.apply_profile();
+        true
+    }
+    /// Rebuild the keybinds from the regular ones and the active profile's.
+    fn apply_profile(&mut self) {
+        self.keybindings = self.base_keybindings.clone();
+        if let Some(profile) = self
+            .active_profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+        {
+            self.keybindings
+                .extend(profile.0.iter().map(|(k, v)| (*k, v.clone())));
+            self.keybindings.retain(|_, actions| !actions.is_empty());
+        }
+        self.fill_hints();
+    }
     /// Returns the first keybind found with the supplied action as the only bound action.
-    fn find_key_with_single_action(&self, action: BuiltinAction) -> Option<KeyCombination> {
+    fn find_key_with_single_action(&self, action: &BuiltinAction) -> Option<KeyCombination> {
         self.keybindings
             .iter()
             .find(|(_, actions)| {
@@ -743,7 +1443,38 @@ impl Keybinds {
                 actions[0]
                     .parse::<BuiltinAction>()
                     .ok()
-                    .is_some_and(|a| a == action)
+                    .is_some_and(|a| &a == action)
+            })
+            .map(|(key_combo, _)| *key_combo)
+    }
+    /// First key bound to only the given action, checking the given contexts before the regular keybinds.
+    ///
+    /// Regular keybinds that the contexts bind to something else are skipped.
+    pub fn key_for_action_in_contexts(
+        &self,
+        contexts: &[KeybindContext],
+        action: &BuiltinAction,
+    ) -> Option<KeyCombination> {
+        let is_only_action = |actions: &Vec<String>| {
+            actions.len() == 1
+                && actions[0]
+                    .parse::<BuiltinAction>()
+                    .is_ok_and(|parsed| &parsed == action)
+        };
+        let context_maps = || {
+            contexts
+                .iter()
+                .filter_map(|context| self.contexts.get(context))
+        };
+
+        context_maps()
+            .flat_map(|keybinds| &keybinds.0)
+            .find(|(_, actions)| is_only_action(actions))
+            .or_else(|| {
+                self.keybindings.iter().find(|(key_combo, actions)| {
+                    is_only_action(actions)
+                        && !context_maps().any(|keybinds| keybinds.0.contains_key(*key_combo))
+                })
             })
             .map(|(key_combo, _)| *key_combo)
     }
@@ -759,9 +1490,148 @@ impl Keybinds {
             })
             .is_some_and(|parsed_action| action == parsed_action)
     }
+    pub fn action_strs_from_mouse(&self, binding: MouseBinding) -> Option<&Vec<String>> {
+        self.mouse.get(&binding)
+    }
     pub fn action_strs_from_key_combo(&self, key_combo: KeyCombination) -> Option<&Vec<String>> {
         self.keybindings.get(&key_combo)
     }
+    /// Actions bound to the key in the first of the given contexts that has it,
+    /// falling back to the regular keybinds.
+    pub fn action_strs_in_contexts(
+        &self,
+        contexts: &[KeybindContext],
+        key_combo: KeyCombination,
+    ) -> Option<&Vec<String>> {
+        contexts
+            .iter()
+            .filter_map(|context| self.contexts.get(context))
+            .find_map(|keybinds| keybinds.0.get(&key_combo))
+            .or_else(|| self.action_strs_from_key_combo(key_combo))
+    }
+    /// If the keys pressed so far are the start of any chord.
+    pub fn is_chord_prefix(&self, keys: &[KeyCombination]) -> bool {
+        self.chords
+            .keys()
+            .any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys))
+    }
+    /// Actions of the chord exactly matching the keys pressed, if any.
+    pub fn chord_actions(&self, keys: &[KeyCombination]) -> Option<&Vec<String>> {
+        self.chords.get(keys)
+    }
+    /// Every key that could be pressed next after the given start of a chord, in the order first bound.
+    pub fn chord_continuations(
+        &self,
+        keys: &[KeyCombination],
+    ) -> Vec<(KeyCombination, ChordContinuation<'_>)> {
+        let mut continuations: IndexMap<KeyCombination, ChordContinuation> = IndexMap::new();
+        for (sequence, actions) in &self.chords {
+            if sequence.len() <= keys.len() || !sequence.starts_with(keys) {
+                continue;
+            }
+            let next = sequence[keys.len()];
+            if sequence.len() == keys.len() + 1 {
+                continuations.insert(next, ChordContinuation::Actions(actions));
+            } else {
+                match continuations
+                    .entry(next)
+                    .or_insert(ChordContinuation::Prefix(0))
+                {
+                    ChordContinuation::Prefix(count) => *count += 1,
+                    // A longer chord sharing a finished one's keys can never be reached.
+                    ChordContinuation::Actions(_) => (),
+                }
+            }
+        }
+        continuations.into_iter().collect()
+    }
+    /// Check for keys bound more than once, keys that can't be reached, and important actions left unbound.
+    pub fn report(&self) -> KeybindReport {
+        KeybindReport {
+            duplicates: self.duplicates.clone(),
+            shadowed: self
+                .keybindings
+                .keys()
+                .chain(
+                    self.contexts
+                        .values()
+                        .flat_map(|keybinds| keybinds.0.keys()),
+                )
+                .filter(|key_combo| ReservedKey::from_key_combo(**key_combo).is_some())
+                .copied()
+                .unique()
+                .collect(),
+            unbound: important_actions()
+                .into_iter()
+                .filter(|action| self.find_key_with_single_action(action).is_none())
+                .collect(),
+        }
+    }
+    /// Every key combo bound to only the named action.
+    pub fn keys_with_single_action(&self, action: &str) -> Vec<KeyCombination> {
+        self.keybindings
+            .iter()
+            .filter(|(_, actions)| actions.len() == 1 && actions[0].eq_ignore_ascii_case(action))
+            .map(|(key_combo, _)| *key_combo)
+            .collect()
+    }
+    /// Bind the key combo to the given actions (unbinding it if empty),
+    /// both in memory and in the user's keybinds file.
+    ///
+    /// The rest of the file is left as-is, comments included.
+    pub fn save_binding(
+        &mut self,
+        key_combo: KeyCombination,
+        actions: Vec<String>,
+    ) -> Result<(), KeybindSaveError> {
+        let keybinds_path = config_adjacent_path(CONFIG_TOML_PATH);
+        let keybinds_str = if keybinds_path.exists() {
+            fs::read_to_string(&keybinds_path).map_err(KeybindSaveError::FileRead)?
+        } else {
+            include_str!("../example_configs/yap_keybinds.toml.blank").to_owned()
+        };
+        let mut document: toml_edit::DocumentMut = keybinds_str.parse()?;
+
+        let table = document
+            .entry("keybindings")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or(KeybindSaveError::NotATable)?;
+
+        // The same combo could've been written differently, like `ctrl-w` vs `Ctrl-w`.
+        let same_combo: Vec<String> = table
+            .iter()
+            .map(|(key, _)| key.to_owned())
+            .filter(|key| crokey::parse(key).is_ok_and(|parsed| parsed == key_combo))
+            .collect();
+        for key in same_combo {
+            table.remove(&key);
+        }
+
+        // Bound to "none" rather than removed, so defaults are unbound too.
+        let value = match actions.as_slice() {
+            [] => toml_edit::value(UNBIND_ACTION),
+            [single] => toml_edit::value(single.as_str()),
+            several => toml_edit::value(
+                several
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<toml_edit::Array>(),
+            ),
+        };
+        table.insert(&key_combo.to_string(), value);
+
+        fs::write(&keybinds_path, document.to_string()).map_err(KeybindSaveError::FileWrite)?;
+
+        if actions.is_empty() {
+            self.base_keybindings.shift_remove(&key_combo);
+        } else {
+            self.base_keybindings.insert(key_combo, actions);
+        }
+        self.apply_profile();
+
+        Ok(())
+    }
 }
 
 // #[cfg(test)]
@@ -786,42 +1656,64 @@ impl Keybinds {
 //     }
 // }
 
-pub fn print_all_actions() {
-    use ratatui::crossterm::style::Stylize;
-    fn print_variants<T: VariantArray + EnumMessage + fmt::Display>(name: &str) {
-        use ratatui::crossterm::style::Stylize;
-
-        let text = format!("{name}:").red();
-        println!("{text}");
-        for variant in T::VARIANTS {
-            let mut doc_comment = String::from(" - ");
-            doc_comment.push_str(
-                variant
+/// Every builtin action's name and doc comment, grouped the same as in `--print-actions`.
+pub fn builtin_action_groups() -> Vec<(&'static str, Vec<(String, &'static str)>)> {
+    fn variants<T: VariantArray + EnumMessage + fmt::Display>(
+        name: &'static str,
+    ) -> (&'static str, Vec<(String, &'static str)>) {
+        let actions = T::VARIANTS
+            .iter()
+            .map(|variant| {
+                let doc_comment = variant
                     .get_documentation()
-                    .unwrap_or_else(|| panic!("BuiltinAction {variant} missing doc comment")),
-            );
-            let styled_doc = doc_comment.dark_grey();
-
-            println!("{variant}{styled_doc}");
-        }
-        println!();
+                    .unwrap_or_else(|| panic!("BuiltinAction {variant} missing doc comment"));
+                (variant.to_string(), doc_comment)
+            })
+            .collect();
+        (name, actions)
     }
 
-    print_variants::<ShowPopupAction>("Show Popup Actions");
-    print_variants::<BaseAction>("Base Actions");
-    print_variants::<PortAction>("Port Actions");
+    // Only pushed to when features with their own actions are enabled.
+    #[allow(unused_mut)]
+    let mut groups = vec![
+        variants::<ShowPopupAction>("Show Popup Actions"),
+        variants::<BaseAction>("Base Actions"),
+        variants::<PortAction>("Port Actions"),
+    ];
 
     #[cfg(feature = "macros")]
-    print_variants::<MacroBuiltinAction>("Macro Actions");
+    groups.push(variants::<MacroBuiltinAction>("Macro Actions"));
+
+    #[cfg(feature = "scripting")]
+    groups.push(variants::<ScriptBuiltinAction>("Script Actions"));
 
     #[cfg(feature = "espflash")]
-    print_variants::<EspBuiltinAction>("ESP Actions");
+    groups.push(variants::<EspBuiltinAction>("ESP Actions"));
 
     #[cfg(feature = "logging")]
-    print_variants::<LoggingAction>("Logging Actions");
+    groups.push(variants::<LoggingAction>("Logging Actions"));
+
+    #[cfg(feature = "defmt")]
+    groups.push(variants::<DefmtSelectAction>("defmt Selection Actions"));
 
     #[cfg(feature = "defmt")]
-    print_variants::<DefmtSelectAction>("defmt Selection Actions");
+    groups.push(variants::<DefmtAction>("defmt Actions"));
+
+    groups
+}
+
+pub fn print_all_actions() {
+    use ratatui::crossterm::style::Stylize;
+
+    for (name, actions) in builtin_action_groups() {
+        let text = format!("{name}:").red();
+        println!("{text}");
+        for (action, doc_comment) in actions {
+            let styled_doc = format!(" - {doc_comment}").dark_grey();
+            println!("{action}{styled_doc}");
+        }
+        println!();
+    }
 
     let tip = "Tip:".green();
 
@@ -871,6 +1763,18 @@ pub fn print_all_actions() {
     println!(
         "\n\nA custom delay can be set between actions using {pause}. This will always take precedence over yap.toml's `action_chain_delay`."
     );
+    #[cfg(feature = "scripting")]
+    {
+        let script = "SCRIPT:[name]".cyan();
+        println!(
+            "\n\nScripts in the `scripts` folder can be run with {script}, using the script's file name without `.rhai`."
+        );
+    }
+    let snapshot = "SNAPSHOT:[name]".cyan();
+    let snapshot_diff = "SNAPSHOT-DIFF:[name]".cyan();
+    println!(
+        "\n\nA named snapshot of the buffer can be taken with {snapshot}, and {snapshot_diff} toggles highlighting lines that weren't present in it."
+    );
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -882,3 +1786,204 @@ pub enum KeybindLoadError {
     #[error("invalid keybinds file")]
     Deser(#[from] toml::de::Error),
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeybindSaveError {
+    #[error("failed reading from keybinds file")]
+    FileRead(#[source] std::io::Error),
+    #[error("failed saving to keybinds file")]
+    FileWrite(#[source] std::io::Error),
+    #[error("invalid keybinds file")]
+    Parse(#[from] toml_edit::TomlError),
+    #[error("keybinds file's `keybindings` isn't a table")]
+    NotATable,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(combo: &str) -> KeyCombination {
+        crokey::parse(combo).unwrap()
+    }
+
+    fn keys(chord: &str) -> Vec<KeyCombination> {
+        chord.split(CHORD_DELIMITER).map(key).collect()
+    }
+
+    #[test]
+    fn chords_continue_until_a_full_sequence() {
+        let keybinds = Keybinds::from_str(
+            r#"
+            [chords]
+            "ctrl-space t w" = "toggle-textwrap"
+            "ctrl-space t s" = "toggle-timestamps"
+            "ctrl-space r" = ["reload-keybinds", "reload-colors"]
+            "ctrl-space r x" = "toggle-indices"
+            "ctrl-space q" = "none"
+            "#,
+        )
+        .unwrap();
+
+        assert!(keybinds.is_chord_prefix(&keys("ctrl-space")));
+        assert!(keybinds.is_chord_prefix(&keys("ctrl-space t")));
+        assert!(!keybinds.is_chord_prefix(&keys("ctrl-space t w")));
+        assert!(!keybinds.is_chord_prefix(&keys("ctrl-x")));
+
+        assert_eq!(
+            keybinds.chord_actions(&keys("ctrl-space t w")),
+            Some(&vec!["toggle-textwrap".to_owned()])
+        );
+        assert_eq!(keybinds.chord_actions(&keys("ctrl-space t")), None);
+        // Unbound chords are dropped entirely.
+        assert_eq!(keybinds.chord_actions(&keys("ctrl-space q")), None);
+
+        let reload = ["reload-keybinds".to_owned(), "reload-colors".to_owned()];
+        assert_eq!(
+            keybinds.chord_continuations(&keys("ctrl-space")),
+            [
+                (key("t"), ChordContinuation::Prefix(2)),
+                // The longer chord under it can never be reached, so isn't counted.
+                (key("r"), ChordContinuation::Actions(&reload)),
+            ]
+        );
+        assert_eq!(
+            keybinds.chord_continuations(&keys("ctrl-space t")),
+            [
+                (
+                    key("w"),
+                    ChordContinuation::Actions(&["toggle-textwrap".to_owned()])
+                ),
+                (
+                    key("s"),
+                    ChordContinuation::Actions(&["toggle-timestamps".to_owned()])
+                ),
+            ]
+        );
+
+        assert!(Keybinds::from_str("[chords]\n\"ctrl-space\" = \"toggle-textwrap\"").is_err());
+    }
+
+    #[test]
+    fn contexts_checked_in_order_before_regular_keybinds() {
+        let keybinds = Keybinds::from_str(
+            r#"
+            [keybindings]
+            ctrl-e = "toggle-indices"
+
+            [contexts.hex-view]
+            ctrl-w = "toggle-hex-view-header"
+
+            [contexts.terminal]
+            ctrl-w = "reload-keybinds"
+            ctrl-e = "reload-colors"
+            "#,
+        )
+        .unwrap();
+        let first_action = |contexts: &[KeybindContext], combo: &str| {
+            keybinds
+                .action_strs_in_contexts(contexts, key(combo))
+                .map(|actions| actions[0].as_str())
+        };
+
+        let hex_view = [KeybindContext::HexView, KeybindContext::Terminal];
+        assert_eq!(
+            first_action(&hex_view, "ctrl-w"),
+            Some("toggle-hex-view-header")
+        );
+        assert_eq!(first_action(&hex_view, "ctrl-e"), Some("reload-colors"));
+        assert_eq!(
+            first_action(&[KeybindContext::Terminal], "ctrl-w"),
+            Some("reload-keybinds")
+        );
+        // Other screens still get the regular (and default) keybinds.
+        let port_selection = [KeybindContext::PortSelection];
+        assert_eq!(
+            first_action(&port_selection, "ctrl-w"),
+            Some("toggle-textwrap")
+        );
+        assert_eq!(
+            first_action(&port_selection, "ctrl-e"),
+            Some("toggle-indices")
+        );
+
+        // Hints skip regular keybinds that the screen binds to something else.
+        let textwrap = BuiltinAction::from(BaseAction::ToggleTextwrap);
+        assert_eq!(
+            keybinds.key_for_action_in_contexts(&port_selection, &textwrap),
+            Some(key("ctrl-w"))
+        );
+        assert_eq!(
+            keybinds.key_for_action_in_contexts(&[KeybindContext::Terminal], &textwrap),
+            None
+        );
+        let header = BuiltinAction::from(BaseAction::ToggleHexViewHeader);
+        assert_eq!(
+            keybinds.key_for_action_in_contexts(&hex_view, &header),
+            Some(key("ctrl-w"))
+        );
+    }
+
+    #[test]
+    fn profiles_layer_over_regular_keybinds_in_turn() {
+        let mut keybinds = Keybinds::from_str(
+            r#"
+            [keybindings]
+            ctrl-e = "toggle-indices"
+
+            [profiles.left-handed]
+            ctrl-q = "toggle-textwrap"
+            ctrl-e = "reload-colors"
+
+            [profiles.screen-reader]
+            ctrl-w = "none"
+            "#,
+        )
+        .unwrap();
+        let first_action = |keybinds: &Keybinds, combo: &str| {
+            keybinds
+                .action_strs_from_key_combo(key(combo))
+                .map(|actions| actions[0].clone())
+        };
+        let regular = |keybinds: &Keybinds| {
+            assert_eq!(keybinds.active_profile(), None);
+            assert_eq!(first_action(keybinds, "ctrl-q"), None);
+            assert_eq!(first_action(keybinds, "ctrl-e").unwrap(), "toggle-indices");
+            assert_eq!(first_action(keybinds, "ctrl-w").unwrap(), "toggle-textwrap");
+        };
+        regular(&keybinds);
+
+        assert!(keybinds.cycle_profile());
+        assert_eq!(keybinds.active_profile(), Some("left-handed"));
+        assert_eq!(
+            first_action(&keybinds, "ctrl-q").unwrap(),
+            "toggle-textwrap"
+        );
+        assert_eq!(first_action(&keybinds, "ctrl-e").unwrap(), "reload-colors");
+        assert_eq!(
+            first_action(&keybinds, "ctrl-w").unwrap(),
+            "toggle-textwrap"
+        );
+
+        // Only one profile is layered at a time.
+        assert!(keybinds.cycle_profile());
+        assert_eq!(keybinds.active_profile(), Some("screen-reader"));
+        assert_eq!(first_action(&keybinds, "ctrl-q"), None);
+        assert_eq!(first_action(&keybinds, "ctrl-e").unwrap(), "toggle-indices");
+        assert_eq!(first_action(&keybinds, "ctrl-w"), None);
+
+        // Then back to the regular keybinds after the last one.
+        assert!(keybinds.cycle_profile());
+        regular(&keybinds);
+
+        assert!(!keybinds.set_profile(Some("missing")));
+        assert!(keybinds.set_profile(Some("screen-reader")));
+        assert_eq!(first_action(&keybinds, "ctrl-w"), None);
+        assert!(keybinds.set_profile(None));
+        regular(&keybinds);
+
+        let mut no_profiles = Keybinds::from_str("").unwrap();
+        assert!(!no_profiles.cycle_profile());
+        assert_eq!(no_profiles.active_profile(), None);
+    }
+}
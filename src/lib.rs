@@ -3,6 +3,7 @@
 use std::{
     net::{SocketAddr, TcpStream},
     path::Path,
+    process::ExitCode,
     str::FromStr,
     sync::{
         Arc, Mutex, OnceLock,
@@ -14,33 +15,62 @@ use std::{
 use app::{App, CrosstermEvent};
 use camino::Utf8PathBuf;
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+#[cfg(feature = "logging")]
+use color_eyre::eyre::Context;
 use crokey::crossterm::event::{KeyCode, KeyModifiers};
 use fs_err as fs;
 use panic_handler::initialize_panic_handler;
 use ratatui::crossterm::{
     self,
-    event::{DisableMouseCapture, EnableMouseCapture, MouseButton, MouseEventKind},
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
 };
 
 use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
-use tracing::{Level, debug, error, level_filters::LevelFilter};
+use tracing::{Level, debug, error, level_filters::LevelFilter, warn};
 use tracing_appender::non_blocking::WorkerGuard;
 
-use crate::{cli::YapCli, serial::DeserializedUsb, settings::Settings};
+use crate::{
+    cli::{YapCli, YapCommand},
+    exit_conditions::ExitConditions,
+    keybinds::MouseBinding,
+    serial::DeserializedUsb,
+    settings::{
+        Settings,
+        overrides::overrides_from_env,
+        profiles::{SETTINGS_PROFILES_DIR_PATH, SettingsProfileError, SettingsProfiles},
+    },
+};
 
 mod app;
 mod buffer;
 mod cli;
+#[cfg(feature = "defmt")]
+mod defmt_decode;
 
 mod event_carousel;
+mod exit_conditions;
+mod external_command;
+#[cfg(any(feature = "defmt-watch", feature = "settings-watch"))]
+mod file_watch;
+mod headless;
 mod keybinds;
+mod list_ports;
+mod logview;
 #[cfg(feature = "macros")]
 mod macros;
 mod notifications;
 mod panic_handler;
+#[cfg(feature = "logging")]
+mod replay;
+#[cfg(feature = "scripting")]
+mod scripting;
 mod serial;
+#[cfg(feature = "logging")]
+mod session;
 mod settings;
+#[cfg(test)]
+mod test_util;
 mod text_input;
 mod traits;
 mod tui;
@@ -60,6 +90,13 @@ pub fn config_adjacent_path<P: Into<Utf8PathBuf>>(path: P) -> Utf8PathBuf {
     config_path.join(path)
 }
 
+static MONOTONIC_EPOCH: OnceLock<Instant> = OnceLock::new();
+/// Microseconds since this was first called, from a clock that never jumps like wall-clock time can.
+pub fn monotonic_micros() -> u64 {
+    let epoch = MONOTONIC_EPOCH.get_or_init(Instant::now);
+    epoch.elapsed().as_micros() as u64
+}
+
 static EXECUTABLE_FILE_STEM: OnceLock<Utf8PathBuf> = OnceLock::new();
 /// Returns name of executable stripped of executable suffix.
 pub fn get_executable_name() -> Utf8PathBuf {
@@ -80,18 +117,37 @@ pub fn get_executable_name() -> Utf8PathBuf {
 /// Wrapper runner so any fatal errors get properly logged, and to
 /// have a clear line between spinning up the whole app and all it's threads,
 /// and just parsing CLI args and possibly exiting early.
-pub fn run() -> color_eyre::Result<()> {
+pub fn run() -> color_eyre::Result<ExitCode> {
+    // Answers the shell and exits if it's asking for completions.
+    clap_complete::CompleteEnv::with_factory(YapCli::command)
+        .var(cli::COMPLETE_ENV_VAR)
+        .complete();
+
     let cli_args = YapCli::parse();
 
+    if let Some(YapCommand::Completions { shell }) = &cli_args.command {
+        cli::print_completions(*shell)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Handled before logging is set up, so it can't end up sending its own logs to itself.
+    if let Some(YapCommand::Logview { listen }) = &cli_args.command {
+        initialize_panic_handler()?;
+        logview::run(*listen)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
     if cli_args.print_actions {
         keybinds::print_all_actions();
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
     // println!("{cli_args:#?}");
     // return Ok(());
 
     initialize_panic_handler()?;
+    // Start counting from the beginning, instead of the first chunk recieved.
+    monotonic_micros();
 
     if let Some(path) = &cli_args.config_path {
         CONFIG_PARENT_PATH_CELL
@@ -110,7 +166,39 @@ pub fn run() -> color_eyre::Result<()> {
         config_adjacent_path(exec_name)
     };
 
-    let settings = Settings::load(config_path)?;
+    // Checked before loading, since that writes out the defaults.
+    let new_config = !config_path.exists();
+    let mut settings = Settings::load(config_path)?;
+
+    if let Some(name) = &cli_args.profile {
+        let (profiles, errors) =
+            SettingsProfiles::load_from_folder(config_adjacent_path(SETTINGS_PROFILES_DIR_PATH))?;
+        // Only the requested profile being invalid is worth stopping for.
+        if let Some(e) = errors.into_iter().find(|e| {
+            matches!(
+                e,
+                SettingsProfileError::Deser { name: invalid, .. }
+                    | SettingsProfileError::Unreadable { name: invalid, .. }
+                    if invalid.eq_ignore_ascii_case(name)
+            )
+        }) {
+            return Err(e.into());
+        }
+        let (name, profile) = profiles.get(name)?;
+        settings.apply_profile(name, profile);
+    }
+
+    // Command line overrides are applied last, so they win over the environment's.
+    let mut overrides = overrides_from_env();
+    overrides.extend(cli_args.set.iter().cloned());
+    let skipped_overrides = if overrides.is_empty() {
+        Vec::new()
+    } else {
+        settings.apply_overrides(overrides)?
+    };
+    for e in &skipped_overrides {
+        eprintln!("Ignoring setting override from environment: {e}");
+    }
 
     let listener_address = settings.misc.log_tcp_socket;
 
@@ -118,8 +206,12 @@ pub fn run() -> color_eyre::Result<()> {
     log_path.set_extension("log");
     let (_log_guard, tcp_log_health) =
         initialize_logging(settings.get_log_level(), log_path, listener_address)?;
+    // Only logged now that there's somewhere for it to go.
+    for e in skipped_overrides {
+        warn!("Ignored setting override from environment: {e}");
+    }
 
-    let result = run_inner(cli_args, settings, tcp_log_health);
+    let result = run_inner(cli_args, settings, tcp_log_health, new_config);
     if let Err(e) = &result {
         error!("App closed with error:");
         for (index, err) in e.chain().enumerate() {
@@ -134,10 +226,51 @@ fn run_inner(
     cli_args: YapCli,
     app_settings: Settings,
     tcp_log_health: Arc<TcpStreamHealth>,
-) -> color_eyre::Result<()> {
+    new_config: bool,
+) -> color_eyre::Result<ExitCode> {
+    if let Some(command) = &cli_args.command {
+        match command {
+            YapCommand::Send(args) => {
+                return headless::send(cli_port_info(args.port.clone())?, args, &app_settings);
+            }
+            #[cfg(feature = "defmt")]
+            YapCommand::DefmtDecode(args) => {
+                defmt_decode::run(args, &app_settings)?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            YapCommand::Completions { .. } | YapCommand::Logview { .. } => {
+                unreachable!("handled before loading settings")
+            }
+            // Played back in the TUI below.
+            #[cfg(feature = "logging")]
+            YapCommand::Play { .. } => (),
+        }
+    }
+
+    #[cfg(feature = "logging")]
+    let replay_path = match &cli_args.command {
+        Some(YapCommand::Play { session }) => Some(session),
+        _ => cli_args.replay.as_ref(),
+    };
+
+    if cli_args.list_ports {
+        list_ports::print_ports(&app_settings, cli_args.json)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let exit_conditions = ExitConditions::from_cli(&cli_args);
+
+    if cli_args.headless {
+        let port = cli_args.port.expect("clap requires a port for headless");
+        let port_info = cli_port_info(port)?;
+        return headless::run(port_info, cli_args.baud, &app_settings, exit_conditions);
+    }
+
     let (tx, rx) = crossbeam::channel::unbounded::<app::Event>();
     let (crossterm_tx, crossterm_rx) = crossbeam::channel::unbounded::<CrosstermEvent>();
     let (ctrl_c_tx, ctrl_c_rx) = crossbeam::channel::bounded::<()>(1);
+    let input_paused = Arc::new(AtomicBool::new(false));
+    let thread_input_paused = input_paused.clone();
     let _crossterm_thread = std::thread::spawn(move || {
         use crokey::crossterm::event::{Event, KeyEventKind};
 
@@ -167,24 +300,32 @@ fn run_inner(
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
                     send_event(CrosstermEvent::KeyPress(key))?
                 }
-                Event::Mouse(mouse) => match mouse.kind {
-                    MouseEventKind::ScrollUp => {
-                        send_event(CrosstermEvent::MouseScroll { up: true })?;
-                    }
-                    MouseEventKind::ScrollDown => {
-                        send_event(CrosstermEvent::MouseScroll { up: false })?;
+                Event::Mouse(mouse) => {
+                    if let Some(binding) = MouseBinding::from_event(&mouse) {
+                        let position = (mouse.column, mouse.row).into();
+                        send_event(CrosstermEvent::Mouse(binding, position))?;
                     }
-                    MouseEventKind::Down(MouseButton::Right) => {
-                        send_event(CrosstermEvent::RightClick)?;
-                    }
-                    _ => (),
-                },
+                }
+                Event::Paste(text) => send_event(CrosstermEvent::Paste(text))?,
                 _ => (),
             }
             Ok(())
         };
 
         loop {
+            // Leaving stdin alone while something else has the terminal, like an editor.
+            if thread_input_paused.load(Ordering::Acquire) {
+                std::thread::sleep(tui::editor::INPUT_PAUSE_POLL);
+                continue;
+            }
+            match crossterm::event::poll(tui::editor::INPUT_PAUSE_POLL) {
+                Ok(true) => (),
+                Ok(false) => continue,
+                Err(e) => {
+                    error!("error encountered when polling crossterm events, shutting down. {e}");
+                    break;
+                }
+            }
             let event = match crossterm::event::read() {
                 Ok(ev) => ev,
                 Err(e) => {
@@ -247,15 +388,21 @@ fn run_inner(
     // };
 
     let allow_first_time_setup = cli_args.port.is_none();
+    #[cfg(feature = "logging")]
+    let allow_first_time_setup = allow_first_time_setup && replay_path.is_none();
+    #[cfg(feature = "self-replace")]
+    let allow_first_time_setup = allow_first_time_setup && cli_args.update_from.is_none();
 
     let mut app = App::build(
         tx,
         rx,
         ctrl_c_tx,
         crossterm_rx,
+        input_paused,
         app_settings,
         tcp_log_health,
         allow_first_time_setup,
+        new_config,
     )?;
 
     #[cfg(feature = "defmt")]
@@ -280,32 +427,80 @@ fn run_inner(
         }
     }
 
-    if let Some(port) = cli_args.port {
-        let port_info = if port.contains(':') {
-            let usb_query = DeserializedUsb::from_str(&port)?;
-            SerialPortInfo {
-                port_name: String::new(),
-                port_type: SerialPortType::UsbPort(UsbPortInfo::from(usb_query)),
-            }
-        } else {
-            SerialPortInfo {
-                port_name: port,
-                port_type: SerialPortType::Unknown,
-            }
-        };
+    #[cfg(feature = "defmt")]
+    for extra_path in &cli_args.extra_defmt_elf {
+        if let Some(locs_err) = app::_try_load_extra_defmt_elf(
+            extra_path,
+            &mut app.buffer.extra_defmt_decoders,
+            #[cfg(feature = "logging")]
+            &app.buffer.log_handle,
+        )? {
+            Err(locs_err)?;
+        }
+    }
 
+    if let Some(port) = cli_args.port {
+        let port_info = cli_port_info(port)?;
         app.try_cli_connect(port_info, cli_args.baud)?;
     };
 
+    #[cfg(feature = "logging")]
+    if let Some(record_path) = &cli_args.record {
+        app.buffer
+            .start_recording(record_path)
+            .wrap_err_with(|| format!("failed to start session recording at {record_path}"))?;
+    }
+
+    #[cfg(feature = "logging")]
+    if let Some(replay_path) = replay_path {
+        app.start_replay(replay_path)?;
+    }
+
+    #[cfg(feature = "self-replace")]
+    if let Some(archive) = cli_args.update_from {
+        app.offer_update_from_archive(archive);
+    }
+
+    if let Some(conditions) = exit_conditions {
+        app.set_exit_conditions(conditions)?;
+    }
+
     let terminal = ratatui::init();
     crossterm::execute!(std::io::stdout(), EnableMouseCapture)?;
+    // Not supported by the legacy Windows console, where pastes just arrive as typed keys instead.
+    _ = crossterm::execute!(std::io::stdout(), EnableBracketedPaste);
 
     let app_result = app.run(terminal);
 
     ratatui::restore();
     crossterm::execute!(std::io::stdout(), DisableMouseCapture)?;
+    _ = crossterm::execute!(std::io::stdout(), DisableBracketedPaste);
+
+    app_result?;
 
-    app_result
+    Ok(match app.exit_outcome {
+        Some(outcome) => {
+            eprintln!("{}", outcome.describe());
+            outcome.exit_code()
+        }
+        None => ExitCode::SUCCESS,
+    })
+}
+
+/// A serial port path, or a USB `VID:PID[:SERIAL]` to search for.
+fn cli_port_info(port: String) -> color_eyre::Result<SerialPortInfo> {
+    Ok(if port.contains(':') {
+        let usb_query = DeserializedUsb::from_str(&port)?;
+        SerialPortInfo {
+            port_name: String::new(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo::from(usb_query)),
+        }
+    } else {
+        SerialPortInfo {
+            port_name: port,
+            port_type: SerialPortType::Unknown,
+        }
+    })
 }
 
 pub fn is_ctrl_c(key: &crossterm::event::KeyEvent) -> bool {
@@ -330,9 +525,14 @@ impl TcpStreamHealth {
         self.ok.load(Ordering::Relaxed)
     }
 
-    fn mark_failed(&self) {
+    pub(crate) fn mark_failed(&self) {
         self.ok.store(false, Ordering::Relaxed);
     }
+
+    #[cfg(feature = "logging")]
+    pub(crate) fn mark_ok(&self) {
+        self.ok.store(true, Ordering::Relaxed);
+    }
 }
 
 /// A simple newtype to monitor for failures during writing,
@@ -0,0 +1,130 @@
+//! Printing the ports yap can see and exiting, for finding the right one to pass as `[PORT]`.
+
+use color_eyre::eyre::Context;
+use serialport::{SerialPortInfo, SerialPortType};
+
+use crate::{app::SCAN_BLOCK_MAX, serial::handle::SerialHandle, settings::Settings};
+
+#[derive(serde::Serialize)]
+/// A found port, flattened to be easier to read from scripts.
+struct ListedPort {
+    port_name: String,
+    /// `usb`, `pci`, `bluetooth`, or `unknown`.
+    port_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vid: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    serial_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manufacturer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    product: Option<String>,
+}
+
+impl From<SerialPortInfo> for ListedPort {
+    fn from(info: SerialPortInfo) -> Self {
+        let mut listed = Self {
+            port_name: info.port_name,
+            port_type: "unknown",
+            vid: None,
+            pid: None,
+            serial_number: None,
+            manufacturer: None,
+            product: None,
+        };
+        match info.port_type {
+            SerialPortType::UsbPort(usb) => {
+                listed.port_type = "usb";
+                listed.vid = Some(usb.vid);
+                listed.pid = Some(usb.pid);
+                listed.serial_number = usb.serial_number;
+                listed.manufacturer = usb.manufacturer;
+                listed.product = usb.product;
+            }
+            SerialPortType::PciPort => listed.port_type = "pci",
+            SerialPortType::BluetoothPort => listed.port_type = "bluetooth",
+            SerialPortType::Unknown => (),
+        }
+        listed
+    }
+}
+
+/// Scan for ports the same way port selection does, leaving out ignored devices,
+/// and print them as a table or as a JSON array.
+pub fn print_ports(settings: &Settings, json: bool) -> color_eyre::Result<()> {
+    let (event_tx, _event_rx) = crossbeam::channel::unbounded();
+    let (serial_buf_tx, _serial_buf_rx) = crossbeam::channel::unbounded();
+    let (serial, _serial_thread, ports) = SerialHandle::build(
+        event_tx,
+        serial_buf_tx,
+        settings.serial.clone(),
+        settings.ignored_devices.clone(),
+        SCAN_BLOCK_MAX,
+    )
+    .wrap_err("failed to build serial worker")?;
+    _ = serial.shutdown();
+
+    let ports: Vec<ListedPort> = ports.into_iter().map(ListedPort::from).collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&ports)?);
+    } else if ports.is_empty() {
+        eprintln!("No ports found.");
+    } else {
+        print_table(&ports);
+    }
+    Ok(())
+}
+
+fn print_table(ports: &[ListedPort]) {
+    const HEADERS: [&str; 6] = [
+        "PORT",
+        "TYPE",
+        "VID:PID",
+        "SERIAL",
+        "MANUFACTURER",
+        "PRODUCT",
+    ];
+
+    let rows: Vec<[String; 6]> = ports
+        .iter()
+        .map(|port| {
+            let vid_pid = match (port.vid, port.pid) {
+                (Some(vid), Some(pid)) => format!("{vid:04X}:{pid:04X}"),
+                _ => String::new(),
+            };
+            [
+                port.port_name.clone(),
+                port.port_type.to_owned(),
+                vid_pid,
+                port.serial_number.clone().unwrap_or_default(),
+                port.manufacturer.clone().unwrap_or_default(),
+                port.product.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: &[&str]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    };
+
+    print_row(&HEADERS);
+    for row in &rows {
+        print_row(&row.each_ref().map(String::as_str));
+    }
+}
@@ -0,0 +1,222 @@
+//! `yap logview`, a companion TUI that listens for the tracing output
+//! other yap instances send to their `log_tcp_socket`, and shows it live.
+
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader},
+    net::{SocketAddr, TcpListener, TcpStream},
+    time::Duration,
+};
+
+use ansi_to_tui::IntoText;
+use color_eyre::eyre::Context;
+use crossbeam::channel::{Receiver, Sender};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::Alignment,
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph},
+};
+
+use crate::is_ctrl_c;
+
+/// Oldest lines are dropped past this, to keep memory use bounded during long sessions.
+const MAX_LINES: usize = 20_000;
+/// How long to wait for input before checking for new log lines again.
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
+enum LogviewEvent {
+    Connected(SocketAddr),
+    Line(Vec<u8>),
+    Disconnected(SocketAddr),
+}
+
+struct LogView {
+    listening_on: SocketAddr,
+    lines: VecDeque<Line<'static>>,
+    /// How many lines up from the newest the view is, `0` follows new lines as they come in.
+    scroll_from_bottom: usize,
+    connections: usize,
+    /// Height of the log area at the last render, for paging.
+    page_height: usize,
+}
+
+/// Listen for tracing output on the given address, showing it until the user quits.
+pub fn run(listen: SocketAddr) -> color_eyre::Result<()> {
+    let listener = TcpListener::bind(listen)
+        .wrap_err_with(|| format!("failed to listen for logs on {listen}"))?;
+    let listening_on = listener.local_addr()?;
+
+    let (event_tx, event_rx) = crossbeam::channel::unbounded();
+    std::thread::Builder::new()
+        .name("logview-listener".into())
+        .spawn(move || accept_connections(listener, event_tx))?;
+
+    let mut view = LogView {
+        listening_on,
+        lines: VecDeque::new(),
+        scroll_from_bottom: 0,
+        connections: 0,
+        page_height: 0,
+    };
+
+    let terminal = ratatui::init();
+    let result = view.run(terminal, &event_rx);
+    ratatui::restore();
+
+    result
+}
+
+fn accept_connections(listener: TcpListener, event_tx: Sender<LogviewEvent>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let event_tx = event_tx.clone();
+        _ = std::thread::Builder::new()
+            .name("logview-reader".into())
+            .spawn(move || read_lines(stream, event_tx));
+    }
+}
+
+/// Forward every line from a connected instance until it closes the connection.
+fn read_lines(stream: TcpStream, event_tx: Sender<LogviewEvent>) {
+    let Ok(peer) = stream.peer_addr() else {
+        return;
+    };
+    if event_tx.send(LogviewEvent::Connected(peer)).is_err() {
+        return;
+    }
+
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                while line.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+                    line.pop();
+                }
+                if event_tx.send(LogviewEvent::Line(line.clone())).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    _ = event_tx.send(LogviewEvent::Disconnected(peer));
+}
+
+impl LogView {
+    fn run(
+        &mut self,
+        mut terminal: DefaultTerminal,
+        event_rx: &Receiver<LogviewEvent>,
+    ) -> color_eyre::Result<()> {
+        loop {
+            for event in event_rx.try_iter() {
+                self.handle_event(event);
+            }
+
+            terminal.draw(|frame| self.render(frame))?;
+
+            if event::poll(INPUT_POLL)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                if is_ctrl_c(&key) {
+                    break;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char('c') => {
+                        self.lines.clear();
+                        self.scroll_from_bottom = 0;
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => self.scroll_up(1),
+                    KeyCode::Down | KeyCode::Char('j') => self.scroll_down(1),
+                    KeyCode::PageUp => self.scroll_up(self.page_height.max(1)),
+                    KeyCode::PageDown => self.scroll_down(self.page_height.max(1)),
+                    KeyCode::Home | KeyCode::Char('g') => self.scroll_up(self.lines.len()),
+                    KeyCode::End | KeyCode::Char('G') => self.scroll_from_bottom = 0,
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+    fn handle_event(&mut self, event: LogviewEvent) {
+        match event {
+            LogviewEvent::Connected(peer) => {
+                self.connections += 1;
+                self.push_line(Line::styled(
+                    format!("--- {peer} connected ---"),
+                    Style::new().green(),
+                ));
+            }
+            LogviewEvent::Disconnected(peer) => {
+                self.connections = self.connections.saturating_sub(1);
+                self.push_line(Line::styled(
+                    format!("--- {peer} disconnected ---"),
+                    Style::new().yellow(),
+                ));
+            }
+            LogviewEvent::Line(bytes) => {
+                let line = match bytes.into_text() {
+                    Ok(text) => text.lines.into_iter().next().unwrap_or_default(),
+                    Err(_) => {
+                        let stripped = strip_ansi_escapes::strip(&bytes);
+                        Line::raw(String::from_utf8_lossy(&stripped).into_owned())
+                    }
+                };
+                self.push_line(line);
+            }
+        }
+    }
+    fn push_line(&mut self, line: Line<'static>) {
+        self.lines.push_back(line);
+        if self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+        // Keep the same lines in view if scrolled up.
+        if self.scroll_from_bottom > 0 {
+            self.scroll_up(1);
+        }
+    }
+    fn scroll_up(&mut self, amount: usize) {
+        let max = self.lines.len().saturating_sub(self.page_height);
+        self.scroll_from_bottom = (self.scroll_from_bottom + amount).min(max);
+    }
+    fn scroll_down(&mut self, amount: usize) {
+        self.scroll_from_bottom = self.scroll_from_bottom.saturating_sub(amount);
+    }
+    fn render(&mut self, frame: &mut Frame) {
+        let status = if self.scroll_from_bottom == 0 {
+            "following".to_owned()
+        } else {
+            format!("{} lines up", self.scroll_from_bottom)
+        };
+        let block = Block::bordered()
+            .title_top(Line::raw(format!(
+                " yap logview | {} | {} connected ",
+                self.listening_on, self.connections
+            )))
+            .title_bottom(Line::raw(format!(
+                " {status} | q: Quit | ↑↓ PgUp PgDn: Scroll | End: Follow | c: Clear "
+            )))
+            .title_alignment(Alignment::Center);
+
+        let area = block.inner(frame.area());
+        self.page_height = area.height as usize;
+
+        let end = self.lines.len().saturating_sub(self.scroll_from_bottom);
+        let start = end.saturating_sub(self.page_height);
+        let visible: Vec<Line> = self.lines.range(start..end).cloned().collect();
+
+        frame.render_widget(block, frame.area());
+        frame.render_widget(Paragraph::new(visible), area);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::VecDeque;
+
+use bstr::ByteSlice;
+use camino::{Utf8Path, Utf8PathBuf};
+use compact_str::{ToCompactString, format_compact};
+use fs_err as fs;
+use tracing::{error, warn};
+
+use super::{
+    MacroCategorySelection, MacroContent, MacroFile, MacroNameTag, MacroSaveError, Macros,
+    MacrosDeserError, SerializedMacro,
+};
+
+/// Bundles placed here are imported, and get moved into `imported` afterwards.
+pub const MACRO_BUNDLES_DIR_PATH: &str = "macro_bundles";
+const EXPORTED_DIR: &str = "exported";
+const IMPORTED_DIR: &str = "imported";
+
+#[derive(Debug, thiserror::Error)]
+pub enum MacroBundleError {
+    #[error("no macros to export")]
+    Empty,
+    #[error("failed reading/writing bundle file")]
+    File(#[from] std::io::Error),
+    #[error(transparent)]
+    Deser(#[from] MacrosDeserError),
+    #[error("failed serializing bundle")]
+    Ser(#[from] toml::ser::Error),
+    #[error("macro \"{0}\" in bundle refers to a content file, which bundles can't include")]
+    ContentFile(MacroNameTag),
+}
+
+/// Bundles are shared on their own, so any `content_file` is placed into the content instead.
+fn inline_content_file(
+    content: MacroContent,
+    macro_dir: &Utf8Path,
+) -> std::io::Result<MacroContent> {
+    let Some(content_file) = &content.content_file else {
+        return Ok(content);
+    };
+    let bytes = fs::read(macro_dir.join(content_file))?;
+    Ok(MacroContent {
+        content: bytes.escape_bytes().to_compact_string(),
+        has_escaped_bytes: true,
+        // Files are sent without a line ending unless one is given.
+        escaped_line_ending: Some(content.escaped_line_ending.clone().unwrap_or_default()),
+        content_file: None,
+        ..content
+    })
+}
+
+/// Every macro in the bundle, refusing the bundle if any of them have a `content_file`.
+fn read_bundle(
+    path: &Utf8Path,
+    bundles_dir: &Utf8Path,
+) -> Result<Vec<(MacroNameTag, MacroContent)>, MacroBundleError> {
+    let file_contents = fs::read_to_string(path)?;
+    let bundle: MacroFile = toml::from_str(&file_contents).map_err(|source| MacrosDeserError {
+        path: path.to_owned(),
+        source,
+    })?;
+    let fallback_category = bundle.fallback_category(path, bundles_dir);
+
+    let mut macros = Vec::with_capacity(bundle.macros.len());
+    for mut ser_macro in bundle.macros {
+        if ser_macro.category.is_none() {
+            ser_macro.category = Some(fallback_category.clone());
+        }
+        let (mut tag, content) = ser_macro.into_tag_and_content();
+        if tag.category.as_ref().is_some_and(|c| c.trim().is_empty()) {
+            tag.category = None;
+        }
+        // Exported bundles never have any, so this could only point elsewhere on disk.
+        if content.content_file.is_some() {
+            return Err(MacroBundleError::ContentFile(tag));
+        }
+        macros.push((tag, content));
+    }
+    Ok(macros)
+}
+
+fn sanitized_file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl Macros {
+    /// Write every macro in the selected category to a single bundle file,
+    /// returning its path and how many macros were in it.
+    pub fn export_selected_category(
+        &self,
+        bundles_dir: &Utf8Path,
+    ) -> Result<(Utf8PathBuf, usize), MacroBundleError> {
+        let mut file = MacroFile::default();
+
+        for (tag, content) in self.filtered_by_category() {
+            let macro_dir = self
+                .sources
+                .get(tag)
+                .and_then(|source| source.parent())
+                .unwrap_or(&self.folder);
+            let content = inline_content_file(content.clone(), macro_dir)?;
+            // No fallback category, so every macro's category is written out.
+            file.macros
+                .push(SerializedMacro::from_tag_and_content(tag, &content, ""));
+        }
+
+        if file.macros.is_empty() {
+            return Err(MacroBundleError::Empty);
+        }
+
+        let name = match self.selected_category() {
+            MacroCategorySelection::AllMacros => "all_macros",
+            MacroCategorySelection::StringsOnly => "strings_only",
+            MacroCategorySelection::WithBytes => "has_bytes",
+            MacroCategorySelection::NoCategory => "no_category",
+            MacroCategorySelection::Category(category) => category,
+        };
+        let export_dir = bundles_dir.join(EXPORTED_DIR);
+        let path = export_dir.join(format!("{}.toml", sanitized_file_stem(name)));
+
+        fs::create_dir_all(&export_dir)?;
+        fs::write(&path, toml::to_string_pretty(&file)?)?;
+
+        Ok((path, file.macros.len()))
+    }
+    /// Read every bundle waiting in the folder, to be imported with `MacroImport::next_conflict`.
+    ///
+    /// Bundles that can't be read are left in place and listed in `MacroImport::failed`.
+    pub fn begin_import(&self, bundles_dir: &Utf8Path) -> Result<MacroImport, MacroBundleError> {
+        let mut import = MacroImport::default();
+
+        if !bundles_dir.is_dir() {
+            fs::create_dir_all(bundles_dir)?;
+            return Ok(import);
+        }
+
+        for entry in fs::read_dir(bundles_dir)? {
+            let Ok(path) = Utf8PathBuf::from_path_buf(entry?.path()) else {
+                warn!("Macro bundle path is not valid UTF-8! Skipping...");
+                continue;
+            };
+            if !path.is_file() || path.extension() != Some("toml") {
+                continue;
+            }
+
+            match read_bundle(&path, bundles_dir) {
+                Ok(macros) => {
+                    import.pending.extend(macros);
+                    import.bundles.push(path);
+                }
+                Err(e) => {
+                    error!("Failed to read macro bundle {path}: {e}");
+                    import.failed.push(path);
+                }
+            }
+        }
+
+        Ok(import)
+    }
+    /// The macro's name followed by the first number that doesn't clash with an existing macro.
+    fn unused_name(&self, tag: &MacroNameTag) -> MacroNameTag {
+        (2..)
+            .map(|n| MacroNameTag {
+                name: format_compact!("{} ({n})", tag.name),
+                category: tag.category.clone(),
+            })
+            .find(|candidate| !self.all.contains_key(candidate))
+            .expect("should find an unused name eventually")
+    }
+}
+
+#[derive(Debug, Default)]
+/// Macros from bundles being imported, waiting on the user to resolve any duplicates.
+pub struct MacroImport {
+    pending: VecDeque<(MacroNameTag, MacroContent)>,
+    /// Imported macro with the same name and category as an existing (but different) one.
+    pub conflict: Option<(MacroNameTag, MacroContent)>,
+    /// If the rest of the conflicts should be skipped without asking.
+    skip_conflicts: bool,
+    bundles: Vec<Utf8PathBuf>,
+    /// Bundles that couldn't be read, which are left where they are.
+    pub failed: Vec<Utf8PathBuf>,
+    pub imported: usize,
+    pub skipped: usize,
+}
+
+impl MacroImport {
+    pub fn is_empty(&self) -> bool {
+        self.bundles.is_empty() && self.failed.is_empty()
+    }
+    pub fn bundle_count(&self) -> usize {
+        self.bundles.len()
+    }
+    /// Save pending macros until one conflicts with an existing macro, returning `true` if one did.
+    ///
+    /// Macros identical to existing ones are skipped.
+    pub fn next_conflict(&mut self, macros: &mut Macros) -> Result<bool, MacroSaveError> {
+        while let Some((tag, content)) = self.pending.pop_front() {
+            match macros.all.get(&tag) {
+                Some(existing) if *existing == content || self.skip_conflicts => self.skipped += 1,
+                Some(_) => {
+                    self.conflict = Some((tag, content));
+                    return Ok(true);
+                }
+                None => {
+                    macros.save_macro(None, tag, content)?;
+                    self.imported += 1;
+                }
+            }
+        }
+        Ok(false)
+    }
+    /// Overwrite the existing macro with the conflicting one.
+    pub fn replace_conflict(&mut self, macros: &mut Macros) -> Result<(), MacroSaveError> {
+        if let Some((tag, content)) = self.conflict.take() {
+            let original = tag.clone();
+            macros.save_macro(Some(&original), tag, content)?;
+            self.imported += 1;
+        }
+        Ok(())
+    }
+    /// Save the conflicting macro alongside the existing one, under a new name.
+    pub fn keep_both(&mut self, macros: &mut Macros) -> Result<(), MacroSaveError> {
+        if let Some((tag, content)) = self.conflict.take() {
+            let renamed = macros.unused_name(&tag);
+            macros.save_macro(None, renamed, content)?;
+            self.imported += 1;
+        }
+        Ok(())
+    }
+    /// Leave the existing macro as-is, and optionally do the same for any later conflicts.
+    pub fn skip_conflict(&mut self, all: bool) {
+        if self.conflict.take().is_some() {
+            self.skipped += 1;
+        }
+        self.skip_conflicts |= all;
+    }
+    /// Move the imported bundles out of the way, so they aren't imported again.
+    pub fn finish(self, bundles_dir: &Utf8Path) {
+        let imported_dir = bundles_dir.join(IMPORTED_DIR);
+        if let Err(e) = fs::create_dir_all(&imported_dir) {
+            error!("Failed to create imported bundles folder: {e}");
+            return;
+        }
+        for bundle in self.bundles {
+            let Some(file_name) = bundle.file_name() else {
+                continue;
+            };
+            if let Err(e) = fs::rename(&bundle, imported_dir.join(file_name)) {
+                error!("Failed to move imported bundle {bundle}: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    #[test]
+    fn bundles_with_content_files_refused() {
+        let dir = scratch_dir("bundle-content-file");
+        fs::write(
+            dir.join("plain.toml"),
+            "[[macro]]\nname = \"Version\"\ncontent = \"version\"\n",
+        )
+        .unwrap();
+        for (file, content_file) in [
+            ("absolute.toml", "/etc/passwd"),
+            ("parent.toml", "../secret.bin"),
+            ("nested.toml", "firmware.bin"),
+        ] {
+            fs::write(
+                dir.join(file),
+                format!("[[macro]]\nname = \"Leak\"\ncontent_file = \"{content_file}\"\n"),
+            )
+            .unwrap();
+        }
+
+        let import = Macros::empty().begin_import(&dir).unwrap();
+        assert_eq!(import.bundle_count(), 1);
+        assert_eq!(import.pending.len(), 1);
+        assert_eq!(import.pending[0].0.name, "Version");
+        let mut failed: Vec<_> = import.failed.iter().filter_map(|p| p.file_name()).collect();
+        failed.sort_unstable();
+        assert_eq!(failed, ["absolute.toml", "nested.toml", "parent.toml"]);
+
+        _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+use compact_str::CompactString;
+use regex::Regex;
+
+use super::MacroNameTag;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
+/// Capture the device's response to a macro, for the clipboard or a later macro's variables.
+///
+/// Captures a single line if neither `lines` nor `until` are given.
+pub struct MacroCapture {
+    /// Lines to ignore before capturing, i.e. the device echoing back what was sent.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skip: Option<u32>,
+    /// How many lines to capture.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<u32>,
+    /// Capture lines up to (but not including) the first one matching this regex.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<CompactString>,
+    /// Store the captured text to fill in `{{variable}}` in later macros.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable: Option<CompactString>,
+    /// Copy the captured text to the clipboard.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub clipboard: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl MacroCapture {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+}
+
+#[derive(Debug)]
+/// A macro's response being captured line-by-line from received data.
+pub struct ResponseCapture {
+    pub tag: MacroNameTag,
+    pub variable: Option<CompactString>,
+    pub clipboard: bool,
+    pub deadline: Instant,
+    skip: u32,
+    lines_wanted: Option<u32>,
+    until: Option<Regex>,
+    /// Received bytes not yet ended by a newline, kept raw so escapes
+    /// and multi-byte characters split across chunks come out whole.
+    partial: Vec<u8>,
+    lines: Vec<String>,
+}
+
+impl ResponseCapture {
+    pub fn new(tag: &MacroNameTag, capture: &MacroCapture) -> Result<Self, regex::Error> {
+        let until = capture.until.as_deref().map(Regex::new).transpose()?;
+        let lines_wanted = match (capture.lines, &until) {
+            (None, None) => Some(1),
+            (lines, _) => lines,
+        };
+        let timeout = capture
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(MacroCapture::DEFAULT_TIMEOUT);
+
+        Ok(Self {
+            tag: tag.clone(),
+            variable: capture.variable.clone(),
+            clipboard: capture.clipboard,
+            deadline: Instant::now() + timeout,
+            skip: capture.skip.unwrap_or_default(),
+            lines_wanted,
+            until,
+            partial: Vec::new(),
+            lines: Vec::new(),
+        })
+    }
+    /// Add freshly received bytes, returning `true` once the capture is complete.
+    pub fn feed(&mut self, bytes: &[u8]) -> bool {
+        self.partial.extend_from_slice(bytes);
+
+        while let Some(newline) = memchr::memchr(b'\n', &self.partial) {
+            let raw: Vec<u8> = self.partial.drain(..=newline).collect();
+            let stripped = strip_ansi_escapes::strip(&raw);
+            let line = String::from_utf8_lossy(&stripped);
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+            if let Some(until) = &self.until
+                && until.is_match(line)
+            {
+                return true;
+            }
+
+            self.lines.push(line.to_owned());
+
+            if self
+                .lines_wanted
+                .is_some_and(|wanted| self.lines.len() >= wanted as usize)
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+    pub fn timed_out(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(lines: Option<u32>, until: Option<&str>) -> ResponseCapture {
+        let capture = MacroCapture {
+            skip: Some(1),
+            lines,
+            until: until.map(Into::into),
+            variable: None,
+            clipboard: false,
+            timeout_ms: None,
+        };
+        ResponseCapture::new(&MacroNameTag::default(), &capture).unwrap()
+    }
+
+    #[test]
+    fn lines_split_across_chunks_come_out_whole() {
+        let mut capture = capture(Some(2), None);
+        // The echo, then an escape and a multi-byte character split between chunks.
+        assert!(!capture.feed(b"echo\r\n\x1b[3"));
+        assert!(!capture.feed(b"2mgr\xC3"));
+        assert!(!capture.feed(b"\xBCn\x1b[0m\r\n"));
+        assert!(capture.feed(b"second\nthird\n"));
+        assert_eq!(capture.text(), "grün\nsecond");
+    }
+
+    #[test]
+    fn capture_stops_before_until() {
+        let mut capture = capture(None, Some("^OK$"));
+        assert!(!capture.feed(b"echo\none\ntwo\n"));
+        assert!(capture.feed(b"OK\n"));
+        assert_eq!(capture.line_count(), 2);
+        assert_eq!(capture.text(), "one\ntwo");
+    }
+}
@@ -0,0 +1,208 @@
+use camino::Utf8PathBuf;
+use compact_str::CompactString;
+use enum_rotate::EnumRotate;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect, Size},
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+};
+use ratatui_macros::horizontal;
+use strum::{VariantArray, VariantNames};
+use tui_input::Input;
+
+use crate::tui::{centered_rect_size, theme::theme};
+
+use super::{MacroCapture, MacroContent, MacroNameTag, MacroRepeat, MacroStep};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumRotate, VariantArray, VariantNames)]
+#[strum(serialize_all = "title_case")]
+pub enum MacroEditorField {
+    #[default]
+    Name,
+    Category,
+    Content,
+    LineEnding,
+    Sensitive,
+}
+
+#[derive(Debug, Default)]
+/// Scratch space for creating a new macro, or editing an existing one.
+pub struct MacroEditor {
+    /// The macro being edited, `None` if making a new one.
+    pub original: Option<MacroNameTag>,
+    pub selected: MacroEditorField,
+    pub name: Input,
+    pub category: Input,
+    /// Macro content, with bytes escaped the same as in macro files.
+    pub content: Input,
+    /// Escaped line ending, left empty to use the one from Port Settings.
+    pub line_ending: Input,
+    pub sensitive: bool,
+    /// Steps of a macro sequence, carried over as-is since they can only be edited in the file.
+    pub steps: Vec<MacroStep>,
+    /// Binary payload file, carried over as-is too.
+    pub content_file: Option<Utf8PathBuf>,
+    /// Repeat, capture, confirm and cooldown settings, also only editable in the file.
+    pub repeat: Option<MacroRepeat>,
+    pub capture: Option<MacroCapture>,
+    pub confirm: bool,
+    pub cooldown_ms: Option<u64>,
+}
+
+impl MacroEditor {
+    /// Begin making a new macro, with the category pre-filled if given.
+    pub fn new(category: Option<&str>) -> Self {
+        Self {
+            category: category.unwrap_or_default().into(),
+            ..Default::default()
+        }
+    }
+    pub fn editing(tag: &MacroNameTag, content: &MacroContent) -> Self {
+        Self {
+            original: Some(tag.clone()),
+            selected: MacroEditorField::default(),
+            name: tag.name.as_str().into(),
+            category: tag.category.as_deref().unwrap_or_default().into(),
+            content: content.as_str().into(),
+            line_ending: content
+                .escaped_line_ending
+                .as_deref()
+                .unwrap_or_default()
+                .into(),
+            sensitive: content.sensitive,
+            steps: content.steps.clone(),
+            content_file: content.content_file.clone(),
+            repeat: content.repeat,
+            capture: content.capture.clone(),
+            confirm: content.confirm,
+            cooldown_ms: content.cooldown_ms,
+        }
+    }
+    /// The text input for the selected field, if it has one.
+    pub fn selected_input(&mut self) -> Option<&mut Input> {
+        match self.selected {
+            MacroEditorField::Name => Some(&mut self.name),
+            MacroEditorField::Category => Some(&mut self.category),
+            MacroEditorField::Content => Some(&mut self.content),
+            MacroEditorField::LineEnding => Some(&mut self.line_ending),
+            MacroEditorField::Sensitive => None,
+        }
+    }
+    pub fn to_tag_and_content(&self) -> (MacroNameTag, MacroContent) {
+        let non_empty = |input: &Input| {
+            Some(CompactString::from(input.value().trim())).filter(|s| !s.is_empty())
+        };
+        let line_ending =
+            Some(CompactString::from(self.line_ending.value())).filter(|s| !s.is_empty());
+        (
+            MacroNameTag {
+                name: self.name.value().trim().into(),
+                category: non_empty(&self.category),
+            },
+            MacroContent::new_with_line_ending(self.content.value(), line_ending, self.sensitive)
+                .with_steps(self.steps.clone())
+                .with_content_file(self.content_file.clone())
+                .with_repeat(self.repeat)
+                .with_capture(self.capture.clone())
+                .with_confirm(self.confirm)
+                .with_cooldown(self.cooldown_ms),
+        )
+    }
+    pub fn render(&self, frame: &mut Frame, screen: Rect) {
+        let fields = <MacroEditorField as VariantArray>::VARIANTS;
+
+        let area = centered_rect_size(
+            Size {
+                width: screen.width.min(60),
+                height: fields.len() as u16 + 2,
+            },
+            screen,
+        );
+
+        let title = if self.original.is_some() {
+            " Edit Macro "
+        } else {
+            " New Macro "
+        };
+        let hint = if self.selected == MacroEditorField::Sensitive {
+            " Space: Toggle | Enter: Save | Esc: Cancel "
+        } else {
+            " Tab: Next | Enter: Save | Esc: Cancel "
+        };
+
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(Line::raw(title).centered().reset())
+            .title_bottom(Line::raw(hint).centered().reset());
+
+        let rows =
+            Layout::vertical(fields.iter().map(|_| Constraint::Length(1))).split(block.inner(area));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        for ((field, name), row) in fields
+            .iter()
+            .zip(<MacroEditorField as VariantNames>::VARIANTS)
+            .zip(rows.iter())
+        {
+            let [label_area, value_area] = horizontal![==13, *=1].areas(*row);
+            let selected = *field == self.selected;
+
+            let label = Span::raw(format!("{name}:"));
+            let label = if selected {
+                label.style(theme().selection_style())
+            } else {
+                label
+            };
+            frame.render_widget(label, label_area);
+
+            let input = match field {
+                MacroEditorField::Name => &self.name,
+                MacroEditorField::Category => &self.category,
+                MacroEditorField::Content => &self.content,
+                MacroEditorField::LineEnding => &self.line_ending,
+                MacroEditorField::Sensitive => {
+                    let checkbox = if self.sensitive { "[x]" } else { "[ ]" };
+                    frame.render_widget(Span::raw(checkbox), value_area);
+                    continue;
+                }
+            };
+
+            // So the cursor doesn't bleed off the edge
+            let width = value_area.width.max(1).saturating_sub(1) as usize;
+            let scroll = input.visual_scroll(width);
+
+            let value = match field {
+                _ if selected => Paragraph::new(input.value()).scroll((0, scroll as u16)),
+                MacroEditorField::Content if self.sensitive && !input.value().is_empty() => {
+                    Paragraph::new("[SENSITIVE]".italic())
+                }
+                MacroEditorField::Content
+                    if input.value().is_empty() && self.content_file.is_some() =>
+                {
+                    let file = self.content_file.as_ref().map(|file| file.as_str());
+                    Paragraph::new(format!("(from {})", file.unwrap_or_default()).fg(theme().dim))
+                }
+                MacroEditorField::Content if input.value().is_empty() && !self.steps.is_empty() => {
+                    Paragraph::new(format!("({} steps)", self.steps.len()).fg(theme().dim))
+                }
+                MacroEditorField::LineEnding if input.value().is_empty() => {
+                    Paragraph::new("(from Port Settings)".fg(theme().dim))
+                }
+                MacroEditorField::Category if input.value().is_empty() => {
+                    Paragraph::new("(none)".fg(theme().dim))
+                }
+                _ => Paragraph::new(input.value()),
+            };
+            frame.render_widget(value, value_area);
+
+            if selected {
+                let cursor_offset = input.visual_cursor().saturating_sub(scroll);
+                frame.set_cursor_position((value_area.x + cursor_offset as u16, value_area.y));
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crokey::KeyCombination;
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    style::{Style, Stylize},
+    text::{Line, Span},
+};
+
+use crate::tui::theme::theme;
+
+use super::{MacroNameTag, Macros};
+
+/// How many Macros can be pinned to the hotbar, one for each of F1-F10 and Alt+1-0.
+pub const HOTBAR_SLOTS: usize = 10;
+
+fn slot_digit(slot: usize) -> char {
+    char::from_digit(((slot + 1) % 10) as u32, 10).expect("slot should be below 10")
+}
+
+/// Which hotbar slot the key combo sends, if any.
+pub fn hotbar_slot(key_combo: KeyCombination) -> Option<usize> {
+    (0..HOTBAR_SLOTS).find(|&slot| {
+        let f_key = KeyCombination::one_key(KeyCode::F(slot as u8 + 1), KeyModifiers::NONE);
+        let alt_digit = KeyCombination::one_key(KeyCode::Char(slot_digit(slot)), KeyModifiers::ALT);
+        key_combo == f_key || key_combo == alt_digit
+    })
+}
+
+/// Pin the macro to the first free hotbar slot, or unpin it if it already was.
+///
+/// Returns if the macro is now pinned, or `None` if the hotbar is full.
+pub fn toggle_hotbar_pin(pinned: &mut Vec<MacroNameTag>, tag: &MacroNameTag) -> Option<bool> {
+    if let Some(index) = pinned.iter().position(|p| p == tag) {
+        pinned.remove(index);
+        return Some(false);
+    }
+    if pinned.len() >= HOTBAR_SLOTS {
+        return None;
+    }
+    pinned.push(tag.clone());
+    Some(true)
+}
+
+impl Macros {
+    /// Each pinned macro's name after its F-key, with macros that no longer exist greyed out.
+    pub fn hotbar_line(&self, pinned: &[MacroNameTag], width: u16) -> Line<'_> {
+        let slot_count = pinned.len().min(HOTBAR_SLOTS);
+        if slot_count == 0 {
+            return Line::default();
+        }
+        let slot_width = (width as usize / slot_count).max(4);
+
+        let spans = pinned
+            .iter()
+            .take(HOTBAR_SLOTS)
+            .enumerate()
+            .flat_map(|(slot, tag)| {
+                let key = format!("F{}", slot + 1);
+                // One space before the name, one after for a gap between slots.
+                let name_width = slot_width.saturating_sub(key.len() + 2);
+                let name: String = tag.name.chars().take(name_width).collect();
+                let name = format!(" {name:<name_width$} ");
+
+                let name_style = if self.all.contains_key(tag) {
+                    Style::new()
+                } else {
+                    theme().dim_style().crossed_out()
+                };
+                [Span::raw(key).reversed(), Span::styled(name, name_style)]
+            });
+
+        Line::from_iter(spans)
+    }
+}
@@ -1,31 +1,51 @@
 use std::{
+    cmp::Reverse,
     collections::{BTreeMap, BTreeSet},
     path::Path,
+    time::{Duration, Instant},
 };
 
 use bstr::ByteVec;
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use compact_str::CompactString;
 use fs_err::{self as fs};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use itertools::Either;
 use ratatui::{
     layout::Constraint,
-    style::{Style, Stylize},
+    style::Stylize,
     text::Text,
     widgets::{Row, Table},
 };
-use tracing::{error, warn};
+use tracing::{debug, error, warn};
 use tui_input::Input;
 
 use crate::{
     keybinds::Keybinds,
     traits::{HasEscapedBytes, LastIndex},
-    tui::single_line_selector::SingleLineSelectorState,
+    tui::{single_line_selector::SingleLineSelectorState, theme::theme},
 };
 
+mod bundle;
+pub use bundle::{MACRO_BUNDLES_DIR_PATH, MacroImport};
+mod capture;
+pub use capture::{MacroCapture, ResponseCapture};
+mod editor;
+pub use editor::{MacroEditor, MacroEditorField};
+mod hotbar;
+pub use hotbar::{hotbar_slot, toggle_hotbar_pin};
 mod macro_nametag;
 pub use macro_nametag::MacroNameTag;
+mod preview;
+pub use preview::MacroPreview;
+mod recorder;
+pub use recorder::MacroRecorder;
+mod sequence;
+pub use sequence::{MacroExpectation, MacroRepeat, MacroStep, RepeatingMacro};
+mod templates;
 mod tui;
+mod variables;
+pub use variables::{MacroVariable, MacroVariablesForm};
 
 // #[derive(Debug)]
 // #[repr(u8)]
@@ -36,6 +56,14 @@ mod tui;
 // }
 
 pub const MACROS_DIR_PATH: &str = "macros";
+/// Project-specific macros, looked for in the current working directory.
+pub const PROJECT_MACROS_DIR_PATH: &str = ".yap/macros";
+
+/// File stem used for new macros made in-app without a category.
+const UNCATEGORIZED_FILE_STEM: &str = "uncategorized";
+
+/// Separates parent categories from their children, like `devices/openshock`.
+const CATEGORY_SEPARATOR: char = '/';
 
 pub enum MacroCategorySelection<'a> {
     AllMacros,
@@ -51,8 +79,31 @@ pub struct MacroNotFound;
 
 pub struct Macros {
     pub all: BTreeMap<MacroNameTag, MacroContent>,
+    /// Which file each macro was loaded from, so edits can be written back to it.
+    sources: BTreeMap<MacroNameTag, Utf8PathBuf>,
+    /// Folder macros were loaded from, and where new ones get written to.
+    folder: Utf8PathBuf,
+    /// Project-local macros folder, if one was found when loading.
+    project_folder: Option<Utf8PathBuf>,
+
+    /// Scratch space for the macro being created/edited in the UI.
+    pub editor: MacroEditor,
+    /// Values being entered for a macro's variables before sending it.
+    pub variables_form: Option<MacroVariablesForm>,
+    /// Macro shown in the preview popup, see `Macros::preview`.
+    pub previewing: Option<MacroPreview>,
+    /// Last values entered for each variable name, shared between macros.
+    pub last_variable_values: BTreeMap<CompactString, CompactString>,
+    /// Values captured from macro responses, filled in without asking.
+    pub captured_values: BTreeMap<CompactString, CompactString>,
+    /// When each macro with a cooldown was last sent.
+    last_sent: BTreeMap<MacroNameTag, Instant>,
+    /// How many times each macro using `{{counter}}` has been sent.
+    counters: BTreeMap<MacroNameTag, u64>,
 
     pub categories_selector: SingleLineSelectorState,
+    /// Parent categories whose children are hidden from the category selector.
+    collapsed_categories: BTreeSet<CompactString>,
 
     // TODO make private?
     pub search_input: Input,
@@ -69,15 +120,42 @@ pub struct MacrosDeserError {
     source: toml::de::Error,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum MacroSaveError {
+    #[error("macro name can't be empty")]
+    EmptyName,
+    #[error("a macro with that name and category already exists")]
+    Duplicate,
+    #[error(transparent)]
+    NotFound(#[from] MacroNotFound),
+    #[error("failed reading/writing macro file")]
+    File(#[from] std::io::Error),
+    #[error(transparent)]
+    Deser(#[from] MacrosDeserError),
+    #[error("failed serializing macro file")]
+    Ser(#[from] toml::ser::Error),
+}
+
 impl Macros {
     pub fn empty() -> Self {
         Self {
             // scrollbar_state: ScrollbarState::new(test_macros.len()),
             all: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            folder: Utf8PathBuf::new(),
+            project_folder: None,
+            editor: MacroEditor::default(),
+            variables_form: None,
+            previewing: None,
+            last_variable_values: BTreeMap::new(),
+            captured_values: BTreeMap::new(),
+            last_sent: BTreeMap::new(),
+            counters: BTreeMap::new(),
             // tx_queue: Vec::new(),
             // ui_state: MacrosPrompt::None,
             search_input: Input::default(),
             categories_selector: SingleLineSelectorState::new().with_selected(2),
+            collapsed_categories: BTreeSet::new(),
             // categories: BTreeSet::new(),
         }
     }
@@ -115,25 +193,49 @@ impl Macros {
             .iter()
             .filter(move |(tag, content)| match category {
                 MacroCategorySelection::AllMacros => true,
-                MacroCategorySelection::StringsOnly => !content.has_escaped_bytes,
-                MacroCategorySelection::WithBytes => content.has_escaped_bytes,
+                MacroCategorySelection::StringsOnly => !content.has_bytes(),
+                MacroCategorySelection::WithBytes => content.has_bytes(),
                 MacroCategorySelection::NoCategory => tag.category.is_none(),
-                MacroCategorySelection::Category(cat) => tag.category.as_deref() == Some(cat),
+                MacroCategorySelection::Category(cat) => tag
+                    .category
+                    .as_deref()
+                    .is_some_and(|category| is_in_category(category, cat)),
             })
     }
-    /// Return an iterator of all Macros, filtered by the entered search query, case-insensitive.
+    /// Return an iterator of all Macros fuzzy-matching the entered search query
+    /// by name, category, or content, best matches first. Case-insensitive.
     fn filtered_by_search(
         &self,
     ) -> impl DoubleEndedIterator<Item = (&MacroNameTag, &MacroContent)> {
         let query = self.search_input.value();
-        let query_len = query.len();
-        self.all.iter().filter(move |(tag, _)| {
-            if tag.name.is_char_boundary(query_len) {
-                tag.name[..query_len].eq_ignore_ascii_case(query)
-            } else {
-                false
-            }
-        })
+        let matcher = SkimMatcherV2::default().ignore_case();
+
+        let mut scored: Vec<_> = self
+            .all
+            .iter()
+            .filter_map(|(tag, content)| {
+                let name_score = matcher.fuzzy_match(&tag.name, query);
+                let category_score = tag
+                    .category
+                    .as_deref()
+                    .and_then(|category| matcher.fuzzy_match(category, query));
+                // Not searching sensitive content, so results don't hint at what's in it.
+                let content_score = (!content.sensitive)
+                    .then(|| matcher.fuzzy_match(&content.content, query))
+                    .flatten();
+
+                // Names are what's usually being searched for, so they outrank the rest.
+                let score = [name_score.map(|s| s * 2), category_score, content_score]
+                    .into_iter()
+                    .flatten()
+                    .max()?;
+                Some((score, tag, content))
+            })
+            .collect();
+
+        // Stable sort, so equal matches stay in alphabetical order.
+        scored.sort_by_key(|(score, ..)| Reverse(*score));
+        scored.into_iter().map(|(_, tag, content)| (tag, content))
     }
     pub fn filtered_macro_iter(
         &self,
@@ -181,22 +283,97 @@ impl Macros {
             .map(|(m, k)| Row::new([Text::raw(m), Text::raw(k).italic()]));
 
         let widths = [Constraint::Fill(4), Constraint::Fill(1)];
-        Table::new(filtered, widths).row_highlight_style(Style::new().reversed())
+        Table::new(filtered, widths).row_highlight_style(theme().selection_style())
+    }
+    /// Carry over what's only kept while running from the macros loaded before a reload:
+    /// cooldowns and counters of macros that still exist, values entered or captured for variables,
+    /// and which of the remaining categories were collapsed.
+    pub fn keep_state_from(&mut self, previous: Macros) {
+        let Macros {
+            last_variable_values,
+            captured_values,
+            last_sent,
+            counters,
+            collapsed_categories,
+            ..
+        } = previous;
+
+        self.last_variable_values = last_variable_values;
+        self.captured_values = captured_values;
+        self.last_sent = last_sent
+            .into_iter()
+            .filter(|(tag, _)| self.all.contains_key(tag))
+            .collect();
+        self.counters = counters
+            .into_iter()
+            .filter(|(tag, _)| self.all.contains_key(tag))
+            .collect();
+
+        let categories: BTreeSet<&str> = self
+            .all
+            .keys()
+            .filter_map(|tag| tag.category.as_deref())
+            .flat_map(category_with_parents)
+            .collect();
+        self.collapsed_categories = collapsed_categories
+            .into_iter()
+            .filter(|category| categories.contains(category.as_str()))
+            .collect();
     }
     pub fn has_no_category_macros(&self) -> bool {
         self.all.iter().any(|(tag, _)| tag.category.is_none())
     }
+    /// Every category and parent category, as a tree in depth-first order.
+    ///
+    /// Children of collapsed categories are left out.
     pub fn categories(&self) -> impl DoubleEndedIterator<Item = &str> {
         let no_category = std::iter::once("No Category").filter(|_| self.has_no_category_macros());
 
         let categories: BTreeSet<&str> = self
             .all
-            .iter()
-            .filter_map(|(tag, _)| tag.category.as_deref())
+            .keys()
+            .filter_map(|tag| tag.category.as_deref())
+            .flat_map(category_with_parents)
+            .filter(|category| !self.is_hidden_by_collapse(category))
             .collect();
 
+        // Sorted by each level's name, so children are kept right after their parent.
+        let mut categories: Vec<&str> = categories.into_iter().collect();
+        categories.sort_by(|a, b| a.split(CATEGORY_SEPARATOR).cmp(b.split(CATEGORY_SEPARATOR)));
+
         no_category.chain(categories)
     }
+    /// Labels for `categories()`, with each parent category marked as expanded or collapsed.
+    pub fn category_labels(&self) -> impl Iterator<Item = String> {
+        self.categories().map(|category| {
+            let has_children = self.all.keys().any(|tag| {
+                tag.category
+                    .as_deref()
+                    .is_some_and(|c| c != category && is_in_category(c, category))
+            });
+            match has_children {
+                false => category.to_owned(),
+                true if self.collapsed_categories.contains(category) => format!("{category} [+]"),
+                true => format!("{category} [-]"),
+            }
+        })
+    }
+    /// Collapse or expand the selected category's children, if it has any.
+    pub fn toggle_selected_category_collapsed(&mut self) {
+        let MacroCategorySelection::Category(category) = self.selected_category() else {
+            return;
+        };
+        let category = CompactString::from(category);
+        if !self.collapsed_categories.remove(&category) {
+            self.collapsed_categories.insert(category);
+        }
+    }
+    /// If any of the category's parents are collapsed.
+    fn is_hidden_by_collapse(&self, category: &str) -> bool {
+        category_with_parents(category)
+            .filter(|parent| *parent != category)
+            .any(|parent| self.collapsed_categories.contains(parent))
+    }
     pub fn get_by_string(&self, query: &str, fuzzy_macro_name_match: bool) -> Option<MacroNameTag> {
         let query_nametag: MacroNameTag = query.parse().ok()?;
 
@@ -204,6 +381,18 @@ impl Macros {
             .all
             .iter()
             .find(|(tag, _)| query_nametag.eq(tag))
+            .or_else(|| {
+                // Macros in subfolders used to be categorized by just their file's stem,
+                // so `openshock|Restart` still finds `devices/openshock|Restart`.
+                let category = query_nametag.category.as_deref()?;
+                self.all.iter().find(|(tag, _)| {
+                    tag.name == query_nametag.name
+                        && tag.category.as_deref().is_some_and(|full| {
+                            full.rsplit_once(CATEGORY_SEPARATOR)
+                                .is_some_and(|(_, stem)| stem == category)
+                        })
+                })
+            })
             .map(|(t, _)| t.clone());
 
         match find_result {
@@ -216,6 +405,55 @@ impl Macros {
             Some(tag) => Some(tag),
         }
     }
+    /// Follows the macros a sequence sends (and the ones those send),
+    /// returning the chain of macros leading back around if any of them ends up sending itself.
+    ///
+    /// Macros that can't be found are skipped over, they're reported when the sequence is queued.
+    pub fn find_sequence_cycle(
+        &self,
+        tag: &MacroNameTag,
+        fuzzy_macro_name_match: bool,
+    ) -> Option<Vec<MacroNameTag>> {
+        let mut chain = Vec::new();
+        let mut finished = BTreeSet::new();
+        self.follow_sequence(tag, fuzzy_macro_name_match, &mut chain, &mut finished)
+            .then_some(chain)
+    }
+    /// Depth-first walk for `find_sequence_cycle`, leaving just the cycle in `chain` if one's found.
+    fn follow_sequence(
+        &self,
+        tag: &MacroNameTag,
+        fuzzy_macro_name_match: bool,
+        chain: &mut Vec<MacroNameTag>,
+        finished: &mut BTreeSet<MacroNameTag>,
+    ) -> bool {
+        if let Some(start) = chain.iter().position(|seen| seen == tag) {
+            chain.drain(..start);
+            chain.push(tag.clone());
+            return true;
+        }
+        if finished.contains(tag) {
+            return false;
+        }
+        chain.push(tag.clone());
+
+        let steps = self.all.get(tag).map(|content| content.steps.as_slice());
+        for macro_ref in steps
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|s| s.macro_ref.as_ref())
+        {
+            if let Some(next) = self.get_by_string(macro_ref, fuzzy_macro_name_match)
+                && self.follow_sequence(&next, fuzzy_macro_name_match, chain, finished)
+            {
+                return true;
+            }
+        }
+
+        chain.pop();
+        finished.insert(tag.clone());
+        false
+    }
     // pub fn macro_from_key_combo<'a>(
     //     &'a self,
     //     key_combo: KeyCombination,
@@ -272,14 +510,21 @@ impl Macros {
     //         .expect("attempted removal of non-existant element");
     // }
 
-    pub fn load_from_folder<P: AsRef<Path>>(
+    /// Load macros from the config folder, and then from the project-local folder if given,
+    /// with project macros shadowing any of the same name and category.
+    ///
+    /// New macros are always written to the config folder.
+    pub fn load_from_folder<P: Into<Utf8PathBuf>>(
         folder: P,
+        project_folder: Option<Utf8PathBuf>,
     ) -> Result<(Self, Vec<MacrosDeserError>), MacrosLoadError> {
         let mut instance = Macros::empty();
         let mut deser_errors = Vec::new();
         fn visit_dir(
+            root: &Utf8Path,
             dir: &Path,
             new_macros: &mut BTreeMap<MacroNameTag, MacroContent>,
+            sources: &mut BTreeMap<MacroNameTag, Utf8PathBuf>,
             deser_errors: &mut Vec<MacrosDeserError>,
         ) -> Result<(), MacrosLoadError> {
             for entry in fs::read_dir(dir)? {
@@ -301,7 +546,9 @@ impl Macros {
 
                 if metadata.is_dir() {
                     // Recurse into subdirectory
-                    if let Err(e) = visit_dir(&entry.path(), new_macros, deser_errors) {
+                    if let Err(e) =
+                        visit_dir(root, &entry.path(), new_macros, sources, deser_errors)
+                    {
                         error!(
                             "Error traversing subdirectory {}: {e}",
                             entry.path().display()
@@ -348,21 +595,13 @@ impl Macros {
 
                 // If a macro has no category set, use either the stem of the file
                 // or the override if one was provided.
-                let fallback_category = deserialized
-                    .category_override
-                    .as_ref()
-                    .map(CompactString::as_str)
-                    .unwrap_or_else(|| {
-                        file_path
-                            .file_stem()
-                            .expect("expected to remove toml extension")
-                    });
+                let fallback_category = deserialized.fallback_category(&file_path, root);
 
                 deserialized
                     .macros
                     .iter_mut()
                     .filter(|m| m.category.is_none())
-                    .for_each(|m| m.category = Some(fallback_category.into()));
+                    .for_each(|m| m.category = Some(fallback_category.clone()));
 
                 for ser_macro in deserialized.macros {
                     let (mut tag, content) = ser_macro.into_tag_and_content();
@@ -376,22 +615,256 @@ impl Macros {
                     if let Some(_old) = new_macros.get(&tag) {
                         warn!("Duplicate found for macro {tag}!")
                     }
+                    _ = sources.insert(tag.clone(), file_path.clone());
                     _ = new_macros.insert(tag, content);
                 }
             }
             Ok(())
         }
-        let folder = folder.as_ref();
+        let folder = folder.into();
 
         if folder.exists() {
             let mut new_macros = BTreeMap::new();
-            visit_dir(folder, &mut new_macros, &mut deser_errors)?;
+            let mut sources = BTreeMap::new();
+            visit_dir(
+                &folder,
+                folder.as_std_path(),
+                &mut new_macros,
+                &mut sources,
+                &mut deser_errors,
+            )?;
             instance.all = new_macros;
+            instance.sources = sources;
         } else {
-            fs::create_dir_all(folder)?;
+            fs::create_dir_all(&folder)?;
         }
+        instance.folder = folder;
+
+        if let Some(project_folder) = project_folder {
+            let mut project_macros = BTreeMap::new();
+            let mut project_sources = BTreeMap::new();
+            visit_dir(
+                &project_folder,
+                project_folder.as_std_path(),
+                &mut project_macros,
+                &mut project_sources,
+                &mut deser_errors,
+            )?;
+            for (tag, content) in project_macros {
+                if instance.all.contains_key(&tag) {
+                    debug!("Project macro {tag} shadows the one in {}", instance.folder);
+                }
+                instance.all.insert(tag, content);
+            }
+            instance.sources.extend(project_sources);
+            instance.project_folder = Some(project_folder);
+        }
+
         Ok((instance, deser_errors))
     }
+    /// Write a macro to disk and update it in memory.
+    ///
+    /// If `original` is given, it's replaced within the file it was loaded from,
+    /// otherwise the macro is appended to a file named after its category.
+    ///
+    /// Note that the file is re-serialized in full, so any comments in it are lost.
+    pub fn save_macro(
+        &mut self,
+        original: Option<&MacroNameTag>,
+        tag: MacroNameTag,
+        content: MacroContent,
+    ) -> Result<(), MacroSaveError> {
+        if tag.name.trim().is_empty() {
+            return Err(MacroSaveError::EmptyName);
+        }
+        if original != Some(&tag) && self.all.contains_key(&tag) {
+            return Err(MacroSaveError::Duplicate);
+        }
+
+        let path = match original.and_then(|original| self.sources.get(original)) {
+            Some(path) => path.to_owned(),
+            None => self.new_macro_path(&tag),
+        };
+
+        rewrite_macro_file(&path, self.root_of(&path), |file, fallback_category| {
+            let serialized =
+                SerializedMacro::from_tag_and_content(&tag, &content, fallback_category);
+            match original.and_then(|original| file.position_of(original, fallback_category)) {
+                Some(index) => file.macros[index] = serialized,
+                None => file.macros.push(serialized),
+            }
+        })?;
+
+        if let Some(original) = original {
+            self.all.remove(original);
+            self.sources.remove(original);
+        }
+        self.sources.insert(tag.clone(), path);
+        self.all.insert(tag, content);
+        self.clamp_category_selection();
+
+        Ok(())
+    }
+    /// Read the bytes of a macro's `content_file`,
+    /// resolved relative to the file the macro was loaded from.
+    pub fn read_content_file(
+        &self,
+        tag: &MacroNameTag,
+        content_file: &Utf8Path,
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let macro_dir = self
+            .sources
+            .get(tag)
+            .and_then(|source| source.parent())
+            .unwrap_or(&self.folder);
+        // Joining an absolute path just replaces the directory.
+        fs::read(macro_dir.join(content_file))
+    }
+    /// How much longer until the macro can be sent again, if it's still cooling down.
+    pub fn cooldown_remaining(
+        &self,
+        tag: &MacroNameTag,
+        content: &MacroContent,
+    ) -> Option<Duration> {
+        let cooldown = Duration::from_millis(content.cooldown_ms?);
+        let last_sent = self.last_sent.get(tag)?;
+        let remaining = cooldown.saturating_sub(last_sent.elapsed());
+        (!remaining.is_zero()).then_some(remaining)
+    }
+    /// Start the macro's cooldown, if it has one.
+    pub fn mark_sent(&mut self, tag: &MacroNameTag, content: &MacroContent) {
+        if content.cooldown_ms.is_some() {
+            self.last_sent.insert(tag.clone(), Instant::now());
+        }
+    }
+    /// A copy of the content with its template functions expanded, `None` if it has none.
+    ///
+    /// Advances the macro's counter if `{{counter}}` was used.
+    pub fn expand_templates(
+        &mut self,
+        tag: &MacroNameTag,
+        content: &MacroContent,
+    ) -> Option<MacroContent> {
+        let counter = self.counters.get(tag).copied().unwrap_or_default();
+        let expanded =
+            templates::expand_templates(&content.content, counter, content.has_escaped_bytes)?;
+        if expanded.used_counter {
+            self.counters.insert(tag.clone(), counter.wrapping_add(1));
+        }
+        Some(MacroContent {
+            content: expanded.content.into(),
+            ..content.clone()
+        })
+    }
+    /// Remove a macro from the file it was loaded from, and from memory.
+    pub fn delete_macro(&mut self, tag: &MacroNameTag) -> Result<(), MacroSaveError> {
+        let path = self.sources.get(tag).ok_or(MacroNotFound)?;
+
+        rewrite_macro_file(path, self.root_of(path), |file, fallback_category| {
+            if let Some(index) = file.position_of(tag, fallback_category) {
+                file.macros.remove(index);
+            }
+        })?;
+
+        self.all.remove(tag);
+        self.sources.remove(tag);
+        self.clamp_category_selection();
+
+        Ok(())
+    }
+    /// The macros folder a file is in, so its fallback category stays the same when rewritten.
+    fn root_of(&self, path: &Utf8Path) -> &Utf8Path {
+        match &self.project_folder {
+            Some(project_folder) if path.starts_with(project_folder) => project_folder,
+            _ => &self.folder,
+        }
+    }
+    /// Where to write a new macro, with parent categories as subfolders.
+    fn new_macro_path(&self, tag: &MacroNameTag) -> Utf8PathBuf {
+        let mut path = self.folder.clone();
+        let category = tag.category.as_deref().unwrap_or(UNCATEGORIZED_FILE_STEM);
+
+        for part in category
+            .split(CATEGORY_SEPARATOR)
+            .filter(|part| !part.trim().is_empty())
+        {
+            let part: String = part
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            path.push(part);
+        }
+        path.set_extension("toml");
+        path
+    }
+    /// Fall back to showing all macros if the selected category no longer exists.
+    fn clamp_category_selection(&mut self) {
+        let category_count = 3 + self.categories().count();
+        if self.categories_selector.current_index >= category_count {
+            self.categories_selector.select(2);
+        }
+    }
+}
+
+/// The project-local macros folder in the current working directory, if there is one.
+pub fn project_macros_folder() -> Option<Utf8PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    let folder = Utf8PathBuf::from_path_buf(cwd)
+        .ok()?
+        .join(PROJECT_MACROS_DIR_PATH);
+    folder.is_dir().then_some(folder)
+}
+
+/// The category itself, followed by each of its parents from the deepest up.
+///
+/// (Example: `a/b/c` -> `a/b/c`, `a/b`, `a`)
+fn category_with_parents(category: &str) -> impl Iterator<Item = &str> {
+    std::iter::once(category).chain(
+        category
+            .rmatch_indices(CATEGORY_SEPARATOR)
+            .map(|(index, _)| &category[..index]),
+    )
+}
+
+/// If the category is the given one, or a child of it.
+fn is_in_category(category: &str, parent: &str) -> bool {
+    category
+        .strip_prefix(parent)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(CATEGORY_SEPARATOR))
+}
+
+/// Read a macro file (if it exists), apply the given edit, and write it back.
+///
+/// The edit is also given the category that macros without one in the file fall back to.
+fn rewrite_macro_file(
+    path: &Utf8Path,
+    root: &Utf8Path,
+    edit: impl FnOnce(&mut MacroFile, &str),
+) -> Result<(), MacroSaveError> {
+    let mut file: MacroFile = if path.is_file() {
+        let file_contents = fs::read_to_string(path)?;
+        toml::from_str(&file_contents).map_err(|source| MacrosDeserError {
+            path: path.to_owned(),
+            source,
+        })?
+    } else {
+        MacroFile::default()
+    };
+
+    let fallback_category = file.fallback_category(path, root);
+    edit(&mut file, &fallback_category);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, toml::to_string_pretty(&file)?)?;
+    Ok(())
 }
 
 impl LastIndex for Macros {
@@ -405,37 +878,149 @@ impl LastIndex for Macros {
     }
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
 struct MacroFile {
     #[serde(default)]
     #[serde(alias = "name")]
     #[serde(alias = "category")]
+    #[serde(rename(serialize = "category"))]
+    #[serde(skip_serializing_if = "Option::is_none")]
     category_override: Option<CompactString>,
     #[serde(rename = "macro")]
+    #[serde(default)]
     macros: Vec<SerializedMacro>,
 }
-#[derive(Debug, serde::Deserialize)]
+impl MacroFile {
+    /// The category used for macros that don't specify one,
+    /// either the override if one was provided, or the file's path in the macros folder
+    /// without its extension, with any subfolders as parent categories.
+    ///
+    /// (Example: `devices/openshock.toml` -> `devices/openshock`)
+    fn fallback_category(&self, file_path: &Utf8Path, root: &Utf8Path) -> CompactString {
+        if let Some(category) = &self.category_override {
+            return category.clone();
+        }
+        let stem = file_path
+            .file_stem()
+            .expect("expected to remove toml extension");
+        let Ok(relative) = file_path.strip_prefix(root) else {
+            return stem.into();
+        };
+
+        let parents = relative
+            .parent()
+            .into_iter()
+            .flat_map(Utf8Path::components)
+            .map(|component| component.as_str());
+
+        let mut category = CompactString::default();
+        for part in parents.chain([stem]) {
+            if !category.is_empty() {
+                category.push(CATEGORY_SEPARATOR);
+            }
+            category.push_str(part);
+        }
+        category
+    }
+    /// Find the index of the given macro, resolving categories the same way as when loading.
+    fn position_of(&self, tag: &MacroNameTag, fallback_category: &str) -> Option<usize> {
+        self.macros.iter().position(|m| {
+            let category = m.category.as_deref().unwrap_or(fallback_category);
+            let category = Some(category).filter(|c| !c.trim().is_empty());
+            m.name == tag.name && category == tag.category.as_deref()
+        })
+    }
+}
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
 struct SerializedMacro {
     name: CompactString,
+    #[serde(skip_serializing_if = "Option::is_none")]
     category: Option<CompactString>,
+    // Not required for macro sequences.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "CompactString::is_empty")]
     content: CompactString,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_file: Option<Utf8PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     line_ending: Option<CompactString>,
     #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
     sensitive: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    steps: Vec<MacroStep>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repeat: Option<MacroRepeat>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capture: Option<MacroCapture>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    confirm: bool,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cooldown_ms: Option<u64>,
 }
 impl SerializedMacro {
+    fn from_tag_and_content(
+        tag: &MacroNameTag,
+        content: &MacroContent,
+        fallback_category: &str,
+    ) -> Self {
+        let category = match tag.category.as_deref() {
+            Some(category) if category == fallback_category => None,
+            Some(category) => Some(category.into()),
+            // Explicitly empty, so the file's fallback category isn't applied.
+            None => Some(CompactString::default()),
+        };
+        Self {
+            name: tag.name.clone(),
+            category,
+            content: content.content.clone(),
+            content_file: content.content_file.clone(),
+            line_ending: content.escaped_line_ending.clone(),
+            sensitive: content.sensitive,
+            steps: content.steps.clone(),
+            repeat: content.repeat,
+            capture: content.capture.clone(),
+            confirm: content.confirm,
+            cooldown_ms: content.cooldown_ms,
+        }
+    }
     fn into_tag_and_content(self) -> (MacroNameTag, MacroContent) {
         let SerializedMacro {
             name,
             category,
             content,
+            content_file,
             line_ending,
             sensitive,
+            steps,
+            repeat,
+            capture,
+            confirm,
+            cooldown_ms,
         } = self;
 
+        let tag = MacroNameTag { name, category };
+        if !steps.is_empty() && !content.is_empty() {
+            warn!("Macro {tag} has both steps and content, only steps will be sent!");
+        }
+        if content_file.is_some() && !content.is_empty() {
+            warn!("Macro {tag} has both content and a content_file, only the file will be sent!");
+        }
+
         (
-            MacroNameTag { name, category },
-            MacroContent::new_with_line_ending(&content, line_ending, sensitive),
+            tag,
+            MacroContent::new_with_line_ending(&content, line_ending, sensitive)
+                .with_steps(steps)
+                .with_content_file(content_file)
+                .with_repeat(repeat)
+                .with_capture(capture)
+                .with_confirm(confirm)
+                .with_cooldown(cooldown_ms),
         )
     }
 }
@@ -447,6 +1032,18 @@ pub struct MacroContent {
     // #[serde(skip_serializing_if = "Option::is_none")]
     pub escaped_line_ending: Option<CompactString>,
     pub sensitive: bool,
+    /// File of bytes to send verbatim instead of the content, as written in the macro's file.
+    pub content_file: Option<Utf8PathBuf>,
+    /// If not empty, this macro is a sequence of these steps rather than its own content.
+    pub steps: Vec<MacroStep>,
+    /// If set, the macro keeps being re-sent after the first time until stopped.
+    pub repeat: Option<MacroRepeat>,
+    /// If set, the device's response is captured after the macro is sent.
+    pub capture: Option<MacroCapture>,
+    /// If the user has to confirm before the macro is sent.
+    pub confirm: bool,
+    /// Minimum time between sends, to avoid accidentally double-firing.
+    pub cooldown_ms: Option<u64>,
 }
 
 impl MacroContent {
@@ -471,10 +1068,47 @@ impl MacroContent {
             content: value.as_ref().into(),
             escaped_line_ending,
             sensitive,
+            content_file: None,
+            steps: Vec::new(),
+            repeat: None,
+            capture: None,
+            confirm: false,
+            cooldown_ms: None,
         }
     }
+    pub fn with_steps(mut self, steps: Vec<MacroStep>) -> Self {
+        self.steps = steps;
+        self
+    }
+    pub fn with_content_file(mut self, content_file: Option<Utf8PathBuf>) -> Self {
+        self.content_file = content_file;
+        self
+    }
+    pub fn with_repeat(mut self, repeat: Option<MacroRepeat>) -> Self {
+        self.repeat = repeat;
+        self
+    }
+    pub fn with_capture(mut self, capture: Option<MacroCapture>) -> Self {
+        self.capture = capture;
+        self
+    }
+    pub fn with_confirm(mut self, confirm: bool) -> Self {
+        self.confirm = confirm;
+        self
+    }
+    pub fn with_cooldown(mut self, cooldown_ms: Option<u64>) -> Self {
+        self.cooldown_ms = cooldown_ms;
+        self
+    }
     pub fn is_empty(&self) -> bool {
-        self.content.is_empty()
+        self.content.is_empty() && self.steps.is_empty() && self.content_file.is_none()
+    }
+    /// If the macro sends raw bytes, either escaped in its content or from a file.
+    pub fn has_bytes(&self) -> bool {
+        self.has_escaped_bytes || self.content_file.is_some()
+    }
+    pub fn is_sequence(&self) -> bool {
+        !self.steps.is_empty()
     }
     pub fn unescape_bytes(&self) -> Vec<u8> {
         Vec::unescape_bytes(&self.content)
@@ -482,4 +1116,117 @@ impl MacroContent {
     pub fn as_str(&self) -> &str {
         &self.content
     }
+    /// Any `{{name}}` variables that need values before the macro can be sent.
+    pub fn variables(&self) -> Vec<MacroVariable> {
+        variables::find_variables(&self.content)
+    }
+    /// Returns a copy with each variable replaced by its given value.
+    ///
+    /// Values are substituted before bytes are unescaped, so they can contain escaped bytes too.
+    pub fn with_variables(&self, values: &BTreeMap<CompactString, CompactString>) -> Self {
+        let filled = variables::replace_placeholders(&self.content, |name, _| {
+            values.get(name).map(CompactString::to_string)
+        });
+        Self {
+            has_escaped_bytes: filled.has_escaped_bytes(),
+            content: filled.into(),
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(refs: &[&str]) -> MacroContent {
+        let steps = refs
+            .iter()
+            .map(|macro_ref| MacroStep {
+                macro_ref: Some((*macro_ref).into()),
+                content: None,
+                line_ending: None,
+                expect: None,
+                timeout_ms: None,
+                delay_ms: None,
+            })
+            .collect();
+        MacroContent::new_with_line_ending("", None, false).with_steps(steps)
+    }
+
+    fn tag(name: &str) -> MacroNameTag {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn reloading_keeps_state_of_remaining_macros() {
+        let mut previous = Macros::empty();
+        previous.counters.insert(tag("kept"), 3);
+        previous.counters.insert(tag("gone"), 5);
+        previous.last_sent.insert(tag("gone"), Instant::now());
+        previous
+            .captured_values
+            .insert("serial".into(), "1234".into());
+        previous.collapsed_categories.insert("boards".into());
+        previous.collapsed_categories.insert("removed".into());
+
+        let mut reloaded = Macros::empty();
+        reloaded.all.insert(tag("kept"), MacroContent::default());
+        reloaded
+            .all
+            .insert(tag("boards/esp|reset"), MacroContent::default());
+        reloaded.keep_state_from(previous);
+
+        assert_eq!(reloaded.counters.get(&tag("kept")), Some(&3));
+        assert!(!reloaded.counters.contains_key(&tag("gone")));
+        assert!(reloaded.last_sent.is_empty());
+        assert_eq!(reloaded.captured_values["serial"], "1234");
+        assert_eq!(
+            reloaded.collapsed_categories.iter().collect::<Vec<_>>(),
+            ["boards"]
+        );
+    }
+
+    #[test]
+    fn stem_categories_find_nested_macros() {
+        let mut macros = Macros::empty();
+        macros
+            .all
+            .insert(tag("devices/openshock|Restart"), MacroContent::default());
+        macros
+            .all
+            .insert(tag("openshock|Status"), MacroContent::default());
+
+        assert_eq!(
+            macros.get_by_string("openshock|Restart", false),
+            Some(tag("devices/openshock|Restart"))
+        );
+        assert_eq!(
+            macros.get_by_string("devices/openshock|Restart", false),
+            Some(tag("devices/openshock|Restart"))
+        );
+        assert_eq!(macros.get_by_string("shock|Restart", false), None);
+    }
+
+    #[test]
+    fn sequence_cycles_are_found() {
+        let mut macros = Macros::empty();
+        macros.all.insert(tag("a"), sequence(&["b", "c"]));
+        macros.all.insert(tag("b"), sequence(&["c"]));
+        macros.all.insert(tag("c"), MacroContent::default());
+        // Sending the same macro twice isn't a cycle.
+        assert_eq!(macros.find_sequence_cycle(&tag("a"), false), None);
+
+        macros.all.insert(tag("c"), sequence(&["missing", "b"]));
+        assert_eq!(
+            macros.find_sequence_cycle(&tag("a"), false),
+            Some(vec![tag("b"), tag("c"), tag("b")])
+        );
+
+        macros.all.insert(tag("self"), sequence(&["self"]));
+        assert_eq!(
+            macros.find_sequence_cycle(&tag("self"), false),
+            Some(vec![tag("self"), tag("self")])
+        );
+    }
 }
@@ -0,0 +1,180 @@
+use bstr::{ByteSlice, ByteVec};
+use ratatui::{
+    Frame,
+    layout::{Rect, Size},
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, Wrap},
+};
+
+use crate::{
+    settings::PortSettings,
+    tui::{centered_rect_size, theme::theme},
+};
+
+use super::{MacroContent, MacroNameTag, Macros, templates};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MacroPreviewError {
+    #[error("sequences send each step separately, preview the steps' macros instead")]
+    Sequence,
+    #[error("failed reading macro file")]
+    File(#[from] std::io::Error),
+}
+
+#[derive(Debug)]
+/// Exactly what a macro would send, worked out without sending anything.
+pub struct MacroPreview {
+    pub tag: MacroNameTag,
+    pub content: Vec<u8>,
+    pub line_ending: Vec<u8>,
+    /// Which setting the line ending came from, following the Macro -> TX -> RX inheritance.
+    pub line_ending_source: String,
+    /// Anything that may differ once the macro is actually sent.
+    pub notes: Vec<&'static str>,
+    /// Content is hidden, only its length is shown.
+    pub sensitive: bool,
+}
+
+impl Macros {
+    /// Work out the bytes and line ending the macro would send with the given port settings.
+    ///
+    /// Template functions are expanded with the macro's current counter, without advancing it.
+    pub fn preview(
+        &self,
+        tag: &MacroNameTag,
+        content: &MacroContent,
+        port_settings: &PortSettings,
+    ) -> Result<MacroPreview, MacroPreviewError> {
+        if content.is_sequence() {
+            return Err(MacroPreviewError::Sequence);
+        }
+
+        let mut notes = Vec::new();
+
+        let (bytes, line_ending, line_ending_source) = if let Some(content_file) =
+            &content.content_file
+        {
+            let bytes = self.read_content_file(tag, content_file)?;
+            // Same as when sending, files are verbatim unless the macro asks for a line ending.
+            let (line_ending, source) = match &content.escaped_line_ending {
+                Some(line_ending) => (Vec::unescape_bytes(line_ending), "Macro's line_ending"),
+                None => (Vec::new(), "None, files are sent as-is"),
+            };
+            (bytes, line_ending, source.to_owned())
+        } else {
+            if !content.variables().is_empty() {
+                notes.push("Variables are shown as-is, and asked for when sent.");
+            }
+            let counter = self.counters.get(tag).copied().unwrap_or_default();
+            let expanded =
+                templates::expand_templates(&content.content, counter, content.has_escaped_bytes);
+            if expanded.is_some() {
+                notes.push("Template functions are expanded again when sent.");
+            }
+            let escaped = expanded.map_or_else(|| content.content.to_string(), |e| e.content);
+
+            let (line_ending, source) = match &content.escaped_line_ending {
+                Some(line_ending) => (
+                    Vec::unescape_bytes(line_ending),
+                    "Macro's line_ending".to_owned(),
+                ),
+                None => (
+                    port_settings
+                        .macro_line_ending
+                        .as_bytes(&port_settings.rx_line_ending, &port_settings.tx_line_ending)
+                        .to_owned(),
+                    format!(
+                        "Port Settings ({})",
+                        port_settings
+                            .macro_line_ending
+                            .inheritance_chain(&port_settings.tx_line_ending)
+                    ),
+                ),
+            };
+            (Vec::unescape_bytes(escaped), line_ending, source)
+        };
+
+        Ok(MacroPreview {
+            tag: tag.clone(),
+            content: bytes,
+            line_ending,
+            line_ending_source,
+            notes,
+            sensitive: content.sensitive,
+        })
+    }
+}
+
+impl MacroPreview {
+    pub fn render(&self, frame: &mut Frame, screen: Rect, scroll: &mut usize) {
+        let gray = theme().dim_style();
+        let escaped = |bytes: &[u8]| format!("\"{}\"", bytes.escape_bytes());
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::raw("Content: ").bold(),
+                if self.sensitive {
+                    Span::raw("[SENSITIVE]").italic()
+                } else {
+                    Span::raw(escaped(&self.content))
+                },
+            ]),
+            Line::from(vec![
+                Span::raw("Line Ending: ").bold(),
+                Span::raw(escaped(&self.line_ending)),
+                Span::styled(format!(" from {}", self.line_ending_source), gray),
+            ]),
+            Line::from(vec![
+                Span::raw("Total: ").bold(),
+                Span::raw(format!(
+                    "{} bytes",
+                    self.content.len() + self.line_ending.len()
+                )),
+            ]),
+        ];
+        lines.extend(
+            self.notes
+                .iter()
+                .map(|note| Line::styled(*note, gray.italic())),
+        );
+
+        let width = screen.width.min(70);
+        // Rough estimate of how many rows the wrapped lines take up.
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let rows: usize = lines
+            .iter()
+            .map(|line| line.width().div_ceil(inner_width).max(1))
+            .sum();
+
+        let area = centered_rect_size(
+            Size {
+                width,
+                height: (rows as u16 + 2).min(20),
+            },
+            screen,
+        );
+        let visible_rows = area.height.saturating_sub(2) as usize;
+        *scroll = (*scroll).min(rows.saturating_sub(visible_rows));
+
+        let title = Line::raw(format!(" Preview: {} ", self.tag))
+            .centered()
+            .reset();
+        let hint = Line::raw(" Nothing was sent | Esc: Close ")
+            .centered()
+            .reset();
+
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(title)
+            .title_bottom(hint);
+
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .scroll((*scroll as u16, 0));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(paragraph, area);
+    }
+}
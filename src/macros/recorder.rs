@@ -0,0 +1,65 @@
+use bstr::ByteSlice;
+use compact_str::{CompactString, ToCompactString};
+
+use super::{MacroEditor, MacroStep};
+
+#[derive(Debug, Default)]
+/// Everything sent through the pseudo-shell while recording, to be saved as a new macro.
+pub struct MacroRecorder {
+    steps: Vec<MacroStep>,
+}
+
+impl MacroRecorder {
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+    /// Record typed text, `unescaped` being if escaped bytes in it were sent as bytes.
+    pub fn record_text(&mut self, text: &str, line_ending: &[u8], unescaped: bool) {
+        let content = if unescaped {
+            text.into()
+        } else {
+            // Otherwise the backslashes were sent as-is, and need to stay that way.
+            text.replace('\\', "\\\\").into()
+        };
+        self.push(content, line_ending);
+    }
+    pub fn record_bytes(&mut self, bytes: &[u8], line_ending: &[u8]) {
+        self.push(bytes.escape_bytes().to_compact_string(), line_ending);
+    }
+    fn push(&mut self, content: CompactString, line_ending: &[u8]) {
+        self.steps.push(MacroStep {
+            macro_ref: None,
+            content: Some(content),
+            line_ending: Some(line_ending.escape_bytes().to_compact_string()),
+            expect: None,
+            timeout_ms: None,
+            delay_ms: None,
+        });
+    }
+    /// Hands the recording over to the editor for naming and saving.
+    ///
+    /// A single entry becomes a plain macro, several become a sequence.
+    pub fn into_editor(mut self, category: Option<&str>) -> MacroEditor {
+        let mut editor = MacroEditor::new(category);
+
+        // An empty line ending would otherwise fall back to Port Settings' in the editor.
+        let single_with_line_ending = self.steps.len() == 1
+            && self.steps[0]
+                .line_ending
+                .as_deref()
+                .is_some_and(|line_ending| !line_ending.is_empty());
+
+        if single_with_line_ending {
+            let step = self.steps.remove(0);
+            editor.content = step.content.unwrap_or_default().as_str().into();
+            editor.line_ending = step.line_ending.unwrap_or_default().as_str().into();
+        } else {
+            editor.steps = self.steps;
+        }
+
+        editor
+    }
+}
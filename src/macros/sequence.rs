@@ -0,0 +1,134 @@
+use std::time::{Duration, Instant};
+
+use compact_str::CompactString;
+use crokey::KeyCombination;
+use regex::bytes::Regex;
+
+use super::{MacroContent, MacroNameTag};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize)]
+/// A single step of a macro sequence, sending either another macro or content of its own,
+/// optionally followed by a delay before the next step.
+///
+/// A step with only a delay just waits.
+pub struct MacroStep {
+    /// Another macro to send, in the same format used for keybinds (i.e. `category|name`).
+    #[serde(rename = "macro")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macro_ref: Option<CompactString>,
+    /// Content to send directly, escaped the same way as a macro's.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<CompactString>,
+    /// Line ending for this step's `content`, otherwise the sequence's own is used.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_ending: Option<CompactString>,
+    /// Regex to wait for in received data after sending this step, before continuing.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect: Option<CompactString>,
+    /// How long to wait for `expect` to match before giving up on the rest of the sequence.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// How long to wait after this step, in place of `action_chain_delay`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+}
+
+impl MacroStep {
+    pub const DEFAULT_EXPECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn expect_timeout(&self) -> Duration {
+        self.timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(Self::DEFAULT_EXPECT_TIMEOUT)
+    }
+}
+
+/// Most recent received bytes kept around for an expectation to match against,
+/// so matches can span multiple RX buffers.
+const EXPECT_WINDOW_LEN: usize = 4096;
+
+#[derive(Debug)]
+/// A macro sequence waiting for a pattern to show up in received data before continuing.
+pub struct MacroExpectation {
+    pub tag: MacroNameTag,
+    pub pattern: Regex,
+    pub deadline: Instant,
+    received: Vec<u8>,
+}
+
+impl MacroExpectation {
+    pub fn new(tag: MacroNameTag, pattern: Regex, timeout: Duration) -> Self {
+        Self {
+            tag,
+            pattern,
+            deadline: Instant::now() + timeout,
+            received: Vec::new(),
+        }
+    }
+    /// Add freshly received bytes, returning `true` if the pattern has now matched.
+    pub fn feed(&mut self, bytes: &[u8]) -> bool {
+        self.received.extend_from_slice(bytes);
+        if self.received.len() > EXPECT_WINDOW_LEN {
+            let excess = self.received.len() - EXPECT_WINDOW_LEN;
+            self.received.drain(..excess);
+        }
+        self.pattern.is_match(&self.received)
+    }
+    pub fn timed_out(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Deserialize, serde::Serialize,
+)]
+/// Re-send a macro every `interval_ms` until stopped, or until it's been sent `count` times.
+pub struct MacroRepeat {
+    pub interval_ms: u64,
+    /// Total times to send the macro, including the first. Repeats until stopped if not given.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+}
+
+#[derive(Debug, Clone)]
+/// A macro currently being re-sent on an interval.
+pub struct RepeatingMacro {
+    pub tag: MacroNameTag,
+    pub content: MacroContent,
+    pub key_combo: Option<KeyCombination>,
+    pub interval: Duration,
+    /// Sends left before stopping, `None` if repeating until stopped.
+    pub remaining: Option<u32>,
+}
+
+impl RepeatingMacro {
+    /// Returns `None` if the macro doesn't repeat, or was only meant to be sent once.
+    pub fn new(
+        tag: &MacroNameTag,
+        content: &MacroContent,
+        key_combo: Option<KeyCombination>,
+    ) -> Option<Self> {
+        let repeat = content.repeat?;
+        // The first send has already happened.
+        let remaining = match repeat.count {
+            Some(count) if count <= 1 => return None,
+            Some(count) => Some(count - 1),
+            None => None,
+        };
+        Some(Self {
+            tag: tag.clone(),
+            content: content.clone(),
+            key_combo,
+            // Anything faster would just flood the action queue and port.
+            interval: Duration::from_millis(repeat.interval_ms.max(10)),
+            remaining,
+        })
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::Write;
+
+use chrono::Local;
+
+use super::variables::replace_placeholder_contents;
+
+/// Separates a template function's name from its argument, like `{{random:0-255}}`.
+const ARGUMENT_SEPARATOR: char = ':';
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+#[derive(Debug, PartialEq, Eq)]
+/// A built-in function in macro content, expanded each time the macro is sent.
+enum TemplateFunction<'a> {
+    /// `{{now}}` or `{{now:%Y-%m-%d}}`, the local time in the given strftime format.
+    Now(&'a str),
+    /// `{{counter}}`, how many times the macro has been sent before.
+    Counter,
+    /// `{{random}}` or `{{random:1-100}}`, a random number in the inclusive range.
+    Random(u64, u64),
+}
+
+fn parse_template(inner: &str) -> Option<TemplateFunction<'_>> {
+    let (name, argument) = match inner.split_once(ARGUMENT_SEPARATOR) {
+        Some((name, argument)) => (name.trim(), Some(argument)),
+        None => (inner.trim(), None),
+    };
+
+    match (name, argument) {
+        ("now", format) => Some(TemplateFunction::Now(format.unwrap_or(DEFAULT_TIME_FORMAT))),
+        ("counter", None) => Some(TemplateFunction::Counter),
+        ("random", range) => {
+            let (min, max) = range.unwrap_or("0-255").split_once('-')?;
+            let min = min.trim().parse().ok()?;
+            let max = max.trim().parse().ok()?;
+            (min <= max).then_some(TemplateFunction::Random(min, max))
+        }
+        _ => None,
+    }
+}
+
+/// If the placeholder is a template function rather than a variable.
+pub(super) fn is_template(inner: &str) -> bool {
+    parse_template(inner).is_some()
+}
+
+/// Content with its template functions expanded, and if `{{counter}}` was used in it.
+pub(super) struct Expanded {
+    pub content: String,
+    pub used_counter: bool,
+}
+
+/// Expand each template function in the content, `None` if it has none.
+///
+/// In macros with escaped bytes, `{{counter}}` (wrapping around) and `{{random}}` ranges
+/// that fit in a byte are sent as a single byte. Wider random ranges are always sent as decimal text,
+/// so a template doesn't switch between the two depending on the value it landed on.
+/// Any other text has its backslashes escaped so it's sent as-is.
+pub(super) fn expand_templates(
+    content: &str,
+    counter: u64,
+    escaped_bytes: bool,
+) -> Option<Expanded> {
+    let mut found = false;
+    let mut used_counter = false;
+
+    let number = |value: u64, as_byte: bool| {
+        if as_byte {
+            format!("\\x{value:02X}")
+        } else {
+            value.to_string()
+        }
+    };
+
+    let expanded = replace_placeholder_contents(content, |inner| {
+        let value = match parse_template(inner)? {
+            TemplateFunction::Now(format) => {
+                let mut time = String::new();
+                // Formatting returns an error for invalid formats, rather than panicking like `to_string()` would.
+                write!(time, "{}", Local::now().format(format)).ok()?;
+                if escaped_bytes {
+                    time.replace('\\', "\\\\")
+                } else {
+                    time
+                }
+            }
+            TemplateFunction::Counter => {
+                used_counter = true;
+                // Wrapping around when sent as a byte.
+                let value = if escaped_bytes {
+                    counter % 256
+                } else {
+                    counter
+                };
+                number(value, escaped_bytes)
+            }
+            TemplateFunction::Random(min, max) => {
+                let as_byte = escaped_bytes && max <= u8::MAX as u64;
+                number(rand::random_range(min..=max), as_byte)
+            }
+        };
+        found = true;
+        Some(value)
+    });
+
+    found.then_some(Expanded {
+        content: expanded,
+        used_counter,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_parse() {
+        assert_eq!(parse_template("counter"), Some(TemplateFunction::Counter));
+        assert_eq!(
+            parse_template("random"),
+            Some(TemplateFunction::Random(0, 255))
+        );
+        assert_eq!(
+            parse_template("random: 5-10"),
+            Some(TemplateFunction::Random(5, 10))
+        );
+        assert_eq!(parse_template("random:10-5"), None);
+        assert_eq!(parse_template("now:%Y"), Some(TemplateFunction::Now("%Y")));
+        assert_eq!(parse_template("counter:5"), None);
+        assert_eq!(parse_template("name"), None);
+    }
+
+    #[test]
+    fn each_template_keeps_one_encoding() {
+        let expanded = expand_templates("{{counter}}", 300, true).unwrap();
+        assert_eq!(expanded.content, "\\x2C");
+        assert!(expanded.used_counter);
+
+        assert_eq!(
+            expand_templates("{{counter}}", 300, false).unwrap().content,
+            "300"
+        );
+        // A value that would fit in a byte still comes out as text if the range doesn't.
+        assert_eq!(
+            expand_templates("{{random:7-7}}", 0, true).unwrap().content,
+            "\\x07"
+        );
+        for _ in 0..32 {
+            let content = expand_templates("{{random:0-1000}}", 0, true)
+                .unwrap()
+                .content;
+            assert!(content.parse::<u64>().is_ok(), "{content}");
+        }
+        assert!(expand_templates("{{name}}", 0, true).is_none());
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use compact_str::CompactString;
+use crokey::KeyCombination;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect, Size},
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+};
+use tui_input::Input;
+
+use crate::tui::{centered_rect_size, theme::theme};
+
+use super::{MacroContent, MacroNameTag, templates::is_template};
+
+const PLACEHOLDER_OPEN: &str = "{{";
+const PLACEHOLDER_CLOSE: &str = "}}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A `{{name}}` or `{{name=default}}` placeholder in a macro's content,
+/// for the user to fill in when the macro is sent.
+pub struct MacroVariable {
+    pub name: CompactString,
+    pub default: CompactString,
+}
+
+/// Splits the inside of a placeholder into its name and default value.
+///
+/// Names may only contain alphanumerics, `_` and `-`, so stray braces in content are left alone.
+fn parse_placeholder(inner: &str) -> Option<(&str, &str)> {
+    let (name, default) = inner.split_once('=').unwrap_or((inner, ""));
+    let name = name.trim();
+
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'));
+
+    valid_name.then(|| (name, default.trim()))
+}
+
+/// Replace each placeholder with what `replacement` returns given its name and default,
+/// leaving it untouched if `None` is returned.
+pub(super) fn replace_placeholders(
+    content: &str,
+    mut replacement: impl FnMut(&str, &str) -> Option<String>,
+) -> String {
+    replace_placeholder_contents(content, |inner| {
+        parse_placeholder(inner).and_then(|(name, default)| replacement(name, default))
+    })
+}
+
+/// Replace each placeholder with what `replacement` returns given everything between its braces,
+/// leaving it untouched if `None` is returned.
+pub(super) fn replace_placeholder_contents(
+    content: &str,
+    mut replacement: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find(PLACEHOLDER_OPEN) {
+        let after_open = &rest[start + PLACEHOLDER_OPEN.len()..];
+        let Some(end) = after_open.find(PLACEHOLDER_CLOSE) else {
+            break;
+        };
+        let inner = &after_open[..end];
+
+        output.push_str(&rest[..start]);
+        match replacement(inner) {
+            Some(value) => output.push_str(&value),
+            None => {
+                output.push_str(PLACEHOLDER_OPEN);
+                output.push_str(inner);
+                output.push_str(PLACEHOLDER_CLOSE);
+            }
+        }
+
+        rest = &after_open[end + PLACEHOLDER_CLOSE.len()..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// All unique variables in the content, in order of first appearance.
+///
+/// If a variable appears more than once, the first given default is used.
+/// Template functions like `{{counter}}` aren't variables, and are left out.
+pub(super) fn find_variables(content: &str) -> Vec<MacroVariable> {
+    let mut found: Vec<MacroVariable> = Vec::new();
+    replace_placeholders(content, |name, default| {
+        if !is_template(name) && !found.iter().any(|v| v.name == name) {
+            found.push(MacroVariable {
+                name: name.into(),
+                default: default.into(),
+            });
+        }
+        None
+    });
+    found
+}
+
+#[derive(Debug)]
+/// Popup form asking the user for a macro's variable values before sending it.
+pub struct MacroVariablesForm {
+    pub tag: MacroNameTag,
+    /// Content to fill in, may be a step of a sequence rather than the named macro's own.
+    pub content: MacroContent,
+    /// Key combo that invoked the macro, if any, for the sent notification.
+    pub key_combo: Option<KeyCombination>,
+    pub selected: usize,
+    pub fields: Vec<(CompactString, Input)>,
+}
+
+impl MacroVariablesForm {
+    /// Pre-fills each field with the last value entered for a variable of the same name,
+    /// falling back to the variable's default.
+    pub fn new(
+        tag: MacroNameTag,
+        content: MacroContent,
+        key_combo: Option<KeyCombination>,
+        last_values: &BTreeMap<CompactString, CompactString>,
+    ) -> Self {
+        let fields = content
+            .variables()
+            .into_iter()
+            .map(|MacroVariable { name, default }| {
+                let value = last_values.get(&name).unwrap_or(&default);
+                let input = Input::from(value.as_str());
+                (name, input)
+            })
+            .collect();
+        Self {
+            tag,
+            content,
+            key_combo,
+            selected: 0,
+            fields,
+        }
+    }
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.fields.len().max(1);
+    }
+    pub fn select_prev(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(self.fields.len().saturating_sub(1));
+    }
+    pub fn selected_input(&mut self) -> Option<&mut Input> {
+        self.fields.get_mut(self.selected).map(|(_, input)| input)
+    }
+    pub fn values(&self) -> BTreeMap<CompactString, CompactString> {
+        self.fields
+            .iter()
+            .map(|(name, input)| (name.clone(), input.value().into()))
+            .collect()
+    }
+    pub fn render(&self, frame: &mut Frame, screen: Rect) {
+        let label_width = self
+            .fields
+            .iter()
+            .map(|(name, _)| name.len() + 2)
+            .max()
+            .unwrap_or_default()
+            .min(20) as u16;
+
+        let area = centered_rect_size(
+            Size {
+                width: screen.width.min(60),
+                height: self.fields.len() as u16 + 2,
+            },
+            screen,
+        );
+
+        let title = Line::raw(format!(" {} ", self.tag)).centered().reset();
+        let hint = Line::raw(" Tab: Next | Enter: Send | Esc: Cancel ")
+            .centered()
+            .reset();
+
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(title)
+            .title_bottom(hint);
+
+        let rows = Layout::vertical(self.fields.iter().map(|_| Constraint::Length(1)))
+            .split(block.inner(area));
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        for (index, ((name, input), row)) in self.fields.iter().zip(rows.iter()).enumerate() {
+            let [label_area, value_area] =
+                Layout::horizontal([Constraint::Length(label_width), Constraint::Fill(1)])
+                    .areas(*row);
+            let selected = index == self.selected;
+
+            let label = Span::raw(format!("{name}:"));
+            let label = if selected {
+                label.style(theme().selection_style())
+            } else {
+                label
+            };
+            frame.render_widget(label, label_area);
+
+            // So the cursor doesn't bleed off the edge
+            let width = value_area.width.max(1).saturating_sub(1) as usize;
+            let scroll = input.visual_scroll(width);
+            frame.render_widget(
+                Paragraph::new(input.value()).scroll((0, scroll as u16)),
+                value_area,
+            );
+
+            if selected {
+                let cursor_offset = input.visual_cursor().saturating_sub(scroll);
+                frame.set_cursor_position((value_area.x + cursor_offset as u16, value_area.y));
+            }
+        }
+    }
+}
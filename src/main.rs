@@ -1,4 +1,4 @@
-fn main() -> color_eyre::Result<()> {
+fn main() -> color_eyre::Result<std::process::ExitCode> {
     yap::run()
 }
 
@@ -1,32 +1,91 @@
 use std::{
     borrow::Cow,
+    collections::VecDeque,
     time::{Duration, Instant},
 };
 
+use chrono::{DateTime, Local};
 use compact_str::{CompactString, format_compact};
 use crossbeam::channel::Sender;
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Clear},
+    widgets::{Block, BorderType, Borders, Clear},
 };
 use ratatui_macros::horizontal;
+use serde::{Deserialize, Serialize};
 use tracing::debug;
 
-use crate::app::{Event, Tick};
+use crate::{
+    app::{Event, Tick},
+    tui::theme::theme,
+};
+
+/// Oldest notifications are forgotten past this many.
+pub const HISTORY_LEN: usize = 500;
 
 /// Very simple helper for showing toast notifications on-screen.
 ///
-/// TODO: Scrollable notification history, with timestamps and level(?).
+/// Every notification is also kept in a history, to look back on ones that were missed.
 pub struct Notifications {
     pub inner: Option<ToastContent>,
     replaced_amount: (usize, Option<CompactString>),
+    history: VecDeque<PastNotification>,
+    /// Notifications less severe than this aren't shown, only kept in the history.
+    pub min_severity: Severity,
+    /// While enabled, no notifications are shown at all, only kept in the history.
+    pub do_not_disturb: bool,
     tx: Sender<Event>,
 }
 
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::VariantArray,
+)]
+#[strum(serialize_all = "title_case")]
+/// How important a notification is, from least to most.
+pub enum Severity {
+    #[default]
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+/// A notification as it was shown, for the notification history.
+pub struct PastNotification {
+    pub shown_at: DateTime<Local>,
+    pub text: String,
+    /// Color it was sent with, if not the theme's one for its severity.
+    pub color: Option<Color>,
+    pub severity: Severity,
+    /// If it was only added to the history, without being shown.
+    pub suppressed: bool,
+}
+
+impl PastNotification {
+    /// The notification and when it was shown, as copied to the clipboard.
+    pub fn to_clipboard_text(&self) -> String {
+        format!("[{}] {}", self.shown_at.format("%H:%M:%S"), self.text)
+    }
+}
+
 #[derive(Debug)]
 pub struct ToastContent {
     pub line: Line<'static>,
-    pub color: Color,
+    /// Color it was sent with, if not the theme's one for its severity.
+    pub color: Option<Color>,
+    pub severity: Severity,
     pub shown_at: Instant,
     pub replaced: bool,
 }
@@ -42,19 +101,52 @@ impl Notifications {
         Self {
             inner: None,
             replaced_amount: (0, None),
+            history: VecDeque::new(),
+            min_severity: Severity::default(),
+            do_not_disturb: false,
             tx,
         }
     }
-    pub fn notify(&mut self, line: Line<'static>, color: Color) {
-        self.notify_inner(line.centered(), color);
+    /// Notify in the theme's color for the severity.
+    pub fn notify(&mut self, line: Line<'static>, severity: Severity) {
+        self.notify_inner(line.centered(), None, severity);
+    }
+    /// Notify in the theme's color for the severity.
+    pub fn notify_str<S: AsRef<str>>(&mut self, text: S, severity: Severity) {
+        self.notify_str_with(text, None, severity);
+    }
+    /// Notify in a color of its own, like for things that are only just starting.
+    pub fn notify_str_colored<S: AsRef<str>>(&mut self, text: S, color: Color, severity: Severity) {
+        self.notify_str_with(text, Some(color), severity);
     }
-    pub fn notify_str<S: AsRef<str>>(&mut self, text: S, color: Color) {
+    fn notify_str_with<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        color: Option<Color>,
+        severity: Severity,
+    ) {
         let text: &str = text.as_ref();
         let line: Line = Span::raw(Cow::Owned(text.to_string())).into_centered_line();
-        self.notify_inner(line, color);
+        self.notify_inner(line, color, severity);
     }
-    fn notify_inner(&mut self, line: Line<'static>, color: Color) {
-        debug!("Notification: \"{line}\", Color: {color}");
+    fn notify_inner(&mut self, line: Line<'static>, color: Option<Color>, severity: Severity) {
+        let suppressed = self.do_not_disturb || severity < self.min_severity;
+        debug!(
+            "Notification: \"{line}\", Color: {color:?}, Severity: {severity}, Suppressed: {suppressed}"
+        );
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(PastNotification {
+            shown_at: Local::now(),
+            text: line.to_string(),
+            color,
+            severity,
+            suppressed,
+        });
+        if suppressed {
+            return;
+        }
         self.replaced_amount = if self.inner.is_none() {
             (0, None)
         } else {
@@ -65,11 +157,17 @@ impl Notifications {
         self.inner = Some(ToastContent {
             line,
             color,
+            severity,
             shown_at: Instant::now(),
             replaced: self.inner.is_some(),
         });
         self.tx.send(Tick::Notification.into()).unwrap();
     }
+    /// Decide which notifications are shown from now on, the rest only going into the history.
+    pub fn set_filter(&mut self, min_severity: Severity, do_not_disturb: bool) {
+        self.min_severity = min_severity;
+        self.do_not_disturb = do_not_disturb;
+    }
     pub fn is_some(&self) -> bool {
         self.inner.is_some()
     }
@@ -77,6 +175,10 @@ impl Notifications {
     pub fn is_none(&self) -> bool {
         self.inner.is_none()
     }
+    /// Every notification sent so far, oldest first, including suppressed ones.
+    pub fn history(&self) -> &VecDeque<PastNotification> {
+        &self.history
+    }
 }
 
 /// Max time a toast should be visible (including transitions!).
@@ -167,7 +269,11 @@ impl Widget for &Notifications {
             Clear::render(Clear, block_area, buf);
             let mut block = Block::new()
                 .borders(Borders::BOTTOM)
-                .border_style(Style::from(toast.color));
+                .border_style(theme().notification_style(toast.color, toast.severity));
+            // Errors get heavier borders, to stand out even in themes without much color.
+            if toast.severity == Severity::Error {
+                block = block.border_type(BorderType::Thick);
+            }
 
             if area.width.saturating_sub(center_area.width) >= 2 {
                 block = block.borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT);
@@ -183,7 +289,11 @@ impl Widget for &Notifications {
                     Line::raw(self.replaced_amount.1.as_ref().unwrap()).right_aligned();
                 replaced_amount_text.render(inner_area, buf);
             }
-            text.render(inner_area, buf);
+            if toast.severity >= Severity::Warning {
+                text.clone().bold().render(inner_area, buf);
+            } else {
+                text.render(inner_area, buf);
+            }
 
             block.render(block_area, buf);
         }
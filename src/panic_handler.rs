@@ -1,5 +1,5 @@
 use color_eyre::Result;
-use ratatui::crossterm::event::DisableMouseCapture;
+use ratatui::crossterm::event::{DisableBracketedPaste, DisableMouseCapture};
 use std::backtrace::Backtrace;
 
 pub fn initialize_panic_handler() -> Result<()> {
@@ -14,7 +14,11 @@ pub fn initialize_panic_handler() -> Result<()> {
     eyre_hook.install()?;
     std::panic::set_hook(Box::new(move |panic_info| {
         ratatui::restore();
-        _ = ratatui::crossterm::execute!(std::io::stdout(), DisableMouseCapture);
+        _ = ratatui::crossterm::execute!(
+            std::io::stdout(),
+            DisableMouseCapture,
+            DisableBracketedPaste
+        );
 
         let msg = format!("{}", panic_hook.panic_report(panic_info));
         let backtrace = Backtrace::force_capture();
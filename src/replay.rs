@@ -0,0 +1,406 @@
+//! Playing a previous session's JSON Lines capture or recording back into the buffer,
+//! as if the port were sending it again.
+
+use std::{
+    io::{BufRead, BufReader},
+    time::{Duration, Instant},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, Local};
+use fs_err as fs;
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+use crate::{
+    buffer::TrafficDirection,
+    session::{self, SessionError},
+    tui::theme::theme,
+};
+
+/// Playback speeds that can be stepped between.
+const SPEEDS: &[f64] = &[0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 64.0];
+const NORMAL_SPEED_INDEX: usize = 2;
+
+/// How far seeking forwards or backwards jumps.
+pub const SEEK_STEP: Duration = Duration::from_secs(10);
+
+/// How many cells wide the seek bar in the status line is.
+const SEEK_BAR_WIDTH: usize = 24;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayError {
+    #[error("failed reading capture: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("invalid record on line {line}: {reason}")]
+    InvalidRecord { line: usize, reason: String },
+    #[error("no data in capture to replay")]
+    Empty,
+    #[error(transparent)]
+    Session(#[from] SessionError),
+}
+
+#[derive(serde::Deserialize)]
+/// The parts of a JSON Lines log record needed to replay it.
+struct ReplayRecord {
+    timestamp: String,
+    monotonic_us: Option<u64>,
+    direction: TrafficDirection,
+    raw: Option<String>,
+}
+
+/// A record read from either kind of capture, before being placed in the replay's timeline.
+struct LoadedRecord {
+    direction: TrafficDirection,
+    timestamp: DateTime<Local>,
+    /// Only comparable with others from the same run of yap.
+    monotonic_us: Option<u64>,
+    bytes: Vec<u8>,
+}
+
+pub struct ReplayChunk {
+    /// How far into the capture these bytes arrived or were sent.
+    offset: Duration,
+    pub direction: TrafficDirection,
+    pub timestamp: DateTime<Local>,
+    pub bytes: Vec<u8>,
+}
+
+/// A loaded capture and how far through it playback is.
+///
+/// User input is replayed too, to be shown as it was sent without being sent again.
+pub struct Replay {
+    path: Utf8PathBuf,
+    chunks: Vec<ReplayChunk>,
+    /// Index of the next chunk yet to be played.
+    next: usize,
+    /// Position in the capture as of `resumed_at`, or where it's paused.
+    position: Duration,
+    /// When playback last started or resumed, `None` while paused.
+    resumed_at: Option<Instant>,
+    speed_index: usize,
+}
+
+impl Replay {
+    /// Read every chunk from a session recording, or from a JSON Lines capture,
+    /// decompressing it first if needed.
+    pub fn load(path: &Utf8Path) -> Result<Self, ReplayError> {
+        let records = if path.extension() == Some(session::EXTENSION) {
+            session::read_records(path)?
+                .into_iter()
+                .map(|record| LoadedRecord {
+                    direction: record.direction,
+                    timestamp: record.timestamp,
+                    monotonic_us: Some(record.monotonic_us),
+                    bytes: record.bytes,
+                })
+                .collect()
+        } else {
+            load_jsonl(path)?
+        };
+        if records.is_empty() {
+            return Err(ReplayError::Empty);
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            chunks: place_chunks(records),
+            next: 0,
+            position: Duration::ZERO,
+            resumed_at: Some(Instant::now()),
+            speed_index: NORMAL_SPEED_INDEX,
+        })
+    }
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+    fn speed(&self) -> f64 {
+        SPEEDS[self.speed_index]
+    }
+    fn length(&self) -> Duration {
+        self.chunks.last().map(|c| c.offset).unwrap_or_default()
+    }
+    pub fn is_paused(&self) -> bool {
+        self.resumed_at.is_none()
+    }
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.chunks.len()
+    }
+    /// How far into the capture playback currently is.
+    pub fn position(&self) -> Duration {
+        let Some(resumed_at) = self.resumed_at else {
+            return self.position;
+        };
+        let played = resumed_at.elapsed().mul_f64(self.speed());
+        (self.position + played).min(self.length())
+    }
+    /// Take every chunk that's due to be played by now, in order, with their original timestamps.
+    pub fn take_due(&mut self) -> &[ReplayChunk] {
+        let position = self.position();
+        let start = self.next;
+        self.next += self.chunks[start..]
+            .iter()
+            .take_while(|chunk| chunk.offset <= position)
+            .count();
+        &self.chunks[start..self.next]
+    }
+    /// Real time until the next chunk is due, or `None` if paused or there's none left.
+    pub fn until_next(&self) -> Option<Duration> {
+        if self.is_paused() {
+            return None;
+        }
+        let next = self.chunks.get(self.next)?;
+        let remaining = next.offset.saturating_sub(self.position());
+        Some(remaining.div_f64(self.speed()))
+    }
+    /// Pause or resume playback, returning if it's now paused.
+    pub fn toggle_pause(&mut self) -> bool {
+        if self.is_paused() {
+            self.resumed_at = Some(Instant::now());
+        } else {
+            self.position = self.position();
+            self.resumed_at = None;
+        }
+        self.is_paused()
+    }
+    /// Continue from the new position, keeping whether it was paused.
+    fn set_position(&mut self, position: Duration) {
+        self.position = position.min(self.length());
+        if self.resumed_at.is_some() {
+            self.resumed_at = Some(Instant::now());
+        }
+    }
+    /// Jump forwards or backwards through the capture.
+    ///
+    /// Returns `true` if the buffer needs clearing to be played back up to the new position,
+    /// since it'd already be showing chunks from after it.
+    pub fn seek(&mut self, forwards: bool, by: Duration) -> bool {
+        let current = self.position();
+        if forwards {
+            self.set_position(current + by);
+            false
+        } else {
+            self.set_position(current.saturating_sub(by));
+            self.next = 0;
+            true
+        }
+    }
+    /// Step to the next faster or slower speed, returning the new speed.
+    pub fn change_speed(&mut self, faster: bool) -> f64 {
+        // Keep how far along it's gotten at the old speed.
+        self.set_position(self.position());
+        self.speed_index = if faster {
+            (self.speed_index + 1).min(SPEEDS.len() - 1)
+        } else {
+            self.speed_index.saturating_sub(1)
+        };
+        self.speed()
+    }
+    /// Play/pause indicator, seek bar, and speed, to show in place of the port's status.
+    pub fn status_line(&self) -> Line<'static> {
+        let position = self.position();
+        let length = self.length();
+
+        let progress = if length.is_zero() {
+            1.0
+        } else {
+            position.as_secs_f64() / length.as_secs_f64()
+        };
+        let filled = ((progress * SEEK_BAR_WIDTH as f64).round() as usize).min(SEEK_BAR_WIDTH);
+
+        let theme = theme();
+        let state = if self.is_finished() {
+            Span::styled("Replay Done ", theme.dim_style())
+        } else if self.is_paused() {
+            Span::styled("Replay Paused ", Style::new().fg(theme.notify_warning))
+        } else {
+            Span::styled("Replaying ", Style::new().fg(theme.notify_success))
+        };
+
+        Line::from(vec![
+            state,
+            Span::raw(format!("{} ", format_duration(position))),
+            Span::raw("━".repeat(filled)),
+            Span::styled("─".repeat(SEEK_BAR_WIDTH - filled), theme.dim_style()),
+            Span::raw(format!(" {} ({}x)", format_duration(length), self.speed())),
+        ])
+    }
+}
+
+/// Give each record its offset into the capture.
+///
+/// Monotonic times are preferred when they've been recorded, since they're unaffected by the
+/// clock being adjusted mid-capture, but they restart with each run of yap. Where they go
+/// backwards, the wall clock gap since the last chunk is used instead, and the next run's
+/// monotonic times are counted on from there. Records without one (like user input in JSON Lines
+/// captures) only go by the wall clock gap.
+fn place_chunks(records: Vec<LoadedRecord>) -> Vec<ReplayChunk> {
+    let mut chunks: Vec<ReplayChunk> = Vec::with_capacity(records.len());
+    // The current run's first monotonic time, and its offset into the capture.
+    let mut run_start: Option<(u64, Duration)> = None;
+    let mut previous_us: Option<u64> = None;
+
+    for record in records {
+        let since_last = chunks.last().map_or(Duration::ZERO, |last| {
+            last.offset
+                + (record.timestamp - last.timestamp)
+                    .to_std()
+                    .unwrap_or_default()
+        });
+        let offset = match (run_start, previous_us, record.monotonic_us) {
+            (Some((start_us, start_offset)), Some(previous), Some(us)) if us >= previous => {
+                start_offset + Duration::from_micros(us - start_us)
+            }
+            (_, _, Some(us)) => {
+                run_start = Some((us, since_last));
+                since_last
+            }
+            (_, _, None) => since_last,
+        };
+        if record.monotonic_us.is_some() {
+            previous_us = record.monotonic_us;
+        }
+        // Clocks can go backwards too, but chunks are still played in order.
+        let offset = chunks.last().map_or(offset, |last| offset.max(last.offset));
+        chunks.push(ReplayChunk {
+            offset,
+            direction: record.direction,
+            timestamp: record.timestamp,
+            bytes: record.bytes,
+        });
+    }
+    chunks
+}
+
+/// `mm:ss`, or `h:mm:ss` for longer captures.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+fn open_capture(path: &Utf8Path) -> Result<Box<dyn BufRead>, std::io::Error> {
+    let file = fs::File::open(path)?;
+    Ok(match path.extension() {
+        // Appended-to captures hold several gzip members, one after another.
+        #[cfg(feature = "log-compression")]
+        Some("gz") => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file))),
+        #[cfg(feature = "log-compression")]
+        Some("zst") => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+        _ => Box::new(BufReader::new(file)),
+    })
+}
+
+/// Every chunk in a JSON Lines capture, with when it arrived or was sent.
+fn load_jsonl(path: &Utf8Path) -> Result<Vec<LoadedRecord>, ReplayError> {
+    use base64::Engine;
+
+    let reader = open_capture(path)?;
+
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |reason: String| ReplayError::InvalidRecord {
+            line: index + 1,
+            reason,
+        };
+
+        let record: ReplayRecord =
+            serde_json::from_str(&line).map_err(|e| invalid(e.to_string()))?;
+        // Decoded defmt frames have no raw bytes, they're in an earlier record already.
+        let Some(raw) = record.raw else {
+            continue;
+        };
+        let timestamp = DateTime::parse_from_rfc3339(&record.timestamp)
+            .map_err(|e| invalid(e.to_string()))?
+            .with_timezone(&Local);
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(raw)
+            .map_err(|e| invalid(e.to_string()))?;
+
+        records.push(LoadedRecord {
+            direction: record.direction,
+            timestamp,
+            monotonic_us: record.monotonic_us,
+            bytes,
+        });
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn record(secs: u32, monotonic_us: Option<u64>) -> LoadedRecord {
+        LoadedRecord {
+            direction: TrafficDirection::Rx,
+            timestamp: Local.with_ymd_and_hms(2024, 5, 17, 12, 0, secs).unwrap(),
+            monotonic_us,
+            bytes: Vec::new(),
+        }
+    }
+
+    fn offsets(records: Vec<LoadedRecord>) -> Vec<u64> {
+        place_chunks(records)
+            .iter()
+            .map(|chunk| chunk.offset.as_millis() as u64)
+            .collect()
+    }
+
+    #[test]
+    fn monotonic_times_preferred_over_wall_clock() {
+        // The wall clock jumping back a few seconds doesn't affect the offsets.
+        let records = vec![
+            record(10, Some(5_000_000)),
+            record(11, Some(5_500_000)),
+            record(8, Some(7_000_000)),
+        ];
+        assert_eq!(offsets(records), [0, 500, 2000]);
+    }
+
+    #[test]
+    fn later_runs_continue_from_the_wall_clock_gap() {
+        // yap restarted between the second and third records, resetting its monotonic clock.
+        let records = vec![
+            record(0, Some(9_000_000)),
+            record(1, Some(10_000_000)),
+            record(31, Some(200_000)),
+            record(32, Some(700_000)),
+        ];
+        assert_eq!(offsets(records), [0, 1000, 31_000, 31_500]);
+    }
+
+    #[test]
+    fn wall_clock_used_without_monotonic_times() {
+        let records = vec![
+            record(0, None),
+            record(2, None),
+            record(1, None),
+            record(5, None),
+        ];
+        assert_eq!(offsets(records), [0, 2000, 2000, 6000]);
+    }
+
+    #[test]
+    fn records_without_monotonic_times_stay_in_the_run() {
+        // Like user input in a JSON Lines capture, between port data that has monotonic times.
+        let records = vec![
+            record(0, Some(1_000_000)),
+            record(3, None),
+            record(2, Some(3_500_000)),
+        ];
+        assert_eq!(offsets(records), [0, 3000, 3000]);
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    rc::Rc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use compact_str::CompactString;
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender, bounded};
+use fs_err as fs;
+use regex::bytes::Regex;
+use rhai::{Engine, EvalAltResult, INT};
+use tracing::{debug, error, info, warn};
+
+use crate::app::Event;
+
+pub const SCRIPTS_DIR_PATH: &str = "scripts";
+/// Keybind actions starting with this run the named script.
+pub const SCRIPT_ACTION_PREFIX: &str = "script:";
+const SCRIPT_EXTENSION: &str = "rhai";
+
+const DEFAULT_EXPECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often blocking script calls check if they've been told to stop.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Most recent received bytes kept around for `expect` to match against,
+/// so matches can span multiple RX buffers.
+const EXPECT_WINDOW_LEN: usize = 4096;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+#[derive(Debug)]
+pub enum ScriptEvent {
+    /// Text to send to the port, and if the macro line ending should follow it.
+    Send { text: String, line_ending: bool },
+    /// Text to show the user as a notification.
+    Notify(String),
+    /// Script stopped running, with the error that stopped it (if any).
+    Finished {
+        name: CompactString,
+        error: Option<String>,
+    },
+}
+
+impl From<ScriptEvent> for Event {
+    fn from(value: ScriptEvent) -> Self {
+        Self::Script(value)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("failed reading scripts folder")]
+pub struct ScriptsLoadError(#[from] std::io::Error);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptRunError {
+    #[error("script not found")]
+    NotFound,
+    #[error("script \"{0}\" is already running")]
+    AlreadyRunning(CompactString),
+    #[error("failed reading script file")]
+    File(#[from] std::io::Error),
+}
+
+/// Most recent received bytes, shared with the script's thread for `expect` to match against.
+type RxWindow = Arc<Mutex<Vec<u8>>>;
+
+#[derive(Debug)]
+struct RunningScript {
+    name: CompactString,
+    cancel: Arc<AtomicBool>,
+    rx_window: RxWindow,
+    /// Wakes a waiting `expect` when more bytes land in `rx_window`.
+    rx_notify: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+#[derive(Debug, Default)]
+pub struct Scripts {
+    /// Script names (file stems) and their paths.
+    pub all: BTreeMap<CompactString, Utf8PathBuf>,
+    running: Option<RunningScript>,
+}
+
+impl Scripts {
+    /// Finds all `.rhai` files in the folder, it not existing just means there are no scripts.
+    pub fn load_from_folder<P: AsRef<Utf8Path>>(folder: P) -> Result<Self, ScriptsLoadError> {
+        let folder = folder.as_ref();
+        let mut all = BTreeMap::new();
+
+        if !folder.exists() {
+            return Ok(Self::default());
+        }
+
+        for entry in fs::read_dir(folder)? {
+            let path = entry?.path();
+            let Ok(path) = Utf8PathBuf::from_path_buf(path) else {
+                warn!("Script path is not valid UTF-8! Skipping...");
+                continue;
+            };
+            if !path.is_file() || path.extension() != Some(SCRIPT_EXTENSION) {
+                continue;
+            }
+            let Some(stem) = path.file_stem() else {
+                continue;
+            };
+            all.insert(stem.into(), path);
+        }
+
+        Ok(Self { all, running: None })
+    }
+    pub fn get(&self, name: &str) -> Option<&CompactString> {
+        self.all.keys().find(|k| k.eq_ignore_ascii_case(name))
+    }
+    pub fn running(&self) -> Option<&str> {
+        self.running.as_ref().map(|r| r.name.as_str())
+    }
+    /// Begin running the script on its own thread, reporting back through `event_tx`.
+    pub fn run(&mut self, name: &str, event_tx: Sender<Event>) -> Result<(), ScriptRunError> {
+        if let Some(running) = &self.running {
+            return Err(ScriptRunError::AlreadyRunning(running.name.clone()));
+        }
+        let (name, path) = self
+            .all
+            .get_key_value(name)
+            .ok_or(ScriptRunError::NotFound)?;
+        let source = fs::read_to_string(path)?;
+
+        let name = name.clone();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let rx_window = RxWindow::default();
+        let (rx_notify, rx_notified) = bounded(1);
+
+        let thread = {
+            let name = name.clone();
+            let cancel = cancel.clone();
+            let expect_state = ExpectState {
+                window: rx_window.clone(),
+                notified: rx_notified,
+            };
+            std::thread::spawn(move || run_script(name, source, expect_state, cancel, event_tx))
+        };
+
+        self.running = Some(RunningScript {
+            name,
+            cancel,
+            rx_window,
+            rx_notify,
+            thread,
+        });
+        Ok(())
+    }
+    /// Ask the running script to stop, returning its name if one was running.
+    pub fn stop(&self) -> Option<&str> {
+        let running = self.running.as_ref()?;
+        running.cancel.store(true, Ordering::Relaxed);
+        Some(&running.name)
+    }
+    /// Pass received bytes along for the running script's `expect` calls,
+    /// only keeping the last `EXPECT_WINDOW_LEN` bytes around.
+    pub fn feed_rx(&self, bytes: &[u8]) {
+        let Some(running) = &self.running else {
+            return;
+        };
+        {
+            let mut window = running.rx_window.lock().unwrap();
+            window.extend_from_slice(bytes);
+            if window.len() > EXPECT_WINDOW_LEN {
+                let excess = window.len() - EXPECT_WINDOW_LEN;
+                window.drain(..excess);
+            }
+        }
+        // Already being woken up if full.
+        _ = running.rx_notify.try_send(());
+    }
+    /// Clean up after the script's thread has reported it's finished.
+    pub fn finished(&mut self) {
+        if let Some(running) = self.running.take()
+            && running.thread.join().is_err()
+        {
+            error!("Script thread for \"{}\" panicked!", running.name);
+        }
+    }
+}
+
+/// Received bytes waiting to be matched by `expect`.
+struct ExpectState {
+    window: RxWindow,
+    notified: Receiver<()>,
+}
+
+impl ExpectState {
+    /// Forget everything received so far, so only responses to what's sent next are matched.
+    fn clear(&mut self) {
+        self.window.lock().unwrap().clear();
+    }
+    fn expect(
+        &mut self,
+        pattern: &str,
+        timeout: Duration,
+        cancel: &AtomicBool,
+    ) -> ScriptResult<String> {
+        let regex = Regex::new(pattern).map_err(|e| format!("invalid expect pattern: {e}"))?;
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            {
+                let mut window = self.window.lock().unwrap();
+                if let Some(found) = regex.find(&window) {
+                    let matched = String::from_utf8_lossy(found.as_bytes()).into_owned();
+                    // Anything after the match is kept for the next expect.
+                    let end = found.end();
+                    window.drain(..end);
+                    return Ok(matched);
+                }
+            }
+
+            if cancel.load(Ordering::Relaxed) {
+                return Err("script stopped".into());
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!("timed out waiting for \"{pattern}\"").into());
+            }
+
+            match self
+                .notified
+                .recv_timeout(remaining.min(CANCEL_POLL_INTERVAL))
+            {
+                Ok(()) | Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => return Err("app closed".into()),
+            }
+        }
+    }
+}
+
+fn sleep_unless_cancelled(duration: Duration, cancel: &AtomicBool) -> ScriptResult<()> {
+    let deadline = Instant::now() + duration;
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("script stopped".into());
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(());
+        }
+        std::thread::sleep(remaining.min(CANCEL_POLL_INTERVAL));
+    }
+}
+
+fn millis(ms: INT) -> Duration {
+    Duration::from_millis(ms.max(0) as u64)
+}
+
+fn run_script(
+    name: CompactString,
+    source: String,
+    expect_state: ExpectState,
+    cancel: Arc<AtomicBool>,
+    event_tx: Sender<Event>,
+) {
+    debug!("Running script \"{name}\"");
+    let mut engine = Engine::new();
+
+    let expect_state = Rc::new(RefCell::new(expect_state));
+
+    {
+        let cancel = cancel.clone();
+        engine.on_progress(move |_| cancel.load(Ordering::Relaxed).then(|| "stopped".into()));
+    }
+    {
+        let name = name.clone();
+        engine.on_print(move |text| info!("[{name}] {text}"));
+    }
+    {
+        let name = name.clone();
+        engine.register_fn("log", move |text: &str| info!("[{name}] {text}"));
+    }
+    {
+        let event_tx = event_tx.clone();
+        engine.register_fn("notify", move |text: &str| {
+            _ = event_tx.send(ScriptEvent::Notify(text.into()).into());
+        });
+    }
+    for (fn_name, line_ending) in [("send", true), ("send_raw", false)] {
+        let event_tx = event_tx.clone();
+        let expect_state = expect_state.clone();
+        engine.register_fn(fn_name, move |text: &str| {
+            expect_state.borrow_mut().clear();
+            _ = event_tx.send(
+                ScriptEvent::Send {
+                    text: text.into(),
+                    line_ending,
+                }
+                .into(),
+            );
+        });
+    }
+    {
+        let cancel = cancel.clone();
+        engine.register_fn("sleep", move |ms: INT| {
+            sleep_unless_cancelled(millis(ms), &cancel)
+        });
+    }
+    {
+        let cancel = cancel.clone();
+        let expect_state = expect_state.clone();
+        engine.register_fn("expect", move |pattern: &str| {
+            expect_state
+                .borrow_mut()
+                .expect(pattern, DEFAULT_EXPECT_TIMEOUT, &cancel)
+        });
+    }
+    {
+        let cancel = cancel.clone();
+        let expect_state = expect_state.clone();
+        engine.register_fn("expect", move |pattern: &str, timeout_ms: INT| {
+            expect_state
+                .borrow_mut()
+                .expect(pattern, millis(timeout_ms), &cancel)
+        });
+    }
+
+    let error = match engine.run(&source) {
+        Ok(()) => None,
+        Err(_) if cancel.load(Ordering::Relaxed) => None,
+        Err(e) => {
+            error!("Script \"{name}\" failed: {e}");
+            Some(e.to_string())
+        }
+    };
+
+    debug!("Script \"{name}\" finished");
+    _ = event_tx.send(ScriptEvent::Finished { name, error }.into());
+}
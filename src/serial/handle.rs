@@ -26,6 +26,8 @@ pub enum SerialWorkerCommand {
         port: SerialPortInfo,
         baud: Option<u32>,
         settings: PortSettings,
+        /// Connect even if the port lock registry says another yap instance has the port.
+        ignore_port_lock: bool,
         result_tx: Sender<Result<(), super::worker::WorkerError>>,
     },
     PortCommand(PortCommand),
@@ -66,7 +68,7 @@ impl SerialHandle {
     /// Try to build a SerialWorker and Handle, and get the current list of available serial ports.
     pub fn build(
         event_tx: Sender<Event>,
-        buffer_tx: Sender<(DateTime<Local>, Vec<u8>)>,
+        buffer_tx: Sender<(DateTime<Local>, u64, Vec<u8>)>,
         port_settings: PortSettings,
         ignored_devices: Ignored,
         scan_timeout: Duration,
@@ -109,6 +111,30 @@ impl SerialHandle {
         settings: PortSettings,
         baud: Option<u32>,
         timeout: Duration,
+    ) -> Result<(), BlockingCommandError> {
+        self.send_connect_blocking(port, settings, baud, timeout, false)
+    }
+    /// Connect without checking if another yap instance has the port first.
+    ///
+    /// This only gets past the lock registry, the OS will still refuse
+    /// if whatever has the port opened it exclusively (which yap itself does).
+    #[cfg(unix)]
+    pub fn force_connect_blocking(
+        &self,
+        port: SerialPortInfo,
+        settings: PortSettings,
+        baud: Option<u32>,
+        timeout: Duration,
+    ) -> Result<(), BlockingCommandError> {
+        self.send_connect_blocking(port, settings, baud, timeout, true)
+    }
+    fn send_connect_blocking(
+        &self,
+        port: SerialPortInfo,
+        settings: PortSettings,
+        baud: Option<u32>,
+        timeout: Duration,
+        ignore_port_lock: bool,
     ) -> Result<(), BlockingCommandError> {
         let (oneshot_tx, oneshot_rx) = bounded(0);
 
@@ -117,6 +143,7 @@ impl SerialHandle {
                 port,
                 baud,
                 settings,
+                ignore_port_lock,
                 result_tx: oneshot_tx,
             })
             .map_err(|_| SerialWorkerMissing)?;
@@ -0,0 +1,101 @@
+use std::io::{Read, Seek, Write};
+
+use fs_err as fs;
+use tracing::debug;
+
+use crate::config_adjacent_path;
+
+/// Folder (beside the config) holding a lock file for each port a yap instance has open.
+pub const PORT_LOCKS_DIR_PATH: &str = "port_locks/";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Who's holding a port, as far as can be told.
+pub struct PortHolder {
+    /// Only known if the port is held by another yap instance.
+    pub pid: Option<u32>,
+}
+
+impl std::fmt::Display for PortHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "another yap instance (PID {pid})"),
+            None => write!(f, "another program"),
+        }
+    }
+}
+
+/// Held for as long as yap has the port open, letting other instances know it's taken.
+///
+/// The lock is on the file itself rather than its existence,
+/// so it's let go of by the OS even if yap is killed, instead of being left stale.
+pub struct PortLock {
+    _file: fs::File,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PortLockError {
+    #[error("port is in use by {0}")]
+    InUse(PortHolder),
+    #[error("failed accessing port lock file")]
+    Io(#[from] std::io::Error),
+}
+
+impl PortLock {
+    /// Take the lock for the given port, or find out who already has it.
+    pub fn acquire(port_name: &str) -> Result<Self, PortLockError> {
+        let locks_dir = config_adjacent_path(PORT_LOCKS_DIR_PATH);
+        fs::create_dir_all(&locks_dir)?;
+        let path = locks_dir.join(lock_file_name(port_name));
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+
+        match file.file().try_lock() {
+            Ok(()) => (),
+            Err(std::fs::TryLockError::WouldBlock) => {
+                let mut contents = String::new();
+                _ = file.read_to_string(&mut contents);
+                let pid = contents.trim().parse().ok();
+                return Err(PortLockError::InUse(PortHolder { pid }));
+            }
+            Err(std::fs::TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+        file.flush()?;
+        debug!("Took port lock at {path}");
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// Port paths like `/dev/ttyUSB0` or `COM3` made into a file name.
+fn lock_file_name(port_name: &str) -> String {
+    let mut name: String = port_name
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    name.push_str(".lock");
+    name
+}
+
+/// If an error from opening a port looks like it's because something else already has it open.
+pub fn looks_in_use(error: &serialport::Error) -> bool {
+    let description = error.description.to_lowercase();
+    match error.kind() {
+        // EBUSY isn't given its own ErrorKind by serialport, so the description is all there is to go on.
+        #[cfg(unix)]
+        serialport::ErrorKind::Io(_) => description.contains("busy"),
+        // Windows reports ports opened elsewhere as Access Denied, which serialport treats as missing.
+        #[cfg(windows)]
+        serialport::ErrorKind::NoDevice => description.contains("denied"),
+        _ => false,
+    }
+}
@@ -21,6 +21,7 @@ use crate::{
 use super::{
     ReconnectType, Reconnections, SerialSignals,
     handle::{PortCommand, SerialWorkerCommand},
+    port_lock::{PortHolder, PortLock, PortLockError, looks_in_use},
 };
 
 #[cfg(feature = "espflash")]
@@ -119,8 +120,10 @@ impl TakeablePort {
 pub struct SerialWorker {
     command_rx: Receiver<SerialWorkerCommand>,
     event_tx: Sender<Event>,
-    buffer_tx: Sender<(DateTime<Local>, Vec<u8>)>,
+    buffer_tx: Sender<(DateTime<Local>, u64, Vec<u8>)>,
     port: TakeablePort,
+    /// Held alongside a native port, so other yap instances can tell who has it.
+    port_lock: Option<PortLock>,
     last_signal_check: Instant,
     scan_snapshot: Vec<SerialPortInfo>,
     rx_buffer: Vec<u8>,
@@ -139,7 +142,7 @@ impl SerialWorker {
     pub fn new(
         command_rx: Receiver<SerialWorkerCommand>,
         event_tx: Sender<Event>,
-        buffer_tx: Sender<(DateTime<Local>, Vec<u8>)>,
+        buffer_tx: Sender<(DateTime<Local>, u64, Vec<u8>)>,
         port_status: Arc<ArcSwap<PortStatus>>,
         port_settings: Arc<ArcSwap<PortSettings>>,
         ignored_devices: Ignored,
@@ -151,6 +154,7 @@ impl SerialWorker {
             shared_status: port_status,
             shared_settings: port_settings,
             port: TakeablePort::default(),
+            port_lock: None,
             last_signal_check: Instant::now(),
             scan_snapshot: vec![],
             rx_buffer: vec![0; 1024 * 1024],
@@ -174,7 +178,7 @@ impl SerialWorker {
             match self.command_rx.recv_timeout(sleep_time) {
                 Ok(SerialWorkerCommand::Shutdown(shutdown_tx)) => {
                     debug!("Got shutdown request, dropping port!");
-                    self.port.drop();
+                    self.drop_port();
 
                     self.shared_status
                         .store(Arc::new(PortStatus::new_idle(&PortSettings::default())));
@@ -201,7 +205,7 @@ impl SerialWorker {
                 Err(crossbeam::channel::RecvTimeoutError::Disconnected) => {
                     error!("Serial worker handle got dropped! Shutting down!");
                     // Drop port explicity if it's present, since normal drop can hang.
-                    self.port.drop();
+                    self.drop_port();
                     break Err(WorkerError::HandleDropped);
                 }
             }
@@ -216,9 +220,11 @@ impl SerialWorker {
                 match port.read(self.rx_buffer.as_mut_slice()) {
                     Ok(t) if t > 0 => {
                         let recieved_at = Local::now();
+                        let recieved_at_us = crate::monotonic_micros();
                         let cloned_buff = self.rx_buffer[..t].to_owned();
                         // info!("{:?}", &serial_buf[..t]);
-                        self.buffer_tx.send((recieved_at, cloned_buff))?;
+                        self.buffer_tx
+                            .send((recieved_at, recieved_at_us, cloned_buff))?;
                         // if let Err(e) = self.buffer_tx.send(cloned_buff) {
                         //     self.port.drop();
                         //     Err(e)?;
@@ -259,7 +265,7 @@ impl SerialWorker {
             error!("had a connection error with no port object held??");
         }
 
-        self.port.drop();
+        self.drop_port();
 
         let last_status = self.shared_status.load().as_ref().clone();
         let known_port_ref = last_status
@@ -301,6 +307,7 @@ impl SerialWorker {
                 port,
                 baud,
                 mut settings,
+                ignore_port_lock,
                 result_tx: oneshot_tx,
             } => {
                 settings.baud_rate = baud.unwrap_or(settings.baud_rate);
@@ -346,10 +353,11 @@ impl SerialWorker {
 
                 match port_info_res {
                     Ok(port_info) => {
-                        oneshot_tx.send(
-                            self.connect_to_port(&port_info, None)
-                                .map_err(WorkerError::SerialPort),
-                        )?;
+                        oneshot_tx.send(self.connect_to_port(
+                            &port_info,
+                            None,
+                            ignore_port_lock,
+                        ))?;
                     }
                     Err(e) => {
                         oneshot_tx.send(Err(e))?;
@@ -367,7 +375,7 @@ impl SerialWorker {
 
                 self.shared_status
                     .store(Arc::new(previous_status.into_idle(&settings)));
-                self.port.drop();
+                self.drop_port();
                 self.event_tx
                     .send(SerialDisconnectReason::Intentional.into())?;
             }
@@ -574,7 +582,7 @@ impl SerialWorker {
             // Sleeping to give the device some time to intialize with Windows
             // (Otherwise Access Denied errors can occur from trying to connect too quick)
             std::thread::sleep(Duration::from_secs(1));
-            self.connect_to_port(port, Some(ReconnectType::PerfectMatch))?;
+            self.connect_to_port(port, Some(ReconnectType::PerfectMatch), false)?;
             return Ok(());
         };
 
@@ -598,7 +606,7 @@ impl SerialWorker {
                     port.port_name
                 );
                 std::thread::sleep(Duration::from_secs(1));
-                self.connect_to_port(port, Some(ReconnectType::UsbStrict))?;
+                self.connect_to_port(port, Some(ReconnectType::UsbStrict), false)?;
                 return Ok(());
             };
 
@@ -624,7 +632,7 @@ impl SerialWorker {
                     port.port_name
                 );
                 std::thread::sleep(Duration::from_secs(1));
-                self.connect_to_port(port, Some(ReconnectType::UsbLoose))?;
+                self.connect_to_port(port, Some(ReconnectType::UsbLoose), false)?;
                 return Ok(());
             };
         }
@@ -638,7 +646,7 @@ impl SerialWorker {
         {
             info!("Last ditch connect attempt on: {}", port.port_name);
             std::thread::sleep(Duration::from_secs(1));
-            self.connect_to_port(port, Some(ReconnectType::LastDitch))?;
+            self.connect_to_port(port, Some(ReconnectType::LastDitch), false)?;
             return Ok(());
         }
 
@@ -680,11 +688,18 @@ impl SerialWorker {
         // info!("Serial port scanning found {} ports", ports.len());
         Ok(ports)
     }
+    /// Drop the current port, and the lock on it if there is one.
+    fn drop_port(&mut self) {
+        self.port.drop();
+        self.port_lock = None;
+    }
+    /// Connect to the given port, first taking its lock unless `ignore_port_lock` is set.
     fn connect_to_port(
         &mut self,
         port_info: &SerialPortInfo,
         reconnect_type: Option<ReconnectType>,
-    ) -> Result<(), serialport::Error> {
+        ignore_port_lock: bool,
+    ) -> Result<(), WorkerError> {
         let mut port_status: PortStatus = self.shared_status.load().as_ref().clone();
         // If this is a normal connection, then this should be set to settings.dtr_on_open
         // otherwise, if we're reconnecting, then this should match the state of DTR at the time of disconnection
@@ -695,19 +710,46 @@ impl SerialWorker {
         if port_info.port_name.eq(MOCK_PORT_NAME) {
             let mut virt_port =
                 virtual_serialport::VirtualPort::loopback(baud_rate, MOCK_DATA.len() as u32)?;
-            virt_port.write_all(MOCK_DATA)?;
+            virt_port
+                .write_all(MOCK_DATA)
+                .map_err(serialport::Error::from)?;
 
             self.port.return_loopback(virt_port);
         } else {
+            let port_lock = if ignore_port_lock {
+                None
+            } else {
+                match PortLock::acquire(&port_info.port_name) {
+                    Ok(lock) => Some(lock),
+                    Err(PortLockError::InUse(holder)) => {
+                        return Err(WorkerError::PortInUse(holder));
+                    }
+                    // Not being able to tell other instances shouldn't stop the connection.
+                    Err(e) => {
+                        warn!("Connecting without port lock: {e}");
+                        None
+                    }
+                }
+            };
+
             let port = serialport::new(&port_info.port_name, baud_rate)
                 .data_bits(settings.data_bits)
                 .flow_control(settings.flow_control)
                 .parity(settings.parity_bits)
                 .stop_bits(settings.stop_bits)
                 .dtr_on_open(dtr_on_open)
-                .open_native()?;
+                .open_native()
+                .map_err(|e| {
+                    // Held by something not using the lock registry, like another serial terminal.
+                    if !ignore_port_lock && looks_in_use(&e) {
+                        WorkerError::PortInUse(PortHolder { pid: None })
+                    } else {
+                        e.into()
+                    }
+                })?;
 
             self.port.return_native(port);
+            self.port_lock = port_lock;
         };
 
         let port = self
@@ -1119,7 +1161,7 @@ impl SerialWorker {
 
 impl Drop for SerialWorker {
     fn drop(&mut self) {
-        self.port.drop();
+        self.drop_port();
     }
 }
 
@@ -1129,6 +1171,8 @@ pub(crate) enum WorkerError {
     RequestedUsbMissing,
     #[error("serial port error")]
     SerialPort(#[from] serialport::Error),
+    #[error("port is in use by {0}")]
+    PortInUse(PortHolder),
     #[error("no parent app receiver to send to")]
     FailedSend,
     #[error("failed to reply to shutdown request in time")]
@@ -0,0 +1,238 @@
+//! Session recordings, a compact binary format holding every chunk sent or recieved
+//! and exactly when, which can be played back later with `yap play`.
+//!
+//! A recording starts with [`MAGIC`] and a version byte, followed by records of:
+//!
+//! | Field          | Size     | Contents                                          |
+//! |----------------|----------|---------------------------------------------------|
+//! | direction      | 1 byte   | `0` for recieved, `1` for sent                    |
+//! | timestamp      | 8 bytes  | Microseconds since the Unix epoch, `i64` LE       |
+//! | monotonic time | 8 bytes  | Microseconds from a monotonic clock, `u64` LE     |
+//! | length         | 4 bytes  | Amount of bytes that follow, `u32` LE             |
+//! | bytes          | `length` | The chunk as-is                                   |
+
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use camino::Utf8Path;
+use chrono::{DateTime, Local};
+use fs_err as fs;
+
+use crate::buffer::TrafficDirection;
+
+/// Extension recordings are expected to have, used to tell them apart from JSON Lines captures.
+pub const EXTENSION: &str = "yapsession";
+
+const MAGIC: &[u8; 10] = b"YAPSESSION";
+const VERSION: u8 = 1;
+
+/// Longest chunk that's accepted when reading, to not allocate wildly for a corrupted length.
+const MAX_CHUNK_LEN: u32 = 16 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("failed reading session: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("not a yap session recording")]
+    NotASession,
+    #[error("session recorded by a newer version of yap (format version {0})")]
+    UnsupportedVersion(u8),
+    #[error("invalid record at byte {offset}: {reason}")]
+    InvalidRecord { offset: u64, reason: &'static str },
+}
+
+pub struct SessionRecord {
+    pub direction: TrafficDirection,
+    pub timestamp: DateTime<Local>,
+    /// Only meaningful relative to other records from the same recording.
+    pub monotonic_us: u64,
+    pub bytes: Vec<u8>,
+}
+
+/// Appends every chunk given to it to a recording, flushing after each one
+/// so nothing's lost if yap closes unexpectedly.
+pub struct SessionRecorder {
+    file: BufWriter<fs::File>,
+}
+
+impl SessionRecorder {
+    /// Start a new recording at the given path, replacing anything already there.
+    pub fn create(path: &Utf8Path) -> Result<Self, std::io::Error> {
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&[VERSION])?;
+        file.flush()?;
+        Ok(Self { file })
+    }
+    pub fn record(
+        &mut self,
+        direction: TrafficDirection,
+        timestamp: DateTime<Local>,
+        monotonic_us: u64,
+        bytes: &[u8],
+    ) -> Result<(), std::io::Error> {
+        let direction = match direction {
+            TrafficDirection::Rx => 0u8,
+            TrafficDirection::Tx => 1u8,
+        };
+        let len = u32::try_from(bytes.len()).map_err(std::io::Error::other)?;
+
+        self.file.write_all(&[direction])?;
+        self.file
+            .write_all(&timestamp.timestamp_micros().to_le_bytes())?;
+        self.file.write_all(&monotonic_us.to_le_bytes())?;
+        self.file.write_all(&len.to_le_bytes())?;
+        self.file.write_all(bytes)?;
+        self.file.flush()
+    }
+}
+
+/// Read every record from a recording, in the order they were recorded.
+pub fn read_records(path: &Utf8Path) -> Result<Vec<SessionRecord>, SessionError> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+
+    let mut header = [0u8; MAGIC.len() + 1];
+    if reader.read_exact(&mut header).is_err() || &header[..MAGIC.len()] != MAGIC {
+        return Err(SessionError::NotASession);
+    }
+    let version = header[MAGIC.len()];
+    if version > VERSION {
+        return Err(SessionError::UnsupportedVersion(version));
+    }
+
+    let mut records = Vec::new();
+    let mut offset = header.len() as u64;
+    loop {
+        let invalid = |reason| SessionError::InvalidRecord { offset, reason };
+
+        let mut fixed = [0u8; 1 + 8 + 8 + 4];
+        match reader.read_exact(&mut fixed[..1]) {
+            Ok(()) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e.into()),
+        }
+        reader
+            .read_exact(&mut fixed[1..])
+            .map_err(|_| invalid("recording ends partway through a record"))?;
+
+        let direction = match fixed[0] {
+            0 => TrafficDirection::Rx,
+            1 => TrafficDirection::Tx,
+            _ => return Err(invalid("unknown direction")),
+        };
+        let timestamp_us = i64::from_le_bytes(fixed[1..9].try_into().expect("8 bytes"));
+        let monotonic_us = u64::from_le_bytes(fixed[9..17].try_into().expect("8 bytes"));
+        let len = u32::from_le_bytes(fixed[17..21].try_into().expect("4 bytes"));
+
+        let timestamp = DateTime::from_timestamp_micros(timestamp_us)
+            .ok_or_else(|| invalid("timestamp out of range"))?
+            .with_timezone(&Local);
+        if len > MAX_CHUNK_LEN {
+            return Err(invalid("chunk too large"));
+        }
+        let mut bytes = vec![0u8; len as usize];
+        reader
+            .read_exact(&mut bytes)
+            .map_err(|_| invalid("recording ends partway through a record"))?;
+
+        offset += fixed.len() as u64 + len as u64;
+        records.push(SessionRecord {
+            direction,
+            timestamp,
+            monotonic_us,
+            bytes,
+        });
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    fn record_all(path: &Utf8Path) {
+        let timestamp = Local.with_ymd_and_hms(2024, 5, 17, 12, 0, 0).unwrap();
+        let mut recorder = SessionRecorder::create(path).unwrap();
+        recorder
+            .record(TrafficDirection::Rx, timestamp, 10, b"boot\r\n")
+            .unwrap();
+        recorder
+            .record(TrafficDirection::Tx, timestamp, 25, b"help\n")
+            .unwrap();
+        recorder
+            .record(TrafficDirection::Rx, timestamp, 40, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let dir = scratch_dir("round-trip");
+        let path = dir.join(format!("session.{EXTENSION}"));
+        record_all(&path);
+
+        let records = read_records(&path).unwrap();
+        let read: Vec<_> = records
+            .iter()
+            .map(|record| {
+                (
+                    record.direction,
+                    record.monotonic_us,
+                    record.bytes.as_slice(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (TrafficDirection::Rx, 10, b"boot\r\n".as_slice()),
+                (TrafficDirection::Tx, 25, b"help\n".as_slice()),
+                (TrafficDirection::Rx, 40, b"".as_slice()),
+            ]
+        );
+        let timestamp = Local.with_ymd_and_hms(2024, 5, 17, 12, 0, 0).unwrap();
+        assert!(records.iter().all(|record| record.timestamp == timestamp));
+
+        // A recording that's only just started has nothing to read yet.
+        SessionRecorder::create(&path).unwrap();
+        assert!(read_records(&path).unwrap().is_empty());
+        _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn truncated_recordings_refused() {
+        let dir = scratch_dir("truncated");
+        let path = dir.join(format!("session.{EXTENSION}"));
+        record_all(&path);
+        let full = fs::read(&path).unwrap();
+
+        // Cut off partway through the last record's fixed fields, then the second's bytes.
+        let header = MAGIC.len() + 1;
+        let first = 1 + 8 + 8 + 4 + b"boot\r\n".len();
+        for len in [full.len() - 3, header + first + 21 + 2] {
+            fs::write(&path, &full[..len]).unwrap();
+            assert!(matches!(
+                read_records(&path),
+                Err(SessionError::InvalidRecord { reason, .. })
+                    if reason == "recording ends partway through a record"
+            ));
+        }
+
+        fs::write(&path, &full[..MAGIC.len() - 2]).unwrap();
+        assert!(matches!(
+            read_records(&path),
+            Err(SessionError::NotASession)
+        ));
+
+        let mut newer = full.clone();
+        newer[MAGIC.len()] = VERSION + 1;
+        fs::write(&path, &newer).unwrap();
+        assert!(matches!(
+            read_records(&path),
+            Err(SessionError::UnsupportedVersion(version)) if version == VERSION + 1
+        ));
+        _ = fs::remove_dir_all(&dir);
+    }
+}
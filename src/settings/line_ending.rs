@@ -182,6 +182,15 @@ impl MacroTxLineEnding {
             MacroTxLineEnding::Custom(_, custom) => custom,
         }
     }
+    /// Which line ending settings are followed to get the bytes, for showing the user.
+    pub fn inheritance_chain(&self, tx: &TxLineEnding) -> &'static str {
+        match (self, tx) {
+            (MacroTxLineEnding::InheritTx, TxLineEnding::InheritRx) => "Macro -> TX -> RX",
+            (MacroTxLineEnding::InheritTx, _) => "Macro -> TX",
+            (MacroTxLineEnding::InheritRx, _) => "Macro -> RX",
+            _ => "Macro",
+        }
+    }
 }
 #[cfg(feature = "macros")]
 impl std::fmt::Display for MacroTxLineEnding {
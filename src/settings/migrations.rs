@@ -0,0 +1,135 @@
+use std::path::{Path, PathBuf};
+
+use fs_err as fs;
+use tracing::info;
+
+use super::SettingsError;
+
+/// Version of the settings file's layout,
+/// bumped (alongside a new entry in [`MIGRATIONS`]) whenever a field is renamed or changes type.
+pub const CONFIG_VERSION: u32 = 2;
+
+const CONFIG_VERSION_KEY: &str = "config_version";
+
+/// Brings a settings file from one version to the next, editing it before it's deserialized.
+type Migration = fn(&mut toml::Table);
+
+/// Each entry migrates from its index's version to the next,
+/// so there should always be exactly [`CONFIG_VERSION`] of them.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [unversioned_to_v1, v1_to_v2];
+
+/// Files from before versioning existed only need the version added.
+fn unversioned_to_v1(_settings: &mut toml::Table) {}
+
+/// `updates.allow_pre_releases` was replaced by picking an `updates.channel`.
+fn v1_to_v2(settings: &mut toml::Table) {
+    let Some(toml::Value::Table(updates)) = settings.get_mut("updates") else {
+        return;
+    };
+    if let Some(toml::Value::Boolean(true)) = updates.remove("allow_pre_releases") {
+        updates.insert("channel".to_owned(), toml::Value::from("PreRelease"));
+    }
+}
+
+/// Version of the given settings file, with files from before versioning being `0`.
+fn file_version(settings: &toml::Table) -> Result<u32, SettingsError> {
+    match settings.get(CONFIG_VERSION_KEY) {
+        None => Ok(0),
+        Some(toml::Value::Integer(version)) => {
+            u32::try_from(*version).map_err(|_| SettingsError::InvalidVersion(version.to_string()))
+        }
+        Some(other) => Err(SettingsError::InvalidVersion(other.to_string())),
+    }
+}
+
+/// Run every migration needed to bring the settings file up to [`CONFIG_VERSION`],
+/// returning the version it was at if any were needed.
+///
+/// Files from a newer version are refused, since saving over them would lose whatever changed.
+pub fn migrate(settings: &mut toml::Table) -> Result<Option<u32>, SettingsError> {
+    let version = file_version(settings)?;
+    if version > CONFIG_VERSION {
+        return Err(SettingsError::NewerVersion(version));
+    }
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(settings);
+    }
+    settings.insert(
+        CONFIG_VERSION_KEY.to_owned(),
+        toml::Value::Integer(CONFIG_VERSION.into()),
+    );
+    info!("Migrated settings from v{version} to v{CONFIG_VERSION}");
+    Ok(Some(version))
+}
+
+/// Copy the settings file as it was before migrating next to it, as `<name>.v<version>.bak`.
+pub fn backup(path: &Path, version: u32) -> Result<PathBuf, SettingsError> {
+    let mut file_name = path.file_name().unwrap_or_default().to_owned();
+    file_name.push(format!(".v{version}.bak"));
+    let backup_path = path.with_file_name(file_name);
+
+    fs::copy(path, &backup_path).map_err(SettingsError::FileWrite)?;
+    info!(
+        "Backed up pre-migration settings to {}",
+        backup_path.display()
+    );
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> toml::Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn file_versions_are_read_or_refused() {
+        assert_eq!(file_version(&table("")).unwrap(), 0);
+        assert_eq!(file_version(&table("config_version = 1")).unwrap(), 1);
+        assert!(matches!(
+            file_version(&table("config_version = -1")),
+            Err(SettingsError::InvalidVersion(_))
+        ));
+        assert!(matches!(
+            file_version(&table("config_version = \"2\"")),
+            Err(SettingsError::InvalidVersion(_))
+        ));
+    }
+
+    #[test]
+    fn unversioned_files_migrate_to_current() {
+        let mut settings = table("[updates]\nallow_pre_releases = true\n");
+        assert_eq!(migrate(&mut settings).unwrap(), Some(0));
+        assert_eq!(file_version(&settings).unwrap(), CONFIG_VERSION);
+
+        let updates = settings["updates"].as_table().unwrap();
+        assert!(!updates.contains_key("allow_pre_releases"));
+        assert_eq!(updates["channel"].as_str(), Some("PreRelease"));
+
+        // Already current, so nothing's done the second time around.
+        assert_eq!(migrate(&mut settings).unwrap(), None);
+    }
+
+    #[test]
+    fn stable_updates_keep_the_default_channel() {
+        let mut settings = table("config_version = 1\n[updates]\nallow_pre_releases = false\n");
+        assert_eq!(migrate(&mut settings).unwrap(), Some(1));
+        let updates = settings["updates"].as_table().unwrap();
+        assert!(updates.is_empty());
+    }
+
+    #[test]
+    fn newer_files_are_refused() {
+        let mut settings = table(&format!("config_version = {}", CONFIG_VERSION + 1));
+        assert!(matches!(
+            migrate(&mut settings),
+            Err(SettingsError::NewerVersion(version)) if version == CONFIG_VERSION + 1
+        ));
+    }
+}
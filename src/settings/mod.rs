@@ -11,37 +11,54 @@ use serde::{Deserialize, Serialize};
 use serde_with::{NoneAsEmptyString, serde_as};
 use serialport::{DataBits, FlowControl, Parity, StopBits};
 use struct_table::StructTable;
-use strum::VariantArray;
 
 // Copied a lot from my other project, redefaulter
 // https://github.com/nullstalgia/redefaulter/blob/ad81fad9468891b50daaac3215b0532386b6d1aa/src/settings/mod.rs
 
 use crate::{
     app::{COMMON_BAUD_TRUNC, DEFAULT_BAUD},
-    buffer::UserEcho,
+    buffer::{InputEncoding, UserEcho},
+    notifications::Severity,
     serial::{DeserializedUsb, Reconnections},
 };
 
+#[cfg(feature = "macros")]
+use crate::macros::MacroNameTag;
+
 pub mod ser;
 use ser::*;
 
 pub mod line_ending;
 use line_ending::*;
 
+pub mod migrations;
+use migrations::CONFIG_VERSION;
+
+pub mod overrides;
+use overrides::{SettingOverride, SettingOverrideError};
+
+pub mod profiles;
+
+#[cfg(feature = "settings-watch")]
+pub mod watcher;
+
 #[cfg(debug_assertions)]
 const DEFAULT_LOG_LEVEL: Level = Level::Trace;
 #[cfg(not(debug_assertions))]
 const DEFAULT_LOG_LEVEL: Level = Level::Debug;
 
-#[cfg(debug_assertions)]
-const DEFAULT_LOG_SOCKET_OPT: Option<SocketAddr> = {
+/// Where `yap logview` listens by default.
+const LOG_SOCKET: SocketAddr = {
     use std::net::{IpAddr, Ipv4Addr};
 
     let addr = Ipv4Addr::new(127, 0, 0, 1);
     let port = 7331;
 
-    Some(SocketAddr::new(IpAddr::V4(addr), port))
+    SocketAddr::new(IpAddr::V4(addr), port)
 };
+
+#[cfg(debug_assertions)]
+const DEFAULT_LOG_SOCKET_OPT: Option<SocketAddr> = Some(LOG_SOCKET);
 #[cfg(not(debug_assertions))]
 const DEFAULT_LOG_SOCKET_OPT: Option<SocketAddr> = None;
 
@@ -54,6 +71,10 @@ const DEFAULT_HIDE_LOOPBACK: bool = false;
 #[serde(default)]
 #[derivative(Default)]
 pub struct Settings {
+    #[derivative(Default(value = "CONFIG_VERSION"))]
+    /// Layout version of the settings file, used to migrate older files.
+    pub config_version: u32,
+
     pub serial: PortSettings,
 
     pub rendering: Rendering,
@@ -74,25 +95,34 @@ pub struct Settings {
 
     #[serde(skip)]
     pub path: PathBuf,
+
+    #[serde(skip)]
+    /// Name of the settings profile last switched to this session, if any.
+    pub profile: Option<String>,
+
+    #[serde(skip)]
+    /// Overrides from the command line or environment, with the values they replaced.
+    overrides: Vec<(SettingOverride, toml::Value)>,
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize, Derivative)]
+#[derive(Debug, Clone, Serialize, Deserialize, StructTable, Derivative)]
 #[serde(default)]
 #[derivative(Default)]
 pub struct Misc {
     #[derivative(Default(value = "DEFAULT_LOG_LEVEL"))]
+    /// Most detailed level of yap's own logs to keep.
     pub log_level: Level,
 
     #[derivative(Default(value = "DEFAULT_LOG_SOCKET_OPT"))]
     #[serde_as(as = "NoneAsEmptyString")]
+    #[table(rename = "Log TCP Socket", values = [LOG_SOCKET], none = "Disabled", allow_unknown_values)]
+    /// Also send yap's own logs to this address, i.e. to a `yap logview`.
     pub log_tcp_socket: Option<SocketAddr>,
 }
 
 // TODO allow setting nicknames to devices?????
 
-// TODO add Reset to Defaults somewhere in the UI
-
 // TODO have flattened buffer behavior struct that gets sent to it on each change.
 macro_rules! inclusive_increment {
     ($len:expr) => {{
@@ -112,7 +142,7 @@ macro_rules! inclusive_increment {
 #[derivative(Default)]
 pub struct Rendering {
     #[derivative(Default(value = "UserEcho::All"))]
-    #[table(values = UserEcho::VARIANTS)]
+    #[table(section = "Text")]
     /// Show user input in buffer after sending.
     pub echo_user_input: UserEcho,
 
@@ -134,30 +164,82 @@ pub struct Rendering {
     /// Show line ending at end of recieved lines.
     pub show_line_ending: bool,
 
+    #[derivative(Default(value = "InputEncoding::Utf8"))]
+    /// Text encoding of incoming bytes, decoded before ANSI parsing. Auto only detects UTF-16LE.
+    pub input_encoding: InputEncoding,
+
     #[derivative(Default(value = "true"))]
     /// Show hidden chars and invalid UTF-8 byte sequences in \xFF notation.
     pub escape_unprintable_bytes: bool,
 
+    #[derivative(Default(value = "BellBehavior::ShowEscaped"))]
+    /// What to do when a BEL (0x07) byte is recieved, counted next to the line count if not shown.
+    pub bell: BellBehavior,
+
     #[derivative(Default(value = "true"))]
     /// Show a placeholder for lines who have had their entire content hidden by color rules.
     pub show_hidden_lines: bool,
 
+    #[table(section = "Hex View")]
     /// Show recieved bytes in a Hex+ASCII view.
     pub hex_view: bool,
 
     #[derivative(Default(value = "true"))]
+    #[table(hide_if = hex_view_off)]
     /// Show Address+Offset Markers+ASCII label above hex view.
     pub hex_view_header: bool,
 
     #[table(values = inclusive_increment!(48))]
     #[table(allow_unknown_values)]
+    #[table(hide_if = hex_view_off)]
     /// Set an optional maximum bytes per line.
     pub bytes_per_line: MaxBytesPerLine,
 
     #[derivative(Default(value = "HexHighlightStyle::HighlightAsciiSymbols"))]
-    #[table(values = HexHighlightStyle::VARIANTS)]
+    #[table(hide_if = hex_view_off)]
     /// Show user input in buffer after sending.
     pub hex_view_highlights: HexHighlightStyle,
+
+    #[table(section = "Display")]
+    #[table(allow_unknown_values)]
+    #[table(display = ["Unlimited", "1,000", "10,000", "50,000", "100,000"])]
+    #[table(values = [0, 1_000, 10_000, 50_000, 100_000])]
+    /// Keep only this many of the newest lines fully styled, older lines are restyled from raw bytes when shown.
+    pub styled_line_limit: usize,
+
+    #[derivative(Default(value = "true"))]
+    /// Highlight panics, backtrace frames, and defmt errors that point to a source location.
+    pub highlight_source_locations: bool,
+
+    /// Colors used throughout the UI. Custom reads them from yap_theme.toml.
+    pub theme: ThemeChoice,
+
+    /// Show a line along the bottom listing keybinds for what's currently on screen.
+    pub help_footer: bool,
+}
+
+/// The hex view's own settings are only shown while it's enabled.
+fn hex_view_off(rendering: &Rendering) -> bool {
+    !rendering.hex_view
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::VariantArray,
+)]
+#[strum(serialize_all = "title_case")]
+pub enum ThemeChoice {
+    #[default]
+    Default,
+    Monochrome,
+    Custom,
 }
 
 #[derive(
@@ -176,6 +258,19 @@ pub enum HexHighlightStyle {
     StyleB,
 }
 
+#[derive(
+    Debug, Clone, Copy, PartialEq, Serialize, Deserialize, strum::Display, strum::VariantArray,
+)]
+#[strum(serialize_all = "title_case")]
+pub enum BellBehavior {
+    /// Leave BEL bytes in the text, shown like any other unprintable byte.
+    ShowEscaped,
+    /// Remove BEL bytes from the text and briefly flash the bar under the buffer.
+    Flash,
+    /// Remove BEL bytes from the text and ring the bell of the terminal running yap.
+    TerminalBell,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct MaxBytesPerLine(u8);
@@ -245,6 +340,9 @@ impl std::fmt::Display for MaxBytesPerLine {
     }
 }
 
+#[cfg(feature = "logging")]
+pub const DEFAULT_FILE_NAME_TEMPLATE: &str = "yap-{date}_{time}";
+
 #[cfg(feature = "logging")]
 #[derive(Debug, Clone, Serialize, Deserialize, StructTable, Derivative)]
 #[serde(default)]
@@ -256,8 +354,12 @@ pub struct Logging {
     /// Whether to log the incoming input in a raw binary file.
     pub log_raw_input_to_file: bool,
 
+    #[table(rename = "Log JSON Lines to File")]
+    /// Whether to log all traffic as JSON records, one per line, for other tools to parse.
+    pub log_jsonl_to_file: bool,
+
     #[derivative(Default(value = "String::from(crate::buffer::DEFAULT_TIMESTAMP_FORMAT)"))]
-    #[table(skip)]
+    #[table(parse = parse_timestamp_format)]
     /// Format for output timestamps.
     pub timestamp: String,
 
@@ -273,7 +375,230 @@ pub struct Logging {
     #[derivative(Default(value = "true"))]
     /// Log any disconnect and reconnect events in text outputs.
     pub log_connection_events: bool,
+
+    #[cfg(feature = "defmt")]
+    /// Also write decoded defmt frames as structured JSONL records next to text logs.
+    pub log_defmt_records: bool,
     // TODO maybe add option to strip ansi escapes for text output?
+    #[table(allow_unknown_values)]
+    #[table(display = ["Never", "1 MB", "10 MB", "100 MB", "1 GB"])]
+    #[table(values = [0, 1, 10, 100, 1000])]
+    #[table(rename = "Rotate at Size")]
+    /// Start a new log file once the current one reaches this many megabytes, 0 to never.
+    pub rotate_at_size_mb: u64,
+
+    /// Start a new log file every hour or day, regardless of size.
+    pub rotation_interval: LogRotationInterval,
+
+    #[derivative(Default(value = "5"))]
+    #[table(range(0, MAX_RETAINED_ROTATIONS, 1))]
+    #[table(format = format_retained_rotations)]
+    #[table(rename = "Rotated Files Kept")]
+    /// How many previous log files to keep of each kind after rotating, 0 to keep all.
+    pub retained_rotations: u32,
+
+    /// Numbered moves the old file to `<name>.1` (and so on), Timestamped names the new file after when it began.
+    pub rollover_naming: RolloverNaming,
+
+    #[cfg(feature = "log-compression")]
+    /// Compress new log files as they're written, adding a `.gz` or `.zst` extension.
+    pub compression: LogCompression,
+
+    #[derivative(Default(value = "String::from(DEFAULT_FILE_NAME_TEMPLATE)"))]
+    #[table(parse = parse_file_name_template)]
+    /// Name of new log files, before their extension.
+    /// `{port}`, `{date}`, `{time}`, and `{session}` are replaced with the port's name,
+    /// when logging began, and how many times logging has begun since yap was opened.
+    pub file_name_template: String,
+
+    /// Start new log files each time a port is connected to, instead of continuing the current ones.
+    pub new_file_per_connection: bool,
+
+    #[derivative(Default(value = "30"))]
+    #[table(allow_unknown_values)]
+    #[table(display = ["Off", "10 s", "30 s", "1 min", "5 min"])]
+    #[table(values = [0, 10, 30, 60, 300])]
+    #[table(rename = "Recent Traffic Kept")]
+    /// Keep this many seconds of recieved data in memory, even when not logging to files,
+    /// to be saved with the `logging-dump-recent` action. 0 to keep none.
+    pub keep_recent_secs: u32,
+
+    #[derivative(Default(value = "1024"))]
+    #[table(allow_unknown_values)]
+    #[table(display = ["64 KB", "256 KB", "1 MB", "4 MB", "16 MB"])]
+    #[table(values = [64, 256, 1024, 4096, 16384])]
+    #[table(rename = "Recent Traffic Limit")]
+    /// Most recieved data kept in memory for `logging-dump-recent`, dropping the oldest past this.
+    pub keep_recent_max_kb: u32,
+
+    #[table(text)]
+    #[table(rename = "Dump Recent On Text")]
+    /// Also save the recent traffic whenever recieved data contains this text. Left empty to never.
+    pub dump_recent_trigger: String,
+
+    #[table(rename = "Forward Lines To")]
+    /// Also send each line of the text log to a collector, as plain lines over TCP or UDP, or as syslog messages over UDP.
+    pub forward_to: LogForwarding,
+
+    #[derivative(Default(value = "String::from(\"127.0.0.1:514\")"))]
+    #[table(parse = parse_forward_address)]
+    /// `host:port` of the collector that text log lines are forwarded to.
+    pub forward_address: String,
+}
+
+#[cfg(feature = "logging")]
+fn parse_timestamp_format(text: &str) -> Result<String, &'static str> {
+    use chrono::format::{Item, StrftimeItems};
+
+    if StrftimeItems::new(text).any(|item| matches!(item, Item::Error)) {
+        return Err("invalid timestamp format");
+    }
+    Ok(text.to_owned())
+}
+
+/// Most previous log files that can be kept of each kind, besides keeping all of them.
+#[cfg(feature = "logging")]
+const MAX_RETAINED_ROTATIONS: u32 = 100;
+
+#[cfg(feature = "logging")]
+fn format_retained_rotations(kept: &u32) -> String {
+    match kept {
+        0 => "Keep All".to_owned(),
+        kept => kept.to_string(),
+    }
+}
+
+#[cfg(feature = "logging")]
+fn parse_file_name_template(text: &str) -> Result<String, &'static str> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("file name can't be empty");
+    }
+    if text.contains(['/', '\\']) {
+        return Err("file name can't contain folders");
+    }
+    Ok(text.to_owned())
+}
+
+#[cfg(feature = "logging")]
+/// Only checking the shape of the address, since resolving it could block.
+fn parse_forward_address(text: &str) -> Result<String, &'static str> {
+    let text = text.trim();
+    match text.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+            Ok(text.to_owned())
+        }
+        _ => Err("expected host:port"),
+    }
+}
+
+#[cfg(feature = "logging")]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::VariantArray,
+    strum::EnumString,
+    strum::Display,
+)]
+/// Where lines of the text log are sent besides the file itself.
+pub enum LogForwarding {
+    #[default]
+    Disabled,
+    /// Newline-terminated lines over a TCP connection.
+    #[strum(serialize = "TCP")]
+    Tcp,
+    /// One UDP datagram per line.
+    #[strum(serialize = "UDP")]
+    Udp,
+    /// RFC 5424 syslog messages over UDP.
+    Syslog,
+}
+
+#[cfg(feature = "logging")]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::VariantArray,
+    strum::EnumString,
+    strum::Display,
+)]
+pub enum LogCompression {
+    #[default]
+    None,
+    #[cfg(feature = "log-compression")]
+    Gzip,
+    #[cfg(feature = "log-compression")]
+    Zstd,
+}
+
+#[cfg(feature = "logging")]
+impl LogCompression {
+    /// Appended to the names of files compressed this way.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            #[cfg(feature = "log-compression")]
+            Self::Gzip => Some("gz"),
+            #[cfg(feature = "log-compression")]
+            Self::Zstd => Some("zst"),
+        }
+    }
+}
+
+#[cfg(feature = "logging")]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::VariantArray,
+    strum::EnumString,
+    strum::Display,
+)]
+pub enum LogRotationInterval {
+    #[default]
+    Never,
+    Hourly,
+    Daily,
+}
+
+#[cfg(feature = "logging")]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    strum::VariantArray,
+    strum::EnumString,
+    strum::Display,
+)]
+/// How log files are named when rotated.
+pub enum RolloverNaming {
+    #[default]
+    /// The current file keeps its name, with older ones shifted to `<name>.1`, `<name>.2`, etc.
+    Numbered,
+    /// Each new file is named after when it began, like the first one.
+    Timestamped,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, StructTable, Derivative)]
@@ -299,13 +624,23 @@ pub struct Behavior {
     /// Text scroll speed modifier, positive increases, negative decreases.
     pub text_scroll_speed: i8,
 
-    // #[serde(default)]
-    // /// Persist Pseudo Shell's command history across sessions (TODO).
-    // pub retain_history: bool,
-    //
+    /// Persist Pseudo Shell's command history across sessions, in yap_history.jsonl.
+    pub retain_history: bool,
+
+    #[derivative(Default(value = "true"))]
+    #[table(hide_if = history_not_retained)]
+    /// Also persist inputs entered in byte mode.
+    pub retain_byte_history: bool,
+
+    #[derivative(Default(value = "true"))]
+    #[table(hide_if = history_not_retained)]
+    /// Keep inputs starting with a space out of the saved history, like shells do. Handy for passwords.
+    pub history_ignore_space: bool,
+
     #[derivative(Default(value = "Duration::from_millis(500)"))]
     #[table(allow_unknown_values)]
-    #[table(display = Debug)]
+    #[table(format = duration_as_ms)]
+    #[table(unit = "ms")]
     #[table(values = [Duration::from_millis(10), Duration::from_millis(100), Duration::from_millis(250), Duration::from_millis(500), Duration::from_secs(1)])]
     #[serde(rename = "action_chain_delay_ms")]
     #[serde(
@@ -317,10 +652,59 @@ pub struct Behavior {
     /// Default delay between chained Actions in keybinds. Can be overwritten with "pause_ms:XXX" in chains.
     pub action_chain_delay: Duration,
 
+    #[table(allow_unknown_values)]
+    #[table(format = duration_as_ms)]
+    #[table(unit = "ms")]
+    #[table(values = [Duration::ZERO, Duration::from_millis(10), Duration::from_millis(50), Duration::from_millis(100), Duration::from_millis(250), Duration::from_millis(500)])]
+    #[serde(rename = "compose_line_delay_ms")]
+    #[serde(
+        serialize_with = "serialize_duration_as_ms",
+        deserialize_with = "deserialize_duration_from_ms"
+    )]
+    /// Delay between each line sent from the compose editor (Alt-Enter by default).
+    pub compose_line_delay: Duration,
+
+    #[derivative(Default(value = "1024"))]
+    #[table(allow_unknown_values)]
+    #[table(display = ["Never Ask", "256 B", "1 KB", "4 KB", "16 KB"])]
+    #[table(values = [0, 256, 1024, 4096, 16384])]
+    /// Ask before sending pastes larger than this many bytes straight to the device, 0 to never ask.
+    pub paste_confirm_threshold: usize,
+
     #[cfg(feature = "macros")]
     #[derivative(Default(value = "true"))]
     /// Allow entering Macros in keybinds without a category.
     pub fuzzy_macro_match: bool,
+    #[cfg(feature = "macros")]
+    #[derivative(Default(value = "true"))]
+    /// Show pinned Macros in a bar under the input line, sent with F1-F10 or Alt+1-0.
+    pub show_macro_hotbar: bool,
+    #[cfg(feature = "macros")]
+    #[table(skip)]
+    /// Macros pinned to the hotbar, in slot order. Pinned from the Macros menu with Ctrl-P.
+    pub macro_hotbar: Vec<MacroNameTag>,
+    #[table(text)]
+    /// Command to open source locations with, where `{file}`, `{line}`, and `{column}` are replaced.
+    /// The TUI is suspended until the editor exits.
+    ///
+    /// If empty, `$VISUAL` or `$EDITOR` is used as `<editor> {file}:{line}`,
+    /// or `<editor> +{line} {file}` for editors like vim and nano.
+    pub editor_command: String,
+
+    /// Least severe notifications to show, any below are only kept in the notification history.
+    pub notification_level: Severity,
+
+    /// Keep all notifications out of sight, only adding them to the notification history.
+    pub do_not_disturb: bool,
+}
+
+/// The history file's own settings are only shown while it's in use.
+fn history_not_retained(behavior: &Behavior) -> bool {
+    !behavior.retain_history
+}
+
+fn duration_as_ms(duration: &Duration) -> String {
+    duration.as_millis().to_string()
 }
 
 #[cfg(feature = "defmt")]
@@ -369,6 +753,33 @@ pub enum DefmtLocation {
     Hidden,
 }
 
+#[cfg(feature = "defmt")]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    strum::VariantArray,
+    strum::EnumString,
+    strum::Display,
+)]
+/// How a device's defmt timestamp is rendered, if it's a monotonic tick count (or seconds).
+pub enum DeviceTimestampMode {
+    /// Shown as formatted by the firmware.
+    #[default]
+    #[strum(serialize = "As Sent")]
+    AsSent,
+    /// Seconds since the device booted, derived with the tick rate.
+    #[strum(serialize = "Since Boot")]
+    SinceBoot,
+    /// Host wall-clock time, relative to when the device's timestamps were anchored.
+    #[strum(serialize = "Wall Clock")]
+    WallClock,
+}
+
 #[derive(
     Debug,
     Default,
@@ -437,6 +848,35 @@ impl From<defmt_parser::Level> for Level {
     }
 }
 
+#[cfg(feature = "defmt")]
+impl Level {
+    /// The next more severe level, wrapping back around to `Trace` after `Error`.
+    pub fn cycled(&self) -> Self {
+        match self {
+            Level::Trace => Level::Debug,
+            Level::Debug => Level::Info,
+            Level::Info => Level::Warn,
+            Level::Warn => Level::Error,
+            Level::Error => Level::Trace,
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl Defmt {
+    /// Returns `true` if logs at this level pass both the maximum level and per-level toggles.
+    pub fn level_shown(&self, level: Level) -> bool {
+        let toggled_on = match level {
+            Level::Trace => self.show_trace,
+            Level::Debug => self.show_debug,
+            Level::Info => self.show_info,
+            Level::Warn => self.show_warn,
+            Level::Error => self.show_error,
+        };
+        toggled_on && self.max_log_level <= level
+    }
+}
+
 #[cfg(feature = "espflash")]
 #[derive(Debug, Clone, Serialize, Deserialize, Derivative)]
 #[serde(default)]
@@ -452,7 +892,6 @@ pub struct Espflash {
 #[serde(default)]
 #[derivative(Default)]
 pub struct Defmt {
-    #[table(values = DefmtSupport::VARIANTS)]
     /// Enable parsing RX'd serial data as defmt packets.
     pub defmt_parsing: DefmtSupport,
 
@@ -463,19 +902,73 @@ pub struct Defmt {
 
     #[derivative(Default(value = "Level::Trace"))]
     #[table(display = Debug)]
-    #[table(values = Level::VARIANTS)]
     /// Maximum log level to display. Items without a level are always shown.
     pub max_log_level: Level,
 
+    #[derivative(Default(value = "true"))]
+    /// Show logs at the Trace level (if allowed by the maximum log level).
+    pub show_trace: bool,
+
+    #[derivative(Default(value = "true"))]
+    /// Show logs at the Debug level (if allowed by the maximum log level).
+    pub show_debug: bool,
+
+    #[derivative(Default(value = "true"))]
+    /// Show logs at the Info level (if allowed by the maximum log level).
+    pub show_info: bool,
+
+    #[derivative(Default(value = "true"))]
+    /// Show logs at the Warn level (if allowed by the maximum log level).
+    pub show_warn: bool,
+
+    #[derivative(Default(value = "true"))]
+    /// Show logs at the Error level (if allowed by the maximum log level).
+    pub show_error: bool,
+
+    /// Only show logs from modules allowed by the module filter. Items without a location are always shown.
+    pub filter_by_module: bool,
+
+    #[table(text)]
+    /// Comma-separated module path globs to show logs from, `!`-prefixed to hide instead.
+    ///
+    /// i.e. `my_app::*, !my_app::radio`
+    pub module_filter: String,
+
+    #[table(text)]
+    #[table(rename = "ELF Search Path")]
+    /// Workspace or target directory searched for the newest defmt ELF.
+    ///
+    /// If empty, `CARGO_TARGET_DIR` is used, falling back to `./target`.
+    pub elf_search_path: String,
+
+    #[cfg(feature = "defmt-watch")]
+    #[table(skip)]
+    /// Other ELFs to watch alongside the loaded one, i.e. the rest of a workspace's binaries.
+    ///
+    /// When any change, whichever was most recently rebuilt gets loaded.
+    pub extra_watched_elfs: Vec<camino::Utf8PathBuf>,
+
     #[derivative(Default(value = "true"))]
     /// Show device-derived timestamps, if available.
     pub device_timestamp: bool,
 
-    #[table(values = DefmtLocation::VARIANTS)]
+    /// Show device timestamps as sent, as time since boot, or as host wall-clock time.
+    pub device_timestamp_mode: DeviceTimestampMode,
+
+    #[derivative(Default(value = "1_000_000"))]
+    #[table(allow_unknown_values)]
+    #[table(display = ["1 kHz", "32.768 kHz", "1 MHz", "16 MHz"])]
+    #[table(values = [1_000, 32_768, 1_000_000, 16_000_000])]
+    /// Ticks per second of the device timestamp, if sent as a plain integer.
+    pub device_tick_rate: u32,
+
+    #[derivative(Default(value = "true"))]
+    /// Re-anchor wall-clock timestamps to host time when the device's ticks go backwards (i.e. after a reset).
+    pub reanchor_on_reset: bool,
+
     /// Show module where log originated from, if available.
     pub show_module: DefmtLocation,
 
-    #[table(values = DefmtLocation::VARIANTS)]
     /// Show file where log originated from, if available.
     pub show_file: DefmtLocation,
 
@@ -529,7 +1022,6 @@ pub struct PortSettings {
     pub limit_tx_speed: bool,
 
     /// Enable reconnections. Strict checks USB PID+VID+Serial#. Loose checks for any similar USB device/COM port.
-    #[table(values = Reconnections::VARIANTS)]
     pub reconnections: Reconnections,
 
     /// Line endings for RX'd data.
@@ -595,7 +1087,52 @@ pub struct Updates {
 
     pub skipped_version: String,
 
-    pub allow_pre_releases: bool,
+    /// Which releases are offered when checking for updates.
+    pub channel: UpdateChannel,
+
+    /// Proxy to check for and download updates through, like `http://proxy.lan:3128`.
+    ///
+    /// If empty, the `HTTPS_PROXY` (or `ALL_PROXY`) environment variable is used instead, if set.
+    pub proxy: String,
+}
+
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    strum::VariantArray,
+    strum::EnumString,
+    strum::Display,
+)]
+/// Release channels to update from, each also offering the releases of those before it.
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    /// Releases tagged `-pre.N`, ahead of the next stable release.
+    #[strum(serialize = "Pre-release")]
+    PreRelease,
+    /// Builds of the latest commit, published daily to the rolling `nightly` release.
+    Nightly,
+}
+
+impl UpdateChannel {
+    /// The most stable channel offering this version.
+    pub fn of(version: &semver::Version) -> Self {
+        if version.pre.is_empty() {
+            Self::Stable
+        } else if version.pre.as_str().starts_with("nightly") {
+            Self::Nightly
+        } else {
+            Self::PreRelease
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Derivative)]
@@ -648,7 +1185,11 @@ impl Settings {
             return Ok(default);
         }
         let settings_toml = fs::read_to_string(path).map_err(SettingsError::FileRead)?;
-        let mut config: Settings = toml::from_str(&settings_toml)?;
+        let mut table: toml::Table = toml::from_str(&settings_toml)?;
+        if let Some(version) = migrations::migrate(&mut table)? {
+            migrations::backup(path, version)?;
+        }
+        let mut config: Settings = toml::Value::Table(table).try_into()?;
         config.path = path.into();
         config.save()?;
         Ok(config)
@@ -660,7 +1201,7 @@ impl Settings {
     }
     // TODO write all enum variants next to each field?
     fn save_at(&self, config_path: &Path) -> Result<(), SettingsError> {
-        let toml_config = toml::to_string(self)?;
+        let toml_config = self.to_saved_string()?;
         fs::File::create(config_path)
             .and_then(|mut file| {
                 file.write_all(toml_config.as_bytes())?;
@@ -671,11 +1212,131 @@ impl Settings {
 
         Ok(())
     }
+    /// Read the settings file again without saving it back, keeping this session's path, profile, and overrides.
+    pub fn reload(&self) -> Result<Self, SettingsError> {
+        let settings_toml = fs::read_to_string(&self.path).map_err(SettingsError::FileRead)?;
+        let mut table: toml::Table = toml::from_str(&settings_toml)?;
+        // Not saved here, so the file is left for the next load to back up.
+        migrations::migrate(&mut table)?;
+        let mut config: Settings = toml::Value::Table(table).try_into()?;
+        config.path = self.path.clone();
+        config.profile = self.profile.clone();
+        if !self.overrides.is_empty() {
+            let overrides = self.overrides.iter().map(|(setting, _)| setting.clone());
+            config.apply_overrides(overrides.collect())?;
+        }
+        Ok(config)
+    }
+    /// Every field that differs between the two, with the value on either side.
+    pub fn diff(&self, other: &Self) -> Result<Vec<SettingsChange>, SettingsError> {
+        let ours = toml::Table::try_from(self)?;
+        let theirs = toml::Table::try_from(other)?;
+
+        let mut changes = Vec::new();
+        for (section, value) in &ours {
+            match (value, theirs.get(section)) {
+                (toml::Value::Table(our_fields), Some(toml::Value::Table(their_fields))) => {
+                    let differs = our_fields
+                        .iter()
+                        .filter(|(field, value)| their_fields.get(*field) != Some(value))
+                        .map(|(field, _)| field);
+                    let added = their_fields
+                        .keys()
+                        .filter(|field| !our_fields.contains_key(*field));
+                    changes.extend(differs.chain(added).map(|field| SettingsChange {
+                        field: format!("{section}.{field}"),
+                        from: our_fields.get(field).cloned(),
+                        to: their_fields.get(field).cloned(),
+                    }));
+                }
+                (value, theirs) if theirs != Some(value) => changes.push(SettingsChange {
+                    field: section.clone(),
+                    from: Some(value.clone()),
+                    to: theirs.cloned(),
+                }),
+                _ => (),
+            }
+        }
+        Ok(changes)
+    }
+    /// Put the given section back to its defaults, leaving the rest as-is.
+    ///
+    /// Doesn't save, that's left to the caller.
+    pub fn reset_section(&mut self, section: SettingsSection) {
+        match section {
+            SettingsSection::Serial => self.serial = PortSettings::default(),
+            SettingsSection::Rendering => self.rendering = Rendering::default(),
+            SettingsSection::Behavior => self.behavior = Behavior::default(),
+            #[cfg(feature = "logging")]
+            SettingsSection::Logging => self.logging = Logging::default(),
+            #[cfg(feature = "defmt")]
+            SettingsSection::Defmt => self.defmt = Defmt::default(),
+        }
+    }
     pub fn get_log_level(&self) -> tracing::Level {
         tracing::Level::from(&self.misc.log_level)
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// A single field that differs between two sets of settings, found by comparing them as TOML.
+pub struct SettingsChange {
+    /// `section.field`, or just `section` if it isn't a table.
+    pub field: String,
+    pub from: Option<toml::Value>,
+    pub to: Option<toml::Value>,
+}
+
+impl SettingsChange {
+    pub fn section(&self) -> &str {
+        self.field.split('.').next().unwrap_or_default()
+    }
+    /// The first few changed fields, with a count of the rest, for a notification.
+    pub fn summarize(changes: &[Self]) -> String {
+        const LISTED_FIELDS: usize = 3;
+        let listed: Vec<&str> = changes
+            .iter()
+            .take(LISTED_FIELDS)
+            .map(|change| change.field.as_str())
+            .collect();
+        let mut summary = listed.join(", ");
+        if changes.len() > LISTED_FIELDS {
+            summary.push_str(&format!(" (+{} more)", changes.len() - LISTED_FIELDS));
+        }
+        summary
+    }
+}
+
+impl std::fmt::Display for SettingsChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |value: &Option<toml::Value>| {
+            value
+                .as_ref()
+                .map_or_else(|| "(unset)".to_owned(), ToString::to_string)
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            value(&self.from),
+            value(&self.to)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::VariantArray, strum::AsRefStr)]
+#[strum(serialize_all = "lowercase")]
+/// Sections of the settings file that have their own menu, named as they are in the file.
+pub enum SettingsSection {
+    Serial,
+    Rendering,
+    Behavior,
+    #[cfg(feature = "logging")]
+    Logging,
+    #[cfg(feature = "defmt")]
+    Defmt,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SettingsError {
     #[error("failed reading from app settings file")]
@@ -686,4 +1347,100 @@ pub enum SettingsError {
     Deser(#[from] toml::de::Error),
     #[error("failed settings serialization")]
     Ser(#[from] toml::ser::Error),
+    #[error("invalid settings version `{0}`")]
+    InvalidVersion(String),
+    #[error(
+        "settings file is from a newer version of yap (v{0}, supports up to v{CONFIG_VERSION})"
+    )]
+    NewerVersion(u32),
+    #[error("failed applying setting overrides")]
+    Overrides(#[from] SettingOverrideError),
+}
+
+#[cfg(test)]
+mod tests {
+    use struct_table::ArrowKey;
+
+    use super::*;
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn retained_rotations_clamped_to_range() {
+        let row = Logging::FIELD_NAMES
+            .iter()
+            .position(|name| *name == "Rotated Files Kept")
+            .unwrap();
+        let mut logging = Logging {
+            retained_rotations: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            logging.handle_input(ArrowKey::Left, row).unwrap(),
+            Some(row)
+        );
+        assert_eq!(logging.retained_rotations, 0);
+        assert_eq!(logging.rows()[row].1, "Keep All");
+        // Already at the minimum.
+        assert_eq!(logging.handle_input(ArrowKey::Left, row).unwrap(), None);
+        assert_eq!(logging.retained_rotations, 0);
+
+        logging.retained_rotations = MAX_RETAINED_ROTATIONS - 1;
+        assert_eq!(
+            logging.handle_input(ArrowKey::Right, row).unwrap(),
+            Some(row)
+        );
+        assert_eq!(logging.retained_rotations, MAX_RETAINED_ROTATIONS);
+        assert_eq!(logging.handle_input(ArrowKey::Right, row).unwrap(), None);
+        assert_eq!(logging.retained_rotations, MAX_RETAINED_ROTATIONS);
+
+        // Typed values are held to the same bounds.
+        assert_eq!(logging.edit_text(row).as_deref(), Some("100"));
+        assert!(logging.apply_text(row, "101").is_err());
+        assert_eq!(logging.apply_text(row, " 25 ").unwrap(), Some(row));
+        assert_eq!(logging.retained_rotations, 25);
+    }
+
+    /// Row of [`Misc::log_tcp_socket`].
+    const LOG_SOCKET_ROW: usize = 1;
+
+    fn log_socket_shown(misc: &Misc) -> String {
+        misc.rows().swap_remove(LOG_SOCKET_ROW).1
+    }
+
+    #[test]
+    fn log_socket_cycles_through_none() {
+        let mut misc = Misc {
+            log_tcp_socket: None,
+            ..Default::default()
+        };
+        assert_eq!(log_socket_shown(&misc), "Disabled");
+
+        misc.handle_input(ArrowKey::Right, LOG_SOCKET_ROW).unwrap();
+        assert_eq!(misc.log_tcp_socket, Some(LOG_SOCKET));
+        assert_eq!(log_socket_shown(&misc), "127.0.0.1:7331");
+
+        misc.handle_input(ArrowKey::Right, LOG_SOCKET_ROW).unwrap();
+        assert_eq!(misc.log_tcp_socket, None);
+        misc.handle_input(ArrowKey::Left, LOG_SOCKET_ROW).unwrap();
+        assert_eq!(misc.log_tcp_socket, Some(LOG_SOCKET));
+    }
+
+    #[test]
+    fn custom_log_socket_kept_until_cycled() {
+        let custom: SocketAddr = "10.0.0.2:9000".parse().unwrap();
+        let mut misc = Misc {
+            log_tcp_socket: Some(custom),
+            ..Default::default()
+        };
+        assert_eq!(log_socket_shown(&misc), "10.0.0.2:9000");
+
+        // Past the end of the known addresses, so cycling forwards wraps around to None.
+        misc.handle_input(ArrowKey::Right, LOG_SOCKET_ROW).unwrap();
+        assert_eq!(misc.log_tcp_socket, None);
+
+        misc.log_tcp_socket = Some(custom);
+        misc.handle_input(ArrowKey::Left, LOG_SOCKET_ROW).unwrap();
+        assert_eq!(misc.log_tcp_socket, Some(LOG_SOCKET));
+    }
 }
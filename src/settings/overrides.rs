@@ -0,0 +1,310 @@
+use std::str::FromStr;
+
+use tracing::warn;
+
+use super::{Settings, SettingsError};
+
+/// Environment variables with this prefix override a setting,
+/// with the section and key split by [`ENV_SECTION_SEPARATOR`] (i.e. `YAP_SERIAL__BAUD_RATE=115200`).
+pub const SETTINGS_ENV_PREFIX: &str = "YAP_";
+const ENV_SECTION_SEPARATOR: &str = "__";
+
+#[derive(Debug, Clone, PartialEq)]
+/// A `section.key=value` given on the command line or through the environment,
+/// merged over the loaded settings without being saved to the settings file.
+pub struct SettingOverride {
+    pub section: String,
+    pub key: String,
+    pub value: toml::Value,
+    /// Set through the environment rather than `--set`, so it's skipped if invalid instead of failing.
+    pub from_env: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettingOverrideError {
+    #[error("setting override `{0}` is missing a value, expected `section.key=value`")]
+    MissingValue(String),
+    #[error("setting override `{0}` is missing a section, expected `section.key=value`")]
+    MissingSection(String),
+    #[error("no settings section named `{0}`")]
+    UnknownSection(String),
+    #[error("no setting named `{key}` in section `{section}`")]
+    UnknownKey { section: String, key: String },
+    #[error("invalid setting overrides")]
+    Invalid(#[from] toml::de::Error),
+    #[error("invalid value for setting `{section}.{key}`")]
+    InvalidValue {
+        section: String,
+        key: String,
+        #[source]
+        source: Box<toml::de::Error>,
+    },
+    #[error("failed settings serialization")]
+    Ser(#[from] toml::ser::Error),
+}
+
+impl FromStr for SettingOverride {
+    type Err = SettingOverrideError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, value) = s
+            .split_once('=')
+            .ok_or_else(|| SettingOverrideError::MissingValue(s.to_owned()))?;
+        let (section, key) = path
+            .trim()
+            .split_once('.')
+            .filter(|(section, key)| !section.is_empty() && !key.is_empty())
+            .ok_or_else(|| SettingOverrideError::MissingSection(path.to_owned()))?;
+        Ok(Self {
+            section: section.to_owned(),
+            key: key.to_owned(),
+            value: parse_value(value.trim()),
+            from_env: false,
+        })
+    }
+}
+
+impl std::fmt::Display for SettingOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}={}", self.section, self.key, self.value)
+    }
+}
+
+/// Read as a TOML value if it is one (numbers, bools, arrays, quoted strings),
+/// otherwise as a plain string, so enum variants and names don't need quoting.
+fn parse_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_owned()))
+}
+
+/// Every `YAP_<SECTION>__<KEY>` environment variable, as overrides.
+pub fn overrides_from_env() -> Vec<SettingOverride> {
+    std::env::vars()
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(SETTINGS_ENV_PREFIX)?;
+            let (section, key) = path.split_once(ENV_SECTION_SEPARATOR)?;
+            Some(SettingOverride {
+                section: section.to_lowercase(),
+                key: key.to_lowercase(),
+                value: parse_value(value.trim()),
+                from_env: true,
+            })
+        })
+        .collect()
+}
+
+/// Put the override's value into the settings table, returning the value it replaced.
+///
+/// Values from the environment are checked right away, so a bad one can be left out alone.
+fn insert_override(
+    table: &mut toml::Table,
+    setting: &SettingOverride,
+) -> Result<toml::Value, SettingOverrideError> {
+    let Some(toml::Value::Table(section)) = table.get_mut(&setting.section) else {
+        return Err(SettingOverrideError::UnknownSection(
+            setting.section.clone(),
+        ));
+    };
+    let Some(previous) = section.insert(setting.key.clone(), setting.value.clone()) else {
+        section.remove(&setting.key);
+        return Err(SettingOverrideError::UnknownKey {
+            section: setting.section.clone(),
+            key: setting.key.clone(),
+        });
+    };
+    if !setting.from_env {
+        return Ok(previous);
+    }
+    match toml::Value::Table(table.clone()).try_into::<Settings>() {
+        Ok(_) => Ok(previous),
+        Err(source) => {
+            if let Some(toml::Value::Table(section)) = table.get_mut(&setting.section) {
+                section.insert(setting.key.clone(), previous);
+            }
+            Err(SettingOverrideError::InvalidValue {
+                section: setting.section.clone(),
+                key: setting.key.clone(),
+                source: Box::new(source),
+            })
+        }
+    }
+}
+
+impl Settings {
+    /// Merge the overrides over these settings, later ones winning.
+    ///
+    /// The values they replaced are kept, so saving writes those instead of the overrides.
+    ///
+    /// Invalid overrides from `--set` are an error, while those from the environment are skipped
+    /// and returned instead, since a stray variable is no reason to not start.
+    pub fn apply_overrides(
+        &mut self,
+        overrides: Vec<SettingOverride>,
+    ) -> Result<Vec<SettingOverrideError>, SettingOverrideError> {
+        let mut table = toml::Table::try_from(&*self)?;
+        let mut replaced = Vec::with_capacity(overrides.len());
+        let mut skipped = Vec::new();
+
+        for setting in overrides {
+            let previous = match insert_override(&mut table, &setting) {
+                Ok(previous) => previous,
+                Err(e) if setting.from_env => {
+                    warn!("Ignoring setting override from environment: {e}");
+                    skipped.push(e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let same_key = |other: &SettingOverride| {
+                other.section == setting.section && other.key == setting.key
+            };
+            match replaced.iter_mut().find(|(other, _)| same_key(other)) {
+                // Given more than once, so what's saved is still from before the first.
+                Some((other, _)) => *other = setting,
+                None => replaced.push((setting, previous)),
+            }
+        }
+
+        let mut merged: Settings = toml::Value::Table(table).try_into()?;
+        merged.path = std::mem::take(&mut self.path);
+        merged.profile = self.profile.take();
+        merged.overrides = replaced;
+        *self = merged;
+        Ok(skipped)
+    }
+    /// Swap any still-overridden values for what they replaced, before writing to the settings file.
+    fn restore_overridden(&self, table: &mut toml::Table) {
+        for (setting, saved) in &self.overrides {
+            let Some(toml::Value::Table(section)) = table.get_mut(&setting.section) else {
+                continue;
+            };
+            // Changed in-app since, so that's worth keeping.
+            if section.get(&setting.key) != Some(&setting.value) {
+                continue;
+            }
+            section.insert(setting.key.clone(), saved.clone());
+        }
+    }
+    /// Serialized as it should be saved, without any overrides.
+    pub(super) fn to_saved_string(&self) -> Result<String, SettingsError> {
+        if self.overrides.is_empty() {
+            return Ok(toml::to_string(self)?);
+        }
+        let mut table = toml::Table::try_from(self)?;
+        self.restore_overridden(&mut table);
+        Ok(toml::to_string(&table)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(s: &str) -> SettingOverride {
+        s.parse().unwrap()
+    }
+
+    fn env(section: &str, key: &str, value: &str) -> SettingOverride {
+        SettingOverride {
+            section: section.to_owned(),
+            key: key.to_owned(),
+            value: parse_value(value),
+            from_env: true,
+        }
+    }
+
+    #[test]
+    fn overrides_parse_from_set_arguments() {
+        let setting = set(" serial.baud_rate = 115200 ");
+        assert_eq!(setting.section, "serial");
+        assert_eq!(setting.key, "baud_rate");
+        assert_eq!(setting.value, toml::Value::Integer(115200));
+        assert!(!setting.from_env);
+
+        assert!(matches!(
+            "serial.baud_rate".parse::<SettingOverride>(),
+            Err(SettingOverrideError::MissingValue(_))
+        ));
+        assert!(matches!(
+            "baud_rate=115200".parse::<SettingOverride>(),
+            Err(SettingOverrideError::MissingSection(_))
+        ));
+        assert!(matches!(
+            ".baud_rate=115200".parse::<SettingOverride>(),
+            Err(SettingOverrideError::MissingSection(_))
+        ));
+    }
+
+    #[test]
+    fn values_are_toml_or_plain_strings() {
+        assert_eq!(parse_value("42"), toml::Value::Integer(42));
+        assert_eq!(parse_value("false"), toml::Value::Boolean(false));
+        assert_eq!(
+            parse_value("[1, 2]"),
+            toml::Value::Array(vec![toml::Value::Integer(1), toml::Value::Integer(2)])
+        );
+        assert_eq!(
+            parse_value("\"quoted\""),
+            toml::Value::String("quoted".into())
+        );
+        assert_eq!(parse_value("CrLf"), toml::Value::String("CrLf".into()));
+        assert_eq!(parse_value(""), toml::Value::String(String::new()));
+    }
+
+    #[test]
+    fn overrides_are_applied_but_not_saved() {
+        let mut settings = Settings::default();
+        let saved_baud = settings.serial.baud_rate;
+
+        let skipped = settings
+            .apply_overrides(vec![
+                set("serial.baud_rate=9600"),
+                set("rendering.timestamps=false"),
+                set("serial.baud_rate=115200"),
+            ])
+            .unwrap();
+        assert!(skipped.is_empty());
+        assert_eq!(settings.serial.baud_rate, 115200);
+        assert!(!settings.rendering.timestamps);
+
+        let saved: Settings = toml::from_str(&settings.to_saved_string().unwrap()).unwrap();
+        assert_eq!(saved.serial.baud_rate, saved_baud);
+        assert!(saved.rendering.timestamps);
+    }
+
+    #[test]
+    fn invalid_overrides_only_fail_from_set() {
+        let mut settings = Settings::default();
+        assert!(matches!(
+            settings.apply_overrides(vec![set("nonsense.baud_rate=1")]),
+            Err(SettingOverrideError::UnknownSection(_))
+        ));
+        assert!(matches!(
+            settings.apply_overrides(vec![set("serial.nonsense=1")]),
+            Err(SettingOverrideError::UnknownKey { .. })
+        ));
+        assert!(matches!(
+            settings.apply_overrides(vec![set("serial.baud_rate=fast")]),
+            Err(SettingOverrideError::Invalid(_))
+        ));
+
+        let skipped = settings
+            .apply_overrides(vec![
+                env("nonsense", "baud_rate", "1"),
+                env("serial", "nonsense", "1"),
+                env("serial", "baud_rate", "fast"),
+                env("serial", "baud_rate", "9600"),
+            ])
+            .unwrap();
+        assert!(matches!(
+            skipped.as_slice(),
+            [
+                SettingOverrideError::UnknownSection(_),
+                SettingOverrideError::UnknownKey { .. },
+                SettingOverrideError::InvalidValue { .. },
+            ]
+        ));
+        assert_eq!(settings.serial.baud_rate, 9600);
+    }
+}
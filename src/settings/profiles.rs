@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use fs_err as fs;
+use ratatui::{
+    layout::Constraint,
+    widgets::{HighlightSpacing, Row, Table},
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::tui::theme::theme;
+
+#[cfg(feature = "defmt")]
+use super::Defmt;
+use super::{PortSettings, Rendering, Settings};
+
+/// Folder of `<name>.toml` settings profiles, one per file.
+pub const SETTINGS_PROFILES_DIR_PATH: &str = "settings_profiles";
+const PROFILE_EXTENSION: &str = "toml";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+/// A named set of settings to switch to all at once, i.e. per device or firmware being worked on.
+///
+/// Any section left out of the profile's file is kept as-is when switching.
+pub struct SettingsProfile {
+    pub serial: Option<PortSettings>,
+    pub rendering: Option<Rendering>,
+    #[cfg(feature = "defmt")]
+    pub defmt: Option<Defmt>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsProfileError {
+    #[error("failed reading settings profiles")]
+    FileRead(#[from] std::io::Error),
+    #[error("couldn't read settings profile `{name}`")]
+    Unreadable {
+        name: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid settings profile `{name}`")]
+    Deser {
+        name: String,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("no settings profile named `{0}`")]
+    NotFound(String),
+}
+
+#[derive(Debug, Default)]
+pub struct SettingsProfiles {
+    all: BTreeMap<String, SettingsProfile>,
+}
+
+impl SettingsProfiles {
+    /// Load every profile in the folder, skipping (and returning the errors of) any that can't be read.
+    ///
+    /// Only fails if the folder itself can't be read.
+    pub fn load_from_folder<P: AsRef<Utf8Path>>(
+        folder: P,
+    ) -> Result<(Self, Vec<SettingsProfileError>), SettingsProfileError> {
+        let folder = folder.as_ref();
+        let mut all = BTreeMap::new();
+        let mut errors = Vec::new();
+
+        if !folder.exists() {
+            return Ok((Self::default(), errors));
+        }
+
+        for entry in fs::read_dir(folder)? {
+            let path = entry?.path();
+            let Ok(path) = Utf8PathBuf::from_path_buf(path) else {
+                warn!("Settings profile path is not valid UTF-8! Skipping...");
+                continue;
+            };
+            if !path.is_file() || path.extension() != Some(PROFILE_EXTENSION) {
+                continue;
+            }
+            let Some(name) = path.file_stem() else {
+                continue;
+            };
+            let profile_toml = match fs::read_to_string(&path) {
+                Ok(profile_toml) => profile_toml,
+                Err(source) => {
+                    warn!("Skipping unreadable settings profile: {source}");
+                    errors.push(SettingsProfileError::Unreadable {
+                        name: name.to_owned(),
+                        source,
+                    });
+                    continue;
+                }
+            };
+            match toml::from_str(&profile_toml) {
+                Ok(profile) => _ = all.insert(name.to_owned(), profile),
+                Err(source) => {
+                    warn!("Skipping invalid settings profile {path}: {source}");
+                    errors.push(SettingsProfileError::Deser {
+                        name: name.to_owned(),
+                        source,
+                    });
+                }
+            }
+        }
+
+        Ok((Self { all }, errors))
+    }
+    /// Case-insensitive lookup, returning the profile's actual name alongside it.
+    pub fn get(&self, name: &str) -> Result<(&str, &SettingsProfile), SettingsProfileError> {
+        self.all
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(k, v)| (k.as_str(), v))
+            .ok_or_else(|| SettingsProfileError::NotFound(name.to_owned()))
+    }
+    pub fn nth_name(&self, nth: usize) -> Option<&str> {
+        self.all.keys().nth(nth).map(String::as_str)
+    }
+    pub fn len(&self) -> usize {
+        self.all.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.all.is_empty()
+    }
+    /// Each profile with the sections it carries, marking the active one.
+    pub fn as_table(&self, active: Option<&str>) -> Table<'static> {
+        let rows = self.all.iter().map(|(name, profile)| {
+            let mut sections = Vec::new();
+            if profile.serial.is_some() {
+                sections.push("Serial");
+            }
+            if profile.rendering.is_some() {
+                sections.push("Rendering");
+            }
+            #[cfg(feature = "defmt")]
+            if profile.defmt.is_some() {
+                sections.push("defmt");
+            }
+            let marker = if active == Some(name.as_str()) {
+                "*"
+            } else {
+                ""
+            };
+            Row::new(vec![marker.to_owned(), name.clone(), sections.join(", ")])
+        });
+
+        let constraints = [
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ];
+        Table::new(rows, constraints)
+            .row_highlight_style(theme().selection_style())
+            .highlight_spacing(HighlightSpacing::Always)
+            .highlight_symbol(">>")
+    }
+}
+
+impl Settings {
+    /// Replace each section the profile carries, remembering its name as the active profile.
+    ///
+    /// Doesn't save, that's left to the caller.
+    pub fn apply_profile(&mut self, name: &str, profile: &SettingsProfile) {
+        if let Some(serial) = &profile.serial {
+            self.serial = serial.clone();
+        }
+        if let Some(rendering) = &profile.rendering {
+            self.rendering = rendering.clone();
+        }
+        #[cfg(feature = "defmt")]
+        if let Some(defmt) = &profile.defmt {
+            self.defmt = defmt.clone();
+        }
+        self.profile = Some(name.to_owned());
+    }
+}
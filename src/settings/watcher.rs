@@ -0,0 +1,105 @@
+use std::{
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender, bounded};
+use fs_err as fs;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use takeable::Takeable;
+use tracing::{debug, error, info};
+
+use crate::{app::Event, file_watch::event_updates_file};
+
+#[derive(Debug)]
+pub enum SettingsWatchEvent {
+    /// The settings file was written to, and has since settled.
+    Changed,
+    Error(String),
+}
+
+impl From<SettingsWatchEvent> for Event {
+    fn from(value: SettingsWatchEvent) -> Self {
+        Self::SettingsWatch(value)
+    }
+}
+
+/// How long the settings file needs to go without changes before it's reloaded,
+/// so editors that write in several steps are only acted on once they're done.
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(300);
+
+/// Watches the settings file for outside edits, the worker closes once this is dropped.
+pub struct SettingsWatchHandle {
+    watcher: Takeable<RecommendedWatcher>,
+    worker: Takeable<JoinHandle<()>>,
+}
+
+impl SettingsWatchHandle {
+    pub fn build(settings_path: &Path, event_tx: Sender<Event>) -> Result<Self, notify::Error> {
+        let (watcher_tx, watcher_rx) = bounded(10);
+        let mut watcher = notify::recommended_watcher(watcher_tx)?;
+
+        // Events come in with absolute paths.
+        let settings_path = fs::canonicalize(settings_path).unwrap_or(settings_path.to_owned());
+        if let Some(parent) = settings_path.parent() {
+            watcher.watch(parent, RecursiveMode::NonRecursive)?;
+            info!("Watch started for: {}", settings_path.display());
+        }
+
+        let worker = std::thread::spawn(move || {
+            work_loop(settings_path, watcher_rx, event_tx);
+            debug!("Settings watcher closed!");
+        });
+
+        Ok(Self {
+            watcher: Takeable::new(watcher),
+            worker: Takeable::new(worker),
+        })
+    }
+}
+
+impl Drop for SettingsWatchHandle {
+    fn drop(&mut self) {
+        // Dropping the watcher disconnects the worker's channel, ending its loop.
+        drop(self.watcher.take());
+        if self.worker.take().join().is_err() {
+            error!("Settings watcher thread closed with an error!");
+        }
+    }
+}
+
+fn work_loop(
+    settings_path: PathBuf,
+    watcher_rx: Receiver<Result<notify::Event, notify::Error>>,
+    event_tx: Sender<Event>,
+) {
+    let mut last_update: Option<Instant> = None;
+    loop {
+        let received = match last_update {
+            Some(instant) => {
+                watcher_rx.recv_timeout(DEBOUNCE_DURATION.saturating_sub(instant.elapsed()))
+            }
+            None => watcher_rx
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let event = match received {
+            Ok(Ok(watcher_event)) => {
+                if event_updates_file(&watcher_event, &settings_path) {
+                    last_update = Some(Instant::now());
+                }
+                continue;
+            }
+            Ok(Err(e)) => SettingsWatchEvent::Error(format!("Settings file watcher error: {e}")),
+            Err(RecvTimeoutError::Timeout) => {
+                last_update = None;
+                SettingsWatchEvent::Changed
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if event_tx.send(event.into()).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! Helpers shared between unit tests.
+
+use camino::Utf8PathBuf;
+use fs_err as fs;
+
+/// A fresh folder for each test, since they run alongside each other.
+pub fn scratch_dir(name: &str) -> Utf8PathBuf {
+    let dir = std::env::temp_dir().join(format!("yap-{name}-{}", std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    Utf8PathBuf::from_path_buf(dir).unwrap()
+}
@@ -1,16 +1,42 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, io::Write};
 
 use arboard::Clipboard;
+use camino::Utf8Path;
 use crokey::crossterm::event::{Event, KeyEvent};
-use crossterm::event::KeyCode;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fs_err as fs;
 use itertools::Itertools;
 use num_integer::Integer;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use tracing::{error, warn};
 use tui_input::{Input, StateChanged, backend::crossterm::EventHandler};
 
 use crate::traits::{LastIndex as _, ToggleBool};
 
+/// File the Pseudo Shell's history is kept in between sessions, beside the config.
+pub const HISTORY_PATH: &str = "yap_history.jsonl";
+/// Only the newest entries are saved past this many.
+const MAX_SAVED_HISTORY: usize = 1000;
+/// Entries appended to the history file between each time it's compacted back down.
+const COMPACT_EVERY: usize = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryFileError {
+    #[error("failed reading from history file")]
+    FileRead(#[source] std::io::Error),
+    #[error("failed saving to history file")]
+    FileWrite(#[source] std::io::Error),
+}
+
+#[derive(Serialize, Deserialize)]
+/// A line of the history file.
+struct SavedEntry<'a> {
+    text: Cow<'a, str>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    bytes: bool,
+}
+
 pub struct TextInput {
     input_box: Input,
     bytes_input: bool,
@@ -22,6 +48,10 @@ pub struct TextInput {
     /// Cache for user's input for when the user begins scrolling/searching in history.
     preserved_input: Option<HistoryEntry<'static>>,
     search_result: Option<usize>,
+    /// Present while in the middle of an incremental (Ctrl+R style) search through history.
+    reverse_search: Option<ReverseSearch>,
+    /// Entries appended to the history file since it was last compacted.
+    history_file_appends: usize,
 
     last_word_regex: Regex,
 }
@@ -40,6 +70,8 @@ impl Default for TextInput {
             all_text_selected: false,
             preserved_input: None,
             search_result: None,
+            reverse_search: None,
+            history_file_appends: 0,
             history: History::new(),
             clipboard,
             bytes_input: false,
@@ -163,6 +195,145 @@ impl TextInput {
             self.input_box = result_text.as_str().into();
         }
     }
+    /// Every text (not byte) entry in history, newest first.
+    pub fn text_history(&self) -> impl Iterator<Item = &str> {
+        self.history
+            .inner
+            .iter()
+            .rev()
+            .filter(|h| h.is_text())
+            .map(HistoryEntry::as_str)
+    }
+    pub fn reverse_search(&self) -> Option<&ReverseSearch> {
+        self.reverse_search.as_ref()
+    }
+    /// Begin an incremental search back through history, or if one's already underway,
+    /// move on to the next older entry matching the query.
+    ///
+    /// Matches are previewed in the input box, with the user's own input kept to be put back if cancelled.
+    pub fn reverse_search_older(&mut self) {
+        let Some(search) = &self.reverse_search else {
+            self.preserve_input();
+            self.reverse_search = Some(ReverseSearch::default());
+            return;
+        };
+        let before = search.matched.unwrap_or(self.history.inner.len());
+        self.update_reverse_search(before);
+    }
+    /// Handle a key pressed during a reverse search.
+    ///
+    /// Typing edits the query, `Esc` and `Ctrl-G` cancel the search.
+    /// Any other key accepts the current match, returning `false` so it can be handled as usual afterwards.
+    pub fn reverse_search_key(&mut self, key: KeyEvent) -> bool {
+        let Some(search) = &mut self.reverse_search else {
+            return false;
+        };
+        let ctrl_pressed = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => self.cancel_reverse_search(),
+            KeyCode::Char('g') if ctrl_pressed => self.cancel_reverse_search(),
+            KeyCode::Char(c) if !ctrl_pressed => {
+                search.query.push(c);
+                // Searching again from the current match, since it may still match the longer query.
+                let before = search
+                    .matched
+                    .map_or(self.history.inner.len(), |index| index + 1);
+                self.update_reverse_search(before);
+            }
+            KeyCode::Backspace => {
+                search.query.pop();
+                // Starting over from the newest entry, as readline does.
+                self.update_reverse_search(self.history.inner.len());
+            }
+            _ => {
+                self.accept_reverse_search();
+                return false;
+            }
+        }
+        true
+    }
+    /// Look for the newest entry before the given index matching the search query,
+    /// previewing it if found, or marking the search as failing if not.
+    fn update_reverse_search(&mut self, before: usize) {
+        let Some(search) = &mut self.reverse_search else {
+            return;
+        };
+        if search.query.is_empty() {
+            search.matched = None;
+            search.failing = false;
+            // Nothing to match yet, so showing what the user had entered.
+            if let Some(preserved) = &self.preserved_input {
+                self.bytes_input = preserved.is_bytes();
+                self.input_box = preserved.as_str().into();
+            }
+            return;
+        }
+        let bytes_only = self
+            .preserved_input
+            .as_ref()
+            .map_or(self.bytes_input, HistoryEntry::is_bytes);
+        let query = search.query.to_lowercase();
+
+        let found = self.history.inner[..before.min(self.history.inner.len())]
+            .iter()
+            .rposition(|h| {
+                h.is_bytes() == bytes_only && h.as_str().to_lowercase().contains(&query)
+            });
+
+        match found {
+            Some(index) => {
+                search.matched = Some(index);
+                search.failing = false;
+                let entry = &self.history.inner[index];
+                self.bytes_input = entry.is_bytes();
+                self.input_box = entry.as_str().into();
+            }
+            // Leaving the last match in place, so there's still something to accept.
+            None => search.failing = true,
+        }
+    }
+    /// Keep the current match in the input box, ending the search.
+    ///
+    /// History can then be scrolled from the accepted entry, same as after finding one with Tab.
+    fn accept_reverse_search(&mut self) {
+        let Some(search) = self.reverse_search.take() else {
+            return;
+        };
+        match search.matched {
+            Some(index) => {
+                self.search_result = Some(index);
+                self.history.selected = Some(index);
+            }
+            None => self.restore_preserved_input(),
+        }
+    }
+    /// End the search, putting back whatever the user had entered before it started.
+    pub fn cancel_reverse_search(&mut self) {
+        if self.reverse_search.take().is_some() {
+            self.restore_preserved_input();
+        }
+    }
+    /// Cache the user's unsent input, if it isn't already, before replacing it with something from history.
+    fn preserve_input(&mut self) {
+        if self.preserved_input.is_some() {
+            return;
+        }
+        let input_to_preserve = self.input_box.value().to_owned();
+        let input_to_preserve = if self.bytes_input {
+            HistoryEntry::Bytes(input_to_preserve.into())
+        } else {
+            HistoryEntry::Text(input_to_preserve.into())
+        };
+        self.preserved_input = Some(input_to_preserve);
+    }
+    fn restore_preserved_input(&mut self) {
+        if let Some(preserved) = self.preserved_input.take() {
+            self.bytes_input = preserved.is_bytes();
+            self.input_box = preserved.as_str().into();
+        }
+        self.history.clear_selection();
+        self.search_result = None;
+    }
     pub fn entered_bytes_iter(&self) -> impl Iterator<Item = &str> {
         if !self.bytes_input {
             panic!("Should only be called when bytes_input is active!")
@@ -213,6 +384,7 @@ impl TextInput {
         self.history.clear_selection();
         self.preserved_input = None;
         self.search_result = None;
+        self.reverse_search = None;
         self.all_text_selected = false;
     }
     pub fn replace_input_with_text(&mut self, text: &str) {
@@ -230,6 +402,30 @@ impl TextInput {
         let current = self.input_box.value();
         self.input_box = format!("{current}{text}").into();
     }
+    /// Insert text at the cursor all at once, like from a paste.
+    ///
+    /// In byte-entry mode, anything that isn't a hex digit is left out.
+    pub fn insert_at_cursor(&mut self, text: &str) {
+        let text: Cow<str> = if self.bytes_input {
+            text.chars()
+                .filter(char::is_ascii_hexdigit)
+                .map(|c| c.to_ascii_uppercase())
+                .collect::<String>()
+                .into()
+        } else {
+            text.into()
+        };
+        self.clear_history_selection();
+
+        let value = self.input_box.value();
+        let cursor = self.input_box.cursor();
+        let split = value
+            .char_indices()
+            .nth(cursor)
+            .map_or(value.len(), |(index, _)| index);
+        let new_value = format!("{}{text}{}", &value[..split], &value[split..]);
+        self.input_box = Input::new(new_value).with_cursor(cursor + text.chars().count());
+    }
     pub fn consume_typing_event(&mut self, mut key: KeyEvent) {
         if self.bytes_input {
             match &mut key.code {
@@ -277,6 +473,147 @@ impl TextInput {
         self.history.push(self.input_box.value(), self.bytes_input);
         self.clear();
     }
+    /// Add entries from a previous session's history file, older than anything entered so far.
+    ///
+    /// A missing file is treated as empty, and lines that can't be read are skipped.
+    /// The file's compacted if it's grown past what's kept.
+    pub fn load_history(&mut self, path: &Utf8Path) -> Result<(), HistoryFileError> {
+        let _lock = lock_history_file(path).map_err(HistoryFileError::FileRead)?;
+        let Some((mut loaded, line_count)) = read_history_file(path)? else {
+            return Ok(());
+        };
+        if line_count > MAX_SAVED_HISTORY {
+            write_history_file(path, &loaded).map_err(HistoryFileError::FileWrite)?;
+        }
+
+        for entry in std::mem::take(&mut self.history.inner) {
+            loaded.push(entry.as_str(), entry.is_bytes());
+        }
+        self.history = loaded;
+        Ok(())
+    }
+    /// Add the newest history entry to the end of the history file,
+    /// compacting the file down to the newest entries every so often.
+    ///
+    /// Only the one line is written, so other instances appending to the same file aren't overwritten.
+    ///
+    /// Byte entries are only kept if `include_bytes` is set, and entries starting with a space
+    /// (a common way to keep passwords and the like out of shell histories) only with `include_space_prefixed`.
+    pub fn append_history(
+        &mut self,
+        path: &Utf8Path,
+        include_bytes: bool,
+        include_space_prefixed: bool,
+    ) -> Result<(), HistoryFileError> {
+        let Some(entry) = self.history.inner.last() else {
+            return Ok(());
+        };
+        if (!include_bytes && entry.is_bytes())
+            || (!include_space_prefixed && entry.as_str().starts_with(' '))
+        {
+            return Ok(());
+        }
+
+        let _lock = lock_history_file(path).map_err(HistoryFileError::FileWrite)?;
+        let mut file = private_file_options()
+            .append(true)
+            .open(path)
+            .map_err(HistoryFileError::FileWrite)?;
+        file.write_all(saved_line(entry).as_bytes())
+            .map_err(HistoryFileError::FileWrite)?;
+        drop(file);
+
+        self.history_file_appends += 1;
+        if self.history_file_appends >= COMPACT_EVERY {
+            self.history_file_appends = 0;
+            if let Some((saved, line_count)) = read_history_file(path)?
+                && line_count > MAX_SAVED_HISTORY
+            {
+                write_history_file(path, &saved).map_err(HistoryFileError::FileWrite)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Options for creating files only the user can read, since typed history can hold anything.
+fn private_file_options() -> fs::OpenOptions {
+    let mut options = fs::OpenOptions::new();
+    options.create(true).write(true);
+    #[cfg(unix)]
+    {
+        use fs_err::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// Held while reading or writing the history file, so other instances wait their turn.
+///
+/// A separate file is locked since compacting replaces the history file itself.
+fn lock_history_file(path: &Utf8Path) -> std::io::Result<fs::File> {
+    let file = private_file_options()
+        .truncate(false)
+        .open(path.with_extension("lock"))?;
+    file.file().lock()?;
+    Ok(file)
+}
+
+/// The newest entries of the history file, along with how many lines it has,
+/// or `None` if there isn't one yet.
+fn read_history_file(path: &Utf8Path) -> Result<Option<(History, usize)>, HistoryFileError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(HistoryFileError::FileRead(e)),
+    };
+
+    let mut history = History::new();
+    let mut line_count = 0;
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        line_count += 1;
+        match serde_json::from_str::<SavedEntry>(line) {
+            Ok(entry) => history.push(&entry.text, entry.bytes),
+            Err(e) => warn!("Skipping unreadable history entry: {e}"),
+        }
+    }
+    let excess = history.inner.len().saturating_sub(MAX_SAVED_HISTORY);
+    history.inner.drain(..excess);
+    Ok(Some((history, line_count)))
+}
+
+/// Replace the history file with the given entries,
+/// written beside it first so it's never left half-written.
+fn write_history_file(path: &Utf8Path, history: &History) -> std::io::Result<()> {
+    let contents: String = history.inner.iter().map(saved_line).collect();
+
+    let temp_path = path.with_extension("jsonl.tmp");
+    let mut file = private_file_options().truncate(true).open(&temp_path)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(&temp_path, path)
+}
+
+/// A history entry as a line of the history file.
+fn saved_line(entry: &HistoryEntry) -> String {
+    let saved = SavedEntry {
+        text: Cow::Borrowed(entry.as_str()),
+        bytes: entry.is_bytes(),
+    };
+    let mut line = serde_json::to_string(&saved).expect("history entries are always valid JSON");
+    line.push('\n');
+    line
+}
+
+#[derive(Debug, Default)]
+/// An incremental search through history, started with Ctrl+R.
+pub struct ReverseSearch {
+    pub query: String,
+    /// Index of the history entry currently previewed.
+    matched: Option<usize>,
+    /// If nothing (older) matches the query, the last match is left previewed.
+    pub failing: bool,
 }
 
 #[derive(Debug, Default)]
@@ -399,3 +736,218 @@ impl<'a> HistoryEntry<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::scratch_dir;
+
+    fn commit(input: &mut TextInput, text: &str) {
+        input.replace_input_with_text(text);
+        input.commit_input_to_history();
+    }
+
+    fn enter(input: &mut TextInput, text: &str, path: &Utf8Path) {
+        commit(input, text);
+        input.append_history(path, true, false).unwrap();
+    }
+
+    fn press(input: &mut TextInput, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        input.reverse_search_key(KeyEvent::new(code, modifiers))
+    }
+
+    fn type_query(input: &mut TextInput, query: &str) {
+        for c in query.chars() {
+            assert!(press(input, KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    /// A text input with some history, and something typed that's yet to be sent.
+    fn with_history(entries: &[&str]) -> TextInput {
+        let mut input = TextInput::default();
+        for entry in entries {
+            commit(&mut input, entry);
+        }
+        input.replace_input_with_text("unsent");
+        input
+    }
+
+    fn texts(input: &TextInput) -> Vec<&str> {
+        input.text_history().collect()
+    }
+
+    #[test]
+    fn instances_append_to_the_same_history_file() {
+        let path = scratch_dir("history-instances").join(HISTORY_PATH);
+        let mut first = TextInput::default();
+        let mut second = TextInput::default();
+        first.load_history(&path).unwrap();
+        second.load_history(&path).unwrap();
+
+        enter(&mut first, "help", &path);
+        enter(&mut second, "reset", &path);
+        enter(&mut first, " password", &path);
+        enter(&mut first, "status", &path);
+
+        let mut next_session = TextInput::default();
+        next_session.load_history(&path).unwrap();
+        assert_eq!(texts(&next_session), ["status", "reset", "help"]);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn history_file_compacted_to_the_newest_entries() {
+        let path = scratch_dir("history-compact").join(HISTORY_PATH);
+        let mut input = TextInput::default();
+        for index in 0..MAX_SAVED_HISTORY + COMPACT_EVERY {
+            enter(&mut input, &format!("line {index}"), &path);
+        }
+        let line_count = |path: &Utf8Path| fs::read_to_string(path).unwrap().lines().count();
+        assert_eq!(line_count(&path), MAX_SAVED_HISTORY);
+
+        // Anything past the limit from appending between compactions is trimmed on load.
+        enter(&mut input, "newest", &path);
+        assert_eq!(line_count(&path), MAX_SAVED_HISTORY + 1);
+
+        let mut next_session = TextInput::default();
+        next_session.load_history(&path).unwrap();
+        assert_eq!(line_count(&path), MAX_SAVED_HISTORY);
+        let history = texts(&next_session);
+        assert_eq!(history.len(), MAX_SAVED_HISTORY);
+        assert_eq!(history[0], "newest");
+        assert_eq!(history.last(), Some(&"line 101"));
+    }
+
+    #[test]
+    fn reverse_search_finds_older_matches() {
+        let mut input = with_history(&["reset", "Status", "help", "status all"]);
+        input.reverse_search_older();
+        // Nothing matched yet, so the user's input is still there.
+        assert_eq!(input.value(), "unsent");
+
+        type_query(&mut input, "sta");
+        assert_eq!(input.value(), "status all");
+        input.reverse_search_older();
+        assert_eq!(input.value(), "Status");
+        assert!(!input.reverse_search().unwrap().failing);
+
+        // Nothing older matches, so the last match stays.
+        input.reverse_search_older();
+        assert_eq!(input.value(), "Status");
+        assert!(input.reverse_search().unwrap().failing);
+
+        // A longer query still matching the current entry stays on it.
+        type_query(&mut input, "tus");
+        assert_eq!(input.value(), "Status");
+        assert!(!input.reverse_search().unwrap().failing);
+    }
+
+    #[test]
+    fn reverse_search_backspace_starts_over() {
+        let mut input = with_history(&["reset", "help", "restart"]);
+        input.reverse_search_older();
+        type_query(&mut input, "res");
+        input.reverse_search_older();
+        assert_eq!(input.value(), "reset");
+
+        assert!(press(&mut input, KeyCode::Backspace, KeyModifiers::NONE));
+        assert_eq!(input.reverse_search().unwrap().query, "re");
+        assert_eq!(input.value(), "restart");
+
+        // Emptying the query shows the user's input again.
+        press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+        press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(input.value(), "unsent");
+    }
+
+    #[test]
+    fn reverse_search_cancelled_puts_input_back() {
+        let mut input = with_history(&["help", "reset"]);
+        for cancel in [
+            (KeyCode::Esc, KeyModifiers::NONE),
+            (KeyCode::Char('g'), KeyModifiers::CONTROL),
+        ] {
+            input.reverse_search_older();
+            type_query(&mut input, "help");
+            assert_eq!(input.value(), "help");
+
+            assert!(press(&mut input, cancel.0, cancel.1));
+            assert!(input.reverse_search().is_none());
+            assert_eq!(input.value(), "unsent");
+        }
+    }
+
+    #[test]
+    fn reverse_search_accepted_by_other_keys() {
+        let mut input = with_history(&["first", "second", "third"]);
+        input.reverse_search_older();
+        type_query(&mut input, "second");
+
+        // Left to be handled as usual, with the match kept.
+        assert!(!press(&mut input, KeyCode::Enter, KeyModifiers::NONE));
+        assert!(input.reverse_search().is_none());
+        assert_eq!(input.value(), "second");
+        assert!(!press(&mut input, KeyCode::Char('x'), KeyModifiers::NONE));
+
+        // Scrolling carries on from the accepted entry.
+        input.scroll_history(true);
+        assert_eq!(input.value(), "first");
+
+        // Accepting without a match leaves the user's input.
+        let mut input = with_history(&["first"]);
+        input.reverse_search_older();
+        type_query(&mut input, "nope");
+        assert!(input.reverse_search().unwrap().failing);
+        press(&mut input, KeyCode::Up, KeyModifiers::NONE);
+        assert_eq!(input.value(), "unsent");
+    }
+
+    #[test]
+    fn reverse_search_keeps_to_the_entry_mode() {
+        let mut input = with_history(&["ab"]);
+        input.replace_input_with_bytes(&[0xAB]);
+        input.commit_input_to_history();
+        input.replace_input_with_bytes(&[0x01]);
+
+        input.reverse_search_older();
+        type_query(&mut input, "ab");
+        assert_eq!(input.value(), "AB");
+        assert!(input.byte_entry_active());
+
+        input.cancel_reverse_search();
+        assert_eq!(input.value(), "01");
+        input.toggle_bytes_entry();
+        input.replace_input_with_text("typed");
+
+        input.reverse_search_older();
+        type_query(&mut input, "AB");
+        assert_eq!(input.value(), "ab");
+        assert!(!input.byte_entry_active());
+    }
+
+    #[test]
+    fn byte_history_searched_by_prefix() {
+        let mut input = TextInput::default();
+        for bytes in [&[0xAB, 0x01][..], &[0xCD], &[0xAB, 0x02]] {
+            input.replace_input_with_bytes(bytes);
+            input.commit_input_to_history();
+        }
+        commit(&mut input, "ab");
+        input.replace_input_with_bytes(&[0xAB]);
+
+        input.find_input_in_history();
+        assert_eq!(input.value(), "AB02");
+        input.find_input_in_history();
+        assert_eq!(input.value(), "AB01");
+        // Nothing older, so staying on the last found.
+        input.find_input_in_history();
+        assert_eq!(input.value(), "AB01");
+        assert!(input.byte_entry_active());
+    }
+}
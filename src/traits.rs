@@ -2,10 +2,13 @@
 
 use std::{borrow::Cow, collections::BTreeMap, ops::Range};
 
-use crate::buffer::{HEX_UPPER, LineEnding};
+use crate::{
+    buffer::{HEX_UPPER, LineEnding},
+    tui::theme::theme,
+};
 use itertools::Itertools;
 use ratatui::{
-    style::{Style, Stylize},
+    style::Style,
     text::{Line, Span},
 };
 
@@ -166,7 +169,7 @@ impl<'a> LineHelpers<'a> for Line<'a> {
                 line_char_index += c.len_utf8();
             });
         // let now2 = Instant::now();
-        let dark_gray = Style::new().dark_gray();
+        let dim = theme().dim_style();
 
         let mut offset: isize = 0;
         for (index, char) in chars_to_escape {
@@ -180,18 +183,18 @@ impl<'a> LineHelpers<'a> for Line<'a> {
                 // TODO handle tab width properly?
                 '\t' => {
                     let tab = "\\t";
-                    self.insert_slice(corrected, tab, Some(dark_gray));
+                    self.insert_slice(corrected, tab, Some(dim));
                     tab.len()
                 }
                 _ if !replace => 0,
                 '\n' => {
                     let lf = "\\n";
-                    self.insert_slice(corrected, lf, Some(dark_gray));
+                    self.insert_slice(corrected, lf, Some(dim));
                     lf.len()
                 }
                 '\r' => {
                     let cr = "\\r";
-                    self.insert_slice(corrected, cr, Some(dark_gray));
+                    self.insert_slice(corrected, cr, Some(dim));
                     cr.len()
                 }
                 _ => {
@@ -201,10 +204,10 @@ impl<'a> LineHelpers<'a> for Line<'a> {
 
                     for i in 0..len {
                         let hex_prefix = "\\x";
-                        self.insert_slice(corrected + added_len, hex_prefix, Some(dark_gray));
+                        self.insert_slice(corrected + added_len, hex_prefix, Some(dim));
                         added_len += hex_prefix.len();
                         let upper_hex = HEX_UPPER[buffer[i] as usize];
-                        self.insert_slice(corrected + added_len, upper_hex, Some(dark_gray));
+                        self.insert_slice(corrected + added_len, upper_hex, Some(dim));
                         added_len += upper_hex.len();
                     }
                     added_len
@@ -0,0 +1,78 @@
+use crokey::KeyCombination;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Row, Table},
+};
+
+use crate::keybinds::{ChordContinuation, Keybinds, chord_to_string};
+
+use super::theme::theme;
+
+/// Small box in the bottom-right corner listing every key that can follow the chord entered so far.
+pub fn render_chord_hint(
+    keybinds: &Keybinds,
+    pending: &[KeyCombination],
+    frame: &mut Frame,
+    screen: Rect,
+) {
+    let continuations = keybinds.chord_continuations(pending);
+
+    let rows: Vec<(String, Line)> = continuations
+        .into_iter()
+        .map(|(key_combo, continuation)| {
+            let description = match continuation {
+                ChordContinuation::Actions(actions) => Line::raw(actions.iter().join(", ")),
+                ChordContinuation::Prefix(count) => {
+                    Line::raw(format!("+{count} more")).cyan().italic()
+                }
+            };
+            (key_combo.to_string(), description)
+        })
+        .collect();
+
+    let key_width = rows
+        .iter()
+        .map(|(key, _)| key.len())
+        .max()
+        .unwrap_or_default() as u16;
+    let description_width = rows
+        .iter()
+        .map(|(_, description)| description.width())
+        .max()
+        .unwrap_or_default() as u16;
+
+    let title = format!(" {} ", chord_to_string(pending));
+    let width = (key_width + description_width + 2)
+        .max(title.len() as u16)
+        .saturating_add(2)
+        .min(screen.width);
+    let height = (rows.len() as u16 + 2).min(screen.height);
+    // Kept clear of the input line at the very bottom.
+    let area = Rect {
+        x: screen.width.saturating_sub(width),
+        y: screen.height.saturating_sub(height + 1),
+        width,
+        height,
+    };
+
+    let block = Block::bordered()
+        .border_style(Style::new().fg(theme().highlight))
+        .title_top(Line::raw(title).reset())
+        .title_bottom(Line::raw(" Esc: Cancel ").centered().reset());
+
+    let table = Table::new(
+        rows.into_iter().map(|(key, description)| {
+            Row::new([Line::raw(key).fg(theme().highlight), description])
+        }),
+        [Constraint::Length(key_width), Constraint::Fill(1)],
+    )
+    .column_spacing(2)
+    .block(block);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(table, area);
+}
@@ -0,0 +1,90 @@
+use strum::{VariantArray, VariantNames};
+
+use crate::{app::SettingsMenu, keybinds::builtin_action_groups};
+
+#[cfg(feature = "macros")]
+use crate::macros::{MacroContent, MacroNameTag};
+
+use super::fuzzy_list::{FuzzyList, FuzzyListEntry};
+
+#[derive(Debug, Clone)]
+/// What's done when an entry in the palette is picked.
+pub enum PaletteCommand {
+    /// A builtin action, by the name it's written as in keybinds.
+    Action(String),
+    #[cfg(feature = "macros")]
+    Macro(MacroNameTag),
+    Settings(SettingsMenu),
+}
+
+#[derive(Debug)]
+pub struct PaletteEntry {
+    /// What sort of entry this is, like `Action` or `Macro`.
+    pub kind: &'static str,
+    pub name: String,
+    pub description: String,
+    pub command: PaletteCommand,
+}
+
+impl PaletteEntry {
+    /// Every builtin action, then every settings menu.
+    pub fn builtin() -> Vec<Self> {
+        let actions = builtin_action_groups()
+            .into_iter()
+            .flat_map(|(_, actions)| actions)
+            .map(|(name, doc_comment)| Self {
+                kind: "Action",
+                description: doc_comment.to_owned(),
+                command: PaletteCommand::Action(name.clone()),
+                name,
+            });
+        let settings = <SettingsMenu as VariantArray>::VARIANTS
+            .iter()
+            .zip(<SettingsMenu as VariantNames>::VARIANTS)
+            .map(|(menu, name)| Self {
+                kind: "Settings",
+                name: (*name).to_owned(),
+                description: format!("Open the {name} settings menu."),
+                command: PaletteCommand::Settings(menu.clone()),
+            });
+        actions.chain(settings).collect()
+    }
+    #[cfg(feature = "macros")]
+    pub fn from_macro(tag: &MacroNameTag, content: &MacroContent) -> Self {
+        let description = if content.sensitive {
+            "Sensitive macro, contents hidden.".to_owned()
+        } else if content.is_sequence() {
+            format!("Sequence of {} steps.", content.steps.len())
+        } else {
+            content.as_str().to_owned()
+        };
+        Self {
+            kind: "Macro",
+            name: tag.to_string(),
+            description,
+            command: PaletteCommand::Macro(tag.clone()),
+        }
+    }
+}
+
+/// Popup for finding and running any action, macro, or settings menu by name.
+///
+/// Entries are rebuilt each time the palette's opened, since macros can change while running.
+pub type CommandPalette = FuzzyList<PaletteEntry>;
+
+impl FuzzyListEntry for PaletteEntry {
+    const TITLE: &'static str = "Command Palette";
+    const FOOTER: &'static str = "Enter: Run | Esc: Close";
+    const NO_MATCHES: &'static str = "Nothing matches.";
+    const MAX_WIDTH: u16 = 80;
+
+    fn kind(&self) -> &str {
+        self.kind
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn description(&self) -> &str {
+        &self.description
+    }
+}
@@ -0,0 +1,258 @@
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Row, Table, TableState},
+};
+
+use super::theme::theme;
+
+/// Most candidates kept from history, and again from words, so neither buries the other.
+const MAX_PER_SOURCE: usize = 20;
+/// Rows shown at once, the rest are scrolled to.
+const SHOWN_ROWS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    /// A previously sent line, replacing the whole input.
+    History,
+    /// A word seen in recently received lines.
+    Received,
+    #[cfg(feature = "macros")]
+    Macro,
+}
+
+impl CompletionKind {
+    fn label(self) -> &'static str {
+        match self {
+            CompletionKind::History => "History",
+            CompletionKind::Received => "RX",
+            #[cfg(feature = "macros")]
+            CompletionKind::Macro => "Macro",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Completion {
+    pub kind: CompletionKind,
+    /// What's shown in the popup, either the whole line or just the completed word.
+    pub label: String,
+    /// The whole input once this is picked.
+    pub input: String,
+}
+
+/// If `candidate` starts with `prefix` (ignoring ASCII case) and has more after it.
+fn extends(candidate: &str, prefix: &str) -> bool {
+    candidate.len() > prefix.len()
+        && candidate.is_char_boundary(prefix.len())
+        && candidate[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
+
+#[derive(Debug)]
+/// Candidates for completing the Pseudo Shell's input, cycled through with Tab and Shift-Tab.
+pub struct Completions {
+    /// Input from before cycling began, put back if cancelled.
+    original: String,
+    candidates: Vec<Completion>,
+    selected: usize,
+}
+
+impl Completions {
+    /// Gather candidates for the given input: previously sent lines starting with all of it,
+    /// then words (like those received, or macro names) starting with the word being typed.
+    ///
+    /// Returns `None` if nothing would complete the input any further.
+    pub fn new<'a>(
+        input: &str,
+        history: impl Iterator<Item = &'a str>,
+        words: impl Iterator<Item = (CompletionKind, &'a str)>,
+    ) -> Option<Self> {
+        if input.is_empty() {
+            return None;
+        }
+        let word = input.rsplit(char::is_whitespace).next().unwrap_or_default();
+        let head = &input[..input.len() - word.len()];
+
+        let from_history = history
+            .filter(|line| extends(line, input))
+            .take(MAX_PER_SOURCE)
+            .map(|line| Completion {
+                kind: CompletionKind::History,
+                label: line.to_owned(),
+                input: line.to_owned(),
+            });
+
+        // Only completing words once one's been started, otherwise every word would match.
+        let from_words = words
+            .filter(|(_, completed)| !word.is_empty() && extends(completed, word))
+            .take(MAX_PER_SOURCE)
+            .map(|(kind, completed)| Completion {
+                kind,
+                label: completed.to_owned(),
+                input: format!("{head}{completed}"),
+            });
+
+        let candidates: Vec<Completion> = from_history
+            .chain(from_words)
+            .unique_by(|completion| completion.input.clone())
+            .collect();
+
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(Self {
+            original: input.to_owned(),
+            candidates,
+            selected: 0,
+        })
+    }
+    /// Input from before any candidate was picked.
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+    /// The whole input with the selected candidate picked.
+    pub fn selected_input(&self) -> &str {
+        &self.candidates[self.selected].input
+    }
+    /// If there's nothing to cycle through, only the one candidate.
+    pub fn is_single(&self) -> bool {
+        self.candidates.len() == 1
+    }
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.candidates.len();
+    }
+    pub fn select_prev(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(self.candidates.len() - 1);
+    }
+    /// Render just above the bottom of the given area, starting from column `x`.
+    pub fn render(&self, frame: &mut Frame, above: Rect, x: u16) {
+        let kind_width = self
+            .candidates
+            .iter()
+            .map(|completion| completion.kind.label().len())
+            .max()
+            .unwrap_or_default() as u16;
+        let label_width = self
+            .candidates
+            .iter()
+            .map(|completion| Line::raw(completion.label.as_str()).width())
+            .max()
+            .unwrap_or_default() as u16;
+
+        // Borders, plus the spacing between columns.
+        let width = (kind_width + label_width + 4).min(above.width);
+        let height = (self.candidates.len().min(SHOWN_ROWS) as u16 + 2).min(above.height);
+        let x = x.min(above.right().saturating_sub(width));
+        let area = Rect::new(x, above.bottom().saturating_sub(height), width, height);
+
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(
+                Line::raw(format!(" {}/{} ", self.selected + 1, self.candidates.len())).reset(),
+            );
+
+        let rows = self.candidates.iter().map(|completion| {
+            Row::new([
+                Line::styled(completion.kind.label(), theme().dim_style()),
+                Line::raw(completion.label.as_str()),
+            ])
+        });
+        let table = Table::new(rows, [Constraint::Length(kind_width), Constraint::Fill(1)])
+            .block(block)
+            .column_spacing(1)
+            .row_highlight_style(theme().selection_style());
+        let mut table_state = TableState::new().with_selected(Some(self.selected));
+
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(table, area, &mut table_state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompletionKind, Completions, MAX_PER_SOURCE};
+
+    fn received<'a>(words: &'a [&'a str]) -> impl Iterator<Item = (CompletionKind, &'a str)> {
+        words.iter().map(|word| (CompletionKind::Received, *word))
+    }
+
+    fn inputs(completions: &mut Completions) -> Vec<String> {
+        let mut inputs = Vec::new();
+        for _ in 0..completions.candidates.len() {
+            inputs.push(completions.selected_input().to_owned());
+            completions.select_next();
+        }
+        inputs
+    }
+
+    #[test]
+    fn nothing_to_complete() {
+        let history = ["reset", "help"];
+        assert!(Completions::new("", history.into_iter(), received(&["reset"])).is_none());
+        assert!(Completions::new("x", history.into_iter(), received(&["reset"])).is_none());
+        // Only candidates that add something are offered.
+        assert!(Completions::new("reset", history.into_iter(), received(&["RESET"])).is_none());
+        // Words are only completed once one's started.
+        assert!(Completions::new("set ", [].into_iter(), received(&["mode"])).is_none());
+    }
+
+    #[test]
+    fn history_then_words_without_duplicates() {
+        let history = ["set mode fast", "status", "Set Mode Slow"];
+        let words = ["mode", "modem", "fast"];
+        let mut completions =
+            Completions::new("set mo", history.into_iter(), received(&words)).unwrap();
+
+        assert_eq!(completions.original(), "set mo");
+        assert!(!completions.is_single());
+        assert_eq!(
+            inputs(&mut completions),
+            ["set mode fast", "Set Mode Slow", "set mode", "set modem"]
+        );
+        assert_eq!(completions.candidates[2].label, "mode");
+
+        // Picking the word again, when history already has the same input.
+        let completions =
+            Completions::new("st", ["status"].into_iter(), received(&["status"])).unwrap();
+        assert!(completions.is_single());
+        assert_eq!(completions.selected_input(), "status");
+        assert_eq!(completions.candidates[0].kind, CompletionKind::History);
+    }
+
+    #[test]
+    fn selection_wraps_both_ways() {
+        let mut completions =
+            Completions::new("a", ["ab", "ac", "ad"].into_iter(), received(&[])).unwrap();
+        completions.select_prev();
+        assert_eq!(completions.selected_input(), "ad");
+        completions.select_next();
+        assert_eq!(completions.selected_input(), "ab");
+        completions.select_next();
+        completions.select_next();
+        completions.select_next();
+        assert_eq!(completions.selected_input(), "ab");
+    }
+
+    #[test]
+    fn each_source_capped() {
+        let lines: Vec<String> = (0..MAX_PER_SOURCE * 2).map(|i| format!("w{i}")).collect();
+        let words: Vec<String> = lines.iter().map(|line| format!("{line}x")).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        let completions =
+            Completions::new("w", lines.iter().map(String::as_str), received(&words)).unwrap();
+        let from_history = completions
+            .candidates
+            .iter()
+            .filter(|completion| completion.kind == CompletionKind::History)
+            .count();
+        assert_eq!(from_history, MAX_PER_SOURCE);
+        assert_eq!(completions.candidates.len(), MAX_PER_SOURCE * 2);
+    }
+}
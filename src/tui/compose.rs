@@ -0,0 +1,311 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Rect, Size},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+};
+
+use super::{centered_rect_size, theme::theme};
+
+#[derive(Debug)]
+/// Multi-line text being written in the compose popup, sent a line at a time once submitted.
+pub struct ComposeEditor {
+    /// Always has at least one line, even if empty.
+    lines: Vec<String>,
+    cursor_row: usize,
+    /// In chars rather than bytes.
+    cursor_col: usize,
+    /// First line shown, kept so the cursor's always in view.
+    scroll: usize,
+}
+
+impl Default for ComposeEditor {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            cursor_row: 0,
+            cursor_col: 0,
+            scroll: 0,
+        }
+    }
+}
+
+/// Byte index of the char at `col`, or the end of the line if it's past the last char.
+fn byte_index(line: &str, col: usize) -> usize {
+    line.char_indices()
+        .nth(col)
+        .map_or(line.len(), |(index, _)| index)
+}
+
+impl ComposeEditor {
+    pub fn is_empty(&self) -> bool {
+        self.lines.iter().all(String::is_empty)
+    }
+    /// Replace the draft with the given text, cursor at the end.
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.lines().map(str::to_owned).collect();
+        if self.lines.is_empty() {
+            self.lines.push(String::new());
+        }
+        self.cursor_row = self.lines.len() - 1;
+        self.cursor_col = self.lines[self.cursor_row].chars().count();
+    }
+    /// Take every line written, leaving the editor empty.
+    ///
+    /// Trailing empty lines are left out, since they're usually just from pressing Enter one time too many.
+    pub fn take_lines(&mut self) -> Vec<String> {
+        let mut lines = std::mem::take(self).lines;
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        lines
+    }
+    /// Insert text at the cursor all at once, like from a paste, keeping its line breaks.
+    pub fn insert_str(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let line = &mut self.lines[self.cursor_row];
+        let rest = line.split_off(byte_index(line, self.cursor_col));
+
+        let mut pasted_lines = text.split('\n');
+        if let Some(first) = pasted_lines.next() {
+            line.push_str(first);
+        }
+        for pasted in pasted_lines {
+            self.cursor_row += 1;
+            self.lines.insert(self.cursor_row, pasted.to_owned());
+        }
+
+        let line = &mut self.lines[self.cursor_row];
+        self.cursor_col = line.chars().count();
+        line.push_str(&rest);
+    }
+    fn current_line_len(&self) -> usize {
+        self.lines[self.cursor_row].chars().count()
+    }
+    /// Edit or move around the draft, anything that isn't an editing key is ignored.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let line = &mut self.lines[self.cursor_row];
+                line.insert(byte_index(line, self.cursor_col), c);
+                self.cursor_col += 1;
+            }
+            KeyCode::Enter => {
+                let line = &mut self.lines[self.cursor_row];
+                let rest = line.split_off(byte_index(line, self.cursor_col));
+                self.cursor_row += 1;
+                self.cursor_col = 0;
+                self.lines.insert(self.cursor_row, rest);
+            }
+            KeyCode::Backspace if self.cursor_col > 0 => {
+                self.cursor_col -= 1;
+                let line = &mut self.lines[self.cursor_row];
+                line.remove(byte_index(line, self.cursor_col));
+            }
+            KeyCode::Backspace if self.cursor_row > 0 => {
+                let line = self.lines.remove(self.cursor_row);
+                self.cursor_row -= 1;
+                self.cursor_col = self.current_line_len();
+                self.lines[self.cursor_row].push_str(&line);
+            }
+            KeyCode::Delete if self.cursor_col < self.current_line_len() => {
+                let line = &mut self.lines[self.cursor_row];
+                line.remove(byte_index(line, self.cursor_col));
+            }
+            KeyCode::Delete if self.cursor_row + 1 < self.lines.len() => {
+                let next = self.lines.remove(self.cursor_row + 1);
+                self.lines[self.cursor_row].push_str(&next);
+            }
+            KeyCode::Left if self.cursor_col > 0 => self.cursor_col -= 1,
+            KeyCode::Left if self.cursor_row > 0 => {
+                self.cursor_row -= 1;
+                self.cursor_col = self.current_line_len();
+            }
+            KeyCode::Right if self.cursor_col < self.current_line_len() => self.cursor_col += 1,
+            KeyCode::Right if self.cursor_row + 1 < self.lines.len() => {
+                self.cursor_row += 1;
+                self.cursor_col = 0;
+            }
+            KeyCode::Up if self.cursor_row > 0 => {
+                self.cursor_row -= 1;
+                self.cursor_col = self.cursor_col.min(self.current_line_len());
+            }
+            KeyCode::Down if self.cursor_row + 1 < self.lines.len() => {
+                self.cursor_row += 1;
+                self.cursor_col = self.cursor_col.min(self.current_line_len());
+            }
+            KeyCode::Home => self.cursor_col = 0,
+            KeyCode::End => self.cursor_col = self.current_line_len(),
+            _ => (),
+        }
+    }
+    pub fn render(&mut self, frame: &mut Frame, screen: Rect) {
+        let area = centered_rect_size(
+            Size {
+                width: screen.width.min(80),
+                height: screen.height.saturating_sub(2).min(17),
+            },
+            screen,
+        );
+
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(
+                Line::raw(format!(" Compose ({} lines) ", self.lines.len()))
+                    .centered()
+                    .reset(),
+            )
+            .title_bottom(
+                Line::raw(" Alt-Enter/Ctrl-S: Send | Esc: Close ")
+                    .centered()
+                    .reset(),
+            );
+        let inner = block.inner(area);
+
+        let height = (inner.height as usize).max(1);
+        if self.cursor_row < self.scroll {
+            self.scroll = self.cursor_row;
+        } else if self.cursor_row >= self.scroll + height {
+            self.scroll = self.cursor_row + 1 - height;
+        }
+
+        let text: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .take(height)
+            .map(|line| Line::raw(line.as_str()))
+            .collect();
+
+        let cursor_line = &self.lines[self.cursor_row];
+        let cursor_x = Line::raw(&cursor_line[..byte_index(cursor_line, self.cursor_col)]).width();
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(text).block(block), area);
+        frame.set_cursor_position((
+            inner.x + (cursor_x as u16).min(inner.width.saturating_sub(1)),
+            inner.y + (self.cursor_row - self.scroll) as u16,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::ComposeEditor;
+
+    fn press(editor: &mut ComposeEditor, code: KeyCode) {
+        editor.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn type_str(editor: &mut ComposeEditor, text: &str) {
+        for c in text.chars() {
+            press(editor, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn enter_splits_lines_at_the_cursor() {
+        let mut editor = ComposeEditor::default();
+        assert!(editor.is_empty());
+
+        type_str(&mut editor, "helloworld");
+        for _ in 0..5 {
+            press(&mut editor, KeyCode::Left);
+        }
+        press(&mut editor, KeyCode::Enter);
+        type_str(&mut editor, "> ");
+        assert!(!editor.is_empty());
+
+        assert_eq!(editor.take_lines(), ["hello", "> world"]);
+        assert!(editor.is_empty());
+    }
+
+    #[test]
+    fn backspace_and_delete_join_lines() {
+        let mut editor = ComposeEditor::default();
+        editor.set_text("ab\ncd");
+
+        // From the start of the second line, joining it onto the first.
+        press(&mut editor, KeyCode::Home);
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(editor.lines, ["abcd"]);
+        assert_eq!((editor.cursor_row, editor.cursor_col), (0, 2));
+
+        press(&mut editor, KeyCode::Enter);
+        press(&mut editor, KeyCode::Up);
+        press(&mut editor, KeyCode::End);
+        press(&mut editor, KeyCode::Delete);
+        assert_eq!(editor.lines, ["abcd"]);
+
+        // Nothing before the start or after the end to take.
+        press(&mut editor, KeyCode::End);
+        press(&mut editor, KeyCode::Delete);
+        press(&mut editor, KeyCode::Home);
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(editor.lines, ["abcd"]);
+    }
+
+    #[test]
+    fn cursor_moves_by_chars_across_lines() {
+        let mut editor = ComposeEditor::default();
+        editor.set_text("héllo\nä");
+        assert_eq!((editor.cursor_row, editor.cursor_col), (1, 1));
+
+        // Wrapping back onto the end of the line above.
+        press(&mut editor, KeyCode::Left);
+        press(&mut editor, KeyCode::Left);
+        assert_eq!((editor.cursor_row, editor.cursor_col), (0, 5));
+        press(&mut editor, KeyCode::Right);
+        assert_eq!((editor.cursor_row, editor.cursor_col), (1, 0));
+
+        // Moving between lines keeps the column where it can.
+        press(&mut editor, KeyCode::Up);
+        press(&mut editor, KeyCode::End);
+        press(&mut editor, KeyCode::Down);
+        assert_eq!((editor.cursor_row, editor.cursor_col), (1, 1));
+
+        press(&mut editor, KeyCode::Up);
+        press(&mut editor, KeyCode::Home);
+        press(&mut editor, KeyCode::Right);
+        press(&mut editor, KeyCode::Backspace);
+        assert_eq!(editor.lines, ["éllo", "ä"]);
+        type_str(&mut editor, "ö");
+        assert_eq!(editor.lines, ["öéllo", "ä"]);
+    }
+
+    #[test]
+    fn ctrl_keys_not_typed() {
+        let mut editor = ComposeEditor::default();
+        editor.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert!(editor.is_empty());
+    }
+
+    #[test]
+    fn pastes_keep_their_line_breaks() {
+        let mut editor = ComposeEditor::default();
+        type_str(&mut editor, "ab");
+        press(&mut editor, KeyCode::Left);
+        editor.insert_str("1\r\n2\r3\n4");
+
+        assert_eq!(editor.lines, ["a1", "2", "3", "4b"]);
+        assert_eq!((editor.cursor_row, editor.cursor_col), (3, 1));
+    }
+
+    #[test]
+    fn trailing_empty_lines_not_taken() {
+        let mut editor = ComposeEditor::default();
+        editor.set_text("first\n\nsecond");
+        press(&mut editor, KeyCode::Enter);
+        press(&mut editor, KeyCode::Enter);
+
+        assert_eq!(editor.take_lines(), ["first", "", "second"]);
+
+        editor.set_text("");
+        assert!(editor.is_empty());
+        assert!(editor.take_lines().is_empty());
+    }
+}
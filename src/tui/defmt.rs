@@ -5,6 +5,8 @@ use ratatui::{
 };
 
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::SystemTime};
+use tui_input::Input;
 
 use fs_err as fs;
 
@@ -16,7 +18,10 @@ use {
     takeable::Takeable,
 };
 
-use crate::config_adjacent_path;
+use crate::{
+    config_adjacent_path,
+    tui::{prompts::PromptKeybind, theme::theme},
+};
 
 const DEFMT_RECENT_PATH: &str = "yap_defmt_recent.toml";
 
@@ -24,11 +29,20 @@ const DEFMT_RECENT_MAX_AMOUNT: usize = 10;
 
 pub const DEFMT_BUTTONS: usize = 2;
 
+/// How many directories deep to look for ELFs,
+/// enough to reach `target/<triple>/<profile>/examples/<binary>`.
+const ELF_SEARCH_MAX_DEPTH: usize = 4;
+
+/// Directories in a cargo target dir that never hold final binaries.
+const ELF_SEARCH_SKIPPED_DIRS: &[&str] = &["deps", "build", "incremental", ".fingerprint", "doc"];
+
 /// This intentionally holds no decoders! Those're held by Arcs owned by the Buffer and Logging workers.
 ///
 /// This holds the recently used ELF paths, and the handle to the worker thread that watches for updates to the current ELF.
 pub struct DefmtHelpers {
     pub recent_elfs: DefmtRecentElfs,
+    /// Text entry for the module filter popup.
+    pub module_filter_input: Input,
     #[cfg(feature = "defmt-watch")]
     pub watcher_handle: ElfWatchHandle,
     #[cfg(feature = "defmt-watch")]
@@ -71,6 +85,7 @@ impl DefmtHelpers {
             let watcher_join_handle = Takeable::new(watcher_join_handle);
             Ok(Self {
                 recent_elfs: DefmtRecentElfs::load()?,
+                module_filter_input: Input::default(),
                 watcher_handle,
                 watcher_join_handle,
             })
@@ -79,6 +94,7 @@ impl DefmtHelpers {
         #[cfg(not(feature = "defmt-watch"))]
         Ok(Self {
             recent_elfs: DefmtRecentElfs::load()?,
+            module_filter_input: Input::default(),
         })
     }
 }
@@ -89,6 +105,12 @@ pub struct DefmtRecentElfs {
     last: Option<Utf8PathBuf>,
     #[serde(default)]
     recent: Vec<Utf8PathBuf>,
+    /// Always listed first, and never pushed out by newer ELFs.
+    #[serde(default)]
+    pinned: Vec<Utf8PathBuf>,
+    /// MD5 of each ELF, as of when it was last loaded.
+    #[serde(default)]
+    md5: BTreeMap<Utf8PathBuf, String>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -121,16 +143,59 @@ impl DefmtRecentElfs {
             Ok(DefmtRecentElfs::default())
         }
     }
-    pub fn elf_loaded(&mut self, newest: &Utf8Path) -> Result<(), DefmtRecentError> {
+    pub fn elf_loaded(&mut self, newest: &Utf8Path, md5: &str) -> Result<(), DefmtRecentError> {
         let _ = self.last.insert(newest.to_owned());
         if let Some(found_index) = self.recent.iter().position(|p| *p == newest) {
             let element = self.recent.remove(found_index);
             self.recent.insert(0, element);
-        } else {
+        } else if !self.pinned.iter().any(|p| *p == newest) {
             self.recent.insert(0, newest.to_owned());
         }
 
         self.recent.truncate(DEFMT_RECENT_MAX_AMOUNT);
+        self.md5.insert(newest.to_owned(), md5.to_owned());
+
+        self.save()
+    }
+    /// Pin the nth listed ELF, or unpin it if already pinned.
+    pub fn toggle_pin(&mut self, nth: usize) -> Result<(), DefmtRecentError> {
+        let Some(path) = self.nth_path(nth).map(ToOwned::to_owned) else {
+            return Ok(());
+        };
+        if let Some(pinned_index) = self.pinned.iter().position(|p| *p == path) {
+            self.pinned.remove(pinned_index);
+            self.recent.insert(0, path);
+            self.recent.truncate(DEFMT_RECENT_MAX_AMOUNT);
+        } else {
+            self.recent.retain(|p| *p != path);
+            self.pinned.push(path);
+        }
+
+        self.save()
+    }
+    /// Forget the nth listed ELF entirely, pinned or not.
+    pub fn remove(&mut self, nth: usize) -> Result<(), DefmtRecentError> {
+        let Some(path) = self.nth_path(nth).map(ToOwned::to_owned) else {
+            return Ok(());
+        };
+        self.pinned.retain(|p| *p != path);
+        self.recent.retain(|p| *p != path);
+        self.md5.remove(&path);
+        if self.last.as_ref() == Some(&path) {
+            self.last = None;
+        }
+
+        self.save()
+    }
+    fn save(&mut self) -> Result<(), DefmtRecentError> {
+        // Don't hold onto hashes of ELFs that have fallen off the list.
+        let Self {
+            recent,
+            pinned,
+            md5,
+            ..
+        } = self;
+        md5.retain(|path, _| recent.contains(path) || pinned.contains(path));
 
         let recent_toml = toml::to_string(&self)?;
 
@@ -142,42 +207,185 @@ impl DefmtRecentElfs {
 
         Ok(())
     }
+    /// Pinned ELFs first, followed by the rest from most to least recently used.
+    fn entries(&self) -> impl Iterator<Item = &Utf8PathBuf> {
+        self.pinned.iter().chain(self.recent.iter())
+    }
+    pub fn is_pinned(&self, nth: usize) -> bool {
+        nth < self.pinned.len()
+    }
+    pub fn pinned_len(&self) -> usize {
+        self.pinned.len()
+    }
     pub fn as_table(&self) -> Table<'static> {
-        let paths_iter = self.recent.iter().map(|p| {
-            let exists = p.exists();
-            (p, exists)
-        });
-
         let mut rows: Vec<Row<'static>> = Vec::new();
-        let constraints = [Constraint::Fill(1)];
+        let constraints = [
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(8),
+        ];
 
-        for (path, exists) in paths_iter {
+        for (index, path) in self.entries().enumerate() {
+            let exists = path.exists();
             let missing_suffix = if exists { "" } else { " [?]" };
             let row_style = if exists {
                 Style::new()
             } else {
                 Style::new().yellow()
             };
+            let pin_marker = if self.is_pinned(index) { "*" } else { "" };
             let row_text = format!("{path}{missing_suffix}");
-            let row = Row::new(vec![Cell::new(row_text)]).style(row_style);
+            let md5_prefix = self
+                .md5
+                .get(path)
+                .map(|md5| md5.chars().take(8).collect::<String>())
+                .unwrap_or_default();
+            let row = Row::new(vec![
+                Cell::new(pin_marker),
+                Cell::new(row_text),
+                Cell::new(md5_prefix).fg(theme().dim),
+            ])
+            .style(row_style);
             rows.push(row);
         }
 
         Table::new(rows, constraints)
-            .row_highlight_style(Style::new().reversed())
+            .row_highlight_style(theme().selection_style())
             .highlight_spacing(HighlightSpacing::Always)
             .highlight_symbol(">>")
     }
     pub fn nth_path(&self, nth: usize) -> Option<&Utf8Path> {
-        self.recent.get(nth).map(Utf8PathBuf::as_path)
+        self.entries().nth(nth).map(Utf8PathBuf::as_path)
     }
     pub fn last(&self) -> Option<&Utf8Path> {
         self.last.as_ref().map(Utf8PathBuf::as_path)
     }
     pub fn len(&self) -> usize {
-        self.recent.len()
+        self.pinned.len() + self.recent.len()
     }
     pub fn is_empty(&self) -> bool {
-        self.recent.is_empty()
+        self.len() == 0
+    }
+}
+
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+#[strum(serialize_all = "title_case")]
+/// Shown after finding the newest defmt ELF in the cargo target directory.
+pub enum DefmtFoundElfPrompt {
+    #[strum(props(keybind = "y"))]
+    #[strum(serialize = "Load ELF")]
+    Load,
+    #[strum(props(keybind = "c"))]
+    Cancel,
+}
+
+impl PromptKeybind for DefmtFoundElfPrompt {}
+
+/// Where to search for ELFs, preferring the user's configured path, then `CARGO_TARGET_DIR`,
+/// falling back to `./target`.
+///
+/// If the configured path is a workspace (i.e. contains a `target` directory), its `target` directory is used.
+pub fn elf_search_root(configured: &str) -> Utf8PathBuf {
+    let configured = configured.trim();
+    if !configured.is_empty() {
+        let configured = Utf8PathBuf::from(configured);
+        let workspace_target = configured.join("target");
+        return if workspace_target.is_dir() {
+            workspace_target
+        } else {
+            configured
+        };
+    }
+
+    match std::env::var("CARGO_TARGET_DIR") {
+        Ok(target_dir) if !target_dir.is_empty() => Utf8PathBuf::from(target_dir),
+        _ => Utf8PathBuf::from("target"),
+    }
+}
+
+/// Find the most recently modified ELF under the given directory that contains a defmt table.
+pub fn find_newest_defmt_elf(root: &Utf8Path) -> Option<Utf8PathBuf> {
+    let mut candidates = Vec::new();
+    collect_elfs(root, 0, &mut candidates);
+    // Newest first
+    candidates.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    candidates
+        .into_iter()
+        .map(|(_, path)| path)
+        .find(|path| has_defmt_table(path))
+}
+
+fn collect_elfs(dir: &Utf8Path, depth: usize, found: &mut Vec<(SystemTime, Utf8PathBuf)>) {
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if depth < ELF_SEARCH_MAX_DEPTH && !ELF_SEARCH_SKIPPED_DIRS.contains(&entry.file_name())
+            {
+                collect_elfs(entry.path(), depth + 1, found);
+            }
+        } else if metadata.is_file()
+            && is_elf(entry.path())
+            && let Ok(modified) = metadata.modified()
+        {
+            found.push((modified, entry.path().to_owned()));
+        }
     }
 }
+
+/// Checks for the ELF magic number without reading the whole file.
+fn is_elf(path: &Utf8Path) -> bool {
+    use std::io::Read;
+
+    let mut magic = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && magic == *b"\x7fELF"
+}
+
+fn has_defmt_table(path: &Utf8Path) -> bool {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| defmt_decoder::Table::parse(&bytes).ok().flatten())
+        .is_some()
+}
+
+/// Bytes shown per row of a hexdump.
+const HEXDUMP_ROW_LEN: usize = 16;
+
+/// Formats bytes as a classic hexdump, each row having an offset,
+/// the bytes in hex, and their printable ASCII representation (with `.` standing in otherwise).
+pub fn hexdump_rows(bytes: &[u8]) -> Vec<String> {
+    bytes
+        .chunks(HEXDUMP_ROW_LEN)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02X}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!(
+                "{offset:04X}  {hex:<width$}  |{ascii}|",
+                offset = row * HEXDUMP_ROW_LEN,
+                hex = hex.join(" "),
+                width = HEXDUMP_ROW_LEN * 3 - 1,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,132 @@
+use std::{
+    path::Path,
+    process::{Command, ExitStatus},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use ratatui::crossterm::{
+    self,
+    event::{DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture},
+    terminal::{EnterAlternateScreen, enable_raw_mode},
+};
+use tracing::debug;
+
+use crate::buffer::SourceLocation;
+
+/// How long the crossterm thread waits for input at a time,
+/// between checks for whether it's been paused (i.e. for an editor to take over the terminal).
+pub const INPUT_PAUSE_POLL: Duration = Duration::from_millis(50);
+
+/// Editors that take the line to open at as a `+line` argument before the file,
+/// rather than understanding `file:line`.
+const PLUS_LINE_EDITORS: &[&str] = &["vi", "vim", "nvim", "nano", "emacs", "emacsclient", "kak"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum EditorLaunchError {
+    #[error("no editor command set, and $VISUAL/$EDITOR are empty")]
+    NoEditor,
+    #[error("editor command is empty")]
+    EmptyCommand,
+    #[error("failed to run editor: {0}")]
+    Spawn(#[from] std::io::Error),
+}
+
+/// The template to use for an editor from `$VISUAL`/`$EDITOR`,
+/// based on how it expects to be told which line to open at.
+fn default_template(editor: &str) -> String {
+    let program = editor.split_whitespace().next().unwrap_or_default();
+    let name = Path::new(program)
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    if PLUS_LINE_EDITORS.contains(&name) {
+        format!("{editor} +{{line}} {{file}}")
+    } else {
+        format!("{editor} {{file}}:{{line}}")
+    }
+}
+
+/// Builds the command to open the given location with, from the user's template
+/// or `$VISUAL`/`$EDITOR` if it's empty.
+pub fn editor_command(
+    template: &str,
+    location: &SourceLocation,
+) -> Result<Command, EditorLaunchError> {
+    let template = if template.trim().is_empty() {
+        let editor = ["VISUAL", "EDITOR"]
+            .into_iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|editor| !editor.trim().is_empty())
+            .ok_or(EditorLaunchError::NoEditor)?;
+        default_template(&editor)
+    } else {
+        template.to_owned()
+    };
+
+    let line = location.line.to_string();
+    let column = location.column.unwrap_or(1).to_string();
+
+    let mut args = template.split_whitespace().map(|arg| {
+        arg.replace("{file}", &location.file)
+            .replace("{line}", &line)
+            .replace("{column}", &column)
+    });
+    let program = args.next().ok_or(EditorLaunchError::EmptyCommand)?;
+
+    debug!("Opening {}:{line} with {program}", location.file);
+    let mut command = Command::new(program);
+    command.args(args);
+    Ok(command)
+}
+
+/// Hands the terminal over to the given command until it exits, so terminal editors can be used.
+///
+/// `input_paused` is set for the duration to keep the crossterm thread from reading stdin,
+/// which it checks every [`INPUT_PAUSE_POLL`].
+pub fn run_suspended(
+    mut command: Command,
+    input_paused: &AtomicBool,
+) -> Result<ExitStatus, EditorLaunchError> {
+    input_paused.store(true, Ordering::Release);
+    // Letting the crossterm thread finish its current wait for input.
+    std::thread::sleep(INPUT_PAUSE_POLL * 2);
+
+    ratatui::restore();
+    _ = crossterm::execute!(
+        std::io::stdout(),
+        DisableMouseCapture,
+        DisableBracketedPaste
+    );
+
+    let status = command.status();
+
+    let resumed = enable_raw_mode().and_then(|_| {
+        crossterm::execute!(std::io::stdout(), EnterAlternateScreen, EnableMouseCapture)
+    });
+    _ = crossterm::execute!(std::io::stdout(), EnableBracketedPaste);
+    input_paused.store(false, Ordering::Release);
+
+    resumed?;
+    Ok(status?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::default_template;
+
+    #[test]
+    fn vi_likes_get_line_before_file() {
+        assert_eq!(default_template("vim"), "vim +{line} {file}");
+        assert_eq!(
+            default_template("/usr/bin/nvim"),
+            "/usr/bin/nvim +{line} {file}"
+        );
+        assert_eq!(
+            default_template("emacsclient -t"),
+            "emacsclient -t +{line} {file}"
+        );
+        assert_eq!(default_template("hx"), "hx {file}:{line}");
+    }
+}
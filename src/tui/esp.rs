@@ -16,7 +16,7 @@ use tracing::{debug, warn};
 
 use crate::{
     config_adjacent_path,
-    notifications::Notifications,
+    notifications::{Notifications, Severity},
     serial::esp::{EspEvent, FlashProgress},
     traits::{LastIndex, LineHelpers},
 };
@@ -25,7 +25,7 @@ const ESP_PROFILES_PATH: &str = "yap_espflash_profiles.toml";
 
 use serde::Deserialize;
 
-use super::centered_rect_size;
+use super::{centered_rect_size, theme::theme};
 
 // TODO move file stuff out of TUI module
 
@@ -428,17 +428,17 @@ impl EspFlashHelper {
         match event {
             EspEvent::BootloaderSuccess { chip } => notifs.notify_str(
                 format!("{chip} successfully reset into bootloader!"),
-                Color::Green,
+                Severity::Success,
             ),
             EspEvent::EraseSuccess { chip } => {
-                notifs.notify_str(format!("{chip} flash erased!"), Color::Green)
+                notifs.notify_str(format!("{chip} flash erased!"), Severity::Success)
             }
             EspEvent::BootloaderAttempt => notifs.notify_str(
                 "Attempted ESP reset into bootloader! (Unchecked)",
-                Color::LightYellow,
+                Severity::Warning,
             ),
             EspEvent::HardResetAttempt => {
-                notifs.notify_str("Attempted ESP hard reset!", Color::LightYellow)
+                notifs.notify_str("Attempted ESP hard reset!", Severity::Warning)
             }
 
             EspEvent::DeviceInfo(info) => {
@@ -685,13 +685,15 @@ impl EspFlashHelper {
                     body1_area,
                 );
                 frame.render_widget(
-                    line!["Try holding down BOOT/IO0"].centered().dark_gray(),
+                    line!["Try holding down BOOT/IO0"]
+                        .centered()
+                        .fg(theme().dim),
                     chunks_text,
                 );
                 frame.render_widget(
                     line!["during connection if unreliable."]
                         .centered()
-                        .dark_gray(),
+                        .fg(theme().dim),
                     which_bytes,
                 );
             }
@@ -710,7 +712,7 @@ impl EspFlashHelper {
         }
     }
     pub fn profiles_table(&self) -> Table<'_> {
-        let cell_highlight_style = Style::new().reversed().italic();
+        let cell_highlight_style = theme().selection_style().italic();
 
         let rows: Vec<_> = self
             .profiles()
@@ -788,7 +790,7 @@ pub fn espflash_buttons(
     unchecked_bootloader: bool,
     awaiting_erase_confirm: bool,
 ) -> Table<'static> {
-    let cell_highlight_style = Style::new().reversed().italic();
+    let cell_highlight_style = theme().selection_style().italic();
 
     let rows: Vec<Row> = vec![
         Row::new([
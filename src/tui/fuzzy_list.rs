@@ -0,0 +1,167 @@
+//! Popup for picking an entry from a list, fuzzy-matched against what's typed in.
+
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect, Size},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Row, Table, TableState, Wrap},
+};
+use tui_input::Input;
+
+use super::{centered_rect_size, theme::theme};
+
+/// Something listed in a [`FuzzyList`], along with the text of the popup it's listed in.
+pub trait FuzzyListEntry {
+    const TITLE: &'static str;
+    /// Keys shown along the bottom of the popup.
+    const FOOTER: &'static str;
+    /// Shown in place of a description when nothing matches the query.
+    const NO_MATCHES: &'static str;
+    const MAX_WIDTH: u16;
+
+    /// Shown dimmed before the name, like which menu the entry is from.
+    fn kind(&self) -> &str;
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+}
+
+#[derive(Debug)]
+pub struct FuzzyList<T> {
+    pub input: Input,
+    entries: Vec<T>,
+    /// Indices into `entries` matching the query, best match first.
+    matches: Vec<usize>,
+    pub selected: usize,
+}
+
+impl<T> Default for FuzzyList<T> {
+    fn default() -> Self {
+        Self {
+            input: Input::default(),
+            entries: Vec::new(),
+            matches: Vec::new(),
+            selected: 0,
+        }
+    }
+}
+
+impl<T: FuzzyListEntry> FuzzyList<T> {
+    pub fn new(entries: Vec<T>) -> Self {
+        let mut list = Self::default();
+        list.replace_entries(entries);
+        list
+    }
+    /// Clear the query, listing every entry again.
+    pub fn reset(&mut self) {
+        self.input.reset();
+        self.update_matches();
+    }
+    /// Clear the query, replacing the entries with the given ones.
+    pub fn replace_entries(&mut self, entries: Vec<T>) {
+        self.entries = entries;
+        self.reset();
+    }
+    /// Re-rank every entry against the current query, fuzzy-matching on both name and description.
+    pub fn update_matches(&mut self) {
+        let query = self.input.value();
+        self.selected = 0;
+        if query.is_empty() {
+            self.matches = (0..self.entries.len()).collect();
+            return;
+        }
+
+        let matcher = SkimMatcherV2::default();
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                let name_score = matcher.fuzzy_match(entry.name(), query);
+                let doc_score = matcher.fuzzy_match(entry.description(), query);
+                // Matching the name is what's usually meant, so it's given the edge.
+                let score = name_score.map(|score| score * 2).max(doc_score)?;
+                Some((score, index))
+            })
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+    }
+    pub fn selected_entry(&self) -> Option<&T> {
+        self.matches
+            .get(self.selected)
+            .and_then(|index| self.entries.get(*index))
+    }
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.matches.len().max(1);
+    }
+    pub fn select_prev(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(self.matches.len().saturating_sub(1));
+    }
+    pub fn render(&self, frame: &mut Frame, screen: Rect) {
+        let area = centered_rect_size(
+            Size {
+                width: screen.width.min(T::MAX_WIDTH),
+                height: screen.height.saturating_sub(2).min(20),
+            },
+            screen,
+        );
+
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(Line::raw(format!(" {} ", T::TITLE)).centered().reset())
+            .title_bottom(Line::raw(format!(" {} ", T::FOOTER)).centered().reset());
+
+        let [input_area, table_area, info_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(2),
+        ])
+        .areas(block.inner(area));
+
+        let query = self.input.value();
+        let input_line = if query.is_empty() {
+            Line::styled("> Type to search...", theme().dim_style())
+        } else {
+            Line::raw(format!("> {query}"))
+        };
+
+        let kind_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.kind().len())
+            .max()
+            .unwrap_or_default() as u16;
+
+        let rows = self.matches.iter().map(|index| {
+            let entry = &self.entries[*index];
+            Row::new([
+                Line::styled(entry.kind(), theme().dim_style()),
+                Line::raw(entry.name()),
+            ])
+        });
+        let table = Table::new(rows, [Constraint::Length(kind_width), Constraint::Fill(1)])
+            .column_spacing(2)
+            .row_highlight_style(theme().selection_style());
+        let mut table_state = TableState::new().with_selected(Some(self.selected));
+
+        let info = match self.selected_entry() {
+            Some(entry) => Paragraph::new(Line::raw(entry.description())),
+            None => Paragraph::new(Line::styled(T::NO_MATCHES, theme().dim_style())),
+        };
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(input_line, input_area);
+        frame.set_cursor_position((
+            input_area.x + 2 + self.input.visual_cursor() as u16,
+            input_area.y,
+        ));
+        frame.render_stateful_widget(table, table_area, &mut table_state);
+        frame.render_widget(info.wrap(Wrap { trim: true }), info_area);
+    }
+}
@@ -0,0 +1,58 @@
+use crokey::KeyCombination;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+};
+
+use crate::keybinds::{BuiltinAction, KeybindContext, Keybinds};
+
+use super::theme::theme;
+
+/// Line along the bottom of the screen listing the keys for what's currently on screen.
+///
+/// Anything bound in the given contexts comes first, followed by whichever of the
+/// relevant actions are bound, labelled with the short names given. Unbound actions are left out.
+pub fn render_help_footer(
+    keybinds: &Keybinds,
+    contexts: &[KeybindContext],
+    relevant: &[(BuiltinAction, &str)],
+    frame: &mut Frame,
+    area: Rect,
+) {
+    if area.is_empty() {
+        return;
+    }
+
+    let context_bindings = contexts
+        .iter()
+        .filter_map(|context| keybinds.contexts.get(context))
+        .flat_map(|keybinds| &keybinds.0)
+        .map(|(key_combo, actions)| (*key_combo, actions.iter().join(", ")));
+    let action_bindings = relevant.iter().filter_map(|(action, label)| {
+        keybinds
+            .key_for_action_in_contexts(contexts, action)
+            .map(|key_combo| (key_combo, (*label).to_owned()))
+    });
+
+    let hints: Vec<(KeyCombination, String)> = context_bindings
+        .chain(action_bindings)
+        .unique_by(|(key_combo, _)| *key_combo)
+        .collect();
+
+    let separator = Span::styled(" | ", theme().dim_style());
+    let spans = Itertools::intersperse(
+        hints.iter().map(|(key_combo, label)| {
+            vec![
+                Span::raw(key_combo.to_string()).bold(),
+                Span::raw(format!(" {label}")),
+            ]
+        }),
+        vec![separator],
+    )
+    .flatten();
+
+    frame.render_widget(Line::from_iter(spans), area);
+}
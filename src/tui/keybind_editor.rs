@@ -0,0 +1,165 @@
+use crokey::KeyCombination;
+use itertools::Itertools;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect, Size},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Row, Table, TableState, Wrap},
+};
+
+use crate::keybinds::{Keybinds, builtin_action_groups};
+
+use super::{centered_rect_size, theme::theme};
+
+#[derive(Debug)]
+pub struct KeybindEditorEntry {
+    pub group: &'static str,
+    pub name: String,
+    pub doc_comment: &'static str,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum KeybindEditorState {
+    #[default]
+    Browsing,
+    /// Waiting for the user to press the new key combo for the selected action.
+    Capturing,
+    /// The captured key combo is already bound to something else, waiting on the user to replace it or not.
+    Conflict {
+        key_combo: KeyCombination,
+        existing: Vec<String>,
+    },
+}
+
+#[derive(Debug)]
+/// Popup for rebinding builtin actions in-app, with changes written back to the keybinds file.
+pub struct KeybindEditor {
+    pub actions: Vec<KeybindEditorEntry>,
+    pub selected: usize,
+    pub state: KeybindEditorState,
+}
+
+impl Default for KeybindEditor {
+    fn default() -> Self {
+        let actions = builtin_action_groups()
+            .into_iter()
+            .flat_map(|(group, actions)| {
+                actions
+                    .into_iter()
+                    .map(move |(name, doc_comment)| KeybindEditorEntry {
+                        group,
+                        name,
+                        doc_comment,
+                    })
+            })
+            .collect();
+        Self {
+            actions,
+            selected: 0,
+            state: KeybindEditorState::default(),
+        }
+    }
+}
+
+impl KeybindEditor {
+    pub fn selected_action(&self) -> Option<&KeybindEditorEntry> {
+        self.actions.get(self.selected)
+    }
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.actions.len().max(1);
+    }
+    pub fn select_prev(&mut self) {
+        self.selected = self
+            .selected
+            .checked_sub(1)
+            .unwrap_or(self.actions.len().saturating_sub(1));
+    }
+    pub fn render(&self, keybinds: &Keybinds, frame: &mut Frame, screen: Rect) {
+        let area = centered_rect_size(
+            Size {
+                width: screen.width.min(80),
+                height: screen.height.saturating_sub(2).min(24),
+            },
+            screen,
+        );
+
+        let hint = match &self.state {
+            KeybindEditorState::Browsing => " Enter: Bind | Del: Unbind | Esc: Back ",
+            KeybindEditorState::Capturing => " Press the new key combo | Esc: Cancel ",
+            KeybindEditorState::Conflict { .. } => " Enter: Replace | Esc: Cancel ",
+        };
+
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(Line::raw(" Keybind Editor ").centered().reset())
+            .title_bottom(Line::raw(hint).centered().reset());
+
+        let [table_area, info_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(block.inner(area));
+
+        let name_width = self
+            .actions
+            .iter()
+            .map(|entry| entry.name.len())
+            .max()
+            .unwrap_or_default() as u16;
+
+        let rows = self.actions.iter().enumerate().map(|(index, entry)| {
+            let capturing = index == self.selected && self.state == KeybindEditorState::Capturing;
+            let keys = if capturing {
+                "...".to_owned()
+            } else {
+                keybinds
+                    .keys_with_single_action(&entry.name)
+                    .iter()
+                    .join(", ")
+            };
+            let keys_style = if keys.is_empty() {
+                theme().dim_style()
+            } else {
+                Style::new().cyan()
+            };
+            let keys = if keys.is_empty() {
+                "(unbound)".to_owned()
+            } else {
+                keys
+            };
+            Row::new([
+                Line::raw(entry.name.clone()),
+                Line::styled(keys, keys_style),
+            ])
+        });
+
+        let table = Table::new(rows, [Constraint::Length(name_width), Constraint::Fill(1)])
+            .column_spacing(2)
+            .row_highlight_style(theme().selection_style());
+        let mut table_state = TableState::new().with_selected(Some(self.selected));
+
+        let info = match (&self.state, self.selected_action()) {
+            (
+                KeybindEditorState::Conflict {
+                    key_combo,
+                    existing,
+                },
+                _,
+            ) => Paragraph::new(
+                Line::raw(format!(
+                    "{key_combo} is already bound to: {}",
+                    existing.join(", ")
+                ))
+                .fg(Color::Yellow),
+            ),
+            (_, Some(entry)) => Paragraph::new(vec![
+                Line::raw(entry.group).fg(theme().dim),
+                Line::raw(entry.doc_comment),
+            ]),
+            (_, None) => Paragraph::default(),
+        };
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_stateful_widget(table, table_area, &mut table_state);
+        frame.render_widget(info.wrap(Wrap { trim: true }), info_area);
+    }
+}
@@ -3,8 +3,10 @@ use ratatui::{
     widgets::{Row, Table},
 };
 
+use super::theme::theme;
+
 pub fn sync_logs_button() -> Table<'static> {
-    let cell_highlight_style = Style::new().reversed().italic();
+    let cell_highlight_style = theme().selection_style().italic();
 
     let rows: Vec<Row> = vec![Row::new([
         Text::raw("Sync Buffer to File(s) ").right_aligned(),
@@ -8,11 +8,32 @@ pub mod esp;
 pub mod logging;
 
 // pub mod buffer;
+mod chord_hint;
 pub mod color_rules;
+mod command_palette;
+mod completion;
+mod compose;
+pub mod editor;
+mod fuzzy_list;
+mod help_footer;
+mod keybind_editor;
 pub mod modifiers;
+mod navigation;
 pub mod prompts;
+mod settings_search;
+mod setup_wizard;
 mod show_keybinds;
 pub mod single_line_selector;
+pub mod theme;
+pub use chord_hint::render_chord_hint;
+pub use command_palette::{CommandPalette, PaletteCommand, PaletteEntry};
+pub use completion::{CompletionKind, Completions};
+pub use compose::ComposeEditor;
+pub use help_footer::render_help_footer;
+pub use keybind_editor::{KeybindEditor, KeybindEditorState};
+pub use navigation::BufferNavigation;
+pub use settings_search::{SettingsSearch, SettingsSearchEntry};
+pub use setup_wizard::SetupWizard;
 pub use show_keybinds::show_keybinds;
 
 /// Popup category selectors count.
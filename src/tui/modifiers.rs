@@ -33,6 +33,12 @@ impl FromStr for ModifierFromStr {
     }
 }
 
+impl From<Modifier> for ModifierFromStr {
+    fn from(value: Modifier) -> Self {
+        Self { inner: value }
+    }
+}
+
 impl From<ModifierFromStr> for Modifier {
     fn from(value: ModifierFromStr) -> Self {
         value.inner
@@ -0,0 +1,53 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+use tui_input::Input;
+
+use super::theme::theme;
+
+#[derive(Debug, Default)]
+/// Vim-style modal navigation of the buffer, for scrolling and searching without PageUp/PageDown or the mouse.
+pub struct BufferNavigation {
+    /// Keys navigate the buffer rather than being typed or sent to the port.
+    pub active: bool,
+    /// Query being typed after pressing `/`.
+    pub search_input: Option<Input>,
+    /// Last submitted query, repeated with `n` and `N`.
+    pub last_search: Option<String>,
+}
+
+impl BufferNavigation {
+    /// Render the mode's status (or the search being typed) in place of the input line.
+    pub fn render(&self, frame: &mut Frame, area: Rect, position_cursor: bool) {
+        if let Some(input) = &self.search_input {
+            let [symbol_area, input_area] = ratatui_macros::horizontal![==1, *=1].areas(area);
+            frame.render_widget(Span::raw("/").yellow(), symbol_area);
+
+            // So the cursor doesn't bleed off the edge
+            let width = input_area.width.max(1).saturating_sub(1) as usize;
+            let scroll = input.visual_scroll(width);
+            frame.render_widget(
+                Paragraph::new(input.value()).scroll((0, scroll as u16)),
+                input_area,
+            );
+            if position_cursor {
+                let cursor_offset = input.visual_cursor().saturating_sub(scroll);
+                frame.set_cursor_position((input_area.x + cursor_offset as u16, input_area.y));
+            }
+            return;
+        }
+
+        let line = Line::from(vec![
+            Span::raw(" NAVIGATION ").reversed().fg(theme().highlight),
+            Span::styled(
+                " j/k: Scroll | g/G: Top/Bottom | /: Search | n/N: Older/Newer Match | i: Input",
+                theme().dim_style(),
+            ),
+        ]);
+        frame.render_widget(line, area);
+    }
+}
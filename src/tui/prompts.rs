@@ -10,6 +10,8 @@ use ratatui::{
 use ratatui_macros::{row, span};
 use strum::{EnumProperty, VariantArray, VariantNames};
 
+use super::theme::theme;
+
 // TODO another prop for alternate label?
 // maybe just a single shared bool for all of them,
 // and whichever is active is the one that gets flipped
@@ -109,15 +111,108 @@ pub enum IgnorePortByNamePrompt {
 
 impl PromptKeybind for IgnorePortByNamePrompt {}
 
-// #[derive(
-//     Debug, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
-// )]
-// #[repr(u8)]
-// pub enum DeleteMacroPrompt {
-//     #[strum(props(color = "red"))]
-//     Delete,
-//     Cancel,
-// }
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+#[strum(serialize_all = "title_case")]
+/// When the chosen port is already held by another yap instance or program.
+pub enum PortInUsePrompt {
+    #[strum(props(keybind = "r"))]
+    Retry,
+    #[strum(props(keybind = "p"))]
+    PickAnotherPort,
+    #[cfg(unix)]
+    #[strum(props(keybind = "f", color = "red"))]
+    ForceConnect,
+}
+
+impl PromptKeybind for PortInUsePrompt {}
+
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+/// When a paste about to be sent straight to the device is over `Behavior::paste_confirm_threshold`.
+pub enum LargePastePrompt {
+    #[strum(props(keybind = "y", color = "red"))]
+    Send,
+    #[strum(props(keybind = "n"))]
+    Cancel,
+}
+
+impl PromptKeybind for LargePastePrompt {}
+
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+#[strum(serialize_all = "title_case")]
+/// For Settings menus, when Ctrl+D is pressed.
+pub enum RevertSettingsPrompt {
+    #[strum(props(keybind = "u"))]
+    RevertUnsavedChanges,
+    #[strum(props(keybind = "z"))]
+    UndoLastChange,
+    #[strum(props(keybind = "s", color = "red"))]
+    ResetThisSection,
+    #[strum(props(keybind = "a", color = "red"))]
+    ResetAllSettings,
+    #[strum(props(keybind = "c"))]
+    Cancel,
+}
+
+impl PromptKeybind for RevertSettingsPrompt {}
+
+#[cfg(feature = "macros")]
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+pub enum DeleteMacroPrompt {
+    #[strum(props(keybind = "d", color = "red"))]
+    Delete,
+    #[strum(props(keybind = "c"))]
+    Cancel,
+}
+
+#[cfg(feature = "macros")]
+impl PromptKeybind for DeleteMacroPrompt {}
+
+#[cfg(feature = "macros")]
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+pub enum ConfirmMacroPrompt {
+    #[strum(props(keybind = "y", color = "red"))]
+    Send,
+    #[strum(props(keybind = "n"))]
+    Cancel,
+}
+
+#[cfg(feature = "macros")]
+impl PromptKeybind for ConfirmMacroPrompt {}
+
+#[cfg(feature = "macros")]
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+#[strum(serialize_all = "title_case")]
+pub enum ImportMacroConflictPrompt {
+    #[strum(props(keybind = "r", color = "red"))]
+    Replace,
+    #[strum(props(keybind = "b"))]
+    KeepBoth,
+    #[strum(props(keybind = "s"))]
+    Skip,
+    #[strum(props(keybind = "a"))]
+    SkipAll,
+}
+
+#[cfg(feature = "macros")]
+impl PromptKeybind for ImportMacroConflictPrompt {}
 
 pub trait PromptTable: VariantNames + VariantArray + EnumProperty + Into<u8> + TryFrom<u8> {
     /// Returns a ratatui [Table] with static references to the names of each enum variant.
@@ -131,7 +226,7 @@ pub trait PromptTable: VariantNames + VariantArray + EnumProperty + Into<u8> + T
     /// # }
     /// ```
     fn prompt_table() -> Table<'static> {
-        let selected_style = Style::new().reversed();
+        let selected_style = theme().selection_style();
 
         // Fully Qualified:
         // <Self as self::VariantNames>::VARIANTS
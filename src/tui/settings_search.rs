@@ -0,0 +1,80 @@
+use struct_table::StructTable;
+
+use crate::{
+    app::SettingsMenu,
+    settings::{Behavior, PortSettings, Rendering},
+};
+
+#[cfg(feature = "defmt")]
+use crate::settings::Defmt;
+#[cfg(feature = "logging")]
+use crate::settings::Logging;
+
+use super::fuzzy_list::{FuzzyList, FuzzyListEntry};
+
+#[derive(Debug)]
+pub struct SettingsSearchEntry {
+    pub menu: SettingsMenu,
+    pub menu_name: &'static str,
+    /// Index of the field in its menu's settings table.
+    pub field_index: usize,
+    pub name: &'static str,
+    pub doc_comment: &'static str,
+}
+
+fn entries_of<T: StructTable>(
+    menu: SettingsMenu,
+    menu_name: &'static str,
+) -> impl Iterator<Item = SettingsSearchEntry> {
+    T::FIELD_NAMES
+        .iter()
+        .zip(T::DOCSTRINGS)
+        .enumerate()
+        .filter(|(field_index, _)| !T::is_section_header(*field_index))
+        .map(
+            move |(field_index, (name, doc_comment))| SettingsSearchEntry {
+                menu: menu.clone(),
+                menu_name,
+                field_index,
+                name,
+                doc_comment,
+            },
+        )
+}
+
+/// Popup for finding a setting by its name or description across every settings menu.
+pub type SettingsSearch = FuzzyList<SettingsSearchEntry>;
+
+impl SettingsSearchEntry {
+    /// Every field in every settings menu, besides section headers.
+    pub fn all() -> Vec<Self> {
+        let entries = entries_of::<PortSettings>(SettingsMenu::SerialPort, "Serial Port")
+            .chain(entries_of::<Rendering>(
+                SettingsMenu::Rendering,
+                "Rendering",
+            ))
+            .chain(entries_of::<Behavior>(SettingsMenu::Behavior, "Behavior"));
+        #[cfg(feature = "logging")]
+        let entries = entries.chain(entries_of::<Logging>(SettingsMenu::Logging, "Logging"));
+        #[cfg(feature = "defmt")]
+        let entries = entries.chain(entries_of::<Defmt>(SettingsMenu::Defmt, "defmt"));
+        entries.collect()
+    }
+}
+
+impl FuzzyListEntry for SettingsSearchEntry {
+    const TITLE: &'static str = "Search Settings";
+    const FOOTER: &'static str = "Enter: Jump | Esc: Close";
+    const NO_MATCHES: &'static str = "No matching settings.";
+    const MAX_WIDTH: u16 = 70;
+
+    fn kind(&self) -> &str {
+        self.menu_name
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn description(&self) -> &str {
+        self.doc_comment
+    }
+}
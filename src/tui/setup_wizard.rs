@@ -0,0 +1,130 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect, Size},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, TableState, Wrap},
+};
+use struct_table::StructTable;
+
+#[cfg(feature = "defmt")]
+use crate::settings::DefmtSupport;
+use crate::{
+    app::COMMON_BAUD_TRUNC,
+    settings::{Settings, line_ending::RxLineEnding},
+};
+
+use super::{centered_rect_size, theme::theme};
+
+#[derive(Debug, Clone, PartialEq, StructTable)]
+#[table(no_default)]
+/// The few settings worth picking before the first connection, asked about one at a time.
+///
+/// Everything else is left at its default, and can be found later in the settings menus.
+pub struct SetupWizard {
+    /// Baud rate to connect to ports with. Can also be changed per-port in Port Selection.
+    #[table(allow_unknown_values)]
+    #[table(values = COMMON_BAUD_TRUNC)]
+    pub baud_rate: u32,
+
+    /// Line endings that split incoming data into lines.
+    #[table(display = ["\\n", "\\r", "\\r\\n", "None"])]
+    #[table(rename = "RX Line Ending")]
+    #[table(values = [RxLineEnding::Preset("\\n", b"\n"), RxLineEnding::Preset("\\r", b"\r"), RxLineEnding::Preset("\\r\\n", b"\r\n"), RxLineEnding::Preset("", b"")])]
+    #[table(allow_unknown_values)]
+    pub rx_line_ending: RxLineEnding,
+
+    #[cfg(feature = "defmt")]
+    /// Decode incoming data as defmt frames once an ELF is loaded, or leave it disabled for plain text.
+    pub defmt_parsing: DefmtSupport,
+
+    #[cfg(feature = "logging")]
+    /// Save incoming data to a text file in the logs folder.
+    pub log_text_to_file: bool,
+
+    #[table(rename = "Check for Updates")]
+    /// Check GitHub for a newer release of yap on startup.
+    pub check_for_updates: bool,
+}
+
+impl SetupWizard {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            baud_rate: settings.serial.baud_rate,
+            rx_line_ending: settings.serial.rx_line_ending.clone(),
+            #[cfg(feature = "defmt")]
+            defmt_parsing: settings.defmt.defmt_parsing.clone(),
+            #[cfg(feature = "logging")]
+            log_text_to_file: settings.logging.log_text_to_file,
+            check_for_updates: true,
+        }
+    }
+    /// Copy the chosen values into the settings, besides the update check, which has its own consent flow.
+    pub fn apply_to(&self, settings: &mut Settings) {
+        settings.serial.baud_rate = self.baud_rate;
+        settings.serial.rx_line_ending = self.rx_line_ending.clone();
+        #[cfg(feature = "defmt")]
+        {
+            settings.defmt.defmt_parsing = self.defmt_parsing.clone();
+        }
+        #[cfg(feature = "logging")]
+        {
+            settings.logging.log_text_to_file = self.log_text_to_file;
+        }
+    }
+    pub fn is_last_step(step: usize) -> bool {
+        step + 1 >= Self::VISIBLE_FIELDS
+    }
+    pub fn render(&self, step: usize, frame: &mut Frame, screen: Rect) {
+        let area = centered_rect_size(
+            Size {
+                width: screen.width.min(60),
+                height: Self::VISIBLE_FIELDS as u16 + 8,
+            },
+            screen,
+        );
+
+        let hint = if Self::is_last_step(step) {
+            " ←/→: Change | Enter: Finish | Esc: Skip "
+        } else {
+            " ←/→: Change | Enter: Next | Esc: Skip "
+        };
+        let block = Block::bordered()
+            .border_style(theme().border_style())
+            .title_top(Line::raw(" Welcome to yap! ").centered().reset())
+            .title_bottom(Line::raw(hint).centered().reset());
+
+        let [progress_area, _, table_area, _, info_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(Self::VISIBLE_FIELDS as u16),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(block.inner(area));
+
+        let progress = Line::styled(
+            format!(
+                "First-time setup, step {} of {}",
+                step + 1,
+                Self::VISIBLE_FIELDS
+            ),
+            theme().dim_style(),
+        )
+        .centered();
+
+        let mut table_state = TableState::new()
+            .with_selected(Some(step))
+            .with_selected_column(Some(usize::MAX));
+
+        let info = Paragraph::new(Self::DOCSTRINGS.get(step).copied().unwrap_or_default())
+            .centered()
+            .wrap(Wrap { trim: true });
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(progress, progress_area);
+        frame.render_stateful_widget(self.as_table(), table_area, &mut table_state);
+        frame.render_widget(info, info_area);
+    }
+}
@@ -10,7 +10,8 @@ use ratatui_macros::{line, span};
 use crate::macros::MacroNameTag;
 use crate::{
     app::App,
-    keybinds::{Action, Keybinds},
+    keybinds::{Action, Keybinds, SnapshotAction, chord_to_string},
+    tui::theme::theme,
 };
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, strum::EnumIs)]
@@ -64,10 +65,28 @@ impl std::fmt::Display for ActionOption {
                     name,
                     category: None,
                 }) => write!(f, "[Macro] {name}"),
+                #[cfg(feature = "macros")]
+                Action::MacroPayload(tag, _) => write!(f, "[Macro Step] {tag}"),
+                #[cfg(feature = "macros")]
+                Action::MacroExpect(_, pattern, timeout) => {
+                    write!(f, "Expect: \"{pattern}\" ({timeout:?})")
+                }
 
                 #[cfg(feature = "espflash")]
                 Action::EspFlashProfile(profile) => write!(f, "[ESP] {profile}"),
 
+                Action::Snapshot(SnapshotAction::Take(name)) => write!(f, "Snapshot: {name}"),
+                Action::Snapshot(SnapshotAction::ToggleDiff(name)) => {
+                    write!(f, "Snapshot Diff: {name}")
+                }
+
+                #[cfg(feature = "scripting")]
+                Action::Script(name) => write!(f, "[Script] {name}"),
+
+                Action::RunCommand(command) => write!(f, "Run: {command}"),
+
+                Action::ComposedLine(line) => write!(f, "Composed: {line}"),
+
                 Action::Pause(duration) => write!(f, "Pause: {duration:?}"),
             },
             ActionOption::Unrecognized(unk) => write!(f, "?{unk}?"),
@@ -214,6 +233,39 @@ pub fn show_keybinds(
         })
         .collect();
 
+    let report = keybinds.report();
+    if !report.is_empty() {
+        rows.push(Line::raw("Keybind Issues:").centered().bold().yellow());
+
+        let issues = report
+            .duplicates
+            .iter()
+            .map(|(_, spellings)| {
+                line![
+                    span!(Color::Yellow; "{}", spellings.join(" & ")),
+                    span!(theme().dim_style(); " - same key, only the last is used"),
+                ]
+            })
+            .chain(report.shadowed.iter().map(|key_combo| {
+                line![
+                    span!(Color::Yellow; "{key_combo}"),
+                    span!(theme().dim_style(); " - always handled by yap, never triggered"),
+                ]
+            }))
+            .chain(report.unbound.iter().map(|action| {
+                line![
+                    span!(Color::Yellow; "{action}"),
+                    span!(theme().dim_style(); " - important action with no key!"),
+                ]
+            }));
+
+        for line in issues {
+            max_line_length = max_line_length.max(line.width());
+            rows.push(line);
+        }
+        rows.push(Line::default());
+    }
+
     if !unknowns.is_empty() {
         rows.push(
             Line::raw("Unrecognized Keybinds:")
@@ -271,6 +323,99 @@ pub fn show_keybinds(
         rows.extend(chain_rows);
     }
 
+    // Context, chord, and mouse bindings are listed with all their actions on one line.
+    let mut bound_actions_line = |label: String, label_width: usize, actions: &[String]| {
+        let actions: Vec<_> = actions
+            .iter()
+            .map(|a| match app.get_action_from_string(a.as_str()) {
+                Some(action) => ActionOption::Recognized(action),
+                None => ActionOption::Unrecognized(a.to_owned()),
+            })
+            .collect();
+        let label_style = if actions.iter().any(ActionOption::is_unrecognized) {
+            Style::new().yellow()
+        } else {
+            Style::new()
+        };
+
+        let mut spans = vec![span!(label_style; "{label:label_width$} - ")];
+        for (index, action) in actions.iter().enumerate() {
+            if index > 0 {
+                spans.push(Span::raw(", "));
+            }
+            spans.push(span!(action_style(action); "{action}"));
+        }
+        let line = Line::from(spans);
+        max_line_length = max_line_length.max(line.width());
+        line
+    };
+
+    for (context, context_keybinds) in &keybinds.contexts {
+        if context_keybinds.0.is_empty() {
+            continue;
+        }
+        rows.push(Line::default());
+        rows.push(Line::raw(format!("{context} Keybinds:")).centered().bold());
+        rows.push(Line::default());
+
+        let key_combo_width = context_keybinds
+            .0
+            .keys()
+            .map(|key_combo| key_combo.to_string().len())
+            .max()
+            .unwrap_or_default();
+
+        for (key_combo, actions) in &context_keybinds.0 {
+            rows.push(bound_actions_line(
+                key_combo.to_string(),
+                key_combo_width,
+                actions,
+            ));
+        }
+    }
+
+    if !keybinds.chords.is_empty() {
+        rows.push(Line::default());
+        rows.push(Line::raw("Chords:").centered().bold());
+        rows.push(Line::default());
+
+        let chord_width = keybinds
+            .chords
+            .keys()
+            .map(|keys| chord_to_string(keys).len())
+            .max()
+            .unwrap_or_default();
+
+        for (keys, actions) in &keybinds.chords {
+            rows.push(bound_actions_line(
+                chord_to_string(keys),
+                chord_width,
+                actions,
+            ));
+        }
+    }
+
+    if !keybinds.mouse.is_empty() {
+        rows.push(Line::default());
+        rows.push(Line::raw("Mouse:").centered().bold());
+        rows.push(Line::default());
+
+        let binding_width = keybinds
+            .mouse
+            .keys()
+            .map(|binding| binding.to_string().len())
+            .max()
+            .unwrap_or_default();
+
+        for (binding, actions) in &keybinds.mouse {
+            rows.push(bound_actions_line(
+                binding.to_string(),
+                binding_width,
+                actions,
+            ));
+        }
+    }
+
     let area = {
         let mut block_area = area;
         block_area.width = block_area.width.min((max_line_length as u16) + 2);
@@ -282,9 +427,16 @@ pub fn show_keybinds(
 
     frame.render_widget(Clear, area);
 
+    let title = match keybinds.active_profile() {
+        Some(profile) => format!(" Keybinds ({profile}) "),
+        None => " Keybinds ".to_owned(),
+    };
     let block = Block::bordered()
-        .title_top(" Keybinds ")
-        .title_bottom(Span::styled(" Ctrl-R: Reload ", Style::new().dark_gray()))
+        .title_top(title)
+        .title_bottom(Span::styled(
+            " Ctrl-R: Reload | Ctrl-E: Edit ",
+            theme().dim_style(),
+        ))
         .title_alignment(Alignment::Center);
     frame.render_widget(&block, area);
 
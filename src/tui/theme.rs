@@ -0,0 +1,193 @@
+use std::{
+    path::Path,
+    sync::{Arc, LazyLock},
+};
+
+use arc_swap::{ArcSwap, Guard};
+use derivative::Derivative;
+use fs_err as fs;
+use ratatui::style::{Color, Modifier, Style};
+use serde_with::{DisplayFromStr, serde_as};
+use tracing::info;
+
+use crate::{notifications::Severity, settings::ThemeChoice, tui::modifiers::ModifierFromStr};
+
+pub const THEME_PATH: &str = "yap_theme.toml";
+
+static THEME: LazyLock<ArcSwap<Theme>> = LazyLock::new(|| ArcSwap::from_pointee(Theme::default()));
+
+/// The theme currently in use, shared by everything that renders.
+pub fn theme() -> Guard<Arc<Theme>> {
+    THEME.load()
+}
+
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, Derivative)]
+#[serde(default)]
+#[derivative(Default)]
+/// Colors used throughout the UI, for anything that doesn't have a meaning of its own
+/// (i.e. the per-menu colors of the settings popups).
+pub struct Theme {
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Green"))]
+    /// Borders of popups and editors.
+    pub border: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::DarkGray"))]
+    /// De-emphasized text, like hints, placeholders, and unbound keys.
+    pub dim: Color,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    /// Foreground of selected rows, if unset the modifier alone is used.
+    pub selection_fg: Option<Color>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    /// Background of selected rows, if unset the modifier alone is used.
+    pub selection_bg: Option<Color>,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "ModifierFromStr::from(Modifier::REVERSED)"))]
+    /// Modifiers of selected rows.
+    pub selection_modifier: ModifierFromStr,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::DarkGray"))]
+    /// Echoed user input lines in the buffer.
+    pub user_input: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Yellow"))]
+    /// Text that should catch the eye, like the keys of a chord hint.
+    pub highlight: Color,
+
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Gray"))]
+    /// Notifications that are only informational.
+    pub notify_info: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Green"))]
+    /// Notifications for things that went right.
+    pub notify_success: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::LightYellow"))]
+    /// Notifications for things worth a look.
+    pub notify_warning: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Red"))]
+    /// Notifications for things that went wrong.
+    pub notify_error: Color,
+
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Red"))]
+    pub defmt_error: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Yellow"))]
+    pub defmt_warn: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Green"))]
+    pub defmt_info: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Blue"))]
+    pub defmt_debug: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Magenta"))]
+    pub defmt_trace: Color,
+    #[serde_as(as = "DisplayFromStr")]
+    #[derivative(Default(value = "Color::Gray"))]
+    /// defmt logs without a level.
+    pub defmt_unknown: Color,
+}
+
+impl Theme {
+    /// Only shades of gray, leaving color to the device's output and color rules.
+    fn monochrome() -> Self {
+        Self {
+            border: Color::Gray,
+            dim: Color::DarkGray,
+            user_input: Color::DarkGray,
+            highlight: Color::White,
+            notify_info: Color::Gray,
+            notify_success: Color::White,
+            notify_warning: Color::Gray,
+            notify_error: Color::White,
+            defmt_error: Color::White,
+            defmt_warn: Color::White,
+            defmt_info: Color::Gray,
+            defmt_debug: Color::DarkGray,
+            defmt_trace: Color::DarkGray,
+            defmt_unknown: Color::DarkGray,
+            ..Self::default()
+        }
+    }
+    pub fn border_style(&self) -> Style {
+        Style::new().fg(self.border)
+    }
+    pub fn dim_style(&self) -> Style {
+        Style::new().fg(self.dim)
+    }
+    pub fn selection_style(&self) -> Style {
+        let mut style = Style::new().add_modifier(self.selection_modifier.into());
+        if let Some(fg) = self.selection_fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.selection_bg {
+            style = style.bg(bg);
+        }
+        style
+    }
+    pub fn notification_color(&self, severity: Severity) -> Color {
+        match severity {
+            Severity::Info => self.notify_info,
+            Severity::Success => self.notify_success,
+            Severity::Warning => self.notify_warning,
+            Severity::Error => self.notify_error,
+        }
+    }
+    /// Notification color (the severity's, unless it was sent with its own),
+    /// with errors also made bold so they stand out regardless of theme.
+    pub fn notification_style(&self, color: Option<Color>, severity: Severity) -> Style {
+        let color = color.unwrap_or_else(|| self.notification_color(severity));
+        let style = Style::new().fg(color);
+        if severity == Severity::Error {
+            style.add_modifier(Modifier::BOLD)
+        } else {
+            style
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ThemeLoadError {
+    #[error("failed reading from theme file")]
+    FileRead(#[source] std::io::Error),
+    #[error("failed saving to theme file")]
+    FileWrite(#[source] std::io::Error),
+    #[error("invalid theme")]
+    Deser(#[from] toml::de::Error),
+}
+
+impl ThemeChoice {
+    /// Switch to the chosen theme, reading the theme file if it's `Custom`.
+    ///
+    /// Returns if the theme in use is any different, so anything styled with the old one can be redone.
+    pub fn apply<P: AsRef<Path>>(&self, theme_path: P) -> Result<bool, ThemeLoadError> {
+        let theme = match self {
+            ThemeChoice::Default => Theme::default(),
+            ThemeChoice::Monochrome => Theme::monochrome(),
+            ThemeChoice::Custom => load_theme_file(theme_path.as_ref())?,
+        };
+        let previous = THEME.swap(Arc::new(theme));
+        Ok(*previous != **THEME.load())
+    }
+}
+
+fn load_theme_file(path: &Path) -> Result<Theme, ThemeLoadError> {
+    if !path.exists() {
+        info!("Theme file not found at specified path, saving example file.");
+        fs::write(
+            path,
+            include_bytes!("../../example_configs/yap_theme.toml.blank"),
+        )
+        .map_err(ThemeLoadError::FileWrite)?;
+
+        return Ok(Theme::default());
+    }
+
+    let buffer = fs::read_to_string(path).map_err(ThemeLoadError::FileRead)?;
+    Ok(toml::from_str(&buffer)?)
+}
@@ -1,26 +1,62 @@
 use color_eyre::Result;
 use crossbeam::channel::{Receiver, Sender, bounded};
-use ratatui::style::Color;
-use self_update::{get_target, update::ReleaseAsset};
+use reqwest::{
+    blocking::Client,
+    header::{ACCEPT, HeaderMap, USER_AGENT},
+};
+use self_update::{
+    get_target,
+    update::{Release, ReleaseAsset},
+};
 use semver::Version;
+#[cfg(feature = "self-replace")]
+use tracing::warn;
 use tracing::{error, info};
 
-use crate::app::{App, Event};
+use crate::{
+    app::{App, Event},
+    notifications::Severity,
+    settings::{UpdateChannel, Updates},
+};
 
 #[cfg(feature = "self-replace")]
 use {
     fs_err as fs,
-    http::HeaderMap,
+    minisign_verify::{PublicKey, Signature},
     sha2::{Digest, Sha512},
     std::env::{consts::EXE_SUFFIX, current_exe},
     std::io::{BufReader, BufWriter, Read, Write},
-    std::path::PathBuf,
+    std::path::{Path, PathBuf},
     std::process::Command,
 };
 
 mod tui;
 pub use tui::*;
 
+/// Rolling release that nightly builds replace the assets of,
+/// named after the version of its current build.
+const NIGHTLY_TAG: &str = "nightly";
+
+/// Start of this build's release archive names.
+#[cfg(feature = "yap-full")]
+const BIN_FLAVOR: &str = "yap-full";
+#[cfg(all(feature = "yap-lite", not(feature = "yap-full")))]
+const BIN_FLAVOR: &str = "yap-lite";
+// Used when self-replacing isn't enabled (such as when no flavor was set)
+// so it just checks for anything newer, regardless of flavor.
+#[cfg(not(any(feature = "yap-full", feature = "yap-lite")))]
+const BIN_FLAVOR: &str = "yap";
+
+#[cfg(feature = "self-replace")]
+/// Key that every release's checksum files are signed with, so a tampered archive can't be
+/// passed off with a matching checksum beside it.
+///
+/// This is the public half (key ID `62F78A7247AB5CE6`) of the `MINISIGN_SECRET_KEY` repository
+/// secret that the release and nightly workflows sign with, as written by `minisign -G`.
+/// If that secret is ever replaced, this file has to be replaced with the new key's `.pub`
+/// in the same release, otherwise every update signed from then on is refused.
+const RELEASE_PUBLIC_KEY: &str = include_str!("release.pub");
+
 type HandleResult<T> = Result<T, UpdateBackendMissing>;
 
 #[derive(Debug, thiserror::Error)]
@@ -35,19 +71,23 @@ impl<T> From<crossbeam::channel::SendError<T>> for UpdateBackendMissing {
 
 #[derive(Debug)]
 enum UpdateCommand {
-    CheckForUpdate {
-        allow_pre_releases: bool,
-    },
+    CheckForUpdate(Updates),
     #[cfg(feature = "self-replace")]
     DownloadUpdate,
     #[cfg(feature = "self-replace")]
+    UpdateFromArchive(PathBuf),
+    #[cfg(feature = "self-replace")]
     LaunchUpdatedApp,
 }
 
 #[derive(Debug)]
 pub enum UpdateEvent {
     UpToDate,
-    UpdateFound(String),
+    UpdateFound {
+        version: String,
+        /// The release's markdown description.
+        notes: String,
+    },
     UpdateCheckError(UpdateError),
     #[cfg(feature = "self-replace")]
     DownloadProgress(f64),
@@ -71,6 +111,10 @@ pub struct UpdateBackend {
     archive_asset: Option<ReleaseAsset>,
     /// Link to SHA512 checksum for `archive_asset`
     checksum_asset: Option<ReleaseAsset>,
+    /// Link to minisign signature for `checksum_asset`
+    signature_asset: Option<ReleaseAsset>,
+    /// Proxy from the settings used for the last check, kept for downloading what it found.
+    proxy: String,
     #[cfg(feature = "self-replace")]
     current_exe: PathBuf,
 }
@@ -81,6 +125,8 @@ impl UpdateBackend {
             event_tx,
             archive_asset: None,
             checksum_asset: None,
+            signature_asset: None,
+            proxy: String::new(),
             #[cfg(feature = "self-replace")]
             current_exe: current_exe().expect("failed to get path of executable"),
         }
@@ -88,9 +134,12 @@ impl UpdateBackend {
 
     fn handle_message(&mut self, msg: UpdateCommand) -> Result<(), UpdateError> {
         match msg {
-            UpdateCommand::CheckForUpdate { allow_pre_releases } => {
-                match self.check_for_update(allow_pre_releases) {
-                    Ok(Some(new)) => self.event_tx.send(UpdateEvent::UpdateFound(new).into())?,
+            UpdateCommand::CheckForUpdate(settings) => {
+                self.proxy = settings.proxy;
+                match self.check_for_update(settings.channel) {
+                    Ok(Some((version, notes))) => self
+                        .event_tx
+                        .send(UpdateEvent::UpdateFound { version, notes }.into())?,
                     Ok(None) => self.event_tx.send(UpdateEvent::UpToDate.into())?,
                     Err(e) => self
                         .event_tx
@@ -103,6 +152,11 @@ impl UpdateBackend {
                 Err(e) => self.event_tx.send(UpdateEvent::UpdateError(e).into())?,
             },
             #[cfg(feature = "self-replace")]
+            UpdateCommand::UpdateFromArchive(path) => match self.update_from_archive(&path) {
+                Ok(()) => self.event_tx.send(UpdateEvent::ReadyToLaunch.into())?,
+                Err(e) => self.event_tx.send(UpdateEvent::UpdateError(e).into())?,
+            },
+            #[cfg(feature = "self-replace")]
             UpdateCommand::LaunchUpdatedApp => match self.start_new_version() {
                 Err(e) => self
                     .event_tx
@@ -113,36 +167,20 @@ impl UpdateBackend {
         Ok(())
     }
 
-    /// Returns Some(String) with the newest released version if found.
+    /// Returns the newest released version and its release notes if found.
     ///
     /// Returns None if the current app version is the newest/newer.
     fn check_for_update(
         &mut self,
-        allow_pre_releases: bool,
-    ) -> Result<Option<String>, UpdateError> {
-        #[cfg(feature = "yap-full")]
-        let bin_flavor = "yap-full";
-        #[cfg(all(feature = "yap-lite", not(feature = "yap-full")))]
-        let bin_flavor = "yap-lite";
-        // Used when self-replacing isn't enabled (such as when no flavor was set)
-        // so it just checks for anything newer, regardless of flavor.
-        #[cfg(not(any(feature = "yap-full", feature = "yap-lite")))]
-        let bin_flavor = "yap";
-
+        channel: UpdateChannel,
+    ) -> Result<Option<(String, String)>, UpdateError> {
+        let bin_flavor = BIN_FLAVOR;
         let current_str = env!("CARGO_PKG_VERSION");
         let current = Version::parse(current_str).expect("failed to parse app's own semver");
-        let releases = self_update::backends::github::Update::configure()
-            // .auth_token("github_pat_xyz")
-            .repo_owner("nullstalgia")
-            .repo_name("yap")
-            .bin_name("yap")
-            .current_version(current_str)
-            .build()?
-            .get_latest_releases(current_str)?;
+        let releases = self.fetch_releases()?;
 
         let newest = releases
             .into_iter()
-            .filter(|rel| allow_pre_releases || !rel.version.contains("pre"))
             .filter_map(|rel| match Version::parse(&rel.version) {
                 Ok(v) => Some((rel, v)),
                 Err(e) => {
@@ -150,6 +188,7 @@ impl UpdateBackend {
                     None
                 }
             })
+            .filter(|(_, ver)| UpdateChannel::of(ver) <= channel)
             .filter(|(_, ver)| *ver > current)
             .max_by(|(_, a_ver), (_, b_ver)| a_ver.cmp(b_ver));
 
@@ -158,76 +197,159 @@ impl UpdateBackend {
         };
 
         let target = get_target();
-        let Some((archive, checksum)) = asset_pair_for(bin_flavor, target, &release.assets) else {
-            error!("Couldn't find SHA+Archive for {bin_flavor} on {target}");
+        let Some((archive, checksum, signature)) =
+            asset_set_for(bin_flavor, target, &release.assets)
+        else {
+            error!("Couldn't find SHA+Signature+Archive for {bin_flavor} on {target}");
             return Err(UpdateError::ChecksumOrFlavorMissing)?;
         };
 
         info!(
-            "Update found! v{} archive name: {}, checksum name: {}",
-            release.version, archive.name, checksum.name
+            "Update found! v{} archive name: {}, checksum name: {}, signature name: {}",
+            release.version, archive.name, checksum.name, signature.name
         );
 
         self.archive_asset = Some(archive.clone());
         self.checksum_asset = Some(checksum.clone());
+        self.signature_asset = Some(signature.clone());
 
-        Ok(Some(release.version))
+        Ok(Some((release.version, release.body.unwrap_or_default())))
     }
-    #[cfg(feature = "self-replace")]
-    /// Streams the supplied URL's contents into the given File, checking the SHA512 hash of the archive with a supplied checksum by URL.
-    fn download_and_verify<T: Write + Unpin>(
-        &self,
-        archive_url: String,
-        checksum_url: String,
-        mut file: T,
-    ) -> Result<(), UpdateError> {
+    /// Builds a client that goes through the proxy from the settings, if one is set.
+    ///
+    /// Otherwise reqwest picks up the usual proxy environment variables by itself.
+    fn http_client(&self) -> Result<Client, UpdateError> {
         let mut headers = HeaderMap::default();
         headers.insert(
-            http::header::ACCEPT,
-            "application/octet-stream"
-                .parse()
-                .expect("pre-validated header error?"),
-        );
-        headers.insert(
-            http::header::USER_AGENT,
+            USER_AGENT,
             "yap/self-update".parse().expect("invalid user-agent"),
         );
         // headers.insert(
-        //     http::header::AUTHORIZATION,
+        //     reqwest::header::AUTHORIZATION,
         //     (String::from("token ") + "github_pat_xyz")
         //         .parse()
         //         .unwrap(),
         // );
 
-        let client = reqwest::blocking::ClientBuilder::new()
-            .default_headers(headers)
-            .build()?;
+        let mut builder = Client::builder().default_headers(headers);
+        let proxy = self.proxy.trim();
+        if !proxy.is_empty() {
+            builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(UpdateError::InvalidProxy)?);
+        }
+        Ok(builder.build()?)
+    }
+    /// Gets the most recent releases from GitHub, newest first.
+    ///
+    /// Done with our own client instead of `self_update`'s, since it has no way to set a proxy.
+    fn fetch_releases(&self) -> Result<Vec<Release>, UpdateError> {
+        #[derive(serde::Deserialize)]
+        struct GithubRelease {
+            tag_name: String,
+            name: Option<String>,
+            published_at: Option<String>,
+            body: Option<String>,
+            assets: Vec<GithubAsset>,
+        }
+        #[derive(serde::Deserialize)]
+        struct GithubAsset {
+            name: String,
+            /// API link to the asset, downloaded by asking for `application/octet-stream`.
+            url: String,
+        }
+
+        let resp = self
+            .http_client()?
+            .get("https://api.github.com/repos/nullstalgia/yap/releases")
+            .header(ACCEPT, "application/vnd.github+json")
+            .send()?;
+        if !resp.status().is_success() {
+            error!("Failed to get release list!");
+            return Err(UpdateError::InvalidHttpCode(resp.status().as_u16()));
+        }
 
-        let resp = client.get(&checksum_url).send()?;
+        let releases = resp
+            .json::<Vec<GithubRelease>>()?
+            .into_iter()
+            .map(|rel| Release {
+                // The nightly release's tag stays the same, so its version is kept in its name.
+                version: if rel.tag_name == NIGHTLY_TAG {
+                    rel.name.clone().unwrap_or_default()
+                } else {
+                    rel.tag_name.trim_start_matches('v').to_owned()
+                },
+                name: rel.name.unwrap_or_else(|| rel.tag_name.clone()),
+                date: rel.published_at.unwrap_or_default(),
+                body: rel.body,
+                assets: rel
+                    .assets
+                    .into_iter()
+                    .map(|asset| ReleaseAsset {
+                        download_url: asset.url,
+                        name: asset.name,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(releases)
+    }
+    #[cfg(feature = "self-replace")]
+    /// Streams the supplied URL's contents into the given File, checking the SHA512 hash of the archive with a supplied checksum by URL.
+    ///
+    /// The checksum itself is only trusted once its signature checks out against [`RELEASE_PUBLIC_KEY`].
+    fn download_and_verify<T: Write + Unpin>(
+        &self,
+        archive_url: String,
+        checksum_url: String,
+        signature_url: String,
+        file: T,
+    ) -> Result<(), UpdateError> {
+        let client = self.http_client()?;
+        let download = |url: &str| {
+            client
+                .get(url)
+                .header(ACCEPT, "application/octet-stream")
+                .send()
+        };
+
+        let resp = download(&checksum_url)?;
         let size = resp.content_length().unwrap_or(0);
         if !resp.status().is_success() || size == 0 {
             error!("Failed to get archive checksum!");
             return Err(UpdateError::InvalidHttpCode(resp.status().as_u16()));
         }
 
-        let content = resp.text()?;
-        // Format is `checksum *filename`
-        // So we just want the first "word" in the line
-        let expected = content
-            .split_whitespace()
-            .next()
-            .ok_or(UpdateError::ChecksumEmpty)?;
+        let content = resp.bytes()?;
+
+        let resp = download(&signature_url)?;
+        if !resp.status().is_success() {
+            error!("Failed to get checksum signature!");
+            return Err(UpdateError::InvalidHttpCode(resp.status().as_u16()));
+        }
+        let signature = resp.text()?;
+        let expected = expected_checksum(&content, &signature)?;
 
-        let resp = client.get(&archive_url).send()?;
+        let resp = download(&archive_url)?;
         let size = resp.content_length().unwrap_or(0);
         if !resp.status().is_success() || size == 0 {
             error!("Failed to get archive!");
             return Err(UpdateError::InvalidHttpCode(resp.status().as_u16()));
         }
 
-        let mut downloaded: u64 = 0;
+        let checksum = self.copy_hashed(resp, size, file)?;
+        check_checksum(&expected, checksum)
+    }
+    #[cfg(feature = "self-replace")]
+    /// Copies everything from the reader into the writer, sending progress events along the way,
+    /// returning the SHA512 hash of what was copied.
+    fn copy_hashed(
+        &self,
+        reader: impl Read,
+        size: u64,
+        mut file: impl Write,
+    ) -> Result<String, UpdateError> {
+        let mut copied: u64 = 0;
         let mut hasher = Sha512::new();
-        let mut reader = BufReader::new(resp);
+        let mut reader = BufReader::new(reader);
 
         let mut buffer = [0; 1024 * 8];
         loop {
@@ -238,8 +360,8 @@ impl UpdateBackend {
                     }
                     hasher.update(&buffer[..n]);
                     file.write_all(&buffer[..n]).map_err(UpdateError::Temp)?;
-                    downloaded += n as u64;
-                    let percentage = downloaded as f64 / size as f64;
+                    copied += n as u64;
+                    let percentage = copied as f64 / size as f64;
                     self.event_tx
                         .send(UpdateEvent::DownloadProgress(percentage).into())?;
                 }
@@ -248,20 +370,7 @@ impl UpdateBackend {
         }
 
         let result = hasher.finalize();
-        let checksum = format!("{result:x}");
-
-        if checksum.eq(expected) {
-            info!("Update checksum matches expected! SHA512: {expected}");
-            Ok(())
-        } else {
-            error!(
-                "Archive SHA512 checksum mismatch! Expected: {expected} != Calculated: {checksum}"
-            );
-            Err(UpdateError::ChecksumMismatch {
-                expected: expected.to_owned(),
-                got: checksum,
-            })
-        }
+        Ok(format!("{result:x}"))
     }
     #[cfg(feature = "self-replace")]
     /// Begin the process of downloading and verifying the archive,
@@ -269,6 +378,10 @@ impl UpdateBackend {
     fn begin_update(&mut self) -> Result<(), UpdateError> {
         let archive = self.archive_asset.take().expect("Missing archive asset");
         let checksum = self.checksum_asset.take().expect("Missing checksum asset");
+        let signature = self
+            .signature_asset
+            .take()
+            .expect("Missing signature asset");
 
         // A lot yoinked from
         // https://github.com/jaemk/self_update/blob/60b3c13533e731650031ee2c410f4bbb4483e845/src/update.rs#L227
@@ -282,22 +395,50 @@ impl UpdateBackend {
         self.download_and_verify(
             archive.download_url,
             checksum.download_url,
+            signature.download_url,
             &mut archive_writer,
         )?;
 
         archive_writer.flush().map_err(UpdateError::Temp)?;
 
-        let bin_name = env!("CARGO_PKG_NAME");
-        let bin_name = format!("{bin_name}{EXE_SUFFIX}");
-
-        self_update::Extract::from_source(&tmp_archive_path)
-            .extract_file(tmp_archive_dir.path(), &bin_name)?;
-
-        let new_exe = tmp_archive_dir.path().join(bin_name);
+        replace_from_archive(&tmp_archive_path, tmp_archive_dir.path())
+    }
+    #[cfg(feature = "self-replace")]
+    /// Verify a release archive downloaded beforehand against the checksum and signature files beside it,
+    /// then replace the currently-running executable with the one inside, for machines without internet access.
+    fn update_from_archive(&mut self, archive_path: &Path) -> Result<(), UpdateError> {
+        let checksum_path = with_suffix(archive_path, ".sha512");
+        let signature_path = with_suffix(&checksum_path, ".minisig");
+        info!(
+            "Updating from {}, checked with {} and {}",
+            archive_path.display(),
+            checksum_path.display(),
+            signature_path.display()
+        );
 
-        self_replace::self_replace(new_exe).map_err(UpdateError::SelfReplace)?;
+        let checksum = fs::read(&checksum_path).map_err(UpdateError::Archive)?;
+        let signature = fs::read_to_string(&signature_path).map_err(UpdateError::Archive)?;
+        let expected = expected_checksum(&checksum, &signature)?;
 
-        Ok(())
+        // Going by the name in the signed checksum file, in case the archive's been renamed.
+        let content = String::from_utf8_lossy(&checksum);
+        let name = content
+            .split_whitespace()
+            .nth(1)
+            .map(|name| name.trim_start_matches('*').to_owned())
+            .or_else(|| Some(archive_path.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        let current =
+            Version::parse(env!("CARGO_PKG_VERSION")).expect("failed to parse app's own semver");
+        check_archive_name(&name, BIN_FLAVOR, get_target(), &current)?;
+
+        let archive = fs::File::open(archive_path).map_err(UpdateError::Archive)?;
+        let size = archive.metadata().map_err(UpdateError::Archive)?.len();
+        let checksum = self.copy_hashed(archive, size, std::io::sink())?;
+        check_checksum(&expected, checksum)?;
+
+        let tmp_extract_dir = self_update::TempDir::new().map_err(UpdateError::Temp)?;
+        replace_from_archive(archive_path, tmp_extract_dir.path())
     }
     #[cfg(feature = "self-replace")]
     /// This should never return, unless an error occurs.
@@ -330,8 +471,12 @@ pub enum UpdateError {
     SelfUpdate(#[from] self_update::errors::Error),
     #[error("reqwest web error")]
     Reqwest(#[from] reqwest::Error),
-    #[error("error getting response contents")]
+    #[error("invalid update proxy")]
+    InvalidProxy(#[source] reqwest::Error),
+    #[error("error reading update archive contents")]
     Download(#[source] std::io::Error),
+    #[error("failed reading archive or the checksum files beside it")]
+    Archive(#[source] std::io::Error),
     #[error("unexpected http status: {0}")]
     InvalidHttpCode(u16),
     #[error("error with temporary folder/file")]
@@ -340,6 +485,12 @@ pub enum UpdateError {
     ChecksumMismatch { expected: String, got: String },
     #[error("release assets for flavor were not found")]
     ChecksumOrFlavorMissing,
+    #[cfg(feature = "self-replace")]
+    #[error("checksum signature doesn't match release key, won't install")]
+    SignatureInvalid(#[source] minisign_verify::Error),
+    #[cfg(feature = "self-replace")]
+    #[error("{name} can't be installed, {reason}")]
+    UnsuitableArchive { name: String, reason: String },
     #[error("checksum file was missing expected contents")]
     ChecksumEmpty,
     #[error("failed to replace current executable")]
@@ -354,17 +505,124 @@ impl<T> From<crossbeam::channel::SendError<T>> for UpdateError {
     }
 }
 
-/// Returns a pair of ReleaseAssets for the given target from the list of assets
+#[cfg(feature = "self-replace")]
+/// Checks the checksum file's signature, returning the SHA512 checksum in it if it's valid.
+fn expected_checksum(checksum: &[u8], signature: &str) -> Result<String, UpdateError> {
+    verify_signature(checksum, signature)?;
+    info!("Checksum signature is valid!");
+
+    let content = String::from_utf8_lossy(checksum);
+    // Format is `checksum *filename`
+    // So we just want the first "word" in the line
+    let expected = content
+        .split_whitespace()
+        .next()
+        .ok_or(UpdateError::ChecksumEmpty)?;
+    Ok(expected.to_owned())
+}
+
+#[cfg(feature = "self-replace")]
+fn check_checksum(expected: &str, checksum: String) -> Result<(), UpdateError> {
+    if checksum.eq(expected) {
+        info!("Update checksum matches expected! SHA512: {expected}");
+        Ok(())
+    } else {
+        error!("Archive SHA512 checksum mismatch! Expected: {expected} != Calculated: {checksum}");
+        Err(UpdateError::ChecksumMismatch {
+            expected: expected.to_owned(),
+            got: checksum,
+        })
+    }
+}
+
+#[cfg(feature = "self-replace")]
+/// Extract the executable from a verified archive into `extract_dir`, and swap it in for the current one.
+fn replace_from_archive(archive_path: &Path, extract_dir: &Path) -> Result<(), UpdateError> {
+    let bin_name = env!("CARGO_PKG_NAME");
+    let bin_name = format!("{bin_name}{EXE_SUFFIX}");
+
+    self_update::Extract::from_source(archive_path).extract_file(extract_dir, &bin_name)?;
+
+    let new_exe = extract_dir.join(bin_name);
+
+    self_replace::self_replace(new_exe).map_err(UpdateError::SelfReplace)?;
+
+    Ok(())
+}
+
+#[cfg(feature = "self-replace")]
+/// Checks that an archive, named like the release workflows name them
+/// (i.e. `yap-full-v0.1.1-x86_64-unknown-linux-gnu.tar.gz`), is of this flavor and target,
+/// and newer than the current version.
 ///
-/// Returns None if there aren't exactly two files for the given target and flavor
-/// (either there's too many or too little, we expect one checksum per archive).
+/// Nightly archives don't carry their version, so only their flavor and target are checked.
+fn check_archive_name(
+    name: &str,
+    flavor: &str,
+    target: &str,
+    current: &Version,
+) -> Result<(), UpdateError> {
+    let unsuitable = |reason: String| UpdateError::UnsuitableArchive {
+        name: name.to_owned(),
+        reason,
+    };
+    let stem = [".tar.gz", ".zip"]
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .ok_or_else(|| unsuitable("not a release archive".into()))?;
+    let stem = stem
+        .strip_suffix(target)
+        .and_then(|stem| stem.strip_suffix('-'))
+        .ok_or_else(|| unsuitable(format!("not built for {target}")))?;
+    let version = stem
+        .strip_prefix(flavor)
+        .and_then(|stem| stem.strip_prefix('-'))
+        .ok_or_else(|| unsuitable(format!("not a {flavor} build")))?;
+
+    if version == NIGHTLY_TAG {
+        warn!("Can't tell the version of nightly archive {name}, installing anyways");
+        return Ok(());
+    }
+    let version = Version::parse(version.trim_start_matches('v'))
+        .map_err(|e| unsuitable(format!("couldn't read its version: {e}")))?;
+    if version <= *current {
+        return Err(unsuitable(format!(
+            "v{version} isn't newer than v{current}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "self-replace")]
+/// `<path><suffix>`, like the checksum file beside an archive.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(feature = "self-replace")]
+/// Checks the checksum file's minisign signature against the key baked into the binary.
+fn verify_signature(checksum: &[u8], signature: &str) -> Result<(), UpdateError> {
+    let public_key =
+        PublicKey::decode(RELEASE_PUBLIC_KEY).expect("baked-in release key is invalid");
+    let signature = Signature::decode(signature).map_err(UpdateError::SignatureInvalid)?;
+    public_key
+        .verify(checksum, &signature, false)
+        .map_err(UpdateError::SignatureInvalid)
+}
+
+/// Returns a set of ReleaseAssets for the given target from the list of assets
 ///
-/// Returns Assets in the order of (Archive, SHA512 Checksum)
-fn asset_pair_for<'a>(
+/// Returns None if there aren't exactly three files for the given target and flavor
+/// (either there's too many or too little, we expect one checksum and one signature per archive).
+///
+/// Returns Assets in the order of (Archive, SHA512 Checksum, Checksum's minisign Signature)
+fn asset_set_for<'a>(
     flavor: &str,
     target: &str,
     releases: &'a [ReleaseAsset],
-) -> Option<(&'a ReleaseAsset, &'a ReleaseAsset)> {
+) -> Option<(&'a ReleaseAsset, &'a ReleaseAsset, &'a ReleaseAsset)> {
     let assets: Vec<&ReleaseAsset> = releases
         .iter()
         .filter(|asset| asset.name.contains(target))
@@ -372,24 +630,27 @@ fn asset_pair_for<'a>(
         .collect();
 
     #[cfg(any(feature = "yap-full", feature = "yap-lite"))]
-    // If we're checking for just `yap`, we're gonna have more than one possible pair
+    // If we're checking for just `yap`, we're gonna have more than one possible set
     // to pick from, so don't include this check if we aren't looking for
     // a specific flavor.
-    if assets.len() != 2 {
+    if assets.len() != 3 {
         return None;
     }
 
-    // I'm gonna assume we get the pair in a non-determinate order, so let's sort them ourselves.
+    // I'm gonna assume we get the set in a non-determinate order, so let's sort them ourselves.
+    let (signatures, assets): (Vec<&ReleaseAsset>, Vec<&ReleaseAsset>) = assets
+        .into_iter()
+        .partition(|asset| asset.name.ends_with(".minisig"));
     let (checksums, archives): (Vec<&ReleaseAsset>, Vec<&ReleaseAsset>) = assets
-        .iter()
+        .into_iter()
         .partition(|asset| asset.name.ends_with(".sha512"));
 
-    // Should be symmetrical since they should come in pairs
-    if checksums.len() != archives.len() {
+    // Should be symmetrical since they should come in sets
+    if checksums.len() != archives.len() || signatures.len() != archives.len() {
         return None;
     }
 
-    Some((archives[0], checksums[0]))
+    Some((archives.first()?, checksums.first()?, signatures.first()?))
 }
 
 #[derive(Debug)]
@@ -406,9 +667,10 @@ impl UpdateHandle {
         });
         Self { command_tx }
     }
-    pub fn query_latest(&self, allow_pre_releases: bool) -> HandleResult<()> {
+    /// Check for a newer release on the settings' channel, through its proxy if one is set.
+    pub fn query_latest(&self, settings: &Updates) -> HandleResult<()> {
         self.command_tx
-            .send(UpdateCommand::CheckForUpdate { allow_pre_releases })?;
+            .send(UpdateCommand::CheckForUpdate(settings.clone()))?;
         Ok(())
     }
     #[cfg(feature = "self-replace")]
@@ -417,6 +679,12 @@ impl UpdateHandle {
         Ok(())
     }
     #[cfg(feature = "self-replace")]
+    pub fn update_from_archive(&self, archive: PathBuf) -> HandleResult<()> {
+        self.command_tx
+            .send(UpdateCommand::UpdateFromArchive(archive))?;
+        Ok(())
+    }
+    #[cfg(feature = "self-replace")]
     pub fn start_new_version(&self) -> HandleResult<()> {
         self.command_tx.send(UpdateCommand::LaunchUpdatedApp)?;
         Ok(())
@@ -471,7 +739,7 @@ impl App {
                 info!("Opening {url} in browser");
                 if let Err(e) = opener::open_browser(url) {
                     let err = format!("Failed to open app repository! {e}");
-                    self.notifs.notify_str(err, Color::Red);
+                    self.notifs.notify_str(err, Severity::Error);
                 }
             }
             UpdateBeginPrompt::AskAgainLater => self.dismiss_popup(),
@@ -494,7 +762,7 @@ impl App {
                 self.settings.save()?;
                 if self
                     .update_worker
-                    .query_latest(self.settings.updates.allow_pre_releases)
+                    .query_latest(&self.settings.updates)
                     .is_err()
                 {
                     error!("Update backend missing! Did a previous check attempt fail?")
@@ -510,6 +778,22 @@ impl App {
         self.dismiss_popup();
         Ok(())
     }
+    #[cfg(feature = "self-replace")]
+    pub fn update_from_archive_choice(&mut self, choice: UpdateFromArchivePrompt) -> Result<()> {
+        use crate::app::Popup;
+
+        match choice {
+            UpdateFromArchivePrompt::Install => {
+                let Some(Popup::UpdateFromArchivePrompt(archive)) = self.popup.take() else {
+                    unreachable!("choice made without the prompt being shown");
+                };
+                self.update_worker.update_from_archive(archive.into())?;
+                self.popup = Some(Popup::UpdateDownloading(0.0));
+            }
+            UpdateFromArchivePrompt::Cancel => self.dismiss_popup(),
+        }
+        Ok(())
+    }
     #[cfg(all(windows, feature = "self-replace"))]
     pub fn update_launch_choice(&mut self, choice: UpdateLaunchPrompt) -> Result<()> {
         match choice {
@@ -522,3 +806,63 @@ impl App {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assets(names: &[&str]) -> Vec<ReleaseAsset> {
+        names
+            .iter()
+            .map(|name| ReleaseAsset {
+                download_url: format!("https://example.com/{name}"),
+                name: (*name).to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn asset_sets_need_a_signature() {
+        let target = "x86_64-unknown-linux-gnu";
+        let signed = assets(&[
+            "yap-full-x86_64-unknown-linux-gnu.tar.gz.sha512.minisig",
+            "yap-full-x86_64-unknown-linux-gnu.tar.gz",
+            "yap-full-x86_64-unknown-linux-gnu.tar.gz.sha512",
+        ]);
+        let (archive, checksum, signature) = asset_set_for("yap-full", target, &signed).unwrap();
+        assert_eq!(archive.name, "yap-full-x86_64-unknown-linux-gnu.tar.gz");
+        assert_eq!(
+            checksum.name,
+            "yap-full-x86_64-unknown-linux-gnu.tar.gz.sha512"
+        );
+        assert_eq!(
+            signature.name,
+            "yap-full-x86_64-unknown-linux-gnu.tar.gz.sha512.minisig"
+        );
+
+        // Without its signature, the checksum can't be trusted.
+        assert!(asset_set_for("yap-full", target, &signed[1..]).is_none());
+
+        // A checksum without its archive isn't a set.
+        assert!(asset_set_for("yap-full", target, &signed[2..]).is_none());
+        assert!(asset_set_for("yap-full", "aarch64-apple-darwin", &signed).is_none());
+    }
+
+    #[cfg(feature = "self-replace")]
+    #[test]
+    fn archives_checked_against_this_build() {
+        let target = "x86_64-pc-windows-msvc";
+        let current = Version::parse("0.1.1-pre.0").unwrap();
+        let check = |name: &str| check_archive_name(name, "yap-lite", target, &current);
+
+        assert!(check("yap-lite-v0.1.1-x86_64-pc-windows-msvc.zip").is_ok());
+        assert!(check("yap-lite-v0.1.1-pre.1-x86_64-pc-windows-msvc.zip").is_ok());
+        assert!(check("yap-lite-nightly-x86_64-pc-windows-msvc.zip").is_ok());
+
+        assert!(check("yap-lite-v0.1.1-pre.0-x86_64-pc-windows-msvc.zip").is_err());
+        assert!(check("yap-lite-v0.1.0-x86_64-pc-windows-msvc.zip").is_err());
+        assert!(check("yap-full-v0.1.1-x86_64-pc-windows-msvc.zip").is_err());
+        assert!(check("yap-lite-v0.1.1-x86_64-unknown-linux-gnu.tar.gz").is_err());
+        assert!(check("yap-lite-v0.1.1-x86_64-pc-windows-msvc.exe").is_err());
+    }
+}
@@ -1,5 +1,64 @@
+use std::sync::LazyLock;
+
+use ratatui::{
+    style::{Style, Stylize},
+    text::{Line, Span},
+};
+use regex::Regex;
+
 use crate::tui::prompts::PromptKeybind;
 
+/// `[text](url)` links and `![alt](url)` images, keeping just the text.
+static MARKDOWN_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").expect("invalid link regex"));
+
+/// Render a release's markdown body as plain lines, keeping just enough of its structure to skim.
+///
+/// Headings are bolded, list items get bullets, and links, emphasis, and comments are dropped.
+pub fn release_notes_lines(markdown: &str) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut in_comment = false;
+    for raw in markdown.lines() {
+        let trimmed = raw.trim();
+        if in_comment || trimmed.starts_with("<!--") {
+            in_comment = !trimmed.contains("-->");
+            continue;
+        }
+
+        let text = MARKDOWN_LINK.replace_all(trimmed, "$1");
+        let text = text.replace("**", "").replace("__", "").replace('`', "");
+
+        let indent = " ".repeat(raw.len() - raw.trim_start().len());
+        let line = if let Some(heading) = text.strip_prefix('#') {
+            Line::styled(
+                heading.trim_start_matches('#').trim().to_owned(),
+                Style::new().bold(),
+            )
+        } else if let Some(item) = text.strip_prefix("- ").or_else(|| text.strip_prefix("* ")) {
+            Line::from(vec![
+                Span::raw(format!("{indent}• ")),
+                Span::raw(item.to_owned()),
+            ])
+        } else if text.chars().all(|c| matches!(c, '-' | '*' | '_')) && text.len() >= 3 {
+            // Horizontal rules
+            Line::raw("")
+        } else {
+            Line::raw(format!("{indent}{text}"))
+        };
+
+        // Only keeping one blank line between paragraphs, and none at the start.
+        let blank = line.width() == 0;
+        if blank && lines.last().is_none_or(|last: &Line| last.width() == 0) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|last| last.width() == 0) {
+        lines.pop();
+    }
+    lines
+}
+
 #[derive(
     Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
 )]
@@ -39,6 +98,23 @@ pub enum UpdateBeginPrompt {
 
 impl PromptKeybind for UpdateBeginPrompt {}
 
+#[cfg(feature = "self-replace")]
+#[derive(
+    Debug, Clone, strum::VariantNames, strum::VariantArray, strum::EnumProperty, int_enum::IntEnum,
+)]
+#[repr(u8)]
+#[strum(serialize_all = "title_case")]
+pub enum UpdateFromArchivePrompt {
+    #[strum(props(keybind = "i"))]
+    #[strum(serialize = "Verify and Install")]
+    Install,
+    #[strum(props(keybind = "c"))]
+    Cancel,
+}
+
+#[cfg(feature = "self-replace")]
+impl PromptKeybind for UpdateFromArchivePrompt {}
+
 // Windows-only, since Unix OSes let you just swap out the currently running executable.
 // Meanwhile Windows requires you open a new *console window* and spawn the executable in there.
 // And since I don't want to check if we're being run in CMD, just offer the option to open that or just close so the user can relaunch.
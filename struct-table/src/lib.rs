@@ -1,9 +1,56 @@
 use std::{error::Error, fmt::Display};
 
-use ratatui::widgets::Table;
+use ratatui::{
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Style, Stylize},
+    text::Text,
+    widgets::{Row, Table, TableState},
+};
 
 pub use struct_table_derive::*;
 
+/// For `#[table(value_align)]`, so deriving crates don't need ratatui themselves.
+pub use ratatui::layout::Alignment;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// How [`StructTable::as_table`] splits its width between the label and value columns,
+/// and lines up the values within theirs.
+pub struct TableLayout {
+    /// Widths of the label and value columns, in that order.
+    pub widths: [Constraint; 2],
+    pub value_alignment: Alignment,
+}
+
+impl TableLayout {
+    /// An even split, with values centered.
+    pub const DEFAULT: Self = Self {
+        widths: [Constraint::Percentage(50), Constraint::Percentage(50)],
+        value_alignment: Alignment::Center,
+    };
+    /// Giving the label column the given percentage of the width, and values the rest.
+    pub const fn label_percent(percent: u16) -> Self {
+        Self {
+            widths: [
+                Constraint::Percentage(percent),
+                Constraint::Percentage(100 - percent),
+            ],
+            ..Self::DEFAULT
+        }
+    }
+    pub const fn value_alignment(self, value_alignment: Alignment) -> Self {
+        Self {
+            value_alignment,
+            ..self
+        }
+    }
+}
+
+impl Default for TableLayout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ArrowKey {
     Up,
@@ -24,27 +71,223 @@ impl std::fmt::Display for ArrowKey {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Mouse input over a table from [`StructTable::as_table`], for [`StructTable::handle_mouse`].
+pub enum TableMouse {
+    /// A left click at the given terminal position.
+    Click(Position),
+    ScrollUp,
+    ScrollDown,
+}
+
 #[derive(Debug)]
-pub struct InvalidFieldIndex;
+pub enum FieldInputError {
+    /// A change was attempted at an invalid field index (>= field amount).
+    InvalidFieldIndex,
+    /// The field's `validate` function refused the new value, with the reason why.
+    Rejected(String),
+}
 
-impl Display for InvalidFieldIndex {
+impl Display for FieldInputError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "given field index out of range")
+        match self {
+            FieldInputError::InvalidFieldIndex => write!(f, "given field index out of range"),
+            FieldInputError::Rejected(reason) => write!(f, "{reason}"),
+        }
     }
 }
 
-impl Error for InvalidFieldIndex {}
+impl Error for FieldInputError {}
+
+#[derive(Debug)]
+/// Why typed text couldn't be applied to a field.
+pub struct FieldParseError(pub String);
+
+impl Display for FieldParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for FieldParseError {}
 
 pub trait StructTable: ::core::marker::Sized + 'static {
-    /// Returns `true` if the input caused a change in the struct.
+    /// Returns the index of the changed field if the input caused a change in the struct,
+    /// for hosts keeping their own state tied to one field (like a text input mirroring it).
+    ///
+    /// The index only covers this one change, so whatever applies the struct as a whole
+    /// (possibly after several edits, or from elsewhere) still has to compare it to before.
     ///
-    /// Returns an `Err` if a change was attempted at an invalid field index (>= field amount).
+    /// Returns an `Err` if a change was attempted at an invalid field index (>= field amount),
+    /// or if the field's `validate` function refused the new value.
     fn handle_input(
         &mut self,
         input: ArrowKey,
         field_index: usize,
-    ) -> Result<bool, InvalidFieldIndex>;
-    fn as_table(&self) -> Table<'_>;
+    ) -> Result<Option<usize>, FieldInputError>;
+    /// The text to start an inline editor with, if the field can be typed into directly.
+    ///
+    /// Returns `None` for fields that can only be cycled through,
+    /// and for invalid field indexes.
+    fn edit_text(&self, field_index: usize) -> Option<String>;
+    /// Parse and apply text typed into a field that [`StructTable::edit_text`] gave `Some` for.
+    ///
+    /// Returns the index of the changed field if the text caused a change in the struct.
+    fn apply_text(
+        &mut self,
+        field_index: usize,
+        text: &str,
+    ) -> Result<Option<usize>, FieldParseError>;
+    /// Put the field at the given index back to its `#[table(default)]` value,
+    /// or its value in the struct's `Default` impl otherwise.
+    ///
+    /// Returns the index of the field if it wasn't at its default already.
+    /// Section and nested headers, and immutable fields, are left as they are.
+    fn reset_field(&mut self, field_index: usize) -> Result<Option<usize>, FieldInputError>;
+    /// Runs the `on_change` hook of the field at the given index, if it has one.
+    ///
+    /// Called by [`StructTable::handle_input`], [`StructTable::apply_text`],
+    /// and [`StructTable::reset_field`] after a change.
+    fn field_changed(&mut self, field_index: usize);
+    /// If the row at the given index is hidden by its field's `hide_if` predicate.
+    ///
+    /// Field indexes still count hidden rows, use [`StructTable::field_index_of_row`]
+    /// and [`StructTable::row_of_field_index`] to convert to and from rows as shown.
+    fn is_row_hidden(&self, field_index: usize) -> bool;
+    /// Amount of rows currently shown, fewer than [`StructTable::VISIBLE_FIELDS`] if any are hidden.
+    fn shown_rows(&self) -> usize {
+        (0..Self::VISIBLE_FIELDS)
+            .filter(|index| !self.is_row_hidden(*index))
+            .count()
+    }
+    /// The field index of a row as shown, or `None` if there aren't that many rows shown.
+    fn field_index_of_row(&self, row: usize) -> Option<usize> {
+        (0..Self::VISIBLE_FIELDS)
+            .filter(|index| !self.is_row_hidden(*index))
+            .nth(row)
+    }
+    /// The row a field is shown at, or the row after where it'd be if it's hidden.
+    fn row_of_field_index(&self, field_index: usize) -> usize {
+        (0..field_index)
+            .filter(|index| !self.is_row_hidden(*index))
+            .count()
+    }
+    /// If the row at the given index is a `#[table(section)]` header,
+    /// which hosts should skip over when moving the selection.
+    fn is_section_header(field_index: usize) -> bool;
+    /// Each visible row's label (with trailing colon) and current displayed value, in order.
+    ///
+    /// Rows of `#[table(nested)]` fields follow their header row, indented.
+    /// Section header rows are labelled with just the section's name.
+    fn rows(&self) -> Vec<(String, String)>;
+    fn as_table(&self) -> Table<'_> {
+        let cell_highlight_style = Style::new().reversed().italic();
+
+        let rows: Vec<Row> = self
+            .rows()
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !self.is_row_hidden(*index))
+            .map(|(index, (label, value))| {
+                if Self::is_section_header(index) {
+                    Row::new([Text::raw(label).right_aligned().bold(), Text::raw(value)])
+                } else {
+                    Row::new([
+                        Text::raw(label).right_aligned(),
+                        Text::raw(value)
+                            .alignment(Self::LAYOUT.value_alignment)
+                            .italic(),
+                    ])
+                }
+            })
+            .collect();
+
+        Table::new(rows, Self::LAYOUT.widths).cell_highlight_style(cell_highlight_style)
+    }
+    /// Select the clicked row, cycling it forward if its value was clicked,
+    /// or move the selection a row when scrolled. Section headers can't be selected.
+    ///
+    /// `area` and `state` should be what the table was last rendered with,
+    /// so the state's selection is a row as shown.
+    ///
+    /// Returns the index of the changed field like [`StructTable::handle_input`].
+    fn handle_mouse(
+        &mut self,
+        mouse: TableMouse,
+        area: Rect,
+        state: &mut TableState,
+    ) -> Result<Option<usize>, FieldInputError> {
+        let selectable = |table: &Self, row: usize| {
+            table
+                .field_index_of_row(row)
+                .is_some_and(|index| !Self::is_section_header(index))
+        };
+        match mouse {
+            TableMouse::ScrollUp => {
+                let Some(selected) = state.selected() else {
+                    return Ok(None);
+                };
+                if let Some(row) = (0..selected).rev().find(|row| selectable(self, *row)) {
+                    state.select(Some(row));
+                }
+                Ok(None)
+            }
+            TableMouse::ScrollDown => {
+                let start = state.selected().map_or(0, |selected| selected + 1);
+                if let Some(row) = (start..self.shown_rows()).find(|row| selectable(self, *row)) {
+                    state.select(Some(row));
+                }
+                Ok(None)
+            }
+            TableMouse::Click(position) => {
+                if !area.contains(position) {
+                    return Ok(None);
+                }
+                let row = state.offset() + usize::from(position.y - area.y);
+                if !selectable(self, row) {
+                    return Ok(None);
+                }
+                state.select(Some(row));
+
+                // Same spacing as the table's default.
+                let [_, value_area] = Layout::horizontal(Self::LAYOUT.widths)
+                    .spacing(1)
+                    .areas(area);
+                if position.x < value_area.x {
+                    return Ok(None);
+                }
+                let field_index = self
+                    .field_index_of_row(row)
+                    .expect("selectable rows are shown");
+                self.handle_input(ArrowKey::Right, field_index)
+            }
+        }
+    }
+    /// Displayed name of each visible field, without the trailing colon.
+    const FIELD_NAMES: &'static [&'static str];
     const DOCSTRINGS: &'static [&'static str];
     const VISIBLE_FIELDS: usize;
+    /// Column widths and value alignment of [`StructTable::as_table`],
+    /// set with `#[table(label_percent = 40, value_align = "left")]` on the struct.
+    const LAYOUT: TableLayout = TableLayout::DEFAULT;
+}
+
+#[doc(hidden)]
+/// Joins the given slices into one array, used by the derive for `#[table(nested)]` fields
+/// since slices can't be concatenated in const contexts otherwise.
+pub const fn concat_strs<const N: usize>(parts: &[&[&'static str]]) -> [&'static str; N] {
+    let mut joined = [""; N];
+    let mut index = 0;
+    let mut part = 0;
+    while part < parts.len() {
+        let mut inner = 0;
+        while inner < parts[part].len() {
+            joined[index] = parts[part][inner];
+            index += 1;
+            inner += 1;
+        }
+        part += 1;
+    }
+    assert!(index == N, "joined length should match visible field count");
+    joined
 }
@@ -1,7 +1,7 @@
 use heck::ToTitleCase;
 use proc_macro2_diagnostics::SpanDiagnosticExt;
 use quote::quote;
-use syn::DeriveInput;
+use syn::{DeriveInput, spanned::Spanned};
 
 extern crate proc_macro;
 
@@ -19,7 +19,7 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
     // panic!("{ast:#?}");
 
     // Extracting the 'struct-global' attributes
-    let _struct_attrs: StructTableAttributes = deluxe::extract_attributes(&mut ast)?;
+    let struct_attrs: StructTableAttributes = deluxe::extract_attributes(&mut ast)?;
     // panic!("{ast:#?}");
     // Vec of each processed field, in order of declaration
     let field_attrs: Vec<StructField> = extract_field_attrs(&mut ast)?;
@@ -45,7 +45,16 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
         .iter()
         .map(|f| {
             let ident = &f.ident;
-            let field_to_string = if let Some(overrides) = &f.display_override {
+            // The value being shown, which for Option fields is what's inside a `Some`
+            let value = if f.is_option {
+                quote! { (*value) }
+            } else {
+                quote! { self.#ident }
+            };
+            let field_to_string = if f.nested {
+                // Header rows for nested fields are left blank, the nested rows follow below.
+                quote! { "" }
+            } else if let Some(overrides) = &f.display_override {
                 // If an override for Display was given
 
                 let use_debug = match overrides {
@@ -76,7 +85,7 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
                             let overrides_ref: &[&'static str] = overrides.as_ref();
 
 
-                            let label_index: usize = if self.#ident { 1 } else { 0 };
+                            let label_index: usize = if #value { 1 } else { 0 };
 
                             overrides_ref[label_index]
                         }
@@ -84,7 +93,7 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
                 } else if use_debug {
                     // if display is set to Debug, use the field's type's Debug impl
                     quote! {
-                        format!("{:?}", self.#ident)
+                        format!("{:?}", #value)
                     }
                 } else {
                     // Getting the overridden label with the same index of the field's value
@@ -94,15 +103,15 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
                         .expect("expected list of values to cycle through");
                     let allow_unknown = if f.allow_unknown_values {
                         quote! {
-                            if let Some(current_position) = variants.iter().position(|v: &_| v == &self.#ident ) {
+                            if let Some(current_position) = variants.iter().position(|v: &_| v == &#value ) {
                                 overrides_ref[current_position].to_string()
                             } else {
-                                self.#ident.to_string()
+                                #value.to_string()
                             }
                         }
                     } else {
                         quote! {
-                            let current_position: usize = variants.iter().position(|v: &_| v == &self.#ident ).expect("current variant not in given list");
+                            let current_position: usize = variants.iter().position(|v: &_| v == &#value ).expect("current variant not in given list");
                             overrides_ref[current_position]
                         }
                     };
@@ -121,14 +130,33 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
                         }
                     }
                 }
+            } else if let Some(format) = &f.format {
+                quote! {
+                    #format(&#value)
+                }
             } else {
                 // If no override was given, attempt to convert the field's value to a String
                 quote! {
-                    self.#ident.to_string()
+                    #value.to_string()
                 }
             };
 
-            field_to_string
+            let field_to_string = match &f.unit {
+                Some(unit) => quote! { format!("{} {}", #field_to_string, #unit) },
+                None => field_to_string,
+            };
+
+            if f.is_option {
+                let none_label = f.none_label.as_deref().unwrap_or("None");
+                quote! {
+                    match &self.#ident {
+                        ::core::option::Option::None => ::std::string::ToString::to_string(#none_label),
+                        ::core::option::Option::Some(value) => ::std::string::ToString::to_string(&#field_to_string),
+                    }
+                }
+            } else {
+                field_to_string
+            }
         })
         .collect();
 
@@ -139,9 +167,345 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
 
     // }
 
+    let field_names: Vec<&str> = field_human_names
+        .iter()
+        .map(|name| name.trim_end_matches(':'))
+        .collect();
+
     let docstrings: Vec<_> = field_attrs.iter().map(|a| a.doc.clone()).collect();
 
-    let docstrings_len = docstrings.len();
+    // How many rows each field takes up, a nested field's header plus all of its own rows.
+    let field_row_counts: Vec<proc_macro2::TokenStream> = field_attrs
+        .iter()
+        .map(|f| {
+            if f.nested {
+                let ty = &f.ty;
+                quote! { (1 + <#ty as ::struct_table::StructTable>::VISIBLE_FIELDS) }
+            } else {
+                quote! { 1 }
+            }
+        })
+        .collect();
+
+    // Fields starting a section have its header row placed right above them.
+    let block_row_counts: Vec<proc_macro2::TokenStream> = field_attrs
+        .iter()
+        .zip(&field_row_counts)
+        .map(|(f, count)| {
+            if f.section.is_some() {
+                quote! { (1 + #count) }
+            } else {
+                count.clone()
+            }
+        })
+        .collect();
+
+    // Index of each section header row, and each field's (first) row, as const expressions.
+    let (section_offsets, field_offsets): (Vec<Option<proc_macro2::TokenStream>>, Vec<_>) =
+        field_attrs
+            .iter()
+            .zip(&block_row_counts)
+            .scan(quote! { 0usize }, |offset, (f, count)| {
+                let block_offset = offset.clone();
+                // Parenthesized, since they're subtracted from in the generated code.
+                *offset = quote! { (#offset + #count) };
+                if f.section.is_some() {
+                    Some((Some(block_offset.clone()), quote! { (#block_offset + 1) }))
+                } else {
+                    Some((None, block_offset))
+                }
+            })
+            .unzip();
+
+    let visible_fields = quote! { 0usize #( + #block_row_counts )* };
+
+    // Header rows can't be changed, but are still valid indexes.
+    let section_arms: Vec<proc_macro2::TokenStream> = section_offsets
+        .iter()
+        .flatten()
+        .map(|offset| quote! { i if i == #offset => (), })
+        .collect();
+
+    let section_header_arms: Vec<proc_macro2::TokenStream> = field_attrs
+        .iter()
+        .zip(section_offsets.iter().zip(&field_offsets))
+        .flat_map(|(f, (section_offset, offset))| {
+            let section_arm = section_offset
+                .as_ref()
+                .map(|section_offset| quote! { i if i == #section_offset => true, });
+            let nested_arm = f.nested.then(|| {
+                let ty = &f.ty;
+                quote! {
+                    i if i > #offset && i <= #offset + <#ty as ::struct_table::StructTable>::VISIBLE_FIELDS => {
+                        <#ty as ::struct_table::StructTable>::is_section_header(i - #offset - 1)
+                    },
+                }
+            });
+            section_arm.into_iter().chain(nested_arm)
+        })
+        .collect();
+
+    let has_nested = field_attrs.iter().any(|f| f.nested);
+
+    // Nested fields' names and docstrings have to be joined in after their header's,
+    // which can't be written out as a plain array.
+    let (field_names_const, docstrings_const) = if has_nested {
+        let ident = &ast.ident;
+        let (name_parts, doc_parts): (Vec<_>, Vec<_>) = field_attrs
+            .iter()
+            .zip(field_names.iter().zip(&docstrings))
+            .flat_map(|(f, (name, doc))| {
+                let section = f
+                    .section
+                    .as_ref()
+                    .map(|section| (quote! { &[#section] }, quote! { &[""] }));
+                let field = if f.nested {
+                    let ty = &f.ty;
+                    (
+                        quote! { &[#name], <#ty as ::struct_table::StructTable>::FIELD_NAMES },
+                        quote! { &[#doc], <#ty as ::struct_table::StructTable>::DOCSTRINGS },
+                    )
+                } else {
+                    (quote! { &[#name] }, quote! { &[#doc] })
+                };
+                section.into_iter().chain([field])
+            })
+            .unzip();
+        (
+            quote! {
+                &::struct_table::concat_strs::<{ <#ident as ::struct_table::StructTable>::VISIBLE_FIELDS }>(&[ #(#name_parts),* ])
+            },
+            quote! {
+                &::struct_table::concat_strs::<{ <#ident as ::struct_table::StructTable>::VISIBLE_FIELDS }>(&[ #(#doc_parts),* ])
+            },
+        )
+    } else {
+        let (row_names, row_docs): (Vec<&str>, Vec<&str>) = field_attrs
+            .iter()
+            .zip(field_names.iter().zip(&docstrings))
+            .flat_map(|(f, (name, doc))| {
+                let section = f.section.as_deref().map(|section| (section, ""));
+                section.into_iter().chain([(*name, doc.as_str())])
+            })
+            .unzip();
+        (
+            quote! { &[ #(#row_names),* ] },
+            quote! { &[ #(#row_docs),* ] },
+        )
+    };
+
+    // Building each field's row(s), plus the indented rows of nested fields.
+    let row_pushes: Vec<proc_macro2::TokenStream> = field_attrs
+        .iter()
+        .zip(field_human_names.iter().zip(&field_string_values))
+        .map(|(f, (name, value))| {
+            let ident = &f.ident;
+            let section = f.section.as_ref().map(|section| {
+                quote! {
+                    rows.push((#section.to_string(), ::std::string::String::new()));
+                }
+            });
+            let header = quote! {
+                #section
+                rows.push((#name.to_string(), ::std::string::ToString::to_string(&#value)));
+            };
+            if f.nested {
+                quote! {
+                    #header
+                    rows.extend(
+                        ::struct_table::StructTable::rows(&self.#ident)
+                            .into_iter()
+                            .map(|(label, value)| (format!("  {label}"), value)),
+                    );
+                }
+            } else {
+                header
+            }
+        })
+        .collect();
+
+    // Fields that can be typed into, and how to parse what was typed into them
+    let (edit_text_arms, apply_text_arms): (Vec<_>, Vec<_>) = field_attrs
+        .iter()
+        .zip(&field_offsets)
+        .filter_map(|(f, offset)| {
+            let ident = &f.ident;
+            let ty = &f.ty;
+            if f.nested {
+                let nested_indexes = quote! {
+                    i if i > #offset && i <= #offset + <#ty as ::struct_table::StructTable>::VISIBLE_FIELDS
+                };
+                return Some((
+                    quote! {
+                        #nested_indexes => ::struct_table::StructTable::edit_text(&self.#ident, i - #offset - 1),
+                    },
+                    quote! {
+                        #nested_indexes => ::struct_table::StructTable::apply_text(&mut self.#ident, i - #offset - 1, text)?.is_some(),
+                    },
+                ));
+            }
+            if f.immutable {
+                return None;
+            }
+            let validate = f.validate.as_ref().map(|validate| {
+                quote! {
+                    let value: #ty = #validate(value).map_err(::struct_table::FieldParseError)?;
+                }
+            });
+            let edit_text_arm = quote! {
+                i if i == #offset => ::core::option::Option::Some(::std::string::ToString::to_string(&self.#ident)),
+            };
+            if f.text {
+                let parsed = match &f.parse {
+                    Some(parse) => quote! { #parse(text) },
+                    None => quote! { text.parse::<#ty>() },
+                };
+                return Some((
+                    edit_text_arm,
+                    quote! {
+                        i if i == #offset => {
+                            let value: #ty = #parsed
+                                .map_err(|e| ::struct_table::FieldParseError(format!("{e}")))?;
+                            #validate
+                            let changed = value != self.#ident;
+                            self.#ident = value;
+                            changed
+                        },
+                    },
+                ));
+            }
+            let RangeAttr { min, max, .. } = f.range.as_ref()?;
+            Some((
+                edit_text_arm,
+                quote! {
+                    i if i == #offset => {
+                        let (min, max): (#ty, #ty) = (#min, #max);
+                        let value: #ty = text
+                            .trim()
+                            .parse()
+                            .map_err(|e| ::struct_table::FieldParseError(format!("{e}")))?;
+                        if value < min || value > max {
+                            return Err(::struct_table::FieldParseError(format!(
+                                "must be between {min} and {max}"
+                            )));
+                        }
+                        #validate
+                        let changed = value != self.#ident;
+                        self.#ident = value;
+                        changed
+                    },
+                },
+            ))
+        })
+        .unzip();
+
+    // Without any typeable fields every arm would return early, leaving the rest unreachable
+    let apply_text_body = if apply_text_arms.is_empty() {
+        quote! {
+            let _ = (field_index, text);
+            Err(::struct_table::FieldParseError("field can't be typed into".to_owned()))
+        }
+    } else {
+        quote! {
+            let changed: bool = match field_index {
+                #( #apply_text_arms )*
+                _ => return Err(::struct_table::FieldParseError("field can't be typed into".to_owned())),
+            };
+
+            if changed {
+                self.field_changed(field_index);
+            }
+
+            Ok(changed.then_some(field_index))
+        }
+    };
+
+    // Hiding rows of fields whose hide_if predicate is true,
+    // along with nested fields' own hidden rows
+    let hidden_row_arms: Vec<_> = field_attrs
+        .iter()
+        .zip(&field_offsets)
+        .filter_map(|(f, offset)| {
+            let ident = &f.ident;
+            let ty = &f.ty;
+            let hide_if = f.hide_if.as_ref().map(|hide_if| quote! { #hide_if(self) });
+            if f.nested {
+                let nested_hidden = quote! {
+                    ::struct_table::StructTable::is_row_hidden(&self.#ident, i - #offset - 1)
+                };
+                let nested_indexes = quote! {
+                    i > #offset && i <= #offset + <#ty as ::struct_table::StructTable>::VISIBLE_FIELDS
+                };
+                return Some(match hide_if {
+                    Some(hide_if) => quote! {
+                        i if i == #offset => #hide_if,
+                        i if #nested_indexes => #hide_if || #nested_hidden,
+                    },
+                    None => quote! {
+                        i if #nested_indexes => #nested_hidden,
+                    },
+                });
+            }
+            let hide_if = hide_if?;
+            Some(quote! {
+                i if i == #offset => #hide_if,
+            })
+        })
+        .collect();
+
+    // Putting fields back to their default values, from the struct's Default impl
+    // unless one was given, or leaving it to a nested field's own defaults
+    let reset_arms: Vec<_> = field_attrs
+        .iter()
+        .zip(&field_offsets)
+        .filter_map(|(f, offset)| {
+            let ident = &f.ident;
+            let ty = &f.ty;
+            if f.nested {
+                return Some(quote! {
+                    i if i > #offset && i <= #offset + <#ty as ::struct_table::StructTable>::VISIBLE_FIELDS => {
+                        ::struct_table::StructTable::reset_field(&mut self.#ident, i - #offset - 1)?.is_some()
+                    },
+                });
+            }
+            if f.immutable {
+                return None;
+            }
+            let default = match &f.default {
+                Some(default) => quote! { #default },
+                None if struct_attrs.no_default => return None,
+                None => quote! { <Self as ::core::default::Default>::default().#ident },
+            };
+            Some(quote! {
+                i if i == #offset => {
+                    let value: #ty = #default;
+                    let changed = value != self.#ident;
+                    self.#ident = value;
+                    changed
+                },
+            })
+        })
+        .collect();
+
+    // Calling each field's on_change hook, for any of its rows if it's nested
+    let on_change_arms: Vec<_> = field_attrs
+        .iter()
+        .zip(&field_offsets)
+        .filter_map(|(f, offset)| {
+            let on_change = f.on_change.as_ref()?;
+            let ty = &f.ty;
+            let indexes = if f.nested {
+                quote! {
+                    i if i > #offset && i <= #offset + <#ty as ::struct_table::StructTable>::VISIBLE_FIELDS
+                }
+            } else {
+                quote! { i if i == #offset }
+            };
+            Some(quote! {
+                #indexes => #on_change(self),
+            })
+        })
+        .collect();
 
     let inner_wrap = |no_wrap: bool| -> proc_macro2::TokenStream {
         if !no_wrap {
@@ -179,20 +543,85 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
         }
     };
 
-    // Vec of field indices for match arms + the logic to cycle between values
-    let (field_indices, field_arms): (Vec<_>, Vec<_>) = field_attrs
+    // The logic to cycle between values for each field, matched by its row index
+    let field_arms: Vec<_> = field_attrs
         .into_iter()
-        .map(|a| {
+        .zip(&field_offsets)
+        .map(|(a, offset)| {
             let ident = &a.ident;
+            // Running the field's validate function on its new value,
+            // putting the old value back if it's refused.
+            let validated = |change: proc_macro2::TokenStream| -> proc_macro2::TokenStream {
+                match &a.validate {
+                    Some(validate) => quote! {
+                        let previous = ::core::clone::Clone::clone(&self.#ident);
+                        #change
+                        match #validate(::core::clone::Clone::clone(&self.#ident)) {
+                            Ok(value) => self.#ident = value,
+                            Err(reason) => {
+                                self.#ident = previous;
+                                return Err(::struct_table::FieldInputError::Rejected(reason));
+                            }
+                        }
+                    },
+                    None => change,
+                }
+            };
+            // Header rows do nothing, the rest are handed to the nested field with its own index
+            if a.nested {
+                let ty = &a.ty;
+                return quote! {
+                    i if i == #offset => (),
+                    i if i > #offset && i <= #offset + <#ty as ::struct_table::StructTable>::VISIBLE_FIELDS => {
+                        self_changed = ::struct_table::StructTable::handle_input(&mut self.#ident, input, i - #offset - 1)?.is_some();
+                    },
+                };
+            }
             // Do nothing if we're just ignoring this value
             if a.immutable {
                 return quote! {
-                    ();
+                    i if i == #offset => {
+                        self_changed = true;
+                    },
+                };
+            }
+            // Text fields are only changed by typing into them
+            if a.text {
+                return quote! {
+                    i if i == #offset => (),
+                };
+            }
+            // Ranges step by the given amount, stopping at either end
+            if let Some(RangeAttr { min, max, step }) = &a.range {
+                let ty = &a.ty;
+                let change = validated(quote! {
+                        let (min, max, step): (#ty, #ty, #ty) = (#min, #max, #step);
+                        let current = self.#ident;
+                        let new_value = if next {
+                            if current >= max || max - current < step {
+                                max
+                            } else {
+                                current + step
+                            }
+                        } else {
+                            if current <= min || current - min < step {
+                                min
+                            } else {
+                                current - step
+                            }
+                        };
+                        self_changed = new_value != current;
+                        self.#ident = new_value;
+                });
+                return quote! {
+                    i if i == #offset => {
+                        #change
+                    },
                 };
             }
             // Bools just always flip
             // (regardless of no_wrap, for now?)
-            if a.is_bool {
+            let arm = if a.is_bool {
                 quote! {
                     self.#ident = !self.#ident;
                 }
@@ -202,6 +631,54 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
                     .expect("expected list of values to cycle through");
 
                 let inner_wrap_logic = inner_wrap(a.no_inner_wrap);
+                // Runtime lists can turn up empty, leaving nothing to cycle to
+                let empty_check = a.runtime_values.then(|| {
+                    quote! {
+                        if variants_ref.is_empty() {
+                            return Ok(None);
+                        }
+                    }
+                });
+                if a.is_option {
+                    // `None` comes first, followed by each of the given values
+                    let unknown_position = if a.allow_unknown_values {
+                        quote! { .unwrap_or(variants_ref.len() + 1) }
+                    } else {
+                        quote! { .expect("current variant not in given list") }
+                    };
+                    let change = validated(quote! {
+                            let variants: _ = #variants;
+
+                            let variants_ref: &[_] = variants.as_ref();
+
+                            #empty_check
+
+                            let current_position: usize = match &self.#ident {
+                                ::core::option::Option::None => 0,
+                                ::core::option::Option::Some(value) => variants_ref
+                                    .iter()
+                                    .position(|v: &_| v == value)
+                                    .map(|position| position + 1)
+                                    #unknown_position,
+                            };
+
+                            let last_index = variants_ref.len();
+
+                            #inner_wrap_logic
+
+                            self.#ident = if new_index == 0 {
+                                ::core::option::Option::None
+                            } else {
+                                ::core::option::Option::Some(variants_ref[new_index - 1].clone().into())
+                            };
+                    });
+                    return quote! {
+                        i if i == #offset => {
+                            #change
+                            self_changed = true;
+                        },
+                    };
+                }
                 let allow_unknown = if a.allow_unknown_values {
                     quote! { let current_position: usize = variants_ref.iter().position(|v: &_| v == &self.#ident ).unwrap_or(variants_ref.len()); }
                 } else {
@@ -212,6 +689,8 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
 
                     let variants_ref: &[_] = variants.as_ref();
 
+                    #empty_check
+
                     #allow_unknown
 
                     let last_index = variants_ref.len() - 1;
@@ -220,10 +699,16 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
 
                     self.#ident = variants_ref[new_index].clone().into();
                 }
+            };
+            let change = validated(quote! { #arm; });
+            quote! {
+                i if i == #offset => {
+                    #change
+                    self_changed = true;
+                },
             }
         })
-        .enumerate()
-        .unzip();
+        .collect();
 
     let ident = &ast.ident;
 
@@ -231,7 +716,41 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
         return Err(ident.span().error("Struct needs fields!").into());
     }
 
-    let final_field_index: usize = field_arms.len() - 1;
+    // Only overriding the layout if the struct asks for a different one
+    let layout_const = if struct_attrs.label_percent.is_some() || struct_attrs.value_align.is_some()
+    {
+        let base = match struct_attrs.label_percent {
+            Some(percent) if percent > 100 => {
+                return Err(ident.span().error("label_percent can't be over 100").into());
+            }
+            Some(percent) => quote! { ::struct_table::TableLayout::label_percent(#percent) },
+            None => quote! { ::struct_table::TableLayout::DEFAULT },
+        };
+        let alignment = match struct_attrs.value_align.as_deref() {
+            None => None,
+            Some("left") => Some(quote! { Left }),
+            Some("center") => Some(quote! { Center }),
+            Some("right") => Some(quote! { Right }),
+            Some(_) => {
+                return Err(ident
+                    .span()
+                    .error("value_align must be \"left\", \"center\", or \"right\"")
+                    .into());
+            }
+        };
+        let layout = match alignment {
+            Some(alignment) => quote! {
+                #base.value_alignment(::struct_table::Alignment::#alignment)
+            },
+            None => base,
+        };
+        Some(quote! {
+            #[automatically_derived]
+            const LAYOUT: ::struct_table::TableLayout = #layout;
+        })
+    } else {
+        None
+    };
 
     // panic!("fields: {}", meow.len());
 
@@ -241,9 +760,8 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
         #[automatically_derived]
         impl #impl_generics ::struct_table::StructTable for #ident #type_generics #where_cause {
             #[automatically_derived]
-            fn handle_input(&mut self, input: ::struct_table::ArrowKey, field_index: usize) -> ::core::result::Result<bool, ::struct_table::InvalidFieldIndex> {
+            fn handle_input(&mut self, input: ::struct_table::ArrowKey, field_index: usize) -> ::core::result::Result<::core::option::Option<usize>, ::struct_table::FieldInputError> {
                 let mut self_changed = false;
-                let final_field_index: usize = #final_field_index;
                 // Assuming left/right only here
                 let next: bool;
 
@@ -254,49 +772,87 @@ fn struct_table_inner(input: proc_macro2::TokenStream) -> deluxe::Result<proc_ma
                 }
 
                 match field_index {
-                    #( #field_indices => {
-                        #field_arms;
-                        self_changed = true;
-                       }, )*
-                    _ => return Err(::struct_table::InvalidFieldIndex),
+                    #( #section_arms )*
+                    #( #field_arms )*
+                    _ => return Err(::struct_table::FieldInputError::InvalidFieldIndex),
+                }
+
+                if self_changed {
+                    self.field_changed(field_index);
                 }
 
-                Ok(self_changed)
+                Ok(self_changed.then_some(field_index))
             }
 
             #[automatically_derived]
-            fn as_table(&self) -> ::ratatui::widgets::Table<'_> {
-                use ::ratatui::{
-                    layout::Constraint,
-                    style::{Style, Stylize},
-                    text::Text,
-                    widgets::{Row, Table},
+            fn edit_text(&self, field_index: usize) -> ::core::option::Option<::std::string::String> {
+                match field_index {
+                    #( #edit_text_arms )*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            #[automatically_derived]
+            fn apply_text(&mut self, field_index: usize, text: &str) -> ::core::result::Result<::core::option::Option<usize>, ::struct_table::FieldParseError> {
+                #apply_text_body
+            }
+
+            #[automatically_derived]
+            fn reset_field(&mut self, field_index: usize) -> ::core::result::Result<::core::option::Option<usize>, ::struct_table::FieldInputError> {
+                let changed: bool = match field_index {
+                    #( #reset_arms )*
+                    i if i < #visible_fields => false,
+                    _ => return Err(::struct_table::FieldInputError::InvalidFieldIndex),
                 };
-                let cell_highlight_style = Style::new().reversed().italic();
 
-                let rows: Vec<Row> = vec![
-                    #(
-                    Row::new([
-                        Text::raw(#field_human_names).right_aligned(),
-                        Text::raw(#field_string_values).centered().italic(),
-                    ])
-                    ),*
-                ];
+                if changed {
+                    self.field_changed(field_index);
+                }
 
-                let option_table = Table::new(
-                    rows,
-                    [Constraint::Percentage(50), Constraint::Percentage(50)],
-                )
-                .cell_highlight_style(cell_highlight_style);
+                Ok(changed.then_some(field_index))
+            }
 
-                option_table
+            #[automatically_derived]
+            fn field_changed(&mut self, field_index: usize) {
+                match field_index {
+                    #( #on_change_arms )*
+                    _ => (),
+                }
             }
 
             #[automatically_derived]
-            const DOCSTRINGS: &'static [&'static str] = &[ #(#docstrings),* ];
+            fn is_row_hidden(&self, field_index: usize) -> bool {
+                match field_index {
+                    #( #hidden_row_arms )*
+                    _ => false,
+                }
+            }
 
             #[automatically_derived]
-            const VISIBLE_FIELDS: usize = #docstrings_len;
+            fn is_section_header(field_index: usize) -> bool {
+                match field_index {
+                    #( #section_header_arms )*
+                    _ => false,
+                }
+            }
+
+            #[automatically_derived]
+            fn rows(&self) -> ::std::vec::Vec<(::std::string::String, ::std::string::String)> {
+                let mut rows = ::std::vec::Vec::with_capacity(#visible_fields);
+                #( #row_pushes )*
+                rows
+            }
+
+            #[automatically_derived]
+            const FIELD_NAMES: &'static [&'static str] = #field_names_const;
+
+            #[automatically_derived]
+            const DOCSTRINGS: &'static [&'static str] = #docstrings_const;
+
+            #[automatically_derived]
+            const VISIBLE_FIELDS: usize = #visible_fields;
+
+            #layout_const
         }
     })
 }
@@ -308,6 +864,19 @@ fn is_bool_field(field: &syn::Field) -> bool {
         false
     }
 }
+/// The `T` of a field whose type path is an `Option<T>`.
+fn option_inner_type(field: &syn::Field) -> Option<&syn::Type> {
+    if let syn::Type::Path(type_path) = &field.ty
+        && let Some(last) = type_path.path.segments.last()
+        && last.ident == "Option"
+        && let syn::PathArguments::AngleBracketed(args) = &last.arguments
+        && let Some(syn::GenericArgument::Type(inner)) = args.args.first()
+    {
+        Some(inner)
+    } else {
+        None
+    }
+}
 fn extract_field_attrs(ast: &mut DeriveInput) -> deluxe::Result<Vec<StructField>> {
     let mut field_attrs: Vec<StructField> = Vec::new();
 
@@ -343,7 +912,45 @@ fn extract_field_attrs(ast: &mut DeriveInput) -> deluxe::Result<Vec<StructField>
                 skip,
                 immutable,
                 allow_unknown_values,
+                nested,
+                none,
+                range,
+                text,
+                parse,
+                validate,
+                on_change,
+                section,
+                hide_if,
+                default,
+                values_fn,
+                unit,
+                format,
             } = deluxe::extract_attributes(field)?;
+            // A parse function is only useful for typed text
+            let text = text || parse.is_some();
+
+            if (unit.is_some() || format.is_some()) && (display.is_some() || nested) {
+                return Err(ident
+                    .span()
+                    .error(
+                        "fields with display labels or nested fields can't have a unit or format",
+                    )
+                    .into());
+            }
+
+            if values.is_some() && values_fn.is_some() {
+                return Err(ident
+                    .span()
+                    .error("only one of values and values_fn can be given")
+                    .into());
+            }
+            // Labels couldn't be checked against a list that's only known at runtime
+            if values_fn.is_some() && display.is_some() {
+                return Err(ident
+                    .span()
+                    .error("values_fn fields can't have display labels, use format instead")
+                    .into());
+            }
 
             if skip {
                 continue;
@@ -353,21 +960,143 @@ fn extract_field_attrs(ast: &mut DeriveInput) -> deluxe::Result<Vec<StructField>
             // let doc = "".to_string();
 
             let is_bool = is_bool_field(field);
+            let option_inner = option_inner_type(field);
+            let is_option = option_inner.is_some();
+
+            // From here on a values_fn is treated like any other list of values,
+            // called once and kept for every change after.
+            // The field could hold a value the list doesn't have (yet), so unknown values are allowed.
+            let runtime_values = values_fn.is_some();
+            let allow_unknown_values = allow_unknown_values || runtime_values;
+            let values = match values_fn {
+                Some(values_fn) => {
+                    let cycled_ty = option_inner.unwrap_or(&field.ty);
+                    Some(ArrayOrConst::Expr(syn::parse_quote! {
+                        {
+                            static VALUES: ::std::sync::OnceLock<::std::vec::Vec<#cycled_ty>> =
+                                ::std::sync::OnceLock::new();
+                            VALUES
+                                .get_or_init(|| {
+                                    #values_fn()
+                                        .into_iter()
+                                        .map(::core::convert::Into::into)
+                                        .collect()
+                                })
+                                .as_slice()
+                        }
+                    }))
+                }
+                None => values,
+            };
+
+            if none.is_some() && !is_option {
+                return Err(ident
+                    .span()
+                    .error("only Option fields can have a label for None")
+                    .into());
+            }
+
+            if range.is_some() && (values.is_some() || display.is_some() || is_bool || is_option) {
+                return Err(ident
+                    .span()
+                    .error(
+                        "range fields can't be bools or optional, or have cycled values or labels",
+                    )
+                    .into());
+            }
+
+            if immutable && default.is_some() {
+                return Err(ident.span().error("immutable fields can't be reset").into());
+            }
 
-            if !is_bool && values.is_none() {}
+            if text
+                && (values.is_some()
+                    || display.is_some()
+                    || is_bool
+                    || is_option
+                    || range.is_some()
+                    || nested)
+            {
+                return Err(ident
+                    .span()
+                    .error("text fields can't be bools, optional, nested, or ranges, or have cycled values or labels")
+                    .into());
+            }
+
+            // Without any values given, cycling through every variant of the field's type,
+            // (or what's inside the Option) as long as it implements strum's `VariantArray`
+            let values = match values {
+                None if !is_bool && !nested && !immutable && !text && range.is_none() => {
+                    let cycled_ty = option_inner.unwrap_or(&field.ty);
+                    Some(ArrayOrConst::Expr(
+                        syn::parse_quote_spanned! {cycled_ty.span()=>
+                            <#cycled_ty as ::strum::VariantArray>::VARIANTS
+                        },
+                    ))
+                }
+                values => values,
+            };
 
             // Some of these checks we can only do if the user
             // supplied an array directly.
             // Extra checks are added in const {} contexts to ensure correctness.
 
-            // Verifying validity of values_to_cycle values
-            match (is_bool, &values, immutable) {
-                (false, None, false) => {
+            if nested {
+                if values.is_some() || display.is_some() || is_bool {
+                    return Err(ident
+                        .span()
+                        .error("nested fields use their own table's values and labels")
+                        .into());
+                }
+                if is_option {
+                    return Err(ident.span().error("nested fields can't be optional").into());
+                }
+                if validate.is_some() {
+                    return Err(ident
+                        .span()
+                        .error("nested fields are validated by their own table")
+                        .into());
+                }
+                if range.is_some() {
+                    return Err(ident.span().error("nested fields can't be ranges").into());
+                }
+                if default.is_some() {
                     return Err(ident
                         .span()
-                        .error("expected #[table(values = [])] with array of values")
+                        .error("nested fields are reset to their own table's defaults")
                         .into());
                 }
+                field_attrs.push(StructField {
+                    ident,
+                    ty: field.ty.clone(),
+                    doc,
+                    values_to_cycle: None,
+                    display_override: None,
+                    is_bool,
+                    no_inner_wrap,
+                    rename,
+                    immutable,
+                    allow_unknown_values,
+                    nested,
+                    is_option,
+                    none_label: none,
+                    range: None,
+                    text: false,
+                    parse: None,
+                    validate: None,
+                    on_change,
+                    section,
+                    hide_if,
+                    default: None,
+                    runtime_values: false,
+                    unit: None,
+                    format: None,
+                });
+                continue;
+            }
+
+            // Verifying validity of values_to_cycle values
+            match (is_bool, &values, immutable) {
                 (false, Some(ArrayOrConst::Array(values)), _) if values.elems.is_empty() => {
                     return Err(ident
                         .span()
@@ -429,6 +1158,7 @@ fn extract_field_attrs(ast: &mut DeriveInput) -> deluxe::Result<Vec<StructField>
 
             let processed_field = StructField {
                 ident,
+                ty: field.ty.clone(),
                 doc,
                 values_to_cycle: values,
                 display_override: display,
@@ -437,6 +1167,20 @@ fn extract_field_attrs(ast: &mut DeriveInput) -> deluxe::Result<Vec<StructField>
                 rename,
                 immutable,
                 allow_unknown_values,
+                nested: false,
+                is_option,
+                none_label: none,
+                range,
+                text,
+                parse,
+                validate,
+                on_change,
+                section,
+                hide_if,
+                default,
+                runtime_values,
+                unit,
+                format,
             };
             field_attrs.push(processed_field);
         }
@@ -508,8 +1252,41 @@ impl quote::ToTokens for ArrayOrConst {
     }
 }
 
+/// The bounds and step size of a `#[table(range(min, max, step))]` field.
+struct RangeAttr {
+    min: syn::Expr,
+    max: syn::Expr,
+    step: syn::Expr,
+}
+
+impl deluxe::ParseMetaItem for RangeAttr {
+    fn parse_meta_item(
+        input: syn::parse::ParseStream,
+        _mode: deluxe::ParseMode,
+    ) -> deluxe::Result<Self> {
+        let parse_parts = |input: syn::parse::ParseStream| -> deluxe::Result<Vec<syn::Expr>> {
+            let parts =
+                syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated(input)?;
+            Ok(parts.into_iter().collect())
+        };
+        // Accepting both `range(min, max, step)` and `range = (min, max, step)`
+        let parts = if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            parse_parts(&content)?
+        } else {
+            parse_parts(input)?
+        };
+        let [min, max, step]: [syn::Expr; 3] = parts
+            .try_into()
+            .map_err(|_| syn::Error::new(input.span(), "expected range(min, max, step)"))?;
+        Ok(RangeAttr { min, max, step })
+    }
+}
+
 struct StructField {
     ident: syn::Ident,
+    ty: syn::Type,
     doc: String,
     values_to_cycle: Option<ArrayOrConst>,
     display_override: Option<ArrayOrConst>,
@@ -518,17 +1295,47 @@ struct StructField {
     rename: Option<String>,
     immutable: bool,
     allow_unknown_values: bool,
+    nested: bool,
+    is_option: bool,
+    none_label: Option<String>,
+    range: Option<RangeAttr>,
+    text: bool,
+    parse: Option<syn::Path>,
+    validate: Option<syn::Path>,
+    on_change: Option<syn::Path>,
+    section: Option<String>,
+    hide_if: Option<syn::Path>,
+    default: Option<syn::Expr>,
+    /// If the values to cycle through come from a `values_fn`, only known at runtime.
+    runtime_values: bool,
+    unit: Option<String>,
+    format: Option<syn::Path>,
 }
 
 #[derive(deluxe::ExtractAttributes)]
 #[deluxe(attributes(table))]
 struct StructFieldAttributes {
     /// The values this field will cycle through
+    ///
+    /// If not given, every variant of the field's type is used through `strum::VariantArray`.
     #[deluxe(default)]
     values: Option<ArrayOrConst>,
+    /// A `fn() -> Vec<T>` giving the values to cycle through at runtime instead,
+    /// i.e. for lists that aren't known until yap is running.
+    ///
+    /// Called the first time the values are needed and kept from then on.
+    /// Implies `allow_unknown_values`, and can't be given `display` labels.
+    #[deluxe(default)]
+    values_fn: Option<syn::Path>,
     /// Override for to_string() for each of the cycled values
     #[deluxe(default)]
     display: Option<ArrayOrConst>,
+    /// A `fn(&T) -> String` to show the field's value with, instead of its `Display` impl.
+    #[deluxe(default)]
+    format: Option<syn::Path>,
+    /// Shown after the field's value, separated by a space, like `ms` for `500 ms`.
+    #[deluxe(default)]
+    unit: Option<String>,
     /// Don't wrap around when at the end/start of the field's cycled values
     #[deluxe(default)]
     no_wrap: bool,
@@ -545,6 +1352,45 @@ struct StructFieldAttributes {
     /// Don't panic if encountering an unknown value, instead behaving as if it's on the 0th value.
     #[deluxe(default)]
     allow_unknown_values: bool,
+    /// This field's type also derives StructTable, flatten its rows into this table
+    /// as an indented section under a header row with this field's name.
+    #[deluxe(default)]
+    nested: bool,
+    /// For `Option` fields, the label shown when it's `None`, otherwise just `None`.
+    ///
+    /// `None` is cycled to before the first of the given values.
+    #[deluxe(default)]
+    none: Option<String>,
+    /// For numeric fields, `range(min, max, step)` to step through with Left/Right
+    /// instead of a list of values, which can also be typed in directly.
+    #[deluxe(default)]
+    range: Option<RangeAttr>,
+    /// Type this field's value in directly with Enter, parsed with its `FromStr` impl.
+    #[deluxe(default)]
+    text: bool,
+    /// For text fields, a `fn(&str) -> Result<T, E>` to parse and validate typed values with
+    /// instead of `FromStr`, where `E: Display`. Implies `text`.
+    #[deluxe(default)]
+    parse: Option<syn::Path>,
+    /// A `fn(T) -> Result<T, String>` run on each new value from cycling or typing,
+    /// able to clamp it by returning a different value, or refuse it with the reason why.
+    #[deluxe(default)]
+    validate: Option<syn::Path>,
+    /// A `fn(&mut Self)` called after this field (or any row of a nested field) is changed
+    /// by cycling or typing, i.e. to keep other fields that depend on it in step.
+    #[deluxe(default)]
+    on_change: Option<syn::Path>,
+    /// Start a new section with this name above the field,
+    /// shown as a header row that can't be selected.
+    #[deluxe(default)]
+    section: Option<String>,
+    /// A `fn(&Self) -> bool` deciding when to hide this field's row(s),
+    /// i.e. for options that only matter while another field is enabled.
+    #[deluxe(default)]
+    hide_if: Option<syn::Path>,
+    /// The value to reset this field to, instead of its value in the struct's `Default` impl.
+    #[deluxe(default)]
+    default: Option<syn::Expr>,
 }
 
 #[derive(deluxe::ExtractAttributes)]
@@ -553,4 +1399,14 @@ struct StructTableAttributes {
     // #[deluxe(default)]
     // no_wrap: bool,
     // skip_field_case_conversion: bool
+    /// The struct doesn't implement `Default`,
+    /// so only fields with their own `default` can be reset.
+    #[deluxe(default)]
+    no_default: bool,
+    /// Percentage of the table's width given to the label column, otherwise half.
+    #[deluxe(default)]
+    label_percent: Option<u16>,
+    /// `"left"`, `"center"`, or `"right"` to line values up with, otherwise centered.
+    #[deluxe(default)]
+    value_align: Option<String>,
 }
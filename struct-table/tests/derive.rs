@@ -0,0 +1,378 @@
+use ratatui::{
+    layout::{Position, Rect},
+    widgets::TableState,
+};
+use struct_table::{ArrowKey, FieldInputError, StructTable, TableMouse};
+
+#[derive(Debug, PartialEq, StructTable)]
+struct Inner {
+    /// If the inner part is on.
+    enabled: bool,
+    /// How much of it there is.
+    #[table(values = [1, 2, 3])]
+    level: u8,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            level: 1,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, StructTable)]
+struct Outer {
+    /// The outer flag.
+    top: bool,
+    /// Settings of the inner part.
+    #[table(nested, rename = "Inner Settings")]
+    inner: Inner,
+    /// The last flag.
+    #[table(section = "Other")]
+    last: bool,
+}
+
+/// Rows of [`Outer`] as laid out by the derive, counting the nested and section header rows.
+mod rows {
+    pub const TOP: usize = 0;
+    pub const INNER_HEADER: usize = 1;
+    pub const INNER_ENABLED: usize = 2;
+    pub const INNER_LEVEL: usize = 3;
+    pub const OTHER_SECTION: usize = 4;
+    pub const LAST: usize = 5;
+}
+
+#[test]
+fn nested_rows_follow_their_header() {
+    assert_eq!(Outer::VISIBLE_FIELDS, 6);
+    assert_eq!(
+        Outer::FIELD_NAMES,
+        ["Top", "Inner Settings", "Enabled", "Level", "Other", "Last"]
+    );
+    assert_eq!(
+        Outer::DOCSTRINGS[rows::INNER_HEADER],
+        "Settings of the inner part."
+    );
+    assert_eq!(
+        Outer::DOCSTRINGS[rows::INNER_LEVEL],
+        "How much of it there is."
+    );
+
+    let rows: Vec<(String, String)> = Outer::default().rows();
+    let rows: Vec<(&str, &str)> = rows
+        .iter()
+        .map(|(label, value)| (label.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("Top:", "false"),
+            ("Inner Settings:", ""),
+            ("  Enabled:", "false"),
+            ("  Level:", "1"),
+            ("Other", ""),
+            ("Last:", "false"),
+        ]
+    );
+
+    assert!(Outer::is_section_header(rows::OTHER_SECTION));
+    assert!(!Outer::is_section_header(rows::INNER_HEADER));
+    assert!(!Outer::is_section_header(rows::INNER_LEVEL));
+}
+
+#[test]
+fn nested_rows_change_the_nested_field() {
+    let mut outer = Outer::default();
+
+    assert_eq!(
+        outer
+            .handle_input(ArrowKey::Right, rows::INNER_LEVEL)
+            .unwrap(),
+        Some(rows::INNER_LEVEL)
+    );
+    assert_eq!(outer.inner.level, 2);
+    assert_eq!(
+        outer
+            .handle_input(ArrowKey::Left, rows::INNER_LEVEL)
+            .unwrap(),
+        Some(rows::INNER_LEVEL)
+    );
+    assert_eq!(
+        outer
+            .handle_input(ArrowKey::Left, rows::INNER_LEVEL)
+            .unwrap(),
+        Some(rows::INNER_LEVEL)
+    );
+    assert_eq!(outer.inner.level, 3);
+
+    outer
+        .handle_input(ArrowKey::Right, rows::INNER_ENABLED)
+        .unwrap();
+    outer.handle_input(ArrowKey::Right, rows::TOP).unwrap();
+    outer.handle_input(ArrowKey::Right, rows::LAST).unwrap();
+    assert!(outer.inner.enabled && outer.top && outer.last);
+
+    // Header rows can't be changed themselves.
+    assert_eq!(
+        outer
+            .handle_input(ArrowKey::Right, rows::INNER_HEADER)
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        outer
+            .handle_input(ArrowKey::Right, rows::OTHER_SECTION)
+            .unwrap(),
+        None
+    );
+    assert!(matches!(
+        outer.handle_input(ArrowKey::Right, Outer::VISIBLE_FIELDS),
+        Err(FieldInputError::InvalidFieldIndex)
+    ));
+}
+
+#[test]
+fn nested_rows_reset_to_their_own_defaults() {
+    let mut outer = Outer::default();
+    outer.inner.level = 3;
+    outer.top = true;
+
+    assert_eq!(
+        outer.reset_field(rows::INNER_LEVEL).unwrap(),
+        Some(rows::INNER_LEVEL)
+    );
+    assert_eq!(outer.inner, Inner::default());
+    assert_eq!(outer.reset_field(rows::INNER_LEVEL).unwrap(), None);
+    assert_eq!(outer.reset_field(rows::INNER_HEADER).unwrap(), None);
+
+    assert_eq!(outer.reset_field(rows::TOP).unwrap(), Some(rows::TOP));
+    assert_eq!(outer, Outer::default());
+}
+
+fn found_levels() -> Vec<u8> {
+    vec![10, 20]
+}
+
+#[derive(Debug, Default, StructTable)]
+struct Found {
+    /// A level from a list found at runtime.
+    #[table(values_fn = found_levels)]
+    level: u8,
+    /// An optional level from the same list.
+    #[table(values_fn = found_levels, none = "Off")]
+    maybe_level: Option<u8>,
+}
+
+#[test]
+fn runtime_values_allow_unknown_values() {
+    let mut found = Found::default();
+    assert_eq!(found.rows()[0].1, "0");
+
+    // Not in the list, so cycled onto its start.
+    found.handle_input(ArrowKey::Right, 0).unwrap();
+    assert_eq!(found.level, 10);
+    found.handle_input(ArrowKey::Right, 0).unwrap();
+    found.handle_input(ArrowKey::Right, 0).unwrap();
+    assert_eq!(found.level, 10);
+
+    found.maybe_level = Some(15);
+    assert_eq!(found.rows()[1].1, "15");
+    found.handle_input(ArrowKey::Right, 1).unwrap();
+    assert_eq!(found.maybe_level, None);
+    assert_eq!(found.rows()[1].1, "Off");
+    found.handle_input(ArrowKey::Left, 1).unwrap();
+    assert_eq!(found.maybe_level, Some(20));
+}
+
+fn even_only(value: u8) -> Result<u8, String> {
+    if value.is_multiple_of(2) {
+        Ok(value)
+    } else {
+        Err(format!("{value} isn't even"))
+    }
+}
+
+fn at_most_eight(value: u8) -> Result<u8, String> {
+    Ok(value.min(8))
+}
+
+#[derive(Debug, Default, StructTable)]
+struct Validated {
+    /// Refuses odd values.
+    #[table(range(0, 10, 1), validate = even_only)]
+    even: u8,
+    /// Clamps anything past eight.
+    #[table(range(0, 20, 5), validate = at_most_eight)]
+    clamped: u8,
+}
+
+#[test]
+fn validate_refuses_or_clamps_new_values() {
+    let mut validated = Validated::default();
+
+    assert!(matches!(
+        validated.handle_input(ArrowKey::Right, 0),
+        Err(FieldInputError::Rejected(reason)) if reason == "1 isn't even"
+    ));
+    assert_eq!(validated.even, 0);
+    assert_eq!(validated.apply_text(0, "4").unwrap(), Some(0));
+    assert_eq!(validated.even, 4);
+    let refused = validated.apply_text(0, "7").unwrap_err();
+    assert_eq!(refused.0, "7 isn't even");
+    assert_eq!(validated.even, 4);
+
+    validated.handle_input(ArrowKey::Right, 1).unwrap();
+    assert_eq!(validated.clamped, 5);
+    validated.handle_input(ArrowKey::Right, 1).unwrap();
+    assert_eq!(validated.clamped, 8);
+    validated.apply_text(1, "15").unwrap();
+    assert_eq!(validated.clamped, 8);
+}
+
+fn details_off(table: &Hiding) -> bool {
+    !table.details
+}
+
+#[derive(Debug, Default, StructTable)]
+struct Hiding {
+    /// Shows the rows below it.
+    details: bool,
+    /// Only shown with details on.
+    #[table(hide_if = details_off)]
+    verbose: bool,
+    /// Also only shown with details on.
+    #[table(hide_if = details_off)]
+    very_verbose: bool,
+    /// Always shown.
+    last: bool,
+}
+
+#[test]
+fn hidden_rows_are_skipped_when_mapping_rows_and_fields() {
+    let mut hiding = Hiding::default();
+    assert!(hiding.is_row_hidden(1) && hiding.is_row_hidden(2));
+    assert!(!hiding.is_row_hidden(0) && !hiding.is_row_hidden(3));
+    assert_eq!(hiding.shown_rows(), 2);
+    assert_eq!(hiding.field_index_of_row(0), Some(0));
+    assert_eq!(hiding.field_index_of_row(1), Some(3));
+    assert_eq!(hiding.field_index_of_row(2), None);
+    assert_eq!(hiding.row_of_field_index(3), 1);
+    // Hidden fields map to the row after where they'd be.
+    assert_eq!(hiding.row_of_field_index(2), 1);
+
+    hiding.handle_input(ArrowKey::Right, 0).unwrap();
+    assert_eq!(hiding.shown_rows(), 4);
+    assert_eq!(hiding.field_index_of_row(1), Some(1));
+    assert_eq!(hiding.field_index_of_row(3), Some(3));
+    assert_eq!(hiding.row_of_field_index(3), 3);
+}
+
+#[test]
+fn mouse_selects_and_cycles_shown_rows() {
+    let area = Rect::new(10, 5, 40, 6);
+    let click = |x: u16, row: u16| TableMouse::Click(Position::new(x, area.y + row));
+    let label_x = area.x + 2;
+    let value_x = area.x + 35;
+
+    let mut outer = Outer::default();
+    let mut state = TableState::new();
+    // Clicking a label only selects its row.
+    assert_eq!(
+        outer
+            .handle_mouse(click(label_x, 0), area, &mut state)
+            .unwrap(),
+        None
+    );
+    assert_eq!(state.selected(), Some(rows::TOP));
+    assert!(!outer.top);
+    // Clicking the value cycles it too.
+    assert_eq!(
+        outer
+            .handle_mouse(click(value_x, rows::INNER_LEVEL as u16), area, &mut state)
+            .unwrap(),
+        Some(rows::INNER_LEVEL)
+    );
+    assert_eq!(state.selected(), Some(rows::INNER_LEVEL));
+    assert_eq!(outer.inner.level, 2);
+
+    // Section headers and clicks outside of the table are ignored.
+    let header = click(value_x, rows::OTHER_SECTION as u16);
+    assert_eq!(outer.handle_mouse(header, area, &mut state).unwrap(), None);
+    let outside = TableMouse::Click(Position::new(value_x, area.bottom()));
+    assert_eq!(outer.handle_mouse(outside, area, &mut state).unwrap(), None);
+    assert_eq!(state.selected(), Some(rows::INNER_LEVEL));
+
+    // Scrolling steps over section headers, and stops at either end.
+    outer
+        .handle_mouse(TableMouse::ScrollDown, area, &mut state)
+        .unwrap();
+    assert_eq!(state.selected(), Some(rows::LAST));
+    outer
+        .handle_mouse(TableMouse::ScrollDown, area, &mut state)
+        .unwrap();
+    assert_eq!(state.selected(), Some(rows::LAST));
+    outer
+        .handle_mouse(TableMouse::ScrollUp, area, &mut state)
+        .unwrap();
+    assert_eq!(state.selected(), Some(rows::INNER_LEVEL));
+
+    // Rows are as shown, so the second row is the last field while the others are hidden.
+    let mut hiding = Hiding::default();
+    let mut state = TableState::new();
+    assert_eq!(
+        hiding
+            .handle_mouse(click(value_x, 1), area, &mut state)
+            .unwrap(),
+        Some(3)
+    );
+    assert!(hiding.last);
+    assert_eq!(state.selected(), Some(1));
+}
+
+fn count_change(table: &mut Resettable) {
+    table.changes += 1;
+}
+
+#[derive(Debug, Default, StructTable)]
+struct Resettable {
+    /// Has a default of its own, rather than the struct's.
+    #[table(range(0, 10, 1), default = 3)]
+    level: u8,
+    /// Can't be changed through the table.
+    #[table(immutable)]
+    fixed: bool,
+    /// Counts each change.
+    #[table(on_change = count_change)]
+    watched: bool,
+    #[table(skip)]
+    changes: usize,
+}
+
+#[test]
+fn reset_uses_field_defaults_and_runs_on_change() {
+    let mut table = Resettable::default();
+    assert_eq!(table.reset_field(0).unwrap(), Some(0));
+    assert_eq!(table.level, 3);
+    assert_eq!(table.reset_field(0).unwrap(), None);
+
+    table.fixed = true;
+    assert_eq!(table.reset_field(1).unwrap(), None);
+    assert!(table.fixed);
+
+    table.handle_input(ArrowKey::Right, 2).unwrap();
+    assert!(table.watched);
+    assert_eq!(table.changes, 1);
+    assert_eq!(table.reset_field(2).unwrap(), Some(2));
+    assert!(!table.watched);
+    assert_eq!(table.changes, 2);
+    // Already at its default, so nothing changed.
+    assert_eq!(table.reset_field(2).unwrap(), None);
+    assert_eq!(table.changes, 2);
+
+    assert!(matches!(
+        table.reset_field(Resettable::VISIBLE_FIELDS),
+        Err(FieldInputError::InvalidFieldIndex)
+    ));
+}